@@ -9,6 +9,7 @@ use std::path::PathBuf;
 
 use libafl::{
     bolts::{
+        bench::bench_fuzzer,
         cli::{parse_args, FuzzerOptions},
         current_nanos,
         launcher::Launcher,
@@ -463,6 +464,22 @@ unsafe fn fuzz(options: FuzzerOptions) -> Result<(), Error> {
                     println!("We imported {} inputs from disk.", state.corpus().count());
                 }
 
+                if let Some(iterations) = options.bench_exec {
+                    let input = state.corpus().get(0)?.borrow_mut().load_input()?.clone();
+                    let results = bench_fuzzer(
+                        &mut fuzzer,
+                        &mut state,
+                        &mut executor,
+                        &mut mgr,
+                        &input,
+                        iterations,
+                    )?;
+                    for result in results {
+                        println!("{}", result);
+                    }
+                    return Ok(());
+                }
+
                 let mut stages = tuple_list!(StdMutationalStage::new(mutator));
 
                 fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)?;
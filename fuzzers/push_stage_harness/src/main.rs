@@ -19,7 +19,7 @@ use libafl::{
     mutators::scheduled::{havoc_mutations, StdScheduledMutator},
     observers::StdMapObserver,
     schedulers::{QueueScheduler, Scheduler},
-    stages::push::{PushStageSharedState, StdMutationalPushStage},
+    stages::push::{PushFuzzer, PushStageSharedState, StdMutationalPushStage},
     state::{HasCorpus, StdState},
 };
 
@@ -104,9 +104,10 @@ pub fn main() {
         stage_idx,
     );
 
-    // Loop, the input, getting a new entry from the push stage each iteration.
-    for input in push_stage {
-        let input = input.unwrap();
+    // The target loop asks for the next input, runs it, and reports how it exited.
+    let mut push_fuzzer = PushFuzzer::new(push_stage, exit_kind);
+    loop {
+        let input = push_fuzzer.next_input().unwrap();
         let target = input.target_bytes();
         let buf = target.as_slice();
         signals_set(0);
@@ -119,8 +120,6 @@ pub fn main() {
                 }
             }
         }
-        (*exit_kind).replace(Some(ExitKind::Ok));
+        push_fuzzer.report(ExitKind::Ok).unwrap();
     }
-
-    println!("One iteration done.");
 }
@@ -0,0 +1,179 @@
+//! Measure the throughput of the components of a fuzzer.
+//!
+//! Running the same input through the executor, the observers, and the feedbacks in a loop
+//! quantifies the overhead each component adds. Compare against a no-op harness
+//! to get the cost of the fuzzer itself, see the `exec_speeds` bench in `libafl_benches`.
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, time::Duration};
+use std::time::Instant;
+
+use crate::{
+    events::EventFirer,
+    executors::{Executor, ExitKind, HasObservers},
+    feedbacks::Feedback,
+    fuzzer::{ExecutesInput, HasFeedback},
+    inputs::Input,
+    observers::ObserversTuple,
+    state::HasClientPerfMonitor,
+    Error,
+};
+
+/// The result of a single benchmark
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    /// The name of the benchmarked component
+    pub name: String,
+    /// How many times the component ran
+    pub iterations: u64,
+    /// The total time spent
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    /// Iterations per second
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn per_sec(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            0.0
+        } else {
+            self.iterations as f64 / self.elapsed.as_secs_f64()
+        }
+    }
+
+    /// The mean time of a single iteration
+    #[must_use]
+    pub fn per_iteration(&self) -> Duration {
+        if self.iterations == 0 {
+            Duration::ZERO
+        } else {
+            self.elapsed / u32::try_from(self.iterations).unwrap_or(u32::MAX)
+        }
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} iterations in {:?} ({:.2}/s, {:?} each)",
+            self.name,
+            self.iterations,
+            self.elapsed,
+            self.per_sec(),
+            self.per_iteration()
+        )
+    }
+}
+
+/// Run `f` `iterations` times and measure it
+fn measure<F>(name: &str, iterations: u64, mut f: F) -> Result<BenchResult, Error>
+where
+    F: FnMut() -> Result<(), Error>,
+{
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f()?;
+    }
+    Ok(BenchResult {
+        name: String::from(name),
+        iterations,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Measure full executions of `input`, including the observers, without evaluating the feedbacks
+pub fn bench_executions<E, EM, I, OT, S, Z>(
+    fuzzer: &mut Z,
+    state: &mut S,
+    executor: &mut E,
+    mgr: &mut EM,
+    input: &I,
+    iterations: u64,
+) -> Result<BenchResult, Error>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    Z: ExecutesInput<I, OT, S, Z>,
+{
+    measure("executions", iterations, || {
+        fuzzer.execute_input(state, executor, mgr, input).map(|_| ())
+    })
+}
+
+/// Measure the reset of the observers done before each execution
+pub fn bench_observers_reset<I, OT, S>(
+    observers: &mut OT,
+    state: &mut S,
+    input: &I,
+    iterations: u64,
+) -> Result<BenchResult, Error>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    measure("observers reset", iterations, || {
+        observers.pre_exec_all(state, input)
+    })
+}
+
+/// Measure the evaluation of `feedback` on the current content of the observers
+pub fn bench_feedback<EM, F, I, OT, S>(
+    feedback: &mut F,
+    state: &mut S,
+    mgr: &mut EM,
+    input: &I,
+    observers: &OT,
+    iterations: u64,
+) -> Result<BenchResult, Error>
+where
+    EM: EventFirer<I>,
+    F: Feedback<I, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor,
+{
+    let name = format!("feedback {}", feedback.name());
+    measure(&name, iterations, || {
+        if feedback.is_interesting(state, mgr, input, observers, &ExitKind::Ok)? {
+            feedback.discard_metadata(state, input)?;
+        }
+        Ok(())
+    })
+}
+
+/// Benchmark the executor, the observers and the feedback of a fuzzer, running `input` `iterations` times.
+/// This is what fuzzers run when passed `--bench-exec`, see `FuzzerOptions::bench_exec` in `bolts::cli`.
+pub fn bench_fuzzer<E, EM, F, I, OT, S, Z>(
+    fuzzer: &mut Z,
+    state: &mut S,
+    executor: &mut E,
+    mgr: &mut EM,
+    input: &I,
+    iterations: u64,
+) -> Result<Vec<BenchResult>, Error>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    EM: EventFirer<I>,
+    F: Feedback<I, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor,
+    Z: ExecutesInput<I, OT, S, Z> + HasFeedback<F, I, S>,
+{
+    let executions = bench_executions(fuzzer, state, executor, mgr, input, iterations)?;
+    let reset = bench_observers_reset(executor.observers_mut(), state, input, iterations)?;
+    // Fill the observers once more, the feedbacks scan what the last run left there
+    fuzzer.execute_input(state, executor, mgr, input)?;
+    let feedback = bench_feedback(
+        fuzzer.feedback_mut(),
+        state,
+        mgr,
+        input,
+        executor.observers(),
+        iterations,
+    )?;
+    Ok(vec![executions, reset, feedback])
+}
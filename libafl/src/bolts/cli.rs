@@ -229,7 +229,7 @@ pub struct FuzzerOptions {
     #[clap(long, help_heading = "Frida Options")]
     pub disable_coverage: bool,
 
-    /// enable DrCov (aarch64 only)
+    /// enable DrCov, writing a trace per execution to ./coverage
     #[cfg(feature = "frida_cli")]
     #[clap(long, help_heading = "Frida Options")]
     pub drcov: bool,
@@ -239,6 +239,11 @@ pub struct FuzzerOptions {
     #[clap(short = 'D', long, help_heading = "Frida Options", parse(try_from_str = parse_instrumentation_location), multiple_occurrences = true)]
     pub dont_instrument: Vec<(String, usize)>,
 
+    /// libraries which will not be instrumented, not even the harness, by name or path (ex: libc.so.6)
+    #[cfg(feature = "frida_cli")]
+    #[clap(long, help_heading = "Frida Options", multiple_occurrences = true)]
+    pub dont_instrument_libs: Vec<String>,
+
     /// trailing arguments (after "--"); can be passed directly to QEMU
     #[cfg(feature = "qemu_cli")]
     #[clap(last = true)]
@@ -303,6 +308,16 @@ pub struct FuzzerOptions {
         requires = "replay"
     )]
     pub repeat: Option<usize>,
+
+    /// Instead of fuzzing, run the first corpus entry the given number of times to benchmark
+    /// the fuzzer components, see `bolts::bench::bench_fuzzer`
+    #[clap(
+        long,
+        default_missing_value = "10000",
+        min_values = 0,
+        help_heading = "Benchmark Options"
+    )]
+    pub bench_exec: Option<u64>,
 }
 
 impl FuzzerOptions {
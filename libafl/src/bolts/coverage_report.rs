@@ -0,0 +1,275 @@
+//! Coverage reports: aggregates the entries of a coverage map into per-function and per-module
+//! coverage, and writes `LCOV` tracefiles, e.g. for `genhtml`.
+//!
+//! The [`CoverageSite`]s, telling which function and module each map entry belongs to, come from
+//! the instrumentation, e.g. the sancov pc-tables of `libafl_targets` or the edge ids of `libafl_qemu`.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::Error;
+
+/// The location in the target of an entry of the coverage map
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CoverageSite {
+    /// The index in the coverage map
+    pub index: usize,
+    /// The module, i.e. the binary or library, of this site
+    pub module: String,
+    /// The function containing this site
+    pub function: String,
+    /// The source file, if known
+    pub file: Option<String>,
+    /// The source line, or 0 if unknown
+    pub line: u32,
+}
+
+/// The coverage of a function or a module
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CoverageStats {
+    /// The name of the function or module
+    pub name: String,
+    /// The module, for a function
+    pub module: Option<String>,
+    /// The number of covered sites
+    pub covered: usize,
+    /// The number of sites
+    pub total: usize,
+}
+
+impl CoverageStats {
+    /// The covered share of the sites, in percent
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.covered as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+impl fmt::Display for CoverageStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.module {
+            Some(module) => write!(f, "{} ({})", self.name, module)?,
+            None => write!(f, "{}", self.name)?,
+        }
+        write!(
+            f,
+            ": {}/{} ({:.2}%)",
+            self.covered,
+            self.total,
+            self.percent()
+        )
+    }
+}
+
+/// The coverage of all modules and functions, see [`CoverageReport::summary`]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CoverageSummary {
+    /// The coverage of each module
+    pub modules: Vec<CoverageStats>,
+    /// The coverage of each function
+    pub functions: Vec<CoverageStats>,
+}
+
+impl fmt::Display for CoverageSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Modules:")?;
+        for module in &self.modules {
+            writeln!(f, "  {}", module)?;
+        }
+        writeln!(f, "Functions:")?;
+        for function in &self.functions {
+            writeln!(f, "  {}", function)?;
+        }
+        Ok(())
+    }
+}
+
+/// The functions of a source file, with their first line and if they were hit, and its lines
+#[cfg(feature = "std")]
+type LcovFile<'a> = (BTreeMap<&'a str, (u32, bool)>, BTreeMap<u32, bool>);
+
+/// Aggregates a coverage map, e.g. the history map of a [`crate::feedbacks::MapFeedbackState`],
+/// along the [`CoverageSite`]s of the target
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CoverageReport {
+    sites: Vec<CoverageSite>,
+}
+
+impl CoverageReport {
+    /// Creates a new [`CoverageReport`] for the given sites
+    #[must_use]
+    pub fn new(mut sites: Vec<CoverageSite>) -> Self {
+        sites.sort_by(|a, b| {
+            (&a.module, &a.function, a.index).cmp(&(&b.module, &b.function, b.index))
+        });
+        Self { sites }
+    }
+
+    /// The sites of this report
+    #[must_use]
+    pub fn sites(&self) -> &[CoverageSite] {
+        &self.sites
+    }
+
+    /// If the site was covered, i.e. its entry in the map is not the initial value
+    fn is_covered<T>(map: &[T], site: &CoverageSite) -> bool
+    where
+        T: Default + PartialEq,
+    {
+        map.get(site.index).map_or(false, |v| *v != T::default())
+    }
+
+    /// Aggregates the `map` into the coverage of each module and function
+    #[must_use]
+    pub fn summary<T>(&self, map: &[T]) -> CoverageSummary
+    where
+        T: Default + PartialEq,
+    {
+        let mut summary = CoverageSummary::default();
+        // The sites are sorted by module and function
+        for site in &self.sites {
+            let covered = usize::from(Self::is_covered(map, site));
+
+            match summary.modules.last_mut() {
+                Some(module) if module.name == site.module => {
+                    module.covered += covered;
+                    module.total += 1;
+                }
+                _ => summary.modules.push(CoverageStats {
+                    name: site.module.clone(),
+                    module: None,
+                    covered,
+                    total: 1,
+                }),
+            }
+            match summary.functions.last_mut() {
+                Some(function)
+                    if function.name == site.function
+                        && function.module.as_ref() == Some(&site.module) =>
+                {
+                    function.covered += covered;
+                    function.total += 1;
+                }
+                _ => summary.functions.push(CoverageStats {
+                    name: site.function.clone(),
+                    module: Some(site.module.clone()),
+                    covered,
+                    total: 1,
+                }),
+            }
+        }
+        summary
+    }
+
+    /// Writes the coverage of the `map` as `LCOV` tracefile.
+    /// Sites without source file are attributed to their module.
+    #[cfg(feature = "std")]
+    pub fn write_lcov<P, T>(&self, path: P, map: &[T]) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+        T: Default + PartialEq,
+    {
+        let mut files: BTreeMap<&str, LcovFile> = BTreeMap::new();
+        for site in &self.sites {
+            let covered = Self::is_covered(map, site);
+            let file = site.file.as_deref().unwrap_or(&site.module);
+            let (functions, lines) = files.entry(file).or_default();
+
+            let function = functions
+                .entry(&site.function)
+                .or_insert((site.line, false));
+            if site.line != 0 && (function.0 == 0 || site.line < function.0) {
+                function.0 = site.line;
+            }
+            function.1 |= covered;
+            if site.line != 0 {
+                *lines.entry(site.line).or_default() |= covered;
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "TN:")?;
+        for (file, (functions, lines)) in files {
+            writeln!(writer, "SF:{}", file)?;
+            for (name, (line, _)) in &functions {
+                writeln!(writer, "FN:{},{}", line, name)?;
+            }
+            for (name, (_, hit)) in &functions {
+                writeln!(writer, "FNDA:{},{}", usize::from(*hit), name)?;
+            }
+            writeln!(writer, "FNF:{}", functions.len())?;
+            writeln!(
+                writer,
+                "FNH:{}",
+                functions.values().filter(|(_, hit)| *hit).count()
+            )?;
+            for (line, hit) in &lines {
+                writeln!(writer, "DA:{},{}", line, usize::from(*hit))?;
+            }
+            writeln!(writer, "LF:{}", lines.len())?;
+            writeln!(writer, "LH:{}", lines.values().filter(|hit| **hit).count())?;
+            writeln!(writer, "end_of_record")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bolts::coverage_report::{CoverageReport, CoverageSite};
+
+    fn site(index: usize, module: &str, function: &str, line: u32) -> CoverageSite {
+        CoverageSite {
+            index,
+            module: module.into(),
+            function: function.into(),
+            file: (line != 0).then(|| "target.c".into()),
+            line,
+        }
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let report = CoverageReport::new(vec![
+            site(0, "target", "main", 10),
+            site(1, "target", "main", 11),
+            site(2, "target", "parse", 20),
+            site(3, "libz.so", "inflate", 0),
+        ]);
+        let map = [1_u8, 0, 3, 0];
+
+        let summary = report.summary(&map);
+        assert_eq!(summary.modules.len(), 2);
+        let target = summary.modules.iter().find(|m| m.name == "target").unwrap();
+        assert_eq!((target.covered, target.total), (2, 3));
+        let main = summary.functions.iter().find(|f| f.name == "main").unwrap();
+        assert!((main.percent() - 50.0).abs() < f64::EPSILON);
+
+        #[cfg(feature = "std")]
+        {
+            let path = std::env::temp_dir().join("libafl_test_coverage_report.info");
+            report.write_lcov(&path, &map).unwrap();
+            let lcov = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert!(lcov.contains("SF:target.c\nFN:10,main\nFN:20,parse\n"));
+            assert!(lcov.contains("DA:11,0\n"));
+            assert!(lcov.contains("FNH:2\n"));
+        }
+    }
+}
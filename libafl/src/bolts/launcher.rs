@@ -9,6 +9,10 @@
 //!
 //! On `Unix` systems, the [`Launcher`] will use `fork` if the `fork` feature is used for `LibAFL`.
 //! Else, it will start subsequent nodes with the same commandline, and will set special `env` variables accordingly.
+//!
+//! With `respawn_clients`, the [`Launcher`] supervises its clients, and respawns those that died
+//! on their core, for example after their restarting manager gave up. Clients dying in a row are
+//! respawned with a growing delay, clients exiting cleanly are not respawned.
 
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
 use crate::bolts::os::startable_self;
@@ -26,22 +30,92 @@ use crate::{
 
 use core::fmt::{self, Debug, Formatter};
 #[cfg(feature = "std")]
-use core::marker::PhantomData;
-#[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
+use core::{marker::PhantomData, num::NonZeroUsize};
+#[cfg(feature = "std")]
 use core_affinity::CoreId;
+#[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
+use hashbrown::HashMap;
 #[cfg(feature = "std")]
 use serde::de::DeserializeOwned;
+#[cfg(all(unix, feature = "std", feature = "fork"))]
+use std::os::unix::io::AsRawFd;
 #[cfg(feature = "std")]
-use std::net::SocketAddr;
+use std::{
+    fs::{File, OpenOptions},
+    net::SocketAddr,
+    path::Path,
+    time::{Duration, Instant},
+};
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
-use std::process::Stdio;
-#[cfg(all(unix, feature = "std", feature = "fork"))]
-use std::{fs::File, os::unix::io::AsRawFd};
+use std::{
+    process::{Child, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
 #[cfg(feature = "std")]
 use typed_builder::TypedBuilder;
 
 /// The (internal) `env` that indicates we're running as client.
 const _AFL_LAUNCHER_CLIENT: &str = "AFL_LAUNCHER_CLIENT";
+/// The delay before respawning a client that died, it doubles with each death in a row
+#[cfg(feature = "std")]
+const RESPAWN_BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// The longest delay before respawning a client. A client living longer is not dying in a row.
+#[cfg(feature = "std")]
+const RESPAWN_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// The delay before respawning a client that died `deaths` times in a row
+#[cfg(feature = "std")]
+fn respawn_delay(deaths: u32) -> Duration {
+    RESPAWN_BACKOFF_BASE
+        .checked_mul(1 << deaths.min(16))
+        .map_or(RESPAWN_BACKOFF_MAX, |delay| delay.min(RESPAWN_BACKOFF_MAX))
+}
+
+/// The deaths in a row of a supervised client, for the backoff of its respawns
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+struct Respawns {
+    deaths: u32,
+    spawned: Instant,
+}
+
+#[cfg(feature = "std")]
+impl Respawns {
+    fn new() -> Self {
+        Self {
+            deaths: 0,
+            spawned: Instant::now(),
+        }
+    }
+
+    /// Records the death of the client, returns the delay before respawning it
+    fn on_death(&mut self) -> Duration {
+        if self.spawned.elapsed() >= RESPAWN_BACKOFF_MAX {
+            self.deaths = 0;
+        }
+        let delay = respawn_delay(self.deaths);
+        self.deaths = self.deaths.saturating_add(1);
+        self.spawned = Instant::now() + delay;
+        delay
+    }
+}
+
+/// Opens the file `client_<core>.log` in `dir`, for the output of the client on `core_id`.
+/// The first client truncates it, a respawned client appends to the output of the dead one.
+#[cfg(feature = "std")]
+fn open_client_log(dir: &Path, core_id: usize, respawned: bool) -> Result<File, Error> {
+    Ok(OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(respawned)
+        .truncate(!respawned)
+        .open(dir.join(format!("client_{}.log", core_id)))?)
+}
+
 /// Provides a Launcher, which can be used to launch a fuzzing run on a specified list of cores
 #[cfg(feature = "std")]
 #[derive(TypedBuilder)]
@@ -72,6 +146,13 @@ where
     /// A file name to write all client output to
     #[builder(default = None)]
     stdout_file: Option<&'a str>,
+    /// A directory to write the output of each client to, in a file `client_<core>.log` of its own.
+    /// Takes precedence over [`Self::stdout_file`].
+    #[builder(default = None)]
+    stdout_dir: Option<&'a Path>,
+    /// If the launcher respawns the clients that died on their core
+    #[builder(default = false)]
+    respawn_clients: bool,
     /// The `ip:port` address of another broker to connect our new broker to for multi-machine
     /// clusters.
     #[builder(default = None)]
@@ -82,6 +163,10 @@ where
     /// Then, clients launched by this [`Launcher`] can connect to the original `broker`.
     #[builder(default = true)]
     spawn_broker: bool,
+    /// The broker exits once this many clients exited cleanly, with [`crate::events::EventRestarter::send_exiting`].
+    /// Usually the number of cores, for runs that end on their own.
+    #[builder(default = None)]
+    exit_cleanly_after: Option<NonZeroUsize>,
     #[builder(setter(skip), default = PhantomData)]
     phantom_data: PhantomData<(&'a I, &'a OT, &'a S, &'a SP)>,
 }
@@ -103,6 +188,9 @@ where
             .field("spawn_broker", &self.spawn_broker)
             .field("remote_broker_addr", &self.remote_broker_addr)
             .field("stdout_file", &self.stdout_file)
+            .field("stdout_dir", &self.stdout_dir)
+            .field("respawn_clients", &self.respawn_clients)
+            .field("exit_cleanly_after", &self.exit_cleanly_after)
             .finish_non_exhaustive()
     }
 }
@@ -117,6 +205,89 @@ where
     SP: ShMemProvider + 'static,
     S: DeserializeOwned,
 {
+    /// The file the output of the client on `core_id` goes to, if any.
+    /// All clients share the `stdout_file`, created once by the launcher.
+    #[cfg(all(unix, feature = "std", feature = "fork"))]
+    fn client_stdout(
+        &self,
+        core_id: usize,
+        stdout_file: Option<&File>,
+        respawned: bool,
+    ) -> Result<Option<File>, Error> {
+        Ok(match (self.stdout_dir, stdout_file) {
+            (Some(dir), _) => Some(open_client_log(dir, core_id, respawned)?),
+            (None, Some(file)) => Some(file.try_clone()?),
+            (None, None) => None,
+        })
+    }
+
+    /// Forks a client bound to `bind_to`, starting after `delay`. Returns the pid of the child in the parent,
+    /// and `None` in the child, once it is done fuzzing.
+    #[cfg(all(unix, feature = "std", feature = "fork"))]
+    fn fork_client(
+        &mut self,
+        bind_to: CoreId,
+        delay: Duration,
+        stdout_file: Option<&File>,
+        respawned: bool,
+    ) -> Result<Option<libc::pid_t>, Error> {
+        self.shmem_provider.pre_fork()?;
+        match unsafe { fork() }? {
+            ForkResult::Parent(child) => {
+                self.shmem_provider.post_fork(false)?;
+                #[cfg(feature = "std")]
+                println!("child spawned and bound to core {}", bind_to.id);
+                Ok(Some(child.pid))
+            }
+            ForkResult::Child => {
+                println!("{:?} PostFork", unsafe { libc::getpid() });
+                self.shmem_provider.post_fork(true)?;
+                // Pin before allocating anything, for the maps to land on the NUMA node of this core
+                core_affinity::set_for_current(bind_to);
+
+                std::thread::sleep(delay);
+
+                if let Some(file) = self.client_stdout(bind_to.id, stdout_file, respawned)? {
+                    dup2(file.as_raw_fd(), libc::STDOUT_FILENO)?;
+                    dup2(file.as_raw_fd(), libc::STDERR_FILENO)?;
+                }
+                // Fuzzer client. keeps retrying the connection to broker till the broker starts
+                let (state, mgr) = RestartingMgr::<I, MT, OT, S, SP>::builder()
+                    .shmem_provider(self.shmem_provider.clone())
+                    .broker_port(self.broker_port)
+                    .kind(ManagerKind::Client {
+                        cpu_core: Some(bind_to),
+                    })
+                    .configuration(self.configuration)
+                    .build()
+                    .launch()?;
+
+                (self.run_client.take().unwrap())(state, mgr, bind_to.id)
+                    .expect("Client closure failed");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Runs the broker in this process, until it exits
+    fn launch_broker(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "std")]
+        println!("I am broker!!.");
+
+        // TODO we don't want always a broker here, think about using different laucher process to spawn different configurations
+        RestartingMgr::<I, MT, OT, S, SP>::builder()
+            .shmem_provider(self.shmem_provider.clone())
+            .monitor(Some(self.monitor.clone()))
+            .broker_port(self.broker_port)
+            .kind(ManagerKind::Broker)
+            .remote_broker_addr(self.remote_broker_addr)
+            .configuration(self.configuration)
+            .exit_cleanly_after(self.exit_cleanly_after)
+            .build()
+            .launch()?;
+        Ok(())
+    }
+
     /// Launch the broker and the clients and fuzz
     #[cfg(all(unix, feature = "std", feature = "fork"))]
     #[allow(clippy::similar_names)]
@@ -128,94 +299,93 @@ where
         }
 
         let core_ids = core_affinity::get_core_ids().unwrap();
-        let num_cores = core_ids.len();
         let mut handles = vec![];
 
         println!("spawning on cores: {:?}", self.cores);
 
-        #[cfg(feature = "std")]
-        let stdout_file = self
-            .stdout_file
-            .map(|filename| File::create(filename).unwrap());
+        let stdout_file = self.stdout_file.map(File::create).transpose()?;
 
         // Spawn clients
         let mut index = 0_u64;
-        for (id, bind_to) in core_ids.iter().enumerate().take(num_cores) {
+        for (id, bind_to) in core_ids.iter().enumerate() {
             if self.cores.ids.iter().any(|&x| x == id.into()) {
                 index += 1;
-                self.shmem_provider.pre_fork()?;
-                match unsafe { fork() }? {
-                    ForkResult::Parent(child) => {
-                        self.shmem_provider.post_fork(false)?;
-                        handles.push(child.pid);
-                        #[cfg(feature = "std")]
-                        println!("child spawned and bound to core {}", id);
-                    }
-                    ForkResult::Child => {
-                        println!("{:?} PostFork", unsafe { libc::getpid() });
-                        self.shmem_provider.post_fork(true)?;
-
-                        #[cfg(feature = "std")]
-                        std::thread::sleep(std::time::Duration::from_millis(index * 100));
-
-                        #[cfg(feature = "std")]
-                        if let Some(file) = stdout_file {
-                            dup2(file.as_raw_fd(), libc::STDOUT_FILENO)?;
-                            dup2(file.as_raw_fd(), libc::STDERR_FILENO)?;
-                        }
-                        // Fuzzer client. keeps retrying the connection to broker till the broker starts
-                        let (state, mgr) = RestartingMgr::<I, MT, OT, S, SP>::builder()
-                            .shmem_provider(self.shmem_provider.clone())
-                            .broker_port(self.broker_port)
-                            .kind(ManagerKind::Client {
-                                cpu_core: Some(*bind_to),
-                            })
-                            .configuration(self.configuration)
-                            .build()
-                            .launch()?;
-
-                        (self.run_client.take().unwrap())(state, mgr, bind_to.id)
-                            .expect("Client closure failed");
-                        break;
-                    }
-                };
+                let delay = Duration::from_millis(index * 100);
+                match self.fork_client(*bind_to, delay, stdout_file.as_ref(), false)? {
+                    Some(pid) => handles.push((pid, *bind_to, Respawns::new())),
+                    // The client is done
+                    None => return Ok(()),
+                }
             }
         }
 
-        if self.spawn_broker {
-            #[cfg(feature = "std")]
-            println!("I am broker!!.");
-
-            // TODO we don't want always a broker here, think about using different laucher process to spawn different configurations
-            RestartingMgr::<I, MT, OT, S, SP>::builder()
-                .shmem_provider(self.shmem_provider.clone())
-                .monitor(Some(self.monitor.clone()))
-                .broker_port(self.broker_port)
-                .kind(ManagerKind::Broker)
-                .remote_broker_addr(self.remote_broker_addr)
-                .configuration(self.configuration)
-                .build()
-                .launch()?;
+        if self.spawn_broker && !self.respawn_clients {
+            self.launch_broker()?;
 
             // Broker exited. kill all clients.
-            for handle in &handles {
+            for (handle, _, _) in &handles {
                 unsafe {
                     libc::kill(*handle, libc::SIGINT);
                 }
             }
+            return Ok(());
+        }
+
+        // Supervise the clients from here, with the broker in a child of its own
+        let broker = if self.spawn_broker {
+            self.shmem_provider.pre_fork()?;
+            match unsafe { fork() }? {
+                ForkResult::Parent(child) => {
+                    self.shmem_provider.post_fork(false)?;
+                    Some(child.pid)
+                }
+                ForkResult::Child => {
+                    self.shmem_provider.post_fork(true)?;
+                    return self.launch_broker();
+                }
+            }
         } else {
-            for handle in &handles {
-                let mut status = 0;
-                println!("Not spawning broker (spawn_broker is false). Waiting for fuzzer children to exit...");
-                unsafe {
-                    libc::waitpid(*handle, &mut status, 0);
-                    if status != 0 {
-                        println!("Client with pid {} exited with status {}", handle, status);
-                    }
+            println!("Not spawning broker (spawn_broker is false). Waiting for fuzzer children to exit...");
+            None
+        };
+
+        while !handles.is_empty() {
+            let mut status = 0;
+            let pid = unsafe { libc::waitpid(-1, &mut status, 0) };
+            if pid < 0 || Some(pid) == broker {
+                break;
+            }
+            let pos = match handles.iter().position(|(handle, _, _)| *handle == pid) {
+                Some(pos) => pos,
+                None => continue,
+            };
+            let (_, bind_to, mut respawns) = handles.remove(pos);
+            // The client is done, do not respawn it
+            if status == 0 {
+                println!("Client on core {} exited cleanly", bind_to.id);
+                continue;
+            }
+            println!("Client with pid {} exited with status {}", pid, status);
+            if self.respawn_clients {
+                // The child waits for its delay, the other clients stay supervised meanwhile
+                let delay = respawns.on_death();
+                println!(
+                    "Respawning the client on core {} in {:?}",
+                    bind_to.id, delay
+                );
+                match self.fork_client(bind_to, delay, stdout_file.as_ref(), true)? {
+                    Some(pid) => handles.push((pid, bind_to, respawns)),
+                    None => return Ok(()),
                 }
             }
         }
 
+        // Broker exited. kill all clients.
+        for (handle, _, _) in &handles {
+            unsafe {
+                libc::kill(*handle, libc::SIGINT);
+            }
+        }
         Ok(())
     }
 
@@ -225,11 +395,11 @@ where
     pub fn launch(&mut self) -> Result<(), Error> {
         let is_client = std::env::var(_AFL_LAUNCHER_CLIENT);
 
-        let mut handles = match is_client {
+        let handles = match is_client {
             Ok(core_conf) => {
                 let core_id = core_conf.parse()?;
-
-                //todo: silence stdout and stderr for clients
+                // Pin before allocating anything, for the maps to land on the NUMA node of this core
+                core_affinity::set_for_current(CoreId { id: core_id });
 
                 // the actual client. do the fuzzing
                 let (state, mgr) = RestartingMgr::<I, MT, OT, S, SP>::builder()
@@ -251,7 +421,7 @@ where
                 // I am a broker
                 // before going to the broker loop, spawn n clients
 
-                if self.stdout_file.is_some() {
+                if self.stdout_file.is_some() && self.stdout_dir.is_none() {
                     println!("Child process file stdio is not supported on Windows yet. Dumping to stdout instead...");
                 }
 
@@ -264,51 +434,200 @@ where
                 //spawn clients
                 for (id, _) in core_ids.iter().enumerate().take(num_cores) {
                     if self.cores.ids.iter().any(|&x| x == id.into()) {
-                        let stdio = if self.stdout_file.is_some() {
-                            Stdio::inherit()
-                        } else {
-                            Stdio::null()
-                        };
-
-                        std::env::set_var(_AFL_LAUNCHER_CLIENT, id.to_string());
-                        let child = startable_self()?.stdout(stdio).spawn()?;
-                        handles.push(child);
+                        handles.push((self.spawn_client(id, false)?, id));
                     }
                 }
 
-                handles
+                Arc::new(Mutex::new(handles))
             }
             Err(_) => panic!("Env variables are broken, received non-unicode!"),
         };
 
         if self.spawn_broker {
-            #[cfg(feature = "std")]
-            println!("I am broker!!.");
-
-            RestartingMgr::<I, MT, OT, S, SP>::builder()
-                .shmem_provider(self.shmem_provider.clone())
-                .monitor(Some(self.monitor.clone()))
-                .broker_port(self.broker_port)
-                .kind(ManagerKind::Broker)
-                .remote_broker_addr(self.remote_broker_addr)
-                .configuration(self.configuration)
-                .build()
-                .launch()?;
+            let stop = Arc::new(AtomicBool::new(false));
+            if self.respawn_clients {
+                let launcher = ClientSpawner::from(&*self);
+                let (handles, stop) = (handles.clone(), stop.clone());
+                thread::spawn(move || launcher.supervise(&handles, &stop));
+            }
+
+            self.launch_broker()?;
 
             //broker exited. kill all clients.
-            for handle in &mut handles {
+            stop.store(true, Ordering::SeqCst);
+            for (handle, _) in handles.lock().unwrap().iter_mut() {
                 handle.kill()?;
             }
         } else {
             println!("Not spawning broker (spawn_broker is false). Waiting for fuzzer children to exit...");
-            for handle in &mut handles {
-                let ecode = handle.wait()?;
-                if !ecode.success() {
-                    println!("Client with handle {:?} exited with {:?}", handle, ecode);
+            ClientSpawner::from(&*self).supervise(&handles, &AtomicBool::new(false))?;
+        }
+
+        Ok(())
+    }
+
+    /// Spawns a client on the core `core_id`, running this same binary
+    #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
+    fn spawn_client(&self, core_id: usize, respawned: bool) -> Result<Child, Error> {
+        ClientSpawner::from(self).spawn(core_id, respawned)
+    }
+}
+
+/// What it takes to spawn and supervise clients, apart from the [`Launcher`]
+#[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
+#[derive(Debug, Clone)]
+struct ClientSpawner {
+    stdout_dir: Option<std::path::PathBuf>,
+    inherit_stdout: bool,
+    respawn_clients: bool,
+}
+
+#[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
+impl<'a, CF, I, MT, OT, S, SP> From<&Launcher<'a, CF, I, MT, OT, S, SP>> for ClientSpawner
+where
+    CF: FnOnce(Option<S>, LlmpRestartingEventManager<I, OT, S, SP>, usize) -> Result<(), Error>,
+    I: Input,
+    MT: Monitor,
+    SP: ShMemProvider + 'static,
+    OT: ObserversTuple<I, S>,
+    S: DeserializeOwned,
+{
+    fn from(launcher: &Launcher<'a, CF, I, MT, OT, S, SP>) -> Self {
+        Self {
+            stdout_dir: launcher.stdout_dir.map(Path::to_path_buf),
+            inherit_stdout: launcher.stdout_file.is_some(),
+            respawn_clients: launcher.respawn_clients,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
+impl ClientSpawner {
+    /// Spawns a client on the core `core_id`, running this same binary
+    fn spawn(&self, core_id: usize, respawned: bool) -> Result<Child, Error> {
+        let mut command = startable_self()?;
+        command.env(_AFL_LAUNCHER_CLIENT, core_id.to_string());
+        if let Some(dir) = &self.stdout_dir {
+            let file = open_client_log(dir, core_id, respawned)?;
+            command
+                .stdout(Stdio::from(file.try_clone()?))
+                .stderr(Stdio::from(file));
+        } else if self.inherit_stdout {
+            command.stdout(Stdio::inherit());
+        } else {
+            command.stdout(Stdio::null());
+        }
+        Ok(command.spawn()?)
+    }
+
+    /// Waits for the clients to exit, respawning those that died if configured, until `stop` is set
+    fn supervise(
+        &self,
+        handles: &Mutex<Vec<(Child, usize)>>,
+        stop: &AtomicBool,
+    ) -> Result<(), Error> {
+        let mut respawns: HashMap<usize, Respawns> = HashMap::new();
+        // The cores of the dead clients, and when to respawn them
+        let mut pending: Vec<(usize, Instant)> = vec![];
+        while !stop.load(Ordering::SeqCst) {
+            {
+                let mut handles = handles.lock().unwrap();
+                if stop.load(Ordering::SeqCst) || (handles.is_empty() && pending.is_empty()) {
+                    break;
+                }
+                let mut idx = 0;
+                while idx < handles.len() {
+                    let (handle, core_id) = &mut handles[idx];
+                    let core_id = *core_id;
+                    match handle.try_wait()? {
+                        None => idx += 1,
+                        Some(ecode) if ecode.success() => {
+                            // The client is done, do not respawn it
+                            println!("Client on core {} exited cleanly", core_id);
+                            handles.remove(idx);
+                        }
+                        Some(ecode) => {
+                            println!("Client with handle {:?} exited with {:?}", handle, ecode);
+                            if self.respawn_clients {
+                                let delay = respawns
+                                    .entry(core_id)
+                                    .or_insert_with(Respawns::new)
+                                    .on_death();
+                                println!(
+                                    "Respawning the client on core {} in {:?}",
+                                    core_id, delay
+                                );
+                                pending.push((core_id, Instant::now() + delay));
+                            }
+                            handles.remove(idx);
+                        }
+                    }
+                }
+
+                let now = Instant::now();
+                let mut idx = 0;
+                while idx < pending.len() {
+                    if pending[idx].1 <= now {
+                        let (core_id, _) = pending.swap_remove(idx);
+                        handles.push((self.spawn(core_id, true)?, core_id));
+                    } else {
+                        idx += 1;
+                    }
                 }
             }
+            thread::sleep(Duration::from_millis(500));
         }
-
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{env, fs, io::Write, time::Instant};
+
+    use crate::bolts::launcher::{
+        open_client_log, respawn_delay, Respawns, RESPAWN_BACKOFF_BASE, RESPAWN_BACKOFF_MAX,
+    };
+
+    #[test]
+    fn test_respawn_backoff() {
+        assert_eq!(respawn_delay(0), RESPAWN_BACKOFF_BASE);
+        assert_eq!(respawn_delay(1), RESPAWN_BACKOFF_BASE * 2);
+        assert_eq!(respawn_delay(3), RESPAWN_BACKOFF_BASE * 8);
+        assert_eq!(respawn_delay(1000), RESPAWN_BACKOFF_MAX);
+
+        // Deaths in a row back off
+        let mut respawns = Respawns::new();
+        assert_eq!(respawns.on_death(), RESPAWN_BACKOFF_BASE);
+        assert_eq!(respawns.on_death(), RESPAWN_BACKOFF_BASE * 2);
+        assert_eq!(respawns.on_death(), RESPAWN_BACKOFF_BASE * 4);
+
+        // A client that lived long enough starts over
+        if let Some(spawned) = Instant::now().checked_sub(RESPAWN_BACKOFF_MAX) {
+            respawns.spawned = spawned;
+            assert_eq!(respawns.on_death(), RESPAWN_BACKOFF_BASE);
+        }
+    }
+
+    #[test]
+    fn test_client_log() {
+        let dir = env::temp_dir().join(format!("libafl_test_client_log_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("client_3.log");
+        fs::write(&log, "last run").unwrap();
+
+        // A new run truncates the log, a respawned client appends to it
+        drop(open_client_log(&dir, 3, false).unwrap());
+        assert_eq!(fs::read_to_string(&log).unwrap(), "");
+        fs::write(&log, "dead client\n").unwrap();
+        let mut file = open_client_log(&dir, 3, true).unwrap();
+        file.write_all(b"respawned client\n").unwrap();
+        drop(file);
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "dead client\nrespawned client\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
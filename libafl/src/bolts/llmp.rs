@@ -52,6 +52,8 @@ with the main thread using [`LlmpBroker::register_client`].
 Finally, call [`LlmpBroker::loop_forever()`].
 
 For broker2broker communication, all messages are forwarded via network sockets.
+With the `llmp_tls` feature, brokers may also connect to each other over TLS, see [`LlmpBroker::connect_b2b_tls`]
+and [`LlmpBroker::launch_tls_listener_on`]. A [`B2bFilter`] decides which messages leave the machine.
 
 Check out the `llmp_test` example in ./examples, or build it with `cargo run --example llmp_test`.
 
@@ -67,6 +69,7 @@ use core::{
     fmt::Debug,
     hint,
     mem::size_of,
+    num::NonZeroUsize,
     ptr, slice,
     sync::atomic::{fence, AtomicU16, Ordering},
     time::Duration,
@@ -76,12 +79,18 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
 use std::{
     env,
-    io::{ErrorKind, Read, Write},
+    io::{self, ErrorKind, Read, Write},
     net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
-    sync::mpsc::channel,
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, RwLock,
+    },
     thread,
 };
 
+#[cfg(feature = "llmp_tls")]
+use native_tls::{TlsAcceptor, TlsConnector, TlsStream};
+
 #[cfg(all(feature = "llmp_debug", feature = "std"))]
 use backtrace::Backtrace;
 
@@ -137,6 +146,10 @@ pub const LLMP_FLAG_FROM_B2B: Flags = 0x2;
 /// before checking for own data to forward again.
 const _LLMP_B2B_BLOCK_TIME: Duration = Duration::from_millis(3_000);
 
+/// Time a new `tls` connection has for its handshake and its first request
+#[cfg(feature = "llmp_tls")]
+const LLMP_TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// If broker2broker is enabled, bind to public IP
 #[cfg(feature = "llmp_bind_public")]
 const _LLMP_BIND_ADDR: &str = "0.0.0.0";
@@ -181,6 +194,10 @@ pub type MessageId = u64;
 #[cfg(not(target_pointer_width = "64"))]
 pub type MessageId = u32;
 
+/// Decides which messages a broker forwards to other brokers.
+/// Gets the tag, flags, and payload of each message of the broker, and returns `true` to forward it.
+pub type B2bFilter = fn(Tag, Flags, &[u8]) -> bool;
+
 /// This is for the server the broker will spawn.
 /// If an llmp connection is local - use sharedmaps
 /// or remote (broker2broker) - forwarded via tcp
@@ -287,12 +304,46 @@ impl TryFrom<Vec<u8>> for TcpResponse {
     }
 }
 
+/// A stream between a broker and its clients or another broker, over plain `tcp` or `tls`
+#[cfg(feature = "std")]
+pub trait B2bStream: Read + Write + Send + Debug + 'static {
+    /// Sets the timeout for reads, after which the broker 2 broker thread forwards its own messages
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+#[cfg(feature = "std")]
+impl B2bStream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[cfg(feature = "llmp_tls")]
+impl B2bStream for TlsStream<TcpStream> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set_read_timeout(timeout)
+    }
+}
+
 /// Abstraction for listeners
 #[cfg(feature = "std")]
-#[derive(Debug)]
 pub enum Listener {
     /// Listener listening on `tcp`.
     Tcp(TcpListener),
+    /// Listener listening on `tcp`, accepting `tls` connections only.
+    #[cfg(feature = "llmp_tls")]
+    Tls(TcpListener, TlsAcceptor),
+}
+
+#[cfg(feature = "std")]
+impl Debug for Listener {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Listener::Tcp(inner) => f.debug_tuple("Tcp").field(inner).finish(),
+            #[cfg(feature = "llmp_tls")]
+            Listener::Tls(inner, _) => f.debug_tuple("Tls").field(inner).finish(),
+        }
+    }
 }
 
 /// A listener stream abstraction
@@ -301,25 +352,67 @@ pub enum Listener {
 pub enum ListenerStream {
     /// Listener listening on `tcp`.
     Tcp(TcpStream, SocketAddr),
+    /// Listener listening on `tcp`, after the `tls` handshake.
+    #[cfg(feature = "llmp_tls")]
+    Tls(TlsStream<TcpStream>, SocketAddr),
     /// No listener provided.
     Empty(),
 }
 
 #[cfg(feature = "std")]
 impl Listener {
-    fn accept(&self) -> ListenerStream {
+    /// Accepts the next connection, and hands it to the listener thread once it is ready
+    fn accept(&self, ready: &Sender<ListenerStream>) {
         match self {
             Listener::Tcp(inner) => match inner.accept() {
-                Ok(res) => ListenerStream::Tcp(res.0, res.1),
-                Err(err) => {
-                    println!("Ignoring failed accept: {:?}", err);
-                    ListenerStream::Empty()
+                Ok((stream, addr)) => drop(ready.send(ListenerStream::Tcp(stream, addr))),
+                Err(err) => println!("Ignoring failed accept: {:?}", err),
+            },
+            #[cfg(feature = "llmp_tls")]
+            Listener::Tls(inner, acceptor) => match inner.accept() {
+                Ok((stream, addr)) => {
+                    // The handshake runs in its own thread, a slow peer does not hold up the other connections
+                    let acceptor = acceptor.clone();
+                    let ready = ready.clone();
+                    thread::spawn(move || {
+                        if let Some(stream) = tls_handshake(&acceptor, stream, addr) {
+                            drop(ready.send(ListenerStream::Tls(stream, addr)));
+                        }
+                    });
                 }
+                Err(err) => println!("Ignoring failed accept: {:?}", err),
             },
         }
     }
 }
 
+/// The `tls` handshake with a new connection, failing after [`LLMP_TLS_HANDSHAKE_TIMEOUT`]
+#[cfg(feature = "llmp_tls")]
+fn tls_handshake(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    addr: SocketAddr,
+) -> Option<TlsStream<TcpStream>> {
+    if let Err(err) = stream
+        .set_read_timeout(Some(LLMP_TLS_HANDSHAKE_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(LLMP_TLS_HANDSHAKE_TIMEOUT)))
+    {
+        println!("Ignoring tls connection from {}: {:?}", addr, err);
+        return None;
+    }
+    match acceptor.accept(stream) {
+        Ok(stream) => {
+            // The broker 2 broker thread sets its own read timeout, writes may block
+            drop(stream.get_ref().set_write_timeout(None));
+            Some(stream)
+        }
+        Err(err) => {
+            println!("Ignoring failed tls handshake with {}: {:?}", addr, err);
+            None
+        }
+    }
+}
+
 /// Get sharedmem from a page
 #[inline]
 #[allow(clippy::cast_ptr_alignment)]
@@ -386,8 +479,9 @@ fn tcp_bind(port: u16) -> Result<TcpListener, Error> {
 
 /// Send one message as `u32` len and `[u8;len]` bytes
 #[cfg(feature = "std")]
-fn send_tcp_msg<T>(stream: &mut TcpStream, msg: &T) -> Result<(), Error>
+fn send_tcp_msg<S, T>(stream: &mut S, msg: &T) -> Result<(), Error>
 where
+    S: Write,
     T: Serialize,
 {
     let msg = postcard::to_allocvec(msg)?;
@@ -413,14 +507,14 @@ where
 
 /// Receive one message of `u32` len and `[u8; len]` bytes
 #[cfg(feature = "std")]
-fn recv_tcp_msg(stream: &mut TcpStream) -> Result<Vec<u8>, Error> {
+fn recv_tcp_msg<S>(stream: &mut S) -> Result<Vec<u8>, Error>
+where
+    S: Read,
+{
     // Always receive one be u32 of size, then the command.
 
     #[cfg(feature = "llmp_debug")]
-    println!(
-        "LLMP TCP: Waiting for packet... (Timeout: {:?})",
-        stream.read_timeout().unwrap_or(None)
-    );
+    println!("LLMP TCP: Waiting for packet...");
 
     let mut size_bytes = [0_u8; 4];
     stream.read_exact(&mut size_bytes)?;
@@ -846,6 +940,12 @@ where
         unsafe { current_out_shmem.msg_to_env(self.last_msg_sent, env_name) }
     }
 
+    /// Tells the receiver that this sender is done, and exits cleanly.
+    /// A broker counts its clients exiting, see [`LlmpBroker::set_exit_cleanly_after`].
+    pub fn send_exiting(&mut self) -> Result<(), Error> {
+        self.send_buf(LLMP_TAG_EXITING, &[])
+    }
+
     /// Waits for this sender to be save to unmap.
     /// If a receiver is involved, this function should always be called.
     pub fn await_safe_to_unmap_blocking(&self) {
@@ -1760,6 +1860,13 @@ where
     pub llmp_clients: Vec<LlmpReceiver<SP>>,
     /// The ShMemProvider to use
     shmem_provider: SP,
+    /// The filter for messages to other brokers, shared with the listener thread
+    #[cfg(feature = "std")]
+    b2b_filter: Arc<RwLock<Option<B2bFilter>>>,
+    /// The clients that exited cleanly
+    exited_clients: Vec<ClientId>,
+    /// The broker exits once this many clients exited cleanly
+    exit_cleanly_after: Option<NonZeroUsize>,
 }
 
 /// A signal handler for the [`LlmpBroker`].
@@ -1806,9 +1913,32 @@ where
             },
             llmp_clients: vec![],
             shmem_provider,
+            #[cfg(feature = "std")]
+            b2b_filter: Arc::new(RwLock::new(None)),
+            exited_clients: vec![],
+            exit_cleanly_after: None,
         })
     }
 
+    /// Exits the broker loop once `n_clients` clients exited cleanly, see [`LlmpClient::send_exiting`].
+    /// Clients that crash or get killed do not count.
+    pub fn set_exit_cleanly_after(&mut self, n_clients: NonZeroUsize) {
+        self.exit_cleanly_after = Some(n_clients);
+    }
+
+    /// If enough clients exited cleanly, see [`LlmpBroker::set_exit_cleanly_after`]
+    fn clients_exited(&self) -> bool {
+        self.exit_cleanly_after
+            .map_or(false, |n| self.exited_clients.len() >= n.get())
+    }
+
+    /// Only forwards the messages accepted by `filter` to other brokers, or all of them for `None`.
+    /// Applies to the broker 2 broker connections established from now on.
+    #[cfg(feature = "std")]
+    pub fn set_b2b_filter(&mut self, filter: Option<B2bFilter>) {
+        *self.b2b_filter.write().unwrap() = filter;
+    }
+
     /// Create a new [`LlmpBroker`] sttaching to a TCP port
     #[cfg(feature = "std")]
     pub fn create_attach_to_tcp(shmem_provider: SP, port: u16) -> Result<Self, Error> {
@@ -1851,7 +1981,34 @@ where
     where
         A: ToSocketAddrs,
     {
-        let mut stream = TcpStream::connect(addr)?;
+        self.connect_b2b_over(TcpStream::connect(addr)?)
+    }
+
+    /// Connects to a broker running on another machine, over `tls`.
+    /// The remote broker has to listen using [`LlmpBroker::launch_tls_listener_on`].
+    /// The `domain` is checked against the certificate of the remote broker.
+    #[cfg(feature = "llmp_tls")]
+    pub fn connect_b2b_tls<A>(
+        &mut self,
+        addr: A,
+        domain: &str,
+        connector: &TlsConnector,
+    ) -> Result<(), Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let stream = connector
+            .connect(domain, TcpStream::connect(addr)?)
+            .map_err(|e| Error::Unknown(format!("B2B: TLS handshake failed: {}", e)))?;
+        self.connect_b2b_over(stream)
+    }
+
+    /// Connects to a broker running on another machine, over an already established `stream`.
+    #[cfg(feature = "std")]
+    pub fn connect_b2b_over<S>(&mut self, mut stream: S) -> Result<(), Error>
+    where
+        S: B2bStream,
+    {
         println!("B2B: Connected to {:?}", stream);
 
         match recv_tcp_msg(&mut stream)?.try_into()? {
@@ -1899,6 +2056,7 @@ where
                 .unwrap()
                 .shmem
                 .description(),
+            *self.b2b_filter.read().unwrap(),
         )?;
 
         let new_shmem = LlmpSharedMap::existing(
@@ -1932,6 +2090,24 @@ where
         Ok(())
     }
 
+    /// Broadcasts a new `buf` in place of `msg`, keeping its sender, broker, and [`LLMP_FLAG_FROM_B2B`]
+    unsafe fn forward_rewritten_msg(
+        &mut self,
+        msg: *const LlmpMsg,
+        tag: Tag,
+        flags: Flags,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        let out: *mut LlmpMsg = self.alloc_next(buf.len())?;
+        (*out).tag = tag;
+        (*out).flags = flags | ((*msg).flags & LLMP_FLAG_FROM_B2B);
+        (*out).sender = (*msg).sender;
+        (*out).broker = (*msg).broker;
+        buf.as_ptr()
+            .copy_to_nonoverlapping((*out).buf.as_mut_ptr(), buf.len());
+        self.llmp_out.send(out, false)
+    }
+
     /// The broker walks all pages and looks for changes, then broadcasts them on
     /// its own shared page, once.
     #[inline]
@@ -1939,9 +2115,33 @@ where
     where
         F: FnMut(ClientId, Tag, Flags, &[u8]) -> Result<LlmpMsgHookResult, Error>,
     {
+        self.once_with_outbox(
+            &mut |client_id, tag, flags, msg, _outbox: &mut Vec<(Tag, Flags, Vec<u8>)>| {
+                on_new_msg(client_id, tag, flags, msg)
+            },
+        )
+    }
+
+    /// Like [`LlmpBroker::once`], broadcasting the messages the hook pushes to the outbox
+    /// in place of each message, see [`LlmpBroker::loop_forever_with_outbox`].
+    #[inline]
+    pub fn once_with_outbox<F>(&mut self, on_new_msg: &mut F) -> Result<(), Error>
+    where
+        F: FnMut(
+            ClientId,
+            Tag,
+            Flags,
+            &[u8],
+            &mut Vec<(Tag, Flags, Vec<u8>)>,
+        ) -> Result<LlmpMsgHookResult, Error>,
+    {
+        let mut outbox = vec![];
         for i in 0..self.llmp_clients.len() {
+            if self.exited_clients.contains(&(i as ClientId)) {
+                continue;
+            }
             unsafe {
-                self.handle_new_msgs(i as u32, on_new_msg)?;
+                self.handle_new_msgs(i as u32, on_new_msg, &mut outbox)?;
             }
         }
         Ok(())
@@ -1969,6 +2169,27 @@ where
     pub fn loop_forever<F>(&mut self, on_new_msg: &mut F, sleep_time: Option<Duration>)
     where
         F: FnMut(ClientId, Tag, Flags, &[u8]) -> Result<LlmpMsgHookResult, Error>,
+    {
+        self.loop_forever_with_outbox(
+            &mut |client_id, tag, flags, msg, _outbox: &mut Vec<(Tag, Flags, Vec<u8>)>| {
+                on_new_msg(client_id, tag, flags, msg)
+            },
+            sleep_time,
+        );
+    }
+
+    /// Loops infinitely, forwarding and handling all incoming messages from clients, like [`LlmpBroker::loop_forever`].
+    /// The hook may also broadcast new messages in place of a message, for example rewritten ones, by pushing their tag, flags, and payload to the outbox.
+    /// They keep the sender of the message, and its [`LLMP_FLAG_FROM_B2B`], so they are not sent back to where they came from.
+    pub fn loop_forever_with_outbox<F>(&mut self, on_new_msg: &mut F, sleep_time: Option<Duration>)
+    where
+        F: FnMut(
+            ClientId,
+            Tag,
+            Flags,
+            &[u8],
+            &mut Vec<(Tag, Flags, Vec<u8>)>,
+        ) -> Result<LlmpMsgHookResult, Error>,
     {
         #[cfg(unix)]
         if let Err(_e) = unsafe { setup_signal_handler(&mut GLOBAL_SIGHANDLER_STATE) } {
@@ -1977,8 +2198,8 @@ where
             println!("Failed to setup signal handlers: {}", _e);
         }
 
-        while !self.is_shutting_down() {
-            self.once(on_new_msg)
+        while !self.is_shutting_down() && !self.clients_exited() {
+            self.once_with_outbox(on_new_msg)
                 .expect("An error occurred when brokering. Exiting.");
 
             #[cfg(feature = "std")]
@@ -2016,6 +2237,19 @@ where
         self.launch_listener(Listener::Tcp(listener))
     }
 
+    /// Launches a thread using a tcp listener socket on the given port, on which other brokers
+    /// may connect to this broker over `tls`, using [`LlmpBroker::connect_b2b_tls`].
+    #[cfg(feature = "llmp_tls")]
+    pub fn launch_tls_listener_on(
+        &mut self,
+        port: u16,
+        acceptor: TlsAcceptor,
+    ) -> Result<thread::JoinHandle<()>, Error> {
+        let listener = tcp_bind(port)?;
+        println!("Server listening for tls on port {}", port);
+        self.launch_listener(Listener::Tls(listener, acceptor))
+    }
+
     /// Announces a new client on the given shared map.
     /// Called from a background thread, typically.
     /// Upon receiving this message, the broker should map the announced page and start trckang it for new messages.
@@ -2044,11 +2278,15 @@ where
     /// The thread exits, when the remote broker disconnects.
     #[cfg(feature = "std")]
     #[allow(clippy::let_and_return)]
-    fn b2b_thread_on(
-        mut stream: TcpStream,
+    fn b2b_thread_on<S>(
+        mut stream: S,
         b2b_client_id: ClientId,
         broker_shmem_description: &ShMemDescription,
-    ) -> Result<ShMemDescription, Error> {
+        filter: Option<B2bFilter>,
+    ) -> Result<ShMemDescription, Error>
+    where
+        S: B2bStream,
+    {
         let broker_shmem_description = *broker_shmem_description;
 
         // A channel to get the new "client's" sharedmap id from
@@ -2105,6 +2343,12 @@ where
                         continue;
                     }
 
+                    if let Some(filter) = filter {
+                        if !filter(tag, flags, payload) {
+                            continue;
+                        }
+                    }
+
                     #[cfg(all(feature = "llmp_debug", feature = "std"))]
                     println!(
                         "Fowarding message ({} bytes) via broker2broker connection",
@@ -2164,13 +2408,16 @@ where
 
     /// handles a single tcp request in the current context.
     #[cfg(feature = "std")]
-    fn handle_tcp_request(
-        mut stream: TcpStream,
+    fn handle_tcp_request<S>(
+        mut stream: S,
         request: &TcpRequest,
         current_client_id: &mut u32,
         sender: &mut LlmpSender<SP>,
         broker_shmem_description: &ShMemDescription,
-    ) {
+        b2b_filter: Option<B2bFilter>,
+    ) where
+        S: B2bStream,
+    {
         match request {
             TcpRequest::LocalClientHello { shmem_description } => {
                 match Self::announce_new_client(sender, shmem_description) {
@@ -2204,9 +2451,12 @@ where
                     return;
                 }
 
-                if let Ok(shmem_description) =
-                    Self::b2b_thread_on(stream, *current_client_id, broker_shmem_description)
-                {
+                if let Ok(shmem_description) = Self::b2b_thread_on(
+                    stream,
+                    *current_client_id,
+                    broker_shmem_description,
+                    b2b_filter,
+                ) {
                     if Self::announce_new_client(sender, &shmem_description).is_err() {
                         println!("B2B: Error announcing client {:?}", shmem_description);
                     };
@@ -2216,6 +2466,53 @@ where
         };
     }
 
+    /// Greets a new connection to the listener, and handles its request.
+    #[cfg(feature = "std")]
+    fn handle_connection<S>(
+        mut stream: S,
+        addr: SocketAddr,
+        broker_hello: &TcpResponse,
+        current_client_id: &mut u32,
+        sender: &mut LlmpSender<SP>,
+        broker_shmem_description: &ShMemDescription,
+        b2b_filter: Option<B2bFilter>,
+    ) where
+        S: B2bStream,
+    {
+        eprintln!("New connection: {:?}", addr);
+
+        // Send initial information, without anyone asking.
+        // This makes it a tiny bit easier to map the  broker map for new Clients.
+        if let Err(e) = send_tcp_msg(&mut stream, broker_hello) {
+            eprintln!("Error sending initial hello: {:?}", e);
+            return;
+        }
+
+        let buf = match recv_tcp_msg(&mut stream) {
+            Ok(buf) => buf,
+            Err(e) => {
+                eprintln!("Error receving from tcp: {:?}", e);
+                return;
+            }
+        };
+        let req = match buf.try_into() {
+            Ok(req) => req,
+            Err(e) => {
+                eprintln!("Could not deserialize tcp message: {:?}", e);
+                return;
+            }
+        };
+
+        Self::handle_tcp_request(
+            stream,
+            &req,
+            current_client_id,
+            sender,
+            broker_shmem_description,
+            b2b_filter,
+        );
+    }
+
     #[cfg(feature = "std")]
     /// Launches a thread using a listener socket, on which new clients may connect to this broker
     pub fn launch_listener(&mut self, listener: Listener) -> Result<thread::JoinHandle<()>, Error> {
@@ -2236,6 +2533,7 @@ where
         };
 
         let llmp_tcp_id = self.llmp_clients.len() as ClientId;
+        let b2b_filter = self.b2b_filter.clone();
 
         // Tcp out map sends messages from background thread tcp server to foreground client
         let tcp_out_shmem = LlmpSharedMap::new(
@@ -2265,51 +2563,34 @@ where
                 shmem_provider: shmem_provider_bg.clone(),
             };
 
-            loop {
-                match listener.accept() {
-                    ListenerStream::Tcp(mut stream, addr) => {
-                        eprintln!(
-                            "New connection: {:?}/{:?}",
-                            addr,
-                            stream.peer_addr().unwrap()
-                        );
-
-                        // Send initial information, without anyone asking.
-                        // This makes it a tiny bit easier to map the  broker map for new Clients.
-                        match send_tcp_msg(&mut stream, &broker_hello) {
-                            Ok(()) => {}
-                            Err(e) => {
-                                eprintln!("Error sending initial hello: {:?}", e);
-                                continue;
-                            }
-                        }
-
-                        let buf = match recv_tcp_msg(&mut stream) {
-                            Ok(buf) => buf,
-                            Err(e) => {
-                                eprintln!("Error receving from tcp: {:?}", e);
-                                continue;
-                            }
-                        };
-                        let req = match buf.try_into() {
-                            Ok(req) => req,
-                            Err(e) => {
-                                eprintln!("Could not deserialize tcp message: {:?}", e);
-                                continue;
-                            }
-                        };
-
-                        Self::handle_tcp_request(
-                            stream,
-                            &req,
-                            &mut current_client_id,
-                            &mut tcp_incoming_sender,
-                            &broker_shmem_description,
-                        );
-                    }
-                    ListenerStream::Empty() => {
-                        continue;
-                    }
+            // Connections are accepted in the background, until they are ready to be handled here
+            let (ready_sender, ready_receiver) = channel();
+            thread::spawn(move || loop {
+                listener.accept(&ready_sender);
+            });
+
+            for stream in ready_receiver {
+                match stream {
+                    ListenerStream::Tcp(stream, addr) => Self::handle_connection(
+                        stream,
+                        addr,
+                        &broker_hello,
+                        &mut current_client_id,
+                        &mut tcp_incoming_sender,
+                        &broker_shmem_description,
+                        *b2b_filter.read().unwrap(),
+                    ),
+                    #[cfg(feature = "llmp_tls")]
+                    ListenerStream::Tls(stream, addr) => Self::handle_connection(
+                        stream,
+                        addr,
+                        &broker_hello,
+                        &mut current_client_id,
+                        &mut tcp_incoming_sender,
+                        &broker_shmem_description,
+                        *b2b_filter.read().unwrap(),
+                    ),
+                    ListenerStream::Empty() => (),
                 };
             }
         });
@@ -2320,9 +2601,20 @@ where
     /// broker broadcast to its own page for all others to read */
     #[inline]
     #[allow(clippy::cast_ptr_alignment)]
-    unsafe fn handle_new_msgs<F>(&mut self, client_id: u32, on_new_msg: &mut F) -> Result<(), Error>
+    unsafe fn handle_new_msgs<F>(
+        &mut self,
+        client_id: u32,
+        on_new_msg: &mut F,
+        outbox: &mut Vec<(Tag, Flags, Vec<u8>)>,
+    ) -> Result<(), Error>
     where
-        F: FnMut(ClientId, Tag, Flags, &[u8]) -> Result<LlmpMsgHookResult, Error>,
+        F: FnMut(
+            ClientId,
+            Tag,
+            Flags,
+            &[u8],
+            &mut Vec<(Tag, Flags, Vec<u8>)>,
+        ) -> Result<LlmpMsgHookResult, Error>,
     {
         let mut next_id = self.llmp_clients.len() as u32;
 
@@ -2330,12 +2622,20 @@ where
         loop {
             let msg = {
                 let client = &mut self.llmp_clients[client_id as usize];
-                match client.recv()? {
-                    None => {
+                match client.recv() {
+                    Ok(None) => {
                         // We're done handling this client
                         return Ok(());
                     }
-                    Some(msg) => msg,
+                    Ok(Some(msg)) => msg,
+                    Err(Error::ShuttingDown) => {
+                        // The client exited cleanly, it won't send anything else
+                        #[cfg(feature = "std")]
+                        println!("Client {} exited", client_id);
+                        self.exited_clients.push(client_id);
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e),
                 }
             };
 
@@ -2398,13 +2698,16 @@ where
                     let map = &mut self.llmp_clients[client_id as usize].current_recv_shmem;
                     let msg_buf = (*msg).try_as_slice(map)?;
                     if let LlmpMsgHookResult::Handled =
-                        (on_new_msg)(client_id, (*msg).tag, (*msg).flags, msg_buf)?
+                        (on_new_msg)(client_id, (*msg).tag, (*msg).flags, msg_buf, outbox)?
                     {
                         should_forward_msg = false;
                     }
                     if should_forward_msg {
                         self.forward_msg(msg)?;
                     }
+                    for (tag, flags, buf) in outbox.drain(..) {
+                        self.forward_rewritten_msg(msg, tag, flags, &buf)?;
+                    }
                 }
             }
         }
@@ -2517,6 +2820,11 @@ where
         self.sender.await_safe_to_unmap_blocking();
     }
 
+    /// Tells the broker that this client is done, and exits cleanly
+    pub fn send_exiting(&mut self) -> Result<(), Error> {
+        self.sender.send_exiting()
+    }
+
     /// If we are allowed to unmap this client
     pub fn safe_to_unmap(&self) -> bool {
         self.sender.safe_to_unmap()
@@ -2723,6 +3031,7 @@ where
 #[cfg(all(unix, feature = "std"))]
 mod tests {
 
+    use core::num::NonZeroUsize;
     use std::{thread::sleep, time::Duration};
 
     use serial_test::serial;
@@ -2730,8 +3039,8 @@ mod tests {
     use super::{
         LlmpClient,
         LlmpConnection::{self, IsBroker, IsClient},
-        LlmpMsgHookResult::ForwardToClients,
-        Tag,
+        LlmpMsgHookResult::{ForwardToClients, Handled},
+        Tag, LLMP_FLAG_INITIALIZED,
     };
 
     use crate::bolts::shmem::{ShMemProvider, StdShMemProvider};
@@ -2787,4 +3096,63 @@ mod tests {
         // We want at least the tcp and sender clients.
         assert_eq!(broker.llmp_clients.len(), 2);
     }
+
+    #[test]
+    #[serial]
+    pub fn llmp_rewritten_msg_keeps_sender() {
+        let shmem_provider = StdShMemProvider::new().unwrap();
+        let mut broker = match LlmpConnection::on_port(shmem_provider.clone(), 1338).unwrap() {
+            IsClient { client: _ } => panic!("Could not bind to port as broker"),
+            IsBroker { broker } => broker,
+        };
+        let mut client = match LlmpConnection::on_port(shmem_provider, 1338).unwrap() {
+            IsBroker { broker: _ } => panic!("Second connect should be a client!"),
+            IsClient { client } => client,
+        };
+        sleep(Duration::from_millis(100));
+        broker
+            .once(&mut |_sender_id, _tag, _flags, _msg| Ok(ForwardToClients))
+            .unwrap();
+
+        client.send_buf(0x1337, &[1]).unwrap();
+        broker
+            .once_with_outbox(&mut |_sender_id, _tag, _flags, _msg, outbox| {
+                outbox.push((0x1338, LLMP_FLAG_INITIALIZED, vec![2]));
+                Ok(Handled)
+            })
+            .unwrap();
+
+        // Only the rewritten message arrives, from the original sender
+        let client_id = client.sender.id;
+        let (sender_id, tag, buf) = client.recv_buf_blocking().unwrap();
+        assert_eq!(sender_id, client_id);
+        assert_eq!(tag, 0x1338);
+        assert_eq!(buf, &[2]);
+    }
+
+    #[test]
+    #[serial]
+    pub fn llmp_exit_cleanly() {
+        let shmem_provider = StdShMemProvider::new().unwrap();
+        let mut broker = match LlmpConnection::on_port(shmem_provider.clone(), 1339).unwrap() {
+            IsClient { client: _ } => panic!("Could not bind to port as broker"),
+            IsBroker { broker } => broker,
+        };
+        broker.set_exit_cleanly_after(NonZeroUsize::new(1).unwrap());
+        let mut client = match LlmpConnection::on_port(shmem_provider, 1339).unwrap() {
+            IsBroker { broker: _ } => panic!("Second connect should be a client!"),
+            IsClient { client } => client,
+        };
+        sleep(Duration::from_millis(100));
+        broker
+            .once(&mut |_sender_id, _tag, _flags, _msg| Ok(ForwardToClients))
+            .unwrap();
+        assert!(!broker.clients_exited());
+
+        client.send_exiting().unwrap();
+        broker
+            .once(&mut |_sender_id, _tag, _flags, _msg| Ok(ForwardToClients))
+            .unwrap();
+        assert!(broker.clients_exited());
+    }
 }
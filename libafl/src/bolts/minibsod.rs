@@ -1,12 +1,21 @@
 //! Implements a mini-bsod generator.
 //! It dumps all important registers and prints a stacktrace.
-//! You may use the [`crate::bolts::os::unix_signals::ucontext`]
-//! function to get a [`ucontext_t`].
+//! On unix, you may use the [`crate::bolts::os::unix_signals::ucontext`]
+//! function to get a `ucontext_t`.
+//! On Windows, the context comes from the `EXCEPTION_POINTERS` of the exception handler.
 
+#[cfg(unix)]
 use libc::siginfo_t;
 use std::io::{BufWriter, Write};
 
+#[cfg(unix)]
 use crate::bolts::os::unix_signals::{ucontext_t, Signal};
+#[cfg(windows)]
+use crate::bolts::os::windows_exceptions::{
+    exception_addresses, exception_code, EXCEPTION_POINTERS,
+};
+#[cfg(windows)]
+use windows::Win32::System::Diagnostics::Debug::CONTEXT;
 
 /// Write the content of all important registers
 #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
@@ -153,7 +162,10 @@ pub fn dump_registers<W: Write>(
 }
 
 #[allow(clippy::unnecessary_wraps)]
-#[cfg(not(any(target_vendor = "apple", target_os = "linux", target_os = "android")))]
+#[cfg(all(
+    unix,
+    not(any(target_vendor = "apple", target_os = "linux", target_os = "android"))
+))]
 fn dump_registers<W: Write>(
     writer: &mut BufWriter<W>,
     _ucontext: &ucontext_t,
@@ -255,7 +267,10 @@ fn write_crash<W: Write>(
     Ok(())
 }
 
-#[cfg(not(any(target_vendor = "apple", target_os = "linux", target_os = "android")))]
+#[cfg(all(
+    unix,
+    not(any(target_vendor = "apple", target_os = "linux", target_os = "android"))
+))]
 fn write_crash<W: Write>(
     writer: &mut BufWriter<W>,
     signal: Signal,
@@ -295,7 +310,86 @@ pub fn generate_minibsod<W: Write>(
     Ok(())
 }
 
-#[cfg(test)]
+/// Write the content of all important registers
+#[cfg(all(windows, target_arch = "x86_64"))]
+#[allow(clippy::similar_names)]
+pub fn dump_registers<W: Write>(
+    writer: &mut BufWriter<W>,
+    context: &CONTEXT,
+) -> Result<(), std::io::Error> {
+    write!(writer, "r8 : {:#016x}, ", context.R8)?;
+    write!(writer, "r9 : {:#016x}, ", context.R9)?;
+    write!(writer, "r10: {:#016x}, ", context.R10)?;
+    writeln!(writer, "r11: {:#016x}, ", context.R11)?;
+    write!(writer, "r12: {:#016x}, ", context.R12)?;
+    write!(writer, "r13: {:#016x}, ", context.R13)?;
+    write!(writer, "r14: {:#016x}, ", context.R14)?;
+    writeln!(writer, "r15: {:#016x}, ", context.R15)?;
+    write!(writer, "rdi: {:#016x}, ", context.Rdi)?;
+    write!(writer, "rsi: {:#016x}, ", context.Rsi)?;
+    write!(writer, "rbp: {:#016x}, ", context.Rbp)?;
+    writeln!(writer, "rbx: {:#016x}, ", context.Rbx)?;
+    write!(writer, "rdx: {:#016x}, ", context.Rdx)?;
+    write!(writer, "rax: {:#016x}, ", context.Rax)?;
+    write!(writer, "rcx: {:#016x}, ", context.Rcx)?;
+    writeln!(writer, "rsp: {:#016x}, ", context.Rsp)?;
+    write!(writer, "rip: {:#016x}, ", context.Rip)?;
+    writeln!(writer, "efl: {:#016x}, ", context.EFlags)?;
+
+    Ok(())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+#[cfg(all(windows, not(target_arch = "x86_64")))]
+fn dump_registers<W: Write>(
+    writer: &mut BufWriter<W>,
+    _context: &CONTEXT,
+) -> Result<(), std::io::Error> {
+    // TODO: Implement dump registers
+    writeln!(
+        writer,
+        "< Dumping registers is not yet supported on platform {:?}. Please add it to `minibsod.rs` >",
+        std::env::consts::ARCH
+    )?;
+    Ok(())
+}
+
+/// Generates a mini-BSOD given the `EXCEPTION_POINTERS` of an exception handler.
+/// The pointers are null for the `abort` signals, then only the backtrace is written.
+///
+/// # Safety
+/// The pointers must be null or point to a valid exception, as passed to the exception handlers.
+#[cfg(windows)]
+#[allow(clippy::non_ascii_literal)]
+pub unsafe fn generate_minibsod<W: Write>(
+    writer: &mut BufWriter<W>,
+    exception_pointers: *const EXCEPTION_POINTERS,
+) -> Result<(), std::io::Error> {
+    writeln!(writer, "{:━^100}", " CRASH ")?;
+    let code = exception_code(exception_pointers);
+    match exception_addresses(exception_pointers) {
+        (Some(pc), Some(fault_address)) => writeln!(
+            writer,
+            "Received exception {} at {:#016x}, fault address: {:#016x}",
+            code, pc, fault_address
+        )?,
+        (Some(pc), None) => writeln!(writer, "Received exception {} at {:#016x}", code, pc)?,
+        _ => writeln!(writer, "Received exception {}", code)?,
+    }
+    if let Some(context) = exception_pointers
+        .as_ref()
+        .and_then(|pointers| pointers.ContextRecord.as_ref())
+    {
+        writeln!(writer, "{:━^100}", " REGISTERS ")?;
+        dump_registers(writer, context)?;
+    }
+    writeln!(writer, "{:━^100}", " BACKTRACE ")?;
+    writeln!(writer, "{:?}", backtrace::Backtrace::new())?;
+
+    Ok(())
+}
+
+#[cfg(all(test, unix, not(target_os = "android")))]
 mod tests {
 
     use std::io::{stdout, BufWriter};
@@ -1,6 +1,8 @@
 //! Bolts are no conceptual fuzzing elements, but they keep libafl-based fuzzers together.
 
 pub mod anymap;
+#[cfg(feature = "std")]
+pub mod bench;
 #[cfg(all(
     any(feature = "cli", feature = "frida_cli", feature = "qemu_cli"),
     feature = "std"
@@ -8,13 +10,14 @@ pub mod anymap;
 pub mod cli;
 #[cfg(feature = "llmp_compression")]
 pub mod compress;
+pub mod coverage_report;
 pub mod cpu;
 #[cfg(feature = "std")]
 pub mod fs;
 #[cfg(feature = "std")]
 pub mod launcher;
 pub mod llmp;
-#[cfg(all(feature = "std", unix))]
+#[cfg(all(feature = "std", any(unix, windows)))]
 pub mod minibsod;
 pub mod os;
 pub mod ownedref;
@@ -133,6 +133,31 @@ impl CoreId {
     pub fn set_affinity(&self) {
         core_affinity::set_for_current(self.into());
     }
+
+    /// The core the current thread runs on, if the OS tells
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        let cpu = unsafe { libc::sched_getcpu() };
+        usize::try_from(cpu).ok().map(|id| CoreId { id })
+    }
+
+    /// The NUMA node of this core, if the OS tells
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn numa_node(&self) -> Option<usize> {
+        std::fs::read_dir(format!("/sys/devices/system/cpu/cpu{}", self.id))
+            .ok()?
+            .filter_map(Result::ok)
+            .find_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix("node")?
+                    .parse()
+                    .ok()
+            })
+    }
 }
 
 impl From<usize> for CoreId {
@@ -226,6 +251,40 @@ impl Cores {
         let core_id = CoreId::from(core_id);
         self.ids.contains(&core_id)
     }
+
+    /// The NUMA nodes of these cores, in order of appearance, as far as the OS tells
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn numa_nodes(&self) -> Vec<usize> {
+        let mut nodes = vec![];
+        for node in self.ids.iter().filter_map(CoreId::numa_node) {
+            if !nodes.contains(&node) {
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
+
+    /// The cores of this [`Cores`] instance on the NUMA node `node`
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn on_numa_node(&self, node: usize) -> Self {
+        let ids = self
+            .ids
+            .iter()
+            .filter(|core_id| core_id.numa_node() == Some(node))
+            .map(|core_id| core_id.id)
+            .collect::<Vec<_>>();
+        Self::from(ids)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for Cores {
+    type Err = Error;
+    fn from_str(cores: &str) -> Result<Self, Self::Err> {
+        Self::from_cmdline(cores)
+    }
 }
 
 impl From<&[usize]> for Cores {
@@ -8,7 +8,7 @@ use core::{
     sync::atomic::{compiler_fence, Ordering},
 };
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(target_os = "android")))]
 use nix::errno::{errno, Errno};
 #[cfg(feature = "std")]
 use std::ffi::CString;
@@ -99,6 +99,8 @@ use crate::Error;
 
 pub use libc::{c_void, siginfo_t};
 
+// Bionic has no `getcontext`
+#[cfg(not(target_os = "android"))]
 extern "C" {
     fn getcontext(ucp: *mut ucontext_t) -> c_int;
 }
@@ -280,7 +282,8 @@ pub unsafe fn setup_signal_handler<T: 'static + Handler>(handler: &mut T) -> Res
 /// Note that calling this method may, of course, alter the state.
 /// We wrap it here, as it seems to be (currently)
 /// not available on `MacOS` in the `libc` crate.
-#[cfg(unix)]
+/// Android's Bionic libc has no `getcontext`, so this is not available there.
+#[cfg(all(unix, not(target_os = "android")))]
 #[allow(clippy::inline_always)] // we assume that inlining will destroy less state
 #[inline(always)]
 pub fn ucontext() -> Result<ucontext_t, Error> {
@@ -163,6 +163,81 @@ impl PartialEq for ExceptionCode {
 
 impl Eq for ExceptionCode {}
 
+impl ExceptionCode {
+    /// The closest signal to this exception, numbered as in the CRT `signal.h`,
+    /// so that crashes can be compared across platforms.
+    #[must_use]
+    pub fn signal(self) -> i32 {
+        match self {
+            ExceptionCode::AccessViolation
+            | ExceptionCode::ArrayBoundsExceeded
+            | ExceptionCode::GuardPageViolation
+            | ExceptionCode::InPageError
+            | ExceptionCode::StackOverflow
+            | ExceptionCode::DatatypeMisalignment => SIGSEGV,
+            ExceptionCode::IllegalInstruction | ExceptionCode::PrivilegedInstruction => SIGILL,
+            ExceptionCode::FltDenormalOperand
+            | ExceptionCode::FltDivideByZero
+            | ExceptionCode::FltInexactResult
+            | ExceptionCode::FltInvalidOperation
+            | ExceptionCode::FltOverflow
+            | ExceptionCode::FltStackCheck
+            | ExceptionCode::FltUnderflow
+            | ExceptionCode::FltMultipleFaults
+            | ExceptionCode::FltMultipleTraps
+            | ExceptionCode::IntegerDivideByZero
+            | ExceptionCode::IntegerOverflow => SIGFPE,
+            _ => SIGABRT,
+        }
+    }
+}
+
+/// The code of the exception behind `exception_pointers`.
+/// The pointers are null for the `abort` signals, reported as an [`ExceptionCode::AssertionFailure`].
+///
+/// # Safety
+/// The pointers must be null or point to a valid exception, as passed to the exception handlers.
+#[must_use]
+pub unsafe fn exception_code(exception_pointers: *const EXCEPTION_POINTERS) -> ExceptionCode {
+    match exception_pointers
+        .as_ref()
+        .and_then(|pointers| pointers.ExceptionRecord.as_ref())
+    {
+        Some(record) => ExceptionCode::try_from(record.ExceptionCode.0).unwrap(),
+        None => ExceptionCode::AssertionFailure,
+    }
+}
+
+/// The address of the instruction that raised the exception, and the address it accessed for
+/// an [`ExceptionCode::AccessViolation`] or an [`ExceptionCode::InPageError`].
+///
+/// # Safety
+/// The pointers must be null or point to a valid exception, as passed to the exception handlers.
+#[must_use]
+pub unsafe fn exception_addresses(
+    exception_pointers: *const EXCEPTION_POINTERS,
+) -> (Option<usize>, Option<usize>) {
+    match exception_pointers
+        .as_ref()
+        .and_then(|pointers| pointers.ExceptionRecord.as_ref())
+    {
+        Some(record) => {
+            let code = ExceptionCode::try_from(record.ExceptionCode.0).unwrap();
+            // The second parameter is the accessed address, the first one the kind of access
+            let fault_address = if (code == ExceptionCode::AccessViolation
+                || code == ExceptionCode::InPageError)
+                && record.NumberParameters >= 2
+            {
+                Some(record.ExceptionInformation[1])
+            } else {
+                None
+            };
+            (Some(record.ExceptionAddress as usize), fault_address)
+        }
+        None => (None, None),
+    }
+}
+
 unsafe impl Sync for ExceptionCode {}
 
 impl Display for ExceptionCode {
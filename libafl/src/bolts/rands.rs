@@ -14,8 +14,38 @@ const HASH_CONST: u64 = 0xa5b35705;
 /// The standard rand implementation for `LibAFL`.
 /// It is usually the right choice, with very good speed and a reasonable randomness.
 /// Not cryptographically secure (which is not what you want during fuzzing ;) )
+#[cfg(feature = "std")]
 pub type StdRand = RomuDuoJrRand;
 
+/// The standard rand implementation for `LibAFL`.
+/// Without `std`, there is no source of random seeds, and the seeds tend to be small and similar.
+/// The larger state of [`RomuTrioRand`] copes better with those than [`RomuDuoJrRand`].
+#[cfg(not(feature = "std"))]
+pub type StdRand = RomuTrioRand;
+
+/// The golden ratio, to spread consecutive stream ids over the seed space
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// One step of `SplitMix64`, the usual way to expand a seed into independent values.
+/// See <https://prng.di.unimi.it/splitmix64.c>
+#[inline]
+#[must_use]
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(GOLDEN_GAMMA);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The seed of the stream `stream` derived from `seed`
+#[inline]
+#[must_use]
+pub fn stream_seed(seed: u64, stream: u64) -> u64 {
+    let mut state = seed ^ stream.wrapping_mul(GOLDEN_GAMMA);
+    splitmix64(&mut state)
+}
+
 /// Ways to get random around here.
 /// Please note that these are not cryptographically secure.
 /// Or, even if some might be by accident, at least they are not seeded in a cryptographically secure fashion.
@@ -78,6 +108,28 @@ pub trait Rand: Debug + Serialize + DeserializeOwned {
     }
 }
 
+/// Rands that derive independent streams, e.g. one for each stage or each client, from one seed.
+///
+/// The streams do not correlate with each other, and stay reproducible: the same seed and stream
+/// id always give the same numbers, no matter the number of clients.
+pub trait SplittableRand: Rand + Sized {
+    /// Creates the rand of stream `stream` of `seed`
+    fn with_stream(seed: u64, stream: u64) -> Self;
+
+    /// Derives a new, independent rand from this one, advancing this one
+    fn split(&mut self) -> Self {
+        let seed = self.next();
+        Self::with_stream(seed, 0)
+    }
+
+    /// Derives the rand of stream `stream` from this one, advancing this one.
+    /// Calling this for several streams on clones of the same rand gives independent rands.
+    fn split_stream(&mut self, stream: u64) -> Self {
+        let seed = self.next();
+        Self::with_stream(seed, stream)
+    }
+}
+
 // helper macro for deriving Default
 macro_rules! default_rand {
     ($rand: ty) => {
@@ -99,6 +151,7 @@ macro_rules! default_rand {
 
 // Derive Default by calling `new(DEFAULT_SEED)` on each of the following Rand types.
 default_rand!(Xoshiro256StarRand);
+default_rand!(Xoshiro256PlusPlusRand);
 default_rand!(XorShift64Rand);
 default_rand!(Lehmer64Rand);
 default_rand!(RomuTrioRand);
@@ -124,6 +177,12 @@ macro_rules! impl_random {
             }
         }
 
+        impl SplittableRand for $rand {
+            fn with_stream(seed: u64, stream: u64) -> Self {
+                Self::with_seed(stream_seed(seed, stream))
+            }
+        }
+
         #[cfg(feature = "rand_trait")]
         impl RngCore for $rand {
             fn next_u32(&mut self) -> u32 {
@@ -146,6 +205,7 @@ macro_rules! impl_random {
 }
 
 impl_random!(Xoshiro256StarRand);
+impl_random!(Xoshiro256PlusPlusRand);
 impl_random!(XorShift64Rand);
 impl_random!(Lehmer64Rand);
 impl_random!(RomuTrioRand);
@@ -197,6 +257,75 @@ impl Xoshiro256StarRand {
     }
 }
 
+/// The `xoshiro256++` generator, with a good statistical quality at nearly the speed of the Romu rands.
+/// See <https://prng.di.unimi.it/>
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Xoshiro256PlusPlusRand {
+    s: [u64; 4],
+}
+
+impl Rand for Xoshiro256PlusPlusRand {
+    /// Expands the seed with `SplitMix64`, so that the state is never all zeroes
+    fn set_seed(&mut self, seed: u64) {
+        let mut state = seed;
+        for s in &mut self.s {
+            *s = splitmix64(&mut state);
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> u64 {
+        let ret = self.s[0]
+            .wrapping_add(self.s[3])
+            .rotate_left(23)
+            .wrapping_add(self.s[0]);
+        let t = self.s[1] << 17;
+
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+
+        ret
+    }
+}
+
+impl Xoshiro256PlusPlusRand {
+    /// Creates a new `xoshiro256++` rand with the given seed
+    #[must_use]
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rand = Self { s: [0; 4] };
+        rand.set_seed(seed);
+        rand
+    }
+
+    /// Advances the state by 2^128 numbers, to get up to 2^128 non-overlapping sequences
+    #[allow(clippy::unreadable_literal)]
+    pub fn jump(&mut self) {
+        const JUMP: [u64; 4] = [
+            0x180ec6d33cfd0aba,
+            0xd5a61266f0c9392c,
+            0xa9582618e03fc9aa,
+            0x39abdc4529b1661c,
+        ];
+        let mut s = [0; 4];
+        for jump in JUMP {
+            for b in 0..64 {
+                if jump & (1 << b) != 0 {
+                    for (acc, state) in s.iter_mut().zip(self.s) {
+                        *acc ^= state;
+                    }
+                }
+                self.next();
+            }
+        }
+        self.s = s;
+    }
+}
+
 /// XXH3 Based, hopefully speedy, rnd implementation
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct XorShift64Rand {
@@ -380,7 +509,8 @@ mod tests {
     //use xxhash_rust::xxh3::xxh3_64_with_seed;
 
     use crate::bolts::rands::{
-        Rand, RomuDuoJrRand, RomuTrioRand, StdRand, XorShift64Rand, Xoshiro256StarRand,
+        Rand, RomuDuoJrRand, RomuTrioRand, SplittableRand, StdRand, XorShift64Rand,
+        Xoshiro256PlusPlusRand, Xoshiro256StarRand,
     };
 
     fn test_single_rand<R: Rand>(rand: &mut R) {
@@ -399,6 +529,39 @@ mod tests {
         test_single_rand(&mut RomuDuoJrRand::with_seed(0));
         test_single_rand(&mut XorShift64Rand::with_seed(0));
         test_single_rand(&mut Xoshiro256StarRand::with_seed(0));
+        test_single_rand(&mut Xoshiro256PlusPlusRand::with_seed(0));
+    }
+
+    #[test]
+    fn test_xoshiro256plusplus() {
+        // The reference output for the state [1, 2, 3, 4]
+        let mut rand = Xoshiro256PlusPlusRand { s: [1, 2, 3, 4] };
+        assert_eq!(rand.next(), 41_943_041);
+        assert_eq!(rand.next(), 58_720_359);
+        assert_eq!(rand.next(), 3_588_806_011_781_223);
+
+        let mut jumped = Xoshiro256PlusPlusRand::with_seed(0);
+        jumped.jump();
+        assert_ne!(jumped.next(), Xoshiro256PlusPlusRand::with_seed(0).next());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut rand = StdRand::with_seed(1337);
+        let mut stage0 = rand.clone().split_stream(0);
+        let mut stage1 = rand.clone().split_stream(1);
+        assert_ne!(stage0.next(), stage1.next());
+
+        // Reproducible
+        let mut again = StdRand::with_stream(42, 3);
+        assert_eq!(again.next(), StdRand::with_stream(42, 3).next());
+        assert_ne!(
+            StdRand::with_stream(42, 3).next(),
+            StdRand::with_stream(42, 4).next()
+        );
+
+        let mut child = rand.split();
+        assert_ne!(child.next(), rand.next());
     }
 
     #[cfg(feature = "std")]
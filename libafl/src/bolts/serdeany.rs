@@ -68,7 +68,11 @@ macro_rules! create_serde_registry_for_trait {
         /// A [`crate::bolts::serdeany`] module.
         pub mod $mod_name {
 
-            use alloc::boxed::Box;
+            use alloc::{
+                boxed::Box,
+                string::{String, ToString},
+                vec::Vec,
+            };
             use core::any::TypeId;
             use core::fmt;
             use postcard;
@@ -116,21 +120,42 @@ macro_rules! create_serde_registry_for_trait {
             #[allow(unused_qualifications)]
             struct Registry {
                 deserializers: Option<HashMap<u64, DeserializeCallback<dyn $trait_name>>>,
+                names: Option<HashMap<u64, &'static str>>,
+                ids: Option<HashMap<&'static str, u64>>,
                 finalized: bool,
             }
 
             #[allow(unused_qualifications)]
             impl Registry {
-                pub fn register<T>(&mut self)
+                pub fn register<T>(&mut self, name: &'static str) -> Result<(), Error>
                 where
                     T: $trait_name + Serialize + serde::de::DeserializeOwned,
                 {
-                    assert!(!self.finalized, "Registry is already finalized!");
+                    if self.finalized {
+                        return Err(Error::IllegalState(format!(
+                            "Cannot register {}, the registry is already finalized",
+                            name
+                        )));
+                    }
 
-                    let deserializers = self.deserializers.get_or_insert_with(HashMap::default);
-                    deserializers.insert(unpack_type_id(TypeId::of::<T>()), |de| {
-                        Ok(Box::new(erased_serde::deserialize::<T>(de)?))
-                    });
+                    let id = unpack_type_id(TypeId::of::<T>());
+                    self.deserializers
+                        .get_or_insert_with(HashMap::default)
+                        .insert(id, |de| Ok(Box::new(erased_serde::deserialize::<T>(de)?)));
+                    let ids = self.ids.get_or_insert_with(HashMap::default);
+                    if let Some(other) = ids.get(name) {
+                        if *other != id {
+                            return Err(Error::IllegalArgument(format!(
+                                "Two types are registered as {}",
+                                name
+                            )));
+                        }
+                    }
+                    ids.insert(name, id);
+                    self.names
+                        .get_or_insert_with(HashMap::default)
+                        .insert(id, name);
+                    Ok(())
                 }
 
                 pub fn finalize(&mut self) {
@@ -140,9 +165,61 @@ macro_rules! create_serde_registry_for_trait {
 
             static mut REGISTRY: Registry = Registry {
                 deserializers: None,
+                names: None,
+                ids: None,
                 finalized: false,
             };
 
+            /// The name the type with the given id is registered as, see [`RegistryBuilder::register_named`].
+            /// Unlike the id, the name stays the same across builds.
+            #[must_use]
+            pub fn registered_name(id: u64) -> Option<&'static str> {
+                unsafe { REGISTRY.names.as_ref()?.get(&id).copied() }
+            }
+
+            /// The id of the type registered as `name`
+            #[must_use]
+            pub fn registered_id(name: &str) -> Option<u64> {
+                unsafe { REGISTRY.ids.as_ref()?.get(name).copied() }
+            }
+
+            /// The id of the only type registered under a path ending in `name`
+            fn registered_id_by_short_name(name: &str) -> Option<u64> {
+                let mut ids = unsafe { REGISTRY.ids.as_ref()? }
+                    .iter()
+                    .filter(|(registered, _)| registered.rsplit("::").next() == Some(name))
+                    .map(|(_, id)| *id);
+                let id = ids.next()?;
+                ids.all(|other| other == id).then(|| id)
+            }
+
+            /// Deserializes an element saved by its registered type name,
+            /// `None` if the type is unknown to this build
+            #[allow(unused_qualifications)]
+            fn deserialize_named_entry(
+                type_name: &str,
+                bytes: &[u8],
+            ) -> Result<Option<(u64, Box<dyn $trait_name>)>, Error> {
+                // Some older state files name the types without their module path
+                let id = match registered_id(type_name).or_else(|| {
+                    if type_name.contains("::") {
+                        None
+                    } else {
+                        registered_id_by_short_name(type_name)
+                    }
+                }) {
+                    Some(id) => id,
+                    None => return Ok(None),
+                };
+                let cb = unsafe { *REGISTRY.deserializers.as_ref().unwrap().get(&id).unwrap() };
+                let mut deserializer = postcard::Deserializer::from_bytes(bytes);
+                let val = cb(&mut <dyn erased_serde::Deserializer>::erase(
+                    &mut deserializer,
+                ))
+                .map_err(|err| Error::Serialize(format!("{}: {}", type_name, err)))?;
+                Ok(Some((id, val)))
+            }
+
             /// This shugar must be used to register all the structs which
             /// have trait objects that can be serialized and deserialized in the program
             #[derive(Debug)]
@@ -150,14 +227,24 @@ macro_rules! create_serde_registry_for_trait {
 
             #[allow(unused_qualifications)]
             impl RegistryBuilder {
-                /// Register a given struct type for trait object (de)serialization
-                pub fn register<T>()
+                /// Register a given struct type for trait object (de)serialization, as its [`core::any::type_name`].
+                /// The type name changes when the type moves, types saved to disk should use [`RegistryBuilder::register_named`].
+                pub fn register<T>() -> Result<(), Error>
                 where
                     T: $trait_name + Serialize + serde::de::DeserializeOwned,
                 {
-                    unsafe {
-                        REGISTRY.register::<T>();
-                    }
+                    unsafe { REGISTRY.register::<T>(core::any::type_name::<T>()) }
+                }
+
+                /// Register a given struct type for trait object (de)serialization, as `name`.
+                /// The name identifies the type in saved states, so it has to be unique, and stay the same.
+                /// If another type is registered as `name` already, the type can still be
+                /// deserialized from its id, but not saved by name.
+                pub fn register_named<T>(name: &'static str) -> Result<(), Error>
+                where
+                    T: $trait_name + Serialize + serde::de::DeserializeOwned,
+                {
+                    unsafe { REGISTRY.register::<T>(name) }
                 }
 
                 /// Finalize the registry, no more registrations are allowed after this call
@@ -287,6 +374,42 @@ macro_rules! create_serde_registry_for_trait {
                         map: HashMap::default(),
                     }
                 }
+
+                /// The elements as pairs of their registered type name and their own `postcard`
+                /// serialization. Unlike the serialization of the whole map, this survives
+                /// rebuilds, and the elements of unknown types can be skipped on load.
+                pub fn to_named_entries(&self) -> Result<Vec<(String, Vec<u8>)>, Error> {
+                    self.map
+                        .iter()
+                        .map(|(id, val)| {
+                            let name = registered_name(*id).ok_or_else(|| {
+                                Error::KeyNotFound(format!("Unregistered metadata type {}", id))
+                            })?;
+                            let bytes = postcard::to_allocvec(&$crate::bolts::serdeany::Wrap(
+                                val.as_ref(),
+                            ))?;
+                            Ok((name.to_string(), bytes))
+                        })
+                        .collect()
+                }
+
+                /// Restores a map from [`SerdeAnyMap::to_named_entries`].
+                /// Also returns the names of the skipped entries, of types unknown to this build.
+                pub fn from_named_entries(
+                    entries: Vec<(String, Vec<u8>)>,
+                ) -> Result<(Self, Vec<String>), Error> {
+                    let mut map = Self::new();
+                    let mut skipped = vec![];
+                    for (name, bytes) in entries {
+                        match deserialize_named_entry(&name, &bytes)? {
+                            Some((id, val)) => {
+                                map.map.insert(id, val);
+                            }
+                            None => skipped.push(name),
+                        }
+                    }
+                    Ok((map, skipped))
+                }
             }
 
             impl Default for SerdeAnyMap {
@@ -302,6 +425,14 @@ macro_rules! create_serde_registry_for_trait {
                 map: HashMap<u64, HashMap<u64, Box<dyn $trait_name>>>,
             }
 
+            // Cloning by serializing and deserializing, as for the [`SerdeAnyMap`].
+            impl Clone for NamedSerdeAnyMap {
+                fn clone(&self) -> Self {
+                    let serialized = postcard::to_allocvec(&self).unwrap();
+                    postcard::from_bytes(&serialized).unwrap()
+                }
+            }
+
             #[allow(unused_qualifications)]
             impl NamedSerdeAnyMap {
                 /// Get an element by name
@@ -507,6 +638,21 @@ macro_rules! create_serde_registry_for_trait {
                         .insert(xxhash_rust::xxh3::xxh3_64(name.as_bytes()), val);
                 }
 
+                /// Remove the element of the given type by `name`. Returns the removed element.
+                #[inline]
+                pub fn remove<T>(&mut self, name: &str) -> Option<Box<T>>
+                where
+                    T: $trait_name,
+                {
+                    let id = unpack_type_id(TypeId::of::<T>());
+                    let named = self.map.get_mut(&id)?;
+                    let val = named.remove(&xxhash_rust::xxh3::xxh3_64(name.as_bytes()))?;
+                    if named.is_empty() {
+                        self.map.remove(&id);
+                    }
+                    Some(val.as_any_boxed().downcast::<T>().unwrap())
+                }
+
                 /// Returns the `len` of this map.
                 #[must_use]
                 #[inline]
@@ -550,6 +696,42 @@ macro_rules! create_serde_registry_for_trait {
                         map: HashMap::default(),
                     }
                 }
+
+                /// The elements as triples of their registered type name, the hash of their name,
+                /// and their own `postcard` serialization, see [`SerdeAnyMap::to_named_entries`].
+                pub fn to_named_entries(&self) -> Result<Vec<(String, u64, Vec<u8>)>, Error> {
+                    let mut entries = vec![];
+                    for (id, named) in &self.map {
+                        let type_name = registered_name(*id).ok_or_else(|| {
+                            Error::KeyNotFound(format!("Unregistered metadata type {}", id))
+                        })?;
+                        for (name_hash, val) in named {
+                            let bytes = postcard::to_allocvec(&$crate::bolts::serdeany::Wrap(
+                                val.as_ref(),
+                            ))?;
+                            entries.push((type_name.to_string(), *name_hash, bytes));
+                        }
+                    }
+                    Ok(entries)
+                }
+
+                /// Restores a map from [`NamedSerdeAnyMap::to_named_entries`].
+                /// Also returns the type names of the skipped entries, of types unknown to this build.
+                pub fn from_named_entries(
+                    entries: Vec<(String, u64, Vec<u8>)>,
+                ) -> Result<(Self, Vec<String>), Error> {
+                    let mut map = Self::new();
+                    let mut skipped = vec![];
+                    for (type_name, name_hash, bytes) in entries {
+                        match deserialize_named_entry(&type_name, &bytes)? {
+                            Some((id, val)) => {
+                                map.map.entry(id).or_default().insert(name_hash, val);
+                            }
+                            None => skipped.push(type_name),
+                        }
+                    }
+                    Ok((map, skipped))
+                }
             }
 
             impl Default for NamedSerdeAnyMap {
@@ -590,11 +772,19 @@ macro_rules! create_serde_registry_for_trait {
 create_serde_registry_for_trait!(serdeany_registry, crate::bolts::serdeany::SerdeAny);
 pub use serdeany_registry::*;
 
-/// Implement a [`SerdeAny`], registering it in the [`RegistryBuilder`]
+/// Implement a [`SerdeAny`], registering it in the [`RegistryBuilder`] as its path.
+/// Types saved in state files should be given an explicit name that stays the same when they
+/// move, see [`RegistryBuilder::register_named`].
 #[cfg(feature = "std")]
 #[macro_export]
 macro_rules! impl_serdeany {
     ($struct_name:ident) => {
+        $crate::impl_serdeany!(
+            $struct_name,
+            concat!(module_path!(), "::", stringify!($struct_name))
+        );
+    };
+    ($struct_name:ident, $registered_name:expr) => {
         impl $crate::bolts::serdeany::SerdeAny for $struct_name {
             fn as_any(&self) -> &dyn ::core::any::Any {
                 self
@@ -614,7 +804,13 @@ macro_rules! impl_serdeany {
         #[allow(non_snake_case)]
         #[$crate::ctor]
         fn $struct_name() {
-            $crate::bolts::serdeany::RegistryBuilder::register::<$struct_name>();
+            if let Err(err) =
+                $crate::bolts::serdeany::RegistryBuilder::register_named::<$struct_name>(
+                    $registered_name,
+                )
+            {
+                ::std::eprintln!("Could not register {}: {:?}", $registered_name, err);
+            }
         }
     };
 }
@@ -624,6 +820,12 @@ macro_rules! impl_serdeany {
 #[macro_export]
 macro_rules! impl_serdeany {
     ($struct_name:ident) => {
+        $crate::impl_serdeany!(
+            $struct_name,
+            concat!(module_path!(), "::", stringify!($struct_name))
+        );
+    };
+    ($struct_name:ident, $registered_name:expr) => {
         impl $crate::bolts::serdeany::SerdeAny for $struct_name {
             fn as_any(&self) -> &dyn ::core::any::Any {
                 self
@@ -12,6 +12,7 @@ use core::{
     cell::RefCell,
     fmt::{self, Debug, Display},
     mem::ManuallyDrop,
+    str::FromStr,
 };
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
@@ -27,6 +28,9 @@ pub use unix_shmem::{MmapShMem, MmapShMemProvider};
 #[cfg(all(feature = "std", unix))]
 pub use unix_shmem::{UnixShMem, UnixShMemProvider};
 
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use unix_shmem::memfd::{MemfdShMem, MemfdShMemProvider};
+
 #[cfg(all(windows, feature = "std"))]
 pub use win32_shmem::{Win32ShMem, Win32ShMemProvider};
 
@@ -84,6 +88,27 @@ impl ShMemDescription {
     }
 }
 
+/// The description as `<size>:<id>`, e.g. to pass it on the command line of a child process
+impl Display for ShMemDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.size, self.id)
+    }
+}
+
+impl FromStr for ShMemDescription {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (size, id) = s
+            .split_once(':')
+            .ok_or_else(|| Error::IllegalArgument(format!("Not a shmem description: {}", s)))?;
+        let size = size
+            .parse()
+            .map_err(|_| Error::IllegalArgument(format!("Illegal shmem size in {}", s)))?;
+        Ok(Self::from_string_and_size(id, size))
+    }
+}
+
 /// An id associated with a given shared memory mapping ([`ShMem`]), which can be used to
 /// establish shared-mappings between proccesses.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
@@ -509,6 +534,113 @@ where
     }
 }
 
+/// A [`ShMemProvider`] placing the maps it creates on a NUMA node,
+/// by default the node of the core the process runs on when creating the map.
+/// Pin the process to its core first, like the [`crate::bolts::launcher::Launcher`] does,
+/// for the [`crate::bolts::llmp`] pages and coverage maps to end up next to the client using them.
+/// Maps of other processes, mapped by id, stay where they are.
+#[cfg(all(target_os = "linux", feature = "std"))]
+#[derive(Debug, Clone, Default)]
+pub struct NumaShMemProvider<SP>
+where
+    SP: ShMemProvider,
+{
+    /// The wrapped [`ShMemProvider`]
+    internal: SP,
+    /// The node to place the maps on, `None` for the node of the current core
+    node: Option<usize>,
+}
+
+#[cfg(all(target_os = "linux", feature = "std"))]
+impl<SP> NumaShMemProvider<SP>
+where
+    SP: ShMemProvider,
+{
+    /// Wraps `internal`, placing the new maps on the node of the current core
+    pub fn new_local(internal: SP) -> Self {
+        Self {
+            internal,
+            node: None,
+        }
+    }
+
+    /// Wraps `internal`, placing the new maps on the NUMA node `node`
+    pub fn with_node(internal: SP, node: usize) -> Self {
+        Self {
+            internal,
+            node: Some(node),
+        }
+    }
+
+    /// Asks the kernel to move the pages of `map` to the NUMA node `node`.
+    /// This is only a preference: if the node is full, or there is no NUMA, nothing happens.
+    fn place_on_node(map: &mut [u8], node: usize) {
+        /// Prefer the given node
+        const MPOL_PREFERRED: libc::c_long = 1;
+        /// Move the pages that are already there
+        const MPOL_MF_MOVE: libc::c_long = 1 << 1;
+
+        let mut nodemask = vec![0_u64; node / 64 + 1];
+        nodemask[node / 64] |= 1 << (node % 64);
+        // The kernel reads one bit less than `maxnode`
+        let maxnode = nodemask.len() * 64 + 1;
+        unsafe {
+            libc::syscall(
+                libc::SYS_mbind,
+                map.as_mut_ptr(),
+                map.len(),
+                MPOL_PREFERRED,
+                nodemask.as_ptr(),
+                maxnode,
+                MPOL_MF_MOVE,
+            );
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "std"))]
+impl<SP> ShMemProvider for NumaShMemProvider<SP>
+where
+    SP: ShMemProvider,
+{
+    type ShMem = SP::ShMem;
+
+    fn new() -> Result<Self, Error> {
+        Ok(Self::new_local(SP::new()?))
+    }
+
+    fn new_shmem(&mut self, map_size: usize) -> Result<Self::ShMem, Error> {
+        let mut shmem = self.internal.new_shmem(map_size)?;
+        let node = self.node.or_else(|| {
+            crate::bolts::os::CoreId::current().and_then(|core_id| core_id.numa_node())
+        });
+        if let Some(node) = node {
+            Self::place_on_node(shmem.as_mut_slice(), node);
+        }
+        Ok(shmem)
+    }
+
+    fn shmem_from_id_and_size(&mut self, id: ShMemId, size: usize) -> Result<Self::ShMem, Error> {
+        self.internal.shmem_from_id_and_size(id, size)
+    }
+
+    fn clone_ref(&mut self, mapping: &Self::ShMem) -> Result<Self::ShMem, Error> {
+        self.internal.clone_ref(mapping)
+    }
+
+    fn pre_fork(&mut self) -> Result<(), Error> {
+        self.internal.pre_fork()
+    }
+
+    fn post_fork(&mut self, is_child: bool) -> Result<(), Error> {
+        self.internal.post_fork(is_child)
+    }
+
+    fn release_shmem(&mut self, shmem: &mut Self::ShMem) {
+        self.internal.release_shmem(shmem);
+    }
+}
+
 /// A Unix sharedmem implementation.
 ///
 /// On Android, this is partially reused to wrap [`unix_shmem::ashmem::AshmemShMem`],
@@ -1138,6 +1270,183 @@ pub mod unix_shmem {
             }
         }
     }
+
+    /// Module containing `memfd_create`-based shared memory, on Linux.
+    ///
+    /// The maps are anonymous files: they need neither `/dev/shm` nor `SysV` IPC, e.g. in
+    /// containers, and the kernel frees them once the last process unmapped them, even after a crash.
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    pub mod memfd {
+        use core::{ptr, slice};
+        use libc::{c_int, close, fcntl, ftruncate, memfd_create, mmap, munmap, open, perror};
+        use std::{ffi::CString, process};
+
+        use crate::{
+            bolts::{
+                shmem::{ShMem, ShMemId, ShMemProvider},
+                AsMutSlice, AsSlice,
+            },
+            Error,
+        };
+
+        /// A shared map backed by a `memfd`.
+        /// Its id is `<pid>:<fd>`, the process that created it and its file descriptor there.
+        /// Other processes open it as `/proc/<pid>/fd/<fd>`, so the creator has to outlive them.
+        /// The fd is closed on `exec`, so it does not leak into the targets.
+        #[derive(Clone, Debug)]
+        pub struct MemfdShMem {
+            /// The shmem id, `<pid>:<fd>` of the creator
+            id: ShMemId,
+            /// The map ptr
+            map: *mut u8,
+            /// The size of this map
+            map_size: usize,
+            /// Our own file descriptor of the memfd
+            fd: c_int,
+        }
+
+        impl MemfdShMem {
+            /// Create a new [`MemfdShMem`]
+            pub fn new(map_size: usize) -> Result<Self, Error> {
+                unsafe {
+                    let fd = memfd_create(b"libafl\0".as_ptr() as *const _, libc::MFD_CLOEXEC);
+                    if fd == -1 {
+                        perror(b"memfd_create\0".as_ptr() as *const _);
+                        return Err(Error::Unknown("Failed to create a memfd".to_string()));
+                    }
+                    if ftruncate(fd, map_size.try_into()?) != 0 {
+                        perror(b"ftruncate\0".as_ptr() as *const _);
+                        close(fd);
+                        return Err(Error::Unknown(format!(
+                            "ftruncate() failed for memfd {}",
+                            fd
+                        )));
+                    }
+                    let id = ShMemId::from_string(&format!("{}:{}", process::id(), fd));
+                    Self::map_fd(id, fd, map_size)
+                }
+            }
+
+            /// Map the [`MemfdShMem`] with the given id, from this or another process
+            pub fn shmem_from_id_and_size(id: ShMemId, map_size: usize) -> Result<Self, Error> {
+                let (pid, orig_fd) = id
+                    .as_str()
+                    .split_once(':')
+                    .and_then(|(pid, fd)| {
+                        Some((pid.parse::<u32>().ok()?, fd.parse::<c_int>().ok()?))
+                    })
+                    .ok_or_else(|| {
+                        Error::IllegalArgument(format!("Not a memfd shmem id: {}", id))
+                    })?;
+                unsafe {
+                    // The fd number means nothing outside of the creator, never fall back to it
+                    let fd = if pid == process::id() {
+                        fcntl(orig_fd, libc::F_DUPFD_CLOEXEC, 0)
+                    } else {
+                        let path = CString::new(format!("/proc/{}/fd/{}", pid, orig_fd)).unwrap();
+                        open(path.as_ptr(), libc::O_RDWR | libc::O_CLOEXEC)
+                    };
+                    if fd == -1 {
+                        perror(b"open\0".as_ptr() as *const _);
+                        return Err(Error::Unknown(format!(
+                            "Could not open memfd with id {}",
+                            id
+                        )));
+                    }
+                    Self::map_fd(id, fd, map_size)
+                }
+            }
+
+            unsafe fn map_fd(id: ShMemId, fd: c_int, map_size: usize) -> Result<Self, Error> {
+                let map = mmap(
+                    ptr::null_mut(),
+                    map_size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    0,
+                );
+                if map == libc::MAP_FAILED || map.is_null() {
+                    perror(b"mmap\0".as_ptr() as *const _);
+                    close(fd);
+                    return Err(Error::Unknown(format!(
+                        "mmap() failed for memfd with id {}",
+                        id
+                    )));
+                }
+                Ok(Self {
+                    id,
+                    map: map as *mut u8,
+                    map_size,
+                    fd,
+                })
+            }
+        }
+
+        impl ShMem for MemfdShMem {
+            fn id(&self) -> ShMemId {
+                self.id
+            }
+
+            fn len(&self) -> usize {
+                self.map_size
+            }
+        }
+
+        impl AsSlice<u8> for MemfdShMem {
+            fn as_slice(&self) -> &[u8] {
+                unsafe { slice::from_raw_parts(self.map, self.map_size) }
+            }
+        }
+
+        impl AsMutSlice<u8> for MemfdShMem {
+            fn as_mut_slice(&mut self) -> &mut [u8] {
+                unsafe { slice::from_raw_parts_mut(self.map, self.map_size) }
+            }
+        }
+
+        impl Drop for MemfdShMem {
+            fn drop(&mut self) {
+                unsafe {
+                    munmap(self.map as *mut _, self.map_size);
+                    self.map = ptr::null_mut();
+                    close(self.fd);
+                }
+            }
+        }
+
+        /// A [`ShMemProvider`] which uses `memfd_create` to provide shared memory mappings.
+        #[derive(Clone, Debug)]
+        pub struct MemfdShMemProvider {}
+
+        unsafe impl Send for MemfdShMemProvider {}
+
+        impl Default for MemfdShMemProvider {
+            fn default() -> Self {
+                Self::new().unwrap()
+            }
+        }
+
+        impl ShMemProvider for MemfdShMemProvider {
+            type ShMem = MemfdShMem;
+
+            fn new() -> Result<Self, Error> {
+                Ok(Self {})
+            }
+
+            fn new_shmem(&mut self, map_size: usize) -> Result<Self::ShMem, Error> {
+                MemfdShMem::new(map_size)
+            }
+
+            fn shmem_from_id_and_size(
+                &mut self,
+                id: ShMemId,
+                size: usize,
+            ) -> Result<Self::ShMem, Error> {
+                MemfdShMem::shmem_from_id_and_size(id, size)
+            }
+        }
+    }
 }
 
 /// Then `win32` implementation for shared memory.
@@ -1434,6 +1743,41 @@ mod tests {
         AsMutSlice, AsSlice,
     };
 
+    #[test]
+    #[serial]
+    #[cfg(target_os = "linux")]
+    fn test_numa_shmem() {
+        use crate::bolts::shmem::NumaShMemProvider;
+
+        let mut provider = NumaShMemProvider::with_node(StdShMemProvider::new().unwrap(), 0);
+        let mut map = provider.new_shmem(1024 * 1024).unwrap();
+        map.as_mut_slice()[4096] = 1;
+        let other = provider.clone_ref(&map).unwrap();
+        assert_eq!(other.as_slice()[4096], 1);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(target_os = "linux")]
+    fn test_memfd_shmem() {
+        use crate::bolts::shmem::{MemfdShMemProvider, ShMem, ShMemDescription, ShMemId};
+
+        let mut provider = MemfdShMemProvider::new().unwrap();
+        let mut map = provider.new_shmem(1024).unwrap();
+        map.as_mut_slice()[0] = 1;
+        let description: ShMemDescription = map.description().to_string().parse().unwrap();
+        let mut other = provider.shmem_from_description(description).unwrap();
+        assert_eq!(other.as_slice()[0], 1);
+        other.as_mut_slice()[1] = 2;
+        drop(other);
+        assert_eq!(map.as_slice()[1], 2);
+
+        // Beyond the maximum pid, no process has this fd to open
+        let fd = map.id().as_str().split_once(':').unwrap().1.to_string();
+        let id = ShMemId::from_string(&format!("{}:{}", u32::MAX, fd));
+        assert!(provider.shmem_from_id_and_size(id, 1024).is_err());
+    }
+
     #[test]
     #[serial]
     fn test_shmem_service() {
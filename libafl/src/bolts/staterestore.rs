@@ -1,5 +1,7 @@
 //! Stores and restores state when a client needs to relaunch.
 //! Uses a [`ShMem`] up to a threshold, then write to disk.
+//! The map grows with the state, up to [`STATE_RESTORER_MAX_MAP_SIZE`], see [`StateRestorer::grow`].
+//! The stored state is checksummed, so a corrupted state is detected, instead of restored.
 use ahash::AHasher;
 use core::{hash::Hasher, marker::PhantomData, mem::size_of, ptr, slice};
 use serde::{de::DeserializeOwned, Serialize};
@@ -19,10 +21,22 @@ use crate::{
     Error,
 };
 
+/// The initial size of the map of a [`StateRestorer`]
+pub const STATE_RESTORER_INITIAL_MAP_SIZE: usize = 1024 * 1024;
+
+/// The size a [`StateRestorer`] map grows to at most, larger states stay on disk
+pub const STATE_RESTORER_MAX_MAP_SIZE: usize = 256 * 1024 * 1024;
+
 /// The struct stored on the shared map, containing either the data, or the filename to read contents from.
 #[repr(C)]
 struct StateShMemContent {
     is_disk: bool,
+    /// Set if the client exited cleanly, and should not be restarted
+    is_exiting: bool,
+    /// The length of the serialized state, in the map or on disk
+    state_len: usize,
+    /// The checksum of the serialized state, in the map or on disk
+    checksum: u64,
     buf_len: usize,
     buf: [u8; 0],
 }
@@ -55,6 +69,7 @@ impl StateShMemContent {
 /// A [`StateRestorer`] saves and restores bytes to a shared map.
 /// If the state gets larger than the preallocated [`ShMem`] shared map,
 /// it will instead write to disk, and store the file name into the map.
+/// The owner of the map then moves it to a larger one with [`StateRestorer::grow`].
 /// Writing to [`StateRestorer`] multiple times is not allowed.
 #[derive(Debug, Clone)]
 pub struct StateRestorer<SP>
@@ -98,6 +113,7 @@ where
     }
 
     /// Saves a state to the connected [`ShMem`], or a tmpfile, if its serialized size get too large.
+    /// The tmpfile grows with the state, so there is no upper bound on its size.
     pub fn save<S>(&mut self, state: &S) -> Result<(), Error>
     where
         S: Serialize,
@@ -118,7 +134,10 @@ where
 
             let filename = format!("{:016x}.libafl_state", hasher.finish());
             let tmpfile = temp_dir().join(&filename);
-            File::create(tmpfile)?.write_all(&serialized)?;
+            // Write to a partial file first, so a crash while writing never leaves a truncated state behind
+            let partial = tmpfile.with_extension("partial");
+            File::create(&partial)?.write_all(&serialized)?;
+            fs::rename(partial, tmpfile)?;
 
             // write the filename to shmem
             let filename_buf = postcard::to_allocvec(&filename)?;
@@ -149,25 +168,62 @@ where
                     len,
                 );
             }
+            shmem_content.state_len = serialized.len();
+            shmem_content.checksum = checksum(&serialized);
             shmem_content.buf_len = len;
             shmem_content.is_disk = true;
         } else {
-            // write to shmem directly
-            let len = serialized.len();
-            let shmem_content = self.content_mut();
-            unsafe {
-                ptr::copy_nonoverlapping(
-                    serialized.as_ptr() as *const u8,
-                    shmem_content.buf.as_mut_ptr(),
-                    len,
-                );
-            }
-            shmem_content.buf_len = len;
-            shmem_content.is_disk = false;
+            self.store_in_map(&serialized, checksum(&serialized));
         };
         Ok(())
     }
 
+    /// Writes the serialized state with the given checksum to the map, which has to fit it
+    fn store_in_map(&mut self, serialized: &[u8], checksum: u64) {
+        let len = serialized.len();
+        let shmem_content = self.content_mut();
+        unsafe {
+            ptr::copy_nonoverlapping(serialized.as_ptr(), shmem_content.buf.as_mut_ptr(), len);
+        }
+        shmem_content.state_len = len;
+        shmem_content.checksum = checksum;
+        shmem_content.buf_len = len;
+        shmem_content.is_disk = false;
+    }
+
+    /// Moves a state that did not fit the map, and got written to disk, to a new map large enough
+    /// for it, so the next states fit as well, up to [`STATE_RESTORER_MAX_MAP_SIZE`].
+    /// Call it from the process owning the map, e.g. the respawner, while no client uses it.
+    /// Returns `true` if the map was replaced; a respawned client has to be told the new one.
+    pub fn grow(&mut self, shmem_provider: &mut SP) -> Result<bool, Error> {
+        let content = self.content();
+        if !content.is_disk || content.buf_len == 0 {
+            return Ok(false);
+        }
+        let state_len = unsafe { read_volatile(&content.state_len) };
+        let expected_checksum = unsafe { read_volatile(&content.checksum) };
+        let map_size = (size_of::<StateShMemContent>() + state_len).next_power_of_two();
+        if map_size > STATE_RESTORER_MAX_MAP_SIZE {
+            return Ok(false);
+        }
+        let tmpfile = match content.tmpfile(self.mapsize())? {
+            Some(tmpfile) => tmpfile,
+            None => return Ok(false),
+        };
+        let serialized = fs::read(tmpfile)?;
+        if serialized.len() != state_len {
+            // Corrupted, restoring fails, and the client starts fresh
+            return Ok(false);
+        }
+
+        let mut grown = Self::new(shmem_provider.new_shmem(map_size)?);
+        // The checksum is kept, a corruption on disk is still detected on restore
+        grown.store_in_map(&serialized, expected_checksum);
+        self.reset();
+        *self = grown;
+        Ok(true)
+    }
+
     /// Reset this [`StateRestorer`] to an empty state.
     pub fn reset(&mut self) {
         let mapsize = self.mapsize();
@@ -177,6 +233,9 @@ where
             drop(fs::remove_file(tmpfile));
         }
         content_mut.is_disk = false;
+        content_mut.is_exiting = false;
+        content_mut.state_len = 0;
+        content_mut.checksum = 0;
         content_mut.buf_len = 0;
     }
 
@@ -195,6 +254,18 @@ where
         unsafe { &*(ptr) }
     }
 
+    /// Marks the client as exiting cleanly, so it is not restarted.
+    /// The state is not saved.
+    pub fn send_exiting(&mut self) {
+        self.reset();
+        self.content_mut().is_exiting = true;
+    }
+
+    /// Returns true, if the client exited cleanly, see [`StateRestorer::send_exiting`].
+    pub fn is_exiting(&self) -> bool {
+        unsafe { read_volatile(&self.content().is_exiting) }
+    }
+
     /// Returns true, if this [`StateRestorer`] has contents.
     pub fn has_content(&self) -> bool {
         self.content().buf_len > 0
@@ -202,6 +273,7 @@ where
 
     /// Restores the contents saved in this [`StateRestorer`], if any are availiable.
     /// Can only be read once.
+    /// Fails with an [`Error::IllegalState`], if the stored state is corrupted.
     pub fn restore<S>(&self) -> Result<Option<S>, Error>
     where
         S: DeserializeOwned,
@@ -233,9 +305,46 @@ where
             }
             state = &file_content;
         }
+        let state_len = unsafe { read_volatile(&state_shmem_content.state_len) };
+        let expected_checksum = unsafe { read_volatile(&state_shmem_content.checksum) };
+        if state.len() != state_len || checksum(state) != expected_checksum {
+            return Err(Error::IllegalState(format!(
+                "Stored state is corrupted! Expected {} bytes with checksum {:016x}, but got {} bytes with checksum {:016x}",
+                state_len,
+                expected_checksum,
+                state.len(),
+                checksum(state)
+            )));
+        }
         let deserialized = postcard::from_bytes(state)?;
         Ok(Some(deserialized))
     }
+
+    /// Restores the contents saved in this [`StateRestorer`], like [`StateRestorer::restore`].
+    /// If the stored state is corrupted or can't be read, it's discarded, to start fresh instead.
+    pub fn restore_or_reset<S>(&mut self) -> Option<S>
+    where
+        S: DeserializeOwned,
+    {
+        match self.restore() {
+            Ok(state) => state,
+            Err(err) => {
+                println!(
+                    "Could not restore the previous state, starting fresh: {:?}",
+                    err
+                );
+                self.reset();
+                None
+            }
+        }
+    }
+}
+
+/// The checksum of a serialized state
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = AHasher::new_with_keys(0, 0);
+    hasher.write(bytes);
+    hasher.finish()
 }
 
 #[cfg(test)]
@@ -296,5 +405,81 @@ mod tests {
         state_restorer.reset();
         assert!(!state_restorer.has_content());
         assert!(!tmpfile.exists());
+
+        // An exiting client leaves no state behind
+        state_restorer.save(&state).unwrap();
+        state_restorer.send_exiting();
+        assert!(state_restorer.is_exiting());
+        assert!(!state_restorer.has_content());
+        state_restorer.reset();
+        assert!(!state_restorer.is_exiting());
+    }
+
+    #[test]
+    #[serial]
+    fn test_state_restore_corrupted() {
+        const TESTMAP_SIZE: usize = 1024;
+
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let shmem = shmem_provider.new_shmem(TESTMAP_SIZE).unwrap();
+        let mut state_restorer = StateRestorer::<StdShMemProvider>::new(shmem);
+
+        state_restorer.save(&"hello world".to_string()).unwrap();
+        // Flip a byte of the stored state
+        unsafe {
+            *state_restorer.content_mut().buf.as_mut_ptr().add(3) ^= 0xff;
+        }
+        assert!(state_restorer.restore::<String>().is_err());
+        assert!(state_restorer.restore_or_reset::<String>().is_none());
+        assert!(!state_restorer.has_content());
+
+        // Truncate a state stored on disk
+        state_restorer.save(&vec![4u8; TESTMAP_SIZE + 1]).unwrap();
+        let tmpfile = state_restorer
+            .content()
+            .tmpfile(state_restorer.mapsize())
+            .unwrap()
+            .unwrap();
+        std::fs::write(&tmpfile, [4u8; 16]).unwrap();
+        assert!(state_restorer.restore::<Vec<u8>>().is_err());
+        assert!(state_restorer.restore_or_reset::<Vec<u8>>().is_none());
+        assert!(!state_restorer.has_content());
+        assert!(!tmpfile.exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test_state_restore_grow() {
+        const TESTMAP_SIZE: usize = 1024;
+
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let shmem = shmem_provider.new_shmem(TESTMAP_SIZE).unwrap();
+        let mut state_restorer = StateRestorer::<StdShMemProvider>::new(shmem);
+
+        // Fits the map, nothing to do
+        state_restorer.save(&"hello world".to_string()).unwrap();
+        assert!(!state_restorer.grow(&mut shmem_provider).unwrap());
+        state_restorer.reset();
+
+        let too_large = vec![4u8; TESTMAP_SIZE + 1];
+        state_restorer.save(&too_large).unwrap();
+        let tmpfile = state_restorer
+            .content()
+            .tmpfile(state_restorer.mapsize())
+            .unwrap()
+            .unwrap();
+        assert!(state_restorer.grow(&mut shmem_provider).unwrap());
+        assert!(state_restorer.mapsize() > TESTMAP_SIZE);
+        assert!(!state_restorer.content().is_disk);
+        assert!(!tmpfile.exists());
+        assert_eq!(
+            state_restorer.restore::<Vec<u8>>().unwrap().unwrap(),
+            too_large
+        );
+
+        // The next state of the same size stays in the map
+        state_restorer.reset();
+        state_restorer.save(&too_large).unwrap();
+        assert!(!state_restorer.content().is_disk);
     }
 }
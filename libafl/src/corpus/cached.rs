@@ -1,9 +1,10 @@
 //! The cached ondisk corpus stores testcases to disk keeping a part of them in memory.
 
-use alloc::collections::vec_deque::VecDeque;
-use core::cell::RefCell;
+use alloc::{collections::vec_deque::VecDeque, vec::Vec};
+use core::cell::{Cell, RefCell};
+use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{fs, path::PathBuf};
 
 use crate::{
     corpus::{
@@ -11,10 +12,31 @@ use crate::{
         Corpus, Testcase,
     },
     inputs::Input,
+    schedulers::minimizer::IsFavoredMetadata,
+    state::HasMetadata,
     Error,
 };
 
-/// A corpus that keep in memory a maximun number of testcases. The eviction policy is FIFO.
+/// Which cached testcase a [`CachedOnDiskCorpus`] evicts first
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheEvictionPolicy {
+    /// The testcase loaded first
+    Fifo,
+    /// The least recently used testcase
+    Lru,
+    /// The least frequently used testcase since it was loaded, the oldest one on ties
+    Lfu,
+}
+
+impl Default for CacheEvictionPolicy {
+    fn default() -> Self {
+        Self::Fifo
+    }
+}
+
+/// A corpus that keep in memory a maximun number of testcases, and optionally a maximum number of
+/// bytes. Which testcase is evicted is decided by the [`CacheEvictionPolicy`], the one loaded first
+/// by default. Pinned testcases, and the favored ones if asked, always stay in memory.
 #[cfg(feature = "std")]
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "I: serde::de::DeserializeOwned")]
@@ -25,6 +47,14 @@ where
     inner: OnDiskCorpus<I>,
     cached_indexes: RefCell<VecDeque<usize>>,
     cache_max_len: usize,
+    policy: CacheEvictionPolicy,
+    cache_max_bytes: Option<usize>,
+    /// The size on disk, the number of uses and the last use of each cached testcase
+    cached_info: RefCell<HashMap<usize, (usize, u64, u64)>>,
+    /// Counts the uses of the cached testcases, to order them
+    uses_clock: Cell<u64>,
+    pinned: HashSet<usize>,
+    keep_favored: bool,
 }
 
 impl<I> Corpus<I> for CachedOnDiskCorpus<I>
@@ -55,7 +85,21 @@ where
     fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
         let testcase = self.inner.remove(idx)?;
         if testcase.is_some() {
-            self.cached_indexes.borrow_mut().retain(|e| *e != idx);
+            // The following testcases moved down by one
+            let shift = |e: usize| if e > idx { e - 1 } else { e };
+            let mut cached_indexes = self.cached_indexes.borrow_mut();
+            cached_indexes.retain(|e| *e != idx);
+            for e in cached_indexes.iter_mut() {
+                *e = shift(*e);
+            }
+            let mut cached_info = self.cached_info.borrow_mut();
+            cached_info.remove(&idx);
+            *cached_info = cached_info
+                .drain()
+                .map(|(e, info)| (shift(e), info))
+                .collect();
+            self.pinned.remove(&idx);
+            self.pinned = self.pinned.drain().map(shift).collect();
         }
         Ok(testcase)
     }
@@ -64,22 +108,28 @@ where
     #[inline]
     fn get(&self, idx: usize) -> Result<&RefCell<Testcase<I>>, Error> {
         let testcase = { self.inner.get(idx)? };
-        if testcase.borrow().input().is_none() {
-            let _ = testcase.borrow_mut().load_input()?;
-            let mut borrowed_num = 0;
-            while self.cached_indexes.borrow().len() >= self.cache_max_len {
-                let removed = self.cached_indexes.borrow_mut().pop_front().unwrap();
-                if let Ok(mut borrowed) = self.inner.get(removed)?.try_borrow_mut() {
-                    *borrowed.input_mut() = None;
-                } else {
-                    self.cached_indexes.borrow_mut().push_back(removed);
-                    borrowed_num += 1;
-                    if self.cache_max_len == borrowed_num {
-                        break;
-                    }
-                }
+        if testcase.borrow().input().is_some() {
+            let now = self.tick();
+            if let Some((_, uses, last_use)) = self.cached_info.borrow_mut().get_mut(&idx) {
+                *uses += 1;
+                *last_use = now;
+            }
+        } else {
+            let size = {
+                let mut borrowed = testcase.borrow_mut();
+                let _ = borrowed.load_input()?;
+                borrowed
+                    .filename()
+                    .as_ref()
+                    .and_then(|filename| fs::metadata(filename).ok())
+                    .map_or(0, |meta| meta.len() as usize)
+            };
+            if !self.pinned.contains(&idx) {
+                self.cached_indexes.borrow_mut().push_back(idx);
+                let now = self.tick();
+                self.cached_info.borrow_mut().insert(idx, (size, 1, now));
+                self.evict(idx)?;
             }
-            self.cached_indexes.borrow_mut().push_back(idx);
         }
         Ok(testcase)
     }
@@ -103,16 +153,7 @@ where
 {
     /// Creates the [`CachedOnDiskCorpus`].
     pub fn new(dir_path: PathBuf, cache_max_len: usize) -> Result<Self, Error> {
-        if cache_max_len == 0 {
-            return Err(Error::IllegalArgument(
-                "The max cache len in CachedOnDiskCorpus cannot be 0".into(),
-            ));
-        }
-        Ok(Self {
-            inner: OnDiskCorpus::new(dir_path)?,
-            cached_indexes: RefCell::new(VecDeque::new()),
-            cache_max_len,
-        })
+        Self::with_inner(OnDiskCorpus::new(dir_path)?, cache_max_len)
     }
 
     /// Creates the [`CachedOnDiskCorpus`] specifying the type of `Metadata` to be saved to disk.
@@ -121,17 +162,151 @@ where
         meta_format: Option<OnDiskMetadataFormat>,
         cache_max_len: usize,
     ) -> Result<Self, Error> {
+        Self::with_inner(
+            OnDiskCorpus::new_save_meta(dir_path, meta_format)?,
+            cache_max_len,
+        )
+    }
+
+    /// Opens an existing [`CachedOnDiskCorpus`], see [`OnDiskCorpus::open`].
+    /// The inputs are loaded when used, keeping at most `cache_max_len` of them in memory.
+    pub fn open(
+        dir_path: PathBuf,
+        meta_format: Option<OnDiskMetadataFormat>,
+        cache_max_len: usize,
+    ) -> Result<Self, Error> {
+        Self::with_inner(OnDiskCorpus::open(dir_path, meta_format)?, cache_max_len)
+    }
+
+    fn with_inner(inner: OnDiskCorpus<I>, cache_max_len: usize) -> Result<Self, Error> {
         if cache_max_len == 0 {
             return Err(Error::IllegalArgument(
                 "The max cache len in CachedOnDiskCorpus cannot be 0".into(),
             ));
         }
         Ok(Self {
-            inner: OnDiskCorpus::new_save_meta(dir_path, meta_format)?,
+            inner,
             cached_indexes: RefCell::new(VecDeque::new()),
             cache_max_len,
+            policy: CacheEvictionPolicy::default(),
+            cache_max_bytes: None,
+            cached_info: RefCell::new(HashMap::new()),
+            uses_clock: Cell::new(0),
+            pinned: HashSet::new(),
+            keep_favored: false,
         })
     }
+
+    /// Use the given [`CacheEvictionPolicy`]
+    #[must_use]
+    pub fn with_policy(mut self, policy: CacheEvictionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Also keep the size of the cached inputs on disk below `cache_max_bytes`
+    #[must_use]
+    pub fn with_max_bytes(mut self, cache_max_bytes: usize) -> Self {
+        self.cache_max_bytes = Some(cache_max_bytes);
+        self
+    }
+
+    /// Never evict the testcases the scheduler favors, see [`IsFavoredMetadata`]
+    #[must_use]
+    pub fn keep_favored(mut self) -> Self {
+        self.keep_favored = true;
+        self
+    }
+
+    /// Keep the testcase at `idx` in memory until it is unpinned.
+    /// Pinned testcases do not count towards the limits of the cache.
+    pub fn pin(&mut self, idx: usize) -> Result<(), Error> {
+        let _ = self.inner.get(idx)?.borrow_mut().load_input()?;
+        self.pinned.insert(idx);
+        self.cached_indexes.borrow_mut().retain(|e| *e != idx);
+        self.cached_info.borrow_mut().remove(&idx);
+        Ok(())
+    }
+
+    /// Let the testcase at `idx` be evicted again
+    pub fn unpin(&mut self, idx: usize) -> Result<(), Error> {
+        if self.pinned.remove(&idx) {
+            // The input is already on disk
+            *self.inner.get(idx)?.borrow_mut().input_mut() = None;
+        }
+        Ok(())
+    }
+
+    /// If the testcase at `idx` is pinned
+    #[must_use]
+    pub fn is_pinned(&self, idx: usize) -> bool {
+        self.pinned.contains(&idx)
+    }
+
+    /// The next value of the uses clock
+    fn tick(&self) -> u64 {
+        let now = self.uses_clock.get() + 1;
+        self.uses_clock.set(now);
+        now
+    }
+
+    fn over_limits(&self) -> bool {
+        self.cached_indexes.borrow().len() > self.cache_max_len
+            || self.cache_max_bytes.map_or(false, |max_bytes| {
+                self.cached_info
+                    .borrow()
+                    .values()
+                    .map(|(size, _, _)| size)
+                    .sum::<usize>()
+                    > max_bytes
+            })
+    }
+
+    /// Evict testcases until the cache is within its limits, but not the one at `keep`
+    fn evict(&self, keep: usize) -> Result<(), Error> {
+        while self.over_limits() {
+            let mut candidates: Vec<usize> = self
+                .cached_indexes
+                .borrow()
+                .iter()
+                .copied()
+                .filter(|e| *e != keep)
+                .collect();
+            // Sorted by load time already, the sorts are stable
+            let cached_info = self.cached_info.borrow();
+            match self.policy {
+                CacheEvictionPolicy::Fifo => (),
+                CacheEvictionPolicy::Lru => {
+                    candidates.sort_by_key(|e| cached_info.get(e).map_or(0, |info| info.2));
+                }
+                CacheEvictionPolicy::Lfu => {
+                    candidates.sort_by_key(|e| cached_info.get(e).map_or(0, |info| info.1));
+                }
+            }
+            drop(cached_info);
+
+            let mut evicted = None;
+            for candidate in candidates {
+                // Testcases in use can not be evicted
+                if let Ok(mut borrowed) = self.inner.get(candidate)?.try_borrow_mut() {
+                    if self.keep_favored && borrowed.has_metadata::<IsFavoredMetadata>() {
+                        continue;
+                    }
+                    *borrowed.input_mut() = None;
+                    evicted = Some(candidate);
+                    break;
+                }
+            }
+            match evicted {
+                Some(evicted) => {
+                    self.cached_indexes.borrow_mut().retain(|e| *e != evicted);
+                    self.cached_info.borrow_mut().remove(&evicted);
+                }
+                None => break,
+            }
+        }
+        Ok(())
+    }
 }
 
 /// ``CachedOnDiskCorpus`` Python bindings
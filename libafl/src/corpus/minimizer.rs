@@ -0,0 +1,216 @@
+//! Corpus minimization (cmin): reduce the corpus to a small subset covering the same map entries.
+//!
+//! The [`MapCorpusMinimizer`] solves the weighted set cover of the [`MapIndexesMetadata`] of the
+//! testcases greedily: it keeps picking the testcase covering the most new entries per weight,
+//! the weight being the [`FavFactor`] of the testcase, and removes the ones it did not pick.
+//! The map feedback must track the indexes, see `MapFeedback::new_tracking`.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    corpus::Corpus,
+    feedbacks::MapIndexesMetadata,
+    fuzzer::HasScheduler,
+    inputs::Input,
+    schedulers::{minimizer::TopRatedsMetadata, FavFactor, LenTimeMulFavFactor, Scheduler},
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// A [`MapCorpusMinimizer`] weighting the testcases by their size times their execution time
+pub type StdCorpusMinimizer<I> = MapCorpusMinimizer<LenTimeMulFavFactor<I>, I>;
+
+/// Minimizes the corpus to the testcases needed to cover all the map entries seen so far.
+/// Testcases without [`MapIndexesMetadata`] can not be judged, they are always kept.
+#[derive(Debug, Clone)]
+pub struct MapCorpusMinimizer<F, I>
+where
+    F: FavFactor<I>,
+    I: Input,
+{
+    phantom: PhantomData<(F, I)>,
+}
+
+impl<F, I> Default for MapCorpusMinimizer<F, I>
+where
+    F: FavFactor<I>,
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, I> MapCorpusMinimizer<F, I>
+where
+    F: FavFactor<I>,
+    I: Input,
+{
+    /// Creates a new [`MapCorpusMinimizer`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Computes the minimal cover, returning for each map entry the index of the testcase covering it.
+    /// The picked testcases are the values of the map, the others may be removed.
+    pub fn cover<S>(&self, state: &mut S) -> Result<HashMap<usize, usize>, Error>
+    where
+        S: HasCorpus<I>,
+    {
+        // (corpus index, weight, covered map entries)
+        let mut candidates = Vec::new();
+        for idx in 0..state.corpus().count() {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            let entries = match testcase.metadata().get::<MapIndexesMetadata>() {
+                Some(meta) => meta.list.clone(),
+                None => continue,
+            };
+            let weight = u128::from(F::compute(&mut testcase)?) + 1;
+            candidates.push((idx, weight, entries));
+        }
+
+        let mut uncovered: HashSet<usize> = candidates
+            .iter()
+            .flat_map(|(_, _, entries)| entries.iter().copied())
+            .collect();
+        let mut covering = HashMap::new();
+
+        while !uncovered.is_empty() {
+            // The best ratio of new entries to weight, compared without floats
+            let mut best: Option<(usize, u128, u128)> = None;
+            for (pos, (_, weight, entries)) in candidates.iter().enumerate() {
+                let gain = entries.iter().filter(|e| uncovered.contains(*e)).count() as u128;
+                if gain == 0 {
+                    continue;
+                }
+                if best.map_or(true, |(_, best_gain, best_weight)| {
+                    gain * best_weight > best_gain * weight
+                }) {
+                    best = Some((pos, gain, *weight));
+                }
+            }
+            let (pos, _, _) = best.expect("An uncovered entry is covered by no testcase");
+            let (idx, _, entries) = candidates.swap_remove(pos);
+            for entry in entries {
+                if uncovered.remove(&entry) {
+                    covering.insert(entry, idx);
+                }
+            }
+            candidates.retain(|(_, _, entries)| entries.iter().any(|e| uncovered.contains(e)));
+        }
+
+        Ok(covering)
+    }
+
+    /// Minimizes the corpus, returning the number of removed testcases.
+    /// The scheduler is told about each removal, and the [`TopRatedsMetadata`], if any,
+    /// is updated to the new indexes.
+    pub fn minimize<CS, S, Z>(&self, fuzzer: &mut Z, state: &mut S) -> Result<usize, Error>
+    where
+        CS: Scheduler<I, S>,
+        S: HasCorpus<I> + HasMetadata,
+        Z: HasScheduler<CS, I, S>,
+    {
+        let covering = self.cover(state)?;
+        let kept: HashSet<usize> = covering.values().copied().collect();
+
+        let mut removed = Vec::new();
+        for idx in 0..state.corpus().count() {
+            let judged = state
+                .corpus()
+                .get(idx)?
+                .borrow()
+                .has_metadata::<MapIndexesMetadata>();
+            if judged && !kept.contains(&idx) {
+                removed.push(idx);
+            }
+        }
+
+        remove_testcases(fuzzer, state, &removed, &covering)?;
+        Ok(removed.len())
+    }
+}
+
+/// Removes the testcases at the sorted indexes in `removed`, telling the scheduler about each.
+/// The entries of the [`TopRatedsMetadata`] held by a removed testcase move to the testcase
+/// `covering` them, if any, and all of them are updated to the new indexes.
+pub(crate) fn remove_testcases<CS, I, S, Z>(
+    fuzzer: &mut Z,
+    state: &mut S,
+    removed: &[usize],
+    covering: &HashMap<usize, usize>,
+) -> Result<(), Error>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+    Z: HasScheduler<CS, I, S>,
+{
+    // Remove from the back, so that the indexes of the remaining ones stay valid
+    for &idx in removed.iter().rev() {
+        let testcase = state.corpus_mut().remove(idx)?;
+        fuzzer.scheduler().on_remove(state, idx, &testcase)?;
+    }
+    *state.corpus_mut().current_mut() = None;
+
+    // The corpus indexes shifted by the number of removed testcases before them
+    let new_idx = |idx: usize| idx - removed.partition_point(|&r| r < idx);
+    if let Some(top_rated) = state.metadata_mut().get_mut::<TopRatedsMetadata>() {
+        top_rated.map.retain(|entry, idx| {
+            if removed.binary_search(idx).is_ok() {
+                match covering.get(entry) {
+                    Some(&covering_idx) => *idx = covering_idx,
+                    None => return false,
+                }
+            }
+            *idx = new_idx(*idx);
+            true
+        });
+        top_rated.changed = true;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, StdCorpusMinimizer, Testcase},
+        feedbacks::MapIndexesMetadata,
+        inputs::BytesInput,
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_cmin_cover() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        for (len, list) in [
+            (1, vec![0, 1]),
+            (1, vec![2, 3]),
+            (4, vec![0, 1, 2, 3]),
+            (1, vec![1]),
+            (1, vec![]),
+        ] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![0; len]));
+            testcase.add_metadata(MapIndexesMetadata::new(list));
+            state.corpus_mut().add(testcase).unwrap();
+        }
+
+        let covering = StdCorpusMinimizer::new().cover(&mut state).unwrap();
+        assert_eq!(covering.len(), 4);
+        assert_eq!(covering[&0], 0);
+        assert_eq!(covering[&1], 0);
+        assert_eq!(covering[&2], 1);
+        assert_eq!(covering[&3], 1);
+    }
+}
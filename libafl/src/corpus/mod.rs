@@ -1,7 +1,7 @@
 //! Corpuses contain the testcases, either in memory, on disk, or somewhere else.
 
 pub mod testcase;
-pub use testcase::{PowerScheduleTestcaseMetaData, Testcase};
+pub use testcase::{PowerScheduleTestcaseMetaData, Testcase, TestcaseNamedMetadata};
 
 pub mod inmemory;
 pub use inmemory::InMemoryCorpus;
@@ -14,7 +14,19 @@ pub use ondisk::OnDiskCorpus;
 #[cfg(feature = "std")]
 pub mod cached;
 #[cfg(feature = "std")]
-pub use cached::CachedOnDiskCorpus;
+pub use cached::{CacheEvictionPolicy, CachedOnDiskCorpus};
+
+pub mod minimizer;
+pub use minimizer::{MapCorpusMinimizer, StdCorpusMinimizer};
+
+pub mod multi;
+pub use multi::{CorpusPartitionMetadata, MultiCorpus};
+
+pub mod pruning;
+pub use pruning::{
+    input_hash, CorpusAgingMetadata, CorpusPruner, DemotedMetadata, PruningAction,
+    TestcaseAgeMetadata,
+};
 
 use core::cell::RefCell;
 
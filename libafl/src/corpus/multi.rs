@@ -0,0 +1,248 @@
+//! A corpus split into several named partitions, e.g. one per objective of the fuzzer.
+//!
+//! New testcases land in the partition named by their [`CorpusPartitionMetadata`], see
+//! [`crate::feedbacks::CorpusPartitionFeedback`], or in the first partition if they have none.
+//! The [`MultiCorpus`] still exposes a single index space over all its partitions, so any
+//! scheduler works on it, while [`crate::schedulers::PartitionScheduler`] interleaves between them.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, marker::PhantomData};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, Testcase},
+    inputs::Input,
+    state::HasMetadata,
+    Error,
+};
+
+/// The name of the [`MultiCorpus`] partition a testcase belongs to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CorpusPartitionMetadata {
+    /// The name of the partition
+    pub name: String,
+}
+
+crate::impl_serdeany!(CorpusPartitionMetadata);
+
+impl CorpusPartitionMetadata {
+    /// Creates a new [`CorpusPartitionMetadata`] for the partition `name`
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+/// A corpus made of several named partitions, each backed by its own corpus.
+///
+/// The entries of all the partitions share a single index space, in the order they were added.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "C: serde::Serialize + serde::de::DeserializeOwned")]
+pub struct MultiCorpus<C, I>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    names: Vec<String>,
+    partitions: Vec<C>,
+    /// For each global index, the partition and the index in the partition
+    entries: Vec<(usize, usize)>,
+    current: Option<usize>,
+    phantom: PhantomData<I>,
+}
+
+impl<C, I> Corpus<I> for MultiCorpus<C, I>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    /// Returns the number of elements, in all the partitions
+    #[inline]
+    fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Add an entry to the partition named in its [`CorpusPartitionMetadata`] and return its index
+    fn add(&mut self, testcase: Testcase<I>) -> Result<usize, Error> {
+        let partition = match testcase.metadata().get::<CorpusPartitionMetadata>() {
+            Some(meta) => self.partition_idx(&meta.name).ok_or_else(|| {
+                Error::KeyNotFound(format!("No corpus partition named {}", meta.name))
+            })?,
+            None => 0,
+        };
+        let local = self
+            .partitions
+            .get_mut(partition)
+            .ok_or_else(|| Error::IllegalState("MultiCorpus has no partitions".to_string()))?
+            .add(testcase)?;
+        self.entries.push((partition, local));
+        Ok(self.entries.len() - 1)
+    }
+
+    /// Replaces the testcase at the given idx, in the partition it already belongs to
+    fn replace(&mut self, idx: usize, testcase: Testcase<I>) -> Result<(), Error> {
+        let (partition, local) = *self
+            .entries
+            .get(idx)
+            .ok_or_else(|| Error::KeyNotFound(format!("Index {} out of bounds", idx)))?;
+        self.partitions[partition].replace(local, testcase)
+    }
+
+    /// Removes an entry from the corpus, returning it if it was present.
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
+        if idx >= self.entries.len() {
+            return Ok(None);
+        }
+        let (partition, local) = self.entries.remove(idx);
+        let removed = self.partitions[partition].remove(local)?;
+        // The partition shifted its own later entries down by one
+        for entry in &mut self.entries {
+            if entry.0 == partition && entry.1 > local {
+                entry.1 -= 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Get by id
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<I>>, Error> {
+        let (partition, local) = *self
+            .entries
+            .get(idx)
+            .ok_or_else(|| Error::KeyNotFound(format!("Index {} out of bounds", idx)))?;
+        self.partitions[partition].get(local)
+    }
+
+    /// Current testcase scheduled
+    #[inline]
+    fn current(&self) -> &Option<usize> {
+        &self.current
+    }
+
+    /// Current testcase scheduled (mutable)
+    #[inline]
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        &mut self.current
+    }
+}
+
+impl<C, I> MultiCorpus<C, I>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    /// Creates a new [`MultiCorpus`] with a first partition, which also receives the testcases
+    /// that do not name their partition
+    #[must_use]
+    pub fn new(name: &str, corpus: C) -> Self {
+        Self {
+            names: vec![name.to_string()],
+            entries: (0..corpus.count()).map(|local| (0, local)).collect(),
+            partitions: vec![corpus],
+            current: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Adds a partition, backed by `corpus`.
+    /// The entries already in `corpus` are appended to the index space.
+    pub fn with_partition(mut self, name: &str, corpus: C) -> Result<Self, Error> {
+        if self.partition_idx(name).is_some() {
+            return Err(Error::IllegalArgument(format!(
+                "Corpus partition {} already exists",
+                name
+            )));
+        }
+        let partition = self.partitions.len();
+        self.entries
+            .extend((0..corpus.count()).map(|local| (partition, local)));
+        self.names.push(name.to_string());
+        self.partitions.push(corpus);
+        Ok(self)
+    }
+
+    /// The number of partitions
+    #[inline]
+    #[must_use]
+    pub fn partitions_count(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// The names of the partitions, in order
+    #[inline]
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The index of the partition named `name`
+    #[must_use]
+    pub fn partition_idx(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    /// The corpus backing the partition at `partition`
+    #[must_use]
+    pub fn partition(&self, partition: usize) -> Option<&C> {
+        self.partitions.get(partition)
+    }
+
+    /// The partition of the entry at the global index `idx`
+    #[must_use]
+    pub fn partition_of(&self, idx: usize) -> Option<usize> {
+        self.entries.get(idx).map(|(partition, _)| *partition)
+    }
+
+    /// The global index of the `nth` entry of the partition at `partition`
+    #[must_use]
+    pub fn nth_in_partition(&self, partition: usize, nth: usize) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(p, local)| *p == partition && *local == nth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        corpus::{Corpus, CorpusPartitionMetadata, InMemoryCorpus, MultiCorpus, Testcase},
+        inputs::{BytesInput, HasBytesVec},
+        state::HasMetadata,
+    };
+
+    fn tagged(byte: u8, partition: &str) -> Testcase<BytesInput> {
+        let mut testcase = Testcase::new(BytesInput::new(vec![byte]));
+        testcase.add_metadata(CorpusPartitionMetadata::new(partition));
+        testcase
+    }
+
+    #[test]
+    fn test_multi_corpus() {
+        let mut corpus = MultiCorpus::new("coverage", InMemoryCorpus::<BytesInput>::new())
+            .with_partition("timeouts", InMemoryCorpus::new())
+            .unwrap();
+
+        corpus.add(Testcase::new(BytesInput::new(vec![0]))).unwrap();
+        corpus.add(tagged(1, "timeouts")).unwrap();
+        corpus.add(tagged(2, "timeouts")).unwrap();
+        corpus.add(tagged(3, "coverage")).unwrap();
+        assert!(corpus.add(tagged(4, "unknown")).is_err());
+
+        assert_eq!(corpus.count(), 4);
+        assert_eq!(corpus.partition(0).unwrap().count(), 2);
+        assert_eq!(corpus.partition(1).unwrap().count(), 2);
+        assert_eq!(corpus.partition_of(2), Some(1));
+        assert_eq!(corpus.nth_in_partition(0, 1), Some(3));
+
+        corpus.remove(1).unwrap();
+        assert_eq!(corpus.count(), 3);
+        assert_eq!(corpus.nth_in_partition(1, 0), Some(1));
+        let input = corpus.get(1).unwrap().borrow().input().clone().unwrap();
+        assert_eq!(input.bytes(), &[2]);
+    }
+}
@@ -1,7 +1,8 @@
 //! The ondisk corpus stores unused testcases to disk.
 
-use alloc::vec::Vec;
-use core::{cell::RefCell, time::Duration};
+use ahash::AHasher;
+use alloc::{string::String, vec::Vec};
+use core::{cell::RefCell, hash::Hasher, time::Duration};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::OpenOptions,
@@ -12,8 +13,8 @@ use std::{
 use std::{fs, fs::File, io::Write};
 
 use crate::{
-    bolts::serdeany::SerdeAnyMap, corpus::Corpus, corpus::Testcase, inputs::Input,
-    state::HasMetadata, Error,
+    bolts::serdeany::SerdeAnyMap, corpus::Corpus, corpus::Testcase, feedbacks::MapIndexesMetadata,
+    inputs::Input, mutators::LogMutationMetadata, state::HasMetadata, Error,
 };
 
 /// Options for the the format of the on-disk metadata
@@ -28,13 +29,59 @@ pub enum OnDiskMetadataFormat {
     JsonPretty,
 }
 
-/// A corpus able to store testcases to disk, and load them from disk, when they are being used.
+/// The metadata of a testcase, written to a sidecar file next to its input.
+/// Besides the [`SerdeAnyMap`], it spells out the most useful values for other tools to read.
 #[cfg(feature = "std")]
 #[derive(Debug, Serialize)]
 pub struct OnDiskMetadata<'a> {
     metadata: &'a SerdeAnyMap,
     exec_time: &'a Option<Duration>,
     executions: &'a usize,
+    coverage_hash: Option<u64>,
+    mutations: Option<&'a [String]>,
+}
+
+impl<'a> OnDiskMetadata<'a> {
+    /// The sidecar metadata of `testcase`
+    #[must_use]
+    pub fn new<I>(testcase: &'a Testcase<I>) -> Self
+    where
+        I: Input,
+    {
+        let coverage_hash = testcase.metadata().get::<MapIndexesMetadata>().map(|meta| {
+            let mut hasher = AHasher::new_with_keys(0, 0);
+            for idx in &meta.list {
+                hasher.write_usize(*idx);
+            }
+            hasher.finish()
+        });
+        Self {
+            metadata: testcase.metadata(),
+            exec_time: testcase.exec_time(),
+            executions: testcase.executions(),
+            coverage_hash,
+            mutations: testcase
+                .metadata()
+                .get::<LogMutationMetadata>()
+                .map(|meta| meta.list.as_slice()),
+        }
+    }
+}
+
+/// The content of a sidecar file, as read back by [`OnDiskCorpus::open`]
+#[cfg(feature = "std")]
+#[derive(Debug, Deserialize)]
+pub struct OnDiskMetadataOwned {
+    /// The metadata of the testcase
+    pub metadata: SerdeAnyMap,
+    /// The execution time of the testcase
+    pub exec_time: Option<Duration>,
+    /// The executions done at discovery time
+    pub executions: usize,
+    /// The hash of the map indexes covered by the testcase, if tracked
+    pub coverage_hash: Option<u64>,
+    /// The mutations that found the testcase, if logged
+    pub mutations: Option<Vec<String>>,
 }
 
 /// A corpus able to store testcases to disk, and load them from disk, when they are being used.
@@ -94,34 +141,7 @@ where
             let filename_str = filename.to_str().expect("Invalid Path");
             testcase.set_filename(filename_str.into());
         };
-        if self.meta_format.is_some() {
-            let mut filename = PathBuf::from(testcase.filename().as_ref().unwrap());
-            filename.set_file_name(format!(
-                ".{}.metadata",
-                filename.file_name().unwrap().to_string_lossy()
-            ));
-            let mut tmpfile_name = PathBuf::from(&filename);
-            tmpfile_name.set_file_name(format!(
-                ".{}.tmp",
-                tmpfile_name.file_name().unwrap().to_string_lossy()
-            ));
-
-            let ondisk_meta = OnDiskMetadata {
-                metadata: testcase.metadata(),
-                exec_time: testcase.exec_time(),
-                executions: testcase.executions(),
-            };
-
-            let mut tmpfile = File::create(&tmpfile_name)?;
-
-            let serialized = match self.meta_format.as_ref().unwrap() {
-                OnDiskMetadataFormat::Postcard => postcard::to_allocvec(&ondisk_meta)?,
-                OnDiskMetadataFormat::Json => serde_json::to_vec(&ondisk_meta)?,
-                OnDiskMetadataFormat::JsonPretty => serde_json::to_vec_pretty(&ondisk_meta)?,
-            };
-            tmpfile.write_all(&serialized)?;
-            fs::rename(&tmpfile_name, &filename)?;
-        }
+        self.save_metadata(&testcase)?;
         testcase
             .store_input()
             .expect("Could not save testcase to disk");
@@ -135,6 +155,7 @@ where
         if idx >= self.entries.len() {
             return Err(Error::KeyNotFound(format!("Index {} out of bounds", idx)));
         }
+        self.save_metadata(&testcase)?;
         self.entries[idx] = RefCell::new(testcase);
         Ok(())
     }
@@ -204,6 +225,90 @@ where
             meta_format,
         })
     }
+
+    /// Opens an existing [`OnDiskCorpus`], e.g. the one of a previous run.
+    /// Only the file names and the sidecar metadata are loaded, the inputs are read from disk
+    /// when they are used, so that huge corpora fit in memory.
+    pub fn open(
+        dir_path: PathBuf,
+        meta_format: Option<OnDiskMetadataFormat>,
+    ) -> Result<Self, Error> {
+        let mut corpus = Self::new_save_meta(dir_path, meta_format)?;
+
+        let mut filenames = Vec::new();
+        for entry in fs::read_dir(&corpus.dir_path)? {
+            let entry = entry?;
+            // Lockfiles, metadata and temporary files are hidden
+            if entry.file_type()?.is_file() && !entry.file_name().to_string_lossy().starts_with('.')
+            {
+                filenames.push(entry.path());
+            }
+        }
+        filenames.sort();
+
+        for filename in filenames {
+            let mut testcase = Testcase::default();
+            if corpus.meta_format.is_some() {
+                let meta_path = Self::metadata_path(&filename);
+                if meta_path.exists() {
+                    let serialized = fs::read(&meta_path)?;
+                    let meta: OnDiskMetadataOwned = match corpus.meta_format.as_ref().unwrap() {
+                        OnDiskMetadataFormat::Postcard => postcard::from_bytes(&serialized)?,
+                        OnDiskMetadataFormat::Json | OnDiskMetadataFormat::JsonPretty => {
+                            serde_json::from_slice(&serialized)?
+                        }
+                    };
+                    *testcase.metadata_mut() = meta.metadata;
+                    *testcase.exec_time_mut() = meta.exec_time;
+                    *testcase.executions_mut() = meta.executions;
+                }
+            }
+            testcase.set_filename(filename.to_str().expect("Invalid Path").into());
+            corpus.entries.push(RefCell::new(testcase));
+        }
+
+        Ok(corpus)
+    }
+
+    /// Writes the sidecar metadata of `testcase` again, e.g. after a stage added metadata
+    pub fn save_metadata(&self, testcase: &Testcase<I>) -> Result<(), Error> {
+        let meta_format = match &self.meta_format {
+            Some(meta_format) => meta_format,
+            None => return Ok(()),
+        };
+        let filename = match testcase.filename() {
+            Some(filename) => Self::metadata_path(Path::new(filename)),
+            None => return Ok(()),
+        };
+        let mut tmpfile_name = PathBuf::from(&filename);
+        tmpfile_name.set_file_name(format!(
+            ".{}.tmp",
+            tmpfile_name.file_name().unwrap().to_string_lossy()
+        ));
+
+        let ondisk_meta = OnDiskMetadata::new(testcase);
+
+        let mut tmpfile = File::create(&tmpfile_name)?;
+
+        let serialized = match meta_format {
+            OnDiskMetadataFormat::Postcard => postcard::to_allocvec(&ondisk_meta)?,
+            OnDiskMetadataFormat::Json => serde_json::to_vec(&ondisk_meta)?,
+            OnDiskMetadataFormat::JsonPretty => serde_json::to_vec_pretty(&ondisk_meta)?,
+        };
+        tmpfile.write_all(&serialized)?;
+        fs::rename(&tmpfile_name, &filename)?;
+        Ok(())
+    }
+
+    /// The path of the sidecar metadata file of the input at `filename`
+    fn metadata_path(filename: &Path) -> PathBuf {
+        let mut meta_path = filename.to_path_buf();
+        meta_path.set_file_name(format!(
+            ".{}.metadata",
+            filename.file_name().unwrap().to_string_lossy()
+        ));
+        meta_path
+    }
 }
 #[cfg(feature = "python")]
 /// `OnDiskCorpus` Python bindings
@@ -0,0 +1,344 @@
+//! Corpus aging: prune the testcases that stopped being useful.
+//!
+//! A testcase is stale once it did not produce any new corpus entry for a number of queue
+//! cycles. The [`CorpusPruner`] demotes or removes the stale testcases whose map entries, from
+//! their [`MapIndexesMetadata`], are all covered by other testcases, and broadcasts the pruned
+//! inputs so that the other clients purge them as well.
+//! The testcases are aged by the [`crate::stages::CorpusPruningStage`].
+
+use ahash::AHasher;
+use alloc::vec::Vec;
+use core::{hash::Hasher, marker::PhantomData};
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::serdeany::SerdeAnyMap,
+    corpus::{minimizer::remove_testcases, Corpus},
+    events::{Event, EventFirer},
+    feedbacks::MapIndexesMetadata,
+    fuzzer::HasScheduler,
+    inputs::Input,
+    schedulers::{
+        minimizer::{IsFavoredMetadata, TopRatedsMetadata},
+        Scheduler,
+    },
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// The default number of queue cycles without a new corpus entry after which a testcase is stale
+pub const DEFAULT_PRUNING_MAX_AGE: u64 = 16;
+
+/// A hash of an input identifying it across clients, whose corpus indexes differ
+pub fn input_hash<I>(input: &I) -> Result<u64, Error>
+where
+    I: Input,
+{
+    let mut hasher = AHasher::new_with_keys(0, 0);
+    hasher.write(&postcard::to_allocvec(input)?);
+    Ok(hasher.finish())
+}
+
+/// The queue cycle in which a testcase last produced a new corpus entry, or was added
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct TestcaseAgeMetadata {
+    /// The queue cycle
+    pub last_productive_cycle: u64,
+}
+
+crate::impl_serdeany!(TestcaseAgeMetadata);
+
+/// A marker for the testcases the [`CorpusPruner`] demoted, they are never favored again
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct DemotedMetadata {}
+
+crate::impl_serdeany!(DemotedMetadata);
+
+/// The state of the corpus aging
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CorpusAgingMetadata {
+    /// The queue cycles so far
+    pub cycles: u64,
+    /// The testcases fuzzed in the current cycle
+    pub runs: usize,
+    /// The corpus size after the last fuzzed testcase
+    pub last_count: usize,
+    /// The number of testcases pruned so far
+    pub pruned: usize,
+    /// The hashes of the inputs other clients pruned, to purge in the next run
+    pub purge_requests: Vec<u64>,
+}
+
+crate::impl_serdeany!(
+    CorpusAgingMetadata,
+    "libafl::corpus::pruning::CorpusAgingMetadata"
+);
+
+impl CorpusAgingMetadata {
+    /// Creates a new [`struct@CorpusAgingMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the next pruning run to purge the inputs with the given hashes, if this corpus
+    /// does not need them. The `metadata` is the one of the state.
+    pub fn request_purge(metadata: &mut SerdeAnyMap, input_hashes: Vec<u64>) {
+        if !metadata.contains::<Self>() {
+            metadata.insert(Self::new());
+        }
+        metadata
+            .get_mut::<Self>()
+            .unwrap()
+            .purge_requests
+            .extend(input_hashes);
+    }
+}
+
+/// What the [`CorpusPruner`] does with the stale testcases
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruningAction {
+    /// Keep them, but never favor them again
+    Demote,
+    /// Remove them from the corpus
+    Remove,
+}
+
+/// Prunes the stale testcases whose coverage other testcases have as well.
+/// Testcases without [`MapIndexesMetadata`] can not be judged, they are always kept.
+#[derive(Debug, Clone)]
+pub struct CorpusPruner<I>
+where
+    I: Input,
+{
+    max_age: u64,
+    action: PruningAction,
+    phantom: PhantomData<I>,
+}
+
+impl<I> Default for CorpusPruner<I>
+where
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new(DEFAULT_PRUNING_MAX_AGE)
+    }
+}
+
+impl<I> CorpusPruner<I>
+where
+    I: Input,
+{
+    /// Creates a new [`CorpusPruner`], removing the testcases that did not produce a new
+    /// corpus entry in `max_age` queue cycles
+    #[must_use]
+    pub fn new(max_age: u64) -> Self {
+        Self {
+            max_age,
+            action: PruningAction::Remove,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Set what to do with the stale testcases
+    #[must_use]
+    pub fn with_action(mut self, action: PruningAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Picks the testcases to prune: the stale ones and the ones other clients pruned, as long
+    /// as the remaining testcases cover all their map entries.
+    /// Returns their sorted indexes, and for each of their map entries a testcase covering it.
+    pub fn redundant<S>(&self, state: &S) -> Result<(Vec<usize>, HashMap<usize, usize>), Error>
+    where
+        S: HasCorpus<I> + HasMetadata,
+    {
+        let (cycles, purge_requests): (u64, HashSet<u64>) =
+            match state.metadata().get::<CorpusAgingMetadata>() {
+                Some(meta) => (meta.cycles, meta.purge_requests.iter().copied().collect()),
+                None => (0, HashSet::new()),
+            };
+
+        // How many testcases cover each map entry
+        let mut covered = HashMap::<usize, usize>::new();
+        let mut candidates = Vec::new();
+        for idx in 0..state.corpus().count() {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            let entries = match testcase.metadata().get::<MapIndexesMetadata>() {
+                Some(meta) => meta.list.clone(),
+                None => continue,
+            };
+            for entry in &entries {
+                *covered.entry(*entry).or_default() += 1;
+            }
+            if testcase.has_metadata::<DemotedMetadata>() {
+                continue;
+            }
+
+            let age = cycles.saturating_sub(
+                testcase
+                    .metadata()
+                    .get::<TestcaseAgeMetadata>()
+                    .map_or(cycles, |meta| meta.last_productive_cycle),
+            );
+            if age >= self.max_age
+                || (!purge_requests.is_empty()
+                    && purge_requests.contains(&input_hash(testcase.load_input()?)?))
+            {
+                candidates.push((idx, age, entries));
+            }
+        }
+
+        // Prune the oldest first, each one only if the others still cover its entries
+        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        let mut pruned = Vec::new();
+        for (idx, _, entries) in candidates {
+            if entries.iter().all(|entry| covered[entry] > 1) {
+                for entry in &entries {
+                    *covered.get_mut(entry).unwrap() -= 1;
+                }
+                pruned.push(idx);
+            }
+        }
+        pruned.sort_unstable();
+
+        let mut covering = HashMap::new();
+        for idx in 0..state.corpus().count() {
+            if pruned.binary_search(&idx).is_ok() {
+                continue;
+            }
+            if let Some(meta) = state
+                .corpus()
+                .get(idx)?
+                .borrow()
+                .metadata()
+                .get::<MapIndexesMetadata>()
+            {
+                for entry in &meta.list {
+                    covering.entry(*entry).or_insert(idx);
+                }
+            }
+        }
+
+        Ok((pruned, covering))
+    }
+
+    /// Prunes the corpus, returning the number of pruned testcases.
+    /// The other clients are told about the pruned inputs, unless they asked for it themselves.
+    pub fn prune<CS, EM, S, Z>(
+        &self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<usize, Error>
+    where
+        CS: Scheduler<I, S>,
+        EM: EventFirer<I>,
+        S: HasCorpus<I> + HasMetadata,
+        Z: HasScheduler<CS, I, S>,
+    {
+        let (pruned, covering) = self.redundant(state)?;
+        let purge_requests = match state.metadata_mut().get_mut::<CorpusAgingMetadata>() {
+            Some(meta) => {
+                meta.pruned += pruned.len();
+                core::mem::take(&mut meta.purge_requests)
+            }
+            None => Vec::new(),
+        };
+        if pruned.is_empty() {
+            return Ok(0);
+        }
+
+        let mut input_hashes = Vec::new();
+        for &idx in &pruned {
+            let hash = input_hash(state.corpus().get(idx)?.borrow_mut().load_input()?)?;
+            if !purge_requests.contains(&hash) {
+                input_hashes.push(hash);
+            }
+        }
+
+        match self.action {
+            PruningAction::Remove => remove_testcases(fuzzer, state, &pruned, &covering)?,
+            PruningAction::Demote => {
+                for &idx in &pruned {
+                    let mut testcase = state.corpus().get(idx)?.borrow_mut();
+                    testcase.add_metadata(DemotedMetadata {});
+                    drop(testcase.metadata_mut().remove::<IsFavoredMetadata>());
+                }
+                // Hand their map entries over to the testcases covering them
+                if let Some(top_rated) = state.metadata_mut().get_mut::<TopRatedsMetadata>() {
+                    top_rated.map.retain(|entry, idx| {
+                        if pruned.binary_search(idx).is_ok() {
+                            match covering.get(entry) {
+                                Some(&covering_idx) => *idx = covering_idx,
+                                None => return false,
+                            }
+                        }
+                        true
+                    });
+                    top_rated.changed = true;
+                }
+            }
+        }
+
+        if !input_hashes.is_empty() {
+            manager.fire(
+                state,
+                Event::PurgeTestcases {
+                    input_hashes,
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        Ok(pruned.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{
+            pruning::input_hash, Corpus, CorpusAgingMetadata, CorpusPruner, InMemoryCorpus,
+            Testcase, TestcaseAgeMetadata,
+        },
+        feedbacks::MapIndexesMetadata,
+        inputs::BytesInput,
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_pruning_redundant() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        // Two stale testcases covering each other, and a young one
+        for (byte, list, cycle) in [(0, vec![0, 1], 0), (1, vec![0, 1], 0), (2, vec![1], 9)] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![byte]));
+            testcase.add_metadata(MapIndexesMetadata::new(list));
+            testcase.add_metadata(TestcaseAgeMetadata {
+                last_productive_cycle: cycle,
+            });
+            state.corpus_mut().add(testcase).unwrap();
+        }
+        let mut meta = CorpusAgingMetadata::new();
+        meta.cycles = 10;
+        state.add_metadata(meta);
+
+        let pruner = CorpusPruner::new(4);
+        let (pruned, covering) = pruner.redundant(&state).unwrap();
+        assert_eq!(pruned, vec![0]);
+        assert_eq!(covering[&0], 1);
+
+        // Another client pruned the young one, the others still cover it
+        let hash = input_hash(&BytesInput::new(vec![2])).unwrap();
+        CorpusAgingMetadata::request_purge(state.metadata_mut(), vec![hash]);
+        let (pruned, _) = pruner.redundant(&state).unwrap();
+        assert_eq!(pruned, vec![0, 2]);
+    }
+}
@@ -3,18 +3,20 @@
 
 use alloc::string::String;
 use core::{convert::Into, default::Default, option::Option, time::Duration};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-    bolts::{serdeany::SerdeAnyMap, HasLen},
+    bolts::{
+        serdeany::{NamedSerdeAnyMap, SerdeAnyMap},
+        HasLen,
+    },
     inputs::Input,
-    state::HasMetadata,
+    state::{HasMetadata, HasNamedMetadata},
     Error,
 };
 
 /// An entry in the Testcase Corpus
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(bound = "I: serde::de::DeserializeOwned")]
+#[derive(Clone, Debug)]
 pub struct Testcase<I>
 where
     I: Input,
@@ -25,6 +27,8 @@ where
     filename: Option<String>,
     /// Map of metadata associated with this testcase
     metadata: SerdeAnyMap,
+    /// Map of metadata associated with this testcase, by name
+    named_metadata: NamedSerdeAnyMap,
     /// Time needed to execute the input
     exec_time: Option<Duration>,
     /// Cached len of the input, if any
@@ -35,6 +39,104 @@ where
     fuzzed: bool,
 }
 
+/// The named metadata of a [`Testcase`], kept in its metadata while serialized.
+/// This way, testcases serialize as they did before they had named metadata.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TestcaseNamedMetadata {
+    map: NamedSerdeAnyMap,
+}
+
+crate::impl_serdeany!(TestcaseNamedMetadata);
+
+/// The serialized layout of a [`Testcase`]
+#[derive(Serialize)]
+struct TestcaseLayoutRef<'a, I>
+where
+    I: Input,
+{
+    input: &'a Option<I>,
+    filename: &'a Option<String>,
+    metadata: &'a SerdeAnyMap,
+    exec_time: &'a Option<Duration>,
+    cached_len: &'a Option<usize>,
+    executions: usize,
+    fuzzed: bool,
+}
+
+/// The serialized layout of a [`Testcase`], owned
+#[derive(Deserialize)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+struct TestcaseLayout<I>
+where
+    I: Input,
+{
+    input: Option<I>,
+    filename: Option<String>,
+    metadata: SerdeAnyMap,
+    exec_time: Option<Duration>,
+    cached_len: Option<usize>,
+    executions: usize,
+    fuzzed: bool,
+}
+
+impl<I> Serialize for Testcase<I>
+where
+    I: Input,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let with_named;
+        let metadata = if self.named_metadata.is_empty() {
+            &self.metadata
+        } else {
+            let mut metadata = self.metadata.clone();
+            metadata.insert(TestcaseNamedMetadata {
+                map: self.named_metadata.clone(),
+            });
+            with_named = metadata;
+            &with_named
+        };
+        TestcaseLayoutRef {
+            input: &self.input,
+            filename: &self.filename,
+            metadata,
+            exec_time: &self.exec_time,
+            cached_len: &self.cached_len,
+            executions: self.executions,
+            fuzzed: self.fuzzed,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, I> Deserialize<'de> for Testcase<I>
+where
+    I: Input,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let layout = TestcaseLayout::<I>::deserialize(deserializer)?;
+        let mut metadata = layout.metadata;
+        let named_metadata = metadata
+            .remove::<TestcaseNamedMetadata>()
+            .map_or_else(NamedSerdeAnyMap::new, |named| named.map);
+        Ok(Self {
+            input: layout.input,
+            filename: layout.filename,
+            metadata,
+            named_metadata,
+            exec_time: layout.exec_time,
+            cached_len: layout.cached_len,
+            executions: layout.executions,
+            fuzzed: layout.fuzzed,
+        })
+    }
+}
+
 impl<I> HasMetadata for Testcase<I>
 where
     I: Input,
@@ -52,6 +154,21 @@ where
     }
 }
 
+impl<I> HasNamedMetadata for Testcase<I>
+where
+    I: Input,
+{
+    #[inline]
+    fn named_metadata(&self) -> &NamedSerdeAnyMap {
+        &self.named_metadata
+    }
+
+    #[inline]
+    fn named_metadata_mut(&mut self) -> &mut NamedSerdeAnyMap {
+        &mut self.named_metadata
+    }
+}
+
 /// Impl of a testcase
 impl<I> Testcase<I>
 where
@@ -214,6 +331,7 @@ where
             input: None,
             filename: None,
             metadata: SerdeAnyMap::new(),
+            named_metadata: NamedSerdeAnyMap::new(),
             exec_time: None,
             cached_len: None,
             executions: 0,
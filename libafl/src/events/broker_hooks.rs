@@ -0,0 +1,187 @@
+//! Hooks into the [`crate::events::LlmpEventBroker`], to observe, filter, or rewrite the events
+//! of the clients before the broker handles and forwards them.
+
+use core::{fmt::Debug, marker::PhantomData};
+
+use crate::{bolts::HasLen, events::Event, inputs::Input, Error};
+
+/// What the broker does with an event, after a [`EventBrokerHook`] saw it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventHookResult {
+    /// Handle and forward the event as usual
+    Keep,
+    /// The hook changed the event, handle and forward the new one
+    Rewritten,
+    /// Drop the event: neither the broker nor the other clients get to see it
+    Drop,
+}
+
+/// A hook into the broker, called for each event of a client
+pub trait EventBrokerHook<I>: Debug
+where
+    I: Input,
+{
+    /// Called for each event of the client `client_id`, before the broker handles it.
+    /// The hook may change the event in place, returning [`EventHookResult::Rewritten`].
+    fn on_event(&mut self, client_id: u32, event: &mut Event<I>) -> Result<EventHookResult, Error>;
+}
+
+/// A tuple of [`EventBrokerHook`]s, called in order
+pub trait EventBrokerHooksTuple<I>: Debug
+where
+    I: Input,
+{
+    /// Calls all hooks on the event, until one of them drops it
+    fn on_event_all(
+        &mut self,
+        client_id: u32,
+        event: &mut Event<I>,
+    ) -> Result<EventHookResult, Error>;
+}
+
+impl<I> EventBrokerHooksTuple<I> for ()
+where
+    I: Input,
+{
+    fn on_event_all(
+        &mut self,
+        _client_id: u32,
+        _event: &mut Event<I>,
+    ) -> Result<EventHookResult, Error> {
+        Ok(EventHookResult::Keep)
+    }
+}
+
+impl<Head, Tail, I> EventBrokerHooksTuple<I> for (Head, Tail)
+where
+    Head: EventBrokerHook<I>,
+    Tail: EventBrokerHooksTuple<I>,
+    I: Input,
+{
+    fn on_event_all(
+        &mut self,
+        client_id: u32,
+        event: &mut Event<I>,
+    ) -> Result<EventHookResult, Error> {
+        match self.0.on_event(client_id, event)? {
+            EventHookResult::Drop => Ok(EventHookResult::Drop),
+            EventHookResult::Keep => self.1.on_event_all(client_id, event),
+            EventHookResult::Rewritten => match self.1.on_event_all(client_id, event)? {
+                EventHookResult::Drop => Ok(EventHookResult::Drop),
+                _ => Ok(EventHookResult::Rewritten),
+            },
+        }
+    }
+}
+
+/// Drops the new testcases larger than a limit, so they never reach the other clients
+#[derive(Debug, Clone, Copy)]
+pub struct MaxInputSizeBrokerHook<I> {
+    max_size: usize,
+    phantom: PhantomData<I>,
+}
+
+impl<I> MaxInputSizeBrokerHook<I> {
+    /// Creates a new [`MaxInputSizeBrokerHook`] dropping the testcases of more than `max_size` bytes
+    #[must_use]
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> EventBrokerHook<I> for MaxInputSizeBrokerHook<I>
+where
+    I: Input + HasLen,
+{
+    fn on_event(
+        &mut self,
+        _client_id: u32,
+        event: &mut Event<I>,
+    ) -> Result<EventHookResult, Error> {
+        match event {
+            Event::NewTestcase { input, .. } if input.len() > self.max_size => {
+                Ok(EventHookResult::Drop)
+            }
+            _ => Ok(EventHookResult::Keep),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use crate::{
+        bolts::{current_time, tuples::tuple_list},
+        events::{
+            broker_hooks::{
+                EventBrokerHook, EventBrokerHooksTuple, EventHookResult, MaxInputSizeBrokerHook,
+            },
+            Event, EventConfig, LogSeverity,
+        },
+        executors::ExitKind,
+        inputs::BytesInput,
+        Error,
+    };
+
+    /// Rewrites all log messages
+    #[derive(Debug)]
+    struct CensorHook;
+
+    impl EventBrokerHook<BytesInput> for CensorHook {
+        fn on_event(
+            &mut self,
+            _client_id: u32,
+            event: &mut Event<BytesInput>,
+        ) -> Result<EventHookResult, Error> {
+            match event {
+                Event::Log { message, .. } => {
+                    *message = "<censored>".into();
+                    Ok(EventHookResult::Rewritten)
+                }
+                _ => Ok(EventHookResult::Keep),
+            }
+        }
+    }
+
+    fn testcase(len: usize) -> Event<BytesInput> {
+        Event::NewTestcase {
+            input: BytesInput::new(vec![0; len]),
+            observers_buf: None,
+            exit_kind: ExitKind::Ok,
+            corpus_size: 1,
+            client_config: EventConfig::AlwaysUnique,
+            time: current_time(),
+            executions: 0,
+            forward_id: None,
+        }
+    }
+
+    #[test]
+    fn test_broker_hooks() {
+        let mut hooks = tuple_list!(MaxInputSizeBrokerHook::new(4), CensorHook);
+
+        assert_eq!(
+            hooks.on_event_all(1, &mut testcase(4)).unwrap(),
+            EventHookResult::Keep
+        );
+        assert_eq!(
+            hooks.on_event_all(1, &mut testcase(5)).unwrap(),
+            EventHookResult::Drop
+        );
+
+        let mut log = Event::Log {
+            severity_level: LogSeverity::Info,
+            message: "secret".into(),
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            hooks.on_event_all(1, &mut log).unwrap(),
+            EventHookResult::Rewritten
+        );
+        assert!(matches!(log, Event::Log { message, .. } if message == "<censored>"));
+    }
+}
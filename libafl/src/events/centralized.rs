@@ -0,0 +1,437 @@
+//! A wrapper manager to send the new testcases of all secondary nodes to one main node.
+//!
+//! Without it, each client re-executes every new testcase of every other client, only to find
+//! out that most of them are not interesting for itself. With a [`CentralizedEventManager`],
+//! the secondary nodes send their new testcases to the main node, over a dedicated llmp broker.
+//! The main node checks them against its own feedbacks, and only broadcasts the novel ones,
+//! using the wrapped event manager.
+
+use core::{marker::PhantomData, time::Duration};
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "llmp_compression")]
+use crate::{
+    bolts::{
+        compress::StdCompressor,
+        llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED},
+    },
+    events::llmp::COMPRESS_THRESHOLD,
+};
+use crate::{
+    bolts::{
+        llmp::{self, Flags, LlmpClient, Tag},
+        shmem::ShMemProvider,
+    },
+    events::{
+        Event, EventConfig, EventFirer, EventManager, EventManagerId, EventProcessor,
+        EventRestarter, HasEventManagerId, ProgressReporter,
+    },
+    executors::{Executor, HasObservers},
+    fuzzer::{EvaluatorObservers, ExecutionProcessor},
+    inputs::Input,
+    observers::ObserversTuple,
+    Error,
+};
+
+/// A new testcase from a secondary node, for the main node
+const _LLMP_TAG_TO_MAIN: Tag = 0x3453453;
+
+/// The llmp broker connecting the secondary nodes to the main node.
+/// It runs next to the usual broker, on a port of its own.
+#[derive(Debug)]
+pub struct CentralizedLlmpEventBroker<SP>
+where
+    SP: ShMemProvider + 'static,
+{
+    llmp: llmp::LlmpBroker<SP>,
+}
+
+impl<SP> CentralizedLlmpEventBroker<SP>
+where
+    SP: ShMemProvider + 'static,
+{
+    /// Create a centralized broker from a raw broker.
+    #[must_use]
+    pub fn new(llmp: llmp::LlmpBroker<SP>) -> Self {
+        Self { llmp }
+    }
+
+    /// Create a centralized broker on a port.
+    /// The port must not be bound yet.
+    #[cfg(feature = "std")]
+    pub fn new_on_port(shmem_provider: SP, port: u16) -> Result<Self, Error> {
+        Ok(Self {
+            llmp: llmp::LlmpBroker::create_attach_to_tcp(shmem_provider, port)?,
+        })
+    }
+
+    /// Run forever in the broker, passing the testcases on to the main node
+    pub fn broker_loop(&mut self) -> Result<(), Error> {
+        self.llmp.loop_forever(
+            &mut |_client_id: u32, _tag: Tag, _flags: Flags, _msg: &[u8]| {
+                Ok(llmp::LlmpMsgHookResult::ForwardToClients)
+            },
+            Some(Duration::from_millis(5)),
+        );
+        Ok(())
+    }
+}
+
+/// An [`EventManager`] wrapping another one, usually an [`crate::events::LlmpEventManager`].
+///
+/// On a secondary node, it sends the new testcases to the main node, instead of broadcasting them.
+/// On the main node, it evaluates the testcases of the secondary nodes, and broadcasts the
+/// interesting ones with the wrapped manager. All other events go to the wrapped manager.
+#[derive(Debug)]
+pub struct CentralizedEventManager<EM, I, OT, S, SP>
+where
+    EM: EventFirer<I>,
+    I: Input,
+    SP: ShMemProvider + 'static,
+{
+    inner: EM,
+    client: LlmpClient<SP>,
+    #[cfg(feature = "llmp_compression")]
+    compressor: StdCompressor,
+    is_main: bool,
+    /// The id of the secondary node whose testcase the main node is evaluating
+    forwarding: Option<u32>,
+    phantom: PhantomData<(I, OT, S)>,
+}
+
+impl<EM, I, OT, S, SP> CentralizedEventManager<EM, I, OT, S, SP>
+where
+    EM: EventFirer<I>,
+    I: Input,
+    SP: ShMemProvider + 'static,
+{
+    /// Creates a new [`CentralizedEventManager`] wrapping `inner`, connected to the
+    /// [`CentralizedLlmpEventBroker`] with `client`
+    pub fn new(inner: EM, client: LlmpClient<SP>, is_main: bool) -> Self {
+        Self {
+            inner,
+            client,
+            #[cfg(feature = "llmp_compression")]
+            compressor: StdCompressor::new(COMPRESS_THRESHOLD),
+            is_main,
+            forwarding: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`CentralizedEventManager`] wrapping `inner`, connected to the
+    /// [`CentralizedLlmpEventBroker`] on the given port
+    #[cfg(feature = "std")]
+    pub fn on_port(inner: EM, shmem_provider: SP, port: u16, is_main: bool) -> Result<Self, Error> {
+        Ok(Self::new(
+            inner,
+            LlmpClient::create_attach_to_tcp(shmem_provider, port)?,
+            is_main,
+        ))
+    }
+
+    /// If this is the main node, checking the testcases of the others
+    #[must_use]
+    pub fn is_main(&self) -> bool {
+        self.is_main
+    }
+
+    /// The wrapped event manager
+    pub fn inner(&self) -> &EM {
+        &self.inner
+    }
+
+    /// The wrapped event manager (mutable)
+    pub fn inner_mut(&mut self) -> &mut EM {
+        &mut self.inner
+    }
+
+    /// Sends an event to the main node
+    #[cfg(feature = "llmp_compression")]
+    fn send_to_main(&mut self, event: &Event<I>) -> Result<(), Error> {
+        let serialized = postcard::to_allocvec(event)?;
+        match self.compressor.compress(&serialized)? {
+            Some(comp_buf) => self.client.send_buf_with_flags(
+                _LLMP_TAG_TO_MAIN,
+                LLMP_FLAG_INITIALIZED | LLMP_FLAG_COMPRESSED,
+                &comp_buf,
+            ),
+            None => self.client.send_buf(_LLMP_TAG_TO_MAIN, &serialized),
+        }
+    }
+
+    /// Sends an event to the main node
+    #[cfg(not(feature = "llmp_compression"))]
+    fn send_to_main(&mut self, event: &Event<I>) -> Result<(), Error> {
+        let serialized = postcard::to_allocvec(event)?;
+        self.client.send_buf(_LLMP_TAG_TO_MAIN, &serialized)
+    }
+
+    /// Evaluates the testcases of the secondary nodes, in the main node
+    fn receive_from_secondary<E, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        executor: &mut E,
+    ) -> Result<usize, Error>
+    where
+        E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+        OT: ObserversTuple<I, S> + DeserializeOwned,
+        Self: EventManager<E, I, S, Z>,
+        Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+    {
+        let mut events = vec![];
+        let self_id = self.client.sender.id;
+        while let Some((client_id, tag, _flags, msg)) = self.client.recv_buf_with_flags()? {
+            if client_id == self_id || tag != _LLMP_TAG_TO_MAIN {
+                continue;
+            }
+            #[cfg(not(feature = "llmp_compression"))]
+            let event_bytes = msg;
+            #[cfg(feature = "llmp_compression")]
+            let compressed;
+            #[cfg(feature = "llmp_compression")]
+            let event_bytes = if _flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+                compressed = self.compressor.decompress(msg)?;
+                &compressed
+            } else {
+                msg
+            };
+            let event: Event<I> = postcard::from_bytes(event_bytes)?;
+            events.push(event);
+        }
+
+        let count = events.len();
+        for event in events {
+            match event {
+                Event::NewTestcase {
+                    input,
+                    client_config,
+                    exit_kind,
+                    observers_buf,
+                    forward_id,
+                    ..
+                } => {
+                    // Broadcasts the testcase with the wrapped manager, if it is interesting,
+                    // marked so the secondary node that found it skips it
+                    self.forwarding = forward_id;
+                    let res = if client_config.match_with(&self.configuration())
+                        && observers_buf.is_some()
+                    {
+                        postcard::from_bytes::<OT>(observers_buf.as_ref().unwrap())
+                            .map_err(Error::from)
+                            .and_then(|observers| {
+                                fuzzer.process_execution(
+                                    state, self, input, &observers, &exit_kind, true,
+                                )
+                            })
+                    } else {
+                        fuzzer.evaluate_input_with_observers(state, executor, self, input, true)
+                    };
+                    self.forwarding = None;
+                    res?;
+                }
+                _ => {
+                    return Err(Error::Unknown(format!(
+                        "Received illegal message for the main node: {:?}.",
+                        event.name()
+                    )))
+                }
+            }
+        }
+        Ok(count)
+    }
+}
+
+impl<EM, I, OT, S, SP> EventFirer<I> for CentralizedEventManager<EM, I, OT, S, SP>
+where
+    EM: EventFirer<I> + HasEventManagerId,
+    I: Input,
+    SP: ShMemProvider + 'static,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn fire<S2>(&mut self, state: &mut S2, mut event: Event<I>) -> Result<(), Error> {
+        if let Event::NewTestcase { forward_id, .. } = &mut event {
+            if !self.is_main {
+                *forward_id = Some(self.inner.mgr_id().id as u32);
+                return self.send_to_main(&event);
+            }
+            if forward_id.is_none() {
+                *forward_id = self.forwarding;
+            }
+        }
+        self.inner.fire(state, event)
+    }
+
+    fn configuration(&self) -> EventConfig {
+        self.inner.configuration()
+    }
+}
+
+impl<EM, I, OT, S, SP> EventRestarter<S> for CentralizedEventManager<EM, I, OT, S, SP>
+where
+    EM: EventFirer<I> + EventRestarter<S>,
+    I: Input,
+    SP: ShMemProvider + 'static,
+{
+    fn on_restart(&mut self, state: &mut S) -> Result<(), Error> {
+        self.client.await_safe_to_unmap_blocking();
+        self.inner.on_restart(state)
+    }
+
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.client.send_exiting()?;
+        self.inner.send_exiting()
+    }
+
+    fn await_restart_safe(&mut self) {
+        self.client.await_safe_to_unmap_blocking();
+        self.inner.await_restart_safe();
+    }
+}
+
+impl<E, EM, I, OT, S, SP, Z> EventProcessor<E, I, S, Z>
+    for CentralizedEventManager<EM, I, OT, S, SP>
+where
+    E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+    EM: EventManager<E, I, S, Z>,
+    I: Input,
+    OT: ObserversTuple<I, S> + DeserializeOwned,
+    SP: ShMemProvider + 'static,
+    Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+{
+    fn process(&mut self, fuzzer: &mut Z, state: &mut S, executor: &mut E) -> Result<usize, Error> {
+        let count = if self.is_main {
+            self.receive_from_secondary(fuzzer, state, executor)?
+        } else {
+            0
+        };
+        Ok(count + self.inner.process(fuzzer, state, executor)?)
+    }
+}
+
+impl<E, EM, I, OT, S, SP, Z> EventManager<E, I, S, Z> for CentralizedEventManager<EM, I, OT, S, SP>
+where
+    E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+    EM: EventManager<E, I, S, Z>,
+    I: Input,
+    OT: ObserversTuple<I, S> + DeserializeOwned,
+    SP: ShMemProvider + 'static,
+    Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+{
+}
+
+impl<EM, I, OT, S, SP> ProgressReporter<I> for CentralizedEventManager<EM, I, OT, S, SP>
+where
+    EM: ProgressReporter<I> + HasEventManagerId,
+    I: Input,
+    SP: ShMemProvider + 'static,
+{
+}
+
+impl<EM, I, OT, S, SP> HasEventManagerId for CentralizedEventManager<EM, I, OT, S, SP>
+where
+    EM: EventFirer<I> + HasEventManagerId,
+    I: Input,
+    SP: ShMemProvider + 'static,
+{
+    fn mgr_id(&self) -> EventManagerId {
+        self.inner.mgr_id()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use core::time::Duration;
+    use serial_test::serial;
+
+    use crate::{
+        bolts::{
+            llmp::{LlmpClient, LlmpSharedMap},
+            shmem::{ShMemProvider, StdShMemProvider},
+        },
+        events::{
+            centralized::CentralizedEventManager, Event, EventConfig, EventFirer, EventManagerId,
+            HasEventManagerId,
+        },
+        executors::ExitKind,
+        inputs::BytesInput,
+        Error,
+    };
+
+    /// Keeps the events fired through it
+    #[derive(Debug, Default)]
+    struct RecordingEventManager {
+        events: Vec<Event<BytesInput>>,
+    }
+
+    impl EventFirer<BytesInput> for RecordingEventManager {
+        fn fire<S>(&mut self, _state: &mut S, event: Event<BytesInput>) -> Result<(), Error> {
+            self.events.push(event);
+            Ok(())
+        }
+    }
+
+    impl HasEventManagerId for RecordingEventManager {
+        fn mgr_id(&self) -> EventManagerId {
+            EventManagerId { id: 3 }
+        }
+    }
+
+    type TestMgr =
+        CentralizedEventManager<RecordingEventManager, BytesInput, (), (), StdShMemProvider>;
+
+    /// A manager on its own map, without a broker
+    fn test_mgr(is_main: bool) -> TestMgr {
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let mut llmp_client = LlmpClient::new(
+            shmem_provider.clone(),
+            LlmpSharedMap::new(0, shmem_provider.new_shmem(1024).unwrap()),
+        )
+        .unwrap();
+        unsafe {
+            llmp_client.mark_safe_to_unmap();
+        }
+        CentralizedEventManager::new(RecordingEventManager::default(), llmp_client, is_main)
+    }
+
+    fn new_testcase() -> Event<BytesInput> {
+        Event::NewTestcase {
+            input: BytesInput::new(vec![0]),
+            observers_buf: None,
+            exit_kind: ExitKind::Ok,
+            corpus_size: 1,
+            client_config: EventConfig::AlwaysUnique,
+            time: Duration::ZERO,
+            executions: 0,
+            forward_id: None,
+        }
+    }
+
+    fn forward_id(event: &Event<BytesInput>) -> Option<u32> {
+        match event {
+            Event::NewTestcase { forward_id, .. } => *forward_id,
+            _ => None,
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_forward_id() {
+        // The testcases of a secondary node only go to the main node
+        let mut secondary = test_mgr(false);
+        secondary.fire(&mut (), new_testcase()).unwrap();
+        assert!(secondary.inner().events.is_empty());
+
+        // The main node marks the testcases of a secondary node with its id when broadcasting them
+        let mut main = test_mgr(true);
+        main.forwarding = Some(3);
+        main.fire(&mut (), new_testcase()).unwrap();
+        main.forwarding = None;
+        main.fire(&mut (), new_testcase()).unwrap();
+        let events = &main.inner().events;
+        assert_eq!(forward_id(&events[0]), Some(3));
+        assert_eq!(forward_id(&events[1]), None);
+    }
+}
@@ -6,68 +6,183 @@ use crate::bolts::os::startable_self;
 use crate::bolts::os::{fork, ForkResult};
 #[cfg(feature = "llmp_compression")]
 use crate::bolts::{
-    compress::GzipCompressor,
+    compress::StdCompressor,
     llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED},
 };
 #[cfg(feature = "std")]
-use crate::bolts::{llmp::LlmpConnection, shmem::StdShMemProvider, staterestore::StateRestorer};
+use crate::bolts::{
+    llmp::LlmpConnection,
+    shmem::StdShMemProvider,
+    staterestore::{StateRestorer, STATE_RESTORER_INITIAL_MAP_SIZE},
+};
+#[cfg(feature = "std")]
+use crate::corpus::input_hash;
 use crate::{
     bolts::{
+        current_nanos, current_time,
         llmp::{self, Flags, LlmpClient, LlmpClientDescription, Tag},
+        rands::{Rand, StdRand},
         shmem::ShMemProvider,
     },
+    corpus::CorpusAgingMetadata,
     events::{
-        BrokerEventResult, Event, EventConfig, EventFirer, EventManager, EventManagerId,
-        EventProcessor, EventRestarter, HasEventManagerId, ProgressReporter,
+        BrokerEventResult, Event, EventBrokerHooksTuple, EventConfig, EventFirer, EventHookResult,
+        EventManager, EventManagerId, EventProcessor, EventRestarter, HasEventManagerId,
+        ProgressReporter, StateMetadataFn,
     },
     executors::{Executor, HasObservers},
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
     inputs::Input,
     monitors::Monitor,
     observers::ObserversTuple,
+    schedulers::ReplayLogMetadata,
+    state::HasMetadata,
     Error,
 };
-use alloc::string::ToString;
+use alloc::{string::ToString, vec::Vec};
 #[cfg(feature = "std")]
 use core::sync::atomic::{compiler_fence, Ordering};
-use core::{marker::PhantomData, time::Duration};
+use core::{marker::PhantomData, num::NonZeroUsize, time::Duration};
 #[cfg(feature = "std")]
 use core_affinity::CoreId;
+#[cfg(feature = "std")]
+use hashbrown::HashSet;
 use serde::de::DeserializeOwned;
 #[cfg(feature = "std")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 #[cfg(feature = "std")]
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::{
+    fs,
+    net::{SocketAddr, ToSocketAddrs},
+    path::PathBuf,
+};
 #[cfg(feature = "std")]
 use typed_builder::TypedBuilder;
+#[cfg(feature = "std")]
+use uuid::Uuid;
 
 /// Forward this to the client
 const _LLMP_TAG_EVENT_TO_CLIENT: Tag = 0x2C11E471;
 /// Only handle this in the broker
-const _LLMP_TAG_EVENT_TO_BROKER: Tag = 0x2B80438;
+const LLMP_TAG_EVENT_TO_BROKER: Tag = 0x2B80438;
 /// Handle in both
 ///
 const LLMP_TAG_EVENT_TO_BOTH: Tag = 0x2B0741;
 const _LLMP_TAG_RESTART: Tag = 0x8357A87;
 const _LLMP_TAG_NO_RESTART: Tag = 0x57A7EE71;
+/// A batch of events, handled in both
+const LLMP_TAG_EVENT_BATCH: Tag = 0x2BA7C4;
+/// A summary of the stats of the clients of a broker, only for the other brokers
+const LLMP_TAG_B2B_STATS: Tag = 0x2B2B57A7;
+
+/// The time between two summaries of the stats for the other brokers, see [`LlmpEventBroker::with_b2b_stats`]
+const B2B_STATS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// When the [`LlmpEventManager`] shares its new testcases with the other nodes.
+///
+/// Sharing everything makes each node re-execute the testcases of all others,
+/// which stops scaling at some point, usually past ~32 cores.
+/// Testcases that are not shared still reach the monitor of the broker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestcaseSharing {
+    /// Share all new testcases
+    Always,
+    /// Share each new testcase with this probability, between `0.0` and `1.0`
+    Probabilistic(f64),
+    /// Share no testcases, only report the objectives
+    ObjectivesOnly,
+    /// Share no testcases over llmp. Instead, write them to a directory shared by all nodes,
+    /// and pick up the testcases of the others from it, every `interval`.
+    #[cfg(feature = "std")]
+    CorpusDirSync {
+        /// The directory shared by all nodes
+        dir: PathBuf,
+        /// The time between two imports from the directory
+        interval: Duration,
+    },
+}
+
+impl Default for TestcaseSharing {
+    fn default() -> Self {
+        Self::Always
+    }
+}
+
+/// The files of the [`TestcaseSharing::CorpusDirSync`] directory this client imported or wrote,
+/// kept in the state, so a restarted client does not import them again.
+/// Only kept by the managers given access to the state, see [`LlmpEventManager::with_state_metadata`].
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CorpusDirSyncMetadata {
+    /// The files imported or written so far
+    pub synced: HashSet<PathBuf>,
+}
+
+#[cfg(feature = "std")]
+crate::impl_serdeany!(
+    CorpusDirSyncMetadata,
+    "libafl::events::llmp::CorpusDirSyncMetadata"
+);
 
 /// The minimum buffer size at which to compress LLMP IPC messages.
 #[cfg(feature = "llmp_compression")]
-const COMPRESS_THRESHOLD: usize = 1024;
+pub(crate) const COMPRESS_THRESHOLD: usize = 1024;
+
+/// The events in a message of the given tag, one or a whole batch
+fn unpack_events<I>(tag: Tag, event_bytes: &[u8]) -> Result<Vec<Event<I>>, Error>
+where
+    I: Input,
+{
+    if tag == LLMP_TAG_EVENT_BATCH || tag == LLMP_TAG_B2B_STATS {
+        let batch: Vec<Vec<u8>> = postcard::from_bytes(event_bytes)?;
+        batch
+            .iter()
+            .map(|bytes| Ok(postcard::from_bytes(bytes)?))
+            .collect()
+    } else {
+        Ok(vec![postcard::from_bytes(event_bytes)?])
+    }
+}
+
+/// Packs events into one message, of the given tag, for the clients
+fn pack_events<I>(events: &[Event<I>]) -> Result<(Tag, Vec<u8>), Error>
+where
+    I: Input,
+{
+    if let [event] = events {
+        Ok((LLMP_TAG_EVENT_TO_BOTH, postcard::to_allocvec(event)?))
+    } else {
+        let batch = events
+            .iter()
+            .map(|event| Ok(postcard::to_allocvec(event)?))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok((LLMP_TAG_EVENT_BATCH, postcard::to_allocvec(&batch)?))
+    }
+}
 
 /// An LLMP-backed event manager for scalable multi-processed fuzzing
+///
+/// The [`EventBrokerHooksTuple`] of the broker, see [`LlmpEventBroker::with_hooks`],
+/// sees each event of the clients first, and may filter or rewrite it.
 #[derive(Debug)]
-pub struct LlmpEventBroker<I, MT, SP>
+pub struct LlmpEventBroker<I, MT, SP, HT = ()>
 where
     I: Input,
     SP: ShMemProvider + 'static,
     MT: Monitor,
+    HT: EventBrokerHooksTuple<I>,
     //CE: CustomEvent<I>,
 {
     monitor: MT,
     llmp: llmp::LlmpBroker<SP>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: StdCompressor,
+    forward_stats: bool,
+    /// The last summary of the stats sent to the other brokers
+    last_b2b_stats: Duration,
+    /// The clients that are other brokers, left out of the summaries
+    remote_clients: Vec<u32>,
+    hooks: HT,
     phantom: PhantomData<I>,
 }
 
@@ -78,12 +193,21 @@ where
     MT: Monitor,
 {
     /// Create an even broker from a raw broker.
-    pub fn new(llmp: llmp::LlmpBroker<SP>, monitor: MT) -> Result<Self, Error> {
+    pub fn new(
+        #[allow(unused_mut)] mut llmp: llmp::LlmpBroker<SP>,
+        monitor: MT,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "std")]
+        llmp.set_b2b_filter(Some(Self::forward_to_broker));
         Ok(Self {
             monitor,
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: StdCompressor::new(COMPRESS_THRESHOLD),
+            forward_stats: false,
+            last_b2b_stats: current_time(),
+            remote_clients: vec![],
+            hooks: (),
             phantom: PhantomData,
         })
     }
@@ -92,13 +216,62 @@ where
     /// The port must not be bound yet to have a broker.
     #[cfg(feature = "std")]
     pub fn new_on_port(shmem_provider: SP, monitor: MT, port: u16) -> Result<Self, Error> {
-        Ok(Self {
+        Self::new(
+            llmp::LlmpBroker::create_attach_to_tcp(shmem_provider, port)?,
             monitor,
-            llmp: llmp::LlmpBroker::create_attach_to_tcp(shmem_provider, port)?,
+        )
+    }
+
+    /// Calls the `hooks` on each event of the clients, before handling it
+    #[must_use]
+    pub fn with_hooks<HT>(self, hooks: HT) -> LlmpEventBroker<I, MT, SP, HT>
+    where
+        HT: EventBrokerHooksTuple<I>,
+    {
+        LlmpEventBroker {
+            monitor: self.monitor,
+            llmp: self.llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: self.compressor,
+            forward_stats: self.forward_stats,
+            last_b2b_stats: self.last_b2b_stats,
+            remote_clients: self.remote_clients,
+            hooks,
             phantom: PhantomData,
-        })
+        }
+    }
+}
+
+impl<I, MT, SP, HT> LlmpEventBroker<I, MT, SP, HT>
+where
+    I: Input,
+    SP: ShMemProvider + 'static,
+    MT: Monitor,
+    HT: EventBrokerHooksTuple<I>,
+{
+    /// The hooks of this broker
+    pub fn hooks(&self) -> &HT {
+        &self.hooks
+    }
+
+    /// The hooks of this broker (mutable)
+    pub fn hooks_mut(&mut self) -> &mut HT {
+        &mut self.hooks
+    }
+
+    /// Also sends a summary of the stats of the local clients, their executions and objectives,
+    /// to the other brokers, every 15 seconds.
+    /// The remote stats show up as the stats of the broker 2 broker connection.
+    #[must_use]
+    pub fn with_b2b_stats(mut self) -> Self {
+        self.forward_stats = true;
+        self
+    }
+
+    /// Exits the [`LlmpEventBroker::broker_loop`] once `n_clients` clients exited cleanly,
+    /// see [`EventRestarter::send_exiting`]
+    pub fn set_exit_cleanly_after(&mut self, n_clients: NonZeroUsize) {
+        self.llmp.set_exit_cleanly_after(n_clients);
     }
 
     /// Connect to an llmp broker on the givien address
@@ -110,30 +283,161 @@ where
         self.llmp.connect_b2b(addr)
     }
 
+    /// Connect to an llmp broker on the given address over `tls`, checking its certificate for `domain`
+    #[cfg(feature = "llmp_tls")]
+    pub fn connect_b2b_tls<A>(
+        &mut self,
+        addr: A,
+        domain: &str,
+        connector: &native_tls::TlsConnector,
+    ) -> Result<(), Error>
+    where
+        A: ToSocketAddrs,
+    {
+        self.llmp.connect_b2b_tls(addr, domain, connector)
+    }
+
+    /// Listen for other brokers connecting over `tls` on the given port
+    #[cfg(feature = "llmp_tls")]
+    pub fn launch_tls_listener_on(
+        &mut self,
+        port: u16,
+        acceptor: native_tls::TlsAcceptor,
+    ) -> Result<(), Error> {
+        self.llmp.launch_tls_listener_on(port, acceptor)?;
+        Ok(())
+    }
+
+    /// The [`llmp::B2bFilter`] of the event broker: the events leave the machine, other messages stay.
+    /// Only the tag matters, the stats and other events for the broker only are not sent to the clients anyway.
+    #[cfg(feature = "std")]
+    fn forward_to_broker(tag: Tag, _flags: Flags, _msg: &[u8]) -> bool {
+        tag == LLMP_TAG_EVENT_TO_BOTH || tag == LLMP_TAG_EVENT_BATCH || tag == LLMP_TAG_B2B_STATS
+    }
+
+    /// The summary of the stats of the local clients, for the other brokers
+    fn b2b_stats(monitor: &MT, remote_clients: &[u32]) -> Result<Vec<u8>, Error> {
+        let (executions, objective_size) = monitor
+            .client_stats()
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| !remote_clients.contains(&(*id as u32)))
+            .fold((0, 0), |(executions, objective_size), (_, client)| {
+                (
+                    executions + client.executions,
+                    objective_size + client.objective_size,
+                )
+            });
+        let (_, serialized) = pack_events::<I>(&[
+            Event::UpdateExecStats {
+                time: current_time(),
+                executions: executions as usize,
+                phantom: PhantomData,
+            },
+            Event::Objective {
+                objective_size: objective_size as usize,
+            },
+        ])?;
+        Ok(serialized)
+    }
+
     /// Run forever in the broker
     pub fn broker_loop(&mut self) -> Result<(), Error> {
         let monitor = &mut self.monitor;
         #[cfg(feature = "llmp_compression")]
         let compressor = &self.compressor;
-        self.llmp.loop_forever(
-            &mut |client_id: u32, tag: Tag, _flags: Flags, msg: &[u8]| {
-                if tag == LLMP_TAG_EVENT_TO_BOTH {
+        let forward_stats = self.forward_stats;
+        let last_b2b_stats = &mut self.last_b2b_stats;
+        let remote_clients = &mut self.remote_clients;
+        let hooks = &mut self.hooks;
+        self.llmp.loop_forever_with_outbox(
+            &mut |client_id: u32,
+                  tag: Tag,
+                  flags: Flags,
+                  msg: &[u8],
+                  outbox: &mut Vec<(Tag, Flags, Vec<u8>)>| {
+                if flags & llmp::LLMP_FLAG_FROM_B2B != 0 {
+                    if !remote_clients.contains(&client_id) {
+                        remote_clients.push(client_id);
+                    }
+                } else if forward_stats && current_time() - *last_b2b_stats >= B2B_STATS_INTERVAL {
+                    // The summary leaves with the message of a local client, only the other brokers read it
+                    *last_b2b_stats = current_time();
+                    outbox.push((
+                        LLMP_TAG_B2B_STATS,
+                        llmp::LLMP_FLAG_INITIALIZED,
+                        Self::b2b_stats(monitor, remote_clients)?,
+                    ));
+                }
+
+                if tag == LLMP_TAG_EVENT_TO_BOTH
+                    || tag == LLMP_TAG_EVENT_BATCH
+                    || tag == LLMP_TAG_EVENT_TO_BROKER
+                    || tag == LLMP_TAG_B2B_STATS
+                {
                     #[cfg(not(feature = "llmp_compression"))]
                     let event_bytes = msg;
                     #[cfg(feature = "llmp_compression")]
                     let compressed;
                     #[cfg(feature = "llmp_compression")]
-                    let event_bytes = if _flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
+                    let event_bytes = if flags & LLMP_FLAG_COMPRESSED == LLMP_FLAG_COMPRESSED {
                         compressed = compressor.decompress(msg)?;
                         &compressed
                     } else {
                         msg
                     };
-                    let event: Event<I> = postcard::from_bytes(event_bytes)?;
-                    match Self::handle_in_broker(monitor, client_id, &event)? {
-                        BrokerEventResult::Forward => Ok(llmp::LlmpMsgHookResult::ForwardToClients),
-                        BrokerEventResult::Handled => Ok(llmp::LlmpMsgHookResult::Handled),
+                    if tag == LLMP_TAG_B2B_STATS {
+                        // The summary of another broker, only for the monitor
+                        for event in unpack_events::<I>(tag, event_bytes)? {
+                            Self::handle_in_broker(monitor, client_id, &event)?;
+                        }
+                        return Ok(llmp::LlmpMsgHookResult::Handled);
+                    }
+                    let events = unpack_events::<I>(tag, event_bytes)?;
+                    let count = events.len();
+                    let mut result = llmp::LlmpMsgHookResult::Handled;
+                    let mut changed = false;
+                    let mut forwarded = vec![];
+                    for mut event in events {
+                        match hooks.on_event_all(client_id, &mut event)? {
+                            EventHookResult::Keep => (),
+                            EventHookResult::Rewritten => changed = true,
+                            EventHookResult::Drop => {
+                                changed = true;
+                                continue;
+                            }
+                        }
+                        // Testcases the client did not share only count in the monitor
+                        if let BrokerEventResult::Forward =
+                            Self::handle_in_broker(monitor, client_id, &event)?
+                        {
+                            if tag != LLMP_TAG_EVENT_TO_BROKER {
+                                result = llmp::LlmpMsgHookResult::ForwardToClients;
+                                forwarded.push(event);
+                            }
+                        }
+                    }
+                    // A batch mixing events for the clients with events only for the broker is split
+                    if !changed && (forwarded.is_empty() || forwarded.len() == count) {
+                        return Ok(result);
                     }
+
+                    // The message changed, the broker sends the new one instead, from the same client
+                    if !forwarded.is_empty() {
+                        let (tag, serialized) = pack_events(&forwarded)?;
+                        #[cfg(not(feature = "llmp_compression"))]
+                        outbox.push((tag, llmp::LLMP_FLAG_INITIALIZED, serialized));
+                        #[cfg(feature = "llmp_compression")]
+                        match compressor.compress(&serialized)? {
+                            Some(comp_buf) => outbox.push((
+                                tag,
+                                LLMP_FLAG_INITIALIZED | LLMP_FLAG_COMPRESSED,
+                                comp_buf,
+                            )),
+                            None => outbox.push((tag, LLMP_FLAG_INITIALIZED, serialized)),
+                        }
+                    }
+                    Ok(llmp::LlmpMsgHookResult::Handled)
                 } else {
                     Ok(llmp::LlmpMsgHookResult::ForwardToClients)
                 }
@@ -151,6 +455,9 @@ where
         client_id: u32,
         event: &Event<I>,
     ) -> Result<BrokerEventResult, Error> {
+        monitor
+            .client_stats_mut_for(client_id)
+            .heartbeat(current_time());
         match &event {
             Event::NewTestcase {
                 input: _,
@@ -160,10 +467,12 @@ where
                 observers_buf: _,
                 time,
                 executions,
+                forward_id: _,
             } => {
                 let client = monitor.client_stats_mut_for(client_id);
                 client.update_corpus_size(*corpus_size as u64);
                 client.update_executions(*executions as u64, *time);
+                client.add_corpus_contribution();
                 monitor.display(event.name().to_string(), client_id);
                 Ok(BrokerEventResult::Forward)
             }
@@ -218,6 +527,17 @@ where
                 monitor.display(event.name().to_string(), client_id);
                 Ok(BrokerEventResult::Handled)
             }
+            Event::PurgeTestcases {
+                input_hashes: _,
+                phantom: _,
+            } => Ok(BrokerEventResult::Forward),
+            Event::ClientName { name, phantom: _ } => {
+                monitor
+                    .client_stats_mut_for(client_id)
+                    .update_name(name.clone());
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
             Event::Log {
                 severity_level,
                 message,
@@ -226,7 +546,12 @@ where
                 let (_, _) = (severity_level, message);
                 // TODO rely on Monitor
                 #[cfg(feature = "std")]
-                println!("[LOG {}]: {}", severity_level, message);
+                println!(
+                    "[LOG {} {}]: {}",
+                    severity_level,
+                    monitor.client_label(client_id),
+                    message
+                );
                 Ok(BrokerEventResult::Handled)
             } //_ => Ok(BrokerEventResult::Forward),
         }
@@ -245,8 +570,32 @@ where
 {
     llmp: LlmpClient<SP>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: StdCompressor,
     configuration: EventConfig,
+    /// The serialized events not sent yet
+    batch: Vec<Vec<u8>>,
+    /// The size of the events not sent yet
+    batch_len: usize,
+    /// The size at which to send the batch, 0 to send each event on its own
+    batch_threshold: usize,
+    /// When to share the new testcases
+    sharing: TestcaseSharing,
+    /// The rand for [`TestcaseSharing::Probabilistic`]
+    rand: StdRand,
+    /// The last import from the [`TestcaseSharing::CorpusDirSync`] directory
+    #[cfg(feature = "std")]
+    last_sync: Duration,
+    /// The unique id of this node, in the names of its files in the [`TestcaseSharing::CorpusDirSync`] directory
+    #[cfg(feature = "std")]
+    node_id: String,
+    /// The files written to the [`TestcaseSharing::CorpusDirSync`] directory since the last import
+    #[cfg(feature = "std")]
+    written: Vec<PathBuf>,
+    /// The files of the [`TestcaseSharing::CorpusDirSync`] directory synced so far, if not kept in the state
+    #[cfg(feature = "std")]
+    synced: HashSet<PathBuf>,
+    /// The metadata of the state, see [`LlmpEventManager::with_state_metadata`]
+    metadata: Option<StateMetadataFn<S>>,
     phantom: PhantomData<(I, OT, S)>,
 }
 
@@ -273,8 +622,22 @@ where
         Ok(Self {
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: StdCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            batch: vec![],
+            batch_len: 0,
+            batch_threshold: 0,
+            sharing: TestcaseSharing::Always,
+            rand: StdRand::with_seed(current_nanos()),
+            #[cfg(feature = "std")]
+            last_sync: current_time(),
+            #[cfg(feature = "std")]
+            node_id: Uuid::new_v4().to_simple().to_string(),
+            #[cfg(feature = "std")]
+            written: vec![],
+            #[cfg(feature = "std")]
+            synced: HashSet::new(),
+            metadata: None,
             phantom: PhantomData,
         })
     }
@@ -288,13 +651,10 @@ where
         port: u16,
         configuration: EventConfig,
     ) -> Result<Self, Error> {
-        Ok(Self {
-            llmp: llmp::LlmpClient::create_attach_to_tcp(shmem_provider, port)?,
-            #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+        Self::new(
+            llmp::LlmpClient::create_attach_to_tcp(shmem_provider, port)?,
             configuration,
-            phantom: PhantomData,
-        })
+        )
     }
 
     /// If a client respawns, it may reuse the existing connection, previously stored by [`LlmpClient::to_env()`].
@@ -304,13 +664,183 @@ where
         env_name: &str,
         configuration: EventConfig,
     ) -> Result<Self, Error> {
-        Ok(Self {
-            llmp: LlmpClient::on_existing_from_env(shmem_provider, env_name)?,
-            #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+        Self::new(
+            LlmpClient::on_existing_from_env(shmem_provider, env_name)?,
             configuration,
-            phantom: PhantomData,
-        })
+        )
+    }
+
+    /// Batches the events, and sends them as one message once they take `threshold` bytes.
+    /// The batch also leaves on each call to [`EventProcessor::process`], so no event waits long.
+    #[must_use]
+    pub fn with_batching(mut self, threshold: usize) -> Self {
+        self.batch_threshold = threshold;
+        self
+    }
+
+    /// Sets when to share the new testcases with the other nodes, see [`TestcaseSharing`]
+    #[must_use]
+    pub fn with_testcase_sharing(mut self, sharing: TestcaseSharing) -> Self {
+        self.sharing = sharing;
+        self
+    }
+
+    /// When the new testcases are shared with the other nodes
+    #[must_use]
+    pub fn testcase_sharing(&self) -> &TestcaseSharing {
+        &self.sharing
+    }
+
+    /// Lets this manager keep its metadata in the state: the purge requests of the other clients
+    /// for the [`crate::stages::CorpusPruningStage`], the received testcases for a recorded
+    /// [`ReplayLogMetadata`], and the [`CorpusDirSyncMetadata`] surviving restarts.
+    /// Without it, purge requests are ignored and received testcases are not recorded.
+    #[must_use]
+    pub fn with_state_metadata(mut self) -> Self
+    where
+        S: HasMetadata,
+    {
+        self.metadata = Some(StateMetadataFn::new());
+        self
+    }
+
+    /// If the next new testcase should be shared with the other nodes
+    #[allow(clippy::cast_precision_loss)]
+    fn should_share(&mut self) -> bool {
+        match self.sharing {
+            TestcaseSharing::Always => true,
+            TestcaseSharing::Probabilistic(probability) => {
+                (self.rand.next() as f64) < probability * (u64::MAX as f64)
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes a testcase to the [`TestcaseSharing::CorpusDirSync`] directory, if any
+    #[cfg(feature = "std")]
+    fn write_to_sync_dir(&mut self, input: &I) -> Result<(), Error> {
+        if let TestcaseSharing::CorpusDirSync { dir, .. } = &self.sharing {
+            let filename = format!("{}-{:016x}", self.node_id, input_hash(input)?);
+            // Write to a hidden file first, so the others never import a partial testcase
+            let partial = dir.join(format!(".{}", filename));
+            let path = dir.join(filename);
+            input.to_file(&partial)?;
+            fs::rename(partial, &path)?;
+            self.written.push(path);
+        }
+        Ok(())
+    }
+
+    /// Imports the testcases of the other nodes from the [`TestcaseSharing::CorpusDirSync`] directory,
+    /// if the interval has passed
+    #[cfg(feature = "std")]
+    fn sync_from_dir<E, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+    ) -> Result<usize, Error>
+    where
+        OT: ObserversTuple<I, S> + DeserializeOwned,
+        E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+        Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+    {
+        let (dir, interval) = match &self.sharing {
+            TestcaseSharing::CorpusDirSync { dir, interval } => (dir.clone(), *interval),
+            _ => return Ok(0),
+        };
+        let written: Vec<PathBuf> = self.written.drain(..).collect();
+        self.synced_files(state).extend(written);
+        if current_time().saturating_sub(self.last_sync) < interval {
+            return Ok(0);
+        }
+        self.last_sync = current_time();
+
+        let own_prefix = format!("{}-", self.node_id);
+        let mut count = 0;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            if name.starts_with('.') || name.starts_with(&own_prefix) {
+                continue;
+            }
+            let synced = self.synced_files(state);
+            if synced.contains(&path) {
+                continue;
+            }
+            let input = I::from_file(&path);
+            synced.insert(path);
+            // The file may belong to something else, skip it
+            let input = match input {
+                Ok(input) => input,
+                Err(_) => continue,
+            };
+            self.record_received(state, &input)?;
+            fuzzer.evaluate_input_with_observers(state, executor, self, input, false)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// The files of the [`TestcaseSharing::CorpusDirSync`] directory synced so far,
+    /// in the [`CorpusDirSyncMetadata`] of the state if this manager has access to it
+    #[cfg(feature = "std")]
+    fn synced_files<'a>(&'a mut self, state: &'a mut S) -> &'a mut HashSet<PathBuf> {
+        match &self.metadata {
+            Some(metadata) => {
+                let metadata = metadata.get(state);
+                if !metadata.contains::<CorpusDirSyncMetadata>() {
+                    metadata.insert(CorpusDirSyncMetadata::default());
+                }
+                &mut metadata.get_mut::<CorpusDirSyncMetadata>().unwrap().synced
+            }
+            None => &mut self.synced,
+        }
+    }
+
+    /// Records a testcase received from another client, if the state records a session
+    fn record_received(&self, state: &mut S, input: &I) -> Result<(), Error> {
+        match &self.metadata {
+            Some(metadata) => ReplayLogMetadata::record_received(metadata.get(state), input),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends the events batched so far
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let serialized = postcard::to_allocvec(&self.batch)?;
+        self.batch.clear();
+        self.batch_len = 0;
+        self.send_serialized(LLMP_TAG_EVENT_BATCH, &serialized)
+    }
+
+    /// Sends a serialized message, compressed if it is large enough
+    #[cfg(feature = "llmp_compression")]
+    fn send_serialized(&mut self, tag: Tag, serialized: &[u8]) -> Result<(), Error> {
+        let flags: Flags = LLMP_FLAG_INITIALIZED;
+
+        match self.compressor.compress(serialized)? {
+            Some(comp_buf) => {
+                self.llmp
+                    .send_buf_with_flags(tag, flags | LLMP_FLAG_COMPRESSED, &comp_buf)?;
+            }
+            None => {
+                self.llmp.send_buf(tag, serialized)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends a serialized message
+    #[cfg(not(feature = "llmp_compression"))]
+    fn send_serialized(&mut self, tag: Tag, serialized: &[u8]) -> Result<(), Error> {
+        self.llmp.send_buf(tag, serialized)
     }
 
     /// Describe the client event mgr's llmp parts in a restorable fashion
@@ -324,13 +854,10 @@ where
         description: &LlmpClientDescription,
         configuration: EventConfig,
     ) -> Result<Self, Error> {
-        Ok(Self {
-            llmp: llmp::LlmpClient::existing_client_from_description(shmem_provider, description)?,
-            #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+        Self::new(
+            llmp::LlmpClient::existing_client_from_description(shmem_provider, description)?,
             configuration,
-            phantom: PhantomData,
-        })
+        )
     }
 
     /// Write the config for a client [`EventManager`] to env vars, a new client can reattach using [`LlmpEventManager::existing_client_from_env()`].
@@ -363,13 +890,19 @@ where
                 observers_buf,
                 time: _,
                 executions: _,
+                forward_id,
             } => {
+                // The testcase came back to the node that found it
+                if forward_id == Some(self.llmp.sender.id) {
+                    return Ok(());
+                }
                 #[cfg(feature = "std")]
                 println!(
                     "Received new Testcase from {} ({:?})",
                     _client_id, client_config
                 );
 
+                self.record_received(state, &input)?;
                 let _res = if client_config.match_with(&self.configuration)
                     && observers_buf.is_some()
                 {
@@ -384,6 +917,16 @@ where
                 }
                 Ok(())
             }
+            Event::PurgeTestcases {
+                input_hashes,
+                phantom: _,
+            } => {
+                // Purged by the next corpus pruning run, which has the scheduler at hand
+                if let Some(metadata) = &self.metadata {
+                    CorpusAgingMetadata::request_purge(metadata.get(state), input_hashes);
+                }
+                Ok(())
+            }
             _ => Err(Error::Unknown(format!(
                 "Received illegal message that message should not have arrived: {:?}.",
                 event.name()
@@ -399,30 +942,28 @@ where
     SP: ShMemProvider,
     //CE: CustomEvent<I>,
 {
-    #[cfg(feature = "llmp_compression")]
     fn fire<S2>(&mut self, _state: &mut S2, event: Event<I>) -> Result<(), Error> {
-        let serialized = postcard::to_allocvec(&event)?;
-        let flags: Flags = LLMP_FLAG_INITIALIZED;
-
-        match self.compressor.compress(&serialized)? {
-            Some(comp_buf) => {
-                self.llmp.send_buf_with_flags(
-                    LLMP_TAG_EVENT_TO_BOTH,
-                    flags | LLMP_FLAG_COMPRESSED,
-                    &comp_buf,
-                )?;
-            }
-            None => {
-                self.llmp.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
+        if let Event::NewTestcase { input, .. } = &event {
+            if !self.should_share() {
+                #[cfg(feature = "std")]
+                self.write_to_sync_dir(input)?;
+                #[cfg(not(feature = "std"))]
+                let _ = input;
+                // Keep the order of the events, then send the testcase to the monitor only
+                self.flush()?;
+                let serialized = postcard::to_allocvec(&event)?;
+                return self.send_serialized(LLMP_TAG_EVENT_TO_BROKER, &serialized);
             }
         }
-        Ok(())
-    }
-
-    #[cfg(not(feature = "llmp_compression"))]
-    fn fire<S2>(&mut self, _state: &mut S2, event: Event<I>) -> Result<(), Error> {
         let serialized = postcard::to_allocvec(&event)?;
-        self.llmp.send_buf(LLMP_TAG_EVENT_TO_BOTH, &serialized)?;
+        if self.batch_threshold == 0 {
+            return self.send_serialized(LLMP_TAG_EVENT_TO_BOTH, &serialized);
+        }
+        self.batch_len += serialized.len();
+        self.batch.push(serialized);
+        if self.batch_len >= self.batch_threshold {
+            self.flush()?;
+        }
         Ok(())
     }
 
@@ -441,9 +982,22 @@ where
     /// The llmp client needs to wait until a broker mapped all pages, before shutting down.
     /// Otherwise, the OS may already have removed the shared maps,
     fn await_restart_safe(&mut self) {
+        // The batched events would get lost, else
+        if let Err(e) = self.flush() {
+            #[cfg(feature = "std")]
+            println!("Could not send the batched events: {:?}", e);
+            #[cfg(not(feature = "std"))]
+            let _ = e;
+        }
         // wait until we can drop the message safely.
         self.llmp.await_safe_to_unmap_blocking();
     }
+
+    /// Tells the broker this client is done, see [`LlmpEventBroker::set_exit_cleanly_after`]
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.flush()?;
+        self.llmp.send_exiting()
+    }
 }
 
 impl<E, I, OT, S, SP, Z> EventProcessor<E, I, S, Z> for LlmpEventManager<I, OT, S, SP>
@@ -455,16 +1009,18 @@ where
     Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>, //CE: CustomEvent<I>,
 {
     fn process(&mut self, fuzzer: &mut Z, state: &mut S, executor: &mut E) -> Result<usize, Error> {
+        self.flush()?;
         // TODO: Get around local event copy by moving handle_in_client
         let mut events = vec![];
         let self_id = self.llmp.sender.id;
         while let Some((client_id, tag, _flags, msg)) = self.llmp.recv_buf_with_flags()? {
             assert!(
-                tag != _LLMP_TAG_EVENT_TO_BROKER,
+                tag != LLMP_TAG_EVENT_TO_BROKER,
                 "EVENT_TO_BROKER parcel should not have arrived in the client!"
             );
 
-            if client_id == self_id {
+            // The stats summaries are for the other brokers
+            if client_id == self_id || tag == LLMP_TAG_B2B_STATS {
                 continue;
             }
             #[cfg(not(feature = "llmp_compression"))]
@@ -478,13 +1034,30 @@ where
             } else {
                 msg
             };
-            let event: Event<I> = postcard::from_bytes(event_bytes)?;
-            events.push((client_id, event));
+            if tag == LLMP_TAG_EVENT_BATCH {
+                // Batches may also hold events only meant for the broker
+                events.extend(
+                    unpack_events::<I>(tag, event_bytes)?
+                        .into_iter()
+                        .filter(|event| {
+                            matches!(
+                                event,
+                                Event::NewTestcase { .. } | Event::PurgeTestcases { .. }
+                            )
+                        })
+                        .map(|event| (client_id, event)),
+                );
+            } else {
+                let event: Event<I> = postcard::from_bytes(event_bytes)?;
+                events.push((client_id, event));
+            }
         }
         let count = events.len();
         events.drain(..).try_for_each(|(client_id, event)| {
             self.handle_in_client(fuzzer, executor, state, client_id, event)
         })?;
+        #[cfg(feature = "std")]
+        let count = count + self.sync_from_dir(fuzzer, executor, state)?;
         Ok(count)
     }
 }
@@ -583,11 +1156,18 @@ where
 
     /// Reset the single page (we reuse it over and over from pos 0), then send the current state to the next runner.
     fn on_restart(&mut self, state: &mut S) -> Result<(), Error> {
+        self.llmp_mgr.flush()?;
         // First, reset the page to 0 so the next iteration can read read from the beginning of this page
         self.staterestorer.reset();
         self.staterestorer
             .save(&(state, &self.llmp_mgr.describe()?))
     }
+
+    /// Tells the respawner not to restart this client, and the broker that it is done
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.staterestorer.send_exiting();
+        self.llmp_mgr.send_exiting()
+    }
 }
 
 #[cfg(feature = "std")]
@@ -653,6 +1233,16 @@ where
         }
     }
 
+    /// Lets the manager keep its metadata in the state, see [`LlmpEventManager::with_state_metadata`]
+    #[must_use]
+    pub fn with_state_metadata(mut self) -> Self
+    where
+        S: HasMetadata,
+    {
+        self.llmp_mgr = self.llmp_mgr.with_state_metadata();
+        self
+    }
+
     /// Get the staterestorer
     pub fn staterestorer(&self) -> &StateRestorer<SP> {
         &self.staterestorer
@@ -743,6 +1333,12 @@ where
     /// The type of manager to build
     #[builder(default = ManagerKind::Any)]
     kind: ManagerKind,
+    /// When the clients share their new testcases
+    #[builder(default = TestcaseSharing::Always)]
+    testcase_sharing: TestcaseSharing,
+    /// The broker exits once this many clients exited cleanly
+    #[builder(default = None)]
+    exit_cleanly_after: Option<NonZeroUsize>,
     #[builder(setter(skip), default = PhantomData)]
     phantom_data: PhantomData<(I, OT, S)>,
 }
@@ -762,10 +1358,14 @@ where
         &mut self,
     ) -> Result<(Option<S>, LlmpRestartingEventManager<I, OT, S, SP>), Error> {
         // We start ourself as child process to actually fuzz
-        let (staterestorer, new_shmem_provider, core_id) = if std::env::var(_ENV_FUZZER_SENDER)
+        let (mut staterestorer, new_shmem_provider, core_id) = if std::env::var(_ENV_FUZZER_SENDER)
             .is_err()
         {
+            let exit_cleanly_after = self.exit_cleanly_after;
             let broker_things = |mut broker: LlmpEventBroker<I, MT, SP>, remote_broker_addr| {
+                if let Some(n_clients) = exit_cleanly_after {
+                    broker.set_exit_cleanly_after(n_clients);
+                }
                 if let Some(remote_broker_addr) = remote_broker_addr {
                     println!("B2b: Connecting to {:?}", &remote_broker_addr);
                     broker.connect_b2b(remote_broker_addr)?;
@@ -834,9 +1434,13 @@ where
             mgr.to_env(_ENV_FUZZER_BROKER_CLIENT_INITIAL);
 
             // First, create a channel from the current fuzzer to the next to store state between restarts.
-            let staterestorer: StateRestorer<SP> =
-                StateRestorer::new(self.shmem_provider.new_shmem(256 * 1024 * 1024)?);
-            // Store the information to a map.
+            // It grows with the state.
+            let mut staterestorer: StateRestorer<SP> = StateRestorer::new(
+                self.shmem_provider
+                    .new_shmem(STATE_RESTORER_INITIAL_MAP_SIZE)?,
+            );
+            // A forked client takes it along, a spawned one finds it in the env
+            #[cfg(any(windows, not(feature = "fork")))]
             staterestorer.write_to_env(_ENV_FUZZER_SENDER)?;
 
             let mut ctr: u64 = 0;
@@ -868,6 +1472,12 @@ where
 
                 compiler_fence(Ordering::SeqCst);
 
+                // The client is done, do not restart it
+                if staterestorer.is_exiting() {
+                    println!("Fuzzer-respawner: The client exited cleanly.");
+                    return Err(Error::ShuttingDown);
+                }
+
                 #[allow(clippy::manual_assert)]
                 if !staterestorer.has_content() {
                     #[cfg(unix)]
@@ -881,6 +1491,12 @@ where
                     panic!("Fuzzer-respawner: Storing state in crashed fuzzer instance did not work, no point to spawn the next client! This can happen if the child calls `exit()`, in that case make sure it uses `abort()`, if it got killed unrecoverable (OOM), or if there is a bug in the fuzzer itself. (Child exited with: {})", child_status);
                 }
 
+                // The state did not fit, move it to a larger map for the next client
+                if staterestorer.grow(&mut self.shmem_provider)? {
+                    #[cfg(any(windows, not(feature = "fork")))]
+                    staterestorer.write_to_env(_ENV_FUZZER_SENDER)?;
+                }
+
                 ctr = ctr.wrapping_add(1);
             }
         } else {
@@ -899,29 +1515,33 @@ where
         }
 
         // If we're restarting, deserialize the old state.
-        let (state, mut mgr) = if let Some((state, mgr_description)) = staterestorer.restore()? {
-            (
-                Some(state),
-                LlmpRestartingEventManager::new(
-                    LlmpEventManager::existing_client_from_description(
-                        new_shmem_provider,
-                        &mgr_description,
-                        self.configuration,
-                    )?,
-                    staterestorer,
-                ),
-            )
-        } else {
-            println!("First run. Let's set it all up");
-            // Mgr to send and receive msgs from/to all other fuzzer instances
-            let mgr = LlmpEventManager::<I, OT, S, SP>::existing_client_from_env(
-                new_shmem_provider,
-                _ENV_FUZZER_BROKER_CLIENT_INITIAL,
-                self.configuration,
-            )?;
-
-            (None, LlmpRestartingEventManager::new(mgr, staterestorer))
-        };
+        // A corrupted state is discarded, and we start fresh.
+        let (state, mut mgr) =
+            if let Some((state, mgr_description)) = staterestorer.restore_or_reset() {
+                (
+                    Some(state),
+                    LlmpRestartingEventManager::new(
+                        LlmpEventManager::existing_client_from_description(
+                            new_shmem_provider,
+                            &mgr_description,
+                            self.configuration,
+                        )?
+                        .with_testcase_sharing(self.testcase_sharing.clone()),
+                        staterestorer,
+                    ),
+                )
+            } else {
+                println!("First run. Let's set it all up");
+                // Mgr to send and receive msgs from/to all other fuzzer instances
+                let mgr = LlmpEventManager::<I, OT, S, SP>::existing_client_from_env(
+                    new_shmem_provider,
+                    _ENV_FUZZER_BROKER_CLIENT_INITIAL,
+                    self.configuration,
+                )?
+                .with_testcase_sharing(self.testcase_sharing.clone());
+
+                (None, LlmpRestartingEventManager::new(mgr, staterestorer))
+            };
         // We reset the staterestorer, the next staterestorer and receiver (after crash) will reuse the page from the initial message.
         mgr.staterestorer.reset();
 
@@ -948,17 +1568,31 @@ mod tests {
             staterestore::StateRestorer,
             tuples::tuple_list,
         },
-        corpus::{Corpus, InMemoryCorpus, Testcase},
-        events::{llmp::_ENV_FUZZER_SENDER, LlmpEventManager},
+        corpus::{Corpus, CorpusAgingMetadata, InMemoryCorpus, Testcase},
+        events::{
+            llmp::{
+                unpack_events, LlmpEventBroker, _ENV_FUZZER_SENDER, LLMP_TAG_B2B_STATS,
+                LLMP_TAG_EVENT_BATCH, LLMP_TAG_EVENT_TO_BOTH, LLMP_TAG_EVENT_TO_BROKER,
+            },
+            CorpusDirSyncMetadata, Event, EventFirer, EventProcessor, LlmpEventManager,
+            TestcaseSharing,
+        },
         executors::{ExitKind, InProcessExecutor},
+        feedbacks::CrashFeedback,
         inputs::BytesInput,
+        monitors::{Monitor, NopMonitor},
         mutators::BitFlipMutator,
         schedulers::RandScheduler,
         stages::StdMutationalStage,
-        state::StdState,
+        state::{HasMetadata, StdState},
         Fuzzer, StdFuzzer,
     };
-    use core::sync::atomic::{compiler_fence, Ordering};
+    use core::{
+        marker::PhantomData,
+        sync::atomic::{compiler_fence, Ordering},
+        time::Duration,
+    };
+    use std::{env, fs, process};
 
     #[test]
     #[serial]
@@ -1044,4 +1678,237 @@ mod tests {
                 .unwrap();
         }
     }
+
+    type TestMgr = LlmpEventManager<BytesInput, (), TestState, StdShMemProvider>;
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// A manager on its own map, without a broker
+    fn test_mgr(sharing: TestcaseSharing) -> TestMgr {
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let mut llmp_client = LlmpClient::new(
+            shmem_provider.clone(),
+            LlmpSharedMap::new(0, shmem_provider.new_shmem(1024).unwrap()),
+        )
+        .unwrap();
+        unsafe {
+            llmp_client.mark_safe_to_unmap();
+        }
+        LlmpEventManager::new(llmp_client, "fuzzer".into())
+            .unwrap()
+            .with_testcase_sharing(sharing)
+            .with_state_metadata()
+    }
+
+    fn new_testcase(input: &[u8]) -> Event<BytesInput> {
+        Event::NewTestcase {
+            input: BytesInput::new(input.to_vec()),
+            observers_buf: None,
+            exit_kind: ExitKind::Ok,
+            corpus_size: 1,
+            client_config: "fuzzer".into(),
+            time: Duration::ZERO,
+            executions: 0,
+            forward_id: None,
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_testcase_sharing() {
+        assert!(test_mgr(TestcaseSharing::Always).should_share());
+        assert!(!test_mgr(TestcaseSharing::ObjectivesOnly).should_share());
+        assert!(!test_mgr(TestcaseSharing::Probabilistic(0.0)).should_share());
+        assert!(test_mgr(TestcaseSharing::Probabilistic(1.0)).should_share());
+    }
+
+    #[test]
+    #[serial]
+    fn test_corpus_dir_sync() {
+        let dir = env::temp_dir().join(format!("libafl_test_corpus_dir_sync_{}", process::id()));
+        drop(fs::remove_dir_all(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        let sharing = TestcaseSharing::CorpusDirSync {
+            dir: dir.clone(),
+            interval: Duration::ZERO,
+        };
+
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), (), ());
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut mgr = test_mgr(sharing.clone());
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        // Another node on the same dir, in the same process
+        let mut other = test_mgr(sharing.clone());
+        other.fire(&mut state, new_testcase(b"other")).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // The own files are skipped, even after a restart
+        mgr.fire(&mut state, new_testcase(b"own")).unwrap();
+        assert_eq!(
+            mgr.process(&mut fuzzer, &mut state, &mut executor).unwrap(),
+            1
+        );
+        assert_eq!(
+            mgr.process(&mut fuzzer, &mut state, &mut executor).unwrap(),
+            0
+        );
+        let mut restarted = test_mgr(sharing);
+        assert_eq!(
+            restarted
+                .process(&mut fuzzer, &mut state, &mut executor)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            state
+                .metadata()
+                .get::<CorpusDirSyncMetadata>()
+                .unwrap()
+                .synced
+                .len(),
+            2
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_forwarded_testcase() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), CrashFeedback::new(), ());
+        let mut harness = |_buf: &BytesInput| ExitKind::Crash;
+        let mut mgr = test_mgr(TestcaseSharing::Always);
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        // A testcase found by this client, forwarded back to it by another node, is skipped
+        let mut own = new_testcase(b"own");
+        if let Event::NewTestcase { forward_id, .. } = &mut own {
+            *forward_id = Some(mgr.llmp.sender.id);
+        }
+        mgr.handle_in_client(&mut fuzzer, &mut executor, &mut state, 1, own)
+            .unwrap();
+        assert_eq!(state.corpus().count(), 0);
+
+        mgr.handle_in_client(
+            &mut fuzzer,
+            &mut executor,
+            &mut state,
+            1,
+            new_testcase(b"other"),
+        )
+        .unwrap();
+        assert_eq!(state.corpus().count(), 1);
+    }
+
+    #[test]
+    #[serial]
+    fn test_purge_requests() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), (), ());
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut mgr = test_mgr(TestcaseSharing::Always);
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+        let purge = || Event::PurgeTestcases {
+            input_hashes: vec![1337],
+            phantom: PhantomData,
+        };
+
+        mgr.handle_in_client(&mut fuzzer, &mut executor, &mut state, 1, purge())
+            .unwrap();
+        assert_eq!(
+            state
+                .metadata()
+                .get::<CorpusAgingMetadata>()
+                .unwrap()
+                .purge_requests,
+            vec![1337]
+        );
+
+        // Without access to the metadata, the requests are ignored
+        drop(state.metadata_mut().remove::<CorpusAgingMetadata>());
+        mgr.metadata = None;
+        mgr.handle_in_client(&mut fuzzer, &mut executor, &mut state, 1, purge())
+            .unwrap();
+        assert!(!state.has_metadata::<CorpusAgingMetadata>());
+    }
+
+    type TestBroker = LlmpEventBroker<BytesInput, NopMonitor, StdShMemProvider>;
+
+    #[test]
+    fn test_b2b_filter() {
+        // Only the tag matters, the payload is never looked at
+        assert!(TestBroker::forward_to_broker(
+            LLMP_TAG_EVENT_TO_BOTH,
+            0,
+            &[]
+        ));
+        assert!(TestBroker::forward_to_broker(LLMP_TAG_EVENT_BATCH, 0, &[]));
+        assert!(TestBroker::forward_to_broker(LLMP_TAG_B2B_STATS, 0, &[]));
+        assert!(!TestBroker::forward_to_broker(
+            LLMP_TAG_EVENT_TO_BROKER,
+            0,
+            &[]
+        ));
+        assert!(!TestBroker::forward_to_broker(0x1234, 0, &[]));
+    }
+
+    #[test]
+    fn test_b2b_stats() {
+        let mut monitor = NopMonitor::new();
+        monitor.client_stats_mut_for(1).executions = 100;
+        monitor.client_stats_mut_for(1).objective_size = 1;
+        monitor.client_stats_mut_for(2).executions = 1000;
+        monitor.client_stats_mut_for(2).objective_size = 10;
+        monitor.client_stats_mut_for(3).executions = 50;
+
+        // Client 2 is another broker, its stats are not sent back
+        let summary = TestBroker::b2b_stats(&monitor, &[2]).unwrap();
+        let events = unpack_events::<BytesInput>(LLMP_TAG_B2B_STATS, &summary).unwrap();
+        assert!(matches!(
+            events[0],
+            Event::UpdateExecStats {
+                executions: 150,
+                ..
+            }
+        ));
+        assert!(matches!(events[1], Event::Objective { objective_size: 1 }));
+    }
 }
@@ -4,6 +4,18 @@ pub mod simple;
 pub use simple::*;
 pub mod llmp;
 pub use llmp::*;
+pub mod centralized;
+pub use centralized::{CentralizedEventManager, CentralizedLlmpEventBroker};
+#[cfg(feature = "std")]
+pub mod tcp;
+#[cfg(feature = "std")]
+pub use tcp::{
+    setup_restarting_mgr_tcp, TcpEventBroker, TcpEventManager, TcpRestartingEventManager,
+};
+pub mod broker_hooks;
+pub use broker_hooks::{
+    EventBrokerHook, EventBrokerHooksTuple, EventHookResult, MaxInputSizeBrokerHook,
+};
 
 use ahash::AHasher;
 use alloc::{
@@ -17,12 +29,12 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    bolts::current_time,
+    bolts::{current_time, serdeany::SerdeAnyMap},
     executors::ExitKind,
     inputs::Input,
-    monitors::UserStats,
+    monitors::{UserStats, UserStatsTracker},
     observers::ObserversTuple,
-    state::{HasClientPerfMonitor, HasExecutions},
+    state::{HasClientPerfMonitor, HasExecutions, HasMetadata},
     Error,
 };
 
@@ -185,6 +197,9 @@ where
         time: Duration,
         /// The executions of this client
         executions: usize,
+        /// The llmp client id of the node that found the testcase, if another node forwards it,
+        /// see [`centralized::CentralizedEventManager`]
+        forward_id: Option<u32>,
     },
     /// New stats event to monitor.
     UpdateExecStats {
@@ -222,6 +237,20 @@ where
         /// Objective corpus size
         objective_size: usize,
     },
+    /// The client pruned testcases from its corpus, the others can purge them too
+    PurgeTestcases {
+        /// The hashes of the pruned inputs, see [`crate::corpus::input_hash`]
+        input_hashes: Vec<u64>,
+        /// [`PhantomData`]
+        phantom: PhantomData<I>,
+    },
+    /// The client announces its name, to tell it apart in the monitor and the logs
+    ClientName {
+        /// The name of the client
+        name: String,
+        /// [`PhantomData`]
+        phantom: PhantomData<I>,
+    },
     /// Write a new log
     Log {
         /// the severity level
@@ -252,6 +281,7 @@ where
                 observers_buf: _,
                 time: _,
                 executions: _,
+                forward_id: _,
             } => "Testcase",
             Event::UpdateExecStats {
                 time: _,
@@ -271,6 +301,14 @@ where
                 phantom: _,
             } => "PerfMonitor",
             Event::Objective { objective_size: _ } => "Objective",
+            Event::PurgeTestcases {
+                input_hashes: _,
+                phantom: _,
+            } => "Purge",
+            Event::ClientName {
+                name: _,
+                phantom: _,
+            } => "Name",
             Event::Log {
                 severity_level: _,
                 message: _,
@@ -316,6 +354,18 @@ where
         )
     }
 
+    /// Send off an [`Event::ClientName`] event to the broker, naming this client in the monitor.
+    /// The broker keeps the name across restarts of the client.
+    fn set_client_name<S>(&mut self, state: &mut S, name: String) -> Result<(), Error> {
+        self.fire(
+            state,
+            Event::ClientName {
+                name,
+                phantom: PhantomData,
+            },
+        )
+    }
+
     /// Serialize all observers for this type and manager
     fn serialize_observers<OT, S>(&mut self, observers: &OT) -> Result<Vec<u8>, Error>
     where
@@ -374,6 +424,21 @@ where
                 )?;
             }
 
+            let updates = state
+                .user_stats_mut()
+                .map(UserStatsTracker::take_updates)
+                .unwrap_or_default();
+            for (name, value) in updates {
+                self.fire(
+                    state,
+                    Event::UpdateUserStats {
+                        name,
+                        value,
+                        phantom: PhantomData,
+                    },
+                )?;
+            }
+
             // If performance monitor are requested, fire the `UpdatePerfMonitor` event
             #[cfg(feature = "introspection")]
             {
@@ -410,6 +475,13 @@ pub trait EventRestarter<S> {
         Ok(())
     }
 
+    /// Send information that this client is exiting cleanly.
+    /// A restarting event manager will not restart it, and no state is saved.
+    #[inline]
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Block until we are safe to exit.
     #[inline]
     fn await_restart_safe(&mut self) {}
@@ -451,6 +523,39 @@ where
 {
 }
 
+/// Gets the [`SerdeAnyMap`] of a [`HasMetadata`] state, for the managers generic over any state,
+/// see e.g. [`LlmpEventManager::with_state_metadata`]
+pub(crate) struct StateMetadataFn<S>(fn(&mut S) -> &mut SerdeAnyMap);
+
+impl<S> StateMetadataFn<S>
+where
+    S: HasMetadata,
+{
+    pub(crate) fn new() -> Self {
+        Self(metadata_of::<S>)
+    }
+}
+
+impl<S> StateMetadataFn<S> {
+    /// The metadata of the `state`
+    pub(crate) fn get<'a>(&self, state: &'a mut S) -> &'a mut SerdeAnyMap {
+        (self.0)(state)
+    }
+}
+
+impl<S> fmt::Debug for StateMetadataFn<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StateMetadataFn")
+    }
+}
+
+fn metadata_of<S>(state: &mut S) -> &mut SerdeAnyMap
+where
+    S: HasMetadata,
+{
+    state.metadata_mut()
+}
+
 /// An eventmgr for tests, and as placeholder if you really don't need an event manager.
 #[derive(Copy, Clone, Debug)]
 pub struct NopEventManager {}
@@ -520,6 +625,7 @@ mod tests {
             client_config: EventConfig::AlwaysUnique,
             time: current_time(),
             executions: 0,
+            forward_id: None,
         };
 
         let serialized = postcard::to_allocvec(&e).unwrap();
@@ -534,6 +640,7 @@ mod tests {
                 client_config: _,
                 time: _,
                 executions: _,
+                forward_id: _,
             } => {
                 let o: tuple_list_type!(StdMapObserver::<u32>) =
                     postcard::from_bytes(observers_buf.as_ref().unwrap()).unwrap();
@@ -21,7 +21,10 @@ use crate::bolts::os::startable_self;
 use crate::bolts::os::{fork, ForkResult};
 #[cfg(feature = "std")]
 use crate::{
-    bolts::{shmem::ShMemProvider, staterestore::StateRestorer},
+    bolts::{
+        shmem::ShMemProvider,
+        staterestore::{StateRestorer, STATE_RESTORER_INITIAL_MAP_SIZE},
+    },
     corpus::Corpus,
     state::{HasCorpus, HasSolutions},
 };
@@ -137,6 +140,7 @@ where
                 observers_buf: _,
                 time,
                 executions,
+                forward_id: _,
             } => {
                 monitor
                     .client_stats_mut_for(0)
@@ -144,6 +148,7 @@ where
                 monitor
                     .client_stats_mut_for(0)
                     .update_executions(*executions as u64, *time);
+                monitor.client_stats_mut_for(0).add_corpus_contribution();
                 monitor.display(event.name().to_string(), 0);
                 Ok(BrokerEventResult::Handled)
             }
@@ -192,6 +197,15 @@ where
                 monitor.display(event.name().to_string(), 0);
                 Ok(BrokerEventResult::Handled)
             }
+            // There are no other clients to purge the testcases
+            Event::PurgeTestcases {
+                input_hashes: _,
+                phantom: _,
+            } => Ok(BrokerEventResult::Handled),
+            Event::ClientName { name, phantom: _ } => {
+                monitor.client_stats_mut_for(0).update_name(name.clone());
+                Ok(BrokerEventResult::Handled)
+            }
             Event::Log {
                 severity_level,
                 message,
@@ -259,6 +273,11 @@ where
         self.staterestorer.reset();
         self.staterestorer.save(state)
     }
+
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.staterestorer.send_exiting();
+        Ok(())
+    }
 }
 
 #[cfg(feature = "std")]
@@ -331,10 +350,11 @@ where
     {
         // We start ourself as child process to actually fuzz
         let mut staterestorer = if std::env::var(_ENV_FUZZER_SENDER).is_err() {
-            // First, create a place to store state in, for restarts.
-            let staterestorer: StateRestorer<SP> =
-                StateRestorer::new(shmem_provider.new_shmem(256 * 1024 * 1024)?);
-            //let staterestorer = { LlmpSender::new(shmem_provider.clone(), 0, false)? };
+            // First, create a place to store state in, for restarts. It grows with the state.
+            let mut staterestorer: StateRestorer<SP> =
+                StateRestorer::new(shmem_provider.new_shmem(STATE_RESTORER_INITIAL_MAP_SIZE)?);
+            // A forked client takes it along, a spawned one finds it in the env
+            #[cfg(any(windows, not(feature = "fork")))]
             staterestorer.write_to_env(_ENV_FUZZER_SENDER)?;
 
             let mut ctr: u64 = 0;
@@ -366,6 +386,12 @@ where
 
                 compiler_fence(Ordering::SeqCst);
 
+                // The client is done, do not restart it
+                if staterestorer.is_exiting() {
+                    println!("Fuzzer-respawner: The client exited cleanly.");
+                    return Err(Error::ShuttingDown);
+                }
+
                 #[allow(clippy::manual_assert)]
                 if !staterestorer.has_content() {
                     #[cfg(unix)]
@@ -379,6 +405,12 @@ where
                     panic!("Fuzzer-respawner: Storing state in crashed fuzzer instance did not work, no point to spawn the next client! This can happen if the child calls `exit()`, in that case make sure it uses `abort()`, if it got killed unrecoverable (OOM), or if there is a bug in the fuzzer itself. (Child exited with: {})", child_status);
                 }
 
+                // The state did not fit, move it to a larger map for the next client
+                if staterestorer.grow(shmem_provider)? {
+                    #[cfg(any(windows, not(feature = "fork")))]
+                    staterestorer.write_to_env(_ENV_FUZZER_SENDER)?;
+                }
+
                 ctr = ctr.wrapping_add(1);
             }
         } else {
@@ -389,7 +421,8 @@ where
         };
 
         // If we're restarting, deserialize the old state.
-        let (state, mgr) = match staterestorer.restore::<S>()? {
+        // A corrupted state is discarded, and we start fresh.
+        let (state, mgr) = match staterestorer.restore_or_reset::<S>() {
             None => {
                 println!("First run. Let's set it all up");
                 // Mgr to send and receive msgs from/to all other fuzzer instances
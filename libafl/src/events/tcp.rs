@@ -0,0 +1,990 @@
+//! An event manager talking plain TCP to its broker, for environments without shared memory.
+//!
+//! Containers, seccomp-restricted processes, or some Windows setups may not be able to map the
+//! shared maps of [`crate::bolts::llmp`]. The [`TcpEventBroker`] and [`TcpEventManager`] offer the
+//! same broker/client semantics as their llmp counterparts, but each client holds a single TCP
+//! connection to the broker, which forwards the events to all other clients.
+//! [`setup_restarting_mgr_tcp`] respawns crashed clients, handing their state over in a file.
+
+use alloc::{string::ToString, vec::Vec};
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
+use hashbrown::HashMap;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env, fs,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+#[cfg(any(windows, not(feature = "fork")))]
+use crate::bolts::os::startable_self;
+#[cfg(all(feature = "fork", unix))]
+use crate::bolts::os::{fork, ForkResult};
+use crate::{
+    bolts::{current_time, staterestore::checksum},
+    corpus::CorpusAgingMetadata,
+    events::{
+        BrokerEventResult, Event, EventConfig, EventFirer, EventManager, EventManagerId,
+        EventProcessor, EventRestarter, HasEventManagerId, ProgressReporter, StateMetadataFn,
+    },
+    executors::{Executor, HasObservers},
+    fuzzer::{EvaluatorObservers, ExecutionProcessor},
+    inputs::Input,
+    monitors::Monitor,
+    observers::ObserversTuple,
+    schedulers::ReplayLogMetadata,
+    state::HasMetadata,
+    Error,
+};
+
+/// The address [`TcpEventBroker::new_on_port`] binds to
+const TCP_BIND_ADDR: &str = "127.0.0.1";
+/// The address [`TcpEventManager::on_port`] connects to
+const TCP_CONNECT_ADDR: &str = "127.0.0.1";
+/// Sent in the handshake by a client without an id yet
+const TCP_NEW_CLIENT: u32 = u32::MAX;
+/// The time a new connection has for the handshake, before the broker drops it
+const TCP_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The frames the broker queues for a client, it drops the frames for a client lagging further behind
+const TCP_CLIENT_QUEUE_LEN: usize = 4096;
+
+/// Writes one frame: the `u32` len of the payload, the `u32` id of the client, and the payload
+fn write_frame<W>(stream: &mut W, client_id: u32, payload: &[u8]) -> Result<(), Error>
+where
+    W: Write,
+{
+    let len = u32::try_from(payload.len()).map_err(|_| {
+        Error::IllegalArgument(format!(
+            "Trying to send a tcp message > u32! (size: {})",
+            payload.len()
+        ))
+    })?;
+    let mut frame = Vec::with_capacity(payload.len() + 8);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(&client_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)?;
+    Ok(())
+}
+
+/// Reads one frame, see [`write_frame`]
+fn read_frame<R>(stream: &mut R) -> Result<(u32, Vec<u8>), Error>
+where
+    R: Read,
+{
+    let mut header = [0_u8; 8];
+    stream.read_exact(&mut header)?;
+    let len = u32::from_be_bytes(header[..4].try_into().unwrap());
+    let client_id = u32::from_be_bytes(header[4..].try_into().unwrap());
+    let mut payload = vec![0_u8; len as usize];
+    stream.read_exact(&mut payload)?;
+    Ok((client_id, payload))
+}
+
+/// Reads frames from `stream` into `sender`, until the connection or the channel closes
+fn spawn_reader(mut stream: TcpStream, sender: Sender<(u32, Vec<u8>)>) {
+    thread::spawn(move || {
+        while let Ok(frame) = read_frame(&mut stream) {
+            if sender.send(frame).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// A frame forwarded by the broker: the id of the sending client, and the payload
+type Frame = Arc<(u32, Vec<u8>)>;
+
+/// Does the handshake with a new client of the broker, then queues the frames it reads into `sender`.
+/// A writer thread sends the frames forwarded to this client, so a stopped client stalls nobody else.
+fn serve_client(
+    mut stream: TcpStream,
+    clients: &Mutex<HashMap<u32, SyncSender<Frame>>>,
+    next_id: &AtomicU32,
+    sender: &Sender<(u32, Vec<u8>)>,
+) -> Result<(), Error> {
+    // A restarted client asks for its old id again
+    stream.set_read_timeout(Some(TCP_HANDSHAKE_TIMEOUT))?;
+    let mut requested = [0_u8; 4];
+    stream.read_exact(&mut requested)?;
+    let client_id = match u32::from_be_bytes(requested) {
+        TCP_NEW_CLIENT => next_id.fetch_add(1, Ordering::SeqCst),
+        client_id => {
+            next_id.fetch_max(client_id.saturating_add(1), Ordering::SeqCst);
+            client_id
+        }
+    };
+    stream.write_all(&client_id.to_be_bytes())?;
+    stream.set_read_timeout(None)?;
+
+    let (queue, frames) = sync_channel::<Frame>(TCP_CLIENT_QUEUE_LEN);
+    let mut writer = stream.try_clone()?;
+    thread::spawn(move || {
+        for frame in frames {
+            if write_frame(&mut writer, frame.0, &frame.1).is_err() {
+                break;
+            }
+        }
+    });
+    // The queue of an old connection of a restarted client goes away, and its writer with it
+    clients.lock().unwrap().insert(client_id, queue);
+
+    // The connection speaks for the client of the handshake only
+    while let Ok((frame_id, payload)) = read_frame(&mut stream) {
+        if frame_id != client_id {
+            println!(
+                "Dropping a message of client {} sent as client {}",
+                client_id, frame_id
+            );
+            continue;
+        }
+        if sender.send((client_id, payload)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The broker of the [`TcpEventManager`]s: it keeps the monitor, and forwards the events of
+/// each client to all the others.
+#[derive(Debug)]
+pub struct TcpEventBroker<I, MT>
+where
+    I: Input,
+    MT: Monitor,
+{
+    monitor: MT,
+    listener: TcpListener,
+    phantom: PhantomData<I>,
+}
+
+impl<I, MT> TcpEventBroker<I, MT>
+where
+    I: Input,
+    MT: Monitor,
+{
+    /// Create a broker accepting clients on a bound `listener`
+    pub fn new(listener: TcpListener, monitor: MT) -> Self {
+        Self {
+            monitor,
+            listener,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Create a broker on a local port.
+    /// The port must not be bound yet.
+    pub fn new_on_port(port: u16, monitor: MT) -> Result<Self, Error> {
+        Ok(Self::new(
+            TcpListener::bind((TCP_BIND_ADDR, port))?,
+            monitor,
+        ))
+    }
+
+    /// Run forever in the broker
+    pub fn broker_loop(&mut self) -> Result<(), Error> {
+        let (sender, receiver) = channel();
+        let clients: Arc<Mutex<HashMap<u32, SyncSender<Frame>>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        // Client 0 is the broker, as in llmp
+        let next_id = Arc::new(AtomicU32::new(1));
+
+        let listener = self.listener.try_clone()?;
+        let accepted = clients.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                // A silent connection only stalls its own thread
+                let clients = accepted.clone();
+                let next_id = next_id.clone();
+                let sender = sender.clone();
+                thread::spawn(move || drop(serve_client(stream, &clients, &next_id, &sender)));
+            }
+        });
+
+        while let Ok((client_id, payload)) = receiver.recv() {
+            let event: Event<I> = match postcard::from_bytes(&payload) {
+                Ok(event) => event,
+                Err(e) => {
+                    println!("Dropping a broken message of client {}: {:?}", client_id, e);
+                    continue;
+                }
+            };
+            if let BrokerEventResult::Forward =
+                Self::handle_in_broker(&mut self.monitor, client_id, &event)?
+            {
+                // Only queue the frame, the writer threads of the clients send it
+                let frame = Arc::new((client_id, payload));
+                clients.lock().unwrap().retain(|other_id, queue| {
+                    if *other_id == client_id {
+                        return true;
+                    }
+                    match queue.try_send(frame.clone()) {
+                        Ok(()) => true,
+                        Err(TrySendError::Full(_)) => {
+                            println!("Client {} lags behind, dropping a message for it", other_id);
+                            true
+                        }
+                        // The client went away
+                        Err(TrySendError::Disconnected(_)) => false,
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle arriving events in the broker
+    #[allow(clippy::unnecessary_wraps)]
+    fn handle_in_broker(
+        monitor: &mut MT,
+        client_id: u32,
+        event: &Event<I>,
+    ) -> Result<BrokerEventResult, Error> {
+        monitor
+            .client_stats_mut_for(client_id)
+            .heartbeat(current_time());
+        match event {
+            Event::NewTestcase {
+                corpus_size,
+                time,
+                executions,
+                ..
+            } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_corpus_size(*corpus_size as u64);
+                client.update_executions(*executions as u64, *time);
+                client.add_corpus_contribution();
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Forward)
+            }
+            Event::UpdateExecStats {
+                time, executions, ..
+            } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_executions(*executions as u64, *time);
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
+            Event::UpdateUserStats { name, value, .. } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_user_stats(name.clone(), value.clone());
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
+            #[cfg(feature = "introspection")]
+            Event::UpdatePerfMonitor {
+                time,
+                executions,
+                introspection_monitor,
+                ..
+            } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_executions(*executions as u64, *time);
+                client.update_introspection_monitor((**introspection_monitor).clone());
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
+            Event::Objective { objective_size } => {
+                let client = monitor.client_stats_mut_for(client_id);
+                client.update_objective_size(*objective_size as u64);
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
+            Event::PurgeTestcases { .. } => Ok(BrokerEventResult::Forward),
+            Event::ClientName { name, .. } => {
+                monitor
+                    .client_stats_mut_for(client_id)
+                    .update_name(name.clone());
+                monitor.display(event.name().to_string(), client_id);
+                Ok(BrokerEventResult::Handled)
+            }
+            Event::Log {
+                severity_level,
+                message,
+                ..
+            } => {
+                println!(
+                    "[LOG {} {}]: {}",
+                    severity_level,
+                    monitor.client_label(client_id),
+                    message
+                );
+                Ok(BrokerEventResult::Handled)
+            }
+        }
+    }
+}
+
+/// An [`EventManager`] sending its events over a single TCP connection to a [`TcpEventBroker`],
+/// which forwards them to the other clients. It needs no shared memory.
+///
+/// After a restart, reconnect with [`TcpEventManager::existing`] and the old [`TcpEventManager::client_id`],
+/// for the broker to keep the stats of this client.
+#[derive(Debug)]
+pub struct TcpEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    stream: TcpStream,
+    client_id: u32,
+    configuration: EventConfig,
+    /// The frames of the other clients, read by a background thread
+    receiver: Receiver<(u32, Vec<u8>)>,
+    /// The metadata of the state, see [`TcpEventManager::with_state_metadata`]
+    metadata: Option<StateMetadataFn<S>>,
+    phantom: PhantomData<(I, OT, S)>,
+}
+
+impl<I, OT, S> TcpEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    /// Connect to a [`TcpEventBroker`] at `addr`, as a new client
+    pub fn new<A>(addr: A, configuration: EventConfig) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::connect(addr, TCP_NEW_CLIENT, configuration)
+    }
+
+    /// Connect to a [`TcpEventBroker`] on a local port, as a new client
+    pub fn on_port(port: u16, configuration: EventConfig) -> Result<Self, Error> {
+        Self::new((TCP_CONNECT_ADDR, port), configuration)
+    }
+
+    /// Reconnect to a [`TcpEventBroker`] at `addr`, as the client `client_id`, e.g. after a restart
+    pub fn existing<A>(addr: A, client_id: u32, configuration: EventConfig) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::connect(addr, client_id, configuration)
+    }
+
+    fn connect<A>(addr: A, requested_id: u32, configuration: EventConfig) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        stream.write_all(&requested_id.to_be_bytes())?;
+        let mut client_id = [0_u8; 4];
+        stream.read_exact(&mut client_id)?;
+
+        let (sender, receiver) = channel();
+        spawn_reader(stream.try_clone()?, sender);
+        Ok(Self {
+            stream,
+            client_id: u32::from_be_bytes(client_id),
+            configuration,
+            receiver,
+            metadata: None,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Lets this manager keep its metadata in the state, like [`crate::events::LlmpEventManager::with_state_metadata`].
+    /// Without it, purge requests are ignored and received testcases are not recorded.
+    #[must_use]
+    pub fn with_state_metadata(mut self) -> Self
+    where
+        S: HasMetadata,
+    {
+        self.metadata = Some(StateMetadataFn::new());
+        self
+    }
+
+    /// The id the broker assigned to this client
+    #[must_use]
+    pub fn client_id(&self) -> u32 {
+        self.client_id
+    }
+
+    // Handle arriving events in the client
+    fn handle_in_client<E, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        client_id: u32,
+        event: Event<I>,
+    ) -> Result<(), Error>
+    where
+        OT: DeserializeOwned,
+        E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+        Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+    {
+        match event {
+            Event::NewTestcase {
+                input,
+                client_config,
+                exit_kind,
+                observers_buf,
+                ..
+            } => {
+                println!(
+                    "Received new Testcase from {} ({:?})",
+                    client_id, client_config
+                );
+
+                if let Some(metadata) = &self.metadata {
+                    ReplayLogMetadata::record_received(metadata.get(state), &input)?;
+                }
+                let _res = if client_config.match_with(&self.configuration)
+                    && observers_buf.is_some()
+                {
+                    let observers: OT = postcard::from_bytes(observers_buf.as_ref().unwrap())?;
+                    fuzzer.process_execution(state, self, input, &observers, &exit_kind, false)?
+                } else {
+                    fuzzer.evaluate_input_with_observers(state, executor, self, input, false)?
+                };
+                if let Some(item) = _res.1 {
+                    println!("Added received Testcase as item #{}", item);
+                }
+                Ok(())
+            }
+            Event::PurgeTestcases { input_hashes, .. } => {
+                if let Some(metadata) = &self.metadata {
+                    CorpusAgingMetadata::request_purge(metadata.get(state), input_hashes);
+                }
+                Ok(())
+            }
+            _ => Err(Error::Unknown(format!(
+                "Received illegal message that message should not have arrived: {:?}.",
+                event.name()
+            ))),
+        }
+    }
+}
+
+impl<I, OT, S> EventFirer<I> for TcpEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    fn fire<S2>(&mut self, _state: &mut S2, event: Event<I>) -> Result<(), Error> {
+        let serialized = postcard::to_allocvec(&event)?;
+        write_frame(&mut self.stream, self.client_id, &serialized)
+    }
+
+    fn configuration(&self) -> EventConfig {
+        self.configuration
+    }
+}
+
+impl<I, OT, S> EventRestarter<S> for TcpEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    /// Make sure the broker got all our events, before shutting down
+    fn await_restart_safe(&mut self) {
+        drop(self.stream.flush());
+    }
+}
+
+impl<E, I, OT, S, Z> EventProcessor<E, I, S, Z> for TcpEventManager<I, OT, S>
+where
+    E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S> + DeserializeOwned,
+    Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+{
+    fn process(&mut self, fuzzer: &mut Z, state: &mut S, executor: &mut E) -> Result<usize, Error> {
+        let mut events = vec![];
+        while let Ok((client_id, payload)) = self.receiver.try_recv() {
+            if client_id == self.client_id {
+                continue;
+            }
+            let event: Event<I> = postcard::from_bytes(&payload)?;
+            events.push((client_id, event));
+        }
+        let count = events.len();
+        events.drain(..).try_for_each(|(client_id, event)| {
+            self.handle_in_client(fuzzer, executor, state, client_id, event)
+        })?;
+        Ok(count)
+    }
+}
+
+impl<E, I, OT, S, Z> EventManager<E, I, S, Z> for TcpEventManager<I, OT, S>
+where
+    E: Executor<Self, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S> + DeserializeOwned,
+    Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+{
+}
+
+impl<I, OT, S> ProgressReporter<I> for TcpEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+}
+
+impl<I, OT, S> HasEventManagerId for TcpEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    fn mgr_id(&self) -> EventManagerId {
+        EventManagerId {
+            id: self.client_id as usize,
+        }
+    }
+}
+
+/// The env var with the file a spawned client of [`setup_restarting_mgr_tcp`] hands its state over in
+const _ENV_TCP_STATE_FILE: &str = "_AFL_ENV_TCP_STATE_FILE";
+
+/// Writes the state for the next run to `path`, behind its checksum
+fn write_handoff<T>(path: &Path, handoff: &T) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    let serialized = postcard::to_allocvec(handoff)?;
+    let mut bytes = Vec::with_capacity(serialized.len() + 8);
+    bytes.extend_from_slice(&checksum(&serialized).to_le_bytes());
+    bytes.extend_from_slice(&serialized);
+    // Crashing while writing does not leave a half written state behind
+    let partial = path.with_extension("partial");
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Takes the state the last run left in `path`, `None` if there is none, or if it is corrupted
+fn take_handoff<T>(path: &Path) -> Option<T>
+where
+    T: DeserializeOwned,
+{
+    let bytes = fs::read(path).ok()?;
+    drop(fs::remove_file(path));
+    if bytes.len() < 8
+        || u64::from_le_bytes(bytes[..8].try_into().unwrap()) != checksum(&bytes[8..])
+    {
+        println!("The state of the last run is corrupted, starting fresh.");
+        return None;
+    }
+    match postcard::from_bytes(&bytes[8..]) {
+        Ok(handoff) => Some(handoff),
+        Err(e) => {
+            println!(
+                "Could not deserialize the state of the last run ({:?}), starting fresh.",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// The file a client leaves next to its state file, when it exits cleanly
+fn exiting_marker(state_file: &Path) -> PathBuf {
+    state_file.with_extension("exiting")
+}
+
+/// A [`TcpEventManager`] restarted by [`setup_restarting_mgr_tcp`].
+/// It hands the state and its client id over to the next run in a file, so it needs no shared memory either.
+#[derive(Debug)]
+pub struct TcpRestartingEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    tcp_mgr: TcpEventManager<I, OT, S>,
+    /// The file the state goes to, for the next run
+    state_file: PathBuf,
+}
+
+impl<I, OT, S> TcpRestartingEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    /// Create a new restarting manager, handing the state over in `state_file`
+    pub fn new(tcp_mgr: TcpEventManager<I, OT, S>, state_file: PathBuf) -> Self {
+        Self {
+            tcp_mgr,
+            state_file,
+        }
+    }
+
+    /// Lets the manager keep its metadata in the state, see [`TcpEventManager::with_state_metadata`]
+    #[must_use]
+    pub fn with_state_metadata(mut self) -> Self
+    where
+        S: HasMetadata,
+    {
+        self.tcp_mgr = self.tcp_mgr.with_state_metadata();
+        self
+    }
+
+    /// The file the state goes to, for the next run
+    #[must_use]
+    pub fn state_file(&self) -> &Path {
+        &self.state_file
+    }
+}
+
+impl<I, OT, S> EventFirer<I> for TcpRestartingEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    fn fire<S2>(&mut self, state: &mut S2, event: Event<I>) -> Result<(), Error> {
+        self.tcp_mgr.fire(state, event)
+    }
+
+    fn configuration(&self) -> EventConfig {
+        self.tcp_mgr.configuration()
+    }
+}
+
+impl<I, OT, S> EventRestarter<S> for TcpRestartingEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    S: Serialize,
+{
+    /// Make sure the broker got all our events, before shutting down
+    fn await_restart_safe(&mut self) {
+        self.tcp_mgr.await_restart_safe();
+    }
+
+    /// Save the state and the client id, for the next run
+    fn on_restart(&mut self, state: &mut S) -> Result<(), Error> {
+        self.await_restart_safe();
+        write_handoff(&self.state_file, &(state, self.tcp_mgr.client_id()))
+    }
+
+    /// Tell the respawner not to restart this client
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        fs::write(exiting_marker(&self.state_file), [])?;
+        Ok(())
+    }
+}
+
+impl<E, I, OT, S, Z> EventProcessor<E, I, S, Z> for TcpRestartingEventManager<I, OT, S>
+where
+    E: Executor<TcpEventManager<I, OT, S>, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S> + DeserializeOwned,
+    Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+{
+    fn process(&mut self, fuzzer: &mut Z, state: &mut S, executor: &mut E) -> Result<usize, Error> {
+        self.tcp_mgr.process(fuzzer, state, executor)
+    }
+}
+
+impl<E, I, OT, S, Z> EventManager<E, I, S, Z> for TcpRestartingEventManager<I, OT, S>
+where
+    E: Executor<TcpEventManager<I, OT, S>, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S> + DeserializeOwned,
+    S: Serialize,
+    Z: ExecutionProcessor<I, OT, S> + EvaluatorObservers<I, OT, S>,
+{
+}
+
+impl<I, OT, S> ProgressReporter<I> for TcpRestartingEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+}
+
+impl<I, OT, S> HasEventManagerId for TcpRestartingEventManager<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    fn mgr_id(&self) -> EventManagerId {
+        self.tcp_mgr.mgr_id()
+    }
+}
+
+/// Sets up a restarting fuzzer talking TCP to its broker, like [`crate::events::setup_restarting_mgr_std`],
+/// but without shared memory. The first process on `broker_port` becomes the broker, the others clients.
+/// A client is respawned each time it crashes or times out, and the next run gets the state back.
+#[allow(clippy::type_complexity)]
+pub fn setup_restarting_mgr_tcp<I, MT, OT, S>(
+    monitor: MT,
+    broker_port: u16,
+    configuration: EventConfig,
+) -> Result<(Option<S>, TcpRestartingEventManager<I, OT, S>), Error>
+where
+    I: Input,
+    MT: Monitor,
+    OT: ObserversTuple<I, S>,
+    S: DeserializeOwned,
+{
+    let state_file = match env::var(_ENV_TCP_STATE_FILE) {
+        // We are a spawned client
+        Ok(state_file) => PathBuf::from(state_file),
+        Err(_) => broker_or_respawn::<I, MT>(monitor, broker_port)?,
+    };
+
+    // A state left by the last run means we are restarting
+    let (state, tcp_mgr) = if let Some((state, client_id)) = take_handoff::<(S, u32)>(&state_file) {
+        (
+            Some(state),
+            TcpEventManager::existing((TCP_CONNECT_ADDR, broker_port), client_id, configuration)?,
+        )
+    } else {
+        println!("First run. Let's set it all up");
+        (None, TcpEventManager::on_port(broker_port, configuration)?)
+    };
+    Ok((state, TcpRestartingEventManager::new(tcp_mgr, state_file)))
+}
+
+/// Runs the broker if `broker_port` is free, else respawns the client until it exits cleanly.
+/// Returns the state file of the client, in the client.
+fn broker_or_respawn<I, MT>(monitor: MT, broker_port: u16) -> Result<PathBuf, Error>
+where
+    I: Input,
+    MT: Monitor,
+{
+    if let Ok(listener) = TcpListener::bind((TCP_BIND_ADDR, broker_port)) {
+        println!("Doing broker things. Run this tool again to start fuzzing in a client.");
+        TcpEventBroker::<I, MT>::new(listener, monitor).broker_loop()?;
+        return Err(Error::ShuttingDown);
+    }
+
+    // We are the fuzzer respawner in a tcp client
+    let state_file = env::temp_dir().join(format!("libafl_tcp_state_{}", process::id()));
+    let exiting = exiting_marker(&state_file);
+    drop(fs::remove_file(&state_file));
+    drop(fs::remove_file(&exiting));
+    // A forked client takes it along, a spawned one finds it in the env
+    #[cfg(any(windows, not(feature = "fork")))]
+    env::set_var(_ENV_TCP_STATE_FILE, &state_file);
+
+    loop {
+        // On Unix, we fork
+        #[cfg(all(unix, feature = "fork"))]
+        let child_status = match unsafe { fork() }? {
+            ForkResult::Parent(handle) => handle.status(),
+            ForkResult::Child => return Ok(state_file),
+        };
+
+        // On windows (or in any case without fork), we spawn ourself again
+        #[cfg(any(windows, not(feature = "fork")))]
+        let child_status = startable_self()?.status()?;
+        #[cfg(all(unix, not(feature = "fork")))]
+        let child_status = child_status.code().unwrap_or_default();
+
+        // The client is done, do not restart it
+        if fs::remove_file(&exiting).is_ok() {
+            drop(fs::remove_file(&state_file));
+            println!("Fuzzer-respawner: The client exited cleanly.");
+            return Err(Error::ShuttingDown);
+        }
+
+        #[allow(clippy::manual_assert)]
+        if !state_file.exists() {
+            panic!("Fuzzer-respawner: Storing state in crashed fuzzer instance did not work, no point to spawn the next client! This can happen if the child calls `exit()`, in that case make sure it uses `abort()`, if it got killed unrecoverable (OOM), or if there is a bug in the fuzzer itself. (Child exited with: {})", child_status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{marker::PhantomData, time::Duration};
+    use std::{
+        env, fs,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use crate::{
+        events::{
+            tcp::{exiting_marker, take_handoff, write_frame, write_handoff, TCP_NEW_CLIENT},
+            Event, EventConfig, EventFirer, EventRestarter, LogSeverity, TcpEventBroker,
+            TcpEventManager, TcpRestartingEventManager,
+        },
+        inputs::BytesInput,
+        monitors::NopMonitor,
+    };
+
+    #[test]
+    fn test_tcp_event_manager() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut broker = TcpEventBroker::<BytesInput, _>::new(listener, NopMonitor::new());
+        thread::spawn(move || broker.broker_loop());
+
+        let mut first =
+            TcpEventManager::<BytesInput, (), ()>::new(addr, EventConfig::AlwaysUnique).unwrap();
+        let mut second =
+            TcpEventManager::<BytesInput, (), ()>::new(addr, EventConfig::AlwaysUnique).unwrap();
+        assert_ne!(first.client_id(), 0);
+        assert_ne!(first.client_id(), second.client_id());
+
+        // Logs stay in the broker, purges get forwarded
+        first
+            .log(&mut (), LogSeverity::Info, "hello".into())
+            .unwrap();
+        first
+            .fire(
+                &mut (),
+                Event::PurgeTestcases {
+                    input_hashes: vec![1337],
+                    phantom: PhantomData,
+                },
+            )
+            .unwrap();
+        let (client_id, payload) = second
+            .receiver
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(client_id, first.client_id());
+        assert!(matches!(
+            postcard::from_bytes::<Event<BytesInput>>(&payload).unwrap(),
+            Event::PurgeTestcases { input_hashes, .. } if input_hashes == vec![1337]
+        ));
+
+        // A restarted client keeps its id
+        let id = second.client_id();
+        drop(second);
+        let second =
+            TcpEventManager::<BytesInput, (), ()>::existing(addr, id, EventConfig::AlwaysUnique)
+                .unwrap();
+        assert_eq!(second.client_id(), id);
+    }
+
+    #[test]
+    fn test_tcp_broker_stalled_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut broker = TcpEventBroker::<BytesInput, _>::new(listener, NopMonitor::new());
+        thread::spawn(move || broker.broker_loop());
+
+        // A connection that never does the handshake does not stall the accepts
+        let _silent = TcpStream::connect(addr).unwrap();
+        // A client that never reads does not stall the forwarding
+        let mut stopped = TcpStream::connect(addr).unwrap();
+        stopped.write_all(&TCP_NEW_CLIENT.to_be_bytes()).unwrap();
+        stopped.read_exact(&mut [0_u8; 4]).unwrap();
+
+        let mut sender =
+            TcpEventManager::<BytesInput, (), ()>::new(addr, EventConfig::AlwaysUnique).unwrap();
+        let receiver =
+            TcpEventManager::<BytesInput, (), ()>::new(addr, EventConfig::AlwaysUnique).unwrap();
+
+        // Far more than the socket buffers of the stopped client hold
+        for _ in 0..256 {
+            sender
+                .fire(
+                    &mut (),
+                    Event::PurgeTestcases {
+                        input_hashes: vec![u64::MAX; 8192],
+                        phantom: PhantomData,
+                    },
+                )
+                .unwrap();
+        }
+        for _ in 0..256 {
+            let (client_id, _) = receiver
+                .receiver
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap();
+            assert_eq!(client_id, sender.client_id());
+        }
+    }
+
+    #[test]
+    fn test_tcp_broker_client_ids() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut broker = TcpEventBroker::<BytesInput, _>::new(listener, NopMonitor::new());
+        thread::spawn(move || broker.broker_loop());
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(&TCP_NEW_CLIENT.to_be_bytes()).unwrap();
+        let mut client_id = [0_u8; 4];
+        client.read_exact(&mut client_id).unwrap();
+        let client_id = u32::from_be_bytes(client_id);
+        let receiver =
+            TcpEventManager::<BytesInput, (), ()>::new(addr, EventConfig::AlwaysUnique).unwrap();
+
+        let purge = |input_hash| {
+            postcard::to_allocvec(&Event::<BytesInput>::PurgeTestcases {
+                input_hashes: vec![input_hash],
+                phantom: PhantomData,
+            })
+            .unwrap()
+        };
+        // Sent as another client, dropped
+        write_frame(&mut client, receiver.client_id(), &purge(1)).unwrap();
+        write_frame(&mut client, client_id + 1000, &purge(2)).unwrap();
+        write_frame(&mut client, client_id, &purge(3)).unwrap();
+
+        let (sender_id, payload) = receiver
+            .receiver
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(sender_id, client_id);
+        assert!(matches!(
+            postcard::from_bytes::<Event<BytesInput>>(&payload).unwrap(),
+            Event::PurgeTestcases { input_hashes, .. } if input_hashes == vec![3]
+        ));
+    }
+
+    #[test]
+    fn test_tcp_state_handoff() {
+        let state_file =
+            env::temp_dir().join(format!("libafl_test_tcp_state_{}", std::process::id()));
+        assert!(take_handoff::<(String, u32)>(&state_file).is_none());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut broker = TcpEventBroker::<BytesInput, _>::new(listener, NopMonitor::new());
+        thread::spawn(move || broker.broker_loop());
+
+        let tcp_mgr =
+            TcpEventManager::<BytesInput, (), String>::new(addr, EventConfig::AlwaysUnique)
+                .unwrap();
+        let client_id = tcp_mgr.client_id();
+        let mut mgr = TcpRestartingEventManager::new(tcp_mgr, state_file.clone());
+        mgr.on_restart(&mut "state".to_string()).unwrap();
+
+        // The next run gets the state and the client id, once
+        assert_eq!(
+            take_handoff::<(String, u32)>(&state_file),
+            Some(("state".to_string(), client_id))
+        );
+        assert!(take_handoff::<(String, u32)>(&state_file).is_none());
+
+        // A corrupted state is discarded
+        write_handoff(&state_file, &("state".to_string(), client_id)).unwrap();
+        let mut bytes = fs::read(&state_file).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        fs::write(&state_file, bytes).unwrap();
+        assert!(take_handoff::<(String, u32)>(&state_file).is_none());
+        assert!(!state_file.exists());
+
+        // A clean exit leaves its marker for the respawner
+        mgr.send_exiting().unwrap();
+        let exiting = exiting_marker(&state_file);
+        assert!(exiting.exists());
+        fs::remove_file(exiting).unwrap();
+    }
+}
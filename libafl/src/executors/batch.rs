@@ -0,0 +1,48 @@
+//! Batched execution: executors run several inputs in one call, so that the setup they can share
+//! between runs is done once per batch.
+
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::Input,
+    observers::ObserversTuple,
+    state::HasExecutions,
+    Error,
+};
+
+/// An [`Executor`] that can run a batch of inputs at once.
+///
+/// Each input still runs on a freshly reset target, so that it behaves as if it ran on its own.
+/// The default implementation runs the inputs back to back, without going back to the fuzzer in
+/// between; executors with a setup that can be shared between runs override
+/// [`BatchExecutor::run_batch`] to do it once for the whole batch.
+pub trait BatchExecutor<EM, I, OT, S, Z>: Executor<EM, I, S, Z> + HasObservers<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    S: HasExecutions,
+{
+    /// Runs all the `inputs`, in order.
+    /// After each run, once the observers are done, `on_exec` gets the observers, the input, and
+    /// how it exited, e.g. to evaluate it with the feedbacks.
+    fn run_batch<F>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        inputs: &[I],
+        mut on_exec: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&mut Z, &mut S, &mut EM, &OT, &I, ExitKind) -> Result<(), Error>,
+    {
+        for input in inputs {
+            self.observers_mut().pre_exec_all(state, input)?;
+            let exit_kind = self.run_target(fuzzer, state, mgr, input)?;
+            *state.executions_mut() += 1;
+            self.observers_mut()
+                .post_exec_all(state, input, &exit_kind)?;
+            on_exec(fuzzer, state, mgr, self.observers(), input, exit_kind)?;
+        }
+        Ok(())
+    }
+}
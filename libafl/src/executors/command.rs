@@ -22,7 +22,10 @@ use crate::{
         AsSlice,
     },
     inputs::HasTargetBytes,
-    observers::{ASANBacktraceObserver, ObserversTuple, StdErrObserver, StdOutObserver},
+    observers::{
+        ASANBacktraceObserver, ObserversTuple, SanitizerReportObserver, StdErrObserver,
+        StdOutObserver,
+    },
 };
 #[cfg(feature = "std")]
 use crate::{inputs::Input, Error};
@@ -30,14 +33,17 @@ use crate::{inputs::Input, Error};
 #[cfg(all(feature = "std", unix))]
 use crate::executors::{Executor, ExitKind};
 
-#[cfg(all(feature = "std", unix))]
 use std::time::Duration;
 
 use super::HasObservers;
 
+/// The default timeout of a run of a [`CommandExecutor`]
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// How to deliver input to an external program
 /// `StdIn`: The traget reads from stdin
 /// `File`: The target reads from the specified [`OutFile`]
+/// `Env`: The target reads from an environment variable
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum InputLocation {
     /// Mutate a commandline argument to deliver an input
@@ -53,6 +59,12 @@ enum InputLocation {
         /// The fiel to write input to. The target should read input from this location.
         out_file: OutFile,
     },
+    /// Deliver the input via an environment variable.
+    /// The input is cut at its first nul byte, which environment variables cannot hold.
+    Env {
+        /// The name of the environment variable
+        name: OsString,
+    },
 }
 
 /// Clones a [`Command`] (without stdio and stdout/stderr - they are not accesible)
@@ -82,6 +94,8 @@ pub struct StdCommandConfigurator {
     input_location: InputLocation,
     /// The Command to execute
     command: Command,
+    /// The timeout of each run
+    timeout: Duration,
 }
 
 impl CommandConfigurator for StdCommandConfigurator {
@@ -123,8 +137,7 @@ impl CommandConfigurator for StdCommandConfigurator {
                 Ok(cmd.spawn()?)
             }
             InputLocation::StdIn => {
-                self.command.stdin(Stdio::piped()).spawn()?;
-                let mut handle = self.command.spawn()?;
+                let mut handle = self.command.stdin(Stdio::piped()).spawn()?;
                 let mut stdin = handle.stdin.take().unwrap();
                 stdin.write_all(input.target_bytes().as_slice())?;
                 stdin.flush()?;
@@ -135,8 +148,23 @@ impl CommandConfigurator for StdCommandConfigurator {
                 out_file.write_buf(input.target_bytes().as_slice())?;
                 Ok(self.command.spawn()?)
             }
+            InputLocation::Env { name } => {
+                let target_bytes = input.target_bytes();
+                let bytes = target_bytes.as_slice();
+                let bytes = &bytes[..bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len())];
+                #[cfg(unix)]
+                self.command.env(&*name, OsStr::from_bytes(bytes));
+                #[cfg(not(unix))]
+                self.command
+                    .env(&*name, String::from_utf8_lossy(bytes).into_owned());
+                Ok(self.command.spawn()?)
+            }
         }
     }
+
+    fn exec_timeout(&self) -> Duration {
+        self.timeout
+    }
 }
 
 /// A `CommandExecutor` is a wrapper around [`std::process::Command`] to execute a target as a child process.
@@ -152,6 +180,8 @@ where
     observers: OT,
     /// cache if the AsanBacktraceObserver is present
     has_asan_observer: bool,
+    /// cache if the [`SanitizerReportObserver`] is present
+    has_sanitizer_observer: bool,
     /// If set, we found a [`StdErrObserver`] in the observer list.
     /// Pipe the child's `stderr` instead of closing it.
     has_stdout_observer: bool,
@@ -230,7 +260,7 @@ where
             .match_name::<StdOutObserver>("StdOutObserver")
             .is_some();
         if has_stdout_observer {
-            command.stderr(Stdio::piped());
+            command.stdout(Stdio::piped());
         }
 
         let has_stderr_observer = observers
@@ -239,19 +269,24 @@ where
         let has_asan_observer = observers
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some();
-        if has_stderr_observer || has_asan_observer {
+        let has_sanitizer_observer = observers
+            .match_name::<SanitizerReportObserver>("SanitizerReportObserver")
+            .is_some();
+        if has_stderr_observer || has_asan_observer || has_sanitizer_observer {
             command.stderr(Stdio::piped());
         }
 
         Ok(Self {
             observers,
             has_asan_observer,
+            has_sanitizer_observer,
             configurer: StdCommandConfigurator {
                 input_location: InputLocation::File {
                     out_file: OutFile::create(path)?,
                 },
                 command,
                 debug_child,
+                timeout: DEFAULT_COMMAND_TIMEOUT,
             },
             has_stdout_observer,
             has_stderr_observer,
@@ -323,7 +358,7 @@ where
         let mut child = self.configurer.spawn_child(input)?;
 
         let res = match child
-            .wait_timeout(Duration::from_secs(5))
+            .wait_timeout(self.configurer.exec_timeout())
             .expect("waiting on child failed")
             .map(|status| status.signal())
         {
@@ -341,7 +376,7 @@ where
             }
         };
 
-        if self.has_asan_observer || self.has_stderr_observer {
+        if self.has_asan_observer || self.has_sanitizer_observer || self.has_stderr_observer {
             let mut stderr = String::new();
             child.stderr.as_mut().ok_or_else(|| {
                 Error::IllegalState(
@@ -354,6 +389,12 @@ where
                     .unwrap()
                     .parse_asan_output(&stderr);
             }
+            if self.has_sanitizer_observer {
+                self.observers
+                    .match_name_mut::<SanitizerReportObserver>("SanitizerReportObserver")
+                    .unwrap()
+                    .parse_output(&stderr);
+            }
             if self.has_stderr_observer {
                 self.observers
                     .match_name_mut::<StdErrObserver>("StdErrObserver")
@@ -399,6 +440,7 @@ pub struct CommandExecutorBuilder {
     input_location: InputLocation,
     cwd: Option<PathBuf>,
     envs: Vec<(OsString, OsString)>,
+    timeout: Duration,
 }
 
 impl Default for CommandExecutorBuilder {
@@ -418,6 +460,7 @@ impl CommandExecutorBuilder {
             cwd: None,
             envs: vec![],
             debug_child: false,
+            timeout: DEFAULT_COMMAND_TIMEOUT,
         }
     }
 
@@ -474,6 +517,15 @@ impl CommandExecutorBuilder {
         self
     }
 
+    /// Sets the input mode to [`InputLocation::Env`]:
+    /// the input is delivered in the environment variable `name`.
+    pub fn env_input<O: AsRef<OsStr>>(&mut self, name: O) -> &mut Self {
+        self.input(InputLocation::Env {
+            name: name.as_ref().to_owned(),
+        });
+        self
+    }
+
     /// Adds an argument to the program's commandline.
     pub fn arg<O: AsRef<OsStr>>(&mut self, arg: O) -> &mut CommandExecutorBuilder {
         self.args.push(arg.as_ref().to_owned());
@@ -529,6 +581,13 @@ impl CommandExecutorBuilder {
         self
     }
 
+    /// Sets the timeout of each run, after which the child gets killed.
+    /// Defaults to [`DEFAULT_COMMAND_TIMEOUT`].
+    pub fn timeout(&mut self, timeout: Duration) -> &mut CommandExecutorBuilder {
+        self.timeout = timeout;
+        self
+    }
+
     /// Builds the `ComandExecutor`
     pub fn build<EM, I, OT, S, Z>(
         &self,
@@ -549,7 +608,7 @@ impl CommandExecutorBuilder {
             InputLocation::StdIn => {
                 command.stdin(Stdio::piped());
             }
-            InputLocation::File { .. } | InputLocation::Arg { .. } => {
+            InputLocation::File { .. } | InputLocation::Arg { .. } | InputLocation::Env { .. } => {
                 command.stdin(Stdio::null());
             }
         }
@@ -569,6 +628,9 @@ impl CommandExecutorBuilder {
         if observers
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some()
+            || observers
+                .match_name::<SanitizerReportObserver>("SanitizerReportObserver")
+                .is_some()
             || observers
                 .match_name::<StdErrObserver>("StdErrObserver")
                 .is_some()
@@ -587,6 +649,7 @@ impl CommandExecutorBuilder {
             debug_child: self.debug_child,
             input_location: self.input_location.clone(),
             command,
+            timeout: self.timeout,
         };
         Ok(configurator.into_executor(observers))
     }
@@ -629,6 +692,11 @@ pub trait CommandConfigurator: Sized + Debug {
     where
         I: Input + HasTargetBytes;
 
+    /// The timeout of each run, after which the child gets killed
+    fn exec_timeout(&self) -> Duration {
+        DEFAULT_COMMAND_TIMEOUT
+    }
+
     /// Create an `Executor` from this `CommandConfigurator`.
     fn into_executor<EM, I, OT, S, Z>(self, observers: OT) -> CommandExecutor<EM, I, OT, S, Self, Z>
     where
@@ -638,6 +706,10 @@ pub trait CommandConfigurator: Sized + Debug {
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some();
 
+        let has_sanitizer_observer = observers
+            .match_name::<SanitizerReportObserver>("SanitizerReportObserver")
+            .is_some();
+
         let has_stdout_observer = observers
             .match_name::<StdOutObserver>("StdOutObserver")
             .is_some();
@@ -649,6 +721,7 @@ pub trait CommandConfigurator: Sized + Debug {
         CommandExecutor {
             observers,
             has_asan_observer,
+            has_sanitizer_observer,
             has_stdout_observer,
             has_stderr_observer,
             configurer: self,
@@ -693,6 +766,36 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_env_input_and_timeout() {
+        use core::time::Duration;
+
+        use crate::executors::ExitKind;
+
+        let mut mgr = SimpleEventManager::<BytesInput, _>::new(SimpleMonitor::new(|status| {
+            println!("{}", status);
+        }));
+
+        let mut executor = CommandExecutor::builder()
+            .program("sh")
+            .arg("-c")
+            .arg("if [ \"$FUZZ\" = crash ]; then kill -SEGV $$; fi; if [ \"$FUZZ\" = hang ]; then sleep 10; fi")
+            .env_input("FUZZ")
+            .timeout(Duration::from_millis(500))
+            .build(())
+            .unwrap();
+
+        let mut run = |bytes: &[u8]| {
+            executor
+                .run_target(&mut (), &mut (), &mut mgr, &BytesInput::new(bytes.to_vec()))
+                .unwrap()
+        };
+        assert_eq!(run(b"ok"), ExitKind::Ok);
+        assert_eq!(run(b"crash"), ExitKind::Crash);
+        assert_eq!(run(b"hang"), ExitKind::Timeout);
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_parse_afl_cmdline() {
@@ -3,37 +3,65 @@
 //! In comparison to the [`crate::executors::CombinedExecutor`] it also runs the secondary executor in `run_target`.
 //!
 use crate::{
+    bolts::tuples::MatchName,
     executors::{Executor, ExitKind, HasObservers},
     inputs::Input,
-    observers::ObserversTuple,
+    observers::{DifferentialObserversTuple, ObserversTuple},
     Error,
 };
 use core::fmt::Debug;
+use serde::{Deserialize, Serialize};
 
-/// A [`DiffExecutor`] wraps a primary executor, forwarding its methods, and a secondary one
+/// A [`DiffExecutor`] wraps a primary executor and a secondary one, running both with each input.
+/// It owns the observers of both runs, in a [`DiffExecutorObservers`]: the executors run without
+/// observers of their own, the [`DiffExecutor`] runs the hooks of the observers of each executor
+/// around it. Its [`crate::observers::DifferentialObserver`]s are handed the observers of each
+/// executor after it ran, which a [`crate::feedbacks::DiffFeedback`] can compare.
 #[derive(Debug)]
-pub struct DiffExecutor<A, B>
+pub struct DiffExecutor<A, B, OTA, OTB, DOT>
 where
     A: Debug,
     B: Debug,
+    OTA: Debug,
+    OTB: Debug,
+    DOT: Debug,
 {
     primary: A,
     secondary: B,
+    observers: DiffExecutorObservers<OTA, OTB, DOT>,
 }
 
-impl<A, B> DiffExecutor<A, B>
+impl<A, B, OTA, OTB, DOT> DiffExecutor<A, B, OTA, OTB, DOT>
 where
     A: Debug,
     B: Debug,
+    OTA: Debug,
+    OTB: Debug,
+    DOT: Debug,
 {
-    /// Create a new `DiffExecutor`, wrapping the given `executor`s.
-    pub fn new<EM, I, S, Z>(primary: A, secondary: B) -> Self
-    where
-        A: Executor<EM, I, S, Z>,
-        B: Executor<EM, I, S, Z>,
-        I: Input,
-    {
-        Self { primary, secondary }
+    /// Create a new `DiffExecutor`, wrapping the given `executor`s, observed by `primary_observers`
+    /// and `secondary_observers`, with the given differential observers.
+    pub fn new(
+        primary: A,
+        secondary: B,
+        primary_observers: OTA,
+        secondary_observers: OTB,
+        observers: DOT,
+    ) -> Self {
+        Self {
+            primary,
+            secondary,
+            observers: DiffExecutorObservers {
+                primary: primary_observers,
+                secondary: secondary_observers,
+                differential: observers,
+            },
+        }
+    }
+
+    /// The differential observers of this `DiffExecutor`
+    pub fn differential_observers(&mut self) -> &mut DOT {
+        &mut self.observers.differential
     }
 
     /// Retrieve the primary `Executor` that is wrapped by this `DiffExecutor`.
@@ -47,11 +75,14 @@ where
     }
 }
 
-impl<A, B, EM, I, S, Z> Executor<EM, I, S, Z> for DiffExecutor<A, B>
+impl<A, B, EM, I, OTA, OTB, DOT, S, Z> Executor<EM, I, S, Z> for DiffExecutor<A, B, OTA, OTB, DOT>
 where
     A: Executor<EM, I, S, Z>,
     B: Executor<EM, I, S, Z>,
+    DOT: DifferentialObserversTuple<OTA, OTB, I, S>,
     I: Input,
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
 {
     fn run_target(
         &mut self,
@@ -60,10 +91,30 @@ where
         mgr: &mut EM,
         input: &I,
     ) -> Result<ExitKind, Error> {
+        // The fuzzer only runs the hooks of the differential observers, run the ones of the executors
+        let observers = &mut self.observers;
+        observers
+            .differential
+            .pre_observe_first_all(&mut observers.primary)?;
+        observers.primary.pre_exec_all(state, input)?;
         let ret1 = self.primary.run_target(fuzzer, state, mgr, input)?;
         self.primary.post_run_reset();
+        observers.primary.post_exec_all(state, input, &ret1)?;
+        observers
+            .differential
+            .post_observe_first_all(&mut observers.primary)?;
+
+        observers
+            .differential
+            .pre_observe_second_all(&mut observers.secondary)?;
+        observers.secondary.pre_exec_all(state, input)?;
         let ret2 = self.secondary.run_target(fuzzer, state, mgr, input)?;
         self.secondary.post_run_reset();
+        observers.secondary.post_exec_all(state, input, &ret2)?;
+        observers
+            .differential
+            .post_observe_second_all(&mut observers.secondary)?;
+
         if ret1 == ret2 {
             Ok(ret1)
         } else {
@@ -76,19 +127,212 @@ where
     }
 }
 
-impl<A, B, I, OT, S> HasObservers<I, OT, S> for DiffExecutor<A, B>
+/// The observers of a [`DiffExecutor`]: the observers of the primary and the secondary executor,
+/// and its differential observers. The `*_all` hooks only run the differential observers,
+/// the [`DiffExecutor`] runs the ones of the executors around each of them.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DiffExecutorObservers<OTA, OTB, DOT> {
+    primary: OTA,
+    secondary: OTB,
+    differential: DOT,
+}
+
+impl<OTA, OTB, DOT> DiffExecutorObservers<OTA, OTB, DOT> {
+    /// The observers of the primary executor
+    pub fn primary(&self) -> &OTA {
+        &self.primary
+    }
+
+    /// The observers of the secondary executor
+    pub fn secondary(&self) -> &OTB {
+        &self.secondary
+    }
+
+    /// The differential observers
+    pub fn differential(&self) -> &DOT {
+        &self.differential
+    }
+}
+
+impl<OTA, OTB, DOT, I, S> ObserversTuple<I, S> for DiffExecutorObservers<OTA, OTB, DOT>
+where
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+    DOT: DifferentialObserversTuple<OTA, OTB, I, S>,
+{
+    fn pre_exec_all(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.differential.pre_exec_all(state, input)
+    }
+
+    fn post_exec_all(
+        &mut self,
+        state: &mut S,
+        input: &I,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.differential.post_exec_all(state, input, exit_kind)
+    }
+
+    fn pre_exec_child_all(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.differential.pre_exec_child_all(state, input)
+    }
+
+    fn post_exec_child_all(
+        &mut self,
+        state: &mut S,
+        input: &I,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.differential
+            .post_exec_child_all(state, input, exit_kind)
+    }
+}
+
+impl<OTA, OTB, DOT> MatchName for DiffExecutorObservers<OTA, OTB, DOT>
 where
-    A: HasObservers<I, OT, S>,
+    OTA: MatchName,
+    OTB: MatchName,
+    DOT: MatchName,
+{
+    fn match_name<T>(&self, name: &str) -> Option<&T> {
+        self.primary
+            .match_name(name)
+            .or_else(|| self.secondary.match_name(name))
+            .or_else(|| self.differential.match_name(name))
+    }
+
+    fn match_name_mut<T>(&mut self, name: &str) -> Option<&mut T> {
+        if let Some(t) = self.primary.match_name_mut(name) {
+            Some(t)
+        } else if let Some(t) = self.secondary.match_name_mut(name) {
+            Some(t)
+        } else {
+            self.differential.match_name_mut(name)
+        }
+    }
+}
+
+impl<A, B, I, OTA, OTB, DOT, S> HasObservers<I, DiffExecutorObservers<OTA, OTB, DOT>, S>
+    for DiffExecutor<A, B, OTA, OTB, DOT>
+where
+    A: Debug,
     B: Debug,
-    OT: ObserversTuple<I, S>,
+    DOT: DifferentialObserversTuple<OTA, OTB, I, S>,
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
 {
     #[inline]
-    fn observers(&self) -> &OT {
-        self.primary.observers()
+    fn observers(&self) -> &DiffExecutorObservers<OTA, OTB, DOT> {
+        &self.observers
     }
 
     #[inline]
-    fn observers_mut(&mut self) -> &mut OT {
-        self.primary.observers_mut()
+    fn observers_mut(&mut self) -> &mut DiffExecutorObservers<OTA, OTB, DOT> {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsSlice},
+        corpus::{Corpus, InMemoryCorpus},
+        events::NopEventManager,
+        executors::{DiffExecutor, Executor, ExitKind},
+        feedback_or,
+        feedbacks::{differential::DiffResult, DiffExitKindFeedback, DiffFeedback},
+        fuzzer::{Evaluator, StdFuzzer},
+        inputs::{BytesInput, HasTargetBytes},
+        observers::{DiffHashObserver, DiffSide, StdMapObserver},
+        schedulers::QueueScheduler,
+        state::{HasSolutions, StdState},
+        Error,
+    };
+
+    static mut PRIMARY_MAP: [u8; 4] = [0; 4];
+    static mut SECONDARY_MAP: [u8; 4] = [0; 4];
+
+    /// Runs a harness, without observers of its own
+    #[derive(Debug)]
+    struct HarnessExecutor {
+        harness: fn(&BytesInput) -> ExitKind,
+    }
+
+    impl<EM, S, Z> Executor<EM, BytesInput, S, Z> for HarnessExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            Ok((self.harness)(input))
+        }
+    }
+
+    fn primary(_input: &BytesInput) -> ExitKind {
+        unsafe { PRIMARY_MAP[0] = 1 };
+        ExitKind::Ok
+    }
+
+    fn secondary(input: &BytesInput) -> ExitKind {
+        let bytes = input.target_bytes();
+        unsafe { SECONDARY_MAP[0] = if bytes.as_slice() == b"diff" { 2 } else { 1 } };
+        if bytes.as_slice() == b"crash" {
+            ExitKind::Crash
+        } else {
+            ExitKind::Ok
+        }
+    }
+
+    #[test]
+    fn test_diff_fuzzer() {
+        let primary_edges =
+            DiffHashObserver::<StdMapObserver<u8>>::new("primary_edges", "edges", DiffSide::First);
+        let secondary_edges = DiffHashObserver::<StdMapObserver<u8>>::new(
+            "secondary_edges",
+            "edges",
+            DiffSide::Second,
+        );
+        let objective = feedback_or!(
+            DiffFeedback::new("edges_diff", &primary_edges, &secondary_edges, |a, b| {
+                if a == b {
+                    DiffResult::Equal
+                } else {
+                    DiffResult::Diff
+                }
+            })
+            .unwrap(),
+            DiffExitKindFeedback::new()
+        );
+        let mut executor = DiffExecutor::new(
+            HarnessExecutor { harness: primary },
+            HarnessExecutor { harness: secondary },
+            tuple_list!(StdMapObserver::new("edges", unsafe { &mut PRIMARY_MAP })),
+            tuple_list!(StdMapObserver::new("edges", unsafe { &mut SECONDARY_MAP })),
+            tuple_list!(primary_edges, secondary_edges),
+        );
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), (), objective);
+        let mut mgr = NopEventManager {};
+
+        // Same coverage and exit kind, then a different coverage, then only the secondary crashes
+        for (input, solutions) in [("same", 0), ("diff", 1), ("crash", 2)] {
+            fuzzer
+                .evaluate_input(
+                    &mut state,
+                    &mut executor,
+                    &mut mgr,
+                    BytesInput::new(input.as_bytes().to_vec()),
+                )
+                .unwrap();
+            assert_eq!(state.solutions().count(), solutions);
+        }
     }
 }
@@ -7,6 +7,7 @@ use core::{
 };
 use std::{
     ffi::{OsStr, OsString},
+    fs,
     io::{self, prelude::*, ErrorKind},
     os::unix::{io::RawFd, process::CommandExt},
     path::Path,
@@ -20,10 +21,14 @@ use crate::{
         shmem::{ShMem, ShMemProvider, StdShMemProvider},
         AsMutSlice, AsSlice,
     },
-    executors::{Executor, ExitKind, HasObservers},
+    executors::{BatchExecutor, Executor, ExitKind, HasObservers},
     inputs::{HasTargetBytes, Input},
     mutators::Tokens,
-    observers::{get_asan_runtime_flags_with_log_path, ASANBacktraceObserver, ObserversTuple},
+    observers::{
+        get_asan_runtime_flags_with_log_path, ASANBacktraceObserver, ObserversTuple,
+        SanitizerReportObserver, ASAN_LOG_PATH,
+    },
+    state::HasExecutions,
     Error,
 };
 
@@ -43,9 +48,17 @@ const FS_OPT_ENABLED: i32 = 0x80000001_u32 as i32;
 const FS_OPT_SHDMEM_FUZZ: i32 = 0x01000000_u32 as i32;
 #[allow(clippy::cast_possible_wrap)]
 const FS_OPT_AUTODICT: i32 = 0x10000000_u32 as i32;
+#[allow(clippy::cast_possible_wrap)]
+const FS_OPT_MAPSIZE: i32 = 0x40000000_u32 as i32;
 const SHMEM_FUZZ_HDR_SIZE: usize = 4;
 const MAX_FILE: usize = 1024 * 1024;
 
+/// The coverage map size the target announces along with [`FS_OPT_MAPSIZE`], as `FS_OPT_GET_MAPSIZE` in AFL++
+#[allow(clippy::cast_sign_loss)]
+fn fs_opt_get_mapsize(status: i32) -> usize {
+    (((status & 0x00ff_fffe) >> 1) + 1) as usize
+}
+
 /// Configure the target, `limit`, `setsid`, `pipe_stdin`, the code was borrowed from the [`Angora`](https://github.com/AngoraFuzzer/Angora) fuzzer
 pub trait ConfigTarget {
     /// Sets the sid
@@ -62,6 +75,10 @@ pub trait ConfigTarget {
         ctl_read: RawFd,
         ctl_write: RawFd,
     ) -> &mut Self;
+    /// Keeps the shared maps with the given file descriptors open in the target.
+    /// On Android, the ids of the ashmem maps are their file descriptors, closed on exec otherwise.
+    #[cfg(target_os = "android")]
+    fn setshmem(&mut self, fds: Vec<RawFd>) -> &mut Self;
 }
 
 impl ConfigTarget for Command {
@@ -124,6 +141,23 @@ impl ConfigTarget for Command {
         }
     }
 
+    #[cfg(target_os = "android")]
+    fn setshmem(&mut self, fds: Vec<RawFd>) -> &mut Self {
+        if fds.is_empty() {
+            return self;
+        }
+        let func = move || {
+            for fd in &fds {
+                // Clear `FD_CLOEXEC`
+                if unsafe { libc::fcntl(*fd, libc::F_SETFD, 0) } < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        };
+        unsafe { self.pre_exec(func) }
+    }
+
     #[allow(trivial_numeric_casts)]
     fn setlimit(&mut self, memlimit: u64) -> &mut Self {
         if memlimit == 0 {
@@ -157,6 +191,26 @@ impl ConfigTarget for Command {
     }
 }
 
+/// The file descriptors of the ashmem maps the target gets in `__AFL_SHM_ID` and `__AFL_SHM_FUZZ_ID`,
+/// from `envs` or from the environment of the fuzzer.
+/// The ids of served maps, e.g. from the [`crate::bolts::shmem::StdShMemProvider`] on Android,
+/// are no file descriptors, the maps for the target have to come from an
+/// [`crate::bolts::shmem::UnixShMemProvider`].
+#[cfg(target_os = "android")]
+fn ashmem_fds(envs: &[(OsString, OsString)]) -> Vec<RawFd> {
+    ["__AFL_SHM_ID", "__AFL_SHM_FUZZ_ID"]
+        .iter()
+        .filter_map(|name| {
+            envs.iter()
+                .rev()
+                .find(|(key, _)| key.as_os_str() == OsStr::new(name))
+                .map(|(_, value)| value.clone())
+                .or_else(|| std::env::var_os(name))
+                .and_then(|value| value.to_str()?.parse().ok())
+        })
+        .collect()
+}
+
 /// The [`Forkserver`] is communication channel with a child process that forks on request of the fuzzer.
 /// The communication happens via pipe.
 #[derive(Debug)]
@@ -188,7 +242,11 @@ impl Forkserver {
             (Stdio::null(), Stdio::null())
         };
 
-        match Command::new(target)
+        #[cfg(target_os = "android")]
+        let shmem_fds = ashmem_fds(&envs);
+
+        let mut command = Command::new(target);
+        command
             .args(args)
             .stdin(Stdio::null())
             .stdout(stdout)
@@ -204,9 +262,11 @@ impl Forkserver {
                 st_pipe.write_end().unwrap(),
                 ctl_pipe.read_end().unwrap(),
                 ctl_pipe.write_end().unwrap(),
-            )
-            .spawn()
-        {
+            );
+        #[cfg(target_os = "android")]
+        command.setshmem(shmem_fds);
+
+        match command.spawn() {
             Ok(_) => (),
             Err(err) => {
                 return Err(Error::Forkserver(format!(
@@ -395,12 +455,13 @@ where
         match &mut self.executor.shmem_mut() {
             Some(shmem) => {
                 let target_bytes = input.target_bytes();
-                let size = target_bytes.as_slice().len();
+                // Longer testcases are truncated, as AFL++ does
+                let size = target_bytes.as_slice().len().min(MAX_FILE);
                 let size_in_bytes = size.to_ne_bytes();
                 // The first four bytes tells the size of the shmem.
                 shmem.as_mut_slice()[..4].copy_from_slice(&size_in_bytes[..4]);
                 shmem.as_mut_slice()[SHMEM_FUZZ_HDR_SIZE..(SHMEM_FUZZ_HDR_SIZE + size)]
-                    .copy_from_slice(target_bytes.as_slice());
+                    .copy_from_slice(&target_bytes.as_slice()[..size]);
             }
             None => {
                 self.executor
@@ -447,6 +508,10 @@ where
             self.executor.forkserver_mut().set_status(status);
             if libc::WIFSIGNALED(self.executor.forkserver().status()) {
                 exit_kind = ExitKind::Crash;
+                crate::triage::record_crash(crate::triage::CrashInfo {
+                    signal: Some(libc::WTERMSIG(self.executor.forkserver().status())),
+                    ..crate::triage::CrashInfo::default()
+                });
             }
         } else {
             self.executor.forkserver_mut().set_last_run_timed_out(1);
@@ -484,9 +549,12 @@ where
     forkserver: Forkserver,
     observers: OT,
     map: Option<SP::ShMem>,
+    coverage_map_size: Option<usize>,
     phantom: PhantomData<(I, S)>,
     /// Cache that indicates if we have a asan observer registered.
     has_asan_observer: Option<bool>,
+    /// If we have a sanitizer report observer registered, and log the `UBSan` reports for it.
+    has_sanitizer_observer: bool,
 }
 
 impl<I, OT, S, SP> Debug for ForkserverExecutor<I, OT, S, SP>
@@ -502,6 +570,7 @@ where
             .field("forkserver", &self.forkserver)
             .field("observers", &self.observers)
             .field("map", &self.map)
+            .field("coverage_map_size", &self.coverage_map_size)
             .finish()
     }
 }
@@ -539,6 +608,11 @@ where
     pub fn out_file(&self) -> &OutFile {
         &self.out_file
     }
+
+    /// The size of the coverage map of the target, if it announced it during the handshake
+    pub fn coverage_map_size(&self) -> Option<usize> {
+        self.coverage_map_size
+    }
 }
 
 /// The builder for `ForkserverExecutor`
@@ -552,6 +626,7 @@ pub struct ForkserverExecutorBuilder<'a, SP> {
     autotokens: Option<&'a mut Tokens>,
     out_filename: Option<OsString>,
     shmem_provider: Option<&'a mut SP>,
+    map_size: Option<usize>,
 }
 
 impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
@@ -573,7 +648,7 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
 
         let out_file = OutFile::create(&out_filename)?;
 
-        let map = match &mut self.shmem_provider {
+        let mut map = match &mut self.shmem_provider {
             None => None,
             Some(provider) => {
                 // setup shared memory
@@ -586,12 +661,34 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             }
         };
 
+        let has_sanitizer_observer = observers
+            .match_name::<SanitizerReportObserver>("SanitizerReportObserver")
+            .is_some();
+        let mut envs = self.envs.clone();
+        if has_sanitizer_observer {
+            // Only log the UBSan reports if they are read, first so the user can override it
+            envs.insert(
+                0,
+                (
+                    OsString::from("UBSAN_OPTIONS"),
+                    OsString::from(format!("print_stacktrace=1:log_path={}", ASAN_LOG_PATH)),
+                ),
+            );
+        }
+        if let Some(map_size) = self.map_size {
+            // Tells the AFL++ runtime how large the coverage map is
+            envs.push((
+                OsString::from("AFL_MAP_SIZE"),
+                OsString::from(map_size.to_string()),
+            ));
+        }
+
         let (target, mut forkserver) = match &self.program {
             Some(t) => {
                 let forkserver = Forkserver::new(
                     t.clone(),
                     self.arguments.clone(),
-                    self.envs.clone(),
+                    envs,
                     out_file.as_raw_fd(),
                     self.use_stdin,
                     0,
@@ -615,13 +712,31 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             ));
         }
         println!("All right - fork server is up.");
+        let mut coverage_map_size = None;
         // If forkserver is responding, we then check if there's any option enabled.
         if status & FS_OPT_ENABLED == FS_OPT_ENABLED {
             let mut send_status = FS_OPT_ENABLED;
 
+            if status & FS_OPT_MAPSIZE == FS_OPT_MAPSIZE {
+                let target_map_size = fs_opt_get_mapsize(status);
+                println!("Target coverage map size: {}", target_map_size);
+                if let Some(map_size) = self.map_size {
+                    if target_map_size > map_size {
+                        return Err(Error::Forkserver(format!(
+                            "The target needs a coverage map of {} bytes, but the map has only {} bytes",
+                            target_map_size, map_size
+                        )));
+                    }
+                }
+                coverage_map_size = Some(target_map_size);
+            }
+
             if (status & FS_OPT_SHDMEM_FUZZ == FS_OPT_SHDMEM_FUZZ) && map.is_some() {
                 println!("Using SHARED MEMORY FUZZING feature.");
                 send_status |= FS_OPT_SHDMEM_FUZZ;
+            } else {
+                // The target reads the testcases from the file
+                map = None;
             }
 
             if (status & FS_OPT_AUTODICT == FS_OPT_AUTODICT) && self.autotokens.is_some() {
@@ -629,11 +744,15 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
                 send_status |= FS_OPT_AUTODICT;
             }
 
-            let send_len = forkserver.write_ctl(send_status)?;
-            if send_len != 4 {
-                return Err(Error::Forkserver(
-                    "Writing to forkserver failed.".to_string(),
-                ));
+            // As `afl-fuzz`, only answer if the target asked for shared memory testcases or
+            // offered a dictionary, the `AFL++` runtime does not read the answer otherwise
+            if status & (FS_OPT_SHDMEM_FUZZ | FS_OPT_AUTODICT) != 0 {
+                let send_len = forkserver.write_ctl(send_status)?;
+                if send_len != 4 {
+                    return Err(Error::Forkserver(
+                        "Writing to forkserver failed.".to_string(),
+                    ));
+                }
             }
 
             if (send_status & FS_OPT_AUTODICT) == FS_OPT_AUTODICT {
@@ -666,6 +785,7 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             }
         } else {
             println!("Forkserver Options are not available.");
+            map = None;
         }
 
         println!(
@@ -682,8 +802,10 @@ impl<'a, SP> ForkserverExecutorBuilder<'a, SP> {
             forkserver,
             observers,
             map,
+            coverage_map_size,
             phantom: PhantomData,
             has_asan_observer: None, // initialized on first use
+            has_sanitizer_observer,
         })
     }
 }
@@ -705,6 +827,7 @@ impl<'a> ForkserverExecutorBuilder<'a, StdShMemProvider> {
             autotokens: None,
             out_filename: None,
             shmem_provider: None,
+            map_size: None,
         }
     }
 
@@ -830,6 +953,26 @@ impl<'a> ForkserverExecutorBuilder<'a, StdShMemProvider> {
         self
     }
 
+    /// Runs the target in persistent mode: it reruns its harness in a loop, stopping after each
+    /// run, as with `__AFL_LOOP` in `AFL++` or `libafl_targets::forkserver::persistent_loop`.
+    /// This sets `__AFL_PERSISTENT` for the target, as `afl-fuzz` does for persistent targets.
+    #[must_use]
+    pub fn is_persistent(mut self, is_persistent: bool) -> Self {
+        if is_persistent {
+            self.envs
+                .push((OsString::from("__AFL_PERSISTENT"), OsString::from("1")));
+        }
+        self
+    }
+
+    /// The size of the coverage map, passed to the target as `AFL_MAP_SIZE`.
+    /// The build fails if the target announces a larger map during the handshake.
+    #[must_use]
+    pub fn coverage_map_size(mut self, size: usize) -> Self {
+        self.map_size = Some(size);
+        self
+    }
+
     /// Shmem provider for forkserver's shared memory testcase feature.
     pub fn shmem_provider<SP: ShMemProvider>(
         self,
@@ -844,6 +987,7 @@ impl<'a> ForkserverExecutorBuilder<'a, StdShMemProvider> {
             autotokens: self.autotokens,
             out_filename: self.out_filename,
             shmem_provider: Some(shmem_provider),
+            map_size: self.map_size,
         }
     }
 }
@@ -874,12 +1018,13 @@ where
         match &mut self.map {
             Some(map) => {
                 let target_bytes = input.target_bytes();
-                let size = target_bytes.as_slice().len();
+                // Longer testcases are truncated, as AFL++ does
+                let size = target_bytes.as_slice().len().min(MAX_FILE);
                 let size_in_bytes = size.to_ne_bytes();
                 // The first four bytes tells the size of the shmem.
                 map.as_mut_slice()[..4].copy_from_slice(&size_in_bytes[..4]);
                 map.as_mut_slice()[SHMEM_FUZZ_HDR_SIZE..(SHMEM_FUZZ_HDR_SIZE + size)]
-                    .copy_from_slice(target_bytes.as_slice());
+                    .copy_from_slice(&target_bytes.as_slice()[..size]);
             }
             None => {
                 self.out_file.write_buf(input.target_bytes().as_slice())?;
@@ -919,21 +1064,46 @@ where
 
         self.forkserver.set_status(status);
 
-        if libc::WIFSIGNALED(self.forkserver.status()) {
-            exit_kind = ExitKind::Crash;
-            if self.has_asan_observer.is_none() {
-                self.has_asan_observer = Some(
-                    self.observers()
-                        .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
-                        .is_some(),
-                );
+        let signaled = libc::WIFSIGNALED(self.forkserver.status());
+        if self.has_asan_observer.is_none() {
+            self.has_asan_observer = Some(
+                self.observers()
+                    .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
+                    .is_some(),
+            );
+        }
+
+        // UBSan also logs the reports of runs that did not crash, so read the log after every run
+        let mut fault_address = None;
+        if self.has_sanitizer_observer {
+            let log_path = format!("{}.{}", ASAN_LOG_PATH, pid);
+            let log = fs::read_to_string(&log_path).unwrap_or_default();
+            let observer = self
+                .observers_mut()
+                .match_name_mut::<SanitizerReportObserver>("SanitizerReportObserver")
+                .unwrap();
+            observer.parse_output(&log);
+            fault_address = observer.report().and_then(|report| report.address);
+            // The ASANBacktraceObserver removes the log of a crash after it read it
+            if !(signaled && self.has_asan_observer.unwrap()) {
+                drop(fs::remove_file(&log_path));
             }
+        }
+
+        if signaled {
+            exit_kind = ExitKind::Crash;
+            let crash_info = crate::triage::CrashInfo {
+                signal: Some(libc::WTERMSIG(self.forkserver.status())),
+                fault_address,
+                ..crate::triage::CrashInfo::default()
+            };
             if self.has_asan_observer.unwrap() {
                 self.observers_mut()
                     .match_name_mut::<ASANBacktraceObserver>("ASANBacktraceObserver")
                     .unwrap()
                     .parse_asan_output_from_asan_log_file(pid)?;
             }
+            crate::triage::record_crash(crash_info);
         }
 
         self.forkserver.set_child_pid(Pid::from_raw(0));
@@ -1014,6 +1184,26 @@ where
     }
 }
 
+/// The `AFL` forkserver protocol needs a round trip per run, the batch is sent back to back:
+/// in persistent mode, the target loop keeps running from one input of the batch to the next.
+impl<EM, I, OT, S, SP, Z> BatchExecutor<EM, I, OT, S, Z> for ForkserverExecutor<I, OT, S, SP>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+    S: HasExecutions,
+    SP: ShMemProvider,
+{
+}
+
+impl<E, EM, I, OT, S, Z> BatchExecutor<EM, I, OT, S, Z> for TimeoutForkserverExecutor<E>
+where
+    E: Debug + Executor<EM, I, S, Z> + HasForkserver + HasObservers<I, OT, S>,
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+    S: HasExecutions,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
@@ -1064,4 +1254,14 @@ mod tests {
         };
         assert!(result);
     }
+
+    #[test]
+    fn test_fs_opt_get_mapsize() {
+        // As AFL++ sends it, `FS_OPT_SET_MAPSIZE(x) = ((x - 1) << 1)`
+        assert_eq!(super::fs_opt_get_mapsize((65536 - 1) << 1), 65536);
+        assert_eq!(
+            super::fs_opt_get_mapsize(super::FS_OPT_ENABLED | super::FS_OPT_MAPSIZE | (1023 << 1)),
+            1024
+        );
+    }
 }
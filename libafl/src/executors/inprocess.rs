@@ -25,11 +25,22 @@ use std::intrinsics::transmute;
 #[cfg(all(feature = "std", unix))]
 use libc::{siginfo_t, ucontext_t};
 
+#[cfg(all(feature = "std", unix))]
+use core::time::Duration;
+
 #[cfg(all(feature = "std", unix))]
 use nix::{
-    sys::wait::{waitpid, WaitStatus},
-    unistd::{fork, ForkResult},
+    errno::Errno,
+    sys::{
+        select::{pselect, FdSet},
+        signal::{kill, SigSet, Signal as NixSignal},
+        time::{TimeSpec, TimeValLike},
+        wait::{waitpid, WaitStatus},
+    },
+    unistd::{close, fork, pipe, read, write, ForkResult, Pid},
 };
+#[cfg(all(feature = "std", unix))]
+use std::os::unix::io::RawFd;
 
 #[cfg(unix)]
 use crate::bolts::os::unix_signals::setup_signal_handler;
@@ -617,10 +628,15 @@ mod unix_signal_handler {
                         .objective_mut()
                         .append_metadata(state, &mut new_testcase)
                         .expect("Failed adding metadata");
-                    state
+                    let idx = state
                         .solutions_mut()
                         .add(new_testcase)
                         .expect("In timeout handler solutions failure.");
+                    if let Some(hooks) = fuzzer.objective_hooks_mut() {
+                        hooks
+                            .on_solution(state, idx, None)
+                            .expect("In timeout handler objective hooks failure.");
+                    }
                     event_mgr
                         .fire(
                             state,
@@ -698,10 +714,15 @@ mod unix_signal_handler {
                 .objective_mut()
                 .append_metadata(state, &mut new_testcase)
                 .expect("Failed adding metadata");
-            state
+            let idx = state
                 .solutions_mut()
                 .add(new_testcase)
                 .expect("In timeout handler solutions failure.");
+            if let Some(hooks) = fuzzer.objective_hooks_mut() {
+                hooks
+                    .on_solution(state, idx, None)
+                    .expect("In timeout handler objective hooks failure.");
+            }
             event_mgr
                 .fire(
                     state,
@@ -764,6 +785,41 @@ mod unix_signal_handler {
                 .post_exec_all(state, input, &ExitKind::Crash)
                 .expect("Observers post_exec_all failed");
 
+            // Only the faults carry a meaningful address
+            #[cfg(target_os = "android")]
+            let fault_address =
+                u64::from(_info._pad[0] as u32) | (u64::from(_info._pad[1] as u32) << 32);
+            #[cfg(not(target_os = "android"))]
+            let fault_address = _info.si_addr() as u64;
+            let fault_address = match signal {
+                Signal::SigSegmentationFault
+                | Signal::SigBus
+                | Signal::SigIllegalInstruction
+                | Signal::SigFloatingPointException => Some(fault_address),
+                _ => None,
+            };
+            #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+            #[allow(clippy::cast_sign_loss)]
+            let stack_pointer = Some(_context.uc_mcontext.gregs[libc::REG_RSP as usize] as u64);
+            #[cfg(all(
+                any(target_os = "linux", target_os = "android"),
+                target_arch = "aarch64"
+            ))]
+            let stack_pointer = Some(_context.uc_mcontext.sp);
+            #[cfg(not(any(
+                all(target_os = "linux", target_arch = "x86_64"),
+                all(
+                    any(target_os = "linux", target_os = "android"),
+                    target_arch = "aarch64"
+                )
+            )))]
+            let stack_pointer = None;
+            crate::triage::record_crash(crate::triage::CrashInfo {
+                signal: Some(signal as i32),
+                fault_address,
+                stack_pointer,
+            });
+
             #[cfg(feature = "std")]
             eprintln!("Child crashed!");
 
@@ -789,10 +845,15 @@ mod unix_signal_handler {
                     .objective_mut()
                     .append_metadata(state, &mut new_testcase)
                     .expect("Failed adding metadata");
-                state
+                let idx = state
                     .solutions_mut()
                     .add(new_testcase)
                     .expect("In crash handler solutions failure.");
+                if let Some(hooks) = fuzzer.objective_hooks_mut() {
+                    hooks
+                        .on_solution(state, idx, None)
+                        .expect("In crash handler objective hooks failure.");
+                }
                 event_mgr
                     .fire(
                         state,
@@ -862,7 +923,8 @@ mod windows_exception_handler {
 
     use crate::{
         bolts::os::windows_exceptions::{
-            ExceptionCode, Handler, CRASH_EXCEPTIONS, EXCEPTION_POINTERS,
+            exception_addresses, exception_code, ExceptionCode, Handler, CRASH_EXCEPTIONS,
+            EXCEPTION_POINTERS, SIGABRT,
         },
         corpus::{Corpus, Testcase},
         events::{Event, EventFirer, EventRestarter},
@@ -957,22 +1019,33 @@ mod windows_exception_handler {
                     .post_exec_all(state, input, &ExitKind::Crash)
                     .expect("Observers post_exec_all failed");
 
+                // A panic aborts, as the unix panic hook reports it
+                crate::triage::record_crash(crate::triage::CrashInfo {
+                    signal: Some(SIGABRT),
+                    ..crate::triage::CrashInfo::default()
+                });
+
                 let interesting = fuzzer
                     .objective_mut()
                     .is_interesting(state, event_mgr, input, observers, &ExitKind::Crash)
-                    .expect("In timeout handler objective failure.");
+                    .expect("In panic handler objective failure.");
 
                 if interesting {
                     let mut new_testcase = Testcase::new(input.clone());
-                    new_testcase.add_metadata(ExitKind::Timeout);
+                    new_testcase.add_metadata(ExitKind::Crash);
                     fuzzer
                         .objective_mut()
                         .append_metadata(state, &mut new_testcase)
                         .expect("Failed adding metadata");
-                    state
+                    let idx = state
                         .solutions_mut()
                         .add(new_testcase)
-                        .expect("In timeout handler solutions failure.");
+                        .expect("In panic handler solutions failure.");
+                    if let Some(hooks) = fuzzer.objective_hooks_mut() {
+                        hooks
+                            .on_solution(state, idx, None)
+                            .expect("In panic handler objective hooks failure.");
+                    }
                     event_mgr
                         .fire(
                             state,
@@ -980,7 +1053,7 @@ mod windows_exception_handler {
                                 objective_size: state.solutions().count(),
                             },
                         )
-                        .expect("Could not send timeouting input");
+                        .expect("Could not send crashing input");
                 }
 
                 event_mgr.on_restart(state).unwrap();
@@ -1059,10 +1132,15 @@ mod windows_exception_handler {
                         .objective_mut()
                         .append_metadata(state, &mut new_testcase)
                         .expect("Failed adding metadata");
-                    state
+                    let idx = state
                         .solutions_mut()
                         .add(new_testcase)
                         .expect("In timeout handler solutions failure.");
+                    if let Some(hooks) = fuzzer.objective_hooks_mut() {
+                        hooks
+                            .on_solution(state, idx, None)
+                            .expect("In timeout handler objective hooks failure.");
+                    }
                     event_mgr
                         .fire(
                             state,
@@ -1132,17 +1210,9 @@ mod windows_exception_handler {
             compiler_fence(Ordering::SeqCst);
         }
 
-        let code = ExceptionCode::try_from(
-            exception_pointers
-                .as_mut()
-                .unwrap()
-                .ExceptionRecord
-                .as_mut()
-                .unwrap()
-                .ExceptionCode
-                .0,
-        )
-        .unwrap();
+        // Null for the `abort` signals
+        let code = exception_code(exception_pointers);
+        let (crash_addr, fault_address) = exception_addresses(exception_pointers);
 
         #[cfg(feature = "std")]
         eprintln!("Crashed with {}", code);
@@ -1150,18 +1220,14 @@ mod windows_exception_handler {
             #[cfg(feature = "std")]
             {
                 eprintln!("Double crash\n");
-                let crash_addr = exception_pointers
-                    .as_mut()
-                    .unwrap()
-                    .ExceptionRecord
-                    .as_mut()
-                    .unwrap()
-                    .ExceptionAddress as usize;
-
                 eprintln!(
                 "We crashed at addr 0x{:x}, but are not in the target... Bug in the fuzzer? Exiting.",
-                    crash_addr
+                    crash_addr.unwrap_or_default()
                 );
+
+                let mut writer = std::io::BufWriter::new(std::io::stderr());
+                crate::bolts::minibsod::generate_minibsod(&mut writer, exception_pointers).unwrap();
+                writer.flush().unwrap();
             }
             #[cfg(feature = "std")]
             {
@@ -1194,15 +1260,31 @@ mod windows_exception_handler {
             // Make sure we don't crash in the crash handler forever.
             let input = data.take_current_input::<I>();
 
-            #[cfg(feature = "std")]
-            eprintln!("Child crashed!");
-            #[cfg(feature = "std")]
-            drop(stdout().flush());
-
             observers
                 .post_exec_all(state, input, &ExitKind::Crash)
                 .expect("Observers post_exec_all failed");
 
+            #[cfg(target_arch = "x86_64")]
+            let stack_pointer = exception_pointers
+                .as_ref()
+                .and_then(|pointers| pointers.ContextRecord.as_ref())
+                .map(|context| context.Rsp);
+            #[cfg(not(target_arch = "x86_64"))]
+            let stack_pointer = None;
+            crate::triage::record_crash(crate::triage::CrashInfo {
+                signal: Some(code.signal()),
+                fault_address: fault_address.map(|address| address as u64),
+                stack_pointer,
+            });
+
+            #[cfg(feature = "std")]
+            {
+                let mut writer = std::io::BufWriter::new(std::io::stderr());
+                writeln!(writer, "input: {:?}", input.generate_name(0)).unwrap();
+                crate::bolts::minibsod::generate_minibsod(&mut writer, exception_pointers).unwrap();
+                writer.flush().unwrap();
+            }
+
             let interesting = fuzzer
                 .objective_mut()
                 .is_interesting(state, event_mgr, input, observers, &ExitKind::Crash)
@@ -1216,10 +1298,15 @@ mod windows_exception_handler {
                     .objective_mut()
                     .append_metadata(state, &mut new_testcase)
                     .expect("Failed adding metadata");
-                state
+                let idx = state
                     .solutions_mut()
                     .add(new_testcase)
                     .expect("In crash handler solutions failure.");
+                if let Some(hooks) = fuzzer.objective_hooks_mut() {
+                    hooks
+                        .on_solution(state, idx, None)
+                        .expect("In crash handler objective hooks failure.");
+                }
                 event_mgr
                     .fire(
                         state,
@@ -1387,7 +1474,78 @@ impl Handler for InProcessForkExecutorGlobalData {
     }
 }
 
+/// The byte a forked child sends to the parent for the [`ExitKind`] its harness returned
+#[cfg(all(feature = "std", unix))]
+fn fork_report_byte(exit_kind: &ExitKind) -> u8 {
+    match exit_kind {
+        ExitKind::Crash => 1,
+        ExitKind::Oom => 2,
+        ExitKind::Timeout => 3,
+        _ => 0,
+    }
+}
+
+/// The [`ExitKind`] a forked child sent to the parent
+#[cfg(all(feature = "std", unix))]
+fn fork_reported_exit_kind(byte: u8) -> ExitKind {
+    match byte {
+        1 => ExitKind::Crash,
+        2 => ExitKind::Oom,
+        3 => ExitKind::Timeout,
+        _ => ExitKind::Ok,
+    }
+}
+
+/// Waits for the forked `child` to report its [`ExitKind`] on `alive_read`, or to exit.
+/// Kills it after `timeout`, returning [`ExitKind::Timeout`].
+/// Returns `None` if the child exited without reporting, e.g. calling `exit` in the harness.
+#[cfg(all(feature = "std", unix))]
+fn await_fork_child(
+    child: Pid,
+    alive_read: RawFd,
+    timeout: Option<Duration>,
+) -> Result<Option<ExitKind>, Error> {
+    if let Some(timeout) = timeout {
+        #[allow(clippy::cast_possible_wrap)]
+        let timeout = TimeSpec::milliseconds(timeout.as_millis() as i64);
+        let ready = loop {
+            let mut readfds = FdSet::new();
+            readfds.insert(alive_read);
+            // Returns once the child reported or exited, closing the pipe, or on timeout
+            match pselect(
+                Some(alive_read + 1),
+                &mut readfds,
+                None,
+                None,
+                Some(&timeout),
+                Some(&SigSet::empty()),
+            ) {
+                Err(Errno::EINTR) => continue,
+                res => break res?,
+            }
+        };
+        if ready == 0 {
+            drop(kill(child, NixSignal::SIGKILL));
+            return Ok(Some(ExitKind::Timeout));
+        }
+    }
+    let mut byte = [0_u8; 1];
+    loop {
+        match read(alive_read, &mut byte) {
+            Ok(1) => return Ok(Some(fork_reported_exit_kind(byte[0]))),
+            Err(Errno::EINTR) => continue,
+            Ok(_) => return Ok(None),
+            Err(e) => return Err(Error::from(e)),
+        }
+    }
+}
+
 /// [`InProcessForkExecutor`] is an executor that forks the current process before each execution.
+/// The harness runs in the child, so that crashes and corrupted global state do not reach the
+/// fuzzer. The observers have to live in shared memory to see what the child did.
+///
+/// With a timeout, see [`InProcessForkExecutor::with_timeout`], children running for too long are
+/// killed and reported as [`ExitKind::Timeout`].
 #[cfg(all(feature = "std", unix))]
 pub struct InProcessForkExecutor<'a, H, I, OT, S, SP>
 where
@@ -1400,6 +1558,7 @@ where
     shmem_provider: SP,
     observers: OT,
     handlers: InChildProcessHandlers,
+    timeout: Option<Duration>,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -1415,6 +1574,7 @@ where
         f.debug_struct("InProcessForkExecutor")
             .field("observers", &self.observers)
             .field("shmem_provider", &self.shmem_provider)
+            .field("timeout", &self.timeout)
             .finish()
     }
 }
@@ -1438,10 +1598,18 @@ where
         input: &I,
     ) -> Result<ExitKind, Error> {
         unsafe {
-            self.shmem_provider.pre_fork()?;
+            // The child reports the exit kind of the harness on the pipe, and holds the write end
+            // until it exits, so the parent can wait with a timeout
+            let (alive_read, alive_write) = pipe()?;
+            if let Err(e) = self.shmem_provider.pre_fork() {
+                drop(close(alive_read));
+                drop(close(alive_write));
+                return Err(e);
+            }
             match fork() {
                 Ok(ForkResult::Child) => {
                     // Child
+                    drop(close(alive_read));
                     self.shmem_provider.post_fork(true)?;
 
                     self.handlers.pre_run_target(self, state, input);
@@ -1450,12 +1618,13 @@ where
                         .pre_exec_child_all(state, input)
                         .expect("Failed to run post_exec on observers");
 
-                    (self.harness_fn)(input);
+                    let exit_kind = (self.harness_fn)(input);
 
                     self.observers
-                        .post_exec_child_all(state, input, &ExitKind::Ok)
+                        .post_exec_child_all(state, input, &exit_kind)
                         .expect("Failed to run post_exec on observers");
 
+                    drop(write(alive_write, &[fork_report_byte(&exit_kind)]));
                     std::process::exit(0);
 
                     Ok(ExitKind::Ok)
@@ -1463,16 +1632,44 @@ where
                 Ok(ForkResult::Parent { child }) => {
                     // Parent
                     // println!("from parent {} child is {}", std::process::id(), child);
-                    self.shmem_provider.post_fork(false)?;
+                    drop(close(alive_write));
+                    let reported = self
+                        .shmem_provider
+                        .post_fork(false)
+                        .and_then(|()| await_fork_child(child, alive_read, self.timeout));
+                    drop(close(alive_read));
+                    let reported = match reported {
+                        Ok(reported) => reported,
+                        Err(e) => {
+                            // Do not leave the child running, nor a zombie
+                            drop(kill(child, NixSignal::SIGKILL));
+                            drop(waitpid(child, None));
+                            return Err(e);
+                        }
+                    };
 
                     let res = waitpid(child, None)?;
 
                     match res {
-                        WaitStatus::Signaled(_, _, _) => Ok(ExitKind::Crash),
-                        _ => Ok(ExitKind::Ok),
+                        // Killed on timeout
+                        WaitStatus::Signaled(..) if reported == Some(ExitKind::Timeout) => {
+                            Ok(ExitKind::Timeout)
+                        }
+                        WaitStatus::Signaled(_, signal, _) => {
+                            crate::triage::record_crash(crate::triage::CrashInfo {
+                                signal: Some(signal as i32),
+                                ..crate::triage::CrashInfo::default()
+                            });
+                            Ok(ExitKind::Crash)
+                        }
+                        _ => Ok(reported.unwrap_or(ExitKind::Ok)),
                     }
                 }
-                Err(e) => Err(Error::from(e)),
+                Err(e) => {
+                    drop(close(alive_read));
+                    drop(close(alive_write));
+                    Err(Error::from(e))
+                }
             }
         }
     }
@@ -1507,10 +1704,25 @@ where
             shmem_provider,
             observers,
             handlers,
+            timeout: None,
             phantom: PhantomData,
         })
     }
 
+    /// Kill the children running for longer than `timeout`, reporting a [`ExitKind::Timeout`]
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The timeout of each run, if any
+    #[inline]
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
     /// Retrieve the harness function.
     #[inline]
     pub fn harness(&self) -> &H {
@@ -1659,6 +1871,7 @@ mod tests {
             shmem_provider: provider,
             observers: tuple_list!(),
             handlers: InChildProcessHandlers::nop(),
+            timeout: None,
             phantom: PhantomData,
         };
         let input = NopInput {};
@@ -1666,6 +1879,71 @@ mod tests {
             .run_target(&mut (), &mut (), &mut (), &input)
             .is_ok());
     }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "fork", unix))]
+    fn test_inprocessfork_exit_kinds() {
+        use core::time::Duration;
+
+        use crate::executors::inprocess::InChildProcessHandlers;
+
+        let provider = StdShMemProvider::new().unwrap();
+
+        let mut harness = |_buf: &NopInput| {
+            std::thread::sleep(Duration::from_secs(10));
+            ExitKind::Ok
+        };
+        let mut in_process_fork_executor = InProcessForkExecutor::<_, NopInput, (), (), _> {
+            harness_fn: &mut harness,
+            shmem_provider: provider,
+            observers: tuple_list!(),
+            handlers: InChildProcessHandlers::nop(),
+            timeout: Some(Duration::from_millis(100)),
+            phantom: PhantomData,
+        };
+        let input = NopInput {};
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Timeout
+        );
+
+        let provider = StdShMemProvider::new().unwrap();
+        let mut harness = |_buf: &NopInput| ExitKind::Oom;
+        let mut in_process_fork_executor = InProcessForkExecutor::<_, NopInput, (), (), _> {
+            harness_fn: &mut harness,
+            shmem_provider: provider,
+            observers: tuple_list!(),
+            handlers: InChildProcessHandlers::nop(),
+            timeout: None,
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Oom
+        );
+
+        // The exit code of a harness exiting on its own is no exit kind
+        let provider = StdShMemProvider::new().unwrap();
+        let mut harness = |_buf: &NopInput| -> ExitKind { std::process::exit(73) };
+        let mut in_process_fork_executor = InProcessForkExecutor::<_, NopInput, (), (), _> {
+            harness_fn: &mut harness,
+            shmem_provider: provider,
+            observers: tuple_list!(),
+            handlers: InChildProcessHandlers::nop(),
+            timeout: None,
+            phantom: PhantomData,
+        };
+        assert_eq!(
+            in_process_fork_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Ok
+        );
+    }
 }
 
 #[cfg(feature = "python")]
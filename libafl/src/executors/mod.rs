@@ -6,12 +6,14 @@ pub use inprocess::InProcessExecutor;
 pub use inprocess::InProcessForkExecutor;
 
 pub mod differential;
-pub use differential::DiffExecutor;
+pub use differential::{DiffExecutor, DiffExecutorObservers};
 
 /// Timeout executor.
 /// Not possible on `no-std` Windows or `no-std`, but works for unix
 #[cfg(any(unix, feature = "std"))]
 pub mod timeout;
+#[cfg(target_os = "linux")]
+pub use timeout::TimeoutBackend;
 #[cfg(any(unix, feature = "std"))]
 pub use timeout::TimeoutExecutor;
 
@@ -29,11 +31,22 @@ pub use shadow::ShadowExecutor;
 pub mod with_observers;
 pub use with_observers::WithObservers;
 
+pub mod batch;
+pub use batch::BatchExecutor;
+
+pub mod repeat;
+pub use repeat::{BatchRunMetadata, HasRepeatRuns, RepeatExecutor};
+
 #[cfg(all(feature = "std", unix))]
 pub mod command;
 #[cfg(all(feature = "std", unix))]
 pub use command::CommandExecutor;
 
+#[cfg(feature = "std")]
+pub mod network;
+#[cfg(feature = "std")]
+pub use network::{NetworkExecutor, NetworkProtocol};
+
 use crate::{
     bolts::AsSlice,
     inputs::{HasTargetBytes, Input},
@@ -0,0 +1,393 @@
+//! The [`NetworkExecutor`] sends each input to a live server over TCP or UDP, for black-box
+//! protocol fuzzing.
+//!
+//! The server runs on its own, e.g. under a supervisor restarting it after a crash. A run counts as
+//! a crash if the server cannot be reached, or if it is dead after the run: the optional liveness
+//! probe decides, or, without one, if the server closed the connection and refuses a new one.
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
+};
+
+use crate::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    Error,
+};
+
+/// The default timeout for connecting and for waiting on a response
+pub const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The transport protocol of a [`NetworkExecutor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkProtocol {
+    /// A TCP stream
+    Tcp,
+    /// UDP datagrams
+    Udp,
+}
+
+/// An open connection to the server
+#[derive(Debug)]
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl Connection {
+    fn send(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.write_all(buf),
+            Connection::Udp(socket) => socket.send(buf).map(|_| ()),
+        }
+    }
+
+    fn recv(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.read(buf),
+            Connection::Udp(socket) => socket.recv(buf),
+        }
+    }
+}
+
+/// The result of a run on the server
+enum RunResult {
+    /// The server answered, or nobody waits for an answer
+    Done,
+    /// No matching response before the timeout
+    Timeout,
+    /// The server closed the connection, or it failed; the server may still be alive
+    Closed,
+}
+
+/// An executor sending the input to a server, optionally waiting for a response.
+///
+/// By default, it connects anew for each run; with [`NetworkExecutor::with_reuse_connection`] it
+/// keeps the connection open as long as it works.
+pub struct NetworkExecutor<I, OT, S> {
+    addr: SocketAddr,
+    protocol: NetworkProtocol,
+    reuse_connection: bool,
+    timeout: Duration,
+    connection: Option<Connection>,
+    response: Vec<u8>,
+    #[allow(clippy::type_complexity)]
+    response_matcher: Option<Box<dyn FnMut(&[u8]) -> bool>>,
+    liveness_probe: Option<Box<dyn FnMut() -> bool>>,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, OT, S> Debug for NetworkExecutor<I, OT, S>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetworkExecutor")
+            .field("addr", &self.addr)
+            .field("protocol", &self.protocol)
+            .field("reuse_connection", &self.reuse_connection)
+            .field("timeout", &self.timeout)
+            .field("connected", &self.connection.is_some())
+            .field("observers", &self.observers)
+            .finish()
+    }
+}
+
+impl<I, OT, S> NetworkExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`NetworkExecutor`] for the server at `addr`
+    pub fn new(addr: SocketAddr, protocol: NetworkProtocol, observers: OT) -> Self {
+        Self {
+            addr,
+            protocol,
+            reuse_connection: false,
+            timeout: DEFAULT_NETWORK_TIMEOUT,
+            connection: None,
+            response: Vec::new(),
+            response_matcher: None,
+            liveness_probe: None,
+            observers,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`NetworkExecutor`] for the TCP server at `addr`
+    pub fn tcp(addr: SocketAddr, observers: OT) -> Self {
+        Self::new(addr, NetworkProtocol::Tcp, observers)
+    }
+
+    /// Creates a new [`NetworkExecutor`] for the UDP server at `addr`
+    pub fn udp(addr: SocketAddr, observers: OT) -> Self {
+        Self::new(addr, NetworkProtocol::Udp, observers)
+    }
+
+    /// Keeps the connection open between the runs, instead of connecting for each run
+    #[must_use]
+    pub fn with_reuse_connection(mut self, reuse_connection: bool) -> Self {
+        self.reuse_connection = reuse_connection;
+        self
+    }
+
+    /// Sets the timeout for connecting, and for waiting on a response
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Waits after each input until the response received so far satisfies `matcher`.
+    /// Without a match before the timeout, the run is a [`ExitKind::Timeout`].
+    #[must_use]
+    pub fn with_response_matcher<F>(mut self, matcher: F) -> Self
+    where
+        F: FnMut(&[u8]) -> bool + 'static,
+    {
+        self.response_matcher = Some(Box::new(matcher));
+        self
+    }
+
+    /// Asks `probe` after each run if the server is still alive, e.g. by checking its pid or a
+    /// health endpoint. A dead server makes the run a [`ExitKind::Crash`].
+    /// Without a probe, a TCP server closing the connection is alive if it accepts a new one,
+    /// and a UDP server refusing a datagram is dead.
+    #[must_use]
+    pub fn with_liveness_probe<F>(mut self, probe: F) -> Self
+    where
+        F: FnMut() -> bool + 'static,
+    {
+        self.liveness_probe = Some(Box::new(probe));
+        self
+    }
+
+    /// The address of the server
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The response of the server to the last input
+    #[must_use]
+    pub fn response(&self) -> &[u8] {
+        &self.response
+    }
+
+    fn connect(&self) -> io::Result<Connection> {
+        match self.protocol {
+            NetworkProtocol::Tcp => {
+                let stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(self.timeout))?;
+                Ok(Connection::Tcp(stream))
+            }
+            NetworkProtocol::Udp => {
+                let socket = if self.addr.is_ipv4() {
+                    UdpSocket::bind("0.0.0.0:0")?
+                } else {
+                    UdpSocket::bind("[::]:0")?
+                };
+                socket.connect(self.addr)?;
+                socket.set_read_timeout(Some(self.timeout))?;
+                Ok(Connection::Udp(socket))
+            }
+        }
+    }
+
+    /// Sends `buf` and waits for the response, if there is a matcher
+    fn send_and_receive(&mut self, connection: &mut Connection, buf: &[u8]) -> RunResult {
+        if connection.send(buf).is_err() {
+            return RunResult::Closed;
+        }
+        let matcher = match &mut self.response_matcher {
+            Some(matcher) => matcher,
+            None => return RunResult::Done,
+        };
+
+        let mut chunk = [0_u8; 4096];
+        loop {
+            match connection.recv(&mut chunk) {
+                // The server hung up before answering; for UDP, this is an empty datagram
+                Ok(0) if self.protocol == NetworkProtocol::Tcp => return RunResult::Closed,
+                Ok(len) => {
+                    self.response.extend_from_slice(&chunk[..len]);
+                    if matcher(&self.response) {
+                        return RunResult::Done;
+                    }
+                }
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return RunResult::Timeout;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => (),
+                Err(_) => return RunResult::Closed,
+            }
+        }
+    }
+
+    /// If the server is still alive after a run, asking the liveness probe first
+    fn is_alive(&mut self, result: &RunResult) -> bool {
+        if let Some(probe) = &mut self.liveness_probe {
+            return probe();
+        }
+        match (result, self.protocol) {
+            (RunResult::Closed, NetworkProtocol::Tcp) => self.connect().is_ok(),
+            (RunResult::Closed, NetworkProtocol::Udp) => false,
+            _ => true,
+        }
+    }
+}
+
+impl<EM, I, OT, S, Z> Executor<EM, I, S, Z> for NetworkExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        self.response.clear();
+
+        let mut connection = match self.connection.take() {
+            Some(connection) => connection,
+            None => match self.connect() {
+                Ok(connection) => connection,
+                Err(err) if err.kind() == ErrorKind::TimedOut => return Ok(ExitKind::Timeout),
+                // Nobody listens anymore
+                Err(_) => return Ok(ExitKind::Crash),
+            },
+        };
+
+        let target_bytes = input.target_bytes();
+        let result = self.send_and_receive(&mut connection, target_bytes.as_slice());
+
+        if !self.is_alive(&result) {
+            return Ok(ExitKind::Crash);
+        }
+        match result {
+            // The server hung up on this input, but lives on
+            RunResult::Closed => Ok(ExitKind::Ok),
+            RunResult::Timeout => Ok(ExitKind::Timeout),
+            RunResult::Done => {
+                if self.reuse_connection {
+                    self.connection = Some(connection);
+                }
+                Ok(ExitKind::Ok)
+            }
+        }
+    }
+}
+
+impl<I, OT, S> HasObservers<I, OT, S> for NetworkExecutor<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use crate::{
+        executors::{network::NetworkExecutor, Executor, ExitKind},
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_network_executor_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            // Echo the first message of each connection, and hang up
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0_u8; 64];
+                let len = stream.read(&mut buf).unwrap();
+                stream.write_all(&buf[..len]).unwrap();
+            }
+        });
+
+        let mut executor = NetworkExecutor::<BytesInput, (), ()>::tcp(addr, ())
+            .with_response_matcher(|response| response.ends_with(b"\n"));
+        let exit_kind = executor
+            .run_target(
+                &mut (),
+                &mut (),
+                &mut (),
+                &BytesInput::new(b"hello\n".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+        assert_eq!(executor.response(), b"hello\n");
+
+        // The echo never matches, the server hangs up, but still accepts connections
+        let exit_kind = executor
+            .run_target(
+                &mut (),
+                &mut (),
+                &mut (),
+                &BytesInput::new(b"hello".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+
+        // The liveness probe has the last word
+        let mut executor = NetworkExecutor::<BytesInput, (), ()>::tcp(addr, ())
+            .with_response_matcher(|response| response.ends_with(b"\n"))
+            .with_liveness_probe(|| false);
+        let exit_kind = executor
+            .run_target(
+                &mut (),
+                &mut (),
+                &mut (),
+                &BytesInput::new(b"hello".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Crash);
+    }
+
+    #[test]
+    fn test_network_executor_dead_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let mut executor = NetworkExecutor::<BytesInput, (), ()>::tcp(addr, ());
+        let exit_kind = executor
+            .run_target(
+                &mut (),
+                &mut (),
+                &mut (),
+                &BytesInput::new(b"hello".to_vec()),
+            )
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Crash);
+    }
+}
@@ -0,0 +1,153 @@
+//! The [`RepeatExecutor`] runs each input several times in a row, for the observers accumulating
+//! their results over the runs, like the [`crate::observers::AccumulatingMapObserver`].
+
+use core::{fmt::Debug, marker::PhantomData};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::Input,
+    observers::ObserversTuple,
+    state::HasMetadata,
+    Error,
+};
+
+/// The position of the current run in the batch of runs of the same input
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchRunMetadata {
+    /// The index of the current run, `0` for the first run of an input
+    pub index: usize,
+    /// The number of runs of the batch
+    pub runs: usize,
+}
+
+crate::impl_serdeany!(
+    BatchRunMetadata,
+    "libafl::executors::repeat::BatchRunMetadata"
+);
+
+/// An executor running each input a configurable number of times
+pub trait HasRepeatRuns {
+    /// The number of runs of each input
+    fn runs(&self) -> usize;
+
+    /// Set the number of runs of each input
+    fn set_runs(&mut self, runs: usize);
+}
+
+/// A wrapper running each input `runs` times with the wrapped executor.
+/// The observers see each run, the feedbacks only the outcome of the batch.
+/// The batch stops at the first run not exiting with [`ExitKind::Ok`], and reports its exit kind.
+#[derive(Debug)]
+pub struct RepeatExecutor<E, OT>
+where
+    E: Debug,
+{
+    executor: E,
+    runs: usize,
+    phantom: PhantomData<OT>,
+}
+
+impl<E, OT> RepeatExecutor<E, OT>
+where
+    E: Debug,
+{
+    /// Run each input `runs` times with `executor`
+    pub fn new<I, S>(executor: E, runs: usize) -> Self
+    where
+        E: HasObservers<I, OT, S>,
+        OT: ObserversTuple<I, S>,
+    {
+        assert!(
+            runs > 0,
+            "A RepeatExecutor needs at least one run per input"
+        );
+        Self {
+            executor,
+            runs,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The wrapped executor
+    pub fn inner(&mut self) -> &mut E {
+        &mut self.executor
+    }
+}
+
+impl<E, OT> HasRepeatRuns for RepeatExecutor<E, OT>
+where
+    E: Debug,
+{
+    fn runs(&self) -> usize {
+        self.runs
+    }
+
+    fn set_runs(&mut self, runs: usize) {
+        assert!(
+            runs > 0,
+            "A RepeatExecutor needs at least one run per input"
+        );
+        self.runs = runs;
+    }
+}
+
+impl<E, EM, I, OT, S, Z> Executor<EM, I, S, Z> for RepeatExecutor<E, OT>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    S: HasMetadata,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let mut index = 0;
+        state.add_metadata(BatchRunMetadata {
+            index,
+            runs: self.runs,
+        });
+        loop {
+            let exit_kind = self.executor.run_target(fuzzer, state, mgr, input)?;
+            // The fuzzer runs the hooks of the observers around the last run of the batch
+            if index + 1 == self.runs || exit_kind != ExitKind::Ok {
+                return Ok(exit_kind);
+            }
+            self.executor.post_run_reset();
+            self.executor
+                .observers_mut()
+                .post_exec_all(state, input, &exit_kind)?;
+            // The observers see the index of the next run in `pre_exec`
+            index += 1;
+            state.add_metadata(BatchRunMetadata {
+                index,
+                runs: self.runs,
+            });
+            self.executor.observers_mut().pre_exec_all(state, input)?;
+        }
+    }
+
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for RepeatExecutor<E, OT>
+where
+    E: HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
@@ -55,6 +55,27 @@ where
     pub fn shadow_observers_mut(&mut self) -> &mut SOT {
         &mut self.shadow_observers
     }
+
+    /// Runs the target with the shadow observers on, for the stages that need them.
+    /// Only the shadow observers get their `pre_exec` and `post_exec` hooks called here, so the
+    /// tracing they enable, e.g. cmplog, costs nothing on the other executions.
+    pub fn run_target_shadowed<EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error>
+    where
+        E: Executor<EM, I, S, Z>,
+        I: Input,
+    {
+        self.shadow_observers.pre_exec_all(state, input)?;
+        let exit_kind = self.executor.run_target(fuzzer, state, mgr, input)?;
+        self.shadow_observers
+            .post_exec_all(state, input, &exit_kind)?;
+        Ok(exit_kind)
+    }
 }
 
 impl<E, EM, I, S, SOT, Z> Executor<EM, I, S, Z> for ShadowExecutor<E, I, S, SOT>
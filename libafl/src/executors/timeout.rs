@@ -22,7 +22,7 @@ use core::{mem::zeroed, ptr::null_mut};
 #[cfg(target_os = "linux")]
 use core::ptr::{addr_of, addr_of_mut};
 
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(unix)]
 use libc::c_int;
 
 #[cfg(all(windows, feature = "std"))]
@@ -42,13 +42,13 @@ use core::{ffi::c_void, ptr::write_volatile};
 use core::sync::atomic::{compiler_fence, Ordering};
 
 #[repr(C)]
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(unix)]
 struct Timeval {
     pub tv_sec: i64,
     pub tv_usec: i64,
 }
 
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(unix)]
 impl Debug for Timeval {
     #[allow(clippy::cast_sign_loss)]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -63,29 +63,59 @@ impl Debug for Timeval {
 }
 
 #[repr(C)]
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(unix)]
 #[derive(Debug)]
 struct Itimerval {
     pub it_interval: Timeval,
     pub it_value: Timeval,
 }
 
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(unix)]
 extern "C" {
     fn setitimer(which: c_int, new_value: *mut Itimerval, old_value: *mut Itimerval) -> c_int;
 }
 
-#[cfg(all(unix, not(target_os = "linux")))]
+#[cfg(unix)]
 const ITIMER_REAL: c_int = 0;
 
+/// An [`Itimerval`] firing once after `exec_tmout`
+#[cfg(unix)]
+fn itimerval_for(exec_tmout: Duration) -> Itimerval {
+    let milli_sec = exec_tmout.as_millis();
+    Itimerval {
+        it_interval: Timeval {
+            tv_sec: 0,
+            tv_usec: 0,
+        },
+        it_value: Timeval {
+            tv_sec: (milli_sec / 1000) as i64,
+            tv_usec: ((milli_sec % 1000) * 1000) as i64,
+        },
+    }
+}
+
+/// The timer a [`TimeoutExecutor`] arms before each run.
+/// Both deliver a `SIGALRM` to the process on timeout.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutBackend {
+    /// A POSIX timer, see `timer_create(2)`, the default
+    PosixTimer,
+    /// The process interval timer, see `setitimer(2)`, for environments without POSIX timers,
+    /// such as some emulators
+    Itimer,
+}
+
 /// The timeout executor is a wrapper that sets a timeout before each run
 pub struct TimeoutExecutor<E> {
     executor: E,
     #[cfg(target_os = "linux")]
+    backend: TimeoutBackend,
+    #[cfg(target_os = "linux")]
     itimerspec: libc::itimerspec,
     #[cfg(target_os = "linux")]
     timerid: libc::timer_t,
-    #[cfg(all(unix, not(target_os = "linux")))]
+    #[cfg(unix)]
     itimerval: Itimerval,
     #[cfg(windows)]
     milli_sec: i64,
@@ -108,6 +138,7 @@ impl<E: Debug> Debug for TimeoutExecutor<E> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("TimeoutExecutor")
             .field("executor", &self.executor)
+            .field("backend", &self.backend)
             .field(
                 "milli_sec",
                 &(&self.itimerspec.it_value.tv_sec * 1000
@@ -138,6 +169,11 @@ impl<E> TimeoutExecutor<E> {
     /// Create a new [`TimeoutExecutor`], wrapping the given `executor` and checking for timeouts.
     /// This should usually be used for `InProcess` fuzzing.
     pub fn new(executor: E, exec_tmout: Duration) -> Self {
+        Self::with_backend(executor, exec_tmout, TimeoutBackend::PosixTimer)
+    }
+
+    /// Create a new [`TimeoutExecutor`], arming the timer of the given `backend` before each run
+    pub fn with_backend(executor: E, exec_tmout: Duration, backend: TimeoutBackend) -> Self {
         let milli_sec = exec_tmout.as_millis();
         let it_value = libc::timespec {
             tv_sec: (milli_sec / 1000) as _,
@@ -152,17 +188,27 @@ impl<E> TimeoutExecutor<E> {
             it_value,
         };
         let mut timerid: libc::timer_t = null_mut();
-        unsafe {
-            // creates a new per-process interval timer
-            libc::timer_create(libc::CLOCK_MONOTONIC, null_mut(), addr_of_mut!(timerid));
+        if backend == TimeoutBackend::PosixTimer {
+            unsafe {
+                // creates a new per-process interval timer
+                libc::timer_create(libc::CLOCK_MONOTONIC, null_mut(), addr_of_mut!(timerid));
+            }
         }
         Self {
             executor,
+            backend,
             itimerspec,
             timerid,
+            itimerval: itimerval_for(exec_tmout),
         }
     }
 
+    /// The timer armed before each run
+    #[must_use]
+    pub fn backend(&self) -> TimeoutBackend {
+        self.backend
+    }
+
     /// Set the timeout for this executor
     pub fn set_timeout(&mut self, exec_tmout: Duration) {
         let milli_sec = exec_tmout.as_millis();
@@ -179,6 +225,7 @@ impl<E> TimeoutExecutor<E> {
             it_value,
         };
         self.itimerspec = itimerspec;
+        self.itimerval = itimerval_for(exec_tmout);
     }
 }
 
@@ -187,41 +234,15 @@ impl<E> TimeoutExecutor<E> {
     /// Create a new [`TimeoutExecutor`], wrapping the given `executor` and checking for timeouts.
     /// This should usually be used for `InProcess` fuzzing.
     pub fn new(executor: E, exec_tmout: Duration) -> Self {
-        let milli_sec = exec_tmout.as_millis();
-        let it_value = Timeval {
-            tv_sec: (milli_sec / 1000) as i64,
-            tv_usec: (milli_sec % 1000) as i64,
-        };
-        let it_interval = Timeval {
-            tv_sec: 0,
-            tv_usec: 0,
-        };
-        let itimerval = Itimerval {
-            it_interval,
-            it_value,
-        };
         Self {
             executor,
-            itimerval,
+            itimerval: itimerval_for(exec_tmout),
         }
     }
 
     /// Set the timeout for this executor
     pub fn set_timeout(&mut self, exec_tmout: Duration) {
-        let milli_sec = exec_tmout.as_millis();
-        let it_value = Timeval {
-            tv_sec: (milli_sec / 1000) as i64,
-            tv_usec: (milli_sec % 1000) as i64,
-        };
-        let it_interval = Timeval {
-            tv_sec: 0,
-            tv_usec: 0,
-        };
-        let itimerval = Itimerval {
-            it_interval,
-            it_value,
-        };
-        self.itimerval = itimerval;
+        self.itimerval = itimerval_for(exec_tmout);
     }
 }
 
@@ -349,7 +370,14 @@ where
         input: &I,
     ) -> Result<ExitKind, Error> {
         unsafe {
-            libc::timer_settime(self.timerid, 0, addr_of_mut!(self.itimerspec), null_mut());
+            match self.backend {
+                TimeoutBackend::PosixTimer => {
+                    libc::timer_settime(self.timerid, 0, addr_of_mut!(self.itimerspec), null_mut());
+                }
+                TimeoutBackend::Itimer => {
+                    setitimer(ITIMER_REAL, &mut self.itimerval, null_mut());
+                }
+            }
             let ret = self.executor.run_target(fuzzer, state, mgr, input);
             // reset timer
             self.post_run_reset();
@@ -359,8 +387,16 @@ where
 
     fn post_run_reset(&mut self) {
         unsafe {
-            let disarmed: libc::itimerspec = zeroed();
-            libc::timer_settime(self.timerid, 0, addr_of!(disarmed), null_mut());
+            match self.backend {
+                TimeoutBackend::PosixTimer => {
+                    let disarmed: libc::itimerspec = zeroed();
+                    libc::timer_settime(self.timerid, 0, addr_of!(disarmed), null_mut());
+                }
+                TimeoutBackend::Itimer => {
+                    let mut itimerval_zero: Itimerval = zeroed();
+                    setitimer(ITIMER_REAL, &mut itimerval_zero, null_mut());
+                }
+            }
         }
         self.executor.post_run_reset();
     }
@@ -411,3 +447,100 @@ where
         self.executor.observers_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use core::time::Duration;
+
+    #[cfg(target_os = "linux")]
+    use crate::{
+        executors::{
+            timeout::{TimeoutBackend, TimeoutExecutor},
+            Executor, ExitKind,
+        },
+        inputs::{BytesInput, Input},
+        Error,
+    };
+
+    #[cfg(unix)]
+    use crate::executors::timeout::itimerval_for;
+
+    /// Checks if the process interval timer is armed while running
+    #[cfg(target_os = "linux")]
+    #[derive(Debug, Default)]
+    struct ItimerProbe {
+        armed: bool,
+    }
+
+    #[cfg(target_os = "linux")]
+    impl<EM, I, S, Z> Executor<EM, I, S, Z> for ItimerProbe
+    where
+        I: Input,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            _input: &I,
+        ) -> Result<ExitKind, Error> {
+            self.armed = itimer_armed();
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn itimer_armed() -> bool {
+        let mut current: libc::itimerval = unsafe { core::mem::zeroed() };
+        unsafe {
+            libc::getitimer(libc::ITIMER_REAL, &mut current);
+        }
+        current.it_value.tv_sec != 0 || current.it_value.tv_usec != 0
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_itimerval_for() {
+        let itimerval = itimerval_for(Duration::from_millis(1500));
+        assert_eq!(itimerval.it_value.tv_sec, 1);
+        assert_eq!(itimerval.it_value.tv_usec, 500_000);
+        assert_eq!(itimerval.it_interval.tv_sec, 0);
+        assert_eq!(itimerval.it_interval.tv_usec, 0);
+
+        let itimerval = itimerval_for(Duration::from_millis(999));
+        assert_eq!(itimerval.it_value.tv_sec, 0);
+        assert_eq!(itimerval.it_value.tv_usec, 999_000);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_timeout_backends() {
+        let input = BytesInput::new(vec![1u8]);
+
+        let mut executor = TimeoutExecutor::new(ItimerProbe::default(), Duration::from_secs(60));
+        assert_eq!(executor.backend(), TimeoutBackend::PosixTimer);
+        executor
+            .run_target(&mut (), &mut (), &mut (), &input)
+            .unwrap();
+        assert!(!executor.executor.armed);
+        let mut current: libc::itimerspec = unsafe { core::mem::zeroed() };
+        unsafe {
+            libc::timer_gettime(executor.timerid, &mut current);
+        }
+        assert_eq!(current.it_value.tv_sec, 0);
+        assert_eq!(current.it_value.tv_nsec, 0);
+
+        let mut executor = TimeoutExecutor::with_backend(
+            ItimerProbe::default(),
+            Duration::from_secs(60),
+            TimeoutBackend::Itimer,
+        );
+        assert_eq!(executor.backend(), TimeoutBackend::Itimer);
+        executor
+            .run_target(&mut (), &mut (), &mut (), &input)
+            .unwrap();
+        assert!(executor.executor.armed);
+        assert!(!itimer_armed());
+    }
+}
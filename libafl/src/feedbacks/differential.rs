@@ -1,5 +1,6 @@
 //! Diff Feedback, comparing the content of two observers of the same type.
-//!
+//! Use them with the [`crate::executors::DiffExecutor`] and its
+//! [`crate::observers::DifferentialObserver`]s to compare two targets, or two versions of one.
 
 use alloc::string::{String, ToString};
 use core::{
@@ -120,7 +121,7 @@ where
     F: FnMut(&O1, &O2) -> DiffResult,
     I: Input,
     S: HasMetadata + HasClientPerfMonitor,
-    O1: Observer<I, S> + PartialEq<O2>,
+    O1: Observer<I, S>,
     O2: Observer<I, S>,
 {
     #[allow(clippy::wrong_self_convention)]
@@ -147,7 +148,49 @@ where
             .match_name(&self.o2_name)
             .ok_or_else(|| err(&self.o2_name))?;
 
-        Ok(o1 != o2)
+        Ok((self.compare_fn)(o1, o2).is_diff())
+    }
+}
+
+/// A [`DiffExitKindFeedback`] reports as interesting if the two executors of a
+/// [`crate::executors::DiffExecutor`] exited differently, e.g. only one of them crashed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DiffExitKindFeedback {}
+
+impl<I, S> Feedback<I, S> for DiffExitKindFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        Ok(matches!(exit_kind, ExitKind::Diff { .. }))
+    }
+}
+
+impl Named for DiffExitKindFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "DiffExitKindFeedback"
+    }
+}
+
+impl DiffExitKindFeedback {
+    /// Creates a new [`DiffExitKindFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
     }
 }
 
@@ -164,7 +207,7 @@ mod tests {
         inputs::{BytesInput, Input},
         monitors::ClientPerfMonitor,
         observers::Observer,
-        state::{HasClientPerfMonitor, HasMetadata},
+        state::{HasClientPerfMonitor, HasMetadata, HasUserStats},
     };
     use alloc::string::{String, ToString};
 
@@ -204,36 +247,55 @@ mod tests {
         }
     }
 
-    struct NopState;
+    #[derive(Default)]
+    struct NopState {
+        metadata: SerdeAnyMap,
+        stability: Option<f32>,
+        #[cfg(feature = "introspection")]
+        introspection_monitor: ClientPerfMonitor,
+    }
     impl HasMetadata for NopState {
         fn metadata(&self) -> &SerdeAnyMap {
-            unimplemented!()
+            &self.metadata
         }
 
         fn metadata_mut(&mut self) -> &mut SerdeAnyMap {
-            unimplemented!()
+            &mut self.metadata
         }
     }
+    impl HasUserStats for NopState {}
     impl HasClientPerfMonitor for NopState {
+        #[cfg(feature = "introspection")]
+        fn introspection_monitor(&self) -> &ClientPerfMonitor {
+            &self.introspection_monitor
+        }
+
+        #[cfg(feature = "introspection")]
+        fn introspection_monitor_mut(&mut self) -> &mut ClientPerfMonitor {
+            &mut self.introspection_monitor
+        }
+
+        #[cfg(not(feature = "introspection"))]
         fn introspection_monitor(&self) -> &ClientPerfMonitor {
             unimplemented!()
         }
 
+        #[cfg(not(feature = "introspection"))]
         fn introspection_monitor_mut(&mut self) -> &mut ClientPerfMonitor {
             unimplemented!()
         }
 
         fn stability(&self) -> &Option<f32> {
-            unimplemented!()
+            &self.stability
         }
 
         fn stability_mut(&mut self) -> &mut Option<f32> {
-            unimplemented!()
+            &mut self.stability
         }
     }
 
     fn test_diff(should_equal: bool) {
-        let mut nop_state = NopState;
+        let mut nop_state = NopState::default();
 
         let o1 = NopObserver::new("o1", true);
         let o2 = NopObserver::new("o2", should_equal);
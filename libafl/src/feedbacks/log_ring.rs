@@ -0,0 +1,99 @@
+//! The [`LogRingFeedback`] attaches the log written by the target in the ring of a
+//! [`LogRingObserver`] to the testcases, e.g. to the crashes when used with the objective.
+//! It never considers a testcase interesting, combine it with another feedback.
+
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{LogRingObserver, ObserversTuple},
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// The log written by the target during the run of a testcase
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TargetLogMetadata {
+    /// The logged bytes, the oldest may be missing if the ring was full
+    pub log: Vec<u8>,
+}
+
+crate::impl_serdeany!(TargetLogMetadata);
+
+impl TargetLogMetadata {
+    /// The log as text, replacing invalid UTF-8
+    #[must_use]
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.log).into_owned()
+    }
+}
+
+/// A feedback attaching the content of a [`LogRingObserver`] to the testcase as [`TargetLogMetadata`]
+#[derive(Debug, Clone)]
+pub struct LogRingFeedback {
+    name: String,
+    log: Option<Vec<u8>>,
+}
+
+impl LogRingFeedback {
+    /// Creates a new [`LogRingFeedback`] for the given observer
+    #[must_use]
+    pub fn new(observer: &LogRingObserver) -> Self {
+        Self {
+            name: observer.name().into(),
+            log: None,
+        }
+    }
+}
+
+impl Named for LogRingFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<I, S> Feedback<I, S> for LogRingFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        // Most runs log nothing, do not copy an empty ring
+        self.log = observers
+            .match_name::<LogRingObserver>(&self.name)
+            .filter(|observer| observer.written() > 0)
+            .map(LogRingObserver::log);
+        Ok(false)
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(log) = self.log.take() {
+            testcase.add_metadata(TargetLogMetadata { log });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.log = None;
+        Ok(())
+    }
+}
@@ -4,7 +4,8 @@ use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
-use core::{fmt::Debug, marker::PhantomData};
+use core::{fmt::Debug, marker::PhantomData, mem::size_of};
+use hashbrown::HashSet;
 use num_traits::PrimInt;
 use serde::{Deserialize, Serialize};
 
@@ -39,6 +40,14 @@ pub type MaxMapPow2Feedback<I, O, S, T> = MapFeedback<I, NextPow2IsNovel, O, Max
 /// but only, if a value is larger than `pow2` of the previous.
 pub type MaxMapOneOrFilledFeedback<I, O, S, T> =
     MapFeedback<I, OneOrFilledIsNovel, O, MaxReducer, S, T>;
+/// A [`MapFeedback`] bucketing the raw hitcounts of the map in the AFL classes, so that the observer
+/// does not have to be a ``HitcountsMapObserver``.
+pub type AflBucketMapFeedback<I, O, S, T> =
+    MapFeedback<I, DifferentIsNovel, O, BucketReducer, S, T>;
+/// A [`MapFeedback`] considering novel each exact hitcount not seen before for an entry,
+/// see [`ExactCountReducer`].
+pub type ExactCountMapFeedback<I, O, S, T> =
+    MapFeedback<I, DifferentIsNovel, O, ExactCountReducer, S, T>;
 
 /// A `Reducer` function is used to aggregate values for the novelty search
 pub trait Reducer<T>: 'static + Debug
@@ -47,6 +56,13 @@ where
 {
     /// Reduce two values to one value, with the current [`Reducer`].
     fn reduce(first: T, second: T) -> T;
+
+    /// If reducing with `value` leaves any history unchanged, i.e. `value` is the neutral element.
+    /// The [`MapFeedback`] skips the unset entries of the map if they hold it.
+    #[inline]
+    fn is_neutral(_value: T) -> bool {
+        false
+    }
 }
 
 /// A [`OrReducer`] reduces the values returning the bitwise OR with the old value
@@ -61,6 +77,11 @@ where
     fn reduce(history: T, new: T) -> T {
         history | new
     }
+
+    #[inline]
+    fn is_neutral(value: T) -> bool {
+        value == T::zero()
+    }
 }
 
 /// A [`AndReducer`] reduces the values returning the bitwise AND with the old value
@@ -75,6 +96,11 @@ where
     fn reduce(history: T, new: T) -> T {
         history & new
     }
+
+    #[inline]
+    fn is_neutral(value: T) -> bool {
+        value == !T::zero()
+    }
 }
 
 /// A [`MaxReducer`] reduces int values and returns their maximum.
@@ -93,6 +119,75 @@ where
             second
         }
     }
+
+    #[inline]
+    fn is_neutral(value: T) -> bool {
+        value == T::min_value()
+    }
+}
+
+/// The AFL hitcount class of `count`, as a single bit: 1, 2, 3, 4-7, 8-15, 16-31, 32-127, 128+
+#[inline]
+fn afl_bucket<T: PrimInt>(count: T) -> T {
+    let class = match count.to_u64().unwrap_or(0) {
+        0 => return T::zero(),
+        1 => 0,
+        2 => 1,
+        3 => 2,
+        4..=7 => 3,
+        8..=15 => 4,
+        16..=31 => 5,
+        32..=127 => 6,
+        _ => 7,
+    };
+    T::one() << class
+}
+
+/// A [`BucketReducer`] buckets the new hitcount in the AFL classes, and ORs its bit with the history.
+/// The history holds the set of classes seen for each entry.
+#[derive(Clone, Debug)]
+pub struct BucketReducer {}
+
+impl<T> Reducer<T> for BucketReducer
+where
+    T: PrimInt + Default + Copy + 'static + PartialOrd,
+{
+    #[inline]
+    fn reduce(history: T, new: T) -> T {
+        history | afl_bucket(new)
+    }
+
+    #[inline]
+    fn is_neutral(value: T) -> bool {
+        value == T::zero()
+    }
+}
+
+/// A [`ExactCountReducer`] ORs the bit of the exact new hitcount with the history.
+/// The history holds the set of hitcounts seen for each entry, the counts not fitting in the bits of
+/// the entry share the highest bit: up to 7 for `u8` maps, 63 for `u64` maps.
+#[derive(Clone, Debug)]
+pub struct ExactCountReducer {}
+
+impl<T> Reducer<T> for ExactCountReducer
+where
+    T: PrimInt + Default + Copy + 'static + PartialOrd,
+{
+    #[inline]
+    fn reduce(history: T, new: T) -> T {
+        let count = new.to_u64().unwrap_or(0);
+        if count == 0 {
+            history
+        } else {
+            let highest = (size_of::<T>() * 8 - 1) as u64;
+            history | (T::one() << (count - 1).min(highest) as usize)
+        }
+    }
+
+    #[inline]
+    fn is_neutral(value: T) -> bool {
+        value == T::zero()
+    }
 }
 
 /// A [`MinReducer`] reduces int values and returns their minimum.
@@ -111,6 +206,56 @@ where
             second
         }
     }
+
+    #[inline]
+    fn is_neutral(value: T) -> bool {
+        value == T::max_value()
+    }
+}
+
+/// Call `f` for each non-zero entry of `map`, with its index.
+/// The map is scanned a `u64` word at a time, sparse coverage maps are mostly zeroes.
+#[inline]
+fn for_each_set<T, F>(map: &[T], mut f: F)
+where
+    T: PrimInt,
+    F: FnMut(usize, T),
+{
+    let entry_size = size_of::<T>();
+    if entry_size > size_of::<u64>() {
+        for (i, &item) in map.iter().enumerate() {
+            if !item.is_zero() {
+                f(i, item);
+            }
+        }
+        return;
+    }
+    let per_word = size_of::<u64>() / entry_size;
+
+    // Safety: the entries are integers and the word size a multiple of their size, so each word is
+    // made of `per_word` whole entries
+    let (prefix, words, _) = unsafe { map.align_to::<u64>() };
+    for (i, &item) in prefix.iter().enumerate() {
+        if !item.is_zero() {
+            f(i, item);
+        }
+    }
+    for (w, &word) in words.iter().enumerate() {
+        if word != 0 {
+            let start = prefix.len() + w * per_word;
+            for (i, &item) in map[start..start + per_word].iter().enumerate() {
+                if !item.is_zero() {
+                    f(start + i, item);
+                }
+            }
+        }
+    }
+    let suffix = prefix.len() + words.len() * per_word;
+    for (i, &item) in map[suffix..].iter().enumerate() {
+        if !item.is_zero() {
+            f(suffix + i, item);
+        }
+    }
 }
 
 /// A `IsNovel` function is used to discriminate if a reduced value is considered novel.
@@ -121,6 +266,13 @@ where
     /// If a new value in the [`MapFeedback`] was found,
     /// this filter can decide if the result is considered novel or not.
     fn is_novel(old: T, new: T) -> bool;
+
+    /// If `is_novel(x, x)` can be `true`.
+    /// If not, the [`MapFeedback`] skips the entries of the map which leave the history unchanged.
+    #[inline]
+    fn unchanged_is_novel() -> bool {
+        true
+    }
 }
 
 /// [`AllIsNovel`] consider everything a novelty. Here mostly just for debugging.
@@ -162,6 +314,11 @@ where
     fn is_novel(old: T, new: T) -> bool {
         old != new
     }
+
+    #[inline]
+    fn unchanged_is_novel() -> bool {
+        false
+    }
 }
 
 /// Only consider as novel the values which are at least the next pow2 class of the old value
@@ -182,6 +339,11 @@ where
             new >= pow2
         }
     }
+
+    #[inline]
+    fn unchanged_is_novel() -> bool {
+        false
+    }
 }
 
 /// A filter that only saves values which are at least the next pow2 class
@@ -195,6 +357,11 @@ where
     fn is_novel(old: T, new: T) -> bool {
         (new == T::one() || new == T::max_value()) && new > old
     }
+
+    #[inline]
+    fn unchanged_is_novel() -> bool {
+        false
+    }
 }
 
 /// A testcase metadata holding a list of indexes of a map
@@ -281,6 +448,10 @@ where
     pub history_map: Vec<T>,
     /// Name identifier of this instance
     pub name: String,
+    /// The entries the calibration found to change between runs of the same input,
+    /// never considered novel
+    #[serde(default)]
+    pub unstable_entries: HashSet<usize>,
 }
 
 impl<T> FeedbackState for MapFeedbackState<T>
@@ -315,6 +486,7 @@ where
         Self {
             history_map: vec![T::min_value(); map_size],
             name: name.to_string(),
+            unstable_entries: HashSet::new(),
         }
     }
 
@@ -327,6 +499,7 @@ where
         Self {
             history_map: vec![T::min_value(); map_observer.len()],
             name: map_observer.name().to_string(),
+            unstable_entries: HashSet::new(),
         }
     }
 
@@ -337,7 +510,37 @@ where
         Self {
             history_map,
             name: name.to_string(),
+            unstable_entries: HashSet::new(),
+        }
+    }
+
+    /// Marks the entry at `idx` as unstable, returns `true` if it was not yet
+    pub fn mark_unstable(&mut self, idx: usize) -> bool {
+        self.unstable_entries.insert(idx)
+    }
+
+    /// The fraction of the entries set in the history that are stable
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn stability(&self) -> f32 {
+        let filled = self
+            .history_map
+            .iter()
+            .filter(|entry| **entry != T::min_value())
+            .count();
+        if filled == 0 {
+            return 1.0;
         }
+        let unstable = self
+            .unstable_entries
+            .iter()
+            .filter(|idx| {
+                self.history_map
+                    .get(**idx)
+                    .map_or(false, |entry| *entry != T::min_value())
+            })
+            .count();
+        (filled - unstable) as f32 / filled as f32
     }
 }
 
@@ -402,11 +605,27 @@ where
 
         assert!(size <= observer.len());
 
-        if self.novelties.is_some() {
+        let skip_unset = initial == T::zero() && R::is_neutral(initial) && !N::unchanged_is_novel();
+        if let Some(map) = observer.as_contiguous().filter(|_| skip_unset) {
+            let history_map = &mut map_state.history_map;
+            let unstable_entries = &map_state.unstable_entries;
+            let novelties = &mut self.novelties;
+            for_each_set(map, |i, item| {
+                let history = history_map[i];
+                let reduced = R::reduce(history, item);
+                if N::is_novel(history, reduced) && !unstable_entries.contains(&i) {
+                    history_map[i] = reduced;
+                    interesting = true;
+                    if let Some(novelties) = novelties.as_mut() {
+                        novelties.push(i);
+                    }
+                }
+            });
+        } else if self.novelties.is_some() {
             for (i, &item) in observer.as_ref_iter().enumerate() {
                 let history = map_state.history_map[i];
                 let reduced = R::reduce(history, item);
-                if N::is_novel(history, reduced) {
+                if N::is_novel(history, reduced) && !map_state.unstable_entries.contains(&i) {
                     map_state.history_map[i] = reduced;
                     interesting = true;
                     self.novelties.as_mut().unwrap().push(i);
@@ -416,7 +635,7 @@ where
             for (i, &item) in observer.as_ref_iter().enumerate() {
                 let history = map_state.history_map[i];
                 let reduced = R::reduce(history, item);
-                if N::is_novel(history, reduced) {
+                if N::is_novel(history, reduced) && !map_state.unstable_entries.contains(&i) {
                     map_state.history_map[i] = reduced;
                     interesting = true;
                 }
@@ -658,7 +877,45 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::feedbacks::{AllIsNovel, IsNovel, NextPow2IsNovel};
+    use super::for_each_set;
+    use crate::feedbacks::{
+        AllIsNovel, BucketReducer, DifferentIsNovel, ExactCountReducer, IsNovel, MapFeedbackState,
+        NextPow2IsNovel, Reducer,
+    };
+
+    #[test]
+    fn test_for_each_set() {
+        let mut map = [0_u8; 67];
+        map[0] = 1;
+        map[9] = 2;
+        map[66] = 3;
+        let mut set = vec![];
+        for_each_set(&map[1..], |i, item| set.push((i + 1, item)));
+        assert_eq!(set, vec![(9, 2), (66, 3)]);
+
+        let mut map = [0_u16; 13];
+        map[5] = 7;
+        let mut set = vec![];
+        for_each_set(&map, |i, item| set.push((i, item)));
+        assert_eq!(set, vec![(5, 7)]);
+    }
+
+    #[test]
+    fn test_map_reducers() {
+        assert_eq!(BucketReducer::reduce(0_u8, 0), 0);
+        assert_eq!(BucketReducer::reduce(0_u8, 3), 4);
+        assert_eq!(BucketReducer::reduce(4_u8, 5), 12);
+        assert_eq!(BucketReducer::reduce(0_u8, 200), 128);
+        assert!(!DifferentIsNovel::is_novel(
+            12_u8,
+            BucketReducer::reduce(12_u8, 6)
+        ));
+
+        assert_eq!(ExactCountReducer::reduce(0_u8, 1), 1);
+        assert_eq!(ExactCountReducer::reduce(1_u8, 3), 5);
+        assert_eq!(ExactCountReducer::reduce(0_u8, 42), 128);
+        assert_eq!(ExactCountReducer::reduce(5_u16, 0), 5);
+    }
 
     #[test]
     fn test_map_is_novel() {
@@ -679,6 +936,17 @@ mod tests {
         assert!(NextPow2IsNovel::is_novel(254_u8, 255));
         assert!(!NextPow2IsNovel::is_novel(255_u8, 255));
     }
+
+    #[test]
+    fn test_map_feedback_state_stability() {
+        let mut map_state = MapFeedbackState::with_history_map("map", vec![0_u8, 1, 1, 1, 1]);
+        assert!((map_state.stability() - 1.0).abs() < f32::EPSILON);
+        assert!(map_state.mark_unstable(1));
+        assert!(!map_state.mark_unstable(1));
+        // Entries never hit do not count
+        map_state.mark_unstable(0);
+        assert!((map_state.stability() - 0.75).abs() < f32::EPSILON);
+    }
 }
 
 #[cfg(feature = "python")]
@@ -5,7 +5,17 @@ pub mod map;
 pub use map::*;
 
 pub mod differential;
-pub use differential::DiffFeedback;
+pub use differential::{DiffExitKindFeedback, DiffFeedback};
+pub mod profiled;
+pub use profiled::ProfiledFeedback;
+pub mod weighted;
+pub use weighted::{InterestingnessMetadata, WeightedFeedback};
+pub mod partition;
+pub use partition::CorpusPartitionFeedback;
+pub mod value_bloom;
+pub use value_bloom::{ValueBloomFeedback, ValueBloomFeedbackState};
+pub mod log_ring;
+pub use log_ring::{LogRingFeedback, TargetLogMetadata};
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
@@ -18,6 +28,11 @@ pub use new_hash_feedback::NewHashFeedback;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedbackState;
 
+#[cfg(feature = "std")]
+pub mod sanitizer;
+#[cfg(feature = "std")]
+pub use sanitizer::SanitizerReportFeedback;
+
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "nautilus")]
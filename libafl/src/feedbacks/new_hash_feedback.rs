@@ -1,4 +1,5 @@
-//! The ``NewHashFeedback`` uses the backtrace hash and a hashset to only keep novel cases
+//! The ``NewHashFeedback`` uses the hash of an observer, e.g. of a backtrace or of the stdout,
+//! and a hashset to only keep novel cases
 
 use std::{fmt::Debug, hash::Hash, marker::PhantomData};
 
@@ -100,6 +101,10 @@ where
 }
 
 /// A [`NewHashFeedback`] maintains a hashset of already seen stacktraces and considers interesting unseen ones
+///
+/// It works with any [`ObserverWithHashField`], such as the [`crate::observers::BacktraceObserver`]
+/// or the [`crate::observers::StdOutObserver`]. To only keep the crashes with a novel backtrace, use it
+/// as `feedback_and_fast!(CrashFeedback::new(), NewHashFeedback::new_with_observer(..))` objective.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NewHashFeedback<O> {
     feedback_name: String,
@@ -128,7 +133,7 @@ where
     {
         let observer = observers
             .match_name::<O>(&self.observer_name)
-            .expect("A NewHashFeedback needs a BacktraceObserver");
+            .expect("A NewHashFeedback needs an observer with a hash field");
 
         let backtrace_state = _state
             .feedback_states_mut()
@@ -0,0 +1,93 @@
+//! The [`CorpusPartitionFeedback`] sends the testcases the wrapped feedback considers interesting
+//! to a partition of a [`crate::corpus::MultiCorpus`], tagging them with a
+//! [`CorpusPartitionMetadata`].
+//!
+//! In a combination of several tagging feedbacks, the first one to tag the testcase wins.
+
+use alloc::string::{String, ToString};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::{CorpusPartitionMetadata, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// A feedback sending the testcases the wrapped feedback considers interesting to the
+/// [`crate::corpus::MultiCorpus`] partition `partition`
+#[derive(Clone, Debug)]
+pub struct CorpusPartitionFeedback<F> {
+    inner: F,
+    partition: String,
+    interesting: bool,
+}
+
+impl<F> CorpusPartitionFeedback<F> {
+    /// Send the testcases `inner` considers interesting to the partition `partition`
+    pub fn new(inner: F, partition: &str) -> Self {
+        Self {
+            inner,
+            partition: partition.to_string(),
+            interesting: false,
+        }
+    }
+
+    /// The name of the partition
+    pub fn partition(&self) -> &str {
+        &self.partition
+    }
+}
+
+impl<F, I, S> Feedback<I, S> for CorpusPartitionFeedback<F>
+where
+    F: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.interesting = self
+            .inner
+            .is_interesting(state, manager, input, observers, exit_kind)?;
+        Ok(self.interesting)
+    }
+
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if self.interesting && !testcase.has_metadata::<CorpusPartitionMetadata>() {
+            testcase.add_metadata(CorpusPartitionMetadata::new(&self.partition));
+        }
+        self.interesting = false;
+        self.inner.append_metadata(state, testcase)
+    }
+
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.interesting = false;
+        self.inner.discard_metadata(state, input)
+    }
+}
+
+impl<F> Named for CorpusPartitionFeedback<F>
+where
+    F: Named,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
@@ -0,0 +1,87 @@
+//! The [`ProfiledFeedback`] adds the time spent in the wrapped feedback to the
+//! [`struct@crate::stages::ProfilerMetadata`] of the state.
+
+use crate::{
+    bolts::{current_time, tuples::Named},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::ObserversTuple,
+    stages::ProfilerMetadata,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// A feedback measuring the time spent evaluating the wrapped feedback
+#[derive(Clone, Debug)]
+pub struct ProfiledFeedback<F> {
+    inner: F,
+    /// The id of the feedback in the [`struct@ProfilerMetadata`]
+    id: Option<usize>,
+}
+
+impl<F> ProfiledFeedback<F> {
+    /// Profile the given feedback, under its own name
+    pub fn new(inner: F) -> Self {
+        Self { inner, id: None }
+    }
+
+    /// The wrapped feedback
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+}
+
+impl<F, I, S> Feedback<I, S> for ProfiledFeedback<F>
+where
+    F: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasMetadata,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let start = current_time();
+        let ret = self
+            .inner
+            .is_interesting(state, manager, input, observers, exit_kind);
+        self.id = Some(ProfilerMetadata::of_state(state).add_feedback_time(
+            self.inner.name(),
+            self.id,
+            current_time() - start,
+        ));
+        ret
+    }
+
+    #[inline]
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        self.inner.append_metadata(state, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.inner.discard_metadata(state, input)
+    }
+}
+
+impl<F> Named for ProfiledFeedback<F>
+where
+    F: Named,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
@@ -0,0 +1,81 @@
+//! The [`SanitizerReportFeedback`] is interesting for runs with a sanitizer report, and stores
+//! the parsed report in the metadata of the testcase.
+
+use alloc::string::{String, ToString};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{ObserversTuple, SanitizerReport, SanitizerReportObserver},
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// A feedback considering interesting the runs with a [`SanitizerReport`], usually an objective.
+/// Combine it with a [`crate::feedbacks::NewHashFeedback`] on the same observer to keep a single
+/// testcase per bug.
+#[derive(Debug, Clone)]
+pub struct SanitizerReportFeedback {
+    observer_name: String,
+    report: Option<SanitizerReport>,
+}
+
+impl<I, S> Feedback<I, S> for SanitizerReportFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<SanitizerReportObserver>(&self.observer_name)
+            .expect("A SanitizerReportFeedback needs a SanitizerReportObserver");
+        self.report = observer.report().cloned();
+        Ok(self.report.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(report) = self.report.take() {
+            testcase.add_metadata(report);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.report = None;
+        Ok(())
+    }
+}
+
+impl Named for SanitizerReportFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "SanitizerReportFeedback"
+    }
+}
+
+impl SanitizerReportFeedback {
+    /// Creates a new [`SanitizerReportFeedback`] for the given observer
+    #[must_use]
+    pub fn new(observer: &SanitizerReportObserver) -> Self {
+        Self {
+            observer_name: observer.name().to_string(),
+            report: None,
+        }
+    }
+}
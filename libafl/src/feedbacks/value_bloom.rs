@@ -0,0 +1,196 @@
+//! The [`ValueBloomFeedback`] keeps the hashes reported to a [`ValueHashObserver`] in a Bloom
+//! filter and considers interesting the runs reporting an unseen hash.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackState},
+    inputs::Input,
+    observers::{ObserversTuple, ValueHashObserver},
+    state::{HasClientPerfMonitor, HasFeedbackStates},
+    Error,
+};
+
+/// The default number of bits of the Bloom filter
+pub const DEFAULT_BLOOM_BITS: usize = 1 << 20;
+/// The default number of bits set for each hash
+pub const DEFAULT_BLOOM_PROBES: usize = 4;
+
+/// The state of [`ValueBloomFeedback`], the Bloom filter of the hashes seen so far
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ValueBloomFeedbackState {
+    name: String,
+    bits: Vec<u64>,
+    probes: usize,
+}
+
+impl FeedbackState for ValueBloomFeedbackState {
+    fn reset(&mut self) -> Result<(), Error> {
+        self.bits.iter_mut().for_each(|w| *w = 0);
+        Ok(())
+    }
+}
+
+impl Named for ValueBloomFeedbackState {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl ValueBloomFeedbackState {
+    /// Create a new [`ValueBloomFeedbackState`] with the default size
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self::with_size(name, DEFAULT_BLOOM_BITS, DEFAULT_BLOOM_PROBES)
+    }
+
+    /// Create a new [`ValueBloomFeedbackState`] for the given [`ValueHashObserver`]
+    #[must_use]
+    pub fn with_observer(observer: &ValueHashObserver) -> Self {
+        Self::new(observer.name())
+    }
+
+    /// Create a new [`ValueBloomFeedbackState`] with `bits` bits, rounded to a power of two,
+    /// setting `probes` bits for each hash
+    #[must_use]
+    pub fn with_size(name: &str, bits: usize, probes: usize) -> Self {
+        let words = (bits.next_power_of_two() / 64).max(1);
+        Self {
+            name: name.to_string(),
+            bits: vec![0; words],
+            probes: probes.max(1),
+        }
+    }
+
+    /// The bits for `hash`, by double hashing
+    fn positions(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let mask = (self.bits.len() * 64 - 1) as u64;
+        let step = hash.rotate_left(32) | 1;
+        (0..self.probes as u64)
+            .map(move |i| (hash.wrapping_add(i.wrapping_mul(step)) & mask) as usize)
+    }
+
+    /// If `hash` may have been inserted before.
+    #[must_use]
+    pub fn contains(&self, hash: u64) -> bool {
+        self.positions(hash)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+
+    /// Insert `hash`, returns `true` if it was surely not inserted before.
+    pub fn insert(&mut self, hash: u64) -> bool {
+        let mut new = false;
+        for pos in self.positions(hash) {
+            let bit = 1 << (pos % 64);
+            new |= self.bits[pos / 64] & bit == 0;
+            self.bits[pos / 64] |= bit;
+        }
+        new
+    }
+}
+
+/// A [`ValueBloomFeedback`] considers interesting the runs reporting a value never seen before
+/// to its [`ValueHashObserver`]. False positives of the Bloom filter may, rarely, hide a new value.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ValueBloomFeedback {
+    name: String,
+    observer_name: String,
+}
+
+impl<I, S> Feedback<I, S> for ValueBloomFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasFeedbackStates,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<ValueHashObserver>(&self.observer_name)
+            .expect("A ValueBloomFeedback needs a ValueHashObserver");
+        let bloom = state
+            .feedback_states_mut()
+            .match_name_mut::<ValueBloomFeedbackState>(&self.observer_name)
+            .expect("A ValueBloomFeedback needs a ValueBloomFeedbackState");
+
+        let mut interesting = false;
+        for hash in observer.hashes() {
+            interesting |= bloom.insert(*hash);
+        }
+        Ok(interesting)
+    }
+}
+
+impl Named for ValueBloomFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ValueBloomFeedback {
+    /// Creates a new [`ValueBloomFeedback`] for the [`ValueHashObserver`] called `observer_name`
+    #[must_use]
+    pub fn new(name: &str, observer_name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            observer_name: observer_name.to_string(),
+        }
+    }
+
+    /// Creates a new [`ValueBloomFeedback`] for the given [`ValueHashObserver`]
+    #[must_use]
+    pub fn new_with_observer(name: &str, observer: &ValueHashObserver) -> Self {
+        Self::new(name, observer.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        executors::ExitKind,
+        feedbacks::ValueBloomFeedbackState,
+        inputs::BytesInput,
+        observers::{Observer, ValueHashObserver},
+        report_value,
+    };
+
+    #[test]
+    fn test_value_bloom() {
+        let input = BytesInput::new(vec![]);
+        let mut observer = ValueHashObserver::new("values");
+        let mut bloom = ValueBloomFeedbackState::with_observer(&observer);
+
+        Observer::<BytesInput, ()>::pre_exec(&mut observer, &mut (), &input).unwrap();
+        report_value!("alloc_size", 0x100_usize);
+        report_value!("state", 3_u32);
+        Observer::<BytesInput, ()>::post_exec(&mut observer, &mut (), &input, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.hashes().len(), 2);
+
+        let new: Vec<bool> = observer.hashes().iter().map(|h| bloom.insert(*h)).collect();
+        assert_eq!(new, vec![true, true]);
+        assert!(observer.hashes().iter().all(|h| bloom.contains(*h)));
+        assert!(!bloom.insert(observer.hashes()[0]));
+    }
+}
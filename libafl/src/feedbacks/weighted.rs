@@ -0,0 +1,114 @@
+//! The [`WeightedFeedback`] adds its weight to the interestingness score of the testcases it
+//! considers interesting, kept in their [`InterestingnessMetadata`].
+//!
+//! Wrap each feedback of an eager combination, e.g. with [`crate::feedback_or`], so that all of them
+//! are evaluated, and the score sums the weights of all the feedbacks the testcase triggered.
+//! The schedulers can then prefer the testcases with the highest score, see
+//! [`crate::schedulers::InterestingnessFavFactor`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// The sum of the weights of the [`WeightedFeedback`]s a testcase triggered
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct InterestingnessMetadata {
+    /// The summed weights
+    pub score: f64,
+    /// The number of feedbacks triggered
+    pub feedbacks: usize,
+}
+
+crate::impl_serdeany!(InterestingnessMetadata);
+
+/// A feedback adding `weight` to the [`InterestingnessMetadata`] of the testcases the wrapped
+/// feedback considers interesting
+#[derive(Clone, Debug)]
+pub struct WeightedFeedback<F> {
+    inner: F,
+    weight: f64,
+    interesting: bool,
+}
+
+impl<F> WeightedFeedback<F> {
+    /// Weight the given feedback
+    pub fn new(inner: F, weight: f64) -> Self {
+        Self {
+            inner,
+            weight,
+            interesting: false,
+        }
+    }
+
+    /// The weight of the feedback
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+impl<F, I, S> Feedback<I, S> for WeightedFeedback<F>
+where
+    F: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.interesting = self
+            .inner
+            .is_interesting(state, manager, input, observers, exit_kind)?;
+        Ok(self.interesting)
+    }
+
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if self.interesting {
+            if !testcase.has_metadata::<InterestingnessMetadata>() {
+                testcase.add_metadata(InterestingnessMetadata::default());
+            }
+            let meta = testcase
+                .metadata_mut()
+                .get_mut::<InterestingnessMetadata>()
+                .unwrap();
+            meta.score += self.weight;
+            meta.feedbacks += 1;
+            self.interesting = false;
+        }
+        self.inner.append_metadata(state, testcase)
+    }
+
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.interesting = false;
+        self.inner.discard_metadata(state, input)
+    }
+}
+
+impl<F> Named for WeightedFeedback<F>
+where
+    F: Named,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
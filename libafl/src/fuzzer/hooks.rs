@@ -0,0 +1,309 @@
+//! Hooks called by the [`crate::fuzzer::StdFuzzer`] on each new objective, e.g. to start the triage
+//! of a crash right away: run a closure, exec a command, or post to a webhook.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt::{self, Debug};
+#[cfg(feature = "std")]
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crate::{corpus::Corpus, inputs::Input, state::HasSolutions, Error};
+
+/// A new objective, as passed to the [`ObjectiveHook`]s
+#[derive(Debug, Clone, Copy)]
+pub struct NewObjective<'a> {
+    /// The index of the testcase in the solutions corpus
+    pub idx: usize,
+    /// The file of the testcase, if the solutions corpus is on disk
+    pub filename: Option<&'a str>,
+    /// The number of objectives found so far
+    pub objective_size: usize,
+    /// The executions at the time the objective was found, unknown in the crash and timeout handlers
+    pub executions: Option<usize>,
+}
+
+/// A hook called for each new objective
+pub trait ObjectiveHook: Debug {
+    /// Called right after the objective was added to the solutions corpus
+    fn on_objective(&mut self, objective: &NewObjective) -> Result<(), Error>;
+}
+
+/// Calls a closure for each new objective
+pub struct ClosureObjectiveHook<F>
+where
+    F: FnMut(&NewObjective) -> Result<(), Error>,
+{
+    closure: F,
+}
+
+impl<F> Debug for ClosureObjectiveHook<F>
+where
+    F: FnMut(&NewObjective) -> Result<(), Error>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClosureObjectiveHook")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> ClosureObjectiveHook<F>
+where
+    F: FnMut(&NewObjective) -> Result<(), Error>,
+{
+    /// Creates a new [`ClosureObjectiveHook`]
+    pub fn new(closure: F) -> Self {
+        Self { closure }
+    }
+}
+
+impl<F> ObjectiveHook for ClosureObjectiveHook<F>
+where
+    F: FnMut(&NewObjective) -> Result<(), Error>,
+{
+    fn on_objective(&mut self, objective: &NewObjective) -> Result<(), Error> {
+        (self.closure)(objective)
+    }
+}
+
+/// Runs a command for each new objective, with the path of the testcase as last argument.
+/// The command runs in the background, the fuzzer does not wait for it.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct CommandObjectiveHook {
+    program: String,
+    args: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+impl CommandObjectiveHook {
+    /// Creates a new [`CommandObjectiveHook`] running `program` with the given `args`
+    #[must_use]
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.into(),
+            args: args.iter().map(|arg| (*arg).into()).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ObjectiveHook for CommandObjectiveHook {
+    fn on_objective(&mut self, objective: &NewObjective) -> Result<(), Error> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .env("LIBAFL_OBJECTIVE_IDX", objective.idx.to_string())
+            .stdin(Stdio::null());
+        if let Some(filename) = objective.filename {
+            command.arg(filename);
+        }
+        let mut child = command.spawn()?;
+        // Reap it, to not leave zombies behind
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// Posts a JSON message to a webhook for each new objective.
+/// Only plain `http://` urls are supported, put a local relay in front of `https` endpoints.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct WebhookObjectiveHook {
+    host: String,
+    path: String,
+    timeout: Duration,
+}
+
+#[cfg(feature = "std")]
+impl WebhookObjectiveHook {
+    /// Creates a new [`WebhookObjectiveHook`] posting to `url`, e.g. `http://localhost:8080/crash`
+    pub fn new(url: &str) -> Result<Self, Error> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            Error::IllegalArgument(format!("Only http:// webhooks are supported: {}", url))
+        })?;
+        let (host, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        let host = if host.contains(':') {
+            host.into()
+        } else {
+            format!("{}:80", host)
+        };
+        Ok(Self {
+            host,
+            path: path.into(),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// Gives up on the webhook after `timeout`, so a dead endpoint does not stall the fuzzer
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Connects to the first address of the host answering within the timeout
+    fn connect(&self) -> Result<TcpStream, Error> {
+        let mut last_err = None;
+        for addr in self.host.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.map_or_else(
+            || Error::IllegalArgument(format!("Could not resolve webhook host {}", self.host)),
+            Error::from,
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl ObjectiveHook for WebhookObjectiveHook {
+    fn on_objective(&mut self, objective: &NewObjective) -> Result<(), Error> {
+        let body = serde_json::json!({
+            "idx": objective.idx,
+            "filename": objective.filename,
+            "objective_size": objective.objective_size,
+            "executions": objective.executions,
+        })
+        .to_string();
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        );
+
+        let mut stream = self.connect()?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        stream.write_all(request.as_bytes())?;
+
+        let mut status = [0; 12];
+        stream.read_exact(&mut status)?;
+        // `HTTP/1.1 2xx`
+        if status[9] == b'2' {
+            Ok(())
+        } else {
+            Err(Error::Unknown(format!(
+                "Webhook {}{} answered {}",
+                self.host,
+                self.path,
+                String::from_utf8_lossy(&status[9..])
+            )))
+        }
+    }
+}
+
+/// The [`ObjectiveHook`]s of a fuzzer
+#[derive(Debug, Default)]
+pub struct ObjectiveHooks {
+    hooks: Vec<Box<dyn ObjectiveHook>>,
+}
+
+impl ObjectiveHooks {
+    /// Creates a new, empty list of hooks
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook
+    pub fn add<H>(&mut self, hook: H)
+    where
+        H: ObjectiveHook + 'static,
+    {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Calls all hooks. A failing hook is reported, but does not stop the fuzzer.
+    pub fn on_objective(&mut self, objective: &NewObjective) {
+        for hook in &mut self.hooks {
+            if let Err(e) = hook.on_objective(objective) {
+                #[cfg(feature = "std")]
+                println!("Objective hook {:?} failed: {:?}", hook, e);
+                #[cfg(not(feature = "std"))]
+                drop(e);
+            }
+        }
+    }
+
+    /// Calls all hooks for the solution at `idx` of the solutions corpus, found after `executions`, if known
+    pub fn on_solution<I, S>(
+        &mut self,
+        state: &S,
+        idx: usize,
+        executions: Option<usize>,
+    ) -> Result<(), Error>
+    where
+        I: Input,
+        S: HasSolutions<I>,
+    {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let filename = state.solutions().get(idx)?.borrow().filename().clone();
+        self.on_objective(&NewObjective {
+            idx,
+            filename: filename.as_deref(),
+            objective_size: state.solutions().count(),
+            executions,
+        });
+        Ok(())
+    }
+
+    /// If there are no hooks
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use crate::fuzzer::hooks::{NewObjective, ObjectiveHook, WebhookObjectiveHook};
+
+    #[test]
+    fn test_webhook_objective_hook() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/crash", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request_line = String::new();
+            BufReader::new(&stream)
+                .read_line(&mut request_line)
+                .unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            request_line
+        });
+
+        let mut hook = WebhookObjectiveHook::new(&url).unwrap();
+        hook.on_objective(&NewObjective {
+            idx: 0,
+            filename: Some("crashes/id_0"),
+            objective_size: 1,
+            executions: Some(1337),
+        })
+        .unwrap();
+        assert!(server.join().unwrap().starts_with("POST /crash HTTP/1.1"));
+
+        assert!(WebhookObjectiveHook::new("https://example.com").is_err());
+    }
+}
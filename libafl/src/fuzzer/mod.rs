@@ -1,10 +1,15 @@
 //! The `Fuzzer` is the main struct for a fuzz campaign.
 
+pub mod hooks;
+pub use hooks::{ClosureObjectiveHook, NewObjective, ObjectiveHook, ObjectiveHooks};
+#[cfg(feature = "std")]
+pub use hooks::{CommandObjectiveHook, WebhookObjectiveHook};
+
 use crate::{
     bolts::current_time,
     corpus::{Corpus, Testcase},
     events::{Event, EventConfig, EventFirer, EventManager, ProgressReporter},
-    executors::{Executor, ExitKind, HasObservers},
+    executors::{BatchExecutor, Executor, ExitKind, HasObservers},
     feedbacks::Feedback,
     inputs::Input,
     mark_feature_time,
@@ -19,7 +24,7 @@ use crate::{
 #[cfg(feature = "introspection")]
 use crate::monitors::PerfFeature;
 
-use alloc::string::ToString;
+use alloc::{string::ToString, vec::Vec};
 use core::{marker::PhantomData, time::Duration};
 
 /// Send a monitor update all 15 (or more) seconds
@@ -64,6 +69,11 @@ where
 
     /// The objective feedback (mutable)
     fn objective_mut(&mut self) -> &mut OF;
+
+    /// The hooks called for each new objective, if any
+    fn objective_hooks_mut(&mut self) -> Option<&mut ObjectiveHooks> {
+        None
+    }
 }
 
 /// Evaluate if an input is interesting using the feedback
@@ -245,6 +255,7 @@ where
     scheduler: CS,
     feedback: F,
     objective: OF,
+    objective_hooks: ObjectiveHooks,
     phantom: PhantomData<(I, OT, S)>,
 }
 
@@ -297,6 +308,10 @@ where
     fn objective_mut(&mut self) -> &mut OF {
         &mut self.objective
     }
+
+    fn objective_hooks_mut(&mut self) -> Option<&mut ObjectiveHooks> {
+        Some(&mut self.objective_hooks)
+    }
 }
 
 impl<CS, F, I, OF, OT, S> ExecutionProcessor<I, OT, S> for StdFuzzer<CS, F, I, OF, OT, S>
@@ -365,7 +380,15 @@ where
                 let mut testcase = Testcase::with_executions(input.clone(), *state.executions());
                 self.feedback_mut().append_metadata(state, &mut testcase)?;
                 let idx = state.corpus_mut().add(testcase)?;
-                self.scheduler_mut().on_add(state, idx)?;
+                if send_events {
+                    self.scheduler_mut().on_add(state, idx)?;
+                } else {
+                    // Received from elsewhere, not a child of the testcase being fuzzed
+                    let current = state.corpus_mut().current_mut().take();
+                    let res = self.scheduler_mut().on_add(state, idx);
+                    *state.corpus_mut().current_mut() = current;
+                    res?;
+                }
 
                 if send_events {
                     // TODO set None for fast targets
@@ -384,6 +407,7 @@ where
                             client_config: manager.configuration(),
                             time: current_time(),
                             executions: *state.executions(),
+                            forward_id: None,
                         },
                     )?;
                 }
@@ -396,7 +420,10 @@ where
                 // The input is a solution, add it to the respective corpus
                 let mut testcase = Testcase::with_executions(input, *state.executions());
                 self.objective_mut().append_metadata(state, &mut testcase)?;
-                state.solutions_mut().add(testcase)?;
+                let idx = state.solutions_mut().add(testcase)?;
+                let executions = *state.executions();
+                self.objective_hooks
+                    .on_solution(state, idx, Some(executions))?;
 
                 if send_events {
                     manager.fire(
@@ -502,6 +529,7 @@ where
                 client_config: manager.configuration(),
                 time: current_time(),
                 executions: *state.executions(),
+                forward_id: None,
             },
         )?;
         Ok(idx)
@@ -572,10 +600,21 @@ where
             scheduler,
             feedback,
             objective,
+            objective_hooks: ObjectiveHooks::new(),
             phantom: PhantomData,
         }
     }
 
+    /// Adds a hook, called for each new objective
+    #[must_use]
+    pub fn with_objective_hook<H>(mut self, hook: H) -> Self
+    where
+        H: ObjectiveHook + 'static,
+    {
+        self.objective_hooks.add(hook);
+        self
+    }
+
     /// Runs the input and triggers observers and feedback
     pub fn execute_input<E, EM>(
         &mut self,
@@ -608,6 +647,51 @@ where
     }
 }
 
+impl<CS, F, I, OF, OT, S> StdFuzzer<CS, F, I, OF, OT, S>
+where
+    CS: Scheduler<I, S>,
+    F: Feedback<I, S>,
+    I: Input,
+    OF: Feedback<I, S>,
+    OT: ObserversTuple<I, S> + serde::Serialize + serde::de::DeserializeOwned,
+    S: HasCorpus<I> + HasSolutions<I> + HasClientPerfMonitor + HasExecutions,
+{
+    /// Runs a batch of inputs with [`BatchExecutor::run_batch`] and evaluates each of them,
+    /// adding them to the respective corpuses if needed and firing the right events.
+    /// Returns the result of each input, in order.
+    pub fn evaluate_batch<E, EM>(
+        &mut self,
+        state: &mut S,
+        executor: &mut E,
+        manager: &mut EM,
+        inputs: &[I],
+    ) -> Result<Vec<(ExecuteInputResult, Option<usize>)>, Error>
+    where
+        E: BatchExecutor<EM, I, OT, S, Self>,
+        EM: EventFirer<I>,
+    {
+        let mut results = Vec::with_capacity(inputs.len());
+        executor.run_batch(
+            self,
+            state,
+            manager,
+            inputs,
+            |fuzzer, state, manager, observers, input, exit_kind| {
+                results.push(fuzzer.process_execution(
+                    state,
+                    manager,
+                    input.clone(),
+                    observers,
+                    &exit_kind,
+                    true,
+                )?);
+                Ok(())
+            },
+        )?;
+        Ok(results)
+    }
+}
+
 /// Structs with this trait will execute an [`Input`]
 pub trait ExecutesInput<I, OT, S, Z>
 where
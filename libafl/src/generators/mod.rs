@@ -4,15 +4,24 @@ use alloc::vec::Vec;
 use core::{cmp::min, marker::PhantomData};
 
 use crate::{
-    bolts::rands::Rand,
-    inputs::{bytes::BytesInput, Input},
-    state::HasRand,
+    bolts::{rands::Rand, tuples::Named},
+    inputs::{bytes::BytesInput, HasBytesVec, Input},
+    mutators::{MutationResult, Mutator},
+    state::{HasMaxSize, HasRand},
     Error,
 };
 
 pub mod gramatron;
 pub use gramatron::*;
 
+pub mod template;
+pub use template::{TemplateGenerator, TemplatePart};
+
+#[cfg(feature = "std")]
+pub mod regexp;
+#[cfg(feature = "std")]
+pub use regexp::RegexGenerator;
+
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "nautilus")]
@@ -126,6 +135,60 @@ where
     }
 }
 
+/// Uses a [`Generator`] as a mutation: replaces a random range of the input with freshly
+/// generated bytes, e.g. to regenerate a field of a structured input.
+#[derive(Clone, Debug)]
+pub struct GeneratorMutator<G> {
+    generator: G,
+}
+
+impl<G, I, S> Mutator<I, S> for GeneratorMutator<G>
+where
+    G: Generator<BytesInput, S>,
+    I: Input + HasBytesVec,
+    S: HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let generated = self.generator.generate(state)?;
+        let size = input.bytes().len();
+        let start = state.rand_mut().between(0, size as u64) as usize;
+        let end = state.rand_mut().between(start as u64, size as u64) as usize;
+
+        let new_size = size - (end - start) + generated.bytes().len();
+        if new_size > state.max_size() {
+            return Ok(MutationResult::Skipped);
+        }
+        input
+            .bytes_mut()
+            .splice(start..end, generated.bytes().iter().copied());
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl<G> Named for GeneratorMutator<G> {
+    fn name(&self) -> &str {
+        "GeneratorMutator"
+    }
+}
+
+impl<G> GeneratorMutator<G> {
+    /// Creates a new [`GeneratorMutator`], mutating with the output of `generator`
+    #[must_use]
+    pub fn new(generator: G) -> Self {
+        Self { generator }
+    }
+
+    /// The generator
+    pub fn generator_mut(&mut self) -> &mut G {
+        &mut self.generator
+    }
+}
+
 /// `Generator` Python bindings
 #[cfg(feature = "python")]
 pub mod pybind {
@@ -0,0 +1,236 @@
+//! Generates the strings matching a regular expression, e.g. to bootstrap a corpus of well-formed
+//! inputs for a parser.
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+use regex::bytes::Regex;
+use regex_syntax::{
+    hir::{Class, Hir, HirKind, Literal, RepetitionKind, RepetitionRange},
+    Parser,
+};
+
+use crate::{
+    bolts::rands::Rand,
+    generators::Generator,
+    inputs::{BytesInput, HasBytesVec},
+    state::HasRand,
+    Error,
+};
+
+/// The default number of extra repetitions for unbounded repetitions, such as `a*` or `a{2,}`
+pub const DEFAULT_MAX_REPEAT: u64 = 8;
+
+/// Generates random [`BytesInput`]s matching a regular expression.
+/// Anchors and word boundaries are ignored.
+pub struct RegexGenerator<S>
+where
+    S: HasRand,
+{
+    pattern: String,
+    hir: Hir,
+    /// The compiled `pattern`, anchored, for [`RegexGenerator::is_match`]
+    regex: Regex,
+    max_repeat: u64,
+    phantom: PhantomData<S>,
+}
+
+impl<S> Debug for RegexGenerator<S>
+where
+    S: HasRand,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegexGenerator")
+            .field("pattern", &self.pattern)
+            .field("max_repeat", &self.max_repeat)
+            .finish()
+    }
+}
+
+impl<S> Generator<BytesInput, S> for RegexGenerator<S>
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<BytesInput, Error> {
+        let mut bytes = vec![];
+        generate_hir(&self.hir, state.rand_mut(), self.max_repeat, &mut bytes);
+        Ok(BytesInput::new(bytes))
+    }
+
+    /// Generates the shortest match, picking the first alternative and the first class member
+    fn generate_dummy(&self, _state: &mut S) -> BytesInput {
+        let mut bytes = vec![];
+        generate_dummy_hir(&self.hir, &mut bytes);
+        BytesInput::new(bytes)
+    }
+}
+
+impl<S> RegexGenerator<S>
+where
+    S: HasRand,
+{
+    /// Creates a new [`RegexGenerator`] for the strings matching `pattern`
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        let hir = Parser::new().parse(pattern).map_err(|err| {
+            Error::IllegalArgument(format!("Invalid regex {:?}: {}", pattern, err))
+        })?;
+        let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|err| {
+            Error::IllegalArgument(format!("Invalid regex {:?}: {}", pattern, err))
+        })?;
+        Ok(Self {
+            pattern: pattern.into(),
+            hir,
+            regex,
+            max_repeat: DEFAULT_MAX_REPEAT,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the most extra repetitions for unbounded repetitions
+    #[must_use]
+    pub fn with_max_repeat(mut self, max_repeat: u64) -> Self {
+        self.max_repeat = max_repeat;
+        self
+    }
+
+    /// The regular expression
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Checks if `input` is the kind of input this generator may produce
+    #[must_use]
+    pub fn is_match(&self, input: &BytesInput) -> bool {
+        self.regex.is_match(input.bytes())
+    }
+}
+
+fn push_char(c: char, bytes: &mut Vec<u8>) {
+    let mut buf = [0; 4];
+    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// The bounds of the number of repetitions
+fn repetition_bounds(kind: &RepetitionKind, max_repeat: u64) -> (u64, u64) {
+    match kind {
+        RepetitionKind::ZeroOrOne => (0, 1),
+        RepetitionKind::ZeroOrMore => (0, max_repeat),
+        RepetitionKind::OneOrMore => (1, 1 + max_repeat),
+        RepetitionKind::Range(RepetitionRange::Exactly(n)) => (u64::from(*n), u64::from(*n)),
+        RepetitionKind::Range(RepetitionRange::AtLeast(n)) => {
+            (u64::from(*n), u64::from(*n) + max_repeat)
+        }
+        RepetitionKind::Range(RepetitionRange::Bounded(m, n)) => (u64::from(*m), u64::from(*n)),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn generate_hir<R>(hir: &Hir, rand: &mut R, max_repeat: u64, bytes: &mut Vec<u8>)
+where
+    R: Rand,
+{
+    match hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => (),
+        HirKind::Literal(Literal::Unicode(c)) => push_char(*c, bytes),
+        HirKind::Literal(Literal::Byte(b)) => bytes.push(*b),
+        HirKind::Class(Class::Unicode(class)) => {
+            if class.ranges().is_empty() {
+                return;
+            }
+            let range = rand.choose(class.ranges());
+            let c = rand.between(u64::from(range.start()), u64::from(range.end())) as u32;
+            // Surrogates are no chars
+            push_char(char::from_u32(c).unwrap_or_else(|| range.start()), bytes);
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            if class.ranges().is_empty() {
+                return;
+            }
+            let range = rand.choose(class.ranges());
+            bytes.push(rand.between(u64::from(range.start()), u64::from(range.end())) as u8);
+        }
+        HirKind::Repetition(repetition) => {
+            let (min, max) = repetition_bounds(&repetition.kind, max_repeat);
+            for _ in 0..rand.between(min, max) {
+                generate_hir(&repetition.hir, rand, max_repeat, bytes);
+            }
+        }
+        HirKind::Group(group) => generate_hir(&group.hir, rand, max_repeat, bytes),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                generate_hir(hir, rand, max_repeat, bytes);
+            }
+        }
+        HirKind::Alternation(hirs) => generate_hir(rand.choose(hirs), rand, max_repeat, bytes),
+    }
+}
+
+fn generate_dummy_hir(hir: &Hir, bytes: &mut Vec<u8>) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => (),
+        HirKind::Literal(Literal::Unicode(c)) => push_char(*c, bytes),
+        HirKind::Literal(Literal::Byte(b)) => bytes.push(*b),
+        HirKind::Class(Class::Unicode(class)) => {
+            if let Some(range) = class.ranges().first() {
+                push_char(range.start(), bytes);
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            if let Some(range) = class.ranges().first() {
+                bytes.push(range.start());
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let (min, _) = repetition_bounds(&repetition.kind, 0);
+            for _ in 0..min {
+                generate_dummy_hir(&repetition.hir, bytes);
+            }
+        }
+        HirKind::Group(group) => generate_dummy_hir(&group.hir, bytes),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                generate_dummy_hir(hir, bytes);
+            }
+        }
+        HirKind::Alternation(hirs) => {
+            if let Some(hir) = hirs.first() {
+                generate_dummy_hir(hir, bytes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        generators::{Generator, RegexGenerator},
+        inputs::{BytesInput, HasBytesVec},
+        state::StdState,
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_regex_generator() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut generator =
+            RegexGenerator::new(r"GET /[a-z]{1,8}(\?id=[0-9]+)? HTTP/1\.[01]").unwrap();
+        for _ in 0..100 {
+            let input = generator.generate(&mut state).unwrap();
+            assert!(generator.is_match(&input), "{:?}", input.bytes());
+        }
+        let dummy = generator.generate_dummy(&mut state);
+        assert_eq!(dummy.bytes(), b"GET /a HTTP/1.0");
+    }
+}
@@ -0,0 +1,205 @@
+//! Generates inputs from a template: fixed bytes with typed holes, such as a file header with a
+//! length field and a random payload.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{bolts::rands::Rand, generators::Generator, inputs::BytesInput, state::HasRand, Error};
+
+/// A part of a [`TemplateGenerator`] template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplatePart {
+    /// These exact bytes
+    Literal(Vec<u8>),
+    /// Between `min_len` and `max_len` random bytes
+    Bytes {
+        /// The least number of bytes
+        min_len: usize,
+        /// The most number of bytes
+        max_len: usize,
+    },
+    /// A random integer of `size` bytes, up to 8
+    Int {
+        /// The size of the integer in bytes
+        size: usize,
+        /// If the integer is big endian
+        big_endian: bool,
+    },
+    /// A random decimal number in ascii, up to `max`
+    AsciiNumber {
+        /// The largest number
+        max: u64,
+    },
+    /// One of these byte strings
+    OneOf(Vec<Vec<u8>>),
+}
+
+/// Generates [`BytesInput`]s from a template, filling the holes with random values of their kind
+#[derive(Clone, Debug)]
+pub struct TemplateGenerator<S>
+where
+    S: HasRand,
+{
+    parts: Vec<TemplatePart>,
+    phantom: PhantomData<S>,
+}
+
+impl<S> Generator<BytesInput, S> for TemplateGenerator<S>
+where
+    S: HasRand,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn generate(&mut self, state: &mut S) -> Result<BytesInput, Error> {
+        let rand = state.rand_mut();
+        let mut bytes = vec![];
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => bytes.extend_from_slice(literal),
+                TemplatePart::Bytes { min_len, max_len } => {
+                    let len = rand.between(*min_len as u64, *max_len as u64);
+                    bytes.extend((0..len).map(|_| rand.below(256) as u8));
+                }
+                TemplatePart::Int { size, big_endian } => {
+                    let val = rand.next().to_le_bytes();
+                    if *big_endian {
+                        bytes.extend(val[..*size].iter().rev());
+                    } else {
+                        bytes.extend_from_slice(&val[..*size]);
+                    }
+                }
+                TemplatePart::AsciiNumber { max } => {
+                    // `between` can not draw from the full range
+                    let val = if *max == u64::MAX {
+                        rand.next()
+                    } else {
+                        rand.between(0, *max)
+                    };
+                    bytes.extend_from_slice(format!("{}", val).as_bytes());
+                }
+                TemplatePart::OneOf(choices) => {
+                    if !choices.is_empty() {
+                        bytes.extend_from_slice(rand.choose(choices));
+                    }
+                }
+            }
+        }
+        Ok(BytesInput::new(bytes))
+    }
+
+    /// Fills the holes with zeroes and the first choices
+    fn generate_dummy(&self, _state: &mut S) -> BytesInput {
+        let mut bytes = vec![];
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => bytes.extend_from_slice(literal),
+                TemplatePart::Bytes { min_len, .. } => bytes.resize(bytes.len() + min_len, 0),
+                TemplatePart::Int { size, .. } => bytes.resize(bytes.len() + size, 0),
+                TemplatePart::AsciiNumber { .. } => bytes.push(b'0'),
+                TemplatePart::OneOf(choices) => {
+                    if let Some(choice) = choices.first() {
+                        bytes.extend_from_slice(choice);
+                    }
+                }
+            }
+        }
+        BytesInput::new(bytes)
+    }
+}
+
+impl<S> TemplateGenerator<S>
+where
+    S: HasRand,
+{
+    /// Creates a new [`TemplateGenerator`] for the given template
+    pub fn new(parts: Vec<TemplatePart>) -> Result<Self, Error> {
+        for part in &parts {
+            match part {
+                TemplatePart::Bytes { min_len, max_len } if min_len > max_len => {
+                    return Err(Error::IllegalArgument(format!(
+                        "Template hole of {} to {} bytes",
+                        min_len, max_len
+                    )));
+                }
+                TemplatePart::Int { size, .. } if *size == 0 || *size > 8 => {
+                    return Err(Error::IllegalArgument(format!(
+                        "Template integer of {} bytes",
+                        size
+                    )));
+                }
+                _ => (),
+            }
+        }
+        Ok(Self {
+            parts,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The template
+    #[must_use]
+    pub fn parts(&self) -> &[TemplatePart] {
+        &self.parts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        generators::{Generator, TemplateGenerator, TemplatePart},
+        inputs::{BytesInput, HasBytesVec},
+        state::StdState,
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_template_generator() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut generator = TemplateGenerator::new(vec![
+            TemplatePart::Literal(b"PK".to_vec()),
+            TemplatePart::Int {
+                size: 2,
+                big_endian: false,
+            },
+            TemplatePart::OneOf(vec![b"a".to_vec(), b"b".to_vec()]),
+            TemplatePart::Bytes {
+                min_len: 1,
+                max_len: 4,
+            },
+        ])
+        .unwrap();
+
+        for _ in 0..10 {
+            let input = generator.generate(&mut state).unwrap();
+            let bytes = input.bytes();
+            assert_eq!(&bytes[..2], b"PK");
+            assert!(bytes[4] == b'a' || bytes[4] == b'b');
+            assert!((6..=9).contains(&bytes.len()));
+        }
+        assert_eq!(generator.generate_dummy(&mut state).bytes(), b"PK\0\0a\0");
+
+        let mut generator =
+            TemplateGenerator::new(vec![TemplatePart::AsciiNumber { max: u64::MAX }]).unwrap();
+        for _ in 0..10 {
+            let input = generator.generate(&mut state).unwrap();
+            assert!(core::str::from_utf8(input.bytes())
+                .unwrap()
+                .parse::<u64>()
+                .is_ok());
+        }
+
+        assert!(TemplateGenerator::<TestState>::new(vec![TemplatePart::Int {
+            size: 9,
+            big_endian: true
+        }])
+        .is_err());
+    }
+}
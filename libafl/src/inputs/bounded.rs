@@ -0,0 +1,272 @@
+//! Bytes inputs with a bound on their size, in buffers that never grow.
+//!
+//! A [`BoundedBytesInput`] reserves its whole bound up front, and the havoc mutations keep it
+//! within [`HasBytesVec::max_len`], so they work in place without reallocating.
+//! With `std`, the buffers come from a per-thread arena and go back to it when the input is
+//! dropped: once the arena is warm, cloning a testcase for each mutation costs no heap allocation.
+
+use ahash::AHasher;
+use alloc::{string::String, vec::Vec};
+use core::{hash::Hasher, mem};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedSlice, HasLen},
+    inputs::{HasBytesVec, HasTargetBytes, Input},
+};
+
+/// The most free buffers the arena of a thread keeps
+#[cfg(feature = "std")]
+const MAX_ARENA_BUFFERS: usize = 64;
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The free buffers of this thread
+    static ARENA: core::cell::RefCell<Vec<Vec<u8>>> = core::cell::RefCell::new(Vec::new());
+}
+
+/// An empty buffer with room for `capacity` bytes, reused from the arena if possible
+#[cfg(feature = "std")]
+fn alloc_buffer(capacity: usize) -> Vec<u8> {
+    match ARENA.try_with(|arena| arena.borrow_mut().pop()) {
+        Ok(Some(mut buffer)) => {
+            buffer.reserve(capacity);
+            buffer
+        }
+        _ => Vec::with_capacity(capacity),
+    }
+}
+
+/// An empty buffer with room for `capacity` bytes
+#[cfg(not(feature = "std"))]
+fn alloc_buffer(capacity: usize) -> Vec<u8> {
+    Vec::with_capacity(capacity)
+}
+
+/// Gives `buffer` back to the arena, or frees it if the arena is full
+#[cfg(feature = "std")]
+fn recycle_buffer(mut buffer: Vec<u8>) {
+    if buffer.capacity() == 0 {
+        return;
+    }
+    buffer.clear();
+    // The arena is gone if the thread is exiting, the buffer is freed then
+    ARENA
+        .try_with(|arena| {
+            let mut arena = arena.borrow_mut();
+            if arena.len() < MAX_ARENA_BUFFERS {
+                arena.push(buffer);
+            }
+        })
+        .ok();
+}
+
+/// Frees `buffer`
+#[cfg(not(feature = "std"))]
+fn recycle_buffer(_buffer: Vec<u8>) {}
+
+/// A bytes input of at most `max_len` bytes, in a buffer with room for all of them.
+///
+/// A fixed-size input, see [`BoundedBytesInput::fixed`], always passes exactly `max_len` bytes to
+/// the target, padded with zeroes if needed, e.g. for a harness taking a struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+#[serde(from = "SerializedBoundedBytesInput")]
+pub struct BoundedBytesInput {
+    bytes: Vec<u8>,
+    max_len: usize,
+    fixed: bool,
+}
+
+/// A [`BoundedBytesInput`] as serialized, deserialized into a buffer with room for the bound
+#[derive(Deserialize)]
+struct SerializedBoundedBytesInput {
+    bytes: Vec<u8>,
+    max_len: usize,
+    fixed: bool,
+}
+
+impl From<SerializedBoundedBytesInput> for BoundedBytesInput {
+    fn from(input: SerializedBoundedBytesInput) -> Self {
+        Self::with_buffer(
+            alloc_buffer(input.max_len),
+            &input.bytes,
+            input.max_len,
+            input.fixed,
+        )
+    }
+}
+
+impl BoundedBytesInput {
+    /// Creates a new [`BoundedBytesInput`], cutting `bytes` at `max_len`
+    #[must_use]
+    pub fn new(bytes: &[u8], max_len: usize) -> Self {
+        Self::with_buffer(alloc_buffer(max_len), bytes, max_len, false)
+    }
+
+    /// Creates a new fixed-size [`BoundedBytesInput`] of `len` bytes
+    #[must_use]
+    pub fn fixed(bytes: &[u8], len: usize) -> Self {
+        Self::with_buffer(alloc_buffer(len), bytes, len, true)
+    }
+
+    fn with_buffer(mut buffer: Vec<u8>, bytes: &[u8], max_len: usize, fixed: bool) -> Self {
+        buffer.clear();
+        buffer.extend_from_slice(&bytes[..bytes.len().min(max_len)]);
+        Self {
+            bytes: buffer,
+            max_len,
+            fixed,
+        }
+    }
+
+    /// If this input always passes [`HasBytesVec::max_len`] bytes to the target
+    #[must_use]
+    pub fn is_fixed(&self) -> bool {
+        self.fixed
+    }
+}
+
+/// Clones get a buffer with room for the whole bound, from the arena
+impl Clone for BoundedBytesInput {
+    fn clone(&self) -> Self {
+        Self::with_buffer(
+            alloc_buffer(self.max_len),
+            &self.bytes,
+            self.max_len,
+            self.fixed,
+        )
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.bytes.clear();
+        self.bytes.reserve(source.max_len);
+        self.bytes.extend_from_slice(&source.bytes);
+        self.max_len = source.max_len;
+        self.fixed = source.fixed;
+    }
+}
+
+impl Drop for BoundedBytesInput {
+    fn drop(&mut self) {
+        recycle_buffer(mem::take(&mut self.bytes));
+    }
+}
+
+impl Input for BoundedBytesInput {
+    /// Generate a name for this input
+    fn generate_name(&self, _idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(&self.bytes);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl HasBytesVec for BoundedBytesInput {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[inline]
+    fn bytes_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.bytes
+    }
+
+    #[inline]
+    fn max_len(&self) -> Option<usize> {
+        Some(self.max_len)
+    }
+}
+
+impl HasTargetBytes for BoundedBytesInput {
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        if self.bytes.len() >= self.max_len {
+            OwnedSlice::from(&self.bytes[..self.max_len])
+        } else if self.fixed {
+            let mut bytes = self.bytes.clone();
+            bytes.resize(self.max_len, 0);
+            OwnedSlice::from(bytes)
+        } else {
+            OwnedSlice::from(&self.bytes)
+        }
+    }
+}
+
+impl HasLen for BoundedBytesInput {
+    #[inline]
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::{Rand, StdRand},
+            tuples::HasConstLen,
+            AsSlice,
+        },
+        corpus::{Corpus, InMemoryCorpus},
+        inputs::{BoundedBytesInput, HasBytesVec, HasTargetBytes},
+        mutators::{havoc_mutations, MutationResult, MutatorsTuple},
+        state::{HasCorpus, HasMaxSize, HasRand, StdState},
+    };
+
+    #[test]
+    fn test_bounded_bytes_input() {
+        let input = BoundedBytesInput::new(b"0123456789", 8);
+        assert_eq!(input.bytes(), b"01234567");
+        assert_eq!(input.max_len(), Some(8));
+
+        // Dropped inputs give their buffer to the next clone
+        let clone = input.clone();
+        let ptr = clone.bytes().as_ptr();
+        drop(clone);
+        let clone = input.clone();
+        assert_eq!(clone.bytes().as_ptr(), ptr);
+
+        // Deserialized inputs get room for their bound too
+        let serialized = postcard::to_allocvec(&input).unwrap();
+        let deserialized: BoundedBytesInput = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(deserialized, input);
+        assert!(deserialized.bytes.capacity() >= 8);
+
+        let fixed = BoundedBytesInput::fixed(b"ab", 4);
+        assert!(fixed.is_fixed());
+        assert_eq!(fixed.target_bytes().as_slice(), b"ab\0\0");
+    }
+
+    #[test]
+    fn test_havoc_in_place() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BoundedBytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        state.set_max_size(1024);
+        for seed in [&b"abcdefgh"[..], b"0123", b"xyzxyzxyzxyz"] {
+            state
+                .corpus_mut()
+                .add(BoundedBytesInput::new(seed, 16).into())
+                .unwrap();
+        }
+
+        let mut mutations = havoc_mutations();
+        let mut input = BoundedBytesInput::new(b"ABCDEFGHIJKL", 16);
+        let ptr = input.bytes().as_ptr();
+        for _ in 0..1000 {
+            let idx = state.rand_mut().below(mutations.len() as u64) as usize;
+            if mutations
+                .get_and_mutate(idx, &mut state, &mut input, 0)
+                .unwrap()
+                == MutationResult::Mutated
+            {
+                // The mutations stay within the bound, in the same buffer
+                assert!(input.bytes().len() <= 16);
+                assert_eq!(input.bytes().as_ptr(), ptr);
+            }
+        }
+    }
+}
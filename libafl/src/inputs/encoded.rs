@@ -30,6 +30,13 @@ pub trait InputDecoder {
     /// Decode encoded input to bytes
     #[allow(clippy::ptr_arg)] // we reuse the alloced `Vec`
     fn decode(&self, input: &EncodedInput, bytes: &mut Vec<u8>) -> Result<(), Error>;
+
+    /// Decode encoded input to a new [`Vec`] of bytes, e.g. for the harness
+    fn decode_to_vec(&self, input: &EncodedInput) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.decode(input, &mut bytes)?;
+        Ok(bytes)
+    }
 }
 
 /// Tokenizer is a trait that can tokenize bytes into a [`Vec`] of tokens
@@ -47,6 +54,8 @@ pub struct TokenInputEncoderDecoder {
     id_table: HashMap<u32, String>,
     /// The next id
     next_id: u32,
+    /// The bytes written after each token when decoding
+    separator: Vec<u8>,
 }
 
 impl<T> InputEncoder<T> for TokenInputEncoderDecoder
@@ -72,12 +81,17 @@ where
 
 impl InputDecoder for TokenInputEncoderDecoder {
     fn decode(&self, input: &EncodedInput, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        if self.next_id == 0 && !input.codes().is_empty() {
+            return Err(Error::IllegalState(
+                "Cannot decode without any token in the decoder table".into(),
+            ));
+        }
         for id in input.codes() {
             let tok = self.id_table.get(&(id % self.next_id)).ok_or_else(|| {
                 Error::IllegalState(format!("Id {} not in the decoder table", id))
             })?;
             bytes.extend_from_slice(tok.as_bytes());
-            bytes.push(b' ');
+            bytes.extend_from_slice(&self.separator);
         }
         Ok(())
     }
@@ -91,8 +105,23 @@ impl TokenInputEncoderDecoder {
             token_table: HashMap::default(),
             id_table: HashMap::default(),
             next_id: 0,
+            separator: vec![b' '],
         }
     }
+
+    /// Sets the bytes written after each token when decoding, a space by default.
+    /// Use an empty separator for targets where the tokens carry all the whitespace.
+    #[must_use]
+    pub fn with_separator(mut self, separator: &[u8]) -> Self {
+        self.separator = separator.to_vec();
+        self
+    }
+
+    /// The number of known tokens
+    #[must_use]
+    pub fn tokens_len(&self) -> usize {
+        self.next_id as usize
+    }
 }
 
 impl Default for TokenInputEncoderDecoder {
@@ -257,7 +286,7 @@ impl EncodedInput {
 #[cfg(test)]
 mod tests {
     use crate::inputs::encoded::{
-        InputDecoder, InputEncoder, NaiveTokenizer, TokenInputEncoderDecoder,
+        EncodedInput, InputDecoder, InputEncoder, NaiveTokenizer, TokenInputEncoderDecoder,
     };
     use core::str::from_utf8;
 
@@ -275,4 +304,15 @@ mod tests {
             "a = 'pippo baudo' ; b = c + a ".to_owned()
         );
     }
+
+    #[test]
+    fn test_separator() {
+        let mut t = NaiveTokenizer::default();
+        let mut ed = TokenInputEncoderDecoder::new().with_separator(b"");
+        assert!(ed.decode_to_vec(&EncodedInput::new(vec![0])).is_err());
+
+        let input = ed.encode(b"f(x,y)", &mut t).unwrap();
+        assert_eq!(ed.tokens_len(), 6);
+        assert_eq!(ed.decode_to_vec(&input).unwrap(), b"f(x,y)");
+    }
 }
@@ -25,6 +25,17 @@ pub enum GeneralizedItem {
     Gap,
 }
 
+/// The generalized form of a testcase, found by the [`crate::stages::GeneralizationStage`].
+/// It is kept in the testcase, as the [`GeneralizedInput`] drops its generalized form whenever
+/// it is mutated at the bit level.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GeneralizedInputMetadata {
+    /// The generalized form, starting and ending with a [`GeneralizedItem::Gap`]
+    pub generalized: Vec<GeneralizedItem>,
+}
+
+crate::impl_serdeany!(GeneralizedInputMetadata);
+
 /// A bytes input with a generalized version mainly used for Grimoire
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct GeneralizedInput {
@@ -3,6 +3,9 @@
 pub mod bytes;
 pub use bytes::BytesInput;
 
+pub mod bounded;
+pub use bounded::BoundedBytesInput;
+
 pub mod encoded;
 pub use encoded::*;
 
@@ -12,6 +15,9 @@ pub use gramatron::*;
 pub mod generalized;
 pub use generalized::*;
 
+pub mod multi;
+pub use multi::MultipartInput;
+
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "nautilus")]
@@ -109,4 +115,8 @@ pub trait HasBytesVec {
     fn bytes(&self) -> &[u8];
     /// The internal bytes map (as mutable borrow)
     fn bytes_mut(&mut self) -> &mut Vec<u8>;
+    /// The most bytes this input holds, if it is bounded. The mutations keep it within the bound.
+    fn max_len(&self) -> Option<usize> {
+        None
+    }
 }
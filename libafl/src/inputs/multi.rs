@@ -0,0 +1,180 @@
+//! The [`MultipartInput`] is made of several named parts, e.g. the key and the value for a
+//! key-value store, each an input on its own, mutated on its own.
+
+use ahash::AHasher;
+use alloc::{string::String, vec::Vec};
+use core::hash::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedSlice, AsSlice},
+    inputs::{HasTargetBytes, Input},
+};
+
+/// An input made of several named parts, for harnesses taking several buffers.
+/// The parts keep their order, and several parts may share a name.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MultipartInput<I> {
+    parts: Vec<I>,
+    names: Vec<String>,
+}
+
+impl<I> MultipartInput<I> {
+    /// Creates a new [`MultipartInput`] without parts
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            parts: Vec::new(),
+            names: Vec::new(),
+        }
+    }
+
+    /// Adds a part named `name`
+    #[must_use]
+    pub fn with_part<S>(mut self, name: S, part: I) -> Self
+    where
+        S: Into<String>,
+    {
+        self.add_part(name, part);
+        self
+    }
+
+    /// Adds a part named `name`
+    pub fn add_part<S>(&mut self, name: S, part: I)
+    where
+        S: Into<String>,
+    {
+        self.parts.push(part);
+        self.names.push(name.into());
+    }
+
+    /// Removes the part at `idx`, with its name
+    pub fn remove_part(&mut self, idx: usize) -> Option<(String, I)> {
+        if idx < self.parts.len() {
+            Some((self.names.remove(idx), self.parts.remove(idx)))
+        } else {
+            None
+        }
+    }
+
+    /// The parts
+    #[must_use]
+    pub fn parts(&self) -> &[I] {
+        &self.parts
+    }
+
+    /// The parts (mutable)
+    pub fn parts_mut(&mut self) -> &mut [I] {
+        &mut self.parts
+    }
+
+    /// The names of the parts, in the order of the parts
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The parts named `name`, with their index
+    pub fn parts_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (usize, &'a I)> + 'a {
+        self.names
+            .iter()
+            .zip(&self.parts)
+            .enumerate()
+            .filter(move |(_, (part_name, _))| *part_name == name)
+            .map(|(idx, (_, part))| (idx, part))
+    }
+
+    /// The first part named `name`
+    #[must_use]
+    pub fn part_by_name(&self, name: &str) -> Option<&I> {
+        self.parts_by_name(name).next().map(|(_, part)| part)
+    }
+
+    /// The first part named `name` (mutable)
+    pub fn part_by_name_mut(&mut self, name: &str) -> Option<&mut I> {
+        let idx = self.names.iter().position(|part_name| part_name == name)?;
+        Some(&mut self.parts[idx])
+    }
+
+    /// The number of parts
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// If there are no parts
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+}
+
+impl<I> Input for MultipartInput<I>
+where
+    I: Input,
+{
+    /// Generate a name for this input, from the names of its parts
+    fn generate_name(&self, idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        for (name, part) in self.names.iter().zip(&self.parts) {
+            hasher.write(name.as_bytes());
+            hasher.write(part.generate_name(idx).as_bytes());
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+/// The target bytes of all parts, each after its length as little endian `u32`.
+/// A harness gets the parts back with [`split_multipart_bytes`].
+impl<I> HasTargetBytes for MultipartInput<I>
+where
+    I: HasTargetBytes,
+{
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        let mut bytes = Vec::new();
+        for part in &self.parts {
+            let part_bytes = part.target_bytes();
+            let part_bytes = part_bytes.as_slice();
+            bytes.extend_from_slice(&(part_bytes.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(part_bytes);
+        }
+        OwnedSlice::from(bytes)
+    }
+}
+
+/// Splits the target bytes of a [`MultipartInput`] into the bytes of its parts, for the harness.
+/// A truncated last part is cut at the end of `bytes`.
+#[must_use]
+pub fn split_multipart_bytes(mut bytes: &[u8]) -> Vec<&[u8]> {
+    let mut parts = Vec::new();
+    while bytes.len() >= 4 {
+        let len = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        let len = len.min(bytes.len() - 4);
+        parts.push(&bytes[4..4 + len]);
+        bytes = &bytes[4 + len..];
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::AsSlice,
+        inputs::{multi::split_multipart_bytes, BytesInput, HasTargetBytes, MultipartInput},
+    };
+
+    #[test]
+    fn test_multipart_target_bytes() {
+        let input = MultipartInput::new()
+            .with_part("key", BytesInput::new(b"abc".to_vec()))
+            .with_part("value", BytesInput::new(vec![]))
+            .with_part("value", BytesInput::new(b"d".to_vec()));
+
+        assert_eq!(input.part_by_name("value"), Some(&BytesInput::new(vec![])));
+        assert_eq!(input.parts_by_name("value").count(), 2);
+
+        let bytes = input.target_bytes();
+        let parts = split_multipart_bytes(bytes.as_slice());
+        assert_eq!(parts, vec![&b"abc"[..], &b""[..], &b"d"[..]]);
+    }
+}
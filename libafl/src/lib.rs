@@ -96,6 +96,7 @@ pub mod observers;
 pub mod schedulers;
 pub mod stages;
 pub mod state;
+pub mod triage;
 
 pub mod fuzzer;
 use alloc::string::{FromUtf8Error, String};
@@ -3,6 +3,21 @@
 pub mod multi;
 pub use multi::MultiMonitor;
 
+#[cfg(feature = "std")]
+pub mod prometheus;
+#[cfg(feature = "std")]
+pub use prometheus::PrometheusMonitor;
+
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "std")]
+pub use telemetry::{JsonLinesMonitor, StatsdMonitor};
+
+#[cfg(feature = "std")]
+pub mod plot;
+#[cfg(feature = "std")]
+pub use plot::AflPlotMonitor;
+
 #[cfg(all(feature = "tui_monitor", feature = "std"))]
 #[allow(missing_docs)]
 pub mod tui;
@@ -52,10 +67,123 @@ impl fmt::Display for UserStats {
     }
 }
 
+/// A named user stat, as accumulated in a [`UserStatsTracker`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum UserStatValue {
+    /// A counter, only ever incremented
+    Counter(u64),
+    /// A gauge, set to the last value
+    Gauge(f64),
+    /// The average of all samples
+    Average {
+        /// The sum of all samples
+        sum: f64,
+        /// The number of samples
+        count: u64,
+    },
+}
+
+impl UserStatValue {
+    /// The [`UserStats`] sent to the monitors for this value
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_user_stats(&self) -> UserStats {
+        match self {
+            Self::Counter(n) => UserStats::Number(*n),
+            Self::Gauge(v) => UserStats::Float(*v),
+            Self::Average { sum, count } => UserStats::Float(if *count == 0 {
+                0.0
+            } else {
+                sum / *count as f64
+            }),
+        }
+    }
+}
+
+/// The named user stats of a client, e.g. from the harness or the executor.
+/// The fuzzer sends the ones updated since its last report to the monitors,
+/// with [`crate::events::ProgressReporter::maybe_report_progress`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UserStatsTracker {
+    /// The stats, and if they changed since the last report
+    stats: HashMap<String, (UserStatValue, bool)>,
+}
+
+impl UserStatsTracker {
+    /// Creates a new, empty [`UserStatsTracker`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Increments the counter `name` by `by`
+    pub fn increment(&mut self, name: &str, by: u64) {
+        match self.stats.get_mut(name) {
+            Some((UserStatValue::Counter(n), dirty)) => {
+                *n += by;
+                *dirty = true;
+            }
+            _ => {
+                self.stats
+                    .insert(name.into(), (UserStatValue::Counter(by), true));
+            }
+        }
+    }
+
+    /// Sets the gauge `name` to `value`
+    pub fn set_gauge(&mut self, name: &str, value: f64) {
+        self.stats
+            .insert(name.into(), (UserStatValue::Gauge(value), true));
+    }
+
+    /// Adds a sample to the average `name`
+    pub fn add_sample(&mut self, name: &str, value: f64) {
+        match self.stats.get_mut(name) {
+            Some((UserStatValue::Average { sum, count }, dirty)) => {
+                *sum += value;
+                *count += 1;
+                *dirty = true;
+            }
+            _ => {
+                self.stats.insert(
+                    name.into(),
+                    (
+                        UserStatValue::Average {
+                            sum: value,
+                            count: 1,
+                        },
+                        true,
+                    ),
+                );
+            }
+        }
+    }
+
+    /// The current value of the stat `name`
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&UserStatValue> {
+        self.stats.get(name).map(|(value, _)| value)
+    }
+
+    /// The stats updated since the last call, to send them to the monitors
+    pub fn take_updates(&mut self) -> Vec<(String, UserStats)> {
+        self.stats
+            .iter_mut()
+            .filter(|(_, (_, dirty))| *dirty)
+            .map(|(name, (value, dirty))| {
+                *dirty = false;
+                (name.clone(), value.to_user_stats())
+            })
+            .collect()
+    }
+}
+
 /// A simple struct to keep track of client monitor
 #[derive(Debug, Clone, Default)]
 pub struct ClientStats {
     // monitor (maybe we need a separated struct?)
+    /// The name of this client, if it announced one
+    pub name: Option<String>,
     /// The corpus size for this client
     pub corpus_size: u64,
     /// The total executions for this client
@@ -70,6 +198,10 @@ pub struct ClientStats {
     pub last_execs_per_sec: f64,
     /// The last time we got this information
     pub last_window_time: Duration,
+    /// The last time we heard from this client
+    pub last_heartbeat: Duration,
+    /// The number of testcases this client found and added to the corpus
+    pub corpus_contributions: u64,
     /// User-defined monitor
     pub user_monitor: HashMap<String, UserStats>,
     /// Client performance statistics
@@ -77,6 +209,9 @@ pub struct ClientStats {
     pub introspection_monitor: ClientPerfMonitor,
 }
 
+/// The prefix of the user stats of the [`crate::stages::ProfilerReportStage`]
+pub const PROFILE_STATS_PREFIX: &str = "profile_";
+
 impl ClientStats {
     /// We got a new information about executions for this client, insert them.
     #[cfg(feature = "afl_exec_sec")]
@@ -98,6 +233,29 @@ impl ClientStats {
         self.executions = executions;
     }
 
+    /// The client announced its name
+    pub fn update_name(&mut self, name: String) {
+        self.name = Some(name);
+    }
+
+    /// We heard from this client
+    pub fn heartbeat(&mut self, cur_time: Duration) {
+        self.last_heartbeat = cur_time;
+    }
+
+    /// The client found a new testcase for the corpus
+    pub fn add_corpus_contribution(&mut self) {
+        self.corpus_contributions += 1;
+    }
+
+    /// The time since we last heard from this client
+    #[must_use]
+    pub fn since_heartbeat(&self, cur_time: Duration) -> Duration {
+        cur_time
+            .checked_sub(self.last_heartbeat)
+            .unwrap_or_default()
+    }
+
     /// We got a new information about corpus size for this client, insert them.
     pub fn update_corpus_size(&mut self, corpus_size: u64) {
         self.corpus_size = corpus_size;
@@ -167,6 +325,20 @@ impl ClientStats {
         self.user_monitor.get(name)
     }
 
+    /// The coverage of this client, as `(covered, size)`: the largest ratio user stat
+    /// not sent by the profiler, usually the one of the `MapFeedback`
+    #[must_use]
+    pub fn coverage(&self) -> Option<(u64, u64)> {
+        self.user_monitor
+            .iter()
+            .filter(|(key, _)| !key.starts_with(PROFILE_STATS_PREFIX))
+            .filter_map(|(_, val)| match val {
+                UserStats::Ratio(covered, size) => Some((*covered, *size)),
+                _ => None,
+            })
+            .max()
+    }
+
     /// Update the current [`ClientPerfMonitor`] with the given [`ClientPerfMonitor`]
     #[cfg(feature = "introspection")]
     pub fn update_introspection_monitor(&mut self, introspection_monitor: ClientPerfMonitor) {
@@ -225,11 +397,24 @@ pub trait Monitor {
         for _ in client_stat_count..(client_id + 1) as usize {
             self.client_stats_mut().push(ClientStats {
                 last_window_time: current_time(),
+                last_heartbeat: current_time(),
                 ..ClientStats::default()
             });
         }
         &mut self.client_stats_mut()[client_id as usize]
     }
+
+    /// The id of a client, with its name, if it announced one, to attribute output to it
+    fn client_label(&self, client_id: u32) -> String {
+        match self
+            .client_stats()
+            .get(client_id as usize)
+            .and_then(|client| client.name.as_ref())
+        {
+            Some(name) => format!("#{} ({})", client_id, name),
+            None => format!("#{}", client_id),
+        }
+    }
 }
 
 /// Monitor that print exactly nothing.
@@ -307,10 +492,10 @@ where
     }
 
     fn display(&mut self, event_msg: String, sender_id: u32) {
-        let fmt = format!(
-            "[{} #{}] run time: {}, clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
+        let mut fmt = format!(
+            "[{} {}] run time: {}, clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
             event_msg,
-            sender_id,
+            self.client_label(sender_id),
             format_duration_hms(&(current_time() - self.start_time)),
             self.client_stats().len(),
             self.corpus_size(),
@@ -318,6 +503,11 @@ where
             self.total_execs(),
             self.execs_per_sec()
         );
+        if let Some(client) = self.client_stats.get(sender_id as usize) {
+            for (key, val) in &client.user_monitor {
+                fmt += &format!(", {}: {}", key, val);
+            }
+        }
         (self.print_fn)(fmt);
 
         // Only print perf monitor if the feature is enabled
@@ -914,3 +1104,42 @@ pub mod pybind {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::monitors::{ClientStats, UserStatValue, UserStats, UserStatsTracker};
+
+    #[test]
+    fn test_user_stats_tracker() {
+        let mut tracker = UserStatsTracker::new();
+        tracker.increment("restored pages", 3);
+        tracker.increment("restored pages", 4);
+        tracker.add_sample("pages per exec", 1.0);
+        tracker.add_sample("pages per exec", 2.0);
+        assert_eq!(
+            tracker.get("restored pages"),
+            Some(&UserStatValue::Counter(7))
+        );
+
+        let mut updates = tracker.take_updates();
+        updates.sort_by(|a, b| a.0.cmp(&b.0));
+        assert!(matches!(updates[0].1, UserStats::Float(avg) if (avg - 1.5).abs() < f64::EPSILON));
+        assert!(matches!(updates[1].1, UserStats::Number(7)));
+
+        // Only the changed stats are sent again
+        assert!(tracker.take_updates().is_empty());
+        tracker.set_gauge("queue depth", 2.0);
+        assert_eq!(tracker.take_updates().len(), 1);
+    }
+
+    #[test]
+    fn test_client_coverage() {
+        let mut client = ClientStats::default();
+        assert_eq!(client.coverage(), None);
+        client.update_user_stats("profile_target".into(), UserStats::Ratio(90, 100));
+        client.update_user_stats("edges".into(), UserStats::Ratio(16, 64));
+        client.update_user_stats("cmps".into(), UserStats::Ratio(4, 64));
+        client.update_user_stats("execs".into(), UserStats::Number(1000));
+        assert_eq!(client.coverage(), Some((16, 64)));
+    }
+}
@@ -42,7 +42,7 @@ where
     }
 
     fn display(&mut self, event_msg: String, sender_id: u32) {
-        let sender = format!("#{}", sender_id);
+        let sender = self.client_label(sender_id);
         let pad = if event_msg.len() + sender.len() < 13 {
             " ".repeat(13 - event_msg.len() - sender.len())
         } else {
@@ -67,8 +67,13 @@ where
 
         let pad = " ".repeat(head.len());
         let mut fmt = format!(
-            " {}   (CLIENT) corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
-            pad, client.corpus_size, client.objective_size, client.executions, exec_sec
+            " {}   (CLIENT) corpus: {}, found: {}, objectives: {}, executions: {}, exec/sec: {}",
+            pad,
+            client.corpus_size,
+            client.corpus_contributions,
+            client.objective_size,
+            client.executions,
+            exec_sec
         );
         for (key, val) in &client.user_monitor {
             fmt += &format!(", {}: {}", key, val);
@@ -0,0 +1,262 @@
+//! A monitor writing AFL-compatible `plot_data` and `fuzzer_stats` files, for `afl-plot` and `afl-whatsup`.
+//!
+//! Each client gets a `client_<id>` directory in the output directory, like the instances of an AFL
+//! campaign in its sync dir; the aggregated `plot_data` of all clients is in the output directory itself.
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor},
+    Error,
+};
+
+/// AFL writes its plot data every 5 seconds
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The header of the `plot_data` files, as written by AFL++
+const PLOT_HEADER: &str = "# relative_time, cycles_done, cur_item, corpus_count, pending_total, pending_favs, map_size, saved_crashes, saved_hangs, max_depth, execs_per_sec, total_execs, edges_found";
+
+/// A row of the plot data
+#[derive(Debug, Default, Clone, Copy)]
+struct PlotRow {
+    corpus: u64,
+    objectives: u64,
+    executions: u64,
+    exec_sec: u64,
+    covered: u64,
+    map_size: u64,
+}
+
+impl PlotRow {
+    #[allow(clippy::cast_precision_loss)]
+    fn map_density(&self) -> f64 {
+        if self.map_size == 0 {
+            0.0
+        } else {
+            self.covered as f64 * 100.0 / self.map_size as f64
+        }
+    }
+}
+
+/// Writes AFL-compatible `plot_data` and `fuzzer_stats` files next to the wrapped monitor.
+#[derive(Debug, Clone)]
+pub struct AflPlotMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    out_dir: PathBuf,
+    interval: Duration,
+    last_emit: Duration,
+}
+
+impl<M> Monitor for AflPlotMonitor<M>
+where
+    M: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        let cur_time = current_time();
+        if cur_time - self.last_emit >= self.interval {
+            self.last_emit = cur_time;
+            if let Err(e) = self.write_plots(cur_time) {
+                println!("Failed to write the plot data: {:?}", e);
+            }
+        }
+
+        self.base.display(event_msg, sender_id);
+    }
+}
+
+impl<M> AflPlotMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`AflPlotMonitor`] wrapping `base`, writing to `out_dir`
+    pub fn new<P>(base: M, out_dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::create_dir_all(&out_dir)?;
+        Ok(Self {
+            base,
+            out_dir: out_dir.as_ref().to_path_buf(),
+            interval: DEFAULT_INTERVAL,
+            last_emit: Duration::ZERO,
+        })
+    }
+
+    /// Writes the plot data at most once per `interval`, instead of every 5 seconds
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn write_plots(&mut self, cur_time: Duration) -> Result<(), Error> {
+        let start_time = self.start_time();
+        let run_time = cur_time - start_time;
+
+        let mut total = PlotRow::default();
+        let mut rows = vec![];
+        for (id, client) in self.client_stats_mut().iter_mut().enumerate() {
+            // The broker itself never executes anything
+            if client.executions == 0 {
+                continue;
+            }
+            let (covered, map_size) = client.coverage().unwrap_or((0, 0));
+            let row = PlotRow {
+                corpus: client.corpus_size,
+                objectives: client.objective_size,
+                executions: client.executions,
+                exec_sec: client.execs_per_sec(cur_time),
+                covered,
+                map_size,
+            };
+            total.corpus += row.corpus;
+            total.objectives += row.objectives;
+            total.executions += row.executions;
+            total.exec_sec += row.exec_sec;
+            // All clients usually share the same map: the aggregated coverage is the best one
+            if row.covered > total.covered {
+                total.covered = row.covered;
+                total.map_size = row.map_size;
+            }
+            rows.push((id, row, client.name.clone()));
+        }
+
+        for (id, row, name) in rows {
+            let dir = self.out_dir.join(format!("client_{}", id));
+            fs::create_dir_all(&dir)?;
+            append_plot_row(&dir.join("plot_data"), run_time, &row)?;
+            let banner = name.unwrap_or_else(|| format!("client {}", id));
+            write_fuzzer_stats(&dir, start_time, cur_time, &banner, &row)?;
+        }
+        append_plot_row(&self.out_dir.join("plot_data"), run_time, &total)
+    }
+}
+
+/// Appends a row to a `plot_data` file, starting it with the header
+fn append_plot_row(path: &Path, run_time: Duration, row: &PlotRow) -> Result<(), Error> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", PLOT_HEADER)?;
+    }
+    writeln!(
+        file,
+        "{}, 0, 0, {}, 0, 0, {:.2}%, {}, 0, 0, {}, {}, {}",
+        run_time.as_secs(),
+        row.corpus,
+        row.map_density(),
+        row.objectives,
+        row.exec_sec,
+        row.executions,
+        row.covered
+    )?;
+    Ok(())
+}
+
+/// Writes the `fuzzer_stats` of a client, as read by `afl-whatsup`
+fn write_fuzzer_stats(
+    dir: &Path,
+    start_time: Duration,
+    cur_time: Duration,
+    banner: &str,
+    row: &PlotRow,
+) -> Result<(), Error> {
+    let stats = format!(
+        "start_time        : {}\n\
+         last_update       : {}\n\
+         run_time          : {}\n\
+         fuzzer_pid        : {}\n\
+         cycles_done       : 0\n\
+         execs_done        : {}\n\
+         execs_per_sec     : {}\n\
+         corpus_count      : {}\n\
+         pending_favs      : 0\n\
+         pending_total     : 0\n\
+         bitmap_cvg        : {:.2}%\n\
+         edges_found       : {}\n\
+         saved_crashes     : {}\n\
+         unique_crashes    : {}\n\
+         saved_hangs       : 0\n\
+         unique_hangs      : 0\n\
+         afl_banner        : {}\n\
+         afl_version       : libafl\n",
+        start_time.as_secs(),
+        cur_time.as_secs(),
+        (cur_time - start_time).as_secs(),
+        process::id(),
+        row.executions,
+        row.exec_sec,
+        row.corpus,
+        row.map_density(),
+        row.covered,
+        row.objectives,
+        row.objectives,
+        banner,
+    );
+    // Write it atomically, `afl-whatsup` may read it any time
+    let tmp = dir.join(".fuzzer_stats.tmp");
+    fs::write(&tmp, stats)?;
+    fs::rename(tmp, dir.join("fuzzer_stats"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::monitors::{AflPlotMonitor, Monitor, NopMonitor, UserStats};
+
+    #[test]
+    fn test_afl_plot_monitor() {
+        let dir = std::env::temp_dir().join("libafl_test_afl_plot_monitor");
+        drop(fs::remove_dir_all(&dir));
+
+        let mut monitor = AflPlotMonitor::new(NopMonitor::new(), &dir).unwrap();
+        let client = monitor.client_stats_mut_for(1);
+        client.update_corpus_size(5);
+        client.update_user_stats("edges".into(), UserStats::Ratio(16, 64));
+        client.executions = 100;
+        monitor.display("Testcase".into(), 1);
+
+        let plot = fs::read_to_string(dir.join("client_1").join("plot_data")).unwrap();
+        let mut lines = plot.lines();
+        assert!(lines.next().unwrap().starts_with("# relative_time"));
+        let columns = lines.next().unwrap().split(", ").collect::<Vec<_>>();
+        assert_eq!(columns.len(), 13);
+        assert_eq!(columns[3], "5");
+        assert_eq!(columns[6], "25.00%");
+        assert_eq!(columns[12], "16");
+
+        let stats = fs::read_to_string(dir.join("client_1").join("fuzzer_stats")).unwrap();
+        assert!(stats.contains("execs_done        : 100\n"));
+        assert!(dir.join("plot_data").exists());
+        assert!(!dir.join("client_0").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
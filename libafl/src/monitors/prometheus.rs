@@ -0,0 +1,355 @@
+//! A monitor exporting the stats of all clients in the Prometheus text format, on an HTTP `/metrics` endpoint.
+//!
+//! Point a Prometheus scraper at it, to track the campaign with the usual Grafana dashboards.
+//! The global stats are exported as `libafl_*`, the stats of each client as `libafl_client_*`,
+//! labelled with the client id; numerical user stats are in `libafl_client_user_stat`.
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{fmt::Write as _, time::Duration};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    thread,
+};
+
+use crate::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor, UserStats},
+    Error,
+};
+
+/// How long a scraper may take to send its request or read the response
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tracking monitor during fuzzing, serving the stats to Prometheus on `/metrics`.
+#[derive(Clone, Debug)]
+pub struct PrometheusMonitor<F>
+where
+    F: FnMut(String),
+{
+    print_fn: F,
+    start_time: Duration,
+    client_stats: Vec<ClientStats>,
+    metrics: Arc<Mutex<String>>,
+    addr: SocketAddr,
+}
+
+impl<F> Monitor for PrometheusMonitor<F>
+where
+    F: FnMut(String),
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        &mut self.client_stats
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        &self.client_stats
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.start_time
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        let fmt = format!(
+            "[{} {}] clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
+            event_msg,
+            self.client_label(sender_id),
+            self.client_stats().len(),
+            self.corpus_size(),
+            self.objective_size(),
+            self.total_execs(),
+            self.execs_per_sec()
+        );
+        (self.print_fn)(fmt);
+
+        let metrics = self.render();
+        *self.metrics.lock().unwrap() = metrics;
+    }
+}
+
+impl<F> PrometheusMonitor<F>
+where
+    F: FnMut(String),
+{
+    /// Creates the monitor, serving the metrics on `addr`, e.g. `0.0.0.0:9090`.
+    /// Each update is also printed with `print_fn`.
+    pub fn new<A>(addr: A, print_fn: F) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        Self::with_time(addr, print_fn, current_time())
+    }
+
+    /// Creates the monitor with a given `start_time`.
+    pub fn with_time<A>(addr: A, print_fn: F, start_time: Duration) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let metrics = Arc::new(Mutex::new(String::new()));
+
+        let served = metrics.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = serve(stream, &served) {
+                    println!("Failed to serve metrics: {:?}", e);
+                }
+            }
+        });
+
+        Ok(Self {
+            print_fn,
+            start_time,
+            client_stats: vec![],
+            metrics,
+            addr,
+        })
+    }
+
+    /// The address the metrics are served on
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Renders the current stats in the Prometheus text format
+    #[allow(clippy::cast_precision_loss)]
+    pub fn render(&mut self) -> String {
+        let mut out = String::new();
+        let run_time = (current_time() - self.start_time).as_secs();
+        let cur_time = current_time();
+
+        gauge(
+            &mut out,
+            "libafl_run_time_seconds",
+            "Run time of the campaign",
+        );
+        metric(&mut out, "libafl_run_time_seconds", "", run_time);
+        gauge(&mut out, "libafl_clients", "Number of clients");
+        metric(&mut out, "libafl_clients", "", self.client_stats().len());
+        gauge(&mut out, "libafl_corpus_size", "Testcases in all corpora");
+        metric(&mut out, "libafl_corpus_size", "", self.corpus_size());
+        gauge(
+            &mut out,
+            "libafl_objectives",
+            "Objectives found by all clients",
+        );
+        metric(&mut out, "libafl_objectives", "", self.objective_size());
+        counter(
+            &mut out,
+            "libafl_executions_total",
+            "Executions of all clients",
+        );
+        metric(&mut out, "libafl_executions_total", "", self.total_execs());
+        gauge(
+            &mut out,
+            "libafl_execs_per_sec",
+            "Executions per second of all clients",
+        );
+        metric(&mut out, "libafl_execs_per_sec", "", self.execs_per_sec());
+
+        let labels = (0..self.client_stats.len())
+            .map(|id| {
+                let mut label = format!("client=\"{}\"", id);
+                if let Some(name) = &self.client_stats[id].name {
+                    let _ = write!(label, ",name=\"{}\"", escape(name));
+                }
+                label
+            })
+            .collect::<Vec<_>>();
+
+        gauge(
+            &mut out,
+            "libafl_client_corpus_size",
+            "Testcases in the corpus of the client",
+        );
+        for (client, label) in self.client_stats.iter().zip(&labels) {
+            metric(
+                &mut out,
+                "libafl_client_corpus_size",
+                label,
+                client.corpus_size,
+            );
+        }
+        gauge(
+            &mut out,
+            "libafl_client_objectives",
+            "Objectives found by the client",
+        );
+        for (client, label) in self.client_stats.iter().zip(&labels) {
+            metric(
+                &mut out,
+                "libafl_client_objectives",
+                label,
+                client.objective_size,
+            );
+        }
+        counter(
+            &mut out,
+            "libafl_client_corpus_contributions_total",
+            "Testcases the client found",
+        );
+        for (client, label) in self.client_stats.iter().zip(&labels) {
+            metric(
+                &mut out,
+                "libafl_client_corpus_contributions_total",
+                label,
+                client.corpus_contributions,
+            );
+        }
+        counter(
+            &mut out,
+            "libafl_client_executions_total",
+            "Executions of the client",
+        );
+        for (client, label) in self.client_stats.iter().zip(&labels) {
+            metric(
+                &mut out,
+                "libafl_client_executions_total",
+                label,
+                client.executions,
+            );
+        }
+        gauge(
+            &mut out,
+            "libafl_client_execs_per_sec",
+            "Executions per second of the client",
+        );
+        for (client, label) in self.client_stats.iter_mut().zip(&labels) {
+            metric(
+                &mut out,
+                "libafl_client_execs_per_sec",
+                label,
+                client.execs_per_sec(cur_time),
+            );
+        }
+        gauge(
+            &mut out,
+            "libafl_client_heartbeat_age_seconds",
+            "Time since the client was last heard of",
+        );
+        for (client, label) in self.client_stats.iter().zip(&labels) {
+            metric(
+                &mut out,
+                "libafl_client_heartbeat_age_seconds",
+                label,
+                client.since_heartbeat(cur_time).as_secs(),
+            );
+        }
+
+        gauge(
+            &mut out,
+            "libafl_client_user_stat",
+            "Numerical user stats of the client",
+        );
+        for (client, label) in self.client_stats.iter().zip(&labels) {
+            for (key, val) in &client.user_monitor {
+                let value = match val {
+                    UserStats::Number(n) => *n as f64,
+                    UserStats::Float(f) => *f,
+                    UserStats::Ratio(a, b) if *b != 0 => *a as f64 / *b as f64,
+                    UserStats::Ratio(..) => 0.0,
+                    UserStats::String(_) => continue,
+                };
+                let label = format!("{},stat=\"{}\"", label, escape(key));
+                metric(&mut out, "libafl_client_user_stat", &label, value);
+            }
+        }
+
+        out
+    }
+}
+
+/// Writes the `HELP` and `TYPE` lines of a gauge
+fn gauge(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+}
+
+/// Writes the `HELP` and `TYPE` lines of a counter
+fn counter(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+}
+
+/// Writes a sample, with the given labels
+fn metric<T>(out: &mut String, name: &str, labels: &str, value: T)
+where
+    T: core::fmt::Display,
+{
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+/// Escapes a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Answers a single HTTP request, with the metrics on `/metrics`
+fn serve(mut stream: TcpStream, metrics: &Mutex<String>) -> Result<(), Error> {
+    // An idle scraper must not block the endpoint
+    stream.set_read_timeout(Some(SCRAPE_TIMEOUT))?;
+    stream.set_write_timeout(Some(SCRAPE_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let mut parts = request.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = metrics.lock().unwrap().clone();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into(),
+    };
+    stream.write_all(response.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use crate::monitors::{Monitor, PrometheusMonitor, UserStats};
+
+    #[test]
+    fn test_prometheus_monitor() {
+        let mut monitor = PrometheusMonitor::new("127.0.0.1:0", |_| {}).unwrap();
+        let client = monitor.client_stats_mut_for(1);
+        client.update_name("fuzz\"er".into());
+        client.update_corpus_size(12);
+        client.update_user_stats("edges".into(), UserStats::Ratio(1, 4));
+        client.update_user_stats("note".into(), UserStats::String("ignored".into()));
+        monitor.display("Testcase".into(), 1);
+
+        let mut stream = TcpStream::connect(monitor.local_addr()).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("\nlibafl_corpus_size 12\n"));
+        assert!(
+            response.contains("\nlibafl_client_corpus_size{client=\"1\",name=\"fuzz\\\"er\"} 12\n")
+        );
+        assert!(response.contains(",stat=\"edges\"} 0.25\n"));
+        assert!(!response.contains("ignored"));
+    }
+}
@@ -0,0 +1,306 @@
+//! Monitors emitting machine-readable stats, as statsd packets or JSON lines, for campaigns run under
+//! external orchestration.
+//!
+//! They wrap another [`Monitor`], which keeps displaying the stats as usual.
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    net::{ToSocketAddrs, UdpSocket},
+    path::Path,
+};
+
+use serde_json::json;
+
+use crate::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor, UserStats},
+    Error,
+};
+
+/// The default time between two emissions
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keep the statsd datagrams below the usual MTU
+const STATSD_MAX_PACKET: usize = 1400;
+
+/// Writes the stats as JSON lines to a file, one object per emission, next to the wrapped monitor.
+#[derive(Debug)]
+pub struct JsonLinesMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    file: File,
+    interval: Duration,
+    last_emit: Duration,
+}
+
+impl<M> Monitor for JsonLinesMonitor<M>
+where
+    M: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        let cur_time = current_time();
+        if cur_time - self.last_emit >= self.interval {
+            self.last_emit = cur_time;
+
+            let run_time = cur_time - self.start_time();
+            let clients = self
+                .client_stats_mut()
+                .iter_mut()
+                .enumerate()
+                .map(|(id, client)| {
+                    json!({
+                        "id": id,
+                        "name": client.name,
+                        "corpus": client.corpus_size,
+                        "objectives": client.objective_size,
+                        "executions": client.executions,
+                        "exec_sec": client.execs_per_sec(cur_time),
+                        "found": client.corpus_contributions,
+                        "user_stats": client.user_monitor,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let line = json!({
+                "run_time": run_time.as_secs(),
+                "clients": clients.len(),
+                "corpus": self.corpus_size(),
+                "objectives": self.objective_size(),
+                "executions": self.total_execs(),
+                "exec_sec": self.execs_per_sec(),
+                "client_stats": clients,
+            });
+            if let Err(e) = writeln!(self.file, "{}", line) {
+                println!("Failed to write the stats: {:?}", e);
+            }
+        }
+
+        self.base.display(event_msg, sender_id);
+    }
+}
+
+impl<M> JsonLinesMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`JsonLinesMonitor`] wrapping `base`, appending to the file at `path`
+    pub fn new<P>(base: M, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            base,
+            file,
+            interval: DEFAULT_INTERVAL,
+            last_emit: Duration::ZERO,
+        })
+    }
+
+    /// Emits the stats at most once per `interval`
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// Sends the stats as statsd gauges over UDP, next to the wrapped monitor.
+///
+/// The global stats are sent as `<prefix>.<stat>`, the ones of each client as
+/// `<prefix>.client.<id>.<stat>`, including its numerical user stats.
+#[derive(Debug)]
+pub struct StatsdMonitor<M>
+where
+    M: Monitor,
+{
+    base: M,
+    socket: UdpSocket,
+    prefix: String,
+    interval: Duration,
+    last_emit: Duration,
+}
+
+impl<M> Monitor for StatsdMonitor<M>
+where
+    M: Monitor,
+{
+    /// the client monitor, mutable
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    /// the client monitor
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    /// Time this fuzzing run stated
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        let cur_time = current_time();
+        if cur_time - self.last_emit >= self.interval {
+            self.last_emit = cur_time;
+
+            let mut gauges: Vec<(String, f64)> = vec![
+                ("clients".into(), self.client_stats().len() as f64),
+                ("corpus".into(), self.corpus_size() as f64),
+                ("objectives".into(), self.objective_size() as f64),
+                ("executions".into(), self.total_execs() as f64),
+                ("exec_sec".into(), self.execs_per_sec() as f64),
+            ];
+            for (id, client) in self.base.client_stats_mut().iter_mut().enumerate() {
+                let client_gauges = [
+                    ("corpus", client.corpus_size as f64),
+                    ("objectives", client.objective_size as f64),
+                    ("executions", client.executions as f64),
+                    ("exec_sec", client.execs_per_sec(cur_time) as f64),
+                    ("found", client.corpus_contributions as f64),
+                ];
+                for (stat, value) in client_gauges {
+                    gauges.push((format!("client.{}.{}", id, stat), value));
+                }
+                for (key, val) in &client.user_monitor {
+                    let value = match val {
+                        UserStats::Number(n) => *n as f64,
+                        UserStats::Float(f) => *f,
+                        UserStats::Ratio(a, b) if *b != 0 => *a as f64 / *b as f64,
+                        UserStats::Ratio(..) => 0.0,
+                        UserStats::String(_) => continue,
+                    };
+                    gauges.push((format!("client.{}.{}", id, sanitize(key)), value));
+                }
+            }
+
+            if let Err(e) = self.send(&gauges) {
+                println!("Failed to send the stats: {:?}", e);
+            }
+        }
+
+        self.base.display(event_msg, sender_id);
+    }
+}
+
+impl<M> StatsdMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`StatsdMonitor`] wrapping `base`, sending to the statsd server at `addr`,
+    /// e.g. `127.0.0.1:8125`, with all stats names starting with `prefix`
+    pub fn new<A>(base: M, addr: A, prefix: &str) -> Result<Self, Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(addr)?;
+        Ok(Self {
+            base,
+            socket,
+            prefix: sanitize(prefix),
+            interval: DEFAULT_INTERVAL,
+            last_emit: Duration::ZERO,
+        })
+    }
+
+    /// Emits the stats at most once per `interval`
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sends the gauges, batched in as few datagrams as possible
+    fn send(&self, gauges: &[(String, f64)]) -> Result<(), Error> {
+        let mut packet = String::new();
+        for (name, value) in gauges {
+            let line = format!("{}.{}:{}|g", self.prefix, name, value);
+            if !packet.is_empty() && packet.len() + line.len() + 1 > STATSD_MAX_PACKET {
+                self.socket.send(packet.as_bytes())?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet += &line;
+        }
+        if !packet.is_empty() {
+            self.socket.send(packet.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Replaces all characters with a meaning in statsd
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::UdpSocket};
+
+    use crate::monitors::{JsonLinesMonitor, Monitor, NopMonitor, StatsdMonitor, UserStats};
+
+    #[test]
+    fn test_telemetry_monitors() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut monitor =
+            StatsdMonitor::new(NopMonitor::new(), server.local_addr().unwrap(), "fuzz").unwrap();
+        let client = monitor.client_stats_mut_for(1);
+        client.update_corpus_size(3);
+        client.update_user_stats("dirty pages".into(), UserStats::Number(7));
+        monitor.display("Testcase".into(), 1);
+
+        let mut buf = [0; 2048];
+        let len = server.recv(&mut buf).unwrap();
+        let packet = String::from_utf8_lossy(&buf[..len]);
+        assert!(packet.contains("fuzz.corpus:3|g"));
+        assert!(packet.contains("fuzz.client.1.dirty_pages:7|g"));
+
+        let path = std::env::temp_dir().join("libafl_test_json_lines_monitor.jsonl");
+        drop(fs::remove_file(&path));
+        let mut monitor = JsonLinesMonitor::new(NopMonitor::new(), &path).unwrap();
+        monitor.client_stats_mut_for(1).update_corpus_size(3);
+        monitor.display("Testcase".into(), 1);
+        // Within the interval, nothing is written
+        monitor.display("Testcase".into(), 1);
+
+        let lines = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(lines.lines().count(), 1);
+        let stats: serde_json::Value = serde_json::from_str(lines.trim()).unwrap();
+        assert_eq!(stats["corpus"], 3);
+        assert_eq!(stats["client_stats"][1]["corpus"], 3);
+    }
+}
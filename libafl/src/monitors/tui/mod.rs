@@ -23,7 +23,7 @@ use super::{ClientPerfMonitor, PerfFeature};
 
 use crate::{
     bolts::{current_time, format_duration_hms},
-    monitors::{ClientStats, Monitor, UserStats},
+    monitors::{ClientStats, Monitor, UserStats, PROFILE_STATS_PREFIX},
 };
 
 mod ui;
@@ -31,6 +31,7 @@ use ui::TuiUI;
 
 const DEFAULT_TIME_WINDOW: u64 = 60 * 10; // 10 min
 const DEFAULT_LOGS_NUMBER: usize = 128;
+const DEFAULT_SPARKLINE_LEN: usize = 64;
 
 #[derive(Debug, Copy, Clone)]
 pub struct TimedStat {
@@ -163,24 +164,57 @@ impl PerfTuiContext {
 
 #[derive(Debug, Default, Clone)]
 pub struct ClientTuiContext {
+    pub name: Option<String>,
     pub corpus: u64,
     pub objectives: u64,
     pub executions: u64,
     pub exec_sec: u64,
+    pub exec_sec_history: VecDeque<u64>,
+    /// The highest number of covered entries, over all coverage maps of the client
+    pub coverage: u64,
+    pub last_objective_time: Option<Duration>,
 
     pub user_stats: HashMap<String, UserStats>,
+    /// The share of the time spent in each stage and feedback, in percent
+    pub stage_timing: Vec<(String, f64)>,
 }
 
 impl ClientTuiContext {
-    pub fn grab_data(&mut self, client: &ClientStats, exec_sec: u64) {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn grab_data(&mut self, client: &ClientStats, exec_sec: u64, cur_time: Duration) {
+        if client.objective_size > self.objectives {
+            self.last_objective_time = Some(cur_time);
+        }
+        self.name = client.name.clone();
         self.corpus = client.corpus_size;
         self.objectives = client.objective_size;
         self.executions = client.executions;
         self.exec_sec = exec_sec;
 
+        while self.exec_sec_history.len() >= DEFAULT_SPARKLINE_LEN {
+            self.exec_sec_history.pop_front();
+        }
+        self.exec_sec_history.push_back(exec_sec);
+
+        self.stage_timing.clear();
         for (key, val) in &client.user_monitor {
-            self.user_stats.insert(key.clone(), val.clone());
+            match (key.strip_prefix(PROFILE_STATS_PREFIX), val) {
+                (Some(stage), UserStats::Ratio(spent, total)) => {
+                    if *total != 0 {
+                        self.stage_timing
+                            .push((stage.into(), *spent as f64 * 100.0 / *total as f64));
+                    }
+                }
+                _ => {
+                    self.user_stats.insert(key.clone(), val.clone());
+                }
+            }
+        }
+        if let Some((covered, _)) = client.coverage() {
+            self.coverage = self.coverage.max(covered);
         }
+        self.stage_timing
+            .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(core::cmp::Ordering::Equal));
     }
 }
 
@@ -192,6 +226,7 @@ pub struct TuiContext {
     pub corpus_size_timed: TimedStats,
     pub objective_size_timed: TimedStats,
     pub execs_per_sec_timed: TimedStats,
+    pub coverage_timed: TimedStats,
 
     #[cfg(feature = "introspection")]
     pub introspection: HashMap<usize, PerfTuiContext>,
@@ -202,6 +237,7 @@ pub struct TuiContext {
 
     pub clients_num: usize,
     pub total_execs: u64,
+    pub last_objective_time: Option<Duration>,
     pub start_time: Duration,
 }
 
@@ -210,8 +246,14 @@ impl TuiContext {
     #[must_use]
     pub fn new(start_time: Duration) -> Self {
         Self {
-            graphs: vec!["corpus".into(), "objectives".into(), "exec/sec".into()],
+            graphs: vec![
+                "corpus".into(),
+                "objectives".into(),
+                "exec/sec".into(),
+                "coverage".into(),
+            ],
             corpus_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
+            coverage_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
             objective_size_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
             execs_per_sec_timed: TimedStats::new(Duration::from_secs(DEFAULT_TIME_WINDOW)),
 
@@ -223,6 +265,7 @@ impl TuiContext {
 
             clients_num: 0,
             total_execs: 0,
+            last_objective_time: None,
             start_time,
         }
     }
@@ -270,10 +313,10 @@ impl Monitor for TuiMonitor {
             ctx.clients_num = self.client_stats.len();
         }
 
+        let sender = self.client_label(sender_id);
         let client = self.client_stats_mut_for(sender_id);
         let exec_sec = client.execs_per_sec(cur_time);
 
-        let sender = format!("#{}", sender_id);
         let pad = if event_msg.len() + sender.len() < 13 {
             " ".repeat(13 - event_msg.len() - sender.len())
         } else {
@@ -291,10 +334,12 @@ impl Monitor for TuiMonitor {
         {
             let client = &self.client_stats()[sender_id as usize];
             let mut ctx = self.context.write().unwrap();
-            ctx.clients
-                .entry(sender_id as usize)
-                .or_default()
-                .grab_data(client, exec_sec);
+            let client_ctx = ctx.clients.entry(sender_id as usize).or_default();
+            client_ctx.grab_data(client, exec_sec, cur_time);
+            let last_objective_time = client_ctx.last_objective_time;
+            ctx.last_objective_time = ctx.last_objective_time.max(last_objective_time);
+            let coverage = ctx.clients.values().map(|c| c.coverage).max().unwrap_or(0);
+            ctx.coverage_timed.add(cur_time - self.start_time, coverage);
             while ctx.client_logs.len() >= DEFAULT_LOGS_NUMBER {
                 ctx.client_logs.pop_front();
             }
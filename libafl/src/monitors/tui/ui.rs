@@ -7,7 +7,8 @@ use tui::{
     symbols,
     text::{Span, Spans},
     widgets::{
-        Axis, Block, Borders, Cell, Chart, Dataset, List, ListItem, Paragraph, Row, Table, Tabs,
+        Axis, Block, Borders, Cell, Chart, Dataset, List, ListItem, Paragraph, Row, Sparkline,
+        Table, Tabs,
     },
     Frame,
 };
@@ -47,7 +48,7 @@ impl TuiUI {
                 self.should_quit = true;
             }
             'g' => {
-                self.charts_tab_idx = (self.charts_tab_idx + 1) % 3;
+                self.charts_tab_idx = (self.charts_tab_idx + 1) % 4;
             }
             't' => {
                 self.show_logs = !self.show_logs;
@@ -127,6 +128,10 @@ impl TuiUI {
                 "objectives",
                 Style::default().fg(Color::LightGreen),
             )),
+            Spans::from(Span::styled(
+                "coverage",
+                Style::default().fg(Color::LightGreen),
+            )),
         ];
         let tabs = Tabs::new(titles)
             .block(
@@ -174,6 +179,16 @@ impl TuiUI {
                     &ctx.objective_size_timed,
                 );
             }
+            3 => {
+                let ctx = app.read().unwrap();
+                self.draw_time_chart(
+                    "coverage chart",
+                    "covered entries",
+                    f,
+                    right_layout[1],
+                    &ctx.coverage_timed,
+                );
+            }
             _ => {}
         }
 
@@ -341,6 +356,22 @@ impl TuiUI {
                         .map_or(0, |x| x.item)
                 ))),
             ]),
+            Row::new(vec![
+                Cell::from(Span::raw("coverage")),
+                Cell::from(Span::raw(format!(
+                    "{}",
+                    app.read()
+                        .unwrap()
+                        .coverage_timed
+                        .series
+                        .back()
+                        .map_or(0, |x| x.item)
+                ))),
+            ]),
+            Row::new(vec![
+                Cell::from(Span::raw("last objective")),
+                Cell::from(Span::raw(since(app.read().unwrap().last_objective_time))),
+            ]),
         ];
 
         let chunks = Layout::default()
@@ -367,9 +398,19 @@ impl TuiUI {
             .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]);
         f.render_widget(table, chunks[0]);
 
+        let client_name = app
+            .read()
+            .unwrap()
+            .clients
+            .get(&self.clients_idx)
+            .and_then(|client| client.name.clone())
+            .map_or_else(String::new, |name| format!(" ({})", name));
         let client_block = Block::default()
             .title(Span::styled(
-                format!("client #{} (l/r arrows to switch)", self.clients_idx),
+                format!(
+                    "client #{}{} (l/r arrows to switch)",
+                    self.clients_idx, client_name
+                ),
                 Style::default()
                     .fg(Color::LightCyan)
                     .add_modifier(Modifier::BOLD),
@@ -379,9 +420,18 @@ impl TuiUI {
         f.render_widget(client_block, chunks[1]);
 
         let mut client_items = vec![];
+        let mut exec_sec_history = vec![];
+        let mut stage_items = vec![];
         {
             let ctx = app.read().unwrap();
             if let Some(client) = ctx.clients.get(&self.clients_idx) {
+                exec_sec_history.extend(client.exec_sec_history.iter().copied());
+                for (stage, percent) in &client.stage_timing {
+                    stage_items.push(Row::new(vec![
+                        Cell::from(Span::raw(stage.clone())),
+                        Cell::from(Span::raw(format!("{:.2}%", percent))),
+                    ]));
+                }
                 client_items.push(Row::new(vec![
                     Cell::from(Span::raw("executions")),
                     Cell::from(Span::raw(format!("{}", client.executions))),
@@ -398,6 +448,10 @@ impl TuiUI {
                     Cell::from(Span::raw("objectives")),
                     Cell::from(Span::raw(format!("{}", client.objectives))),
                 ]));
+                client_items.push(Row::new(vec![
+                    Cell::from(Span::raw("last objective")),
+                    Cell::from(Span::raw(since(client.last_objective_time))),
+                ]));
                 for (key, val) in &client.user_stats {
                     client_items.push(Row::new(vec![
                         Cell::from(Span::raw(key.clone())),
@@ -407,6 +461,15 @@ impl TuiUI {
             };
         }
 
+        let pane_chunks = Layout::default()
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(client_area);
+        let sparkline = Sparkline::default()
+            .block(Block::default().title("exec/sec"))
+            .style(Style::default().fg(Color::LightYellow))
+            .data(&exec_sec_history);
+        f.render_widget(sparkline, pane_chunks[0]);
+
         #[cfg(feature = "introspection")]
         let client_chunks = Layout::default()
             .constraints(
@@ -416,17 +479,43 @@ impl TuiUI {
                 ]
                 .as_ref(),
             )
-            .split(client_area);
+            .split(pane_chunks[1]);
         #[cfg(not(feature = "introspection"))]
         let client_chunks = Layout::default()
-            .constraints([Constraint::Percentage(100)].as_ref())
-            .split(client_area);
+            .constraints(if stage_items.is_empty() {
+                [Constraint::Percentage(100)].as_ref()
+            } else {
+                [
+                    Constraint::Length(client_items.len() as u16),
+                    Constraint::Min(0),
+                ]
+                .as_ref()
+            })
+            .split(pane_chunks[1]);
 
         let table = Table::new(client_items)
             .block(Block::default())
             .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]);
         f.render_widget(table, client_chunks[0]);
 
+        // The stage timing of the `ProfilerStage`; the introspection pane shows the finer one
+        #[cfg(not(feature = "introspection"))]
+        if !stage_items.is_empty() {
+            let table = Table::new(stage_items)
+                .block(
+                    Block::default()
+                        .title(Span::styled(
+                            "stage timing",
+                            Style::default()
+                                .fg(Color::LightCyan)
+                                .add_modifier(Modifier::BOLD),
+                        ))
+                        .borders(Borders::ALL),
+                )
+                .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)]);
+            f.render_widget(table, client_chunks[1]);
+        }
+
         #[cfg(feature = "introspection")]
         {
             let mut items = vec![];
@@ -505,3 +594,11 @@ impl TuiUI {
         f.render_widget(logs, area);
     }
 }
+
+/// The time since `time`, or `none`
+fn since(time: Option<Duration>) -> String {
+    time.map_or_else(
+        || "none".into(),
+        |time| format!("{} ago", format_duration_hms(&(current_time() - time))),
+    )
+}
@@ -0,0 +1,372 @@
+//! Use `AFL++` custom mutators, loaded from shared objects, as [`Mutator`]s.
+//!
+//! The shared object must export `afl_custom_init` and at least one of `afl_custom_fuzz` and
+//! `afl_custom_havoc_mutation`. `afl_custom_deinit` is used if present.
+//! The `afl` state pointer passed to `afl_custom_init` is always null, mutators dereferencing it are not supported.
+//!
+//! As in `AFL++`, `afl_custom_post_process` only changes what the target gets, the corpus keeps
+//! the mutated input. Call the [`AflCustomPostProcessor`] of the mutator in the harness.
+
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::{
+    ffi::c_void,
+    fmt::{self, Debug},
+    mem::transmute,
+    ptr,
+};
+use std::{
+    ffi::{CStr, CString},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+};
+
+use crate::{
+    bolts::{rands::Rand, tuples::Named},
+    corpus::Corpus,
+    inputs::{HasBytesVec, Input},
+    mutators::{max_size_of, MutationResult, Mutator},
+    state::{HasCorpus, HasMaxSize, HasRand},
+    Error,
+};
+
+type AflCustomInitFn = unsafe extern "C" fn(afl: *mut c_void, seed: u32) -> *mut c_void;
+type AflCustomFuzzFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *mut u8,
+    add_buf: *mut u8,
+    add_buf_size: usize,
+    max_size: usize,
+) -> usize;
+type AflCustomHavocMutationFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *mut u8,
+    max_size: usize,
+) -> usize;
+type AflCustomPostProcessFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *mut u8,
+) -> usize;
+type AflCustomDeinitFn = unsafe extern "C" fn(data: *mut c_void);
+
+/// The loaded shared object, shared by the mutator and its post processor
+struct AflCustomLibrary {
+    handle: *mut c_void,
+    data: *mut c_void,
+    fuzz: Option<AflCustomFuzzFn>,
+    havoc_mutation: Option<AflCustomHavocMutationFn>,
+    post_process: Option<AflCustomPostProcessFn>,
+    deinit: Option<AflCustomDeinitFn>,
+}
+
+impl Drop for AflCustomLibrary {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(deinit) = self.deinit {
+                deinit(self.data);
+            }
+            if !self.handle.is_null() {
+                libc::dlclose(self.handle);
+            }
+        }
+    }
+}
+
+/// An `AFL++` custom mutator loaded from a shared object
+pub struct AflCustomMutator {
+    name: String,
+    lib: Rc<AflCustomLibrary>,
+}
+
+impl Debug for AflCustomMutator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AflCustomMutator")
+            .field("name", &self.name)
+            .field("fuzz", &self.lib.fuzz.is_some())
+            .field("havoc_mutation", &self.lib.havoc_mutation.is_some())
+            .field("post_process", &self.lib.post_process.is_some())
+            .finish()
+    }
+}
+
+/// The `afl_custom_post_process` of an [`AflCustomMutator`], to apply to the inputs in the harness
+pub struct AflCustomPostProcessor {
+    lib: Rc<AflCustomLibrary>,
+}
+
+impl Debug for AflCustomPostProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AflCustomPostProcessor").finish()
+    }
+}
+
+impl AflCustomPostProcessor {
+    /// The bytes to pass to the target for `buf`, `None` if the custom mutator skips this input
+    #[must_use]
+    pub fn post_process(&self, buf: &[u8]) -> Option<Vec<u8>> {
+        let post_process = self.lib.post_process.unwrap();
+        let mut buf = buf.to_vec();
+        let mut processed: *mut u8 = ptr::null_mut();
+        let len =
+            unsafe { post_process(self.lib.data, buf.as_mut_ptr(), buf.len(), &mut processed) };
+        if len == 0 || processed.is_null() {
+            None
+        } else {
+            Some(unsafe { core::slice::from_raw_parts(processed, len) }.to_vec())
+        }
+    }
+}
+
+/// The last error reported by the dynamic loader
+fn dl_error() -> String {
+    unsafe {
+        let err = libc::dlerror();
+        if err.is_null() {
+            String::from("unknown error")
+        } else {
+            CStr::from_ptr(err).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// Look up `name` in the library, `None` if it is not exported
+unsafe fn dl_symbol(handle: *mut c_void, name: &str) -> Option<*mut c_void> {
+    let name = CString::new(name).unwrap();
+    let sym = libc::dlsym(handle, name.as_ptr());
+    if sym.is_null() {
+        None
+    } else {
+        Some(sym)
+    }
+}
+
+impl AflCustomMutator {
+    /// Load the custom mutator at `path` and initialize it with the given `seed`
+    pub fn new<P>(path: P, seed: u32) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| Error::IllegalArgument(format!("Invalid path {:?}", path)))?;
+
+        unsafe {
+            let handle = libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW);
+            if handle.is_null() {
+                return Err(Error::IllegalArgument(format!(
+                    "Failed to load custom mutator {:?}: {}",
+                    path,
+                    dl_error()
+                )));
+            }
+
+            let init: AflCustomInitFn = match dl_symbol(handle, "afl_custom_init") {
+                Some(sym) => transmute(sym),
+                None => {
+                    libc::dlclose(handle);
+                    return Err(Error::IllegalArgument(format!(
+                        "Custom mutator {:?} does not export afl_custom_init",
+                        path
+                    )));
+                }
+            };
+            let fuzz: Option<AflCustomFuzzFn> =
+                dl_symbol(handle, "afl_custom_fuzz").map(|sym| transmute(sym));
+            let havoc_mutation: Option<AflCustomHavocMutationFn> =
+                dl_symbol(handle, "afl_custom_havoc_mutation").map(|sym| transmute(sym));
+            let post_process: Option<AflCustomPostProcessFn> =
+                dl_symbol(handle, "afl_custom_post_process").map(|sym| transmute(sym));
+            let deinit: Option<AflCustomDeinitFn> =
+                dl_symbol(handle, "afl_custom_deinit").map(|sym| transmute(sym));
+
+            if fuzz.is_none() && havoc_mutation.is_none() {
+                libc::dlclose(handle);
+                return Err(Error::IllegalArgument(format!(
+                    "Custom mutator {:?} exports neither afl_custom_fuzz nor afl_custom_havoc_mutation",
+                    path
+                )));
+            }
+
+            let data = init(ptr::null_mut(), seed);
+            if data.is_null() {
+                libc::dlclose(handle);
+                return Err(Error::Unknown(format!(
+                    "afl_custom_init of {:?} failed",
+                    path
+                )));
+            }
+
+            Ok(Self {
+                name: format!("AflCustomMutator({})", path.display()),
+                lib: Rc::new(AflCustomLibrary {
+                    handle,
+                    data,
+                    fuzz,
+                    havoc_mutation,
+                    post_process,
+                    deinit,
+                }),
+            })
+        }
+    }
+
+    /// The post processor of this custom mutator, if it exports `afl_custom_post_process`
+    #[must_use]
+    pub fn post_processor(&self) -> Option<AflCustomPostProcessor> {
+        self.lib.post_process.map(|_| AflCustomPostProcessor {
+            lib: self.lib.clone(),
+        })
+    }
+}
+
+impl<I, S> Mutator<I, S> for AflCustomMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasCorpus<I> + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = max_size_of(state, input);
+        let mut out_buf: *mut u8 = ptr::null_mut();
+
+        let lib = &*self.lib;
+        let len = if let Some(fuzz) = lib.fuzz {
+            // afl_custom_fuzz gets another corpus entry to splice with, as in AFL++
+            let count = state.corpus().count();
+            let mut add_buf: Vec<u8> = if count == 0 {
+                vec![]
+            } else {
+                let idx = state.rand_mut().below(count as u64) as usize;
+                let mut other = state.corpus().get(idx)?.borrow_mut();
+                other.load_input()?.bytes().to_vec()
+            };
+            let buf = input.bytes_mut();
+            unsafe {
+                fuzz(
+                    lib.data,
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut out_buf,
+                    add_buf.as_mut_ptr(),
+                    add_buf.len(),
+                    max_size,
+                )
+            }
+        } else {
+            let havoc_mutation = lib.havoc_mutation.unwrap();
+            let buf = input.bytes_mut();
+            unsafe {
+                havoc_mutation(
+                    lib.data,
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut out_buf,
+                    max_size,
+                )
+            }
+        };
+
+        // A custom mutator returns 0 to skip this round
+        if len == 0 || out_buf.is_null() {
+            return Ok(MutationResult::Skipped);
+        }
+        let mut mutated = unsafe { core::slice::from_raw_parts(out_buf, len) }.to_vec();
+        mutated.truncate(max_size);
+        *input.bytes_mut() = mutated;
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for AflCustomMutator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::{ffi::c_void, ptr};
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{
+            afl_custom::{AflCustomLibrary, AflCustomMutator},
+            MutationResult, Mutator,
+        },
+        state::StdState,
+    };
+
+    static mut OUT: [u8; 64] = [0; 64];
+    static mut POST: [u8; 65] = [0; 65];
+
+    /// Reverses the input
+    unsafe extern "C" fn reverse(
+        _data: *mut c_void,
+        buf: *mut u8,
+        buf_size: usize,
+        out_buf: *mut *mut u8,
+        _add_buf: *mut u8,
+        _add_buf_size: usize,
+        _max_size: usize,
+    ) -> usize {
+        let buf = core::slice::from_raw_parts(buf, buf_size);
+        for (i, byte) in buf.iter().rev().enumerate() {
+            OUT[i] = *byte;
+        }
+        *out_buf = OUT.as_mut_ptr();
+        buf_size
+    }
+
+    /// Appends a `!`
+    unsafe extern "C" fn exclaim(
+        _data: *mut c_void,
+        buf: *mut u8,
+        buf_size: usize,
+        out_buf: *mut *mut u8,
+    ) -> usize {
+        POST[..buf_size].copy_from_slice(core::slice::from_raw_parts(buf, buf_size));
+        POST[buf_size] = b'!';
+        *out_buf = POST.as_mut_ptr();
+        buf_size + 1
+    }
+
+    #[test]
+    fn test_post_process_not_persisted() {
+        let mut corpus = InMemoryCorpus::new();
+        corpus.add(Testcase::new(BytesInput::new(vec![0]))).unwrap();
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+
+        let mut mutator = AflCustomMutator {
+            name: "test".into(),
+            lib: Rc::new(AflCustomLibrary {
+                handle: ptr::null_mut(),
+                data: ptr::null_mut(),
+                fuzz: Some(reverse),
+                havoc_mutation: None,
+                post_process: Some(exclaim),
+                deinit: None,
+            }),
+        };
+        let mut input = BytesInput::new(b"abc".to_vec());
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+        // The corpus gets the mutated input, the target the post processed one
+        assert_eq!(input.bytes(), b"cba");
+        let post_processor = mutator.post_processor().unwrap();
+        assert_eq!(post_processor.post_process(input.bytes()).unwrap(), b"cba!");
+    }
+}
@@ -3,9 +3,13 @@
 
 use alloc::vec::Vec;
 use core::cmp::{max, min};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    bolts::{rands::Rand, tuples::Named},
+    bolts::{
+        rands::Rand,
+        tuples::{tuple_list, tuple_list_type, Named},
+    },
     corpus::Corpus,
     inputs::{GeneralizedInput, GeneralizedItem},
     mutators::{token_mutations::Tokens, MutationResult, Mutator},
@@ -18,6 +22,99 @@ const RECURSIVE_REPLACEMENT_DEPTH: [usize; 6] = [2, 4, 8, 16, 32, 64];
 const MAX_RECURSIVE_REPLACEMENT_LEN: usize = 64 << 10;
 const CHOOSE_SUBINPUT_PROB: u64 = 50;
 
+/// The shortest byte chunk of a generalized input learned as a token
+pub const MIN_LEARNED_TOKEN_LEN: usize = 2;
+/// The longest byte chunk of a generalized input learned as a token
+pub const MAX_LEARNED_TOKEN_LEN: usize = 32;
+
+/// A state metadata holding the tokens Grimoire learned from the generalized inputs:
+/// the chunks of bytes between their gaps.
+/// The Grimoire mutators use them along with the [`Tokens`] dictionary.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GrimoireLearnedTokens {
+    /// The learned tokens
+    pub tokens: Tokens,
+}
+
+crate::impl_serdeany!(
+    GrimoireLearnedTokens,
+    "libafl::mutators::grimoire::GrimoireLearnedTokens"
+);
+
+impl GrimoireLearnedTokens {
+    /// Creates a new, empty [`GrimoireLearnedTokens`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Learn the byte chunks of `generalized`, returning the number of new tokens
+    pub fn learn(&mut self, generalized: &[GeneralizedItem]) -> usize {
+        let mut learned = 0;
+        for item in generalized {
+            if let GeneralizedItem::Bytes(bytes) = item {
+                if (MIN_LEARNED_TOKEN_LEN..=MAX_LEARNED_TOKEN_LEN).contains(&bytes.len())
+                    && self.tokens.add_token(bytes)
+                {
+                    learned += 1;
+                }
+            }
+        }
+        learned
+    }
+}
+
+/// The number of tokens in the dictionary and learned by Grimoire
+fn tokens_count<S>(state: &S) -> usize
+where
+    S: HasMetadata,
+{
+    state
+        .metadata()
+        .get::<Tokens>()
+        .map_or(0, |meta| meta.tokens().len())
+        + state
+            .metadata()
+            .get::<GrimoireLearnedTokens>()
+            .map_or(0, |meta| meta.tokens.tokens().len())
+}
+
+/// The token at `idx`, counting the dictionary first, then the learned tokens
+fn nth_token<S>(state: &S, idx: usize) -> Option<&Vec<u8>>
+where
+    S: HasMetadata,
+{
+    let dictionary = state
+        .metadata()
+        .get::<Tokens>()
+        .map_or(&[][..], |meta| meta.tokens());
+    if idx < dictionary.len() {
+        return Some(&dictionary[idx]);
+    }
+    state
+        .metadata()
+        .get::<GrimoireLearnedTokens>()
+        .and_then(|meta| meta.tokens.tokens().get(idx - dictionary.len()))
+}
+
+/// The Grimoire mutations, to use in a [`crate::stages::GrimoireMutationalStage`]
+#[must_use]
+pub fn grimoire_mutations() -> tuple_list_type!(
+    GrimoireExtensionMutator,
+    GrimoireRecursiveReplacementMutator,
+    GrimoireStringReplacementMutator,
+    GrimoireRandomDeleteMutator,
+    GrimoireRandomDeleteMutator,
+) {
+    tuple_list!(
+        GrimoireExtensionMutator::new(),
+        GrimoireRecursiveReplacementMutator::new(),
+        GrimoireStringReplacementMutator::new(),
+        GrimoireRandomDeleteMutator::new(),
+        GrimoireRandomDeleteMutator::new(),
+    )
+}
+
 fn extend_with_random_generalized<S>(
     state: &mut S,
     items: &mut Vec<GeneralizedItem>,
@@ -89,20 +186,19 @@ where
 
         let rand1 = state.rand_mut().next() as usize;
 
-        if let Some(meta) = state.metadata().get::<Tokens>() {
-            if !meta.tokens().is_empty() {
-                let tok = &meta.tokens()[rand1 % meta.tokens().len()];
-                if items.last() != Some(&GeneralizedItem::Gap) {
-                    items.push(GeneralizedItem::Gap);
-                }
-                items.push(GeneralizedItem::Bytes(tok.clone()));
+        let tokens_len = tokens_count(state);
+        if tokens_len > 0 {
+            let tok = nth_token(state, rand1 % tokens_len).unwrap();
+            if items.last() != Some(&GeneralizedItem::Gap) {
                 items.push(GeneralizedItem::Gap);
+            }
+            items.push(GeneralizedItem::Bytes(tok.clone()));
+            items.push(GeneralizedItem::Gap);
 
-                debug_assert!(items.first() == Some(&GeneralizedItem::Gap));
-                debug_assert!(items.last() == Some(&GeneralizedItem::Gap));
+            debug_assert!(items.first() == Some(&GeneralizedItem::Gap));
+            debug_assert!(items.last() == Some(&GeneralizedItem::Gap));
 
-                return Ok(());
-            }
+            return Ok(());
         }
     }
 
@@ -243,7 +339,7 @@ impl GrimoireRecursiveReplacementMutator {
     }
 }
 
-/// Replace matching tokens with others from the tokens metadata
+/// Replace matching tokens with others from the tokens metadata and the [`GrimoireLearnedTokens`]
 #[derive(Debug, Default)]
 pub struct GrimoireStringReplacementMutator {}
 
@@ -261,16 +357,10 @@ where
             return Ok(MutationResult::Skipped);
         }
 
-        let tokens_len = {
-            let meta = state.metadata().get::<Tokens>();
-            if meta.is_none() {
-                return Ok(MutationResult::Skipped);
-            }
-            if meta.unwrap().tokens().is_empty() {
-                return Ok(MutationResult::Skipped);
-            }
-            meta.unwrap().tokens().len()
-        };
+        let tokens_len = tokens_count(state);
+        if tokens_len == 0 {
+            return Ok(MutationResult::Skipped);
+        }
         let token_find = state.rand_mut().below(tokens_len as u64) as usize;
         let mut token_replace = state.rand_mut().below(tokens_len as u64) as usize;
         if token_find == token_replace {
@@ -280,9 +370,8 @@ where
         let stop_at_first = state.rand_mut().below(100) > 50;
         let mut rand_idx = state.rand_mut().next() as usize;
 
-        let meta = state.metadata().get::<Tokens>().unwrap();
-        let token_1 = &meta.tokens()[token_find];
-        let token_2 = &meta.tokens()[token_replace];
+        let token_1 = nth_token(state, token_find).unwrap();
+        let token_2 = nth_token(state, token_replace).unwrap();
 
         let mut mutated = MutationResult::Skipped;
 
@@ -407,3 +496,30 @@ impl GrimoireRandomDeleteMutator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{inputs::GeneralizedItem, mutators::GrimoireLearnedTokens};
+
+    #[test]
+    fn test_grimoire_learned_tokens() {
+        let generalized = vec![
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"if".to_vec()),
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"(".to_vec()),
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"if".to_vec()),
+            GeneralizedItem::Bytes(b"while".to_vec()),
+            GeneralizedItem::Gap,
+        ];
+        let mut learned = GrimoireLearnedTokens::new();
+        // Too short chunks and duplicates are left out
+        assert_eq!(learned.learn(&generalized), 2);
+        assert_eq!(learned.learn(&generalized), 0);
+        assert_eq!(
+            learned.tokens.tokens(),
+            &[b"if".to_vec(), b"while".to_vec()]
+        );
+    }
+}
@@ -14,6 +14,16 @@ pub mod gramatron;
 pub use gramatron::*;
 pub mod grimoire;
 pub use grimoire::*;
+pub mod similarity;
+pub use similarity::*;
+pub mod unicode;
+pub use unicode::*;
+pub mod multi;
+pub use multi::MultipartMutator;
+#[cfg(all(feature = "std", unix))]
+pub mod afl_custom;
+#[cfg(all(feature = "std", unix))]
+pub use afl_custom::{AflCustomMutator, AflCustomPostProcessor};
 
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
@@ -0,0 +1,206 @@
+//! Mutations for [`MultipartInput`]s, with a mutator of its own for each part
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt::{self, Debug, Formatter};
+
+use crate::{
+    bolts::{rands::Rand, tuples::Named},
+    inputs::{Input, MultipartInput},
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error,
+};
+
+/// Mutates one random part of a [`MultipartInput`], with the mutator registered for the name of
+/// the part, or with the fallback mutator for the parts without one.
+pub struct MultipartMutator<I, S>
+where
+    I: Input,
+{
+    mutators: Vec<(String, Box<dyn Mutator<I, S>>)>,
+    fallback: Option<Box<dyn Mutator<I, S>>>,
+}
+
+impl<I, S> Debug for MultipartMutator<I, S>
+where
+    I: Input,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultipartMutator")
+            .field(
+                "parts",
+                &self
+                    .mutators
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("has_fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+impl<I, S> MultipartMutator<I, S>
+where
+    I: Input,
+{
+    /// Creates a new [`MultipartMutator`], without any mutator yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            mutators: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// Mutates the parts named `name` with `mutator`
+    #[must_use]
+    pub fn with_part_mutator<N, M>(mut self, name: N, mutator: M) -> Self
+    where
+        N: Into<String>,
+        M: Mutator<I, S> + 'static,
+    {
+        self.mutators.push((name.into(), Box::new(mutator)));
+        self
+    }
+
+    /// Mutates the parts without a mutator of their own with `mutator`
+    #[must_use]
+    pub fn with_fallback<M>(mut self, mutator: M) -> Self
+    where
+        M: Mutator<I, S> + 'static,
+    {
+        self.fallback = Some(Box::new(mutator));
+        self
+    }
+
+    /// The mutator for the parts named `name`
+    fn mutator_for(&mut self, name: &str) -> Option<&mut Box<dyn Mutator<I, S>>> {
+        match self.mutators.iter().position(|(part, _)| part == name) {
+            Some(idx) => Some(&mut self.mutators[idx].1),
+            None => self.fallback.as_mut(),
+        }
+    }
+}
+
+impl<I, S> Default for MultipartMutator<I, S>
+where
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> Mutator<MultipartInput<I>, S> for MultipartMutator<I, S>
+where
+    I: Input,
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultipartInput<I>,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let candidates = input
+            .names()
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| {
+                self.fallback.is_some() || self.mutators.iter().any(|(part, _)| part == *name)
+            })
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let idx = *state.rand_mut().choose(&candidates);
+        let name = input.names()[idx].clone();
+        let mutator = self.mutator_for(&name).unwrap();
+        mutator.mutate(state, &mut input.parts_mut()[idx], stage_idx)
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        stage_idx: i32,
+        corpus_idx: Option<usize>,
+    ) -> Result<(), Error> {
+        for (_, mutator) in &mut self.mutators {
+            mutator.post_exec(state, stage_idx, corpus_idx)?;
+        }
+        if let Some(fallback) = &mut self.fallback {
+            fallback.post_exec(state, stage_idx, corpus_idx)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I, S> Named for MultipartMutator<I, S>
+where
+    I: Input,
+{
+    fn name(&self) -> &str {
+        "MultipartMutator"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec, MultipartInput},
+        mutators::{MultipartMutator, MutationResult, Mutator},
+        state::StdState,
+        Error,
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[derive(Debug)]
+    struct PushByte(u8);
+
+    impl Mutator<BytesInput, TestState> for PushByte {
+        fn mutate(
+            &mut self,
+            _state: &mut TestState,
+            input: &mut BytesInput,
+            _stage_idx: i32,
+        ) -> Result<MutationResult, Error> {
+            input.bytes_mut().push(self.0);
+            Ok(MutationResult::Mutated)
+        }
+    }
+
+    #[test]
+    fn test_multipart_mutator() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut input = MultipartInput::new()
+            .with_part("key", BytesInput::new(vec![]))
+            .with_part("value", BytesInput::new(vec![]));
+        let mut mutator = MultipartMutator::new().with_part_mutator("value", PushByte(b'v'));
+
+        for _ in 0..10 {
+            mutator.mutate(&mut state, &mut input, 0).unwrap();
+        }
+        // Only the part with a mutator changes
+        assert!(input.parts()[0].bytes().is_empty());
+        assert_eq!(input.parts()[1].bytes(), &[b'v'; 10]);
+
+        let mut mutator = mutator.with_fallback(PushByte(b'k'));
+        for _ in 0..100 {
+            mutator.mutate(&mut state, &mut input, 0).unwrap();
+        }
+        assert!(input.parts()[0].bytes().iter().all(|b| *b == b'k'));
+        assert!(!input.parts()[0].bytes().is_empty());
+    }
+}
@@ -56,6 +56,19 @@ pub fn buffer_set<T: Clone>(data: &mut [T], from: usize, len: usize, val: T) {
     }
 }
 
+/// The size the mutations may grow `input` to: the max size of the `state`, within the bound of
+/// the input if it has one
+#[inline]
+pub fn max_size_of<I, S>(state: &S, input: &I) -> usize
+where
+    I: HasBytesVec,
+    S: HasMaxSize,
+{
+    input
+        .max_len()
+        .map_or(state.max_size(), |max_len| max_len.min(state.max_size()))
+}
+
 /// The max value that will be added or subtracted during add mutations
 pub const ARITH_MAX: u64 = 35;
 
@@ -511,7 +524,7 @@ where
         input: &mut I,
         _stage_idx: i32,
     ) -> Result<MutationResult, Error> {
-        let max_size = state.max_size();
+        let max_size = max_size_of(state, input);
         let size = input.bytes().len();
         let off = state.rand_mut().below((size + 1) as u64) as usize;
         let mut len = 1 + state.rand_mut().below(16) as usize;
@@ -560,7 +573,7 @@ where
         input: &mut I,
         _stage_idx: i32,
     ) -> Result<MutationResult, Error> {
-        let max_size = state.max_size();
+        let max_size = max_size_of(state, input);
         let size = input.bytes().len();
         if size == 0 {
             return Ok(MutationResult::Skipped);
@@ -615,7 +628,7 @@ where
         input: &mut I,
         _stage_idx: i32,
     ) -> Result<MutationResult, Error> {
-        let max_size = state.max_size();
+        let max_size = max_size_of(state, input);
         let size = input.bytes().len();
         let off = state.rand_mut().below((size + 1) as u64) as usize;
         let mut len = 1 + state.rand_mut().below(16) as usize;
@@ -801,7 +814,7 @@ where
         input: &mut I,
         _stage_idx: i32,
     ) -> Result<MutationResult, Error> {
-        let max_size = state.max_size();
+        let max_size = max_size_of(state, input);
         let size = input.bytes().len();
         if size == 0 {
             return Ok(MutationResult::Skipped);
@@ -872,9 +885,19 @@ where
         let second = state.rand_mut().below(input.bytes().len() as u64) as usize;
         let len = 1 + state.rand_mut().below((size - max(first, second)) as u64) as usize;
 
-        let tmp = input.bytes()[first..(first + len)].to_vec();
-        buffer_self_copy(input.bytes_mut(), second, first, len);
-        buffer_copy(input.bytes_mut(), &tmp, 0, second, len);
+        // Swap in place; overlapping blocks rotate, moving the first block to `second`
+        let bytes = input.bytes_mut();
+        if first + len <= second {
+            let (head, tail) = bytes.split_at_mut(second);
+            head[first..first + len].swap_with_slice(&mut tail[..len]);
+        } else if second + len <= first {
+            let (head, tail) = bytes.split_at_mut(first);
+            head[second..second + len].swap_with_slice(&mut tail[..len]);
+        } else if first < second {
+            bytes[first..second + len].rotate_right(second - first);
+        } else {
+            bytes[second..first + len].rotate_left(first - second);
+        }
 
         Ok(MutationResult::Mutated)
     }
@@ -931,7 +954,7 @@ where
             return Ok(MutationResult::Skipped);
         }
 
-        let max_size = state.max_size();
+        let max_size = max_size_of(state, input);
         let from = state.rand_mut().below(other_size as u64) as usize;
         let to = state.rand_mut().below(size as u64) as usize;
         let mut len = 1 + state.rand_mut().below((other_size - from) as u64) as usize;
@@ -1077,34 +1100,53 @@ where
             }
         }
 
-        let (first_diff, last_diff) = {
-            let mut other_testcase = state.corpus().get(idx)?.borrow_mut();
-            let other = other_testcase.load_input()?;
+        splice_with_corpus_entry(state, input, idx)
+    }
+}
 
-            let mut counter: u32 = 0;
-            loop {
-                let (f, l) = locate_diffs(input.bytes(), other.bytes());
+/// Splice the given input with the corpus entry at `idx`, at a random point between
+/// the first and the last byte in which they differ.
+#[allow(clippy::cast_sign_loss)]
+pub(crate) fn splice_with_corpus_entry<I, S>(
+    state: &mut S,
+    input: &mut I,
+    idx: usize,
+) -> Result<MutationResult, Error>
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasCorpus<I>,
+{
+    let (first_diff, last_diff) = {
+        let mut other_testcase = state.corpus().get(idx)?.borrow_mut();
+        let other = other_testcase.load_input()?;
 
-                if f != l && f >= 0 && l >= 2 {
-                    break (f as u64, l as u64);
-                }
-                if counter == 3 {
-                    return Ok(MutationResult::Skipped);
-                }
-                counter += 1;
+        let mut counter: u32 = 0;
+        loop {
+            let (f, l) = locate_diffs(input.bytes(), other.bytes());
+
+            if f != l && f >= 0 && l >= 2 {
+                break (f as u64, l as u64);
             }
-        };
+            if counter == 3 {
+                return Ok(MutationResult::Skipped);
+            }
+            counter += 1;
+        }
+    };
 
-        let split_at = state.rand_mut().between(first_diff, last_diff) as usize;
+    let split_at = state.rand_mut().between(first_diff, last_diff) as usize;
 
-        let mut other_testcase = state.corpus().get(idx)?.borrow_mut();
-        let other = other_testcase.load_input()?;
-        input
-            .bytes_mut()
-            .splice(split_at.., other.bytes()[split_at..].iter().copied());
+    let mut other_testcase = state.corpus().get(idx)?.borrow_mut();
+    let other = other_testcase.load_input()?;
+    // Bounded inputs take as much of the tail as they can hold
+    let end = input.max_len().map_or(other.bytes().len(), |max_len| {
+        max_len.min(other.bytes().len())
+    });
+    input
+        .bytes_mut()
+        .splice(split_at.., other.bytes()[split_at..end].iter().copied());
 
-        Ok(MutationResult::Mutated)
-    }
+    Ok(MutationResult::Mutated)
 }
 
 impl Named for SpliceMutator {
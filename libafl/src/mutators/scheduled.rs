@@ -2,8 +2,10 @@
 
 use alloc::{string::String, vec::Vec};
 use core::{
+    cmp::{min, Ordering},
     fmt::{self, Debug},
     marker::PhantomData,
+    mem,
 };
 use serde::{Deserialize, Serialize};
 
@@ -14,7 +16,7 @@ use crate::{
         AsMutSlice, AsSlice,
     },
     corpus::Corpus,
-    inputs::Input,
+    inputs::{HasBytesVec, Input},
     mutators::{MutationResult, Mutator, MutatorsTuple},
     state::{HasCorpus, HasMetadata, HasRand},
     Error,
@@ -53,6 +55,40 @@ impl LogMutationMetadata {
     }
 }
 
+/// The chain of mutations that produced a [`crate::corpus::Testcase`] from its parent,
+/// placed in the testcase by a [`LoggerScheduledMutator`].
+/// Given the same parent input, the same mutator re-applies the chain with [`LoggerScheduledMutator::replay`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutationChainMetadata {
+    /// The corpus index of the parent testcase, if any
+    pub parent: Option<usize>,
+    /// The rand of the state right before the chain, serialized with `postcard`
+    pub rand: Vec<u8>,
+    /// The indexes of the applied mutations in the mutations tuple, in order
+    pub steps: Vec<usize>,
+}
+
+crate::impl_serdeany!(MutationChainMetadata);
+
+impl AsSlice<usize> for MutationChainMetadata {
+    #[must_use]
+    fn as_slice(&self) -> &[usize] {
+        self.steps.as_slice()
+    }
+}
+
+impl MutationChainMetadata {
+    /// Creates new [`struct@MutationChainMetadata`].
+    #[must_use]
+    pub fn new(parent: Option<usize>, rand: Vec<u8>, steps: Vec<usize>) -> Self {
+        Self {
+            parent,
+            rand,
+            steps,
+        }
+    }
+}
+
 /// A [`Mutator`] that composes multiple mutations into one.
 pub trait ComposedByMutations<I, MT, S>
 where
@@ -286,6 +322,8 @@ where
 {
     scheduled: SM,
     mutation_log: Vec<usize>,
+    rand: Vec<u8>,
+    parent: Option<usize>,
     phantom: PhantomData<(I, MT, S)>,
 }
 
@@ -331,12 +369,18 @@ where
         if let Some(idx) = corpus_idx {
             let mut testcase = (*state.corpus_mut().get(idx)?).borrow_mut();
             let mut log = Vec::<String>::new();
-            while let Some(idx) = self.mutation_log.pop() {
-                let name = String::from(self.scheduled.mutations().name(idx).unwrap()); // TODO maybe return an Error on None
+            for idx in self.mutation_log.iter().rev() {
+                let name = String::from(self.scheduled.mutations().name(*idx).unwrap()); // TODO maybe return an Error on None
                 log.push(name);
             }
             let meta = LogMutationMetadata::new(log);
             testcase.add_metadata(meta);
+            let chain = MutationChainMetadata::new(
+                self.parent,
+                mem::take(&mut self.rand),
+                self.mutation_log.clone(),
+            );
+            testcase.add_metadata(chain);
         };
         // Always reset the log for each run
         self.mutation_log.clear();
@@ -387,13 +431,49 @@ where
         state: &mut S,
         input: &mut I,
         stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        self.parent = *state.corpus().current();
+        // Keep the rand the chain starts from, so that it can be replayed
+        self.rand = postcard::to_allocvec(state.rand())?;
+        let mut steps = mem::take(&mut self.mutation_log);
+        let r = self.mutate_chain(state, input, stage_idx, &mut steps);
+        self.mutation_log = steps;
+        r
+    }
+}
+
+impl<I, MT, S, SM> LoggerScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S> + NamedTuple,
+    S: HasRand + HasCorpus<I>,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    /// Create a new [`StdScheduledMutator`] instance without mutations and corpus
+    pub fn new(scheduled: SM) -> Self {
+        Self {
+            scheduled,
+            mutation_log: vec![],
+            rand: vec![],
+            parent: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Schedules and applies a chain of mutations, logging their indexes in `steps`
+    fn mutate_chain(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+        steps: &mut Vec<usize>,
     ) -> Result<MutationResult, Error> {
         let mut r = MutationResult::Skipped;
         let num = self.iterations(state, input);
-        self.mutation_log.clear();
+        steps.clear();
         for _ in 0..num {
             let idx = self.schedule(state, input);
-            self.mutation_log.push(idx);
+            steps.push(idx);
             let outcome = self
                 .mutations_mut()
                 .get_and_mutate(idx, state, input, stage_idx)?;
@@ -403,37 +483,246 @@ where
         }
         Ok(r)
     }
+
+    /// Re-apply a recorded [`struct@MutationChainMetadata`] to the given (parent) input,
+    /// through the mutations of this mutator, drawing from the recorded rand.
+    /// The rand of the state is left as it was, so the fuzzing campaign is unaffected by the replay.
+    pub fn replay(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        chain: &MutationChainMetadata,
+    ) -> Result<MutationResult, Error> {
+        let recorded: <S as HasRand>::Rand = postcard::from_bytes(&chain.rand)?;
+        let rand = mem::replace(state.rand_mut(), recorded);
+        let mut steps = vec![];
+        let r = self.mutate_chain(state, input, 0, &mut steps);
+        *state.rand_mut() = rand;
+        if r.is_ok() && steps != chain.steps {
+            return Err(Error::IllegalArgument(
+                "The chain was recorded with other mutations".into(),
+            ));
+        }
+        r
+    }
 }
 
-impl<I, MT, S, SM> LoggerScheduledMutator<I, MT, S, SM>
+/// The constraint on the length of the inputs produced by a [`LengthBoundedScheduledMutator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LengthMode {
+    /// The length is not constrained
+    Unbounded,
+    /// The mutated input keeps the length of the original input
+    Preserve,
+    /// The mutated input always has exactly this length
+    Exact(usize),
+    /// The mutated input is at most this long
+    Max(usize),
+}
+
+/// A [`Mutator`] that wraps around a [`ScheduledMutator`] to constrain the length of its output.
+/// Excess bytes are removed from the end of the mutated region, so the unchanged prefix and suffix
+/// of the input (e.g. headers and trailers of a frame) are kept. Missing bytes, in the exact modes,
+/// are taken back from the original input, or zeroed.
+pub struct LengthBoundedScheduledMutator<I, MT, S, SM>
 where
-    I: Input,
-    MT: MutatorsTuple<I, S> + NamedTuple,
-    S: HasRand + HasCorpus<I>,
+    I: Input + HasBytesVec,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
     SM: ScheduledMutator<I, MT, S>,
 {
-    /// Create a new [`StdScheduledMutator`] instance without mutations and corpus
-    pub fn new(scheduled: SM) -> Self {
+    scheduled: SM,
+    mode: LengthMode,
+    phantom: PhantomData<(I, MT, S)>,
+}
+
+impl<I, MT, S, SM> Debug for LengthBoundedScheduledMutator<I, MT, S, SM>
+where
+    I: Input + HasBytesVec,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LengthBoundedScheduledMutator ({:?}) with {} mutations for Input type {}",
+            self.mode,
+            self.scheduled.mutations().len(),
+            core::any::type_name::<I>()
+        )
+    }
+}
+
+impl<I, MT, S, SM> Mutator<I, S> for LengthBoundedScheduledMutator<I, MT, S, SM>
+where
+    I: Input + HasBytesVec,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    #[inline]
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        self.scheduled_mutate(state, input, stage_idx)
+    }
+
+    #[inline]
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        stage_idx: i32,
+        corpus_idx: Option<usize>,
+    ) -> Result<(), Error> {
+        self.scheduled.post_exec(state, stage_idx, corpus_idx)
+    }
+}
+
+impl<I, MT, S, SM> ComposedByMutations<I, MT, S> for LengthBoundedScheduledMutator<I, MT, S, SM>
+where
+    I: Input + HasBytesVec,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    #[inline]
+    fn mutations(&self) -> &MT {
+        self.scheduled.mutations()
+    }
+
+    #[inline]
+    fn mutations_mut(&mut self) -> &mut MT {
+        self.scheduled.mutations_mut()
+    }
+}
+
+impl<I, MT, S, SM> ScheduledMutator<I, MT, S> for LengthBoundedScheduledMutator<I, MT, S, SM>
+where
+    I: Input + HasBytesVec,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    /// Compute the number of iterations used to apply stacked mutations
+    fn iterations(&self, state: &mut S, input: &I) -> u64 {
+        self.scheduled.iterations(state, input)
+    }
+
+    /// Get the next mutation to apply
+    fn schedule(&self, state: &mut S, input: &I) -> usize {
+        self.scheduled.schedule(state, input)
+    }
+
+    fn scheduled_mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        if self.mode == LengthMode::Unbounded {
+            return self.scheduled.scheduled_mutate(state, input, stage_idx);
+        }
+        let original = input.bytes().to_vec();
+        let r = self.scheduled.scheduled_mutate(state, input, stage_idx)?;
+        let target = match self.mode {
+            LengthMode::Unbounded => unreachable!(),
+            LengthMode::Preserve => original.len(),
+            LengthMode::Exact(len) => len,
+            LengthMode::Max(max) => min(input.bytes().len(), max),
+        };
+        fit_to_length(input.bytes_mut(), &original, target);
+        Ok(r)
+    }
+}
+
+impl<I, MT, S, SM> LengthBoundedScheduledMutator<I, MT, S, SM>
+where
+    I: Input + HasBytesVec,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    /// Create a new [`LengthBoundedScheduledMutator`] constraining the output of `scheduled`
+    pub fn new(scheduled: SM, mode: LengthMode) -> Self {
         Self {
             scheduled,
-            mutation_log: vec![],
+            mode,
             phantom: PhantomData,
         }
     }
+
+    /// The current [`LengthMode`]
+    #[must_use]
+    pub fn mode(&self) -> LengthMode {
+        self.mode
+    }
+
+    /// Change the [`LengthMode`]
+    pub fn set_mode(&mut self, mode: LengthMode) {
+        self.mode = mode;
+    }
+}
+
+/// Shrink or grow `bytes` to `target` bytes.
+/// Bytes are removed at the end of the region that differs from `original`, falling back to truncation,
+/// and added back from the same offsets of `original`, falling back to zeroes.
+fn fit_to_length(bytes: &mut Vec<u8>, original: &[u8], target: usize) {
+    let len = bytes.len();
+    match len.cmp(&target) {
+        Ordering::Greater => {
+            let excess = len - target;
+            let max_common = min(len, original.len());
+            let prefix = bytes
+                .iter()
+                .zip(original.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let suffix = bytes
+                .iter()
+                .rev()
+                .zip(original.iter().rev())
+                .take(max_common - prefix)
+                .take_while(|(a, b)| a == b)
+                .count();
+            let region_end = len - suffix;
+            if region_end - prefix >= excess {
+                bytes.drain(region_end - excess..region_end);
+            } else {
+                bytes.truncate(target);
+            }
+        }
+        Ordering::Less => {
+            if len < original.len() {
+                bytes.extend_from_slice(&original[len..min(target, original.len())]);
+            }
+            bytes.resize(target, 0);
+        }
+        Ordering::Equal => (),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        bolts::rands::{Rand, StdRand, XkcdRand},
+        bolts::{
+            rands::{Rand, StdRand, XkcdRand},
+            tuples::tuple_list,
+        },
         corpus::{Corpus, InMemoryCorpus, Testcase},
         inputs::{BytesInput, HasBytesVec},
         mutators::{
             mutations::SpliceMutator,
-            scheduled::{havoc_mutations, StdScheduledMutator},
+            scheduled::{
+                havoc_mutations, LengthBoundedScheduledMutator, LengthMode, LoggerScheduledMutator,
+                MutationChainMetadata, StdScheduledMutator,
+            },
             Mutator,
         },
-        state::StdState,
+        state::{HasRand, StdState},
     };
 
     #[test]
@@ -494,4 +783,70 @@ mod tests {
             assert_ne!(equal_in_a_row, 5);
         }
     }
+
+    #[test]
+    fn test_replay_chain() {
+        let rand = StdRand::with_seed(0x1337);
+        let mut corpus: InMemoryCorpus<BytesInput> = InMemoryCorpus::new();
+        corpus.add(Testcase::new(vec![b'a', b'b', b'c'])).unwrap();
+        corpus.add(Testcase::new(vec![b'd', b'e', b'f'])).unwrap();
+
+        let testcase = corpus.get(0).expect("Corpus did not contain entries");
+        let parent = testcase.borrow_mut().load_input().unwrap().clone();
+
+        let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+        let mut mutator = LoggerScheduledMutator::new(StdScheduledMutator::new(havoc_mutations()));
+
+        let mut mutated = parent.clone();
+        mutator.mutate(&mut state, &mut mutated, 0).unwrap();
+        let chain =
+            MutationChainMetadata::new(Some(0), mutator.rand.clone(), mutator.mutation_log.clone());
+        assert!(!chain.steps.is_empty());
+
+        // The replay leaves the rand of the state alone
+        let mut expected = *state.rand();
+        let mut replayed = parent.clone();
+        mutator.replay(&mut state, &mut replayed, &chain).unwrap();
+        assert_eq!(mutated, replayed);
+        assert_eq!(state.rand_mut().next(), expected.next());
+
+        // Other mutations do not replay the chain
+        let mut other = LoggerScheduledMutator::new(StdScheduledMutator::new(tuple_list!(
+            SpliceMutator::new()
+        )));
+        let mut input = parent.clone();
+        assert!(other.replay(&mut state, &mut input, &chain).is_err());
+
+        let broken = MutationChainMetadata::new(None, vec![], chain.steps.clone());
+        let mut input = parent;
+        assert!(mutator.replay(&mut state, &mut input, &broken).is_err());
+    }
+
+    #[test]
+    fn test_length_bounded() {
+        let rand = StdRand::with_seed(0x1337);
+        let mut corpus: InMemoryCorpus<BytesInput> = InMemoryCorpus::new();
+        corpus.add(Testcase::new(vec![b'a'; 16])).unwrap();
+        corpus.add(Testcase::new(vec![b'b'; 32])).unwrap();
+
+        let testcase = corpus.get(0).expect("Corpus did not contain entries");
+        let mut input = testcase.borrow_mut().load_input().unwrap().clone();
+
+        let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+
+        let mut havoc = LengthBoundedScheduledMutator::new(
+            StdScheduledMutator::new(havoc_mutations()),
+            LengthMode::Preserve,
+        );
+        for i in 0..42 {
+            havoc.mutate(&mut state, &mut input, i).unwrap();
+            assert_eq!(input.bytes().len(), 16);
+        }
+
+        havoc.set_mode(LengthMode::Max(20));
+        for i in 0..42 {
+            havoc.mutate(&mut state, &mut input, i).unwrap();
+            assert!(input.bytes().len() <= 20);
+        }
+    }
 }
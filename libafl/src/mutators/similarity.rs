@@ -0,0 +1,220 @@
+//! Splice mutations choosing the second parent by the similarity of its coverage,
+//! estimated with `MinHash` sketches of the covered map indexes.
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{rands::Rand, tuples::Named, AsSlice},
+    corpus::Corpus,
+    feedbacks::MapIndexesMetadata,
+    inputs::{HasBytesVec, Input},
+    mutators::{mutations::splice_with_corpus_entry, MutationResult, Mutator},
+    state::{HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// The default number of hash functions in a [`struct@MinHashMetadata`] sketch
+pub const DEFAULT_MINHASH_SIZE: usize = 16;
+
+/// A `MinHash` sketch of the map indexes covered by a testcase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinHashMetadata {
+    /// The minimum hash of the covered indexes, for each hash function
+    pub signature: Vec<u64>,
+}
+
+crate::impl_serdeany!(MinHashMetadata);
+
+impl AsSlice<u64> for MinHashMetadata {
+    /// Convert to a slice
+    fn as_slice(&self) -> &[u64] {
+        self.signature.as_slice()
+    }
+}
+
+impl MinHashMetadata {
+    /// Creates a new [`struct@MinHashMetadata`] sketching the given map indexes with `size` hash functions
+    #[must_use]
+    pub fn new(indexes: &[usize], size: usize) -> Self {
+        let signature = (0..size as u64)
+            .map(|seed| {
+                indexes
+                    .iter()
+                    .map(|&idx| minhash_mix(idx as u64, seed))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect();
+        Self { signature }
+    }
+
+    /// The estimated Jaccard similarity, between `0.0` and `1.0`, of the two sketched index sets
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn similarity(&self, other: &Self) -> f64 {
+        let len = self.signature.len().min(other.signature.len());
+        if len == 0 {
+            return 0.0;
+        }
+        let equal = self
+            .signature
+            .iter()
+            .zip(other.signature.iter())
+            .filter(|(a, b)| a == b)
+            .count();
+        equal as f64 / len as f64
+    }
+}
+
+/// The `splitmix64` finalizer, seeded to derive a family of hash functions
+#[inline]
+fn minhash_mix(value: u64, seed: u64) -> u64 {
+    let mut z = value
+        .wrapping_add(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+        .wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Which corpus entry the [`SimilaritySpliceMutator`] picks as second parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpliceNeighbor {
+    /// The entry with the most similar coverage, to recombine inputs exercising the same code
+    Nearest,
+    /// The entry with the least similar coverage, to combine far apart behaviors
+    Farthest,
+}
+
+/// Splice mutation for inputs with a bytes vector, choosing the other input by coverage similarity.
+/// Testcases need a [`MapIndexesMetadata`], i.e. the map feedback must track indexes;
+/// the [`struct@MinHashMetadata`] sketches are computed lazily and cached in the testcases.
+/// If the current testcase has no coverage information, a random entry is used as for the
+/// [`crate::mutators::SpliceMutator`].
+#[derive(Debug, Clone)]
+pub struct SimilaritySpliceMutator {
+    neighbor: SpliceNeighbor,
+    sketch_size: usize,
+}
+
+impl<I, S> Mutator<I, S> for SimilaritySpliceMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasCorpus<I>,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let count = state.corpus().count();
+        if count < 2 {
+            return Ok(MutationResult::Skipped);
+        }
+        let current = *state.corpus().current();
+
+        let current_sketch = match current {
+            Some(cur) => self.sketch::<I, S>(state, cur)?.map(|sketch| (cur, sketch)),
+            None => None,
+        };
+
+        let idx = match current_sketch {
+            Some((cur, sketch)) => {
+                // Start at a random offset so that ties do not always favor the same entry
+                let offset = state.rand_mut().below(count as u64) as usize;
+                let mut best: Option<(usize, f64)> = None;
+                for i in 0..count {
+                    let idx = (offset + i) % count;
+                    if idx == cur {
+                        continue;
+                    }
+                    if let Some(other) = self.sketch::<I, S>(state, idx)? {
+                        let similarity = sketch.similarity(&other);
+                        let better = match (best, self.neighbor) {
+                            (None, _) => true,
+                            (Some((_, s)), SpliceNeighbor::Nearest) => similarity > s,
+                            (Some((_, s)), SpliceNeighbor::Farthest) => similarity < s,
+                        };
+                        if better {
+                            best = Some((idx, similarity));
+                        }
+                    }
+                }
+                match best {
+                    Some((idx, _)) => idx,
+                    None => return Ok(MutationResult::Skipped),
+                }
+            }
+            None => {
+                let idx = state.rand_mut().below(count as u64) as usize;
+                if current == Some(idx) {
+                    return Ok(MutationResult::Skipped);
+                }
+                idx
+            }
+        };
+
+        splice_with_corpus_entry(state, input, idx)
+    }
+}
+
+impl Named for SimilaritySpliceMutator {
+    fn name(&self) -> &str {
+        "SimilaritySpliceMutator"
+    }
+}
+
+impl SimilaritySpliceMutator {
+    /// Creates a new [`SimilaritySpliceMutator`] picking the given neighbor.
+    #[must_use]
+    pub fn new(neighbor: SpliceNeighbor) -> Self {
+        Self::with_sketch_size(neighbor, DEFAULT_MINHASH_SIZE)
+    }
+
+    /// Creates a new [`SimilaritySpliceMutator`] using `sketch_size` hash functions per sketch.
+    #[must_use]
+    pub fn with_sketch_size(neighbor: SpliceNeighbor, sketch_size: usize) -> Self {
+        Self {
+            neighbor,
+            sketch_size,
+        }
+    }
+
+    /// Get the sketch of the corpus entry at `idx`, computing and storing it if needed.
+    /// Returns `None` if the entry has no coverage information.
+    fn sketch<I, S>(&self, state: &mut S, idx: usize) -> Result<Option<MinHashMetadata>, Error>
+    where
+        I: Input,
+        S: HasCorpus<I>,
+    {
+        let mut testcase = state.corpus().get(idx)?.borrow_mut();
+        if let Some(sketch) = testcase.metadata().get::<MinHashMetadata>() {
+            if sketch.signature.len() == self.sketch_size {
+                return Ok(Some(sketch.clone()));
+            }
+        }
+        let sketch = match testcase.metadata().get::<MapIndexesMetadata>() {
+            Some(meta) => MinHashMetadata::new(meta.as_slice(), self.sketch_size),
+            None => return Ok(None),
+        };
+        testcase.add_metadata(sketch.clone());
+        Ok(Some(sketch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutators::similarity::{MinHashMetadata, DEFAULT_MINHASH_SIZE};
+
+    #[test]
+    fn test_minhash_similarity() {
+        let a = MinHashMetadata::new(&[1, 2, 3, 4, 5, 6, 7, 8], DEFAULT_MINHASH_SIZE);
+        let b = MinHashMetadata::new(&[1, 2, 3, 4, 5, 6, 7, 8], DEFAULT_MINHASH_SIZE);
+        let c = MinHashMetadata::new(&[100, 200, 300, 400], DEFAULT_MINHASH_SIZE);
+        assert!((a.similarity(&b) - 1.0).abs() < f64::EPSILON);
+        assert!(a.similarity(&c) < a.similarity(&b));
+    }
+}
@@ -24,8 +24,10 @@ use std::{
 use crate::{
     bolts::{rands::Rand, AsSlice},
     inputs::{HasBytesVec, Input},
-    mutators::{buffer_self_copy, mutations::buffer_copy, MutationResult, Mutator, Named},
-    observers::cmp::{CmpValues, CmpValuesMetadata},
+    mutators::{
+        buffer_self_copy, max_size_of, mutations::buffer_copy, MutationResult, Mutator, Named,
+    },
+    observers::cmp::{CmpValues, CmpValuesMetadata, TaintMetadata},
     state::{HasMaxSize, HasMetadata, HasRand},
     Error,
 };
@@ -39,7 +41,7 @@ pub struct Tokens {
     tokens_set: HashSet<Vec<u8>>,
 }
 
-crate::impl_serdeany!(Tokens);
+crate::impl_serdeany!(Tokens, "libafl::mutators::token_mutations::Tokens");
 
 /// The metadata used for token mutators
 impl Tokens {
@@ -306,7 +308,7 @@ where
         input: &mut I,
         _stage_idx: i32,
     ) -> Result<MutationResult, Error> {
-        let max_size = state.max_size();
+        let max_size = max_size_of(state, input);
         let tokens_len = {
             let meta = state.metadata().get::<Tokens>();
             if meta.is_none() {
@@ -420,6 +422,7 @@ impl TokenReplace {
 
 /// A `I2SRandReplace` [`Mutator`] replaces a random matching input-2-state comparison operand with the other.
 /// It needs a valid [`CmpValuesMetadata`] in the state.
+/// With a [`TaintMetadata`] in the state, it starts looking for the operand in a tainted range.
 #[derive(Debug, Default)]
 pub struct I2SRandReplace;
 
@@ -452,7 +455,23 @@ where
         };
         let idx = state.rand_mut().below(cmps_len as u64) as usize;
 
-        let off = state.rand_mut().below(size as u64) as usize;
+        // Start looking in a tainted range, if the colorization found some
+        let tainted = state
+            .metadata()
+            .get::<TaintMetadata>()
+            .map_or(0, |meta| meta.ranges.len());
+        let off = if tainted == 0 {
+            state.rand_mut().below(size as u64) as usize
+        } else {
+            let range_idx = state.rand_mut().below(tainted as u64) as usize;
+            let range = state.metadata().get::<TaintMetadata>().unwrap().ranges[range_idx].clone();
+            let off = range.start + state.rand_mut().below(range.len() as u64) as usize;
+            if off < size {
+                off
+            } else {
+                state.rand_mut().below(size as u64) as usize
+            }
+        };
         let len = input.bytes().len();
         let bytes = input.bytes_mut();
 
@@ -0,0 +1,421 @@
+//! Mutations for text inputs that keep them valid UTF-8.
+//! All of them are skipped if the input is not valid UTF-8 in the first place.
+
+use core::ops::Range;
+
+use crate::{
+    bolts::{
+        rands::Rand,
+        tuples::{tuple_list, tuple_list_type, Named},
+    },
+    inputs::{HasBytesVec, Input},
+    mutators::{max_size_of, MutationResult, Mutator},
+    state::{HasMaxSize, HasRand},
+    Error,
+};
+
+/// Code points at the borders of the UTF-8 encoding lengths and around the surrogate range,
+/// plus a few special characters parsers tend to get wrong.
+pub const UTF8_BORDER_CODEPOINTS: [char; 14] = [
+    '\u{7f}',
+    '\u{80}',
+    '\u{7ff}',
+    '\u{800}',
+    '\u{d7ff}',
+    '\u{e000}',
+    '\u{fdd0}',
+    '\u{fefe}',
+    '\u{feff}',
+    '\u{fffd}',
+    '\u{ffff}',
+    '\u{10000}',
+    '\u{10ffff}',
+    '\u{200b}',
+];
+
+/// The zero of decimal digits in scripts other than latin, each followed by the other nine digits
+const LOCALE_DIGIT_ZEROS: [u32; 8] = [
+    0x0660, // Arabic-Indic
+    0x06f0, // Extended Arabic-Indic
+    0x0966, // Devanagari
+    0x09e6, // Bengali
+    0x0e50, // Thai
+    0x0f20, // Tibetan
+    0x1040, // Myanmar
+    0xff10, // Fullwidth
+];
+
+/// Precomposed characters and their canonical decomposition (NFC <-> NFD)
+const NORMALIZATION_PAIRS: [(&str, &str); 11] = [
+    ("\u{e0}", "a\u{300}"),
+    ("\u{e1}", "a\u{301}"),
+    ("\u{e4}", "a\u{308}"),
+    ("\u{e7}", "c\u{327}"),
+    ("\u{e8}", "e\u{300}"),
+    ("\u{e9}", "e\u{301}"),
+    ("\u{f1}", "n\u{303}"),
+    ("\u{f6}", "o\u{308}"),
+    ("\u{fc}", "u\u{308}"),
+    ("\u{c5}", "A\u{30a}"),
+    ("\u{212b}", "\u{c5}"),
+];
+
+/// The input as `str`, if it is valid UTF-8
+fn as_utf8<I>(input: &I) -> Option<&str>
+where
+    I: HasBytesVec,
+{
+    core::str::from_utf8(input.bytes()).ok()
+}
+
+/// Replace `range` of the input with `with`, respecting the max size
+fn replace_range<I>(
+    input: &mut I,
+    range: Range<usize>,
+    with: &str,
+    max_size: usize,
+) -> MutationResult
+where
+    I: HasBytesVec,
+{
+    if input.bytes().len() - range.len() + with.len() > max_size {
+        return MutationResult::Skipped;
+    }
+    input.bytes_mut().splice(range, with.bytes());
+    MutationResult::Mutated
+}
+
+/// A random char boundary in `s`, including its end
+fn random_boundary<R>(rand: &mut R, s: &str) -> usize
+where
+    R: Rand,
+{
+    let count = s.chars().count();
+    let nth = rand.below(count as u64 + 1) as usize;
+    s.char_indices().nth(nth).map_or(s.len(), |(off, _)| off)
+}
+
+/// Insert a random multi-byte code point at a char boundary
+#[derive(Default, Debug)]
+pub struct Utf8InsertCodepointMutator;
+
+impl<I, S> Mutator<I, S> for Utf8InsertCodepointMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = max_size_of(state, input);
+        let off = match as_utf8(input) {
+            Some(s) => random_boundary(state.rand_mut(), s),
+            None => return Ok(MutationResult::Skipped),
+        };
+        // Pick the encoded length first, so that 2, 3 and 4 bytes sequences are equally likely
+        let codepoint = loop {
+            let candidate = match state.rand_mut().below(3) {
+                0 => state.rand_mut().between(0x80, 0x7ff),
+                1 => state.rand_mut().between(0x800, 0xffff),
+                _ => state.rand_mut().between(0x10000, 0x10ffff),
+            };
+            // Surrogates are not valid code points
+            if let Some(c) = char::from_u32(candidate as u32) {
+                break c;
+            }
+        };
+        let mut buf = [0; 4];
+        Ok(replace_range(input, off..off, codepoint.encode_utf8(&mut buf), max_size))
+    }
+}
+
+impl Named for Utf8InsertCodepointMutator {
+    fn name(&self) -> &str {
+        "Utf8InsertCodepointMutator"
+    }
+}
+
+impl Utf8InsertCodepointMutator {
+    /// Creates a new [`Utf8InsertCodepointMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Flip the case of a random char, which may change its encoded length (e.g. `ß` -> `SS`)
+#[derive(Default, Debug)]
+pub struct Utf8CaseFlipMutator;
+
+impl<I, S> Mutator<I, S> for Utf8CaseFlipMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = max_size_of(state, input);
+        let (off, c) = match as_utf8(input) {
+            Some(s) if !s.is_empty() => {
+                let nth = state.rand_mut().below(s.chars().count() as u64) as usize;
+                s.char_indices().nth(nth).unwrap()
+            }
+            _ => return Ok(MutationResult::Skipped),
+        };
+        let mut flipped = [0; 16];
+        let mut len = 0;
+        if c.is_lowercase() {
+            for u in c.to_uppercase() {
+                len += u.encode_utf8(&mut flipped[len..]).len();
+            }
+        } else if c.is_uppercase() {
+            for l in c.to_lowercase() {
+                len += l.encode_utf8(&mut flipped[len..]).len();
+            }
+        } else {
+            return Ok(MutationResult::Skipped);
+        }
+        let flipped = core::str::from_utf8(&flipped[..len]).unwrap();
+        Ok(replace_range(input, off..off + c.len_utf8(), flipped, max_size))
+    }
+}
+
+impl Named for Utf8CaseFlipMutator {
+    fn name(&self) -> &str {
+        "Utf8CaseFlipMutator"
+    }
+}
+
+impl Utf8CaseFlipMutator {
+    /// Creates a new [`Utf8CaseFlipMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Swap a character between its composed and decomposed normalization forms
+#[derive(Default, Debug)]
+pub struct Utf8NormalizationMutator;
+
+impl<I, S> Mutator<I, S> for Utf8NormalizationMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = max_size_of(state, input);
+        let s = match as_utf8(input) {
+            Some(s) => s,
+            None => return Ok(MutationResult::Skipped),
+        };
+        // Start from a random pair, and use the first one found in the input
+        let start = state.rand_mut().below(NORMALIZATION_PAIRS.len() as u64) as usize;
+        let mut found = None;
+        for i in 0..NORMALIZATION_PAIRS.len() {
+            let (composed, decomposed) =
+                NORMALIZATION_PAIRS[(start + i) % NORMALIZATION_PAIRS.len()];
+            if let Some(off) = s.find(composed) {
+                found = Some((off..off + composed.len(), decomposed));
+                break;
+            }
+            if let Some(off) = s.find(decomposed) {
+                found = Some((off..off + decomposed.len(), composed));
+                break;
+            }
+        }
+        match found {
+            Some((range, with)) => Ok(replace_range(input, range, with, max_size)),
+            None => {
+                // Nothing to normalize, insert a decomposed sequence instead
+                let off = random_boundary(state.rand_mut(), s);
+                let (_, decomposed) = NORMALIZATION_PAIRS[start];
+                Ok(replace_range(input, off..off, decomposed, max_size))
+            }
+        }
+    }
+}
+
+impl Named for Utf8NormalizationMutator {
+    fn name(&self) -> &str {
+        "Utf8NormalizationMutator"
+    }
+}
+
+impl Utf8NormalizationMutator {
+    /// Creates a new [`Utf8NormalizationMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Insert or replace a char with one of the [`UTF8_BORDER_CODEPOINTS`]
+#[derive(Default, Debug)]
+pub struct Utf8BorderCodepointMutator;
+
+impl<I, S> Mutator<I, S> for Utf8BorderCodepointMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = max_size_of(state, input);
+        let range = match as_utf8(input) {
+            Some(s) => {
+                let off = random_boundary(state.rand_mut(), s);
+                let replaced = if state.rand_mut().below(2) == 0 {
+                    0
+                } else {
+                    s[off..].chars().next().map_or(0, char::len_utf8)
+                };
+                off..off + replaced
+            }
+            None => return Ok(MutationResult::Skipped),
+        };
+        let c = *state.rand_mut().choose(&UTF8_BORDER_CODEPOINTS);
+        let mut buf = [0; 4];
+        Ok(replace_range(input, range, c.encode_utf8(&mut buf), max_size))
+    }
+}
+
+impl Named for Utf8BorderCodepointMutator {
+    fn name(&self) -> &str {
+        "Utf8BorderCodepointMutator"
+    }
+}
+
+impl Utf8BorderCodepointMutator {
+    /// Creates a new [`Utf8BorderCodepointMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Replace an ASCII digit with the same digit of another script, e.g. `3` -> `٣`
+#[derive(Default, Debug)]
+pub struct Utf8LocaleDigitMutator;
+
+impl<I, S> Mutator<I, S> for Utf8LocaleDigitMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = max_size_of(state, input);
+        let (off, digit) = match as_utf8(input) {
+            Some(s) => {
+                let digits = s.bytes().filter(u8::is_ascii_digit).count();
+                if digits == 0 {
+                    return Ok(MutationResult::Skipped);
+                }
+                let nth = state.rand_mut().below(digits as u64) as usize;
+                let (off, digit) = s
+                    .bytes()
+                    .enumerate()
+                    .filter(|(_, b)| b.is_ascii_digit())
+                    .nth(nth)
+                    .unwrap();
+                (off, u32::from(digit - b'0'))
+            }
+            None => return Ok(MutationResult::Skipped),
+        };
+        let zero = *state.rand_mut().choose(&LOCALE_DIGIT_ZEROS);
+        let c = char::from_u32(zero + digit).unwrap();
+        let mut buf = [0; 4];
+        Ok(replace_range(input, off..off + 1, c.encode_utf8(&mut buf), max_size))
+    }
+}
+
+impl Named for Utf8LocaleDigitMutator {
+    fn name(&self) -> &str {
+        "Utf8LocaleDigitMutator"
+    }
+}
+
+impl Utf8LocaleDigitMutator {
+    /// Creates a new [`Utf8LocaleDigitMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Get the mutations that keep UTF-8 inputs valid
+#[must_use]
+pub fn utf8_mutations() -> tuple_list_type!(
+    Utf8InsertCodepointMutator,
+    Utf8CaseFlipMutator,
+    Utf8NormalizationMutator,
+    Utf8BorderCodepointMutator,
+    Utf8LocaleDigitMutator,
+) {
+    tuple_list!(
+        Utf8InsertCodepointMutator::new(),
+        Utf8CaseFlipMutator::new(),
+        Utf8NormalizationMutator::new(),
+        Utf8BorderCodepointMutator::new(),
+        Utf8LocaleDigitMutator::new(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{unicode::utf8_mutations, MutatorsTuple},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_utf8_mutations_keep_utf8() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut mutations = utf8_mutations();
+        let mut input = BytesInput::new("Straße 42, café".as_bytes().to_vec());
+        for i in 0..100 {
+            mutations
+                .get_and_mutate(i % 5, &mut state, &mut input, 0)
+                .unwrap();
+            assert!(core::str::from_utf8(input.bytes()).is_ok());
+        }
+
+        // Not valid UTF-8, nothing happens
+        let mut invalid = BytesInput::new(vec![0xff, 0xfe, b'1']);
+        for i in 0..5 {
+            mutations
+                .get_and_mutate(i, &mut state, &mut invalid, 0)
+                .unwrap();
+        }
+        assert_eq!(invalid.bytes(), &[0xff, 0xfe, b'1']);
+    }
+}
@@ -4,7 +4,7 @@ use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
-use core::fmt::Debug;
+use core::{fmt::Debug, ops::Range};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
@@ -60,7 +60,10 @@ pub struct CmpValuesMetadata {
     pub list: Vec<CmpValues>,
 }
 
-crate::impl_serdeany!(CmpValuesMetadata);
+crate::impl_serdeany!(
+    CmpValuesMetadata,
+    "libafl::observers::cmp::CmpValuesMetadata"
+);
 
 impl AsSlice<CmpValues> for CmpValuesMetadata {
     /// Convert to a slice
@@ -85,6 +88,45 @@ impl CmpValuesMetadata {
     }
 }
 
+/// The input-dependent ranges of a testcase, found by the
+/// [`crate::stages::ColorizationStage`]: replacing their bytes leaves the coverage unchanged.
+/// It is stored in the testcase and, for the testcase currently fuzzed, in the state,
+/// where the [`crate::mutators::I2SRandReplace`] mutator looks for it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaintMetadata {
+    /// The colorized input, with random bytes in the tainted ranges
+    pub input_vec: Vec<u8>,
+    /// The tainted ranges, sorted and disjoint
+    pub ranges: Vec<Range<usize>>,
+}
+
+crate::impl_serdeany!(TaintMetadata, "libafl::observers::cmp::TaintMetadata");
+
+impl TaintMetadata {
+    /// Creates a new [`struct@TaintMetadata`], merging the overlapping or adjacent `ranges`
+    #[must_use]
+    pub fn new(input_vec: Vec<u8>, mut ranges: Vec<Range<usize>>) -> Self {
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Self {
+            input_vec,
+            ranges: merged,
+        }
+    }
+
+    /// The number of tainted bytes
+    #[must_use]
+    pub fn tainted_len(&self) -> usize {
+        self.ranges.iter().map(ExactSizeIterator::len).sum()
+    }
+}
+
 /// A [`CmpMap`] traces comparisons during the current execution
 pub trait CmpMap: Debug {
     /// Get the number of cmps
@@ -263,3 +305,15 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::observers::TaintMetadata;
+
+    #[test]
+    fn test_taint_metadata_merge() {
+        let meta = TaintMetadata::new(vec![0; 16], vec![8..10, 0..2, 2..4, 9..12, 14..15]);
+        assert_eq!(meta.ranges, vec![0..4, 8..12, 14..15]);
+        assert_eq!(meta.tainted_len(), 9);
+    }
+}
@@ -0,0 +1,323 @@
+//! Observers for differential fuzzing with the [`crate::executors::DiffExecutor`].
+//!
+//! The differential observers belong to the [`crate::executors::DiffExecutor`] itself. After each
+//! of the two wrapped executors ran, they are handed its observers, to record what the run did.
+//! A [`crate::feedbacks::DiffFeedback`] then compares the records of the two runs, e.g. two
+//! [`DiffHashObserver`]s hashing the stdout of the primary and the secondary executor.
+
+use alloc::string::{String, ToString};
+use core::{fmt::Debug, hash::Hasher, marker::PhantomData, ops::Range};
+
+use ahash::AHasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    observers::{MapObserver, Observer, ObserversTuple},
+    Error,
+};
+
+/// An observer recording the runs of the two executors of a [`crate::executors::DiffExecutor`].
+/// Each hook gets the observers of the corresponding executor, after its own hooks ran.
+pub trait DifferentialObserver<OTA, OTB, I, S>: Observer<I, S>
+where
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    /// Called before the primary executor runs
+    fn pre_observe_first(&mut self, _observers: &mut OTA) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after the primary executor ran
+    fn post_observe_first(&mut self, _observers: &mut OTA) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called before the secondary executor runs
+    fn pre_observe_second(&mut self, _observers: &mut OTB) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called after the secondary executor ran
+    fn post_observe_second(&mut self, _observers: &mut OTB) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A haskell-style tuple of [`DifferentialObserver`]s
+pub trait DifferentialObserversTuple<OTA, OTB, I, S>: ObserversTuple<I, S>
+where
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    /// Call [`DifferentialObserver::pre_observe_first`] for all the observers
+    fn pre_observe_first_all(&mut self, observers: &mut OTA) -> Result<(), Error>;
+
+    /// Call [`DifferentialObserver::post_observe_first`] for all the observers
+    fn post_observe_first_all(&mut self, observers: &mut OTA) -> Result<(), Error>;
+
+    /// Call [`DifferentialObserver::pre_observe_second`] for all the observers
+    fn pre_observe_second_all(&mut self, observers: &mut OTB) -> Result<(), Error>;
+
+    /// Call [`DifferentialObserver::post_observe_second`] for all the observers
+    fn post_observe_second_all(&mut self, observers: &mut OTB) -> Result<(), Error>;
+}
+
+impl<OTA, OTB, I, S> DifferentialObserversTuple<OTA, OTB, I, S> for ()
+where
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    fn pre_observe_first_all(&mut self, _observers: &mut OTA) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_observe_first_all(&mut self, _observers: &mut OTA) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn pre_observe_second_all(&mut self, _observers: &mut OTB) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_observe_second_all(&mut self, _observers: &mut OTB) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<Head, Tail, OTA, OTB, I, S> DifferentialObserversTuple<OTA, OTB, I, S> for (Head, Tail)
+where
+    Head: DifferentialObserver<OTA, OTB, I, S>,
+    Tail: DifferentialObserversTuple<OTA, OTB, I, S>,
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    fn pre_observe_first_all(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        self.0.pre_observe_first(observers)?;
+        self.1.pre_observe_first_all(observers)
+    }
+
+    fn post_observe_first_all(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        self.0.post_observe_first(observers)?;
+        self.1.post_observe_first_all(observers)
+    }
+
+    fn pre_observe_second_all(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        self.0.pre_observe_second(observers)?;
+        self.1.pre_observe_second_all(observers)
+    }
+
+    fn post_observe_second_all(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        self.0.post_observe_second(observers)?;
+        self.1.post_observe_second_all(observers)
+    }
+}
+
+/// An observer whose outcome can be summarized by a hash, to compare two runs
+pub trait DiffHash {
+    /// The hash of the last run, restricted to the entries in `range` for maps
+    fn diff_hash(&self, range: Option<&Range<usize>>) -> Option<u64>;
+}
+
+impl<M> DiffHash for M
+where
+    M: MapObserver,
+{
+    fn diff_hash(&self, range: Option<&Range<usize>>) -> Option<u64> {
+        match range {
+            None => Some(self.hash()),
+            Some(range) => {
+                let mut hasher = AHasher::new_with_keys(0, 0);
+                let end = range.end.min(self.usable_count());
+                for i in range.start..end {
+                    hasher.write_i128(self.get(i).to_i128().unwrap_or_default());
+                }
+                Some(hasher.finish())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl DiffHash for crate::observers::StdOutObserver {
+    fn diff_hash(&self, _range: Option<&Range<usize>>) -> Option<u64> {
+        *crate::observers::ObserverWithHashField::hash(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl DiffHash for crate::observers::StdErrObserver {
+    fn diff_hash(&self, _range: Option<&Range<usize>>) -> Option<u64> {
+        *crate::observers::ObserverWithHashField::hash(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> DiffHash for crate::observers::BacktraceObserver<'a> {
+    fn diff_hash(&self, _range: Option<&Range<usize>>) -> Option<u64> {
+        *crate::observers::ObserverWithHashField::hash(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl DiffHash for crate::observers::ASANBacktraceObserver {
+    fn diff_hash(&self, _range: Option<&Range<usize>>) -> Option<u64> {
+        *crate::observers::ObserverWithHashField::hash(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl DiffHash for crate::observers::SanitizerReportObserver {
+    fn diff_hash(&self, _range: Option<&Range<usize>>) -> Option<u64> {
+        *crate::observers::ObserverWithHashField::hash(self)
+    }
+}
+
+/// The executor of a [`crate::executors::DiffExecutor`] a [`DiffHashObserver`] records
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSide {
+    /// The primary executor
+    First,
+    /// The secondary executor
+    Second,
+}
+
+/// A differential observer recording the [`DiffHash`] of an observer of one of the two executors.
+/// Compare a pair of them with a [`crate::feedbacks::DiffFeedback`], e.g. to catch
+/// a different stdout, or different coverage in the given region of the maps.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound = "")]
+pub struct DiffHashObserver<O> {
+    name: String,
+    observer_name: String,
+    side: DiffSide,
+    range: Option<Range<usize>>,
+    hash: Option<u64>,
+    phantom: PhantomData<O>,
+}
+
+impl<O> DiffHashObserver<O>
+where
+    O: DiffHash,
+{
+    /// Creates a new [`DiffHashObserver`] recording the observer named `observer_name` of the
+    /// executor on the given `side`
+    #[must_use]
+    pub fn new(name: &str, observer_name: &str, side: DiffSide) -> Self {
+        Self {
+            name: name.to_string(),
+            observer_name: observer_name.to_string(),
+            side,
+            range: None,
+            hash: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Only hash the entries in `range`, for map observers
+    #[must_use]
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    /// The recorded hash of the last run
+    #[must_use]
+    pub fn hash(&self) -> Option<u64> {
+        self.hash
+    }
+
+    fn record<OT>(&mut self, observers: &OT) -> Result<(), Error>
+    where
+        OT: MatchName,
+    {
+        let observer = observers
+            .match_name::<O>(&self.observer_name)
+            .ok_or_else(|| {
+                Error::KeyNotFound(format!(
+                    "DiffHashObserver: observer {} not found",
+                    self.observer_name
+                ))
+            })?;
+        self.hash = observer.diff_hash(self.range.as_ref());
+        Ok(())
+    }
+}
+
+impl<O> PartialEq for DiffHashObserver<O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+    }
+}
+
+impl<I, O, S> Observer<I, S> for DiffHashObserver<O>
+where
+    O: Debug,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.hash = None;
+        Ok(())
+    }
+}
+
+impl<O, OTA, OTB, I, S> DifferentialObserver<OTA, OTB, I, S> for DiffHashObserver<O>
+where
+    O: DiffHash + Debug,
+    OTA: ObserversTuple<I, S>,
+    OTB: ObserversTuple<I, S>,
+{
+    fn post_observe_first(&mut self, observers: &mut OTA) -> Result<(), Error> {
+        if self.side == DiffSide::First {
+            self.record(observers)?;
+        }
+        Ok(())
+    }
+
+    fn post_observe_second(&mut self, observers: &mut OTB) -> Result<(), Error> {
+        if self.side == DiffSide::Second {
+            self.record(observers)?;
+        }
+        Ok(())
+    }
+}
+
+impl<O> Named for DiffHashObserver<O> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::tuples::tuple_list,
+        observers::{DiffHashObserver, DiffSide, DifferentialObserver, StdMapObserver},
+    };
+
+    #[test]
+    fn test_diff_hash_map_region() {
+        let mut map_a = [0_u8, 1, 2, 3];
+        let mut map_b = [0_u8, 1, 2, 4];
+        let mut first = tuple_list!(StdMapObserver::new("edges", &mut map_a));
+        let mut second = tuple_list!(StdMapObserver::new("edges", &mut map_b));
+
+        let mut hash_a = DiffHashObserver::<StdMapObserver<u8>>::new("a", "edges", DiffSide::First)
+            .with_range(0..3);
+        let mut hash_b =
+            DiffHashObserver::<StdMapObserver<u8>>::new("b", "edges", DiffSide::Second)
+                .with_range(0..3);
+        for observer in [&mut hash_a, &mut hash_b] {
+            DifferentialObserver::<_, _, (), ()>::post_observe_first(observer, &mut first).unwrap();
+            DifferentialObserver::<_, _, (), ()>::post_observe_second(observer, &mut second)
+                .unwrap();
+        }
+        assert!(hash_a.hash().is_some());
+        assert!(hash_a == hash_b);
+
+        hash_b = hash_b.with_range(0..4);
+        DifferentialObserver::<_, _, (), ()>::post_observe_second(&mut hash_b, &mut second)
+            .unwrap();
+        assert!(hash_a != hash_b);
+    }
+}
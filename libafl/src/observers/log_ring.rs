@@ -0,0 +1,141 @@
+//! The [`LogRingObserver`] maps a ring buffer, usually in shared memory, where the target writes its
+//! log lines. Unlike capturing the stdout of the target, the log costs nothing unless it is read,
+//! and the [`crate::feedbacks::LogRingFeedback`] reads it only for the testcases kept in a corpus.
+//!
+//! The first [`LOG_RING_HEADER_SIZE`] bytes of the buffer hold the number of bytes written since the
+//! start of the run, as little endian `u64`, the remaining bytes the last written ones.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedSliceMut, tuples::Named, AsMutSlice, AsSlice},
+    observers::Observer,
+    Error,
+};
+
+/// The default name of the env variable holding the shared memory of the log ring
+pub const LOG_RING_ENV: &str = "LIBAFL_LOG_RING_SHM";
+/// The default size of the log ring, header included
+pub const LOG_RING_DEFAULT_SIZE: usize = 64 * 1024;
+/// The size of the header of the log ring, counting the written bytes
+pub const LOG_RING_HEADER_SIZE: usize = 8;
+
+/// The number of bytes written to the log `ring` since its last reset
+#[must_use]
+pub fn log_ring_written(ring: &[u8]) -> u64 {
+    let mut header = [0; LOG_RING_HEADER_SIZE];
+    header.copy_from_slice(&ring[..LOG_RING_HEADER_SIZE]);
+    u64::from_le_bytes(header)
+}
+
+/// Append `data` to the log `ring`, overwriting the oldest bytes when full
+pub fn log_ring_write(ring: &mut [u8], data: &[u8]) {
+    let written = log_ring_written(ring);
+    let (header, buf) = ring.split_at_mut(LOG_RING_HEADER_SIZE);
+    if buf.is_empty() {
+        return;
+    }
+    let cap = buf.len();
+    // Only the last `cap` bytes of `data` survive anyway
+    let skipped = data.len().saturating_sub(cap);
+    let tail = &data[skipped..];
+    let pos = ((written + skipped as u64) % cap as u64) as usize;
+    let first = tail.len().min(cap - pos);
+    buf[pos..pos + first].copy_from_slice(&tail[..first]);
+    buf[..tail.len() - first].copy_from_slice(&tail[first..]);
+    header.copy_from_slice(&(written + data.len() as u64).to_le_bytes());
+}
+
+/// The content of the log `ring`, from the oldest byte still there to the last written
+#[must_use]
+pub fn log_ring_read(ring: &[u8]) -> Vec<u8> {
+    let written = log_ring_written(ring);
+    let buf = &ring[LOG_RING_HEADER_SIZE..];
+    let cap = buf.len() as u64;
+    if written <= cap {
+        buf[..written as usize].to_vec()
+    } else {
+        let pos = (written % cap) as usize;
+        let mut log = buf[pos..].to_vec();
+        log.extend_from_slice(&buf[..pos]);
+        log
+    }
+}
+
+/// An observer for a log ring written by the target, which it resets before each run
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct LogRingObserver<'a> {
+    name: String,
+    ring: OwnedSliceMut<'a, u8>,
+}
+
+impl<'a> LogRingObserver<'a> {
+    /// Creates a new [`LogRingObserver`] for the given ring, of at least [`LOG_RING_HEADER_SIZE`] bytes
+    #[must_use]
+    pub fn new(name: &str, ring: &'a mut [u8]) -> Self {
+        assert!(ring.len() > LOG_RING_HEADER_SIZE);
+        Self {
+            name: name.to_string(),
+            ring: OwnedSliceMut::from(ring),
+        }
+    }
+
+    /// Creates a new [`LogRingObserver`] from a raw pointer
+    ///
+    /// # Safety
+    /// Will dereference the `ring_ptr` with up to `len` elements.
+    pub unsafe fn new_from_ptr(name: &str, ring_ptr: *mut u8, len: usize) -> Self {
+        assert!(len > LOG_RING_HEADER_SIZE);
+        Self {
+            name: name.to_string(),
+            ring: OwnedSliceMut::from_raw_parts_mut(ring_ptr, len),
+        }
+    }
+
+    /// The number of bytes logged by the last run, the oldest may have been overwritten
+    #[must_use]
+    pub fn written(&self) -> u64 {
+        log_ring_written(self.ring.as_slice())
+    }
+
+    /// The log of the last run
+    #[must_use]
+    pub fn log(&self) -> Vec<u8> {
+        log_ring_read(self.ring.as_slice())
+    }
+}
+
+impl<'a, I, S> Observer<I, S> for LogRingObserver<'a> {
+    #[inline]
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.ring.as_mut_slice()[..LOG_RING_HEADER_SIZE].fill(0);
+        Ok(())
+    }
+}
+
+impl<'a> Named for LogRingObserver<'a> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::observers::{log_ring_read, log_ring_write, LOG_RING_HEADER_SIZE};
+
+    #[test]
+    fn test_log_ring_wraps() {
+        let mut ring = [0_u8; LOG_RING_HEADER_SIZE + 8];
+        log_ring_write(&mut ring, b"hello ");
+        assert_eq!(log_ring_read(&ring), b"hello ");
+        log_ring_write(&mut ring, b"world");
+        assert_eq!(log_ring_read(&ring), b"lo world");
+        log_ring_write(&mut ring, b"0123456789abc");
+        assert_eq!(log_ring_read(&ring), b"56789abc");
+    }
+}
@@ -22,8 +22,9 @@ use crate::{
         tuples::Named,
         AsMutIterator, AsMutSlice, AsRefIterator, AsSlice, HasLen,
     },
-    executors::ExitKind,
+    executors::{BatchRunMetadata, ExitKind},
     observers::Observer,
+    state::HasMetadata,
     Error,
 };
 
@@ -73,6 +74,13 @@ pub trait MapObserver: HasLen + Named + Serialize + serde::de::DeserializeOwned
     /// Compute the hash of the map
     fn hash(&self) -> u64;
 
+    /// Get the usable entries as a slice, if the map is contiguous in memory.
+    /// The [`crate::feedbacks::MapFeedback`] then scans it a word at a time.
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[Self::Entry]> {
+        None
+    }
+
     /// Get the initial value for reset()
     fn initial(&self) -> Self::Entry;
 
@@ -300,6 +308,12 @@ where
         hash_slice(self.as_slice())
     }
 
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[T]> {
+        let cnt = self.usable_count();
+        Some(&self.as_slice()[..cnt])
+    }
+
     #[inline]
     fn initial(&self) -> T {
         self.initial
@@ -510,13 +524,18 @@ where
     }
 
     fn usable_count(&self) -> usize {
-        self.as_slice().len()
+        N
     }
 
     fn hash(&self) -> u64 {
         hash_slice(self.as_slice())
     }
 
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[T]> {
+        Some(&self.as_slice()[..N])
+    }
+
     fn to_vec(&self) -> Vec<T> {
         self.as_slice().to_vec()
     }
@@ -710,6 +729,13 @@ where
     fn hash(&self) -> u64 {
         hash_slice(self.as_slice())
     }
+
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[T]> {
+        let cnt = self.usable_count();
+        Some(&self.as_slice()[..cnt])
+    }
+
     fn to_vec(&self) -> Vec<T> {
         self.as_slice().to_vec()
     }
@@ -867,6 +893,12 @@ where
     fn hash(&self) -> u64 {
         self.base.hash()
     }
+
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[u8]> {
+        self.base.as_contiguous()
+    }
+
     fn to_vec(&self) -> Vec<u8> {
         self.base.to_vec()
     }
@@ -951,6 +983,193 @@ where
     }
 }
 
+/// How an [`AccumulatingMapObserver`] combines the maps of the runs of a batch
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccumulationMode {
+    /// An entry is set if it was set by any run, to the highest value
+    Union,
+    /// An entry is set only if it was set by all the runs, to the lowest value
+    Intersection,
+}
+
+/// Map observer accumulating the map of the wrapped observer over a batch of runs of the same input,
+/// executed by a [`crate::executors::RepeatExecutor`]. For targets with nondeterministic coverage,
+/// the intersection of the maps only keeps the stable entries, and avoids adding the same input
+/// over and over for its flaky edges.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    base: M,
+    mode: AccumulationMode,
+    #[serde(skip)]
+    accumulated: Vec<M::Entry>,
+    /// Set after the last run of a batch, the next run starts a new accumulation
+    #[serde(skip)]
+    batch_done: bool,
+}
+
+impl<M> AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    /// Creates a new [`AccumulatingMapObserver`], combining the maps of `base` in the given `mode`
+    pub fn new(base: M, mode: AccumulationMode) -> Self {
+        Self {
+            base,
+            mode,
+            accumulated: Vec::new(),
+            batch_done: true,
+        }
+    }
+
+    /// The map of the last run
+    pub fn base(&self) -> &M {
+        &self.base
+    }
+
+    /// Combine the map of the last run with the ones of the previous runs of the batch
+    fn accumulate(&mut self) {
+        let cnt = self.base.usable_count();
+        if self.accumulated.is_empty() {
+            self.accumulated = self.base.to_vec();
+            self.accumulated.truncate(cnt);
+            return;
+        }
+        let initial = self.base.initial();
+        for (i, acc) in self.accumulated.iter_mut().enumerate().take(cnt) {
+            let item = *self.base.get(i);
+            *acc = match self.mode {
+                AccumulationMode::Union if *acc == initial => item,
+                AccumulationMode::Union if item != initial => (*acc).max(item),
+                AccumulationMode::Union => *acc,
+                AccumulationMode::Intersection if *acc == initial || item == initial => initial,
+                AccumulationMode::Intersection => (*acc).min(item),
+            };
+        }
+    }
+}
+
+impl<I, S, M> Observer<I, S> for AccumulatingMapObserver<M>
+where
+    M: MapObserver + Observer<I, S>,
+    S: HasMetadata,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        // The first run of an input, the fuzzer calls `pre_exec` before the batch sets its metadata
+        if self.batch_done {
+            self.accumulated.clear();
+            self.batch_done = false;
+        }
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)?;
+        self.accumulate();
+        // A batch ends after its last run, or at the first run not exiting normally
+        self.batch_done = *exit_kind != ExitKind::Ok
+            || state
+                .metadata()
+                .get::<BatchRunMetadata>()
+                .map_or(true, |batch| batch.index + 1 >= batch.runs);
+        Ok(())
+    }
+}
+
+impl<M> Named for AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.base.name()
+    }
+}
+
+impl<M> HasLen for AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl<M> MapObserver for AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Entry = M::Entry;
+
+    #[inline]
+    fn initial(&self) -> M::Entry {
+        self.base.initial()
+    }
+
+    #[inline]
+    fn initial_mut(&mut self) -> &mut M::Entry {
+        self.base.initial_mut()
+    }
+
+    #[inline]
+    fn usable_count(&self) -> usize {
+        self.accumulated.len()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> &M::Entry {
+        &self.accumulated[idx]
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> &mut M::Entry {
+        &mut self.accumulated[idx]
+    }
+
+    fn hash(&self) -> u64 {
+        hash_slice(&self.accumulated)
+    }
+
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[M::Entry]> {
+        Some(&self.accumulated)
+    }
+
+    fn to_vec(&self) -> Vec<M::Entry> {
+        self.accumulated.clone()
+    }
+}
+
+impl<'it, M> AsRefIterator<'it> for AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Item = M::Entry;
+    type IntoIter = Iter<'it, M::Entry>;
+
+    fn as_ref_iter(&'it self) -> Self::IntoIter {
+        self.accumulated.iter()
+    }
+}
+
+impl<'it, M> AsMutIterator<'it> for AccumulatingMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Item = M::Entry;
+    type IntoIter = IterMut<'it, M::Entry>;
+
+    fn as_mut_iter(&'it mut self) -> Self::IntoIter {
+        self.accumulated.iter_mut()
+    }
+}
+
 /// The Multi Map Observer merge different maps into one observer
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(bound = "T: serde::de::DeserializeOwned")]
@@ -1298,6 +1517,12 @@ where
         hash_slice(self.as_slice())
     }
 
+    #[inline]
+    fn as_contiguous(&self) -> Option<&[T]> {
+        let cnt = self.usable_count();
+        Some(&self.as_slice()[..cnt])
+    }
+
     #[inline]
     fn initial(&self) -> T {
         self.initial
@@ -6,6 +6,18 @@ pub use map::*;
 pub mod cmp;
 pub use cmp::*;
 
+pub mod value;
+pub use value::*;
+
+pub mod log_ring;
+pub use log_ring::*;
+
+pub mod differential;
+pub use differential::*;
+
+pub mod profiler;
+pub use profiler::IntrospectionObserver;
+
 #[cfg(feature = "std")]
 pub mod stdio;
 #[cfg(feature = "std")]
@@ -16,6 +28,11 @@ pub mod stacktrace;
 #[cfg(feature = "std")]
 pub use stacktrace::*;
 
+#[cfg(feature = "std")]
+pub mod sanitizer;
+#[cfg(feature = "std")]
+pub use sanitizer::*;
+
 pub mod concolic;
 
 #[cfg(unstable_feature)]
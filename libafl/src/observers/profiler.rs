@@ -0,0 +1,56 @@
+//! The [`IntrospectionObserver`] adds the time spent in the target to the
+//! [`struct@crate::stages::ProfilerMetadata`] of the state.
+
+use alloc::string::{String, ToString};
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{current_time, tuples::Named},
+    executors::ExitKind,
+    observers::Observer,
+    stages::ProfilerMetadata,
+    state::HasMetadata,
+    Error,
+};
+
+/// An observer timing each execution of the target, for the profiler
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IntrospectionObserver {
+    name: String,
+    start: Option<Duration>,
+}
+
+impl IntrospectionObserver {
+    /// Creates a new [`IntrospectionObserver`] with the given name.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            start: None,
+        }
+    }
+}
+
+impl<I, S> Observer<I, S> for IntrospectionObserver
+where
+    S: HasMetadata,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.start = Some(current_time());
+        Ok(())
+    }
+
+    fn post_exec(&mut self, state: &mut S, _input: &I, _exit_kind: &ExitKind) -> Result<(), Error> {
+        if let Some(start) = self.start.take() {
+            ProfilerMetadata::of_state(state).target += current_time() - start;
+        }
+        Ok(())
+    }
+}
+
+impl Named for IntrospectionObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
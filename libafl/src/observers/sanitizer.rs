@@ -0,0 +1,285 @@
+//! The [`SanitizerReportObserver`] parses the reports of the sanitizers (`ASan`, `UBSan`, `MSan`, ...)
+//! printed by a target running in a child process.
+//! The [`crate::executors::CommandExecutor`] passes it the stderr of the child, the
+//! [`crate::executors::ForkserverExecutor`] the sanitizer log file of a crashing child.
+
+use ahash::AHasher;
+use core::hash::{Hash, Hasher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::Once;
+
+use crate::{
+    bolts::tuples::Named,
+    executors::ExitKind,
+    observers::{Observer, ObserverWithHashField},
+    Error,
+};
+
+/// The default number of frames kept from the stacktrace of a report
+pub const DEFAULT_REPORT_FRAMES: usize = 8;
+/// How many of the top frames identify a report, for deduplication
+const HASHED_FRAMES: usize = 3;
+
+/// The patterns of the sanitizer reports, compiled once
+struct ReportPatterns {
+    header: Regex,
+    ubsan: Regex,
+    ubsan_summary: Regex,
+    frame: Regex,
+}
+
+static REPORT_PATTERNS_INIT: Once = Once::new();
+static mut REPORT_PATTERNS: Option<ReportPatterns> = None;
+
+/// The patterns of the sanitizer reports, compiled on first use
+fn report_patterns() -> &'static ReportPatterns {
+    unsafe {
+        REPORT_PATTERNS_INIT.call_once(|| {
+            REPORT_PATTERNS = Some(ReportPatterns {
+                header: Regex::new(
+                    r"==\d+==(?:ERROR|WARNING): (\w+Sanitizer): ([\w-]+)(?: on (?:unknown )?address 0x([0-9a-fA-F]+))?",
+                )
+                .unwrap(),
+                ubsan: Regex::new(r"runtime error: (.*)").unwrap(),
+                ubsan_summary: Regex::new(r"SUMMARY: UndefinedBehaviorSanitizer: ([\w-]+)")
+                    .unwrap(),
+                frame: Regex::new(r"(?m)^\s*#(\d+) 0x([0-9a-fA-F]+)(?: in (\S+))?(?: (\S+))?")
+                    .unwrap(),
+            });
+        });
+        REPORT_PATTERNS.as_ref().unwrap()
+    }
+}
+
+/// A frame of the stacktrace of a sanitizer report
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SanitizerFrame {
+    /// The program counter
+    pub pc: u64,
+    /// The function, if symbolized
+    pub function: Option<String>,
+    /// The source location or the module, if known
+    pub location: Option<String>,
+}
+
+/// A parsed sanitizer report, also stored in the metadata of the testcases
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SanitizerReport {
+    /// The sanitizer, e.g. `AddressSanitizer`
+    pub sanitizer: String,
+    /// The type of error, e.g. `heap-buffer-overflow`
+    pub error_type: String,
+    /// The faulting address, if any
+    pub address: Option<u64>,
+    /// The top frames of the stacktrace of the error
+    pub frames: Vec<SanitizerFrame>,
+}
+
+crate::impl_serdeany!(SanitizerReport);
+
+impl SanitizerReport {
+    /// Parse the first sanitizer report in `output`, keeping at most `max_frames` frames
+    #[must_use]
+    pub fn parse(output: &str, max_frames: usize) -> Option<Self> {
+        let ReportPatterns {
+            header,
+            ubsan,
+            ubsan_summary,
+            frame,
+        } = report_patterns();
+
+        let (sanitizer, error_type, address, start) = if let Some(c) = header.captures(output) {
+            (
+                c[1].to_string(),
+                c[2].to_string(),
+                c.get(3)
+                    .and_then(|m| u64::from_str_radix(m.as_str(), 16).ok()),
+                c.get(0).unwrap().end(),
+            )
+        } else if let Some(c) = ubsan.captures(output) {
+            let error_type = ubsan_summary
+                .captures(output)
+                .map_or_else(|| "undefined-behavior".to_string(), |s| s[1].to_string());
+            (
+                "UndefinedBehaviorSanitizer".to_string(),
+                error_type,
+                None,
+                c.get(0).unwrap().end(),
+            )
+        } else {
+            return None;
+        };
+
+        // Only the first stacktrace, the others are for the allocation and the free of the memory
+        let mut frames = vec![];
+        for c in frame.captures_iter(&output[start..]) {
+            if &c[1] == "0" && !frames.is_empty() {
+                break;
+            }
+            if frames.len() < max_frames {
+                frames.push(SanitizerFrame {
+                    pc: u64::from_str_radix(&c[2], 16).unwrap_or_default(),
+                    function: c.get(3).map(|m| m.as_str().to_string()),
+                    location: c.get(4).map(|m| m.as_str().to_string()),
+                });
+            }
+        }
+
+        Some(Self {
+            sanitizer,
+            error_type,
+            address,
+            frames,
+        })
+    }
+
+    /// A hash of the error type and the top frames, identifying the bug.
+    /// Symbolized frames are hashed by function, to be independent of ASLR.
+    #[must_use]
+    pub fn dedup_hash(&self) -> u64 {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        self.sanitizer.hash(&mut hasher);
+        self.error_type.hash(&mut hasher);
+        for frame in self.frames.iter().take(HASHED_FRAMES) {
+            match &frame.function {
+                Some(function) => function.hash(&mut hasher),
+                None => frame.pc.hash(&mut hasher),
+            }
+        }
+        hasher.finish()
+    }
+}
+
+/// An observer parsing the sanitizer report of the last run, if any
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SanitizerReportObserver {
+    observer_name: String,
+    max_frames: usize,
+    report: Option<SanitizerReport>,
+    hash: Option<u64>,
+}
+
+impl SanitizerReportObserver {
+    /// Creates a new [`SanitizerReportObserver`] with the given name.
+    /// Executors look it up by name, use [`Default`] unless there are more of them.
+    #[must_use]
+    pub fn new(observer_name: &str) -> Self {
+        Self {
+            observer_name: observer_name.to_string(),
+            max_frames: DEFAULT_REPORT_FRAMES,
+            report: None,
+            hash: None,
+        }
+    }
+
+    /// Keep at most `max_frames` frames of each report
+    #[must_use]
+    pub fn with_max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// The report of the last run
+    #[must_use]
+    pub fn report(&self) -> Option<&SanitizerReport> {
+        self.report.as_ref()
+    }
+
+    /// Parse the output of the target, e.g. its stderr
+    pub fn parse_output(&mut self, output: &str) {
+        self.report = SanitizerReport::parse(output, self.max_frames);
+        match self.report.as_ref().map(SanitizerReport::dedup_hash) {
+            Some(hash) => self.update_hash(hash),
+            None => self.clear_hash(),
+        }
+    }
+}
+
+impl Default for SanitizerReportObserver {
+    fn default() -> Self {
+        Self::new("SanitizerReportObserver")
+    }
+}
+
+impl ObserverWithHashField for SanitizerReportObserver {
+    /// The dedup hash of the last report
+    fn hash(&self) -> &Option<u64> {
+        &self.hash
+    }
+
+    fn update_hash(&mut self, hash: u64) {
+        self.hash = Some(hash);
+    }
+
+    fn clear_hash(&mut self) {
+        self.hash = None;
+    }
+}
+
+impl<I, S> Observer<I, S> for SanitizerReportObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.report = None;
+        self.clear_hash();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Named for SanitizerReportObserver {
+    fn name(&self) -> &str {
+        &self.observer_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::observers::SanitizerReport;
+
+    #[test]
+    fn test_parse_asan_report() {
+        let output = "=================================================================
+==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x4c3f2e bp 0x7ffd sp 0x7ffc
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x4c3f2e in parse_header /src/parser.c:12:3
+    #1 0x4c3f9a in main /src/main.c:20:5
+    #2 0x7f0a12 in __libc_start_main (/lib/x86_64-linux-gnu/libc.so.6+0x21b96)
+
+0x602000000011 is located 0 bytes to the right of 1-byte region
+allocated by thread T0 here:
+    #0 0x494cad in malloc
+    #1 0x4c3f70 in main /src/main.c:18:9
+";
+        let report = SanitizerReport::parse(output, 8).unwrap();
+        assert_eq!(report.sanitizer, "AddressSanitizer");
+        assert_eq!(report.error_type, "heap-buffer-overflow");
+        assert_eq!(report.address, Some(0x602000000011));
+        assert_eq!(report.frames.len(), 3);
+        assert_eq!(report.frames[0].function.as_deref(), Some("parse_header"));
+        assert_eq!(
+            report.frames[1].location.as_deref(),
+            Some("/src/main.c:20:5")
+        );
+    }
+
+    #[test]
+    fn test_parse_ubsan_report() {
+        let output = "/src/math.c:7:12: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+    #0 0x4c1234 in add /src/math.c:7:12
+SUMMARY: UndefinedBehaviorSanitizer: undefined-behavior /src/math.c:7:12 in
+";
+        let report = SanitizerReport::parse(output, 8).unwrap();
+        assert_eq!(report.sanitizer, "UndefinedBehaviorSanitizer");
+        assert_eq!(report.error_type, "undefined-behavior");
+        assert_eq!(report.frames.len(), 1);
+        assert!(SanitizerReport::parse("all good\n", 8).is_none());
+    }
+}
@@ -1,8 +1,28 @@
 //! The [`StdOutObserver`] and [`StdErrObserver`] observers look at the stdout of a program
 //! The executor must explicitely support these observers.
 //! For example, they are supported on the [`crate::executors::CommandExecutor`].
+//!
+//! Both hash the captured output, so that a [`crate::feedbacks::NewHashFeedback`] keeps only the
+//! runs with a novel output.
 
-use crate::{bolts::tuples::Named, observers::Observer};
+use ahash::AHasher;
+use core::hash::Hasher;
+
+use crate::{
+    bolts::tuples::Named,
+    executors::ExitKind,
+    observers::{Observer, ObserverWithHashField},
+    Error,
+};
+
+/// Hash the output of a run, if captured
+fn hash_output(output: Option<&String>) -> Option<u64> {
+    output.map(|output| {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(output.as_bytes());
+        hasher.finish()
+    })
+}
 
 /// An observer that captures stdout of a target.
 /// Only works for supported executors.
@@ -12,6 +32,7 @@ pub struct StdOutObserver {
     pub name: String,
     /// The stdout of the target during its last execution.
     pub stdout: Option<String>,
+    hash: Option<u64>,
 }
 
 /// An observer that captures stdout of a target.
@@ -19,11 +40,46 @@ impl StdOutObserver {
     /// Create a new [`StdOutObserver`] with the given name.
     #[must_use]
     pub fn new(name: String) -> Self {
-        Self { name, stdout: None }
+        Self {
+            name,
+            stdout: None,
+            hash: None,
+        }
     }
 }
 
-impl<I, S> Observer<I, S> for StdOutObserver {}
+impl<I, S> Observer<I, S> for StdOutObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.stdout = None;
+        self.hash = None;
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.hash = hash_output(self.stdout.as_ref());
+        Ok(())
+    }
+}
+
+impl ObserverWithHashField for StdOutObserver {
+    /// The hash of the captured stdout
+    fn hash(&self) -> &Option<u64> {
+        &self.hash
+    }
+
+    fn update_hash(&mut self, hash: u64) {
+        self.hash = Some(hash);
+    }
+
+    fn clear_hash(&mut self) {
+        self.hash = None;
+    }
+}
 
 impl Named for StdOutObserver {
     fn name(&self) -> &str {
@@ -39,6 +95,7 @@ pub struct StdErrObserver {
     pub name: String,
     /// The stderr of the target during its last execution.
     pub stderr: Option<String>,
+    hash: Option<u64>,
 }
 
 /// An observer that captures stderr of a target.
@@ -46,11 +103,46 @@ impl StdErrObserver {
     /// Create a new [`StdErrObserver`] with the given name.
     #[must_use]
     pub fn new(name: String) -> Self {
-        Self { name, stderr: None }
+        Self {
+            name,
+            stderr: None,
+            hash: None,
+        }
+    }
+}
+
+impl<I, S> Observer<I, S> for StdErrObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.stderr = None;
+        self.hash = None;
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.hash = hash_output(self.stderr.as_ref());
+        Ok(())
     }
 }
 
-impl<I, S> Observer<I, S> for StdErrObserver {}
+impl ObserverWithHashField for StdErrObserver {
+    /// The hash of the captured stderr
+    fn hash(&self) -> &Option<u64> {
+        &self.hash
+    }
+
+    fn update_hash(&mut self, hash: u64) {
+        self.hash = Some(hash);
+    }
+
+    fn clear_hash(&mut self) {
+        self.hash = None;
+    }
+}
 
 impl Named for StdErrObserver {
     fn name(&self) -> &str {
@@ -0,0 +1,111 @@
+//! The [`ValueHashObserver`] collects the hashes of arbitrary values reported by the harness,
+//! for example allocation sizes or the states of a state machine, with [`crate::report_value`].
+//! Together with a [`crate::feedbacks::ValueBloomFeedback`], they become a custom coverage dimension.
+
+use ahash::AHasher;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::hash::{Hash, Hasher};
+use serde::{Deserialize, Serialize};
+
+use crate::{bolts::tuples::Named, executors::ExitKind, observers::Observer, Error};
+
+/// The maximum number of values reported in a single run, the others are dropped
+pub const VALUE_HASHES_MAX: usize = 4096;
+
+/// The hashes of the values reported in the current run
+pub static mut VALUE_HASHES: [u64; VALUE_HASHES_MAX] = [0; VALUE_HASHES_MAX];
+/// The number of hashes in [`VALUE_HASHES`]
+pub static mut VALUE_HASHES_LEN: usize = 0;
+
+/// Hash a reported value, with fixed keys to get the same hashes across runs
+#[must_use]
+pub fn hash_value<T>(value: &T) -> u64
+where
+    T: Hash + ?Sized,
+{
+    let mut hasher = AHasher::new_with_keys(0, 0);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Report the hash of a value in the current run, see [`crate::report_value`]
+#[inline]
+pub fn report_value_hash(hash: u64) {
+    unsafe {
+        if VALUE_HASHES_LEN < VALUE_HASHES_MAX {
+            VALUE_HASHES[VALUE_HASHES_LEN] = hash;
+            VALUE_HASHES_LEN += 1;
+        }
+    }
+}
+
+/// Report a value from the harness to the [`crate::observers::ValueHashObserver`].
+/// The value must implement [`core::hash::Hash`].
+/// An optional tag keeps equal values of different dimensions apart, e.g.
+/// `report_value!("alloc_size", size)` and `report_value!("state", state)`.
+#[macro_export]
+macro_rules! report_value {
+    ($value:expr) => {
+        $crate::observers::report_value_hash($crate::observers::hash_value(&$value))
+    };
+    ($tag:expr, $value:expr) => {
+        $crate::observers::report_value_hash($crate::observers::hash_value(&($tag, $value)))
+    };
+}
+
+/// An observer collecting the hashes of the values reported with [`crate::report_value`].
+/// The reported values are global, use a single [`ValueHashObserver`] per fuzzer.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ValueHashObserver {
+    name: String,
+    hashes: Vec<u64>,
+}
+
+impl ValueHashObserver {
+    /// Creates a new [`ValueHashObserver`] with the given name.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            hashes: vec![],
+        }
+    }
+
+    /// The hashes reported in the last run
+    #[must_use]
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+}
+
+impl<I, S> Observer<I, S> for ValueHashObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.hashes.clear();
+        unsafe {
+            VALUE_HASHES_LEN = 0;
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        unsafe {
+            self.hashes
+                .extend_from_slice(&VALUE_HASHES[..VALUE_HASHES_LEN]);
+        }
+        Ok(())
+    }
+}
+
+impl Named for ValueHashObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -76,7 +76,10 @@ pub struct TopAccountingMetadata {
     pub max_accounting: Vec<u32>,
 }
 
-crate::impl_serdeany!(TopAccountingMetadata);
+crate::impl_serdeany!(
+    TopAccountingMetadata,
+    "libafl::schedulers::accounting::TopAccountingMetadata"
+);
 
 impl TopAccountingMetadata {
     /// Creates a new [`struct@TopAccountingMetadata`]
@@ -0,0 +1,250 @@
+//! The [`AflScheduler`] walks the corpus like AFL: it culls the corpus to the favored testcases,
+//! the top rated for some map entry, and mostly skips the others, counting the queue cycles.
+
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{rands::Rand, serdeany::SerdeAny, AsSlice, HasRefCnt},
+    corpus::{Corpus, Testcase},
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    schedulers::{
+        minimizer::{IsFavoredMetadata, TopRatedsMetadata},
+        FavFactor, LenTimeMulFavFactor, MinimizerScheduler, QueueScheduler, Scheduler,
+    },
+    state::{HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// The probability to skip a testcase, if favored testcases are still pending, as in AFL
+const SKIP_TO_NEW_PROB: u64 = 99;
+/// The probability to skip a non-favored testcase that was not fuzzed yet, as in AFL
+const SKIP_NFAV_NEW_PROB: u64 = 75;
+/// The probability to skip a non-favored testcase that was fuzzed already, as in AFL
+const SKIP_NFAV_OLD_PROB: u64 = 95;
+/// Below this size, AFL fuzzes all the corpus
+const SKIP_MIN_CORPUS_SIZE: usize = 10;
+
+/// A state metadata holding the progress of the [`AflScheduler`] through the queue
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AflSchedulerMetadata {
+    /// The number of complete cycles through the queue
+    pub queue_cycles: u64,
+    /// The last scheduled testcase
+    pub last_idx: Option<usize>,
+    /// The number of favored testcases not fuzzed yet
+    pub pending_favored: usize,
+}
+
+crate::impl_serdeany!(
+    AflSchedulerMetadata,
+    "libafl::schedulers::afl::AflSchedulerMetadata"
+);
+
+impl AflSchedulerMetadata {
+    /// Creates a new [`struct@AflSchedulerMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A scheduler mirroring the queue of AFL: the favored testcases, see [`MinimizerScheduler`],
+/// are always fuzzed, the others are skipped with high probability, more so while favored
+/// testcases are pending and once they were fuzzed before.
+#[derive(Debug, Clone)]
+pub struct AflScheduler<CS, F, I, M, S>
+where
+    CS: Scheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    M: AsSlice<usize> + SerdeAny + HasRefCnt,
+    S: HasCorpus<I> + HasMetadata,
+{
+    minimizer: MinimizerScheduler<CS, F, I, M, S>,
+    phantom: PhantomData<(F, I, M, S)>,
+}
+
+impl<CS, F, I, M, S> Scheduler<I, S> for AflScheduler<CS, F, I, M, S>
+where
+    CS: Scheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    M: AsSlice<usize> + SerdeAny + HasRefCnt,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        self.minimizer.on_add(state, idx)
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.minimizer.on_replace(state, idx, testcase)
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.minimizer.on_remove(state, idx, testcase)
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        if !state.has_metadata::<AflSchedulerMetadata>() {
+            state.add_metadata(AflSchedulerMetadata::new());
+        }
+
+        let culling = state
+            .metadata()
+            .get::<TopRatedsMetadata>()
+            .map_or(false, |top_rated| top_rated.changed);
+
+        // The testcase scheduled before was fuzzed in the meantime
+        if let Some(last_idx) = *state.corpus().current() {
+            if last_idx < state.corpus().count() {
+                let was_pending = {
+                    let mut entry = state.corpus().get(last_idx)?.borrow_mut();
+                    let was_pending = !entry.fuzzed() && entry.has_metadata::<IsFavoredMetadata>();
+                    entry.set_fuzzed(true);
+                    was_pending
+                };
+                if was_pending {
+                    let meta = state
+                        .metadata_mut()
+                        .get_mut::<AflSchedulerMetadata>()
+                        .unwrap();
+                    meta.pending_favored = meta.pending_favored.saturating_sub(1);
+                }
+            }
+        }
+
+        if culling {
+            self.minimizer.cull(state)?;
+            self.update_pending_favored(state)?;
+        }
+
+        loop {
+            let idx = self.minimizer.base().next(state)?;
+            let meta = state
+                .metadata_mut()
+                .get_mut::<AflSchedulerMetadata>()
+                .unwrap();
+            if meta.last_idx.map_or(false, |last_idx| idx <= last_idx) {
+                meta.queue_cycles += 1;
+            }
+            meta.last_idx = Some(idx);
+            let (queue_cycles, pending_favored) = (meta.queue_cycles, meta.pending_favored);
+
+            let (favored, fuzzed) = {
+                let entry = state.corpus().get(idx)?.borrow();
+                (entry.has_metadata::<IsFavoredMetadata>(), entry.fuzzed())
+            };
+            let skip_prob = if pending_favored > 0 {
+                if fuzzed || !favored {
+                    SKIP_TO_NEW_PROB
+                } else {
+                    0
+                }
+            } else if !favored && state.corpus().count() > SKIP_MIN_CORPUS_SIZE {
+                if queue_cycles > 0 && !fuzzed {
+                    SKIP_NFAV_NEW_PROB
+                } else {
+                    SKIP_NFAV_OLD_PROB
+                }
+            } else {
+                0
+            };
+
+            if skip_prob == 0 || state.rand_mut().below(100) >= skip_prob {
+                return Ok(idx);
+            }
+        }
+    }
+}
+
+impl<CS, F, I, M, S> AflScheduler<CS, F, I, M, S>
+where
+    CS: Scheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    M: AsSlice<usize> + SerdeAny + HasRefCnt,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    /// Creates a new [`AflScheduler`] walking the corpus with the `base` [`Scheduler`]
+    pub fn new(base: CS) -> Self {
+        Self {
+            // The skipping is done here
+            minimizer: MinimizerScheduler::with_skip_prob(base, 0),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of complete cycles through the queue so far
+    #[must_use]
+    pub fn queue_cycles(state: &S) -> u64 {
+        state
+            .metadata()
+            .get::<AflSchedulerMetadata>()
+            .map_or(0, |meta| meta.queue_cycles)
+    }
+
+    /// Count the favored testcases that were not fuzzed yet
+    #[allow(clippy::unused_self)]
+    fn update_pending_favored(&self, state: &mut S) -> Result<(), Error> {
+        let mut pending_favored = 0;
+        for idx in 0..state.corpus().count() {
+            let entry = state.corpus().get(idx)?.borrow();
+            if entry.has_metadata::<IsFavoredMetadata>() && !entry.fuzzed() {
+                pending_favored += 1;
+            }
+        }
+        state
+            .metadata_mut()
+            .get_mut::<AflSchedulerMetadata>()
+            .unwrap()
+            .pending_favored = pending_favored;
+        Ok(())
+    }
+}
+
+/// An [`AflScheduler`] walking the corpus as a queue, with the favored testcases exercising
+/// all the entries registered in the [`MapIndexesMetadata`] with the [`LenTimeMulFavFactor`].
+pub type StdAflScheduler<I, S> =
+    AflScheduler<QueueScheduler, LenTimeMulFavFactor<I>, I, MapIndexesMetadata, S>;
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::MapIndexesMetadata,
+        inputs::BytesInput,
+        schedulers::{QueueScheduler, Scheduler, StdAflScheduler},
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_afl_scheduler_cycles() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for idx in 0..3 {
+            let mut testcase = Testcase::new(BytesInput::new(vec![0; 4]));
+            testcase.add_metadata(MapIndexesMetadata::new(vec![idx]));
+            corpus.add(testcase).unwrap();
+        }
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+
+        let scheduler = StdAflScheduler::new(QueueScheduler::new());
+        for idx in 0..3 {
+            scheduler.on_add(&mut state, idx).unwrap();
+        }
+
+        // Each testcase is the favored one for its entry, none is skipped
+        for expected in [0, 1, 2, 0, 1, 2] {
+            assert_eq!(scheduler.next(&mut state).unwrap(), expected);
+        }
+        assert_eq!(StdAflScheduler::<BytesInput, _>::queue_cycles(&state), 1);
+    }
+}
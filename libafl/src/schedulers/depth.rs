@@ -0,0 +1,187 @@
+//! Depth-aware scheduling: favor the testcases many mutations away from the seeds.
+//!
+//! The depth is the one the power schedules track in the [`PowerScheduleTestcaseMetaData`],
+//! attached by the [`crate::schedulers::PowerQueueScheduler`]. The [`DepthBoostScheduler`]
+//! favors the deep testcases, to push further down the mutation tree, and reports the
+//! maximum depth to the monitor as `max depth`.
+
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::{Corpus, PowerScheduleTestcaseMetaData, Testcase},
+    inputs::Input,
+    schedulers::Scheduler,
+    state::{HasCorpus, HasMetadata, HasRand, HasUserStats},
+    Error,
+};
+
+/// The default probability, in percent, to draw again if the drawn testcase is shallow
+pub const DEFAULT_SHALLOW_REDRAW_PROB: u64 = 50;
+
+/// The depth of a testcase in its [`PowerScheduleTestcaseMetaData`], 0 if it has none
+fn depth_of<I>(testcase: &Testcase<I>) -> u64
+where
+    I: Input,
+{
+    testcase
+        .metadata()
+        .get::<PowerScheduleTestcaseMetaData>()
+        .map_or(0, PowerScheduleTestcaseMetaData::depth)
+}
+
+/// The deepest testcase added to the corpus so far
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct MaxDepthMetadata {
+    /// The maximum depth
+    pub max_depth: u64,
+}
+
+crate::impl_serdeany!(
+    MaxDepthMetadata,
+    "libafl::schedulers::depth::MaxDepthMetadata"
+);
+
+/// A scheduler wrapping a `base` scheduler, drawing again with some probability
+/// if the testcase it picked is in the shallower half of the corpus.
+/// The `base` scheduler must track the depth, e.g. a [`crate::schedulers::PowerQueueScheduler`].
+#[derive(Debug, Clone)]
+pub struct DepthBoostScheduler<CS, I, S>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand + HasUserStats,
+{
+    base: CS,
+    redraw_prob: u64,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<CS, I, S> Scheduler<I, S> for DepthBoostScheduler<CS, I, S>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand + HasUserStats,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        self.base.on_add(state, idx)?;
+        let depth = depth_of(&state.corpus().get(idx)?.borrow());
+        if !state.has_metadata::<MaxDepthMetadata>() {
+            state.add_metadata(MaxDepthMetadata::default());
+        }
+        let meta = state.metadata_mut().get_mut::<MaxDepthMetadata>().unwrap();
+        if depth > meta.max_depth {
+            meta.max_depth = depth;
+            if let Some(stats) = state.user_stats_mut() {
+                stats.set_gauge("max depth", depth as f64);
+            }
+        }
+        Ok(())
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, idx, testcase)
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let max_depth = state
+            .metadata()
+            .get::<MaxDepthMetadata>()
+            .map_or(0, |meta| meta.max_depth);
+        let mut idx = self.base.next(state)?;
+        let depth = depth_of(&state.corpus().get(idx)?.borrow());
+        if depth * 2 < max_depth && state.rand_mut().below(100) < self.redraw_prob {
+            idx = self.base.next(state)?;
+        }
+        Ok(idx)
+    }
+}
+
+impl<CS, I, S> DepthBoostScheduler<CS, I, S>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand + HasUserStats,
+{
+    /// Creates a new [`DepthBoostScheduler`] wrapping `base`, drawing again with a probability
+    /// of [`DEFAULT_SHALLOW_REDRAW_PROB`] for the shallow testcases
+    pub fn new(base: CS) -> Self {
+        Self::with_redraw_prob(base, DEFAULT_SHALLOW_REDRAW_PROB)
+    }
+
+    /// Creates a new [`DepthBoostScheduler`] wrapping `base`, drawing again with a probability
+    /// of `redraw_prob` percent for the shallow testcases
+    pub fn with_redraw_prob(base: CS, redraw_prob: u64) -> Self {
+        Self {
+            base,
+            redraw_prob,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        monitors::UserStatValue,
+        schedulers::{
+            depth::{depth_of, DepthBoostScheduler, MaxDepthMetadata},
+            PowerQueueScheduler, Scheduler,
+        },
+        state::{HasCorpus, HasMetadata, HasUserStats, StdState},
+    };
+
+    #[test]
+    fn test_depth_boost() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let scheduler = DepthBoostScheduler::new(PowerQueueScheduler::new());
+
+        let seed = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0])))
+            .unwrap();
+        scheduler.on_add(&mut state, seed).unwrap();
+
+        *state.corpus_mut().current_mut() = Some(seed);
+        let child = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![1])))
+            .unwrap();
+        scheduler.on_add(&mut state, child).unwrap();
+
+        let seed_depth = depth_of(&state.corpus().get(seed).unwrap().borrow());
+        let child_depth = depth_of(&state.corpus().get(child).unwrap().borrow());
+        assert_eq!(child_depth, seed_depth + 1);
+        assert_eq!(
+            state
+                .metadata()
+                .get::<MaxDepthMetadata>()
+                .unwrap()
+                .max_depth,
+            child_depth
+        );
+        assert!(matches!(
+            state.user_stats().unwrap().get("max depth"),
+            Some(UserStatValue::Gauge(_))
+        ));
+    }
+}
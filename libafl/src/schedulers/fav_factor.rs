@@ -1,6 +1,9 @@
 //! The `FavFactor` is an evaluator providing scores of corpus items.
 
-use crate::{bolts::HasLen, corpus::Testcase, inputs::Input, Error};
+use crate::{
+    bolts::HasLen, corpus::Testcase, feedbacks::InterestingnessMetadata, inputs::Input,
+    state::HasMetadata, Error,
+};
 
 use core::marker::PhantomData;
 
@@ -32,3 +35,29 @@ where
         Ok(entry.exec_time().map_or(1, |d| d.as_millis()) as u64 * entry.cached_len()? as u64)
     }
 }
+
+/// Divide the [`LenTimeMulFavFactor`] by the interestingness score of the testcase plus one,
+/// see [`crate::feedbacks::WeightedFeedback`].
+/// This favors small and quick testcases which triggered many feedbacks.
+#[derive(Debug, Clone)]
+pub struct InterestingnessFavFactor<I>
+where
+    I: Input + HasLen,
+{
+    phantom: PhantomData<I>,
+}
+
+impl<I> FavFactor<I> for InterestingnessFavFactor<I>
+where
+    I: Input + HasLen,
+{
+    #[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    fn compute(entry: &mut Testcase<I>) -> Result<u64, Error> {
+        let score = entry
+            .metadata()
+            .get::<InterestingnessMetadata>()
+            .map_or(0.0, |meta| meta.score.max(0.0));
+        let factor = LenTimeMulFavFactor::compute(entry)?;
+        Ok((factor as f64 / (1.0 + score)) as u64)
+    }
+}
@@ -29,9 +29,15 @@ crate::impl_serdeany!(IsFavoredMetadata);
 pub struct TopRatedsMetadata {
     /// map index -> corpus index
     pub map: HashMap<usize, usize>,
+    /// If the map changed since the corpus was last culled
+    #[serde(default)]
+    pub changed: bool,
 }
 
-crate::impl_serdeany!(TopRatedsMetadata);
+crate::impl_serdeany!(
+    TopRatedsMetadata,
+    "libafl::schedulers::minimizer::TopRatedsMetadata"
+);
 
 impl TopRatedsMetadata {
     /// Creates a new [`struct@TopRatedsMetadata`]
@@ -39,6 +45,7 @@ impl TopRatedsMetadata {
     pub fn new() -> Self {
         Self {
             map: HashMap::default(),
+            changed: false,
         }
     }
 }
@@ -188,25 +195,35 @@ where
             return Ok(());
         }
 
+        let top_rated = state.metadata_mut().get_mut::<TopRatedsMetadata>().unwrap();
         for elem in new_favoreds {
-            state
-                .metadata_mut()
-                .get_mut::<TopRatedsMetadata>()
-                .unwrap()
-                .map
-                .insert(elem, idx);
+            top_rated.map.insert(elem, idx);
         }
+        top_rated.changed = true;
         Ok(())
     }
 
-    /// Cull the `Corpus` using the `MinimizerScheduler`
+    /// Cull the `Corpus` using the `MinimizerScheduler`, like AFL does: only once the top rated
+    /// changed, the favored flag of the testcases no longer needed is cleared
     #[allow(clippy::unused_self)]
     pub fn cull(&self, state: &mut S) -> Result<(), Error> {
-        let top_rated = match state.metadata().get::<TopRatedsMetadata>() {
-            None => return Ok(()),
-            Some(val) => val,
-        };
+        match state.metadata_mut().get_mut::<TopRatedsMetadata>() {
+            Some(top_rated) if top_rated.changed => top_rated.changed = false,
+            _ => return Ok(()),
+        }
+
+        for idx in 0..state.corpus().count() {
+            drop(
+                state
+                    .corpus()
+                    .get(idx)?
+                    .borrow_mut()
+                    .metadata_mut()
+                    .remove::<IsFavoredMetadata>(),
+            );
+        }
 
+        let top_rated = state.metadata().get::<TopRatedsMetadata>().unwrap();
         let mut acc = HashSet::new();
 
         for (key, idx) in &top_rated.map {
@@ -10,7 +10,7 @@ pub mod accounting;
 pub use accounting::CoverageAccountingScheduler;
 
 pub mod fav_factor;
-pub use fav_factor::{FavFactor, LenTimeMulFavFactor};
+pub use fav_factor::{FavFactor, InterestingnessFavFactor, LenTimeMulFavFactor};
 
 pub mod minimizer;
 pub use minimizer::{
@@ -20,6 +20,30 @@ pub use minimizer::{
 pub mod powersched;
 pub use powersched::PowerQueueScheduler;
 
+#[cfg(feature = "std")]
+pub mod proximity;
+#[cfg(feature = "std")]
+pub use proximity::{CrashProximityMetadata, CrashProximityScheduler};
+
+pub mod afl;
+pub use afl::{AflScheduler, AflSchedulerMetadata, StdAflScheduler};
+
+pub mod depth;
+pub use depth::{DepthBoostScheduler, MaxDepthMetadata};
+
+pub mod partition;
+pub use partition::{PartitionScheduler, PartitionSchedulerMetadata};
+
+pub mod weighted;
+pub use weighted::{
+    std_weighted_score, StdWeightedScheduler, WeightedScheduleMetadata, WeightedScheduler,
+};
+
+pub mod replay;
+pub use replay::{
+    RecordingScheduler, ReplayEntry, ReplayLogMetadata, ReplayScheduler, DEFAULT_MAX_REPLAY_ENTRIES,
+};
+
 use alloc::borrow::ToOwned;
 
 use crate::{
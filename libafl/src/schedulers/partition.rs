@@ -0,0 +1,192 @@
+//! The [`PartitionScheduler`] interleaves between the partitions of a [`MultiCorpus`].
+//!
+//! Each call picks a non-empty partition at random, proportionally to its weight,
+//! then walks that partition like a queue.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::{Corpus, MultiCorpus},
+    inputs::Input,
+    schedulers::Scheduler,
+    state::{HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// The weight of the partitions without an explicit weight
+pub const DEFAULT_PARTITION_WEIGHT: u64 = 1;
+
+/// A state metadata holding the queue position of the [`PartitionScheduler`] in each partition
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartitionSchedulerMetadata {
+    /// The index, in its partition, of the next entry to schedule, for each partition
+    pub cursors: Vec<usize>,
+}
+
+crate::impl_serdeany!(
+    PartitionSchedulerMetadata,
+    "libafl::schedulers::partition::PartitionSchedulerMetadata"
+);
+
+/// Schedule the entries of a [`MultiCorpus`], picking a partition by weight,
+/// then the next entry of that partition in queue order
+#[derive(Debug, Clone)]
+pub struct PartitionScheduler<C, I, S>
+where
+    C: Corpus<I>,
+    I: Input,
+    S: HasCorpus<I, Corpus = MultiCorpus<C, I>> + HasMetadata + HasRand,
+{
+    weights: Vec<(String, u64)>,
+    phantom: PhantomData<(C, I, S)>,
+}
+
+impl<C, I, S> Scheduler<I, S> for PartitionScheduler<C, I, S>
+where
+    C: Corpus<I>,
+    I: Input,
+    S: HasCorpus<I, Corpus = MultiCorpus<C, I>> + HasMetadata + HasRand,
+{
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        if state.corpus().count() == 0 {
+            return Err(Error::Empty("No entries in corpus".to_owned()));
+        }
+
+        let corpus = state.corpus();
+        let weights: Vec<u64> = (0..corpus.partitions_count())
+            .map(|partition| {
+                if corpus.partition(partition).unwrap().count() == 0 {
+                    0
+                } else {
+                    self.weight(&corpus.names()[partition])
+                }
+            })
+            .collect();
+        let total: u64 = weights.iter().sum();
+        if total == 0 {
+            return Err(Error::Empty(
+                "No entries in the weighted corpus partitions".to_owned(),
+            ));
+        }
+
+        let mut pick = state.rand_mut().below(total);
+        let partition = weights
+            .iter()
+            .position(|weight| {
+                if pick < *weight {
+                    true
+                } else {
+                    pick -= weight;
+                    false
+                }
+            })
+            .unwrap();
+
+        if !state.has_metadata::<PartitionSchedulerMetadata>() {
+            state.add_metadata(PartitionSchedulerMetadata::default());
+        }
+        let partition_count = state.corpus().partition(partition).unwrap().count();
+        let meta = state
+            .metadata_mut()
+            .get_mut::<PartitionSchedulerMetadata>()
+            .unwrap();
+        if meta.cursors.len() < weights.len() {
+            meta.cursors.resize(weights.len(), 0);
+        }
+        // Entries may have been removed since the last pick
+        let nth = meta.cursors[partition] % partition_count;
+        meta.cursors[partition] = nth + 1;
+
+        let id = state
+            .corpus()
+            .nth_in_partition(partition, nth)
+            .ok_or_else(|| Error::KeyNotFound(format!("No entry {} in partition", nth)))?;
+        *state.corpus_mut().current_mut() = Some(id);
+        Ok(id)
+    }
+}
+
+impl<C, I, S> PartitionScheduler<C, I, S>
+where
+    C: Corpus<I>,
+    I: Input,
+    S: HasCorpus<I, Corpus = MultiCorpus<C, I>> + HasMetadata + HasRand,
+{
+    /// Creates a new [`PartitionScheduler`], giving all the partitions the same weight
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            weights: vec![],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the weight of the partition named `name`, 0 to never schedule it
+    #[must_use]
+    pub fn with_weight(mut self, name: &str, weight: u64) -> Self {
+        self.weights.retain(|(n, _)| n != name);
+        self.weights.push((name.to_string(), weight));
+        self
+    }
+
+    /// The weight of the partition named `name`
+    #[must_use]
+    pub fn weight(&self, name: &str) -> u64 {
+        self.weights
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(DEFAULT_PARTITION_WEIGHT, |(_, weight)| *weight)
+    }
+}
+
+impl<C, I, S> Default for PartitionScheduler<C, I, S>
+where
+    C: Corpus<I>,
+    I: Input,
+    S: HasCorpus<I, Corpus = MultiCorpus<C, I>> + HasMetadata + HasRand,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, CorpusPartitionMetadata, InMemoryCorpus, MultiCorpus, Testcase},
+        inputs::BytesInput,
+        schedulers::{PartitionScheduler, Scheduler},
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_partition_scheduler() {
+        let mut corpus = MultiCorpus::new("coverage", InMemoryCorpus::<BytesInput>::new())
+            .with_partition("crashes", InMemoryCorpus::new())
+            .unwrap();
+        corpus.add(Testcase::new(BytesInput::new(vec![0]))).unwrap();
+        for byte in [1, 2] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![byte]));
+            testcase.add_metadata(CorpusPartitionMetadata::new("crashes"));
+            corpus.add(testcase).unwrap();
+        }
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+
+        // Only the crashes are scheduled, in queue order
+        let scheduler = PartitionScheduler::new().with_weight("coverage", 0);
+        for expected in [1, 2, 1, 2] {
+            assert_eq!(scheduler.next(&mut state).unwrap(), expected);
+        }
+
+        let scheduler = PartitionScheduler::new().with_weight("crashes", 0);
+        assert_eq!(scheduler.next(&mut state).unwrap(), 0);
+    }
+}
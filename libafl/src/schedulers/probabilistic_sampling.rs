@@ -34,7 +34,10 @@ pub struct ProbabilityMetadata {
     pub total_probability: f64,
 }
 
-crate::impl_serdeany!(ProbabilityMetadata);
+crate::impl_serdeany!(
+    ProbabilityMetadata,
+    "libafl::schedulers::probabilistic_sampling::ProbabilityMetadata"
+);
 
 impl ProbabilityMetadata {
     /// Creates a new [`struct@ProbabilityMetadata`]
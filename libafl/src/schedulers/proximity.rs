@@ -0,0 +1,457 @@
+//! The crash proximity scheduler is a hybrid corpus scheduler that combines the rarity of the covered
+//! map entries with the distance, in the call graph, from code that already crashed.
+//! This biases the exploration toward code already known to be fragile.
+//! The crash sites are the crashing pcs found in the [`SanitizerReport`] of the solutions,
+//! mapped to map indexes with the pc table.
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    string::String,
+    vec::Vec,
+};
+use core::marker::PhantomData;
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{rands::Rand, AsSlice},
+    corpus::{Corpus, Testcase},
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    observers::SanitizerReport,
+    schedulers::Scheduler,
+    state::{HasCorpus, HasMetadata, HasRand, HasSolutions},
+    Error,
+};
+
+/// The distance assigned to map entries that cannot reach any crash site
+pub const UNREACHABLE_DISTANCE: u32 = u32::MAX;
+
+/// The default boost given to an entry covering a crash site (distance 0)
+pub const DEFAULT_PROXIMITY_BOOST: f64 = 8.0;
+
+/// A state metadata holding the call graph, the crash sites and the derived distances
+/// used by the [`CrashProximityScheduler`].
+/// The nodes of the graph are indexes in the coverage map, usually obtained from the pc tables.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrashProximityMetadata {
+    /// The start pc of each block with its map index, sorted by pc
+    blocks: Vec<(u64, usize)>,
+    /// For each map index, the map indexes calling into it
+    callers: Vec<Vec<usize>>,
+    /// The map indexes in which a crash happened
+    crash_sites: HashSet<usize>,
+    /// For each map index, the distance to the nearest crash site
+    distances: Vec<u32>,
+    /// For each map index, how many corpus entries cover it
+    hits: Vec<u64>,
+    /// corpus index -> score, in index order so that the sampling is deterministic
+    scores: BTreeMap<usize, f64>,
+    /// The sum of all the scores
+    total_score: f64,
+    /// How many solutions were inspected for crash sites so far
+    solutions_seen: usize,
+    /// If the scores must be recomputed
+    changed: bool,
+}
+
+crate::impl_serdeany!(
+    CrashProximityMetadata,
+    "libafl::schedulers::proximity::CrashProximityMetadata"
+);
+
+impl CrashProximityMetadata {
+    /// Creates a new [`struct@CrashProximityMetadata`] given the start pc of the block of each map index,
+    /// as in the pc table, and the edges `(caller, callee)` of the call graph between map indexes.
+    #[must_use]
+    pub fn new(pcs: &[u64], call_edges: &[(usize, usize)]) -> Self {
+        let map_len = pcs.len();
+        let mut blocks: Vec<(u64, usize)> = pcs.iter().copied().zip(0..).collect();
+        blocks.sort_unstable();
+        let mut callers = vec![vec![]; map_len];
+        for &(caller, callee) in call_edges {
+            if caller < map_len && callee < map_len {
+                callers[callee].push(caller);
+            }
+        }
+        Self {
+            blocks,
+            callers,
+            crash_sites: HashSet::default(),
+            distances: vec![UNREACHABLE_DISTANCE; map_len],
+            hits: vec![0; map_len],
+            scores: BTreeMap::new(),
+            total_score: 0.0,
+            solutions_seen: 0,
+            changed: false,
+        }
+    }
+
+    /// Register a new crash site, returns `true` if it was not known yet
+    pub fn add_crash_site(&mut self, idx: usize) -> bool {
+        if idx >= self.distances.len() || !self.crash_sites.insert(idx) {
+            return false;
+        }
+        self.compute_distances();
+        self.changed = true;
+        true
+    }
+
+    /// The map index of the block containing `pc`, if it lies in the instrumented code
+    #[must_use]
+    pub fn map_index_of(&self, pc: u64) -> Option<usize> {
+        let last = self.blocks.last()?.0;
+        if pc > last {
+            // Past the start of the last block, most likely in the sanitizer runtime or a library
+            return None;
+        }
+        match self.blocks.binary_search_by_key(&pc, |&(start, _)| start) {
+            Ok(pos) => Some(self.blocks[pos].1),
+            Err(0) => None,
+            Err(pos) => Some(self.blocks[pos - 1].1),
+        }
+    }
+
+    /// Register the crash site of a [`SanitizerReport`], the innermost frame in the instrumented code.
+    /// Returns `true` if it was not known yet.
+    pub fn add_crash_report(&mut self, report: &SanitizerReport) -> bool {
+        match report
+            .frames
+            .iter()
+            .find_map(|frame| self.map_index_of(frame.pc))
+        {
+            Some(idx) => self.add_crash_site(idx),
+            None => false,
+        }
+    }
+
+    /// The known crash sites
+    #[must_use]
+    pub fn crash_sites(&self) -> &HashSet<usize> {
+        &self.crash_sites
+    }
+
+    /// The distance of the given map index to the nearest crash site
+    #[must_use]
+    pub fn distance(&self, idx: usize) -> u32 {
+        self.distances
+            .get(idx)
+            .copied()
+            .unwrap_or(UNREACHABLE_DISTANCE)
+    }
+
+    /// Recompute the distances with a reverse BFS starting from all the crash sites
+    fn compute_distances(&mut self) {
+        self.distances
+            .iter_mut()
+            .for_each(|d| *d = UNREACHABLE_DISTANCE);
+        let mut queue = VecDeque::new();
+        for &site in &self.crash_sites {
+            self.distances[site] = 0;
+            queue.push_back(site);
+        }
+        while let Some(node) = queue.pop_front() {
+            let next = self.distances[node] + 1;
+            for &caller in &self.callers[node] {
+                if self.distances[caller] > next {
+                    self.distances[caller] = next;
+                    queue.push_back(caller);
+                }
+            }
+        }
+    }
+
+    /// Compute the score of an entry covering the given map indexes
+    #[allow(clippy::cast_precision_loss)]
+    fn score(&self, indexes: &[usize], boost: f64) -> f64 {
+        let mut rarity = 0.0;
+        let mut min_distance = UNREACHABLE_DISTANCE;
+        for &idx in indexes {
+            if idx >= self.hits.len() {
+                continue;
+            }
+            rarity += 1.0 / (self.hits[idx].max(1) as f64);
+            min_distance = min_distance.min(self.distances[idx]);
+        }
+        let proximity = if min_distance == UNREACHABLE_DISTANCE {
+            1.0
+        } else {
+            1.0 + boost / f64::from(1 + min_distance)
+        };
+        // Never let an entry starve completely
+        (rarity * proximity).max(f64::EPSILON)
+    }
+}
+
+/// A corpus scheduler sampling entries proportionally to the rarity of the map entries they cover,
+/// boosted for entries whose execution path passes near previously-crashing code.
+/// Entries must have a [`MapIndexesMetadata`], i.e. the map feedback must track indexes.
+/// The crash sites are collected from the [`SanitizerReport`] of new solutions, if any,
+/// or can be registered manually with [`CrashProximityMetadata::add_crash_site`].
+#[derive(Debug, Clone)]
+pub struct CrashProximityScheduler<I, S>
+where
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata + HasRand,
+{
+    boost: f64,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, S> CrashProximityScheduler<I, S>
+where
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata + HasRand,
+{
+    /// Creates a new [`CrashProximityScheduler`], adding a [`struct@CrashProximityMetadata`]
+    /// built from the pc table and the call graph edges `(caller, callee)` to the state.
+    pub fn new(state: &mut S, pcs: &[u64], call_edges: &[(usize, usize)]) -> Self {
+        Self::with_boost(state, pcs, call_edges, DEFAULT_PROXIMITY_BOOST)
+    }
+
+    /// Creates a new [`CrashProximityScheduler`] with a non-default proximity `boost`.
+    pub fn with_boost(
+        state: &mut S,
+        pcs: &[u64],
+        call_edges: &[(usize, usize)],
+        boost: f64,
+    ) -> Self {
+        if !state.has_metadata::<CrashProximityMetadata>() {
+            state.add_metadata(CrashProximityMetadata::new(pcs, call_edges));
+        }
+        Self {
+            boost,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Look for crash sites in the solutions added since the last call
+    #[allow(clippy::unused_self)]
+    fn collect_crash_sites(&self, state: &mut S) -> Result<(), Error> {
+        let seen = Self::meta(state)?.solutions_seen;
+        let count = state.solutions().count();
+        let mut reports = vec![];
+        for idx in seen..count {
+            let solution = state.solutions().get(idx)?.borrow();
+            if let Some(report) = solution.metadata().get::<SanitizerReport>() {
+                reports.push(report.clone());
+            }
+        }
+        let meta = Self::meta_mut(state)?;
+        meta.solutions_seen = count;
+        for report in &reports {
+            meta.add_crash_report(report);
+        }
+        Ok(())
+    }
+
+    /// Recompute the score of all the entries in the corpus
+    fn recompute_scores(&self, state: &mut S) -> Result<(), Error> {
+        let mut scores = BTreeMap::new();
+        let mut total = 0.0;
+        {
+            let meta = Self::meta(state)?;
+            for idx in 0..state.corpus().count() {
+                let entry = state.corpus().get(idx)?.borrow();
+                let score = entry
+                    .metadata()
+                    .get::<MapIndexesMetadata>()
+                    .map_or(f64::EPSILON, |m| meta.score(m.as_slice(), self.boost));
+                total += score;
+                scores.insert(idx, score);
+            }
+        }
+        let meta = Self::meta_mut(state)?;
+        meta.scores = scores;
+        meta.total_score = total;
+        meta.changed = false;
+        Ok(())
+    }
+
+    fn meta(state: &S) -> Result<&CrashProximityMetadata, Error> {
+        state
+            .metadata()
+            .get::<CrashProximityMetadata>()
+            .ok_or_else(|| Error::KeyNotFound(String::from("CrashProximityMetadata not found")))
+    }
+
+    fn meta_mut(state: &mut S) -> Result<&mut CrashProximityMetadata, Error> {
+        state
+            .metadata_mut()
+            .get_mut::<CrashProximityMetadata>()
+            .ok_or_else(|| Error::KeyNotFound(String::from("CrashProximityMetadata not found")))
+    }
+}
+
+impl<I, S> Scheduler<I, S> for CrashProximityScheduler<I, S>
+where
+    I: Input,
+    S: HasCorpus<I> + HasSolutions<I> + HasMetadata + HasRand,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        let indexes = state
+            .corpus()
+            .get(idx)?
+            .borrow()
+            .metadata()
+            .get::<MapIndexesMetadata>()
+            .map(|m| m.as_slice().to_vec());
+        let meta = Self::meta_mut(state)?;
+        if let Some(indexes) = indexes {
+            for idx in indexes {
+                if idx < meta.hits.len() {
+                    meta.hits[idx] += 1;
+                }
+            }
+        }
+        // The rarity of other entries changed as well
+        meta.changed = true;
+        Ok(())
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        _idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        let meta = Self::meta_mut(state)?;
+        if let Some(indexes) = testcase
+            .as_ref()
+            .and_then(|t| t.metadata().get::<MapIndexesMetadata>())
+        {
+            for &idx in indexes.as_slice() {
+                if idx < meta.hits.len() {
+                    meta.hits[idx] = meta.hits[idx].saturating_sub(1);
+                }
+            }
+        }
+        meta.changed = true;
+        Ok(())
+    }
+
+    /// Gets the next entry, sampled proportionally to its score
+    #[allow(clippy::cast_precision_loss)]
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        if state.corpus().count() == 0 {
+            return Err(Error::Empty(String::from("No entries in corpus")));
+        }
+        self.collect_crash_sites(state)?;
+        if Self::meta(state)?.changed {
+            self.recompute_scores(state)?;
+        }
+
+        let rand_prob = (state.rand_mut().below(u64::from(u32::MAX)) as f64) / f64::from(u32::MAX);
+        let meta = Self::meta(state)?;
+        let threshold = meta.total_score * rand_prob;
+        let mut k = 0.0;
+        let mut id = 0;
+        for (idx, score) in &meta.scores {
+            id = *idx;
+            k += score;
+            if k >= threshold {
+                break;
+            }
+        }
+        *state.corpus_mut().current_mut() = Some(id);
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec, vec::Vec};
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::MapIndexesMetadata,
+        inputs::BytesInput,
+        observers::{SanitizerFrame, SanitizerReport},
+        schedulers::{
+            proximity::{CrashProximityMetadata, CrashProximityScheduler, UNREACHABLE_DISTANCE},
+            Scheduler,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    const PCS: [u64; 5] = [0x1000, 0x1010, 0x1020, 0x1030, 0x1040];
+
+    #[test]
+    fn test_crash_distances() {
+        // 0 -> 1 -> 2 -> 3, 4 is disconnected
+        let mut meta = CrashProximityMetadata::new(&PCS, &[(0, 1), (1, 2), (2, 3)]);
+        assert!(meta.add_crash_site(3));
+        assert!(!meta.add_crash_site(3));
+        assert_eq!(meta.distance(3), 0);
+        assert_eq!(meta.distance(2), 1);
+        assert_eq!(meta.distance(0), 3);
+        assert_eq!(meta.distance(4), UNREACHABLE_DISTANCE);
+
+        assert!(meta.add_crash_site(1));
+        assert_eq!(meta.distance(0), 1);
+        assert!(meta.score(&[2], 8.0) > meta.score(&[4], 8.0));
+    }
+
+    #[test]
+    fn test_crash_report_sites() {
+        // The pc table is in map order, not sorted by pc
+        let mut meta = CrashProximityMetadata::new(&[0x1020, 0x1000, 0x1010], &[(1, 2), (2, 0)]);
+        assert_eq!(meta.map_index_of(0xfff), None);
+        assert_eq!(meta.map_index_of(0x1000), Some(1));
+        assert_eq!(meta.map_index_of(0x100f), Some(1));
+        assert_eq!(meta.map_index_of(0x1010), Some(2));
+        assert_eq!(meta.map_index_of(0x1020), Some(0));
+        assert_eq!(meta.map_index_of(0x7f00_0000_0000), None);
+
+        let frame = |pc| SanitizerFrame {
+            pc,
+            function: None,
+            location: None,
+        };
+        let report = SanitizerReport {
+            sanitizer: String::from("AddressSanitizer"),
+            error_type: String::from("heap-buffer-overflow"),
+            address: Some(0x6020_0000_0010),
+            // The innermost frame is in the sanitizer runtime
+            frames: vec![frame(0x7f00_0000_1234), frame(0x1014), frame(0x1004)],
+        };
+        assert!(meta.add_crash_report(&report));
+        assert!(!meta.add_crash_report(&report));
+        assert_eq!(meta.crash_sites().len(), 1);
+        assert_eq!(meta.distance(2), 0);
+        assert_eq!(meta.distance(1), 1);
+        assert_eq!(meta.distance(0), UNREACHABLE_DISTANCE);
+
+        let report = SanitizerReport {
+            frames: vec![frame(0x7f00_0000_1234)],
+            ..report
+        };
+        assert!(!meta.add_crash_report(&report));
+    }
+
+    #[test]
+    fn test_proximity_next_deterministic() {
+        // The same seed picks the same entries
+        let picks = || {
+            let mut state = StdState::new(
+                StdRand::with_seed(0),
+                InMemoryCorpus::<BytesInput>::new(),
+                InMemoryCorpus::new(),
+                (),
+            );
+            let scheduler =
+                CrashProximityScheduler::new(&mut state, &PCS, &[(0, 1), (1, 2), (2, 3)]);
+            for idx in 0..PCS.len() {
+                let mut testcase = Testcase::new(BytesInput::new(vec![b'a'; idx + 1]));
+                testcase.add_metadata(MapIndexesMetadata::new(vec![idx]));
+                let idx = state.corpus_mut().add(testcase).unwrap();
+                scheduler.on_add(&mut state, idx).unwrap();
+            }
+            (0..32)
+                .map(|_| scheduler.next(&mut state).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks(), picks());
+    }
+}
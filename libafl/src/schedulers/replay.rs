@@ -0,0 +1,441 @@
+//! Record and replay of a fuzzing session, to debug the behavior of the fuzzer.
+//!
+//! The [`RecordingScheduler`] logs the seed of the RNG and every decision of the scheduler it wraps
+//! in a [`ReplayLogMetadata`], which can be saved to a file. Starting a new state from the same
+//! seed and corpus with a [`ReplayScheduler`] over the log takes the same path again: it checks
+//! each decision against the log, fails at the first divergence, and stops the fuzzer at its end,
+//! e.g. right at the testcase leading to a finding.
+//!
+//! The testcases received from other clients are recorded as well, by the event managers with
+//! access to the state metadata (see [`crate::events::LlmpEventManager::with_state_metadata`]),
+//! and evaluated again at the same point of the replay by a [`crate::stages::ReplayReceivedStage`].
+
+use alloc::{string::ToString, vec::Vec};
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{rands::Rand, serdeany::SerdeAnyMap},
+    corpus::Testcase,
+    inputs::Input,
+    schedulers::Scheduler,
+    state::{HasMetadata, HasRand},
+    Error,
+};
+
+/// The maximum number of entries of a [`ReplayLogMetadata`], by default
+pub const DEFAULT_MAX_REPLAY_ENTRIES: usize = 1 << 20;
+
+/// A decision or event of the scheduler during a recorded session
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ReplayEntry {
+    /// The scheduler picked this corpus entry
+    Scheduled(usize),
+    /// A testcase was added at this index
+    Added(usize),
+    /// The testcase at this index was replaced
+    Replaced(usize),
+    /// The testcase at this index was removed
+    Removed(usize),
+    /// This serialized input was received from another client
+    Received(Vec<u8>),
+}
+
+/// The log of a recorded session: the seed of the RNG and the sequence of scheduler events
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReplayLogMetadata {
+    /// The seed the RNG started from
+    pub seed: u64,
+    /// The events, in order
+    pub entries: Vec<ReplayEntry>,
+    /// The number of entries replayed so far
+    pub cursor: usize,
+    /// The maximum number of entries, the recording stops there
+    pub max_entries: usize,
+    /// If the entries are recorded, not replayed
+    pub recording: bool,
+}
+
+crate::impl_serdeany!(
+    ReplayLogMetadata,
+    "libafl::schedulers::replay::ReplayLogMetadata"
+);
+
+impl ReplayLogMetadata {
+    /// Creates a new, empty [`ReplayLogMetadata`] for a session starting from `seed`
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            entries: Vec::new(),
+            cursor: 0,
+            max_entries: DEFAULT_MAX_REPLAY_ENTRIES,
+            recording: false,
+        }
+    }
+
+    /// Seeds the RNG of the `state` and starts recording in it,
+    /// up to [`DEFAULT_MAX_REPLAY_ENTRIES`] entries
+    pub fn start_recording<S>(state: &mut S, seed: u64)
+    where
+        S: HasRand + HasMetadata,
+    {
+        Self::start_recording_with_max_entries(state, seed, DEFAULT_MAX_REPLAY_ENTRIES);
+    }
+
+    /// Seeds the RNG of the `state` and starts recording in it, up to `max_entries` entries.
+    /// A replay of the log ends where the recording stopped.
+    pub fn start_recording_with_max_entries<S>(state: &mut S, seed: u64, max_entries: usize)
+    where
+        S: HasRand + HasMetadata,
+    {
+        state.rand_mut().set_seed(seed);
+        let mut log = Self::new(seed);
+        log.max_entries = max_entries;
+        log.recording = true;
+        state.add_metadata(log);
+    }
+
+    /// Records an input received from another client, if the state with this `metadata` records a session
+    pub fn record_received<I>(metadata: &mut SerdeAnyMap, input: &I) -> Result<(), Error>
+    where
+        I: Input,
+    {
+        if let Some(log) = metadata.get_mut::<Self>() {
+            if log.recording {
+                log.push(ReplayEntry::Received(postcard::to_allocvec(input)?));
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends an entry while recording, until the log is full
+    fn push(&mut self, entry: ReplayEntry) {
+        if self.recording && self.entries.len() < self.max_entries {
+            self.entries.push(entry);
+        }
+    }
+
+    /// The next input received from another client at this point of the replay, if any
+    pub fn next_received<S>(state: &mut S) -> Result<Option<Vec<u8>>, Error>
+    where
+        S: HasMetadata,
+    {
+        let log = state
+            .metadata_mut()
+            .get_mut::<Self>()
+            .ok_or_else(|| Error::KeyNotFound("ReplayLogMetadata not found".to_string()))?;
+        if log.recording {
+            return Ok(None);
+        }
+        match log.entries.get(log.cursor) {
+            Some(ReplayEntry::Received(bytes)) => {
+                let bytes = bytes.clone();
+                log.cursor += 1;
+                Ok(Some(bytes))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Seeds the RNG of the `state` from this log and starts replaying it
+    pub fn start_replay<S>(mut self, state: &mut S)
+    where
+        S: HasRand + HasMetadata,
+    {
+        state.rand_mut().set_seed(self.seed);
+        self.cursor = 0;
+        self.recording = false;
+        state.add_metadata(self);
+    }
+
+    /// The entries up to the `n`th time the scheduler picked a testcase, to replay the path to it
+    #[must_use]
+    pub fn truncated_at_schedule(&self, n: usize) -> Self {
+        let end = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry, ReplayEntry::Scheduled(_)))
+            .nth(n)
+            .map_or(self.entries.len(), |(idx, _)| idx + 1);
+        Self {
+            seed: self.seed,
+            entries: self.entries[..end].to_vec(),
+            cursor: 0,
+            max_entries: self.max_entries,
+            recording: false,
+        }
+    }
+
+    /// Saves this log to a file
+    #[cfg(feature = "std")]
+    pub fn save<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, postcard::to_allocvec(self)?)?;
+        Ok(())
+    }
+
+    /// Loads a log from a file
+    #[cfg(feature = "std")]
+    pub fn load<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(postcard::from_bytes(&fs::read(path)?)?)
+    }
+}
+
+fn record<S>(state: &mut S, entry: ReplayEntry) -> Result<(), Error>
+where
+    S: HasMetadata,
+{
+    let log = state
+        .metadata_mut()
+        .get_mut::<ReplayLogMetadata>()
+        .ok_or_else(|| Error::KeyNotFound("ReplayLogMetadata not found".to_string()))?;
+    log.push(entry);
+    Ok(())
+}
+
+fn replay<S>(state: &mut S, entry: ReplayEntry) -> Result<(), Error>
+where
+    S: HasMetadata,
+{
+    let log = state
+        .metadata_mut()
+        .get_mut::<ReplayLogMetadata>()
+        .ok_or_else(|| Error::KeyNotFound("ReplayLogMetadata not found".to_string()))?;
+    match log.entries.get(log.cursor) {
+        None => Err(Error::ShuttingDown),
+        Some(expected) if *expected == entry => {
+            log.cursor += 1;
+            Ok(())
+        }
+        Some(expected) => Err(Error::IllegalState(format!(
+            "Replay diverged at entry {}: expected {:?}, got {:?}",
+            log.cursor, expected, entry
+        ))),
+    }
+}
+
+/// A [`Scheduler`] recording the decisions of the scheduler it wraps in the [`ReplayLogMetadata`]
+#[derive(Debug, Clone)]
+pub struct RecordingScheduler<CS> {
+    base: CS,
+}
+
+impl<CS> RecordingScheduler<CS> {
+    /// Creates a new [`RecordingScheduler`] wrapping `base`.
+    /// Start the recording with [`ReplayLogMetadata::start_recording`].
+    #[must_use]
+    pub fn new(base: CS) -> Self {
+        Self { base }
+    }
+}
+
+impl<CS, I, S> Scheduler<I, S> for RecordingScheduler<CS>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasMetadata,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        record(state, ReplayEntry::Added(idx))?;
+        self.base.on_add(state, idx)
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        record(state, ReplayEntry::Replaced(idx))?;
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        record(state, ReplayEntry::Removed(idx))?;
+        self.base.on_remove(state, idx, testcase)
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let idx = self.base.next(state)?;
+        record(state, ReplayEntry::Scheduled(idx))?;
+        Ok(idx)
+    }
+}
+
+/// A [`Scheduler`] replaying a [`ReplayLogMetadata`] with the scheduler that recorded it.
+///
+/// Each event is checked against the log, with an [`Error::IllegalState`] at the first divergence,
+/// e.g. from a nondeterministic target; once the log is over, [`Error::ShuttingDown`] stops the
+/// fuzzer.
+#[derive(Debug, Clone)]
+pub struct ReplayScheduler<CS> {
+    base: CS,
+}
+
+impl<CS> ReplayScheduler<CS> {
+    /// Creates a new [`ReplayScheduler`] wrapping `base`, the kind of scheduler of the recording.
+    /// Start the replay with [`ReplayLogMetadata::start_replay`].
+    #[must_use]
+    pub fn new(base: CS) -> Self {
+        Self { base }
+    }
+}
+
+impl<CS, I, S> Scheduler<I, S> for ReplayScheduler<CS>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasMetadata,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        replay(state, ReplayEntry::Added(idx))?;
+        self.base.on_add(state, idx)
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        replay(state, ReplayEntry::Replaced(idx))?;
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        replay(state, ReplayEntry::Removed(idx))?;
+        self.base.on_remove(state, idx, testcase)
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let idx = self.base.next(state)?;
+        replay(state, ReplayEntry::Scheduled(idx))?;
+        Ok(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        schedulers::{
+            RandScheduler, RecordingScheduler, ReplayEntry, ReplayLogMetadata, ReplayScheduler,
+            Scheduler,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+        Error,
+    };
+
+    #[test]
+    fn test_record_replay() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for i in 0..8_u8 {
+            corpus.add(Testcase::new(vec![i])).unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus.clone(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        ReplayLogMetadata::start_recording(&mut state, 1337);
+        let recorder = RecordingScheduler::new(RandScheduler::new());
+        let recorded = (0..10)
+            .map(|_| recorder.next(&mut state).unwrap())
+            .collect::<Vec<_>>();
+        let log = state
+            .metadata()
+            .get::<ReplayLogMetadata>()
+            .unwrap()
+            .truncated_at_schedule(4);
+        assert_eq!(log.entries.len(), 5);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        log.clone().start_replay(&mut state);
+        let replayer = ReplayScheduler::new(RandScheduler::new());
+        for idx in &recorded[..5] {
+            assert_eq!(replayer.next(&mut state).unwrap(), *idx);
+        }
+        assert!(matches!(
+            replayer.next(&mut state),
+            Err(Error::ShuttingDown)
+        ));
+
+        // A different corpus takes another path
+        state.corpus_mut().add(Testcase::new(vec![8_u8])).unwrap();
+        log.start_replay(&mut state);
+        assert!(matches!(
+            replayer.on_add(&mut state, 8),
+            Err(Error::IllegalState(_))
+        ));
+    }
+
+    #[test]
+    fn test_record_received() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![0_u8])).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        ReplayLogMetadata::start_recording_with_max_entries(&mut state, 1337, 3);
+        let recorder = RecordingScheduler::new(RandScheduler::new());
+        recorder.next(&mut state).unwrap();
+        let input = BytesInput::new(vec![1, 2, 3]);
+        let received = postcard::to_allocvec(&input).unwrap();
+        ReplayLogMetadata::record_received(state.metadata_mut(), &input).unwrap();
+        recorder.next(&mut state).unwrap();
+        recorder.next(&mut state).unwrap();
+
+        // The recording stops at its cap
+        let log = state.metadata().get::<ReplayLogMetadata>().unwrap().clone();
+        assert_eq!(log.entries.len(), 3);
+        assert_eq!(log.entries[1], ReplayEntry::Received(received.clone()));
+
+        log.start_replay(&mut state);
+        let replayer = ReplayScheduler::new(RandScheduler::new());
+        replayer.next(&mut state).unwrap();
+        // The received input is due before the next schedule
+        assert!(matches!(
+            replayer.next(&mut state),
+            Err(Error::IllegalState(_))
+        ));
+
+        // The cursor survives a restart
+        let saved =
+            postcard::to_allocvec(state.metadata().get::<ReplayLogMetadata>().unwrap()).unwrap();
+        let restored: ReplayLogMetadata = postcard::from_bytes(&saved).unwrap();
+        assert_eq!(restored.cursor, 1);
+        assert!(!restored.recording);
+        state.add_metadata(restored);
+
+        assert_eq!(
+            ReplayLogMetadata::next_received(&mut state).unwrap(),
+            Some(received)
+        );
+        assert_eq!(ReplayLogMetadata::next_received(&mut state).unwrap(), None);
+        replayer.next(&mut state).unwrap();
+        assert!(matches!(
+            replayer.next(&mut state),
+            Err(Error::ShuttingDown)
+        ));
+    }
+}
@@ -0,0 +1,341 @@
+//! The [`WeightedScheduler`] samples the corpus by the score of each testcase, using the alias method.
+//! The score is computed by a pluggable function over the testcase and the state.
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{rands::Rand, HasLen},
+    corpus::{Corpus, PowerScheduleTestcaseMetaData, Testcase},
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    schedulers::Scheduler,
+    state::{HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// The scoring function of a [`StdWeightedScheduler`]
+pub type WeightedScoreFn<I, S> = fn(&S, &mut Testcase<I>) -> Result<f64, Error>;
+
+/// A [`WeightedScheduler`] scoring the testcases with [`std_weighted_score`]
+pub type StdWeightedScheduler<I, S> = WeightedScheduler<WeightedScoreFn<I, S>, I, S>;
+
+/// A state metadata holding the alias table of the [`WeightedScheduler`]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WeightedScheduleMetadata {
+    /// The probability to pick each entry itself rather than its alias
+    pub alias_probability: Vec<f64>,
+    /// The alias of each entry
+    pub alias_table: Vec<usize>,
+    /// The number of testcases covering each map index, for the rarity of the edges
+    pub edge_counts: HashMap<usize, usize>,
+    /// If the corpus changed since the table was built
+    pub dirty: bool,
+}
+
+crate::impl_serdeany!(
+    WeightedScheduleMetadata,
+    "libafl::schedulers::weighted::WeightedScheduleMetadata"
+);
+
+impl WeightedScheduleMetadata {
+    /// Creates a new [`struct@WeightedScheduleMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Count the map indexes covered by a testcase in the edge counts
+fn count_edges(
+    edge_counts: &mut HashMap<usize, usize>,
+    testcase: &Testcase<impl Input>,
+    add: bool,
+) {
+    if let Some(meta) = testcase.metadata().get::<MapIndexesMetadata>() {
+        for idx in &meta.list {
+            let count = edge_counts.entry(*idx).or_insert(0);
+            if add {
+                *count += 1;
+            } else {
+                *count = count.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// The mean inverse frequency of the map indexes covered by `testcase`: a testcase only covering
+/// edges no other testcase covers scores 1, one covering common edges scores close to 0.
+/// Returns 1 if the map feedback does not track the indexes.
+#[allow(clippy::cast_precision_loss)]
+pub fn edge_rarity<I, S>(state: &S, testcase: &Testcase<I>) -> f64
+where
+    I: Input,
+    S: HasMetadata,
+{
+    let edge_counts = match state.metadata().get::<WeightedScheduleMetadata>() {
+        Some(meta) => &meta.edge_counts,
+        None => return 1.0,
+    };
+    match testcase.metadata().get::<MapIndexesMetadata>() {
+        Some(meta) if !meta.list.is_empty() => {
+            let sum: f64 = meta
+                .list
+                .iter()
+                .map(|idx| 1.0 / edge_counts.get(idx).copied().unwrap_or(1).max(1) as f64)
+                .sum();
+            sum / meta.list.len() as f64
+        }
+        _ => 1.0,
+    }
+}
+
+/// The default score: rare edges, fast executions, small inputs and deep testcases score higher
+#[allow(clippy::cast_precision_loss)]
+pub fn std_weighted_score<I, S>(state: &S, testcase: &mut Testcase<I>) -> Result<f64, Error>
+where
+    I: Input + HasLen,
+    S: HasMetadata,
+{
+    let mut score = edge_rarity(state, testcase);
+    if let Some(exec_time) = testcase.exec_time() {
+        score /= 1.0 + exec_time.as_secs_f64() * 1000.0;
+    }
+    if let Ok(len) = testcase.cached_len() {
+        score /= 1.0 + ((len + 1) as f64).log2();
+    }
+    if let Some(meta) = testcase.metadata().get::<PowerScheduleTestcaseMetaData>() {
+        score *= 1.0 + (meta.depth() as f64 / 8.0).min(4.0);
+    }
+    Ok(score)
+}
+
+/// A scheduler picking testcases with a probability proportional to their score.
+/// The samples are drawn in constant time with the alias method, the table is rebuilt
+/// lazily once the corpus changed.
+pub struct WeightedScheduler<F, I, S>
+where
+    F: Fn(&S, &mut Testcase<I>) -> Result<f64, Error>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    score: F,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<F, I, S> Debug for WeightedScheduler<F, I, S>
+where
+    F: Fn(&S, &mut Testcase<I>) -> Result<f64, Error>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeightedScheduler").finish_non_exhaustive()
+    }
+}
+
+impl<I, S> StdWeightedScheduler<I, S>
+where
+    I: Input + HasLen,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    /// Creates a new [`WeightedScheduler`] scoring the testcases with [`std_weighted_score`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_score(std_weighted_score::<I, S>)
+    }
+}
+
+impl<I, S> Default for StdWeightedScheduler<I, S>
+where
+    I: Input + HasLen,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, I, S> WeightedScheduler<F, I, S>
+where
+    F: Fn(&S, &mut Testcase<I>) -> Result<f64, Error>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    /// Creates a new [`WeightedScheduler`] with the given scoring function.
+    /// Testcases scoring 0 or less are never picked, unless all of them do.
+    #[must_use]
+    pub fn with_score(score: F) -> Self {
+        Self {
+            score,
+            phantom: PhantomData,
+        }
+    }
+
+    fn metadata_mut(state: &mut S) -> &mut WeightedScheduleMetadata {
+        if !state.has_metadata::<WeightedScheduleMetadata>() {
+            state.add_metadata(WeightedScheduleMetadata::new());
+        }
+        state
+            .metadata_mut()
+            .get_mut::<WeightedScheduleMetadata>()
+            .unwrap()
+    }
+
+    /// Build the alias table from the scores of all the testcases, with Vose's method
+    #[allow(clippy::cast_precision_loss)]
+    pub fn create_alias_table(&self, state: &mut S) -> Result<(), Error> {
+        let n = state.corpus().count();
+        let mut scores = Vec::with_capacity(n);
+        for idx in 0..n {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            let score = (self.score)(state, &mut testcase)?;
+            scores.push(if score.is_finite() && score > 0.0 {
+                score
+            } else {
+                0.0
+            });
+        }
+        let sum: f64 = scores.iter().sum();
+        if sum <= 0.0 {
+            scores.iter_mut().for_each(|score| *score = 1.0);
+        }
+        let sum: f64 = scores.iter().sum();
+
+        let mut probability: Vec<f64> = scores.iter().map(|s| s * n as f64 / sum).collect();
+        let mut alias: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&idx| probability[idx] < 1.0);
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            alias[s] = l;
+            probability[l] -= 1.0 - probability[s];
+            if probability[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // What is left is 1, up to rounding errors
+        for idx in small.into_iter().chain(large) {
+            probability[idx] = 1.0;
+        }
+
+        let meta = Self::metadata_mut(state);
+        meta.alias_probability = probability;
+        meta.alias_table = alias;
+        meta.dirty = false;
+        Ok(())
+    }
+}
+
+impl<F, I, S> Scheduler<I, S> for WeightedScheduler<F, I, S>
+where
+    F: Fn(&S, &mut Testcase<I>) -> Result<f64, Error>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        let mut edge_counts = core::mem::take(&mut Self::metadata_mut(state).edge_counts);
+        count_edges(&mut edge_counts, &state.corpus().get(idx)?.borrow(), true);
+        let meta = Self::metadata_mut(state);
+        meta.edge_counts = edge_counts;
+        meta.dirty = true;
+        Ok(())
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        let mut edge_counts = core::mem::take(&mut Self::metadata_mut(state).edge_counts);
+        count_edges(&mut edge_counts, testcase, false);
+        count_edges(&mut edge_counts, &state.corpus().get(idx)?.borrow(), true);
+        let meta = Self::metadata_mut(state);
+        meta.edge_counts = edge_counts;
+        meta.dirty = true;
+        Ok(())
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        _idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        let meta = Self::metadata_mut(state);
+        if let Some(testcase) = testcase {
+            count_edges(&mut meta.edge_counts, testcase, false);
+        }
+        meta.dirty = true;
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let n = state.corpus().count();
+        if n == 0 {
+            return Err(Error::Empty(String::from("No entries in corpus")));
+        }
+        let stale = {
+            let meta = Self::metadata_mut(state);
+            meta.dirty || meta.alias_table.len() != n
+        };
+        if stale {
+            self.create_alias_table(state)?;
+        }
+
+        let idx = state.rand_mut().below(n as u64) as usize;
+        let coin = state.rand_mut().next() as f64 / u64::MAX as f64;
+        let meta = state.metadata().get::<WeightedScheduleMetadata>().unwrap();
+        let idx = if coin < meta.alias_probability[idx] {
+            idx
+        } else {
+            meta.alias_table[idx]
+        };
+        *state.corpus_mut().current_mut() = Some(idx);
+        Ok(idx)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        schedulers::{Scheduler, WeightedScheduler},
+        state::{HasCorpus, StdState},
+        Error,
+    };
+
+    #[test]
+    fn test_weighted_scheduler() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for len in [1, 2, 3] {
+            corpus
+                .add(Testcase::new(BytesInput::new(vec![0; len])))
+                .unwrap();
+        }
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+
+        // Only the second testcase has weight
+        let scheduler = WeightedScheduler::with_score(
+            |_state, testcase: &mut Testcase<BytesInput>| -> Result<f64, Error> {
+                Ok(if testcase.cached_len()? == 2 {
+                    1.0
+                } else {
+                    0.0
+                })
+            },
+        );
+        for idx in 0..3 {
+            scheduler.on_add(&mut state, idx).unwrap();
+        }
+        for _ in 0..32 {
+            assert_eq!(scheduler.next(&mut state).unwrap(), 1);
+        }
+        assert_eq!(*state.corpus().current(), Some(1));
+    }
+}
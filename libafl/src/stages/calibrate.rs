@@ -1,4 +1,8 @@
 //! The calibration stage. The fuzzer measures the average exec time and the bitmap size.
+//!
+//! Each new testcase is run several times. The map entries that change between the runs are
+//! marked unstable in the [`MapFeedbackState`], so that the feedbacks ignore them, and the
+//! testcase gets a [`CalibrationMetadata`] with its exec time statistics and stability.
 
 use crate::{
     bolts::current_time,
@@ -19,7 +23,7 @@ use alloc::{
     vec::Vec,
 };
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
-use num_traits::Bounded;
+use hashbrown::HashSet;
 use serde::{Deserialize, Serialize};
 
 /// The calibration stage will measure the average exec time and the target's stability for this input.
@@ -60,6 +64,16 @@ where
         mgr: &mut EM,
         corpus_idx: usize,
     ) -> Result<(), Error> {
+        // Only calibrate the new testcases, later runs only count the fuzzing rounds
+        if state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow()
+            .has_metadata::<CalibrationMetadata>()
+        {
+            return bump_fuzz_level(state, corpus_idx);
+        }
+
         let mut iter = self.stage_max;
         let handicap = state
             .metadata()
@@ -73,46 +87,25 @@ where
             .load_input()?
             .clone();
 
-        // Run once to get the initial calibration map
-        executor.observers_mut().pre_exec_all(state, &input)?;
-        let mut start = current_time();
-
-        let mut total_time = if executor.run_target(fuzzer, state, mgr, &input)? == ExitKind::Ok {
-            current_time() - start
-        } else {
-            mgr.log(
-                state,
-                LogSeverity::Warn,
-                "Corpus entry errored on execution!".into(),
-            )?;
-            // assume one second as default time
-            Duration::from_secs(1)
-        };
-
-        let map_first = &executor
-            .observers()
-            .match_name::<O>(&self.map_observer_name)
-            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
-            .to_vec();
-
-        // Run CAL_STAGE_START - 1 times, increase by 2 for every time a new
-        // run is found to be unstable, with CAL_STAGE_MAX total runs.
-        let mut i = 1;
+        // Run CAL_STAGE_START times, increase by 2 for every time a run errors or
+        // new unstable entries are found, with CAL_STAGE_MAX total runs.
+        let mut i = 0;
         let mut has_errors = false;
-        let mut unstable_entries: usize = 0;
-        let map_len: usize = map_first.len();
+        let mut times = vec![];
+        let mut map_first: Option<Vec<O::Entry>> = None;
+        let mut unstable_entries = HashSet::new();
         while i < iter {
-            let input = state
-                .corpus()
-                .get(corpus_idx)?
-                .borrow_mut()
-                .load_input()?
-                .clone();
+            i += 1;
 
             executor.observers_mut().pre_exec_all(state, &input)?;
-            start = current_time();
-
-            if executor.run_target(fuzzer, state, mgr, &input)? != ExitKind::Ok {
+            let start = current_time();
+            let exit_kind = executor.run_target(fuzzer, state, mgr, &input)?;
+            let elapsed = current_time() - start;
+            executor
+                .observers_mut()
+                .post_exec_all(state, &input, &exit_kind)?;
+
+            if exit_kind != ExitKind::Ok {
                 if !has_errors {
                     mgr.log(
                         state,
@@ -128,37 +121,77 @@ where
                 continue;
             };
 
-            total_time += current_time() - start;
+            times.push(elapsed);
 
-            let map = &executor
+            let map = executor
                 .observers()
                 .match_name::<O>(&self.map_observer_name)
                 .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
                 .to_vec();
 
-            let history_map = &mut state
-                .feedback_states_mut()
-                .match_name_mut::<MapFeedbackState<O::Entry>>(&self.map_observer_name)
-                .unwrap()
-                .history_map;
-
-            for j in 0..map_len {
-                if map_first[j] != map[j] && history_map[j] != O::Entry::max_value() {
-                    history_map[j] = O::Entry::max_value();
-                    unstable_entries += 1;
-                };
+            match &map_first {
+                None => map_first = Some(map),
+                Some(first) => {
+                    let before = unstable_entries.len();
+                    for (j, (a, b)) in first.iter().zip(map.iter()).enumerate() {
+                        if a != b {
+                            unstable_entries.insert(j);
+                        }
+                    }
+                    if unstable_entries.len() > before && iter < CAL_STAGE_MAX {
+                        iter += 2;
+                    }
+                }
             }
+        }
 
-            i += 1;
+        let map = executor
+            .observers()
+            .match_name::<O>(&self.map_observer_name)
+            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?;
+        let initial = map.initial();
+        let bitmap_size = map.count_bytes();
+
+        // The feedbacks ignore the unstable entries from now on
+        let map_state = state
+            .feedback_states_mut()
+            .match_name_mut::<MapFeedbackState<O::Entry>>(&self.map_observer_name)
+            .ok_or_else(|| Error::KeyNotFound("MapFeedbackState not found".to_string()))?;
+        for j in &unstable_entries {
+            map_state.mark_unstable(*j);
+        }
+        if !map_state.unstable_entries.is_empty() {
+            let stability = map_state.stability();
+            *state.stability_mut() = Some(stability);
         }
 
+        let covered = map_first.as_ref().map_or(0, |first| {
+            first.iter().filter(|entry| **entry != initial).count()
+        });
         #[allow(clippy::cast_precision_loss)]
-        if unstable_entries != 0 {
-            *state.stability_mut() = Some((map_len - unstable_entries) as f32 / (map_len as f32));
+        let stability = if covered == 0 {
+            100.0
+        } else {
+            (covered.saturating_sub(unstable_entries.len()) as f64) * 100.0 / (covered as f64)
+        };
 
-            if iter < CAL_STAGE_MAX {
-                iter += 2;
-            }
+        // assume one second as default time if no run succeeded
+        let total_time: Duration = times.iter().sum();
+        let exec_time = if times.is_empty() {
+            Duration::from_secs(1)
+        } else {
+            total_time / (times.len() as u32)
+        };
+        let mut unstable_entries: Vec<usize> = unstable_entries.into_iter().collect();
+        unstable_entries.sort_unstable();
+        let calibration = CalibrationMetadata {
+            runs: times.len(),
+            exec_time_mean: exec_time,
+            exec_time_min: times.iter().min().copied().unwrap_or(exec_time),
+            exec_time_max: times.iter().max().copied().unwrap_or(exec_time),
+            flaky: !unstable_entries.is_empty() || (has_errors && !times.is_empty()),
+            unstable_entries,
+            stability,
         };
 
         let psmeta = state
@@ -166,36 +199,65 @@ where
             .get_mut::<PowerScheduleMetadata>()
             .ok_or_else(|| Error::KeyNotFound("PowerScheduleMetadata not found".to_string()))?;
 
-        let map = executor
-            .observers()
-            .match_name::<O>(&self.map_observer_name)
-            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?;
-
-        let bitmap_size = map.count_bytes();
-
         psmeta.set_exec_time(psmeta.exec_time() + total_time);
         psmeta.set_cycles(psmeta.cycles() + (iter as u64));
         psmeta.set_bitmap_size(psmeta.bitmap_size() + bitmap_size);
         psmeta.set_bitmap_entries(psmeta.bitmap_entries() + 1);
 
-        let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+        {
+            let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
 
-        testcase.set_exec_time(total_time / (iter as u32));
-        // println!("time: {:#?}", testcase.exec_time());
-        let data = testcase
-            .metadata_mut()
-            .get_mut::<PowerScheduleTestcaseMetaData>()
-            .ok_or_else(|| Error::KeyNotFound("PowerScheduleTestData not found".to_string()))?;
+            testcase.set_exec_time(exec_time);
+            testcase.add_metadata(calibration);
+            let data = testcase
+                .metadata_mut()
+                .get_mut::<PowerScheduleTestcaseMetaData>()
+                .ok_or_else(|| Error::KeyNotFound("PowerScheduleTestData not found".to_string()))?;
 
-        data.set_bitmap_size(bitmap_size);
-        data.set_handicap(handicap);
-        data.set_fuzz_level(data.fuzz_level() + 1);
-        // println!("data: {:#?}", data);
+            data.set_bitmap_size(bitmap_size);
+            data.set_handicap(handicap);
+        }
 
-        Ok(())
+        bump_fuzz_level(state, corpus_idx)
     }
 }
 
+/// Counts one more fuzzing round for the testcase at `corpus_idx`
+fn bump_fuzz_level<I, S>(state: &mut S, corpus_idx: usize) -> Result<(), Error>
+where
+    I: Input,
+    S: HasCorpus<I>,
+{
+    let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+    let data = testcase
+        .metadata_mut()
+        .get_mut::<PowerScheduleTestcaseMetaData>()
+        .ok_or_else(|| Error::KeyNotFound("PowerScheduleTestData not found".to_string()))?;
+    data.set_fuzz_level(data.fuzz_level() + 1);
+    Ok(())
+}
+
+/// The statistics the [`CalibrationStage`] measured for a testcase
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CalibrationMetadata {
+    /// The number of runs that completed without error
+    pub runs: usize,
+    /// The mean exec time of the completed runs
+    pub exec_time_mean: Duration,
+    /// The fastest completed run
+    pub exec_time_min: Duration,
+    /// The slowest completed run
+    pub exec_time_max: Duration,
+    /// The map entries that changed between runs
+    pub unstable_entries: Vec<usize>,
+    /// The percentage of the covered map entries that did not change between runs
+    pub stability: f64,
+    /// If the testcase behaved differently between runs, with unstable entries or errors
+    pub flaky: bool,
+}
+
+crate::impl_serdeany!(CalibrationMetadata);
+
 /// The n fuzz size
 pub const N_FUZZ_SIZE: usize = 1 << 21;
 
@@ -299,7 +361,10 @@ impl PowerScheduleMetadata {
     }
 }
 
-crate::impl_serdeany!(PowerScheduleMetadata);
+crate::impl_serdeany!(
+    PowerScheduleMetadata,
+    "libafl::stages::calibrate::PowerScheduleMetadata"
+);
 
 impl<I, O, OT, S> CalibrationStage<I, O, OT, S>
 where
@@ -0,0 +1,94 @@
+//! The [`CorpusMinimizerStage`] periodically minimizes the corpus with a [`MapCorpusMinimizer`].
+
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::MapCorpusMinimizer,
+    fuzzer::HasScheduler,
+    inputs::Input,
+    schedulers::{FavFactor, Scheduler},
+    stages::Stage,
+    state::{HasCorpus, HasExecutions, HasMetadata},
+    Error,
+};
+
+/// Metadata used to store when the corpus was last minimized
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct CorpusMinimizerMetadata {
+    /// The executions when the last minimization ran
+    pub last_executions: usize,
+    /// The number of testcases removed by the last minimization
+    pub last_removed: usize,
+}
+
+crate::impl_serdeany!(
+    CorpusMinimizerMetadata,
+    "libafl::stages::cmin::CorpusMinimizerMetadata"
+);
+
+/// A stage minimizing the corpus every `interval` executions.
+/// The corpus indexes shift on removal, so it must be the last stage.
+#[derive(Debug)]
+pub struct CorpusMinimizerStage<CS, F, I, S>
+where
+    F: FavFactor<I>,
+    I: Input,
+{
+    minimizer: MapCorpusMinimizer<F, I>,
+    interval: usize,
+    phantom: PhantomData<(CS, S)>,
+}
+
+impl<CS, E, EM, F, I, S, Z> Stage<E, EM, S, Z> for CorpusMinimizerStage<CS, F, I, S>
+where
+    CS: Scheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    S: HasCorpus<I> + HasExecutions + HasMetadata,
+    Z: HasScheduler<CS, I, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let executions = *state.executions();
+        let last = state
+            .metadata()
+            .get::<CorpusMinimizerMetadata>()
+            .map_or(0, |meta| meta.last_executions);
+        if executions < last + self.interval {
+            return Ok(());
+        }
+
+        let removed = self.minimizer.minimize(fuzzer, state)?;
+        state.add_metadata(CorpusMinimizerMetadata {
+            last_executions: executions,
+            last_removed: removed,
+        });
+        Ok(())
+    }
+}
+
+impl<CS, F, I, S> CorpusMinimizerStage<CS, F, I, S>
+where
+    CS: Scheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    S: HasCorpus<I> + HasExecutions + HasMetadata,
+{
+    /// Creates a new [`CorpusMinimizerStage`], minimizing the corpus every `interval` executions
+    #[must_use]
+    pub fn new(interval: usize) -> Self {
+        Self {
+            minimizer: MapCorpusMinimizer::new(),
+            interval,
+            phantom: PhantomData,
+        }
+    }
+}
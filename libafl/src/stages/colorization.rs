@@ -0,0 +1,214 @@
+//! The colorization stage of `RedQueen`: it replaces ranges of a testcase with random bytes as
+//! long as the coverage stays the same, to find the bytes the input-to-state mutations can
+//! freely replace.
+//!
+//! The tainted ranges end up in a [`TaintMetadata`], consumed by [`crate::mutators::I2SRandReplace`].
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{marker::PhantomData, ops::Range};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    observers::{MapObserver, ObserversTuple, TaintMetadata},
+    stages::Stage,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// The default maximum number of executions spent on colorizing a testcase, per input byte
+pub const DEFAULT_COLORIZATION_EXECS_PER_BYTE: usize = 2;
+
+/// A stage colorizing each testcase once, see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct ColorizationStage<EM, I, O, OT, S, Z>
+where
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I> + HasRand,
+{
+    map_observer_name: String,
+    execs_per_byte: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(EM, I, O, OT, S, Z)>,
+}
+
+impl<E, EM, I, O, OT, S, Z> Stage<E, EM, S, Z> for ColorizationStage<EM, I, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I> + HasRand,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        start_timer!(state);
+        let input = {
+            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+            // Colorized before, only make its taint the current one
+            if let Some(meta) = entry.metadata().get::<TaintMetadata>() {
+                let meta = meta.clone();
+                drop(entry);
+                state.add_metadata(meta);
+                return Ok(());
+            }
+            entry.load_input()?.clone()
+        };
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+        let meta = self.colorize(fuzzer, executor, state, manager, &input)?;
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(meta.clone());
+        state.add_metadata(meta);
+        Ok(())
+    }
+}
+
+impl<EM, I, O, OT, S, Z> ColorizationStage<EM, I, O, OT, S, Z>
+where
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I> + HasRand,
+{
+    /// Create a new [`ColorizationStage`] comparing the coverage of the given map observer
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self::from_name(map_observer.name())
+    }
+
+    /// Create a new [`ColorizationStage`] comparing the coverage of the map observer with the given name
+    #[must_use]
+    pub fn from_name(map_observer_name: &str) -> Self {
+        Self {
+            map_observer_name: map_observer_name.to_string(),
+            execs_per_byte: DEFAULT_COLORIZATION_EXECS_PER_BYTE,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Spend at most `execs_per_byte` executions per byte of each testcase
+    #[must_use]
+    pub fn with_execs_per_byte(mut self, execs_per_byte: usize) -> Self {
+        self.execs_per_byte = execs_per_byte;
+        self
+    }
+
+    /// Colorize `input`: randomize the largest ranges first, keep the randomized bytes if the
+    /// coverage did not change, and split the range in halves otherwise.
+    pub fn colorize<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+    ) -> Result<TaintMetadata, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        let len = input.bytes().len();
+        let expected = self.run(fuzzer, executor, state, manager, input)?;
+        // Nothing to compare the coverage with
+        if expected.is_none() || len == 0 {
+            return Ok(TaintMetadata::new(input.bytes().to_vec(), vec![]));
+        }
+
+        let mut budget = len * self.execs_per_byte;
+        let mut colorized = input.clone();
+        let mut pending: Vec<Range<usize>> = vec![0..len];
+        let mut taint = vec![];
+
+        while budget > 0 {
+            // The largest range first
+            let largest = match pending
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, range)| range.len())
+            {
+                Some((idx, _)) => idx,
+                None => break,
+            };
+            let range = pending.swap_remove(largest);
+            budget -= 1;
+
+            let mut candidate = colorized.clone();
+            for byte in &mut candidate.bytes_mut()[range.clone()] {
+                // Always a different byte
+                *byte = byte.wrapping_add(1 + state.rand_mut().below(255) as u8);
+            }
+            if self.run(fuzzer, executor, state, manager, &candidate)? == expected {
+                colorized = candidate;
+                taint.push(range);
+            } else if range.len() > 1 {
+                let mid = range.start + range.len() / 2;
+                pending.push(range.start..mid);
+                pending.push(mid..range.end);
+            }
+        }
+
+        Ok(TaintMetadata::new(colorized.bytes().to_vec(), taint))
+    }
+
+    /// Run `input`, returning the hash of the map if it exited normally
+    fn run<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+    ) -> Result<Option<u64>, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        start_timer!(state);
+        executor.observers_mut().pre_exec_all(state, input)?;
+        mark_feature_time!(state, PerfFeature::PreExecObservers);
+
+        start_timer!(state);
+        let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
+        mark_feature_time!(state, PerfFeature::TargetExecution);
+
+        *state.executions_mut() += 1;
+
+        start_timer!(state);
+        executor
+            .observers_mut()
+            .post_exec_all(state, input, &exit_kind)?;
+        mark_feature_time!(state, PerfFeature::PostExecObservers);
+
+        if exit_kind != ExitKind::Ok {
+            return Ok(None);
+        }
+        Ok(Some(
+            executor
+                .observers()
+                .match_name::<O>(&self.map_observer_name)
+                .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
+                .hash(),
+        ))
+    }
+}
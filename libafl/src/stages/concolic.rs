@@ -8,7 +8,10 @@ use crate::{
     corpus::Corpus,
     executors::{Executor, HasObservers},
     inputs::Input,
-    observers::{concolic::ConcolicObserver, ObserversTuple},
+    observers::{
+        concolic::{ConcolicMetadata, ConcolicObserver},
+        ObserversTuple,
+    },
     state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
     Error,
 };
@@ -44,6 +47,15 @@ where
         manager: &mut EM,
         corpus_idx: usize,
     ) -> Result<(), Error> {
+        // The trace of a testcase does not change, trace it only once
+        if state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow()
+            .has_metadata::<ConcolicMetadata>()
+        {
+            return Ok(());
+        }
         self.inner
             .perform(fuzzer, executor, state, manager, corpus_idx)?;
         if let Some(observer) = self
@@ -85,7 +97,7 @@ where
 use crate::{
     inputs::HasBytesVec,
     mark_feature_time,
-    observers::concolic::{ConcolicMetadata, SymExpr, SymExprRef},
+    observers::concolic::{SymExpr, SymExprRef},
     start_timer, Evaluator,
 };
 
@@ -166,7 +178,11 @@ fn generate_mutations(iter: impl Iterator<Item = (SymExprRef, SymExpr)>) -> Vec<
             SymExpr::Integer { value, bits } => {
                 Some(BV::from_u64(&ctx, value, u32::from(bits)).into())
             }
-            SymExpr::Integer128 { high: _, low: _ } => todo!(),
+            SymExpr::Integer128 { high, low } => Some(
+                BV::from_u64(&ctx, high, 64)
+                    .concat(&BV::from_u64(&ctx, low, 64))
+                    .into(),
+            ),
             SymExpr::NullPointer => Some(BV::from_u64(&ctx, 0, usize::BITS).into()),
             SymExpr::True => Some(Bool::from_bool(&ctx, true).into()),
             SymExpr::False => Some(Bool::from_bool(&ctx, false).into()),
@@ -312,16 +328,13 @@ fn generate_mutations(iter: impl Iterator<Item = (SymExprRef, SymExpr)>) -> Vec<
                             if let [offset_str, value_str] =
                                 l.split(" -> ").collect::<Vec<_>>().as_slice()
                             {
-                                let offset = offset_str
-                                    .trim_start_matches("k!")
-                                    .parse::<usize>()
-                                    .unwrap();
+                                let offset = offset_str.trim_start_matches("k!").parse::<usize>();
                                 let value =
-                                    u8::from_str_radix(value_str.trim_start_matches("#x"), 16)
-                                        .unwrap();
-                                replacements.push((offset, value));
-                            } else {
-                                panic!();
+                                    u8::from_str_radix(value_str.trim_start_matches("#x"), 16);
+                                // The model may also assign the symbols we did not create
+                                if let (Ok(offset), Ok(value)) = (offset, value) {
+                                    replacements.push((offset, value));
+                                }
                             }
                         }
                         res.push(replacements);
@@ -380,8 +393,11 @@ where
             let input = { testcase.borrow().input().as_ref().unwrap().clone() };
             for mutation in mutations {
                 let mut input_copy = input.to_owned();
+                let bytes = input_copy.bytes_mut();
                 for (index, new_byte) in mutation {
-                    input_copy.bytes_mut()[index] = new_byte;
+                    if let Some(byte) = bytes.get_mut(index) {
+                        *byte = new_byte;
+                    }
                 }
                 // Time is measured directly the `evaluate_input` function
                 let _ = fuzzer.evaluate_input(state, executor, manager, input_copy)?;
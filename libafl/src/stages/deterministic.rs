@@ -0,0 +1,296 @@
+//! The [`DeterministicStage`] walks the deterministic mutations of AFL over each new testcase,
+//! exactly once: walking bitflips, byte flips, arithmetics and interesting values at every offset.
+//!
+//! As in AFL, the arithmetics and interesting values that a bitflip already produced are skipped.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    mutators::mutations::{ARITH_MAX, INTERESTING_16, INTERESTING_32, INTERESTING_8},
+    stages::Stage,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// Marks a testcase the [`DeterministicStage`] already walked
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct DeterministicDoneMetadata {
+    /// The executions the walk took
+    pub executions: usize,
+}
+
+crate::impl_serdeany!(DeterministicDoneMetadata);
+
+/// If the change `xor` between the old and the new value could come from a bitflip walk,
+/// as `could_be_bitflip` in AFL
+#[must_use]
+pub fn could_be_bitflip(mut xor: u32) -> bool {
+    if xor == 0 {
+        return true;
+    }
+    let shift = xor.trailing_zeros();
+    xor >>= shift;
+    // 1, 2 and 4 bits anywhere
+    if xor == 1 || xor == 3 || xor == 15 {
+        return true;
+    }
+    // 8, 16 and 32 bits, byte aligned
+    shift & 7 == 0 && (xor == 0xff || xor == 0xffff || xor == 0xffff_ffff)
+}
+
+/// Reads `N` bytes at `pos` as an integer, little endian or big endian
+fn read_uint<const N: usize>(bytes: &[u8], pos: usize, big_endian: bool) -> u32 {
+    let mut buf = [0_u8; 4];
+    if big_endian {
+        buf[4 - N..].copy_from_slice(&bytes[pos..pos + N]);
+        u32::from_be_bytes(buf)
+    } else {
+        buf[..N].copy_from_slice(&bytes[pos..pos + N]);
+        u32::from_le_bytes(buf)
+    }
+}
+
+/// Writes the low `N` bytes of `val` at `pos`, little endian or big endian
+fn write_uint<const N: usize>(bytes: &mut [u8], pos: usize, val: u32, big_endian: bool) {
+    if big_endian {
+        bytes[pos..pos + N].copy_from_slice(&val.to_be_bytes()[4 - N..]);
+    } else {
+        bytes[pos..pos + N].copy_from_slice(&val.to_le_bytes()[..N]);
+    }
+}
+
+/// Walks the arithmetics and interesting values of width `N` over `bytes`
+fn walk_values<const N: usize, F>(
+    bytes: &mut [u8],
+    interesting: &[u32],
+    f: &mut F,
+) -> Result<(), Error>
+where
+    F: FnMut(&[u8]) -> Result<(), Error>,
+{
+    let mask = u32::MAX >> (32 - N * 8);
+    let orders: &[bool] = if N == 1 { &[false] } else { &[false, true] };
+    if bytes.len() < N {
+        return Ok(());
+    }
+    for pos in 0..=bytes.len() - N {
+        for &big_endian in orders {
+            let orig = read_uint::<N>(bytes, pos, big_endian);
+            let mut try_val = |bytes: &mut [u8], val: u32| -> Result<(), Error> {
+                let val = val & mask;
+                if could_be_bitflip(orig ^ val) {
+                    return Ok(());
+                }
+                write_uint::<N>(bytes, pos, val, big_endian);
+                let res = f(bytes);
+                write_uint::<N>(bytes, pos, orig, big_endian);
+                res
+            };
+            for j in 1..=ARITH_MAX as u32 {
+                try_val(bytes, orig.wrapping_add(j))?;
+                try_val(bytes, orig.wrapping_sub(j))?;
+            }
+            for &val in interesting {
+                try_val(bytes, val)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Calls `f` with every deterministic mutation of `bytes`, restoring `bytes` in between
+pub fn deterministic_walk<F>(bytes: &mut [u8], mut f: F) -> Result<(), Error>
+where
+    F: FnMut(&[u8]) -> Result<(), Error>,
+{
+    let bits = bytes.len() * 8;
+
+    // Bitflips of 1, 2 and 4 consecutive bits
+    for width in [1, 2, 4] {
+        for start in 0..bits.saturating_sub(width - 1) {
+            for bit in start..start + width {
+                bytes[bit >> 3] ^= 0x80 >> (bit & 7);
+            }
+            let res = f(bytes);
+            for bit in start..start + width {
+                bytes[bit >> 3] ^= 0x80 >> (bit & 7);
+            }
+            res?;
+        }
+    }
+
+    // Byte flips of 1, 2 and 4 consecutive bytes
+    for width in [1, 2, 4] {
+        for start in 0..bytes.len().saturating_sub(width - 1) {
+            bytes[start..start + width]
+                .iter_mut()
+                .for_each(|b| *b ^= 0xff);
+            let res = f(bytes);
+            bytes[start..start + width]
+                .iter_mut()
+                .for_each(|b| *b ^= 0xff);
+            res?;
+        }
+    }
+
+    // Arithmetics and interesting values, in both byte orders
+    #[allow(clippy::cast_sign_loss)]
+    let interesting_8: Vec<u32> = INTERESTING_8.iter().map(|v| *v as u32).collect();
+    #[allow(clippy::cast_sign_loss)]
+    let interesting_16: Vec<u32> = INTERESTING_16.iter().map(|v| *v as u32).collect();
+    #[allow(clippy::cast_sign_loss)]
+    let interesting_32: Vec<u32> = INTERESTING_32.iter().map(|v| *v as u32).collect();
+    walk_values::<1, F>(bytes, &interesting_8, &mut f)?;
+    walk_values::<2, F>(bytes, &interesting_16, &mut f)?;
+    walk_values::<4, F>(bytes, &interesting_32, &mut f)
+}
+
+/// A stage walking the deterministic mutations of AFL over each new testcase, once.
+/// The testcase is marked with a [`DeterministicDoneMetadata`] before the walk, so that a
+/// crash in the middle of it does not restart the walk over and over.
+#[derive(Clone, Debug)]
+pub struct DeterministicStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    max_len: Option<usize>,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for DeterministicStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        start_timer!(state);
+        let input = {
+            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+            if entry.has_metadata::<DeterministicDoneMetadata>() {
+                return Ok(());
+            }
+            entry.add_metadata(DeterministicDoneMetadata { executions: 0 });
+            entry.load_input()?.clone()
+        };
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+        if self
+            .max_len
+            .map_or(false, |max_len| input.bytes().len() > max_len)
+        {
+            return Ok(());
+        }
+
+        let executions = *state.executions();
+        let mut bytes = input.bytes().to_vec();
+        deterministic_walk(&mut bytes, |candidate| {
+            let mut mutated = input.clone();
+            mutated.bytes_mut().clear();
+            mutated.bytes_mut().extend_from_slice(candidate);
+            fuzzer.evaluate_input(state, executor, manager, mutated)?;
+            Ok(())
+        })?;
+
+        let executions = *state.executions() - executions;
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(DeterministicDoneMetadata { executions });
+        Ok(())
+    }
+}
+
+impl<E, EM, I, S, Z> DeterministicStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    /// Creates a new [`DeterministicStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_len: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Skip the testcases longer than `max_len` bytes, their walk being too long
+    #[must_use]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+}
+
+impl<E, EM, I, S, Z> Default for DeterministicStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{could_be_bitflip, deterministic_walk};
+
+    #[test]
+    fn test_could_be_bitflip() {
+        assert!(could_be_bitflip(0b1000));
+        assert!(could_be_bitflip(0b0110_0000));
+        assert!(could_be_bitflip(0xff00));
+        assert!(!could_be_bitflip(0x0ff0));
+        assert!(!could_be_bitflip(0b101));
+    }
+
+    #[test]
+    fn test_deterministic_walk() {
+        let mut bytes = vec![0_u8, 0];
+        let mut candidates = vec![];
+        deterministic_walk(&mut bytes, |candidate| {
+            candidates.push(candidate.to_vec());
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(bytes, vec![0, 0]);
+        assert!(candidates.iter().all(|c| c.len() == 2 && c != &[0, 0]));
+        // A bitflip, an arithmetic, an interesting byte and a big endian interesting word
+        assert!(candidates.contains(&vec![0x80, 0]));
+        assert!(candidates.contains(&vec![0x23, 0]));
+        assert!(candidates.contains(&vec![0, 100]));
+        assert!(candidates.contains(&vec![0x03, 0xe8]));
+        // Subtracting one flips all the bits, the byte flips already did
+        assert_eq!(
+            candidates.iter().filter(|c| c == &&vec![0xff, 0]).count(),
+            1
+        );
+    }
+}
@@ -1,4 +1,10 @@
-//! The tracing stage can trace the target and enrich a testcase with metadata, for example for `CmpLog`.
+//! The generalization stage finds the gaps of a testcase for Grimoire: the chunks of the input
+//! that can be removed while the target still reaches the new coverage of the testcase,
+//! or, with [`GeneralizationStage::with_exact_coverage`], the very same coverage.
+//!
+//! The generalized form is set in the [`GeneralizedInput`] and kept in a
+//! [`GeneralizedInputMetadata`] of the testcase. The chunks between its gaps are learned as
+//! [`GrimoireLearnedTokens`].
 
 use alloc::{
     string::{String, ToString},
@@ -13,8 +19,9 @@ use crate::{
     corpus::Corpus,
     executors::{Executor, HasObservers},
     feedbacks::map::MapNoveltiesMetadata,
-    inputs::{GeneralizedInput, GeneralizedItem, HasBytesVec},
+    inputs::{GeneralizedInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec},
     mark_feature_time,
+    mutators::GrimoireLearnedTokens,
     observers::{MapObserver, ObserversTuple},
     stages::Stage,
     start_timer,
@@ -34,7 +41,10 @@ pub struct GeneralizedIndexesMetadata {
     pub indexes: HashSet<usize>,
 }
 
-crate::impl_serdeany!(GeneralizedIndexesMetadata);
+crate::impl_serdeany!(
+    GeneralizedIndexesMetadata,
+    "libafl::stages::generalization::GeneralizedIndexesMetadata"
+);
 
 impl GeneralizedIndexesMetadata {
     /// Create the metadata
@@ -58,7 +68,7 @@ fn find_next_char(list: &[Option<u8>], mut idx: usize, ch: u8) -> usize {
     idx
 }
 
-/// A stage that runs a tracer executor
+/// A stage generalizing each testcase once, see the [module documentation](self)
 #[derive(Clone, Debug)]
 pub struct GeneralizationStage<EM, O, OT, S, Z>
 where
@@ -67,6 +77,9 @@ where
     S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<GeneralizedInput>,
 {
     map_observer_name: String,
+    exact_coverage: bool,
+    /// The hash of the map for the testcase being generalized, with `exact_coverage`
+    expected_hash: Option<u64>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(EM, O, OT, S, Z)>,
 }
@@ -101,7 +114,16 @@ where
             state.corpus().get(corpus_idx)?.borrow_mut().load_input()?;
             mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
             let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+
+            // Generalized before, but mutated or reloaded since
+            let restored = entry
+                .metadata()
+                .get::<GeneralizedInputMetadata>()
+                .map(|meta| meta.generalized.clone());
             let input = entry.input_mut().as_mut().unwrap();
+            if input.generalized().is_none() {
+                *input.generalized_mut() = restored;
+            }
 
             if input.generalized().is_some() {
                 drop(entry);
@@ -126,9 +148,19 @@ where
         };
 
         // Do not generalized unstable inputs
+        self.expected_hash = None;
         if !self.verify_input(fuzzer, executor, state, manager, &novelties, &original)? {
             return Ok(());
         }
+        if self.exact_coverage {
+            self.expected_hash = Some(
+                executor
+                    .observers()
+                    .match_name::<O>(&self.map_observer_name)
+                    .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
+                    .hash(),
+            );
+        }
 
         self.find_gaps(
             fuzzer,
@@ -315,14 +347,15 @@ where
 
         if payload.len() <= MAX_GENERALIZED_LEN {
             // Save the modified input in the corpus
-            {
+            let generalized = {
                 let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
                 entry.load_input()?;
-                entry
-                    .input_mut()
-                    .as_mut()
-                    .unwrap()
-                    .generalized_from_options(&payload);
+                let input = entry.input_mut().as_mut().unwrap();
+                input.generalized_from_options(&payload);
+                let generalized = input.generalized().unwrap().to_vec();
+                entry.add_metadata(GeneralizedInputMetadata {
+                    generalized: generalized.clone(),
+                });
                 entry.store_input()?;
 
                 debug_assert!(
@@ -333,7 +366,8 @@ where
                     entry.load_input()?.generalized().unwrap().last()
                         == Some(&GeneralizedItem::Gap)
                 );
-            }
+                generalized
+            };
 
             state
                 .metadata_mut()
@@ -341,6 +375,16 @@ where
                 .unwrap()
                 .indexes
                 .insert(corpus_idx);
+
+            // The chunks between the gaps are the tokens of the input language
+            if !state.has_metadata::<GrimoireLearnedTokens>() {
+                state.add_metadata(GrimoireLearnedTokens::new());
+            }
+            state
+                .metadata_mut()
+                .get_mut::<GrimoireLearnedTokens>()
+                .unwrap()
+                .learn(&generalized);
         }
 
         Ok(())
@@ -356,10 +400,7 @@ where
     /// Create a new [`GeneralizationStage`].
     #[must_use]
     pub fn new(map_observer: &O) -> Self {
-        Self {
-            map_observer_name: map_observer.name().to_string(),
-            phantom: PhantomData,
-        }
+        Self::from_name(map_observer.name())
     }
 
     /// Create a new [`GeneralizationStage`] from name
@@ -367,10 +408,20 @@ where
     pub fn from_name(map_observer_name: &str) -> Self {
         Self {
             map_observer_name: map_observer_name.to_string(),
+            exact_coverage: false,
+            expected_hash: None,
             phantom: PhantomData,
         }
     }
 
+    /// Only remove the chunks without which the map stays exactly the same,
+    /// rather than only keeping the new coverage of the testcase
+    #[must_use]
+    pub fn with_exact_coverage(mut self) -> Self {
+        self.exact_coverage = true;
+        self
+    }
+
     fn verify_input<E>(
         &self,
         fuzzer: &mut Z,
@@ -399,13 +450,13 @@ where
             .post_exec_all(state, input, &exit_kind)?;
         mark_feature_time!(state, PerfFeature::PostExecObservers);
 
-        let cnt = executor
+        let observer = executor
             .observers()
             .match_name::<O>(&self.map_observer_name)
-            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
-            .how_many_set(novelties);
+            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?;
+        let cnt = observer.how_many_set(novelties);
 
-        Ok(cnt == novelties.len())
+        Ok(cnt == novelties.len() && self.expected_hash.map_or(true, |h| h == observer.hash()))
     }
 
     fn trim_payload(payload: &mut Vec<Option<u8>>) {
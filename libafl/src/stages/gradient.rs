@@ -0,0 +1,545 @@
+//! A gradient-guided mutational stage, in the style of `NEUZZ`.
+//!
+//! A small neural network, built with [`autograd`], is trained incrementally to predict from the
+//! bytes of an input which map entries it covers. The gradient of a covered entry with respect to
+//! the input bytes tells which offsets influence it the most: those are the offsets the stage mutates.
+
+use alloc::vec::Vec;
+use autograd::{self as ag, ndarray::Array2, tensor_ops as T, EvalError};
+use core::{cmp::Ordering, marker::PhantomData};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{
+        rands::{Rand, StdRand},
+        AsSlice,
+    },
+    corpus::Corpus,
+    feedbacks::MapIndexesMetadata,
+    fuzzer::Evaluator,
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    stages::Stage,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// The default number of input bytes seen by the model
+pub const DEFAULT_GRADIENT_INPUT_LEN: usize = 1024;
+/// The default number of neurons of the hidden layer
+pub const DEFAULT_GRADIENT_HIDDEN: usize = 32;
+/// The default maximum number of map entries the model predicts
+pub const DEFAULT_GRADIENT_MAX_OUTPUTS: usize = 512;
+
+const LEARNING_RATE: f32 = 0.01;
+/// How many already known entries are replayed when training on new ones, against forgetting
+const REPLAYED_SAMPLES: usize = 16;
+
+/// A row vector, for the input and output layers
+fn row(values: &[f32]) -> Result<Array2<f32>, Error> {
+    matrix(1, values.len(), values)
+}
+
+fn matrix(rows: usize, cols: usize, values: &[f32]) -> Result<Array2<f32>, Error> {
+    Array2::from_shape_vec((rows, cols), values.to_vec())
+        .map_err(|e| Error::IllegalState(format!("Bad gradient model shape: {}", e)))
+}
+
+fn eval_error(e: EvalError) -> Error {
+    Error::Unknown(format!("Could not evaluate the gradient model: {:?}", e))
+}
+
+/// The output layer of the model: `relu(x * w1 + b1) * w2 + b2`, before the sigmoid
+fn logits<'g>(x: ag::Tensor<'g, f32>, weights: &[ag::Tensor<'g, f32>; 4]) -> ag::Tensor<'g, f32> {
+    let hidden = T::relu(T::matmul(x, weights[0]) + weights[1]);
+    T::matmul(hidden, weights[2]) + weights[3]
+}
+
+/// The surrogate model, stored in the state: one `ReLU` hidden layer and one sigmoid output
+/// for each tracked map entry. The weights are fed to an [`autograd`] graph for each step.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GradientModelMetadata {
+    input_len: usize,
+    hidden: usize,
+    max_outputs: usize,
+    /// output neuron -> map index
+    edges: Vec<usize>,
+    /// map index -> output neuron
+    outputs: HashMap<usize, usize>,
+    /// `input_len` x `hidden`
+    w1: Vec<f32>,
+    b1: Vec<f32>,
+    /// `hidden` x `max_outputs`
+    w2: Vec<f32>,
+    b2: Vec<f32>,
+    /// How many corpus entries were used for training so far
+    trained: usize,
+}
+
+crate::impl_serdeany!(
+    GradientModelMetadata,
+    "libafl::stages::gradient::GradientModelMetadata"
+);
+
+impl GradientModelMetadata {
+    /// Creates a new [`struct@GradientModelMetadata`] with randomly initialized weights
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new<R>(rand: &mut R, input_len: usize, hidden: usize, max_outputs: usize) -> Self
+    where
+        R: Rand,
+    {
+        let mut weight = |fan_in: usize| {
+            // Uniform in [-1/sqrt(fan_in), 1/sqrt(fan_in)]
+            let bound = 1.0 / libm::sqrtf(fan_in.max(1) as f32);
+            (rand.below(1 << 16) as f32 / (1 << 16) as f32 * 2.0 - 1.0) * bound
+        };
+        let w1 = (0..input_len * hidden).map(|_| weight(input_len)).collect();
+        let w2 = (0..hidden * max_outputs).map(|_| weight(hidden)).collect();
+        Self {
+            input_len,
+            hidden,
+            max_outputs,
+            edges: vec![],
+            outputs: HashMap::default(),
+            w1,
+            b1: vec![0.0; hidden],
+            w2,
+            b2: vec![0.0; max_outputs],
+            trained: 0,
+        }
+    }
+
+    /// The map entries predicted by the model
+    #[must_use]
+    pub fn edges(&self) -> &[usize] {
+        &self.edges
+    }
+
+    /// Scale the input bytes to the input layer
+    fn encode(&self, bytes: &[u8]) -> Vec<f32> {
+        let mut x = vec![0.0; self.input_len];
+        for (xi, b) in x.iter_mut().zip(bytes.iter()) {
+            *xi = f32::from(*b) / 255.0;
+        }
+        x
+    }
+
+    /// The weights as matrices, in the order of [`logits`]
+    fn weights(&self) -> Result<[Array2<f32>; 4], Error> {
+        Ok([
+            matrix(self.input_len, self.hidden, &self.w1)?,
+            row(&self.b1)?,
+            matrix(self.hidden, self.max_outputs, &self.w2)?,
+            row(&self.b2)?,
+        ])
+    }
+
+    /// Track the given map indexes as outputs, as long as there is room
+    fn register_edges(&mut self, indexes: &[usize]) {
+        for &idx in indexes {
+            if self.edges.len() >= self.max_outputs {
+                return;
+            }
+            if !self.outputs.contains_key(&idx) {
+                self.outputs.insert(idx, self.edges.len());
+                self.edges.push(idx);
+            }
+        }
+    }
+
+    /// One step of stochastic gradient descent on the binary cross entropy of a sample,
+    /// over the tracked outputs
+    fn train_sample(&mut self, bytes: &[u8], covered: &[usize]) -> Result<(), Error> {
+        let x = row(&self.encode(bytes))?;
+        let mut target = vec![0.0; self.max_outputs];
+        for idx in covered {
+            if let Some(&k) = self.outputs.get(idx) {
+                target[k] = 1.0;
+            }
+        }
+        let target = row(&target)?;
+        let mut tracked = vec![0.0; self.max_outputs];
+        tracked[..self.edges.len()].fill(1.0);
+        let tracked = row(&tracked)?;
+        let weights = self.weights()?;
+
+        let grads = ag::run(|ctx: &mut ag::Context<f32>| {
+            let input = ctx.placeholder("x", &[-1, -1]);
+            let t = ctx.placeholder("target", &[-1, -1]);
+            let mask = ctx.placeholder("tracked", &[-1, -1]);
+            let w = [
+                ctx.placeholder("w1", &[-1, -1]),
+                ctx.placeholder("b1", &[-1, -1]),
+                ctx.placeholder("w2", &[-1, -1]),
+                ctx.placeholder("b2", &[-1, -1]),
+            ];
+            let loss = T::reduce_sum(
+                T::sigmoid_cross_entropy(logits(input, &w), t) * mask,
+                &[0, 1],
+                false,
+            );
+            let grads = T::grad(&[loss], &w);
+            ctx.evaluator()
+                .push(&grads[0])
+                .push(&grads[1])
+                .push(&grads[2])
+                .push(&grads[3])
+                .feed(input, x.view())
+                .feed(t, target.view())
+                .feed(mask, tracked.view())
+                .feed(w[0], weights[0].view())
+                .feed(w[1], weights[1].view())
+                .feed(w[2], weights[2].view())
+                .feed(w[3], weights[3].view())
+                .run()
+        });
+
+        for (values, grad) in [&mut self.w1, &mut self.b1, &mut self.w2, &mut self.b2]
+            .into_iter()
+            .zip(grads)
+        {
+            let grad = grad.map_err(eval_error)?;
+            for (value, g) in values.iter_mut().zip(grad.iter()) {
+                *value -= LEARNING_RATE * g;
+            }
+        }
+        Ok(())
+    }
+
+    /// The probability of each output to be covered by the input
+    pub fn predict(&self, bytes: &[u8]) -> Result<Vec<f32>, Error> {
+        let x = row(&self.encode(bytes))?;
+        let weights = self.weights()?;
+        let mut out = ag::run(|ctx: &mut ag::Context<f32>| {
+            let input = ctx.placeholder("x", &[-1, -1]);
+            let w = [
+                ctx.placeholder("w1", &[-1, -1]),
+                ctx.placeholder("b1", &[-1, -1]),
+                ctx.placeholder("w2", &[-1, -1]),
+                ctx.placeholder("b2", &[-1, -1]),
+            ];
+            let probabilities = T::sigmoid(logits(input, &w));
+            ctx.evaluator()
+                .push(&probabilities)
+                .feed(input, x.view())
+                .feed(w[0], weights[0].view())
+                .feed(w[1], weights[1].view())
+                .feed(w[2], weights[2].view())
+                .feed(w[3], weights[3].view())
+                .run()
+        });
+        Ok(out.remove(0).map_err(eval_error)?.iter().copied().collect())
+    }
+
+    /// The gradient of the output neuron `k` with respect to each input byte
+    pub fn gradient(&self, bytes: &[u8], k: usize) -> Result<Vec<f32>, Error> {
+        let x = row(&self.encode(bytes))?;
+        let mut selected = vec![0.0; self.max_outputs];
+        selected[k] = 1.0;
+        let selected = row(&selected)?;
+        let weights = self.weights()?;
+        let mut grads = ag::run(|ctx: &mut ag::Context<f32>| {
+            let input = ctx.placeholder("x", &[-1, -1]);
+            let mask = ctx.placeholder("selected", &[-1, -1]);
+            let w = [
+                ctx.placeholder("w1", &[-1, -1]),
+                ctx.placeholder("b1", &[-1, -1]),
+                ctx.placeholder("w2", &[-1, -1]),
+                ctx.placeholder("b2", &[-1, -1]),
+            ];
+            let output = T::reduce_sum(logits(input, &w) * mask, &[0, 1], false);
+            let grads = T::grad(&[output], &[input]);
+            ctx.evaluator()
+                .push(&grads[0])
+                .feed(input, x.view())
+                .feed(mask, selected.view())
+                .feed(w[0], weights[0].view())
+                .feed(w[1], weights[1].view())
+                .feed(w[2], weights[2].view())
+                .feed(w[3], weights[3].view())
+                .run()
+        });
+        Ok(grads
+            .remove(0)
+            .map_err(eval_error)?
+            .iter()
+            .copied()
+            .collect())
+    }
+}
+
+/// The offsets with the highest gradient found for a testcase, and the sign of their gradient.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GradientHotOffsetsMetadata {
+    /// (offset, the byte should increase)
+    pub offsets: Vec<(usize, bool)>,
+}
+
+crate::impl_serdeany!(GradientHotOffsetsMetadata);
+
+/// A mutational stage mutating the bytes with the highest gradient, according to the model
+/// in the [`struct@GradientModelMetadata`]. The model is trained on each new corpus entry,
+/// which needs a [`MapIndexesMetadata`], i.e. the map feedback must track indexes.
+#[derive(Clone, Debug)]
+pub struct GradientMutationalStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasRand + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    top_k: usize,
+    iterations: usize,
+    input_len: usize,
+    hidden: usize,
+    max_outputs: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+impl<E, EM, I, S, Z> GradientMutationalStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasRand + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    /// Creates a new [`GradientMutationalStage`], mutating the `top_k` offsets with the highest gradient in `iterations` executions
+    #[must_use]
+    pub fn new(top_k: usize, iterations: usize) -> Self {
+        Self::with_model_size(
+            top_k,
+            iterations,
+            DEFAULT_GRADIENT_INPUT_LEN,
+            DEFAULT_GRADIENT_HIDDEN,
+            DEFAULT_GRADIENT_MAX_OUTPUTS,
+        )
+    }
+
+    /// Creates a new [`GradientMutationalStage`] with a model of the given size
+    #[must_use]
+    pub fn with_model_size(
+        top_k: usize,
+        iterations: usize,
+        input_len: usize,
+        hidden: usize,
+        max_outputs: usize,
+    ) -> Self {
+        Self {
+            top_k,
+            iterations,
+            input_len,
+            hidden,
+            max_outputs,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Train the model on the corpus entries added since the last call
+    fn update_model(&self, state: &mut S) -> Result<(), Error> {
+        let mut rand = StdRand::with_seed(state.rand_mut().next());
+        if !state.has_metadata::<GradientModelMetadata>() {
+            let model = GradientModelMetadata::new(
+                &mut rand,
+                self.input_len,
+                self.hidden,
+                self.max_outputs,
+            );
+            state.add_metadata(model);
+        }
+
+        let trained = state
+            .metadata()
+            .get::<GradientModelMetadata>()
+            .unwrap()
+            .trained;
+        let count = state.corpus().count();
+        if trained >= count {
+            return Ok(());
+        }
+
+        let mut samples = vec![];
+        let mut sample_at = |idx: usize, samples: &mut Vec<(Vec<u8>, Vec<usize>)>| {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            let indexes = testcase
+                .metadata()
+                .get::<MapIndexesMetadata>()
+                .map(|m| m.as_slice().to_vec());
+            if let Some(indexes) = indexes {
+                samples.push((testcase.load_input()?.bytes().to_vec(), indexes));
+            }
+            Ok::<(), Error>(())
+        };
+        let new_samples = count - trained;
+        for idx in trained..count {
+            sample_at(idx, &mut samples)?;
+        }
+        if trained > 0 {
+            for _ in 0..REPLAYED_SAMPLES.min(trained) {
+                sample_at(rand.below(trained as u64) as usize, &mut samples)?;
+            }
+        }
+
+        let model = state
+            .metadata_mut()
+            .get_mut::<GradientModelMetadata>()
+            .unwrap();
+        for (_, indexes) in samples.iter().take(new_samples) {
+            model.register_edges(indexes);
+        }
+        for (bytes, indexes) in &samples {
+            model.train_sample(bytes, indexes)?;
+        }
+        model.trained = count;
+        Ok(())
+    }
+
+    /// Compute the hot offsets of the testcase at `corpus_idx` for a random covered map entry
+    fn hot_offsets(&self, state: &mut S, corpus_idx: usize) -> Result<Vec<(usize, bool)>, Error> {
+        let (bytes, indexes) = {
+            let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+            let indexes = testcase
+                .metadata()
+                .get::<MapIndexesMetadata>()
+                .map(|m| m.as_slice().to_vec())
+                .unwrap_or_default();
+            (testcase.load_input()?.bytes().to_vec(), indexes)
+        };
+
+        let pick = state.rand_mut().next();
+        let model = state.metadata().get::<GradientModelMetadata>().unwrap();
+        // Like NEUZZ, follow the gradient of a random output; prefer one covered by this entry
+        let covered: Vec<usize> = indexes
+            .iter()
+            .filter_map(|idx| model.outputs.get(idx).copied())
+            .collect();
+        let k = if covered.is_empty() {
+            if model.edges.is_empty() {
+                return Ok(vec![]);
+            }
+            (pick % model.edges.len() as u64) as usize
+        } else {
+            covered[(pick % covered.len() as u64) as usize]
+        };
+
+        let grad = model.gradient(&bytes, k)?;
+        let mut offsets: Vec<usize> = (0..bytes.len().min(grad.len())).collect();
+        offsets.sort_unstable_by(|a, b| {
+            grad[*b]
+                .abs()
+                .partial_cmp(&grad[*a].abs())
+                .unwrap_or(Ordering::Equal)
+        });
+        offsets.truncate(self.top_k);
+        Ok(offsets
+            .into_iter()
+            .map(|off| (off, grad[off] > 0.0))
+            .collect())
+    }
+
+    /// Mutates the hot offsets of the testcase at `corpus_idx`, `iterations` times
+    fn perform_gradient(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.update_model(state)?;
+        let hot = self.hot_offsets(state, corpus_idx)?;
+        if hot.is_empty() {
+            return Ok(());
+        }
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(GradientHotOffsetsMetadata {
+                offsets: hot.clone(),
+            });
+
+        start_timer!(state);
+        let original = state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .load_input()?
+            .clone();
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+        for _ in 0..self.iterations {
+            start_timer!(state);
+            let mut input = original.clone();
+            // Mutate a growing prefix of the hottest offsets, in the direction of the gradient
+            let count = 1 + state.rand_mut().below(hot.len() as u64) as usize;
+            for &(off, increase) in &hot[..count] {
+                let step = 1 + state.rand_mut().below(32) as u8;
+                let byte = &mut input.bytes_mut()[off];
+                *byte = if increase {
+                    byte.saturating_add(step)
+                } else {
+                    byte.saturating_sub(step)
+                };
+            }
+            mark_feature_time!(state, PerfFeature::Mutate);
+
+            // Time is measured directly the `evaluate_input` function
+            fuzzer.evaluate_input(state, executor, manager, input)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for GradientMutationalStage<E, EM, I, S, Z>
+where
+    I: Input + HasBytesVec,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasRand + HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let ret = self.perform_gradient(fuzzer, executor, state, manager, corpus_idx);
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bolts::rands::StdRand, stages::gradient::GradientModelMetadata};
+
+    #[test]
+    fn test_gradient_model_learns() {
+        let mut rand = StdRand::with_seed(1337);
+        let mut model = GradientModelMetadata::new(&mut rand, 8, 8, 4);
+        model.register_edges(&[3, 7]);
+
+        // Entry 3 is covered iff the first byte is high
+        let high = [255, 0, 0, 0, 0, 0, 0, 0];
+        let low = [0; 8];
+        let before = model.predict(&high).unwrap()[0];
+        for _ in 0..200 {
+            model.train_sample(&high, &[3]).unwrap();
+            model.train_sample(&low, &[]).unwrap();
+        }
+        assert!(model.predict(&high).unwrap()[0] > before);
+        assert!(model.predict(&high).unwrap()[0] > model.predict(&low).unwrap()[0]);
+
+        // Raising the first byte raises the prediction of entry 3
+        let gradient = model.gradient(&high, 0).unwrap();
+        assert_eq!(gradient.len(), 8);
+        assert!(gradient[0] > 0.0);
+    }
+}
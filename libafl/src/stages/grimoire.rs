@@ -0,0 +1,123 @@
+//! The [`GrimoireMutationalStage`] runs the Grimoire mutations on the generalized testcases only,
+//! for text-like targets without a grammar.
+//! Put it after a [`crate::stages::GeneralizationStage`], which generalizes the testcases.
+
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::{GeneralizedInput, GeneralizedInputMetadata},
+    mutators::Mutator,
+    stages::{
+        mutational::{MutationalStage, DEFAULT_MUTATIONAL_MAX_ITERATIONS},
+        Stage,
+    },
+    state::{HasClientPerfMonitor, HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// A mutational stage for the Grimoire mutations, see [`crate::mutators::grimoire_mutations`].
+/// The testcases the [`crate::stages::GeneralizationStage`] did not generalize are skipped.
+#[derive(Clone, Debug)]
+pub struct GrimoireMutationalStage<E, EM, M, S, Z>
+where
+    M: Mutator<GeneralizedInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    mutator: M,
+    max_iterations: u64,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<E, EM, M, S, Z> MutationalStage<E, EM, GeneralizedInput, M, S, Z>
+    for GrimoireMutationalStage<E, EM, M, S, Z>
+where
+    M: Mutator<GeneralizedInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    /// The mutator, added to this stage
+    #[inline]
+    fn mutator(&self) -> &M {
+        &self.mutator
+    }
+
+    /// The list of mutators, added to this stage (as mutable ref)
+    #[inline]
+    fn mutator_mut(&mut self) -> &mut M {
+        &mut self.mutator
+    }
+
+    /// Gets the number of iterations as a random number
+    fn iterations(&self, state: &mut S, _corpus_idx: usize) -> Result<usize, Error> {
+        Ok(1 + state.rand_mut().below(self.max_iterations) as usize)
+    }
+}
+
+impl<E, EM, M, S, Z> Stage<E, EM, S, Z> for GrimoireMutationalStage<E, EM, M, S, Z>
+where
+    M: Mutator<GeneralizedInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        {
+            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+            let restored = if entry.load_input()?.generalized().is_some() {
+                None
+            } else {
+                match entry.metadata().get::<GeneralizedInputMetadata>() {
+                    Some(meta) => Some(meta.generalized.clone()),
+                    // Not generalized, nothing for Grimoire to work with
+                    None => return Ok(()),
+                }
+            };
+            if let Some(generalized) = restored {
+                *entry.input_mut().as_mut().unwrap().generalized_mut() = Some(generalized);
+            }
+        }
+
+        let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        ret
+    }
+}
+
+impl<E, EM, M, S, Z> GrimoireMutationalStage<E, EM, M, S, Z>
+where
+    M: Mutator<GeneralizedInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    /// Creates a new [`GrimoireMutationalStage`], running between 1 and
+    /// [`DEFAULT_MUTATIONAL_MAX_ITERATIONS`] mutations per testcase
+    pub fn new(mutator: M) -> Self {
+        Self {
+            mutator,
+            max_iterations: DEFAULT_MUTATIONAL_MAX_ITERATIONS,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Run between 1 and `max_iterations` mutations per testcase
+    #[must_use]
+    pub fn with_max_iterations(mut self, max_iterations: u64) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+}
@@ -0,0 +1,236 @@
+//! Stages wrapping other stages to decide when they run: on a condition, every few executions,
+//! or every once in a while.
+//! The wrapped stages are a [`StagesTuple`], so a whole pipeline can be made conditional.
+
+use core::{marker::PhantomData, time::Duration};
+
+use crate::{
+    bolts::current_time,
+    stages::{Stage, StagesTuple},
+    state::HasExecutions,
+    Error,
+};
+
+/// A stage running the wrapped stages only if the closure returns `true`
+#[derive(Debug)]
+pub struct IfStage<CB, E, EM, S, ST, Z>
+where
+    CB: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<bool, Error>,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    closure: CB,
+    if_stages: ST,
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<CB, E, EM, S, ST, Z> Stage<E, EM, S, Z> for IfStage<CB, E, EM, S, ST, Z>
+where
+    CB: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<bool, Error>,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if (self.closure)(fuzzer, executor, state, manager, corpus_idx)? {
+            self.if_stages
+                .perform_all(fuzzer, executor, state, manager, corpus_idx)?;
+        }
+        Ok(())
+    }
+}
+
+impl<CB, E, EM, S, ST, Z> IfStage<CB, E, EM, S, ST, Z>
+where
+    CB: FnMut(&mut Z, &mut E, &mut S, &mut EM, usize) -> Result<bool, Error>,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    /// Create a new [`IfStage`], running `if_stages` when `closure` returns `true`
+    pub fn new(closure: CB, if_stages: ST) -> Self {
+        Self {
+            closure,
+            if_stages,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A stage running the wrapped stages once at least `n` executions happened since their last run.
+/// The first time, they run right away.
+#[derive(Debug)]
+pub struct EveryNExecsStage<E, EM, S, ST, Z>
+where
+    S: HasExecutions,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    n: usize,
+    last_run: Option<usize>,
+    stages: ST,
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for EveryNExecsStage<E, EM, S, ST, Z>
+where
+    S: HasExecutions,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let executions = *state.executions();
+        if self
+            .last_run
+            .map_or(true, |last_run| executions - last_run >= self.n)
+        {
+            self.last_run = Some(executions);
+            self.stages
+                .perform_all(fuzzer, executor, state, manager, corpus_idx)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E, EM, S, ST, Z> EveryNExecsStage<E, EM, S, ST, Z>
+where
+    S: HasExecutions,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    /// Create a new [`EveryNExecsStage`], running `stages` every `n` executions
+    pub fn new(n: usize, stages: ST) -> Self {
+        Self {
+            n,
+            last_run: None,
+            stages,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A stage running the wrapped stages once their `timeout` expired since their last run,
+/// e.g. to sync with other fuzzers every ten minutes.
+/// The first time, they run right away.
+#[derive(Debug)]
+pub struct TimeoutStage<E, EM, S, ST, Z>
+where
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    timeout: Duration,
+    last_run: Option<Duration>,
+    stages: ST,
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for TimeoutStage<E, EM, S, ST, Z>
+where
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if self.last_run.map_or(true, |last_run| {
+            now.saturating_sub(last_run) >= self.timeout
+        }) {
+            self.last_run = Some(now);
+            self.stages
+                .perform_all(fuzzer, executor, state, manager, corpus_idx)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E, EM, S, ST, Z> TimeoutStage<E, EM, S, ST, Z>
+where
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    /// Create a new [`TimeoutStage`], running `stages` every `timeout`
+    pub fn new(timeout: Duration, stages: ST) -> Self {
+        Self {
+            timeout,
+            last_run: None,
+            stages,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, time::Duration};
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        inputs::BytesInput,
+        stages::{ClosureStage, EveryNExecsStage, IfStage, Stage, TimeoutStage},
+        state::{HasExecutions, StdState},
+        Error,
+    };
+
+    #[test]
+    fn test_logic_stages() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let runs = Cell::new(0);
+        let count = |_: &mut (), _: &mut (), _: &mut _, _: &mut (), _| -> Result<(), Error> {
+            runs.set(runs.get() + 1);
+            Ok(())
+        };
+
+        let mut stage = IfStage::new(
+            |_: &mut (), _: &mut (), _: &mut _, _: &mut (), idx| Ok(idx == 1),
+            tuple_list!(ClosureStage::new(count)),
+        );
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 1)
+            .unwrap();
+        assert_eq!(runs.get(), 1);
+
+        let mut stage = EveryNExecsStage::new(10, tuple_list!(ClosureStage::new(count)));
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        *state.executions_mut() += 9;
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        *state.executions_mut() += 1;
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(runs.get(), 3);
+
+        let mut stage = TimeoutStage::new(
+            Duration::from_secs(3600),
+            tuple_list!(ClosureStage::new(count)),
+        );
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(runs.get(), 4);
+    }
+}
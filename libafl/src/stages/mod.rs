@@ -6,7 +6,9 @@ Other stages may enrich [`crate::corpus::Testcase`]s with metadata.
 
 /// Mutational stage is the normal fuzzing stage.
 pub mod mutational;
-pub use mutational::{MutationalStage, StdMutationalStage};
+pub use mutational::{
+    LenTimeIterations, MutationalIterations, MutationalStage, RandIterations, StdMutationalStage,
+};
 
 pub mod push;
 
@@ -14,7 +16,7 @@ pub mod tracing;
 pub use tracing::{ShadowTracingStage, TracingStage};
 
 pub mod calibrate;
-pub use calibrate::{CalibrationStage, PowerScheduleMetadata};
+pub use calibrate::{CalibrationMetadata, CalibrationStage, PowerScheduleMetadata};
 
 pub mod power;
 pub use power::PowerMutationalStage;
@@ -22,9 +24,47 @@ pub use power::PowerMutationalStage;
 pub mod generalization;
 pub use generalization::GeneralizationStage;
 
+pub mod grimoire;
+pub use grimoire::GrimoireMutationalStage;
+
 pub mod owned;
 pub use owned::StagesOwnedList;
 
+pub mod profiler;
+pub use profiler::{ProfiledStage, ProfilerMetadata, ProfilerReportStage};
+
+pub mod logics;
+pub use logics::{EveryNExecsStage, IfStage, TimeoutStage};
+
+pub mod repeat;
+pub use repeat::RepeatRunsStage;
+
+pub mod replay;
+pub use replay::ReplayReceivedStage;
+
+pub mod resume;
+pub use resume::{IndexedStagesTuple, ResumableStages, StageProgress, StageProgressMetadata};
+
+pub mod cmin;
+pub use cmin::{CorpusMinimizerMetadata, CorpusMinimizerStage};
+
+pub mod prune;
+pub use prune::CorpusPruningStage;
+
+pub mod colorization;
+pub use colorization::ColorizationStage;
+
+pub mod deterministic;
+pub use deterministic::{DeterministicDoneMetadata, DeterministicStage};
+
+pub mod tmin;
+pub use tmin::{MinimizedInputMetadata, TMinMutationalStage};
+
+#[cfg(feature = "gradient_mutation")]
+pub mod gradient;
+#[cfg(feature = "gradient_mutation")]
+pub use gradient::{GradientModelMetadata, GradientMutationalStage};
+
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
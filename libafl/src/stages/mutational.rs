@@ -10,9 +10,9 @@ use crate::{
     inputs::Input,
     mark_feature_time,
     mutators::Mutator,
-    stages::Stage,
+    stages::{PowerScheduleMetadata, Stage},
     start_timer,
-    state::{HasClientPerfMonitor, HasCorpus, HasRand},
+    state::{HasClientPerfMonitor, HasCorpus, HasMetadata, HasRand},
     Error,
 };
 
@@ -81,26 +81,128 @@ where
 /// It may randomly continue earlier.
 pub static DEFAULT_MUTATIONAL_MAX_ITERATIONS: u64 = 128;
 
-/// The default mutational stage
+/// Decides how many mutations a [`MutationalStage`] runs on a testcase
+pub trait MutationalIterations<I, S>
+where
+    I: Input,
+{
+    /// Gets the number of iterations for the testcase at `corpus_idx`
+    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error>;
+}
+
+/// A random number of iterations, between 1 and `max_iterations`
+#[derive(Clone, Copy, Debug)]
+pub struct RandIterations {
+    max_iterations: u64,
+}
+
+impl<I, S> MutationalIterations<I, S> for RandIterations
+where
+    I: Input,
+    S: HasRand,
+{
+    fn iterations(&self, state: &mut S, _corpus_idx: usize) -> Result<usize, Error> {
+        Ok(1 + state.rand_mut().below(self.max_iterations) as usize)
+    }
+}
+
+impl RandIterations {
+    /// Between 1 and `max_iterations` iterations
+    #[must_use]
+    pub fn new(max_iterations: u64) -> Self {
+        Self { max_iterations }
+    }
+}
+
+impl Default for RandIterations {
+    /// Between 1 and [`DEFAULT_MUTATIONAL_MAX_ITERATIONS`] iterations
+    fn default() -> Self {
+        Self::new(DEFAULT_MUTATIONAL_MAX_ITERATIONS)
+    }
+}
+
+/// The input length from which [`LenTimeIterations`] starts to reduce the iterations
+pub const LEN_TIME_ITERATIONS_LONG_INPUT: usize = 1024;
+
+/// A number of iterations derived from the exec time and the length of the testcase.
+///
+/// Starting from `base_iterations`, the testcases faster than the average calibrated exec time
+/// get up to four times more iterations, the slower ones down to a tenth, and every
+/// [`LEN_TIME_ITERATIONS_LONG_INPUT`] bytes of input halve them again.
+#[derive(Clone, Copy, Debug)]
+pub struct LenTimeIterations {
+    base_iterations: u64,
+}
+
+impl<I, S> MutationalIterations<I, S> for LenTimeIterations
+where
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+{
+    #[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error> {
+        let avg_exec_time = state
+            .metadata()
+            .get::<PowerScheduleMetadata>()
+            .filter(|psmeta| psmeta.cycles() > 0)
+            .map(|psmeta| psmeta.exec_time().as_nanos() as f64 / psmeta.cycles() as f64);
+
+        let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+        let mut iterations = self.base_iterations as f64;
+        if let (Some(avg), Some(exec_time)) = (avg_exec_time, *testcase.exec_time()) {
+            let exec_time = exec_time.as_nanos() as f64;
+            if exec_time > 0.0 {
+                iterations *= (avg / exec_time).clamp(0.1, 4.0);
+            }
+        }
+        let halvings = testcase.cached_len()? / LEN_TIME_ITERATIONS_LONG_INPUT;
+        iterations /= f64::from(1_u32 << halvings.min(16));
+
+        Ok((iterations as usize).max(1))
+    }
+}
+
+impl LenTimeIterations {
+    /// Around `base_iterations` iterations for an average testcase
+    #[must_use]
+    pub fn new(base_iterations: u64) -> Self {
+        Self { base_iterations }
+    }
+}
+
+impl Default for LenTimeIterations {
+    /// Around [`DEFAULT_MUTATIONAL_MAX_ITERATIONS`] iterations for an average testcase
+    fn default() -> Self {
+        Self::new(DEFAULT_MUTATIONAL_MAX_ITERATIONS)
+    }
+}
+
+/// The default mutational stage.
+/// The number of iterations comes from the [`MutationalIterations`] policy `P`,
+/// by default a random number, see [`RandIterations`].
 #[derive(Clone, Debug)]
-pub struct StdMutationalStage<E, EM, I, M, S, Z>
+pub struct StdMutationalStage<E, EM, I, M, S, Z, P = RandIterations>
 where
     M: Mutator<I, S>,
     I: Input,
     S: HasClientPerfMonitor + HasCorpus<I> + HasRand,
     Z: Evaluator<E, EM, I, S>,
+    P: MutationalIterations<I, S>,
 {
     mutator: M,
+    iterations: P,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, S, Z)>,
 }
 
-impl<E, EM, I, M, S, Z> MutationalStage<E, EM, I, M, S, Z> for StdMutationalStage<E, EM, I, M, S, Z>
+impl<E, EM, I, M, S, Z, P> MutationalStage<E, EM, I, M, S, Z>
+    for StdMutationalStage<E, EM, I, M, S, Z, P>
 where
     M: Mutator<I, S>,
     I: Input,
     S: HasClientPerfMonitor + HasCorpus<I> + HasRand,
     Z: Evaluator<E, EM, I, S>,
+    P: MutationalIterations<I, S>,
 {
     /// The mutator, added to this stage
     #[inline]
@@ -114,18 +216,19 @@ where
         &mut self.mutator
     }
 
-    /// Gets the number of iterations as a random number
-    fn iterations(&self, state: &mut S, _corpus_idx: usize) -> Result<usize, Error> {
-        Ok(1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize)
+    /// Gets the number of iterations from the [`MutationalIterations`] policy
+    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error> {
+        self.iterations.iterations(state, corpus_idx)
     }
 }
 
-impl<E, EM, I, M, S, Z> Stage<E, EM, S, Z> for StdMutationalStage<E, EM, I, M, S, Z>
+impl<E, EM, I, M, S, Z, P> Stage<E, EM, S, Z> for StdMutationalStage<E, EM, I, M, S, Z, P>
 where
     M: Mutator<I, S>,
     I: Input,
     S: HasClientPerfMonitor + HasCorpus<I> + HasRand,
     Z: Evaluator<E, EM, I, S>,
+    P: MutationalIterations<I, S>,
 {
     #[inline]
     #[allow(clippy::let_and_return)]
@@ -155,13 +258,63 @@ where
 {
     /// Creates a new default mutational stage
     pub fn new(mutator: M) -> Self {
+        Self::with_iterations(mutator, RandIterations::default())
+    }
+}
+
+impl<E, EM, I, M, S, Z, P> StdMutationalStage<E, EM, I, M, S, Z, P>
+where
+    M: Mutator<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasRand,
+    Z: Evaluator<E, EM, I, S>,
+    P: MutationalIterations<I, S>,
+{
+    /// Creates a new mutational stage, running as many iterations as `iterations` decides
+    pub fn with_iterations(mutator: M, iterations: P) -> Self {
         Self {
             mutator,
+            iterations,
             phantom: PhantomData,
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        stages::{LenTimeIterations, MutationalIterations, PowerScheduleMetadata},
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_len_time_iterations() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let mut testcase = Testcase::new(BytesInput::new(vec![0; 2048]));
+        testcase.set_exec_time(Duration::from_millis(1));
+        corpus.add(testcase).unwrap();
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+
+        let policy = LenTimeIterations::new(128);
+        // Not calibrated yet, only the length counts
+        let iterations = MutationalIterations::<BytesInput, _>::iterations(&policy, &mut state, 0);
+        assert_eq!(iterations.unwrap(), 32);
+
+        // Twice as fast as the average
+        let mut psmeta = PowerScheduleMetadata::new();
+        psmeta.set_exec_time(Duration::from_millis(4));
+        psmeta.set_cycles(2);
+        state.add_metadata(psmeta);
+        let iterations = MutationalIterations::<BytesInput, _>::iterations(&policy, &mut state, 0);
+        assert_eq!(iterations.unwrap(), 64);
+    }
+}
+
 #[cfg(feature = "python")]
 /// `StdMutationalStage` Python bindings
 pub mod pybind {
@@ -10,9 +10,9 @@ use crate::{
     inputs::Input,
     mutators::Mutator,
     observers::{MapObserver, ObserversTuple},
-    schedulers::minimizer::IsFavoredMetadata,
-    stages::{MutationalStage, PowerScheduleMetadata, Stage},
-    state::{HasClientPerfMonitor, HasCorpus, HasMetadata},
+    schedulers::minimizer::{IsFavoredMetadata, TopRatedsMetadata},
+    stages::{MutationalIterations, MutationalStage, PowerScheduleMetadata, Stage},
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
     Error,
 };
 
@@ -26,180 +26,20 @@ pub enum PowerSchedule {
     LIN,
     QUAD,
     EXPLOIT,
+    RARE,
 }
 
 const POWER_BETA: f64 = 1.0;
 const MAX_FACTOR: f64 = POWER_BETA * 32.0;
 const HAVOC_MAX_MULT: f64 = 64.0;
 
-/// The mutational stage using power schedules
-#[derive(Clone, Debug)]
-pub struct PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
-where
-    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
-    I: Input,
-    M: Mutator<I, S>,
-    O: MapObserver,
-    OT: ObserversTuple<I, S>,
-    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata,
-    Z: Evaluator<E, EM, I, S>,
-{
-    map_observer_name: String,
-    mutator: M,
-    /// The employed power schedule strategy
-    strat: PowerSchedule,
-    #[allow(clippy::type_complexity)]
-    phantom: PhantomData<(E, EM, I, O, OT, S, Z)>,
-}
-
-impl<E, EM, I, M, O, OT, S, Z> MutationalStage<E, EM, I, M, S, Z>
-    for PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
-where
-    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
-    I: Input,
-    M: Mutator<I, S>,
-    O: MapObserver,
-    OT: ObserversTuple<I, S>,
-    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata,
-    Z: Evaluator<E, EM, I, S>,
-{
-    /// The mutator, added to this stage
-    #[inline]
-    fn mutator(&self) -> &M {
-        &self.mutator
-    }
-
-    /// The list of mutators, added to this stage (as mutable ref)
-    #[inline]
-    fn mutator_mut(&mut self) -> &mut M {
-        &mut self.mutator
-    }
-
-    /// Gets the number of iterations as a random number
-    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error> {
-        let psmeta = state
-            .metadata()
-            .get::<PowerScheduleMetadata>()
-            .ok_or_else(|| Error::KeyNotFound("PowerScheduleMetadata not found".to_string()))?;
-
-        let mut fuzz_mu = 0.0;
-        if self.strat == PowerSchedule::COE {
-            fuzz_mu = self.fuzz_mu(state, psmeta)?;
-        }
-        let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
-
-        // 1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize
-        self.calculate_score(&mut testcase, psmeta, fuzz_mu)
-    }
-
-    #[allow(clippy::cast_possible_wrap)]
-    fn perform_mutational(
-        &mut self,
-        fuzzer: &mut Z,
-        executor: &mut E,
-        state: &mut S,
-        manager: &mut EM,
-        corpus_idx: usize,
-    ) -> Result<(), Error> {
-        let num = self.iterations(state, corpus_idx)?;
-
-        for i in 0..num {
-            let mut input = state
-                .corpus()
-                .get(corpus_idx)?
-                .borrow_mut()
-                .load_input()?
-                .clone();
-
-            self.mutator_mut().mutate(state, &mut input, i as i32)?;
-
-            let (_, corpus_idx) = fuzzer.evaluate_input(state, executor, manager, input)?;
-
-            let observer = executor
-                .observers()
-                .match_name::<O>(&self.map_observer_name)
-                .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?;
-
-            let mut hash = observer.hash() as usize;
-
-            let psmeta = state
-                .metadata_mut()
-                .get_mut::<PowerScheduleMetadata>()
-                .ok_or_else(|| Error::KeyNotFound("PowerScheduleMetadata not found".to_string()))?;
-
-            hash %= psmeta.n_fuzz().len();
-            // Update the path frequency
-            psmeta.n_fuzz_mut()[hash] = psmeta.n_fuzz()[hash].saturating_add(1);
-
-            if let Some(idx) = corpus_idx {
-                state
-                    .corpus()
-                    .get(idx)?
-                    .borrow_mut()
-                    .metadata_mut()
-                    .get_mut::<PowerScheduleTestcaseMetaData>()
-                    .ok_or_else(|| {
-                        Error::KeyNotFound("PowerScheduleTestData not found".to_string())
-                    })?
-                    .set_n_fuzz_entry(hash);
-            }
-
-            self.mutator_mut().post_exec(state, i as i32, corpus_idx)?;
-        }
-
-        Ok(())
-    }
-}
-
-impl<E, EM, I, M, O, OT, S, Z> Stage<E, EM, S, Z> for PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
-where
-    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
-    I: Input,
-    M: Mutator<I, S>,
-    O: MapObserver,
-    OT: ObserversTuple<I, S>,
-    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata,
-    Z: Evaluator<E, EM, I, S>,
-{
-    #[inline]
-    #[allow(clippy::let_and_return)]
-    fn perform(
-        &mut self,
-        fuzzer: &mut Z,
-        executor: &mut E,
-        state: &mut S,
-        manager: &mut EM,
-        corpus_idx: usize,
-    ) -> Result<(), Error> {
-        let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
-        ret
-    }
-}
-
-impl<E, EM, I, M, O, OT, S, Z> PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
-where
-    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
-    I: Input,
-    M: Mutator<I, S>,
-    O: MapObserver,
-    OT: ObserversTuple<I, S>,
-    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata,
-    Z: Evaluator<E, EM, I, S>,
-{
-    /// Creates a new [`PowerMutationalStage`]
-    pub fn new(mutator: M, strat: PowerSchedule, map_observer_name: &O) -> Self {
-        Self {
-            map_observer_name: map_observer_name.name().to_string(),
-            mutator,
-            strat,
-            phantom: PhantomData,
-        }
-    }
-
+impl PowerSchedule {
     /// Compute the parameter `μ` used in the COE schedule.
-    #[inline]
-    #[allow(clippy::unused_self)]
-    pub fn fuzz_mu(&self, state: &S, psmeta: &PowerScheduleMetadata) -> Result<f64, Error> {
+    pub fn fuzz_mu<I, S>(state: &S, psmeta: &PowerScheduleMetadata) -> Result<f64, Error>
+    where
+        I: Input,
+        S: HasCorpus<I>,
+    {
         let corpus = state.corpus();
         let mut n_paths = 0;
         let mut fuzz_mu = 0.0;
@@ -223,6 +63,42 @@ where
         Ok(fuzz_mu)
     }
 
+    /// Compute the energy of the testcase at `corpus_idx`, the number of mutations the
+    /// [`PowerMutationalStage`] spends on it.
+    /// Entries hitting rare paths, or holding rare edges for [`PowerSchedule::RARE`], get more.
+    pub fn energy<I, S>(&self, state: &S, corpus_idx: usize) -> Result<usize, Error>
+    where
+        I: Input,
+        S: HasCorpus<I> + HasMetadata + HasExecutions,
+    {
+        let psmeta = state
+            .metadata()
+            .get::<PowerScheduleMetadata>()
+            .ok_or_else(|| Error::KeyNotFound("PowerScheduleMetadata not found".to_string()))?;
+
+        let mut fuzz_mu = 0.0;
+        if *self == PowerSchedule::COE {
+            fuzz_mu = Self::fuzz_mu(state, psmeta)?;
+        }
+
+        // The number of map entries this testcase is the best one for
+        let mut top_rated = 0;
+        if *self == PowerSchedule::RARE {
+            if let Some(meta) = state.metadata().get::<TopRatedsMetadata>() {
+                top_rated = meta.map.values().filter(|idx| **idx == corpus_idx).count();
+            }
+        }
+
+        let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+        self.calculate_score(
+            &mut testcase,
+            psmeta,
+            fuzz_mu,
+            top_rated,
+            *state.executions(),
+        )
+    }
+
     /// Compute the `power` we assign to each corpus entry
     #[inline]
     #[allow(
@@ -230,20 +106,34 @@ where
         clippy::too_many_lines,
         clippy::cast_sign_loss
     )]
-    fn calculate_score(
+    fn calculate_score<I>(
         &self,
         testcase: &mut Testcase<I>,
         psmeta: &PowerScheduleMetadata,
         fuzz_mu: f64,
-    ) -> Result<usize, Error> {
+        top_rated: usize,
+        executions: usize,
+    ) -> Result<usize, Error>
+    where
+        I: Input,
+    {
         let mut perf_score = 100.0;
         let q_exec_us = testcase
             .exec_time()
             .ok_or_else(|| Error::KeyNotFound("exec_time not set".to_string()))?
             .as_nanos() as f64;
 
-        let avg_exec_us = psmeta.exec_time().as_nanos() as f64 / psmeta.cycles() as f64;
-        let avg_bitmap_size = psmeta.bitmap_size() / psmeta.bitmap_entries();
+        // Nothing was calibrated yet, compare against the testcase itself
+        let avg_exec_us = if psmeta.cycles() == 0 {
+            q_exec_us
+        } else {
+            psmeta.exec_time().as_nanos() as f64 / psmeta.cycles() as f64
+        };
+        let avg_bitmap_size = if psmeta.bitmap_entries() == 0 {
+            None
+        } else {
+            Some(psmeta.bitmap_size() as f64 / psmeta.bitmap_entries() as f64)
+        };
 
         let favored = testcase.has_metadata::<IsFavoredMetadata>();
         let tcmeta = testcase
@@ -267,19 +157,21 @@ where
             perf_score = 150.0;
         }
 
-        let q_bitmap_size = tcmeta.bitmap_size() as f64;
-        if q_bitmap_size * 0.3 > avg_bitmap_size as f64 {
-            perf_score *= 3.0;
-        } else if q_bitmap_size * 0.5 > avg_bitmap_size as f64 {
-            perf_score *= 2.0;
-        } else if q_bitmap_size * 0.75 > avg_bitmap_size as f64 {
-            perf_score *= 1.5;
-        } else if q_bitmap_size * 3.0 < avg_bitmap_size as f64 {
-            perf_score *= 0.25;
-        } else if q_bitmap_size * 2.0 < avg_bitmap_size as f64 {
-            perf_score *= 0.5;
-        } else if q_bitmap_size * 1.5 < avg_bitmap_size as f64 {
-            perf_score *= 0.75;
+        if let Some(avg_bitmap_size) = avg_bitmap_size {
+            let q_bitmap_size = tcmeta.bitmap_size() as f64;
+            if q_bitmap_size * 0.3 > avg_bitmap_size {
+                perf_score *= 3.0;
+            } else if q_bitmap_size * 0.5 > avg_bitmap_size {
+                perf_score *= 2.0;
+            } else if q_bitmap_size * 0.75 > avg_bitmap_size {
+                perf_score *= 1.5;
+            } else if q_bitmap_size * 3.0 < avg_bitmap_size {
+                perf_score *= 0.25;
+            } else if q_bitmap_size * 2.0 < avg_bitmap_size {
+                perf_score *= 0.5;
+            } else if q_bitmap_size * 1.5 < avg_bitmap_size {
+                perf_score *= 0.75;
+            }
         }
 
         if tcmeta.handicap() >= 4 {
@@ -304,7 +196,7 @@ where
 
         // COE and Fast schedule are fairly different from what are described in the original thesis,
         // This implementation follows the changes made in this pull request https://github.com/AFLplusplus/AFLplusplus/pull/568
-        match &self.strat {
+        match self {
             PowerSchedule::EXPLORE => {
                 // Nothing happens in EXPLORE
             }
@@ -366,9 +258,18 @@ where
                 factor = ((tcmeta.fuzz_level() * tcmeta.fuzz_level()) as f64)
                     / f64::from(psmeta.n_fuzz()[tcmeta.n_fuzz_entry()] + 1);
             }
+            PowerSchedule::RARE => {
+                // Boost the entries holding many rare edges, and damp the ones
+                // whose path the mutations keep ending up on
+                perf_score += (top_rated * 10) as f64;
+                if executions > 0 {
+                    let n_fuzz = f64::from(psmeta.n_fuzz()[tcmeta.n_fuzz_entry()]);
+                    perf_score *= (1.0 - n_fuzz / executions as f64).max(0.0);
+                }
+            }
         }
 
-        if self.strat != PowerSchedule::EXPLORE {
+        if *self != PowerSchedule::EXPLORE {
             if factor > MAX_FACTOR {
                 factor = MAX_FACTOR;
             }
@@ -376,8 +277,8 @@ where
             perf_score *= factor / POWER_BETA;
         }
 
-        // Lower bound if the strat is not COE.
-        if self.strat == PowerSchedule::COE && perf_score < 1.0 {
+        // Lower bound if the strat is not COE, which skips entries by giving them no energy
+        if *self != PowerSchedule::COE && perf_score < 1.0 {
             perf_score = 1.0;
         }
 
@@ -389,3 +290,221 @@ where
         Ok(perf_score as usize)
     }
 }
+
+/// The power schedule as the iteration policy of a [`crate::stages::StdMutationalStage`]:
+/// each testcase gets as many iterations as its energy.
+impl<I, S> MutationalIterations<I, S> for PowerSchedule
+where
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasExecutions,
+{
+    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error> {
+        self.energy(state, corpus_idx)
+    }
+}
+
+/// The mutational stage using power schedules
+#[derive(Clone, Debug)]
+pub struct PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    M: Mutator<I, S>,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasExecutions,
+    Z: Evaluator<E, EM, I, S>,
+{
+    map_observer_name: String,
+    mutator: M,
+    /// The employed power schedule strategy
+    strat: PowerSchedule,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, O, OT, S, Z)>,
+}
+
+impl<E, EM, I, M, O, OT, S, Z> MutationalStage<E, EM, I, M, S, Z>
+    for PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    M: Mutator<I, S>,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasExecutions,
+    Z: Evaluator<E, EM, I, S>,
+{
+    /// The mutator, added to this stage
+    #[inline]
+    fn mutator(&self) -> &M {
+        &self.mutator
+    }
+
+    /// The list of mutators, added to this stage (as mutable ref)
+    #[inline]
+    fn mutator_mut(&mut self) -> &mut M {
+        &mut self.mutator
+    }
+
+    /// Gets the number of iterations from the energy the power schedule assigns to the testcase
+    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error> {
+        self.strat.energy(state, corpus_idx)
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn perform_mutational(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let num = self.iterations(state, corpus_idx)?;
+
+        for i in 0..num {
+            let mut input = state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .load_input()?
+                .clone();
+
+            self.mutator_mut().mutate(state, &mut input, i as i32)?;
+
+            let (_, corpus_idx) = fuzzer.evaluate_input(state, executor, manager, input)?;
+
+            let observer = executor
+                .observers()
+                .match_name::<O>(&self.map_observer_name)
+                .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?;
+
+            let mut hash = observer.hash() as usize;
+
+            let psmeta = state
+                .metadata_mut()
+                .get_mut::<PowerScheduleMetadata>()
+                .ok_or_else(|| Error::KeyNotFound("PowerScheduleMetadata not found".to_string()))?;
+
+            hash %= psmeta.n_fuzz().len();
+            // Update the path frequency
+            psmeta.n_fuzz_mut()[hash] = psmeta.n_fuzz()[hash].saturating_add(1);
+
+            if let Some(idx) = corpus_idx {
+                state
+                    .corpus()
+                    .get(idx)?
+                    .borrow_mut()
+                    .metadata_mut()
+                    .get_mut::<PowerScheduleTestcaseMetaData>()
+                    .ok_or_else(|| {
+                        Error::KeyNotFound("PowerScheduleTestData not found".to_string())
+                    })?
+                    .set_n_fuzz_entry(hash);
+            }
+
+            self.mutator_mut().post_exec(state, i as i32, corpus_idx)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<E, EM, I, M, O, OT, S, Z> Stage<E, EM, S, Z> for PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    M: Mutator<I, S>,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasExecutions,
+    Z: Evaluator<E, EM, I, S>,
+{
+    #[inline]
+    #[allow(clippy::let_and_return)]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
+        ret
+    }
+}
+
+impl<E, EM, I, M, O, OT, S, Z> PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    M: Mutator<I, S>,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasExecutions,
+    Z: Evaluator<E, EM, I, S>,
+{
+    /// Creates a new [`PowerMutationalStage`]
+    pub fn new(mutator: M, strat: PowerSchedule, map_observer_name: &O) -> Self {
+        Self {
+            map_observer_name: map_observer_name.name().to_string(),
+            mutator,
+            strat,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Compute the parameter `μ` used in the COE schedule.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub fn fuzz_mu(&self, state: &S, psmeta: &PowerScheduleMetadata) -> Result<f64, Error> {
+        PowerSchedule::fuzz_mu(state, psmeta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, PowerScheduleTestcaseMetaData, Testcase},
+        inputs::BytesInput,
+        schedulers::minimizer::TopRatedsMetadata,
+        stages::{power::PowerSchedule, PowerScheduleMetadata},
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_rare_energy() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for _ in 0..2 {
+            let mut testcase = Testcase::new(BytesInput::new(vec![0; 4]));
+            testcase.set_exec_time(Duration::from_millis(1));
+            testcase.add_metadata(PowerScheduleTestcaseMetaData::new(1));
+            corpus.add(testcase).unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        // Nothing was calibrated, this must not divide by zero
+        state.add_metadata(PowerScheduleMetadata::new());
+
+        let mut top_rated = TopRatedsMetadata::new();
+        for entry in 0..3 {
+            top_rated.map.insert(entry, 0);
+        }
+        state.add_metadata(top_rated);
+
+        let explore = PowerSchedule::EXPLORE.energy::<BytesInput, _>(&state, 0);
+        assert_eq!(explore.unwrap(), 100);
+        let rare = PowerSchedule::RARE.energy::<BytesInput, _>(&state, 0);
+        assert_eq!(rare.unwrap(), 130);
+        let rare = PowerSchedule::RARE.energy::<BytesInput, _>(&state, 1);
+        assert_eq!(rare.unwrap(), 100);
+    }
+}
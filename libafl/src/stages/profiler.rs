@@ -0,0 +1,279 @@
+//! A lightweight wall-clock profiler, measuring the time spent in each stage, each feedback, and
+//! the target, independently of the `introspection` feature.
+//!
+//! Wrap the stages in [`ProfiledStage`]s, the feedbacks in [`crate::feedbacks::ProfiledFeedback`]s,
+//! and add an [`crate::observers::IntrospectionObserver`] to time the target. The totals are kept in
+//! the [`struct@ProfilerMetadata`] of the state. A [`ProfilerReportStage`] periodically samples them
+//! into a time series and sends the share of each component to the monitor, as user stats.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{marker::PhantomData, time::Duration};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::current_time,
+    events::{Event, EventFirer},
+    inputs::Input,
+    monitors::{UserStats, PROFILE_STATS_PREFIX},
+    stages::Stage,
+    state::HasMetadata,
+    Error,
+};
+
+/// The maximum number of samples kept in the time series, the oldest are dropped
+pub const PROFILER_MAX_SAMPLES: usize = 1024;
+
+/// A snapshot of the totals of the [`struct@ProfilerMetadata`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProfileSample {
+    /// The time of the snapshot
+    pub time: Duration,
+    /// The total time spent in the target
+    pub target: Duration,
+    /// The total time spent in each stage
+    pub stages: Vec<(String, Duration)>,
+    /// The total time spent in each feedback
+    pub feedbacks: Vec<(String, Duration)>,
+}
+
+/// The time spent in each component of the fuzzer.
+/// The time of a stage includes the executions and the feedbacks it runs.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ProfilerMetadata {
+    /// When the profiling started
+    pub start: Option<Duration>,
+    /// The total time spent in the target
+    pub target: Duration,
+    /// The total time spent in each stage, indexed by the id of the stage
+    pub stages: Vec<(String, Duration)>,
+    /// The total time spent in each feedback, indexed by the id of the feedback
+    pub feedbacks: Vec<(String, Duration)>,
+    /// The periodic snapshots of the totals
+    pub samples: Vec<ProfileSample>,
+}
+
+crate::impl_serdeany!(
+    ProfilerMetadata,
+    "libafl::stages::profiler::ProfilerMetadata"
+);
+
+impl ProfilerMetadata {
+    /// The [`struct@ProfilerMetadata`] of the state, added if missing
+    pub fn of_state<S>(state: &mut S) -> &mut Self
+    where
+        S: HasMetadata,
+    {
+        if !state.has_metadata::<Self>() {
+            state.add_metadata(Self {
+                start: Some(current_time()),
+                ..Self::default()
+            });
+        }
+        state.metadata_mut().get_mut::<Self>().unwrap()
+    }
+
+    /// Add `elapsed` to the time of the stage `name`, returning its id.
+    /// Pass the id returned last time as `id`, to skip looking up the name.
+    pub fn add_stage_time(&mut self, name: &str, id: Option<usize>, elapsed: Duration) -> usize {
+        let id = entry_id(&mut self.stages, name, id);
+        self.stages[id].1 += elapsed;
+        id
+    }
+
+    /// Add `elapsed` to the time of the feedback `name`, returning its id.
+    /// Pass the id returned last time as `id`, to skip looking up the name.
+    pub fn add_feedback_time(&mut self, name: &str, id: Option<usize>, elapsed: Duration) -> usize {
+        let id = entry_id(&mut self.feedbacks, name, id);
+        self.feedbacks[id].1 += elapsed;
+        id
+    }
+
+    /// Snapshot the current totals into the time series
+    pub fn sample(&mut self, time: Duration) {
+        if self.samples.len() == PROFILER_MAX_SAMPLES {
+            self.samples.remove(0);
+        }
+        self.samples.push(ProfileSample {
+            time,
+            target: self.target,
+            stages: self.stages.clone(),
+            feedbacks: self.feedbacks.clone(),
+        });
+    }
+}
+
+/// The id of the entry `name`, added if missing.
+/// `id` is checked first, it is stale if the metadata was replaced.
+fn entry_id(entries: &mut Vec<(String, Duration)>, name: &str, id: Option<usize>) -> usize {
+    if let Some(id) = id {
+        if entries.get(id).map_or(false, |(entry, _)| entry == name) {
+            return id;
+        }
+    }
+    entries
+        .iter()
+        .position(|(entry, _)| entry == name)
+        .unwrap_or_else(|| {
+            entries.push((name.to_string(), Duration::ZERO));
+            entries.len() - 1
+        })
+}
+
+/// A stage measuring the time spent in the wrapped stage
+#[derive(Clone, Debug)]
+pub struct ProfiledStage<ST> {
+    name: String,
+    /// The id of the stage in the [`struct@ProfilerMetadata`]
+    id: Option<usize>,
+    inner: ST,
+}
+
+impl<ST> ProfiledStage<ST> {
+    /// Profile `inner` with the given name
+    pub fn new(name: &str, inner: ST) -> Self {
+        Self {
+            name: name.to_string(),
+            id: None,
+            inner,
+        }
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.inner
+    }
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for ProfiledStage<ST>
+where
+    ST: Stage<E, EM, S, Z>,
+    S: HasMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let start = current_time();
+        let ret = self
+            .inner
+            .perform(fuzzer, executor, state, manager, corpus_idx);
+        self.id = Some(ProfilerMetadata::of_state(state).add_stage_time(
+            &self.name,
+            self.id,
+            current_time() - start,
+        ));
+        ret
+    }
+}
+
+/// A stage sampling the [`struct@ProfilerMetadata`] every `interval`, and reporting to the monitor
+/// the share of the fuzzing time spent in each component, as `profile_<component>` user stats.
+#[derive(Clone, Debug)]
+pub struct ProfilerReportStage<I> {
+    interval: Duration,
+    last: Duration,
+    phantom: PhantomData<I>,
+}
+
+impl<I> ProfilerReportStage<I> {
+    /// Creates a new [`ProfilerReportStage`] reporting every `interval`
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Duration::ZERO,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for ProfilerReportStage<I>
+where
+    EM: EventFirer<I>,
+    I: Input,
+    S: HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        if now - self.last < self.interval {
+            return Ok(());
+        }
+        self.last = now;
+
+        let profiler = ProfilerMetadata::of_state(state);
+        profiler.sample(now);
+        let total = now - profiler.start.unwrap_or(now);
+        let mut stats = vec![(format!("{}target", PROFILE_STATS_PREFIX), profiler.target)];
+        stats.extend(
+            profiler
+                .stages
+                .iter()
+                .map(|(name, t)| (format!("{}stage_{}", PROFILE_STATS_PREFIX, name), *t)),
+        );
+        stats.extend(
+            profiler
+                .feedbacks
+                .iter()
+                .map(|(name, t)| (format!("{}feedback_{}", PROFILE_STATS_PREFIX, name), *t)),
+        );
+
+        for (name, spent) in stats {
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name,
+                    value: UserStats::Ratio(spent.as_millis() as u64, total.as_millis() as u64),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::stages::ProfilerMetadata;
+
+    #[test]
+    fn test_profiler_samples() {
+        let mut profiler = ProfilerMetadata::default();
+        let id = profiler.add_stage_time("mutational", None, Duration::from_millis(3));
+        assert_eq!(
+            profiler.add_stage_time("mutational", Some(id), Duration::from_millis(4)),
+            id
+        );
+        profiler.add_feedback_time("edges", None, Duration::from_millis(1));
+        profiler.sample(Duration::from_secs(1));
+
+        assert_eq!(
+            profiler.stages,
+            vec![("mutational".into(), Duration::from_millis(7))]
+        );
+        // A stale id is looked up again
+        let other = profiler.add_stage_time("other", Some(id), Duration::from_millis(1));
+        assert_ne!(other, id);
+        assert_eq!(profiler.stages[id].1, Duration::from_millis(7));
+        assert_eq!(profiler.samples.len(), 1);
+        assert_eq!(
+            profiler.samples[0].feedbacks,
+            vec![("edges".into(), Duration::from_millis(1))]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! The [`CorpusPruningStage`] ages the testcases and prunes the stale ones with a [`CorpusPruner`].
+
+use core::marker::PhantomData;
+
+use crate::{
+    corpus::{Corpus, CorpusAgingMetadata, CorpusPruner, PruningAction, TestcaseAgeMetadata},
+    events::EventFirer,
+    fuzzer::HasScheduler,
+    inputs::Input,
+    schedulers::Scheduler,
+    stages::Stage,
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// A stage keeping track of the testcases producing new corpus entries, pruning the corpus
+/// once per queue cycle.
+/// It must run after the mutational stages, to see the entries they added, and since the
+/// corpus indexes shift on removal, as the last stage.
+/// The purge requests of the other clients only reach it through an event manager with access
+/// to the state metadata, see [`crate::events::LlmpEventManager::with_state_metadata`].
+#[derive(Debug)]
+pub struct CorpusPruningStage<CS, I, S>
+where
+    I: Input,
+{
+    pruner: CorpusPruner<I>,
+    phantom: PhantomData<(CS, S)>,
+}
+
+impl<CS, E, EM, I, S, Z> Stage<E, EM, S, Z> for CorpusPruningStage<CS, I, S>
+where
+    CS: Scheduler<I, S>,
+    EM: EventFirer<I>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+    Z: HasScheduler<CS, I, S>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if !state.has_metadata::<CorpusAgingMetadata>() {
+            state.add_metadata(CorpusAgingMetadata::new());
+        }
+        let count = state.corpus().count();
+        let meta = state
+            .metadata_mut()
+            .get_mut::<CorpusAgingMetadata>()
+            .unwrap();
+        // The first run only learns the corpus size
+        let productive = (meta.runs > 0 || meta.cycles > 0) && count > meta.last_count;
+        meta.runs += 1;
+        let end_of_cycle = meta.runs >= count;
+        if end_of_cycle {
+            meta.runs = 0;
+            meta.cycles += 1;
+        }
+        let cycles = meta.cycles;
+        let first_new = meta.last_count.min(count);
+
+        if productive {
+            state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .add_metadata(TestcaseAgeMetadata {
+                    last_productive_cycle: cycles,
+                });
+        }
+        // The new testcases start young
+        for idx in first_new..count {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            if !testcase.has_metadata::<TestcaseAgeMetadata>() {
+                testcase.add_metadata(TestcaseAgeMetadata {
+                    last_productive_cycle: cycles,
+                });
+            }
+        }
+
+        if end_of_cycle {
+            self.pruner.prune(fuzzer, state, manager)?;
+        }
+
+        let count = state.corpus().count();
+        state
+            .metadata_mut()
+            .get_mut::<CorpusAgingMetadata>()
+            .unwrap()
+            .last_count = count;
+        Ok(())
+    }
+}
+
+impl<CS, I, S> CorpusPruningStage<CS, I, S>
+where
+    CS: Scheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata,
+{
+    /// Creates a new [`CorpusPruningStage`], removing the testcases that did not produce a new
+    /// corpus entry in `max_age` queue cycles
+    #[must_use]
+    pub fn new(max_age: u64) -> Self {
+        Self {
+            pruner: CorpusPruner::new(max_age),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Set what to do with the stale testcases
+    #[must_use]
+    pub fn with_action(mut self, action: PruningAction) -> Self {
+        self.pruner = self.pruner.with_action(action);
+        self
+    }
+}
@@ -0,0 +1,90 @@
+//! The [`PushFuzzer`] lets the target drive the fuzzing: the main loop of the target, e.g. an
+//! event loop or an emulator, asks for the next input, runs it, and reports how it exited.
+
+use alloc::rc::Rc;
+use core::{cell::Cell, marker::PhantomData};
+
+use crate::{executors::ExitKind, inputs::Input, Error};
+
+/// Drives a push stage, such as a [`super::StdMutationalPushStage`], from the target loop.
+///
+/// Unlike iterating over the push stage, the [`PushFuzzer`] never runs out of inputs: once the
+/// stage is done with a testcase, it starts over with the next one from the scheduler.
+#[derive(Debug)]
+pub struct PushFuzzer<I, PS>
+where
+    I: Input,
+    PS: Iterator<Item = Result<I, Error>>,
+{
+    push_stage: PS,
+    exit_kind: Rc<Cell<Option<ExitKind>>>,
+    running: bool,
+    phantom: PhantomData<I>,
+}
+
+impl<I, PS> PushFuzzer<I, PS>
+where
+    I: Input,
+    PS: Iterator<Item = Result<I, Error>>,
+{
+    /// Creates a new [`PushFuzzer`], sharing the `exit_kind` cell with the `push_stage`
+    #[must_use]
+    pub fn new(push_stage: PS, exit_kind: Rc<Cell<Option<ExitKind>>>) -> Self {
+        Self {
+            push_stage,
+            exit_kind,
+            running: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The next input for the target to run.
+    /// The target has to [`PushFuzzer::report`] how the previous input exited first.
+    pub fn next_input(&mut self) -> Result<I, Error> {
+        if self.running {
+            return Err(Error::IllegalState(
+                "The exit kind of the last input was not reported".into(),
+            ));
+        }
+        loop {
+            match self.push_stage.next() {
+                Some(Ok(input)) => {
+                    self.running = true;
+                    return Ok(input);
+                }
+                Some(Err(err)) => return Err(err),
+                // Done with this testcase, go on with the next one
+                None => (),
+            }
+        }
+    }
+
+    /// Reports how the target exited on the last input
+    pub fn report(&mut self, exit_kind: ExitKind) -> Result<(), Error> {
+        if !self.running {
+            return Err(Error::IllegalState(
+                "No input is running, nothing to report".into(),
+            ));
+        }
+        self.exit_kind.set(Some(exit_kind));
+        self.running = false;
+        Ok(())
+    }
+
+    /// If an input is running, waiting for its [`PushFuzzer::report`]
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// The push stage
+    #[must_use]
+    pub fn push_stage(&self) -> &PS {
+        &self.push_stage
+    }
+
+    /// The push stage (mutable)
+    pub fn push_stage_mut(&mut self) -> &mut PS {
+        &mut self.push_stage
+    }
+}
@@ -8,6 +8,10 @@
 pub mod mutational;
 pub use mutational::StdMutationalPushStage;
 
+/// Lets the target loop ask for the inputs and report their exit kinds.
+pub mod client;
+pub use client::PushFuzzer;
+
 use alloc::rc::Rc;
 use core::{
     cell::{Cell, RefCell},
@@ -261,22 +265,39 @@ where
             // We already ran once
 
             let last_input = self.push_stage_helper_mut().current_input.take().unwrap();
+            let exit_kind = match self.push_stage_helper().exit_kind() {
+                Some(exit_kind) => exit_kind,
+                None => {
+                    self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                    return Some(Err(Error::IllegalState(
+                        "The exit kind of the last input was not set".into(),
+                    )));
+                }
+            };
 
-            self.post_exec(
-                &mut shared_state.fuzzer,
-                &mut shared_state.state,
-                &mut shared_state.event_mgr,
-                &mut shared_state.observers,
-                last_input,
-                self.push_stage_helper().exit_kind().unwrap(),
-            )
+            *shared_state.state.executions_mut() += 1;
+            shared_state
+                .observers
+                .post_exec_all(&mut shared_state.state, &last_input, &exit_kind)
+                .and_then(|_| {
+                    self.post_exec(
+                        &mut shared_state.fuzzer,
+                        &mut shared_state.state,
+                        &mut shared_state.event_mgr,
+                        &mut shared_state.observers,
+                        last_input,
+                        exit_kind,
+                    )
+                })
         } else {
-            self.init(
+            let ret = self.init(
                 &mut shared_state.fuzzer,
                 &mut shared_state.state,
                 &mut shared_state.event_mgr,
                 &mut shared_state.observers,
-            )
+            );
+            self.push_stage_helper_mut().initialized = ret.is_ok();
+            ret
         };
         if let Err(err) = step_success {
             self.push_stage_helper_mut().end_of_iter(shared_state, true);
@@ -321,8 +342,19 @@ where
 
             self.push_stage_helper_mut().last_monitor_time = new_monitor_time;
             //self.fuzzer.maybe_report_monitor();
-        } else {
+        } else if let Some(Ok(input)) = &ret {
             self.push_stage_helper_mut().reset_exit_kind();
+            // The target runs the input next
+            if let Err(err) = shared_state
+                .observers
+                .pre_exec_all(&mut shared_state.state, input)
+            {
+                self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                return Some(Err(err));
+            }
+        } else {
+            self.push_stage_helper_mut().end_of_iter(shared_state, true);
+            return ret;
         }
         self.push_stage_helper_mut()
             .end_of_iter(shared_state, false);
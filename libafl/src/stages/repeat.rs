@@ -0,0 +1,46 @@
+//! The [`RepeatRunsStage`] sets the number of runs of each input of a [`crate::executors::RepeatExecutor`]
+//! for the wrapped stage, e.g. more runs to calibrate, fewer to mutate.
+
+use crate::{executors::HasRepeatRuns, stages::Stage, Error};
+
+/// A stage running the wrapped stage with `runs` runs of each input
+#[derive(Clone, Debug)]
+pub struct RepeatRunsStage<ST> {
+    runs: usize,
+    inner: ST,
+}
+
+impl<ST> RepeatRunsStage<ST> {
+    /// Run `inner` with `runs` runs of each input
+    pub fn new(runs: usize, inner: ST) -> Self {
+        Self { runs, inner }
+    }
+
+    /// The wrapped stage
+    pub fn inner(&self) -> &ST {
+        &self.inner
+    }
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for RepeatRunsStage<ST>
+where
+    E: HasRepeatRuns,
+    ST: Stage<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let runs = executor.runs();
+        executor.set_runs(self.runs);
+        let ret = self
+            .inner
+            .perform(fuzzer, executor, state, manager, corpus_idx);
+        executor.set_runs(runs);
+        ret
+    }
+}
@@ -0,0 +1,56 @@
+//! The [`ReplayReceivedStage`] evaluates the testcases a recorded session received from other clients,
+//! at the same point of its replay.
+
+use core::marker::PhantomData;
+
+use crate::{
+    fuzzer::Evaluator, inputs::Input, schedulers::ReplayLogMetadata, stages::Stage,
+    state::HasMetadata, Error,
+};
+
+/// A stage evaluating the inputs the recorded session received from other clients, in a replay
+/// with a [`crate::schedulers::ReplayScheduler`].
+/// The testcases arrive after the stages of a fuzzing round, so put it last.
+#[derive(Debug)]
+pub struct ReplayReceivedStage<E, EM, I, S, Z> {
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for ReplayReceivedStage<E, EM, I, S, Z>
+where
+    I: Input,
+    S: HasMetadata,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        while let Some(bytes) = ReplayLogMetadata::next_received(state)? {
+            let input: I = postcard::from_bytes(&bytes)?;
+            fuzzer.evaluate_input_events(state, executor, manager, input, false)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E, EM, I, S, Z> ReplayReceivedStage<E, EM, I, S, Z> {
+    /// Creates a new [`ReplayReceivedStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, I, S, Z> Default for ReplayReceivedStage<E, EM, I, S, Z> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,315 @@
+//! The [`ResumableStages`] run a tuple of stages, possibly in a loop, and keep track of their
+//! progress in the state. If the fuzzer restarts in the middle of them, e.g. after a crash with a
+//! restarting event manager, they continue where they stopped instead of starting over.
+//!
+//! [`ResumableStages`] can be nested, each one keeps its own progress under its own name.
+
+use alloc::string::{String, ToString};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    stages::{Stage, StagesTuple},
+    state::HasMetadata,
+    Error,
+};
+
+/// Where some [`ResumableStages`] are at
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageProgress {
+    /// The testcase the stages run on
+    pub corpus_idx: usize,
+    /// The current iteration of the loop
+    pub iteration: usize,
+    /// The index of the stage running, in the tuple
+    pub stage_idx: usize,
+    /// If the stage running already got interrupted once
+    pub resumed: bool,
+}
+
+/// The progress of all the [`ResumableStages`], by name
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StageProgressMetadata {
+    /// The progress of the [`ResumableStages`] currently running, by name
+    pub progress: HashMap<String, StageProgress>,
+}
+
+crate::impl_serdeany!(
+    StageProgressMetadata,
+    "libafl::stages::resume::StageProgressMetadata"
+);
+
+impl StageProgressMetadata {
+    /// Creates a new, empty [`StageProgressMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A tuple of stages that can be performed one by one, by index
+pub trait IndexedStagesTuple<E, EM, S, Z>: StagesTuple<E, EM, S, Z> {
+    /// The number of stages in this tuple
+    const LEN: usize;
+
+    /// Performs the `n`th stage of this tuple
+    fn perform_nth(
+        &mut self,
+        n: usize,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error>;
+}
+
+impl<E, EM, S, Z> IndexedStagesTuple<E, EM, S, Z> for () {
+    const LEN: usize = 0;
+
+    fn perform_nth(
+        &mut self,
+        n: usize,
+        _: &mut Z,
+        _: &mut E,
+        _: &mut S,
+        _: &mut EM,
+        _: usize,
+    ) -> Result<(), Error> {
+        Err(Error::KeyNotFound(format!("No stage at index {}", n)))
+    }
+}
+
+impl<Head, Tail, E, EM, S, Z> IndexedStagesTuple<E, EM, S, Z> for (Head, Tail)
+where
+    Head: Stage<E, EM, S, Z>,
+    Tail: IndexedStagesTuple<E, EM, S, Z>,
+{
+    const LEN: usize = 1 + Tail::LEN;
+
+    fn perform_nth(
+        &mut self,
+        n: usize,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if n == 0 {
+            self.0.perform(fuzzer, executor, state, manager, corpus_idx)
+        } else {
+            self.1
+                .perform_nth(n - 1, fuzzer, executor, state, manager, corpus_idx)
+        }
+    }
+}
+
+/// A stage running a tuple of stages `loops` times, resuming after a restart.
+///
+/// The progress is saved in a [`StageProgressMetadata`] before each stage.
+/// If the fuzzer restarted in the middle, the next call first finishes the interrupted run on its
+/// testcase, from the interrupted stage. A stage interrupted twice in a row is skipped, so that a
+/// stage crashing the fuzzer does not restart over and over.
+#[derive(Debug, Clone)]
+pub struct ResumableStages<ST> {
+    name: String,
+    loops: usize,
+    stages: ST,
+}
+
+impl<ST> Named for ResumableStages<ST> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for ResumableStages<ST>
+where
+    S: HasMetadata,
+    ST: IndexedStagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if !state.has_metadata::<StageProgressMetadata>() {
+            state.add_metadata(StageProgressMetadata::new());
+        }
+        let interrupted = state
+            .metadata()
+            .get::<StageProgressMetadata>()
+            .unwrap()
+            .progress
+            .get(&self.name)
+            .copied();
+
+        if let Some(progress) = interrupted {
+            let stage_idx = if progress.resumed {
+                progress.stage_idx + 1
+            } else {
+                progress.stage_idx
+            };
+            self.run(
+                fuzzer,
+                executor,
+                state,
+                manager,
+                progress.corpus_idx,
+                progress.iteration,
+                stage_idx,
+                !progress.resumed,
+            )?;
+            if progress.corpus_idx == corpus_idx {
+                return Ok(());
+            }
+        }
+
+        self.run(fuzzer, executor, state, manager, corpus_idx, 0, 0, false)
+    }
+}
+
+impl<ST> ResumableStages<ST> {
+    /// Create new [`ResumableStages`], running `stages` once per testcase.
+    /// The `name` must be unique among the nested [`ResumableStages`].
+    pub fn new(name: &str, stages: ST) -> Self {
+        Self {
+            name: name.to_string(),
+            loops: 1,
+            stages,
+        }
+    }
+
+    /// Run the stages `loops` times per testcase
+    #[must_use]
+    pub fn with_loops(mut self, loops: usize) -> Self {
+        self.loops = loops;
+        self
+    }
+
+    /// The wrapped stages
+    pub fn stages(&self) -> &ST {
+        &self.stages
+    }
+
+    /// Run the stages on `corpus_idx`, starting at the given iteration and stage
+    #[allow(clippy::too_many_arguments)]
+    fn run<E, EM, S, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        start_iteration: usize,
+        start_stage_idx: usize,
+        resumed: bool,
+    ) -> Result<(), Error>
+    where
+        S: HasMetadata,
+        ST: IndexedStagesTuple<E, EM, S, Z>,
+    {
+        for iteration in start_iteration..self.loops {
+            let first_stage_idx = if iteration == start_iteration {
+                start_stage_idx
+            } else {
+                0
+            };
+            for stage_idx in first_stage_idx..ST::LEN {
+                let progress = StageProgress {
+                    corpus_idx,
+                    iteration,
+                    stage_idx,
+                    resumed: resumed
+                        && iteration == start_iteration
+                        && stage_idx == start_stage_idx,
+                };
+                state
+                    .metadata_mut()
+                    .get_mut::<StageProgressMetadata>()
+                    .unwrap()
+                    .progress
+                    .insert(self.name.clone(), progress);
+
+                self.stages
+                    .perform_nth(stage_idx, fuzzer, executor, state, manager, corpus_idx)?;
+            }
+        }
+
+        state
+            .metadata_mut()
+            .get_mut::<StageProgressMetadata>()
+            .unwrap()
+            .progress
+            .remove(&self.name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        inputs::BytesInput,
+        stages::{ClosureStage, ResumableStages, Stage, StageProgress, StageProgressMetadata},
+        state::{HasMetadata, StdState},
+        Error,
+    };
+
+    #[test]
+    fn test_resumable_stages() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let runs = RefCell::new(Vec::new());
+        let first = |_: &mut (), _: &mut (), _: &mut _, _: &mut (), idx| -> Result<(), Error> {
+            runs.borrow_mut().push((0, idx));
+            Ok(())
+        };
+        let second = |_: &mut (), _: &mut (), _: &mut _, _: &mut (), idx| -> Result<(), Error> {
+            runs.borrow_mut().push((1, idx));
+            Ok(())
+        };
+        let mut stages = ResumableStages::new(
+            "resumable",
+            tuple_list!(ClosureStage::new(first), ClosureStage::new(second)),
+        )
+        .with_loops(2);
+
+        // The fuzzer restarted in the second stage of the last loop on testcase 3
+        let mut meta = StageProgressMetadata::new();
+        meta.progress.insert(
+            "resumable".into(),
+            StageProgress {
+                corpus_idx: 3,
+                iteration: 1,
+                stage_idx: 1,
+                resumed: false,
+            },
+        );
+        state.add_metadata(meta);
+
+        stages
+            .perform(&mut (), &mut (), &mut state, &mut (), 5)
+            .unwrap();
+        assert_eq!(*runs.borrow(), vec![(1, 3), (0, 5), (1, 5), (0, 5), (1, 5)]);
+        assert!(state
+            .metadata()
+            .get::<StageProgressMetadata>()
+            .unwrap()
+            .progress
+            .is_empty());
+    }
+}
@@ -1,7 +1,8 @@
-//| The [`MutationalStage`] is the default stage used during fuzzing.
-//! For the current input, it will perform a range of random mutations, and then run them in the executor.
+//! The [`SyncFromDiskStage`] imports the testcases other fuzzers, such as AFL++, write to disk.
+//! It scans foreign corpus directories every now and then and evaluates the new files.
 
-use core::marker::PhantomData;
+use core::{marker::PhantomData, time::Duration};
+use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
@@ -10,6 +11,7 @@ use std::{
 };
 
 use crate::{
+    bolts::current_time,
     fuzzer::Evaluator,
     inputs::Input,
     stages::Stage,
@@ -17,24 +19,74 @@ use crate::{
     Error,
 };
 
+/// The files of a sync directory imported so far
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SyncedFiles {
+    /// The last modification time of the files imported so far
+    pub last_time: SystemTime,
+    /// The files modified at `last_time` already imported, others may share their time
+    pub names: HashSet<PathBuf>,
+}
+
+impl SyncedFiles {
+    /// Create a new [`SyncedFiles`], with the files modified before `last_time` already imported
+    #[must_use]
+    pub fn new(last_time: SystemTime) -> Self {
+        Self {
+            last_time,
+            names: HashSet::new(),
+        }
+    }
+
+    /// If the file at `path`, modified at `time`, was not imported yet
+    #[must_use]
+    pub fn is_new(&self, path: &Path, time: SystemTime) -> bool {
+        time > self.last_time || (time == self.last_time && !self.names.contains(path))
+    }
+
+    /// Record the import of the file at `path`, modified at `time`
+    pub fn record(&mut self, path: &Path, time: SystemTime) {
+        if time > self.last_time {
+            self.last_time = time;
+            self.names.clear();
+        }
+        if time == self.last_time {
+            self.names.insert(path.to_path_buf());
+        }
+    }
+}
+
 /// Metadata used to store information about disk sync time
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SyncFromDiskMetadata {
-    /// The last time the sync was done
+    /// The last time the sync was done, the files modified before are ignored in new sync directories
     pub last_time: SystemTime,
+    /// The files imported so far, per sync directory
+    pub synced: HashMap<PathBuf, SyncedFiles>,
+    /// When the sync directories were last scanned
+    pub last_sync: Duration,
 }
 
-crate::impl_serdeany!(SyncFromDiskMetadata);
+crate::impl_serdeany!(
+    SyncFromDiskMetadata,
+    "libafl::stages::sync::SyncFromDiskMetadata"
+);
 
 impl SyncFromDiskMetadata {
     /// Create a new [`struct@SyncFromDiskMetadata`]
     #[must_use]
     pub fn new(last_time: SystemTime) -> Self {
-        Self { last_time }
+        Self {
+            last_time,
+            synced: HashMap::new(),
+            last_sync: Duration::ZERO,
+        }
     }
 }
 
-/// A stage that loads testcases from disk to sync with other fuzzers such as AFL++
+/// A stage that loads testcases from disk to sync with other fuzzers such as AFL++.
+/// In the output directory of an AFL++ instance, only the `queue` is imported;
+/// hidden files and directories, like `.state` and `.synced`, are always skipped.
 #[derive(Debug)]
 pub struct SyncFromDiskStage<CB, E, EM, I, S, Z>
 where
@@ -43,7 +95,8 @@ where
     S: HasClientPerfMonitor + HasCorpus<I> + HasRand + HasMetadata,
     Z: Evaluator<E, EM, I, S>,
 {
-    sync_dir: PathBuf,
+    sync_dirs: Vec<PathBuf>,
+    interval: Duration,
     load_callback: CB,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, S, Z)>,
@@ -65,26 +118,41 @@ where
         manager: &mut EM,
         _corpus_idx: usize,
     ) -> Result<(), Error> {
-        let last = state
+        if !state.has_metadata::<SyncFromDiskMetadata>() {
+            state.add_metadata(SyncFromDiskMetadata::new(SystemTime::UNIX_EPOCH));
+        }
+        let now = current_time();
+        let last_sync = state
             .metadata()
             .get::<SyncFromDiskMetadata>()
-            .map(|m| m.last_time);
-        let path = self.sync_dir.clone();
-        if let Some(max_time) =
-            self.load_from_directory(&path, &last, fuzzer, executor, state, manager)?
-        {
-            if last.is_none() {
-                state
-                    .metadata_mut()
-                    .insert(SyncFromDiskMetadata::new(max_time));
-            } else {
-                state
-                    .metadata_mut()
-                    .get_mut::<SyncFromDiskMetadata>()
-                    .unwrap()
-                    .last_time = max_time;
-            }
+            .unwrap()
+            .last_sync;
+        if now < last_sync + self.interval {
+            return Ok(());
+        }
+
+        for path in self.sync_dirs.clone() {
+            let last = {
+                let meta = state.metadata().get::<SyncFromDiskMetadata>().unwrap();
+                meta.synced
+                    .get(&path)
+                    .cloned()
+                    .unwrap_or_else(|| SyncedFiles::new(meta.last_time))
+            };
+            let mut synced = last.clone();
+            self.load_from_directory(&path, &last, &mut synced, fuzzer, executor, state, manager)?;
+            let meta = state
+                .metadata_mut()
+                .get_mut::<SyncFromDiskMetadata>()
+                .unwrap();
+            meta.last_time = meta.last_time.max(synced.last_time);
+            meta.synced.insert(path, synced);
         }
+        state
+            .metadata_mut()
+            .get_mut::<SyncFromDiskMetadata>()
+            .unwrap()
+            .last_sync = now;
 
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().finish_stage();
@@ -103,25 +171,49 @@ where
     /// Creates a new [`SyncFromDiskStage`]
     #[must_use]
     pub fn new(sync_dir: PathBuf, load_callback: CB) -> Self {
+        Self::with_dirs(vec![sync_dir], load_callback)
+    }
+
+    /// Creates a new [`SyncFromDiskStage`] syncing with all the given directories
+    #[must_use]
+    pub fn with_dirs(sync_dirs: Vec<PathBuf>, load_callback: CB) -> Self {
         Self {
-            sync_dir,
+            sync_dirs,
+            interval: Duration::ZERO,
             load_callback,
             phantom: PhantomData,
         }
     }
 
+    /// Scan the sync directories at most once every `interval`, instead of on every run of the stage
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn load_from_directory(
         &mut self,
         in_dir: &Path,
-        last: &Option<SystemTime>,
+        last: &SyncedFiles,
+        synced: &mut SyncedFiles,
         fuzzer: &mut Z,
         executor: &mut E,
         state: &mut S,
         manager: &mut EM,
-    ) -> Result<Option<SystemTime>, Error> {
-        let mut max_time = None;
+    ) -> Result<(), Error> {
+        // The output directory of an AFL++ instance, only its queue holds testcases
+        let queue = in_dir.join("queue");
+        if queue.is_dir() {
+            return self
+                .load_from_directory(&queue, last, synced, fuzzer, executor, state, manager);
+        }
         for entry in fs::read_dir(in_dir)? {
             let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
             let path = entry.path();
             let attributes = fs::metadata(&path);
 
@@ -133,25 +225,19 @@ where
 
             if attr.is_file() && attr.len() > 0 {
                 if let Ok(time) = attr.modified() {
-                    if let Some(l) = last {
-                        if time.duration_since(*l).is_err() {
-                            continue;
-                        }
+                    if !last.is_new(&path, time) {
+                        continue;
                     }
-                    max_time = Some(max_time.map_or(time, |t: SystemTime| t.max(time)));
+                    synced.record(&path, time);
                     let input = (self.load_callback)(fuzzer, state, &path)?;
                     drop(fuzzer.evaluate_input(state, executor, manager, input)?);
                 }
             } else if attr.is_dir() {
-                let dir_max_time =
-                    self.load_from_directory(&path, last, fuzzer, executor, state, manager)?;
-                if let Some(time) = dir_max_time {
-                    max_time = Some(max_time.map_or(time, |t: SystemTime| t.max(time)));
-                }
+                self.load_from_directory(&path, last, synced, fuzzer, executor, state, manager)?;
             }
         }
 
-        Ok(max_time)
+        Ok(())
     }
 }
 
@@ -165,13 +251,53 @@ where
     /// Creates a new [`SyncFromDiskStage`] invoking `Input::from_file` to load inputs
     #[must_use]
     pub fn with_from_file(sync_dir: PathBuf) -> Self {
+        Self::with_dirs_from_file(vec![sync_dir])
+    }
+
+    /// Creates a new [`SyncFromDiskStage`] syncing with all the given directories,
+    /// invoking `Input::from_file` to load inputs
+    #[must_use]
+    pub fn with_dirs_from_file(sync_dirs: Vec<PathBuf>) -> Self {
         fn load_callback<Z, S, I: Input>(_: &mut Z, _: &mut S, p: &Path) -> Result<I, Error> {
             I::from_file(p)
         }
         Self {
-            sync_dir,
+            sync_dirs,
+            interval: Duration::ZERO,
             load_callback: load_callback::<_, _, I>,
             phantom: PhantomData,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::{path::Path, time::SystemTime};
+
+    use crate::stages::sync::SyncedFiles;
+
+    #[test]
+    fn test_synced_files_same_mtime() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let t1 = t0 + Duration::from_secs(1);
+        let mut synced = SyncedFiles::new(t0);
+        assert!(synced.is_new(Path::new("a"), t0));
+        assert!(!synced.is_new(Path::new("a"), t0 - Duration::from_secs(1)));
+
+        synced.record(Path::new("a"), t1);
+        assert!(!synced.is_new(Path::new("a"), t1));
+        // Written in the same second as an already imported file
+        assert!(synced.is_new(Path::new("b"), t1));
+        synced.record(Path::new("b"), t1);
+        assert!(!synced.is_new(Path::new("b"), t1));
+
+        // Older files do not move the time back
+        synced.record(Path::new("c"), t0);
+        assert_eq!(synced.last_time, t1);
+        assert_eq!(synced.names.len(), 2);
+
+        synced.record(Path::new("d"), t1 + Duration::from_secs(1));
+        assert_eq!(synced.names.len(), 1);
+    }
+}
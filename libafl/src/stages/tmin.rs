@@ -0,0 +1,247 @@
+//! The [`TMinMutationalStage`] shrinks a testcase while it keeps its exit kind and coverage,
+//! like `afl-tmin`, and stores the result next to the original.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Debug, marker::PhantomData};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    observers::{MapObserver, ObserversTuple},
+    stages::Stage,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// The default maximum number of executions spent on minimizing a single testcase
+pub const DEFAULT_TMIN_MAX_EXECUTIONS: usize = 4096;
+
+/// The byte the simplification pass replaces the others with, as `afl-tmin` does
+const SIMPLE_BYTE: u8 = b'0';
+
+/// The minimized bytes of a testcase, stored alongside its original input
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MinimizedInputMetadata {
+    /// The bytes of the minimized input
+    pub bytes: Vec<u8>,
+    /// The executions the minimization took
+    pub executions: usize,
+}
+
+crate::impl_serdeany!(MinimizedInputMetadata);
+
+/// A stage minimizing testcases by removing blocks of bytes and replacing bytes with `'0'`.
+/// A shrinking step is kept if the target exits the same way; for the inputs that do not crash
+/// or time out, the hash of the map observer must stay the same as well.
+#[derive(Clone, Debug)]
+pub struct TMinMutationalStage<EM, I, O, OT, S, Z>
+where
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I>,
+{
+    map_observer_name: String,
+    max_executions: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(EM, I, O, OT, S, Z)>,
+}
+
+impl<E, EM, I, O, OT, S, Z> Stage<E, EM, S, Z> for TMinMutationalStage<EM, I, O, OT, S, Z>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        start_timer!(state);
+        let input = {
+            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+            if entry.has_metadata::<MinimizedInputMetadata>() {
+                return Ok(());
+            }
+            entry.load_input()?.clone()
+        };
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+        let executions = *state.executions();
+        let minimized = self.minimize(fuzzer, executor, state, manager, &input)?;
+        let meta = MinimizedInputMetadata {
+            bytes: minimized.bytes().to_vec(),
+            executions: *state.executions() - executions,
+        };
+
+        let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+        #[cfg(feature = "std")]
+        if let Some(filename) = entry.filename() {
+            // Put the minimized input next to the original one on disk, hidden like the
+            // metadata sidecars so that it is not taken for a testcase of its own
+            let path = std::path::Path::new(filename);
+            let min_path = path.with_file_name(format!(
+                ".{}.min",
+                path.file_name().unwrap().to_string_lossy()
+            ));
+            std::fs::write(min_path, &meta.bytes)?;
+        }
+        entry.add_metadata(meta);
+        Ok(())
+    }
+}
+
+impl<EM, I, O, OT, S, Z> TMinMutationalStage<EM, I, O, OT, S, Z>
+where
+    I: Input + HasBytesVec,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus<I>,
+{
+    /// Create a new [`TMinMutationalStage`] comparing the coverage of the given map observer
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self::from_name(map_observer.name())
+    }
+
+    /// Create a new [`TMinMutationalStage`] comparing the coverage of the map observer with the given name
+    #[must_use]
+    pub fn from_name(map_observer_name: &str) -> Self {
+        Self {
+            map_observer_name: map_observer_name.to_string(),
+            max_executions: DEFAULT_TMIN_MAX_EXECUTIONS,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Spend at most `max_executions` executions on each testcase
+    #[must_use]
+    pub fn with_max_executions(mut self, max_executions: usize) -> Self {
+        self.max_executions = max_executions;
+        self
+    }
+
+    /// Minimize `input`, e.g. a crash from the solutions, returning the smallest variant found
+    pub fn minimize<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+    ) -> Result<I, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        let expected = self.run(fuzzer, executor, state, manager, input)?;
+        let mut budget = self.max_executions;
+        let mut current = input.clone();
+
+        loop {
+            let mut changed = false;
+
+            // Remove blocks, from half the input down to single bytes
+            let mut block_len = current.bytes().len() / 2;
+            while block_len > 0 {
+                let mut pos = 0;
+                while pos < current.bytes().len() {
+                    if budget == 0 {
+                        return Ok(current);
+                    }
+                    budget -= 1;
+                    let end = (pos + block_len).min(current.bytes().len());
+                    let mut candidate = current.clone();
+                    candidate.bytes_mut().drain(pos..end);
+                    if self.run(fuzzer, executor, state, manager, &candidate)? == expected {
+                        current = candidate;
+                        changed = true;
+                    } else {
+                        pos += block_len;
+                    }
+                }
+                block_len /= 2;
+            }
+
+            // Simplify the remaining bytes
+            for pos in 0..current.bytes().len() {
+                if current.bytes()[pos] == SIMPLE_BYTE {
+                    continue;
+                }
+                if budget == 0 {
+                    return Ok(current);
+                }
+                budget -= 1;
+                let mut candidate = current.clone();
+                candidate.bytes_mut()[pos] = SIMPLE_BYTE;
+                if self.run(fuzzer, executor, state, manager, &candidate)? == expected {
+                    current = candidate;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return Ok(current);
+            }
+        }
+    }
+
+    /// Run `input`, returning its exit kind and, if it exited normally, the hash of the map
+    fn run<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+    ) -> Result<(ExitKind, Option<u64>), Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        start_timer!(state);
+        executor.observers_mut().pre_exec_all(state, input)?;
+        mark_feature_time!(state, PerfFeature::PreExecObservers);
+
+        start_timer!(state);
+        let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
+        mark_feature_time!(state, PerfFeature::TargetExecution);
+
+        *state.executions_mut() += 1;
+
+        start_timer!(state);
+        executor
+            .observers_mut()
+            .post_exec_all(state, input, &exit_kind)?;
+        mark_feature_time!(state, PerfFeature::PostExecObservers);
+
+        // A crash keeps crashing on another path, only compare the coverage of the normal runs
+        let hash = if exit_kind == ExitKind::Ok {
+            Some(
+                executor
+                    .observers()
+                    .match_name::<O>(&self.map_observer_name)
+                    .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
+                    .hash(),
+            )
+        } else {
+            None
+        };
+        Ok((exit_kind, hash))
+    }
+}
@@ -136,22 +136,16 @@ where
         mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
 
         start_timer!(state);
-        executor
-            .shadow_observers_mut()
-            .pre_exec_all(state, &input)?;
         executor.observers_mut().pre_exec_all(state, &input)?;
         mark_feature_time!(state, PerfFeature::PreExecObservers);
 
         start_timer!(state);
-        let exit_kind = executor.run_target(fuzzer, state, manager, &input)?;
+        let exit_kind = executor.run_target_shadowed(fuzzer, state, manager, &input)?;
         mark_feature_time!(state, PerfFeature::TargetExecution);
 
         *state.executions_mut() += 1;
 
         start_timer!(state);
-        executor
-            .shadow_observers_mut()
-            .post_exec_all(state, &input, &exit_kind)?;
         executor
             .observers_mut()
             .post_exec_all(state, &input, &exit_kind)?;
@@ -1,17 +1,27 @@
 //! The fuzzer, and state are the core pieces of every good fuzzer
 
+#[cfg(feature = "std")]
+use ahash::AHasher;
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use core::hash::Hasher;
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
+#[cfg(feature = "std")]
+use hashbrown::HashMap;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 #[cfg(feature = "std")]
 use std::{
     fs,
+    io::Write,
     path::{Path, PathBuf},
 };
 
 use crate::{
     bolts::{
         rands::Rand,
-        serdeany::{SerdeAny, SerdeAnyMap},
+        serdeany::{NamedSerdeAnyMap, SerdeAny, SerdeAnyMap},
     },
     corpus::Corpus,
     events::{Event, EventFirer, LogSeverity},
@@ -19,13 +29,69 @@ use crate::{
     fuzzer::{Evaluator, ExecuteInputResult},
     generators::Generator,
     inputs::Input,
-    monitors::ClientPerfMonitor,
+    monitors::{ClientPerfMonitor, UserStatsTracker},
     Error,
 };
 
 /// The maximum size of a testcase
 pub const DEFAULT_MAX_SIZE: usize = 1_048_576;
 
+/// The version of the on-disk format written by [`StdState::save_to`].
+/// Version 2 added the named metadata and the user stats, version 3 saves each field on its own,
+/// by name, and version 4 saves the named metadata by registered type name.
+/// The testcases of versions 2 and 3 have another layout, so those versions do not load anymore.
+pub const STATE_FORMAT_VERSION: u32 = 4;
+/// The first bytes of a state file
+#[cfg(feature = "std")]
+const STATE_FILE_MAGIC: &[u8; 8] = b"LIBAFLST";
+/// The magic, the format version, and the checksum of the payload
+#[cfg(feature = "std")]
+const STATE_FILE_HEADER_LEN: usize = 8 + 4 + 8;
+
+/// The payload of a state file, since format version 4.
+/// The fields of the state and its metadata are serialized one by one, with their names,
+/// so the ones unknown on load are skipped, and the missing ones get their default.
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize)]
+struct StateFile {
+    /// The fields of the state, but the metadata, by name
+    fields: Vec<(String, Vec<u8>)>,
+    /// The metadata of the state by registered name
+    metadata: Vec<(String, Vec<u8>)>,
+}
+
+/// The payload of a state file, in format version 1
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize)]
+struct StateFileV1 {
+    /// The metadata of the state by type name, to skip the types unknown on load
+    metadata: Vec<(String, Vec<u8>)>,
+    /// The state, without its metadata
+    state: Vec<u8>,
+}
+
+/// A [`StdState`] in format version 1, before the named metadata and the user stats
+#[cfg(feature = "std")]
+#[derive(Deserialize)]
+#[cfg_attr(test, derive(Serialize))]
+#[serde(bound(
+    serialize = "C: Serialize, FT: Serialize, R: Serialize, SC: Serialize",
+    deserialize = "C: DeserializeOwned, FT: DeserializeOwned, R: DeserializeOwned, SC: DeserializeOwned"
+))]
+struct StdStateV1<C, FT, R, SC> {
+    rand: R,
+    executions: usize,
+    start_time: Duration,
+    corpus: C,
+    feedback_states: FT,
+    solutions: SC,
+    metadata: SerdeAnyMap,
+    max_size: usize,
+    stability: Option<f32>,
+    #[cfg(feature = "introspection")]
+    introspection_monitor: ClientPerfMonitor,
+}
+
 /// The [`State`] of the fuzzer.
 /// Contains all important information about the current run.
 /// Will be used to restart the fuzzing process at any timme.
@@ -69,8 +135,22 @@ pub trait HasRand {
     fn rand_mut(&mut self) -> &mut Self::Rand;
 }
 
+/// Trait for states tracking named user stats, reported to the monitors.
+/// The default implementation tracks nothing.
+pub trait HasUserStats {
+    /// The named user stats of this node, if tracked
+    fn user_stats(&self) -> Option<&UserStatsTracker> {
+        None
+    }
+
+    /// The named user stats of this node (mutable), e.g. to count something in the harness
+    fn user_stats_mut(&mut self) -> Option<&mut UserStatsTracker> {
+        None
+    }
+}
+
 /// Trait for offering a [`ClientPerfMonitor`]
-pub trait HasClientPerfMonitor {
+pub trait HasClientPerfMonitor: HasUserStats {
     /// [`ClientPerfMonitor`] itself
     fn introspection_monitor(&self) -> &ClientPerfMonitor;
 
@@ -110,6 +190,33 @@ pub trait HasMetadata {
     }
 }
 
+/// Trait for elements offering metadata by name, next to the metadata by type, e.g. to store
+/// several instances of the same metadata type
+pub trait HasNamedMetadata {
+    /// A map, storing all named metadata
+    fn named_metadata(&self) -> &NamedSerdeAnyMap;
+    /// A map, storing all named metadata (mutable)
+    fn named_metadata_mut(&mut self) -> &mut NamedSerdeAnyMap;
+
+    /// Add a metadata named `name` to the named metadata map
+    #[inline]
+    fn add_named_metadata<M>(&mut self, name: &str, meta: M)
+    where
+        M: SerdeAny,
+    {
+        self.named_metadata_mut().insert(Box::new(meta), name);
+    }
+
+    /// Check for a metadata named `name`
+    #[inline]
+    fn has_named_metadata<M>(&self, name: &str) -> bool
+    where
+        M: SerdeAny,
+    {
+        self.named_metadata().contains::<M>(name)
+    }
+}
+
 /// Trait for elements offering a feedback
 pub trait HasFeedbackStates {
     /// The associated feedback type implementing [`FeedbackStatesTuple`].
@@ -164,10 +271,14 @@ where
     solutions: SC,
     /// Metadata stored for this state by one of the components
     metadata: SerdeAnyMap,
+    /// Metadata stored for this state by name
+    named_metadata: NamedSerdeAnyMap,
     /// MaxSize testcase size for mutators that appreciate it
     max_size: usize,
     /// The stability of the current fuzzing process
     stability: Option<f32>,
+    /// The user stats, sent to the monitors with the next report
+    user_stats: UserStatsTracker,
 
     /// Performance statistics for this fuzzer
     #[cfg(feature = "introspection")]
@@ -276,6 +387,25 @@ where
     }
 }
 
+impl<C, FT, I, R, SC> HasNamedMetadata for StdState<C, FT, I, R, SC>
+where
+    C: Corpus<I>,
+    I: Input,
+    R: Rand,
+    FT: FeedbackStatesTuple,
+    SC: Corpus<I>,
+{
+    #[inline]
+    fn named_metadata(&self) -> &NamedSerdeAnyMap {
+        &self.named_metadata
+    }
+
+    #[inline]
+    fn named_metadata_mut(&mut self) -> &mut NamedSerdeAnyMap {
+        &mut self.named_metadata
+    }
+}
+
 impl<C, FT, I, R, SC> HasFeedbackStates for StdState<C, FT, I, R, SC>
 where
     C: Corpus<I>,
@@ -478,6 +608,198 @@ where
     {
         self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, false)
     }
+
+    /// Saves this state to `path`, in the versioned format of [`STATE_FORMAT_VERSION`].
+    /// The file gets replaced atomically, so a crash while saving leaves the last save intact.
+    pub fn save_to<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        #[allow(unused_mut)]
+        let mut fields = vec![
+            state_file_field("rand", &self.rand)?,
+            state_file_field("executions", &self.executions)?,
+            state_file_field("start_time", &self.start_time)?,
+            state_file_field("corpus", &self.corpus)?,
+            state_file_field("feedback_states", &self.feedback_states)?,
+            state_file_field("solutions", &self.solutions)?,
+            state_file_field("named_metadata", &self.named_metadata.to_named_entries()?)?,
+            state_file_field("max_size", &self.max_size)?,
+            state_file_field("stability", &self.stability)?,
+            state_file_field("user_stats", &self.user_stats)?,
+        ];
+        #[cfg(feature = "introspection")]
+        fields.push(state_file_field(
+            "introspection_monitor",
+            &self.introspection_monitor,
+        )?);
+        let payload = postcard::to_allocvec(&StateFile {
+            fields,
+            metadata: self.metadata.to_named_entries()?,
+        })?;
+
+        let mut bytes = Vec::with_capacity(STATE_FILE_HEADER_LEN + payload.len());
+        bytes.extend_from_slice(STATE_FILE_MAGIC);
+        bytes.extend_from_slice(&STATE_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&state_file_checksum(&payload).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let mut tmp_file = fs::File::create(&tmp_path)?;
+        tmp_file.write_all(&bytes)?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Loads a state saved with [`StdState::save_to`], by this or an older version of `LibAFL`.
+    /// The fields and the state metadata unknown to this build are skipped.
+    /// The metadata of the testcases has to be known, though.
+    pub fn load_from<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = fs::read(path)?;
+        if bytes.len() < STATE_FILE_HEADER_LEN || &bytes[..8] != STATE_FILE_MAGIC {
+            return Err(Error::IllegalArgument("Not a LibAFL state file".into()));
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        let checksum = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
+        let payload = &bytes[STATE_FILE_HEADER_LEN..];
+        if version == 0 || version > STATE_FORMAT_VERSION {
+            return Err(Error::IllegalState(format!(
+                "Unsupported state file version {}, this build reads up to version {}",
+                version, STATE_FORMAT_VERSION
+            )));
+        }
+        if version == 2 || version == 3 {
+            return Err(Error::IllegalState(format!(
+                "State file version {} is not supported anymore, its testcases have another layout",
+                version
+            )));
+        }
+        if state_file_checksum(payload) != checksum {
+            return Err(Error::IllegalState("The state file is corrupted".into()));
+        }
+
+        let (mut state, metadata) = if version == 1 {
+            let file: StateFileV1 = postcard::from_bytes(payload)?;
+            (Self::from_v1(&file.state)?, file.metadata)
+        } else {
+            let file: StateFile = postcard::from_bytes(payload)?;
+            (Self::from_fields(file.fields)?, file.metadata)
+        };
+        let (metadata, skipped) = SerdeAnyMap::from_named_entries(metadata)?;
+        for name in skipped {
+            println!("Skipping the unknown state metadata {}", name);
+        }
+        state.metadata = metadata;
+        Ok(state)
+    }
+
+    /// The state from its fields in a state file, the metadata aside
+    fn from_fields(fields: Vec<(String, Vec<u8>)>) -> Result<Self, Error> {
+        let mut fields: HashMap<String, Vec<u8>> = fields.into_iter().collect();
+        let mut state = Self {
+            rand: take_required_state_file_field(&mut fields, "rand")?,
+            executions: take_state_file_field(&mut fields, "executions")?.unwrap_or_default(),
+            start_time: take_state_file_field(&mut fields, "start_time")?.unwrap_or_default(),
+            corpus: take_required_state_file_field(&mut fields, "corpus")?,
+            feedback_states: take_required_state_file_field(&mut fields, "feedback_states")?,
+            solutions: take_required_state_file_field(&mut fields, "solutions")?,
+            metadata: SerdeAnyMap::new(),
+            named_metadata: NamedSerdeAnyMap::new(),
+            max_size: take_state_file_field(&mut fields, "max_size")?.unwrap_or(DEFAULT_MAX_SIZE),
+            stability: take_state_file_field(&mut fields, "stability")?.flatten(),
+            user_stats: take_state_file_field(&mut fields, "user_stats")?.unwrap_or_default(),
+            #[cfg(feature = "introspection")]
+            introspection_monitor: take_state_file_field(&mut fields, "introspection_monitor")?
+                .unwrap_or_else(ClientPerfMonitor::new),
+            phantom: PhantomData,
+        };
+        if let Some(entries) = take_state_file_field(&mut fields, "named_metadata")? {
+            let (named_metadata, skipped) = NamedSerdeAnyMap::from_named_entries(entries)?;
+            for name in skipped {
+                println!("Skipping the unknown named state metadata {}", name);
+            }
+            state.named_metadata = named_metadata;
+        }
+        for name in fields.keys() {
+            println!("Skipping the unknown state field {}", name);
+        }
+        Ok(state)
+    }
+
+    /// The state from the positional serialization of format version 1, the metadata aside
+    fn from_v1(bytes: &[u8]) -> Result<Self, Error> {
+        let old: StdStateV1<C, FT, R, SC> = postcard::from_bytes(bytes)?;
+        Ok(Self {
+            rand: old.rand,
+            executions: old.executions,
+            start_time: old.start_time,
+            corpus: old.corpus,
+            feedback_states: old.feedback_states,
+            solutions: old.solutions,
+            metadata: old.metadata,
+            named_metadata: NamedSerdeAnyMap::new(),
+            max_size: old.max_size,
+            stability: old.stability,
+            user_stats: UserStatsTracker::new(),
+            #[cfg(feature = "introspection")]
+            introspection_monitor: old.introspection_monitor,
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// A field of a state file, by name
+#[cfg(feature = "std")]
+fn state_file_field<T>(name: &str, value: &T) -> Result<(String, Vec<u8>), Error>
+where
+    T: Serialize,
+{
+    Ok((name.to_string(), postcard::to_allocvec(value)?))
+}
+
+/// Deserializes the field of a state file with the given name, if the file has it
+#[cfg(feature = "std")]
+fn take_state_file_field<T>(
+    fields: &mut HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<Option<T>, Error>
+where
+    T: DeserializeOwned,
+{
+    fields
+        .remove(name)
+        .map(|bytes| {
+            postcard::from_bytes(&bytes)
+                .map_err(|err| Error::Serialize(format!("State field {}: {}", name, err)))
+        })
+        .transpose()
+}
+
+/// Deserializes a field every state file has
+#[cfg(feature = "std")]
+fn take_required_state_file_field<T>(
+    fields: &mut HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    take_state_file_field(fields, name)?
+        .ok_or_else(|| Error::IllegalState(format!("The state file has no {}", name)))
+}
+
+/// The checksum of the payload of a state file
+#[cfg(feature = "std")]
+fn state_file_checksum(payload: &[u8]) -> u64 {
+    let mut hasher = AHasher::new_with_keys(0, 0);
+    hasher.write(payload);
+    hasher.finish()
 }
 
 impl<C, FT, I, R, SC> StdState<C, FT, I, R, SC>
@@ -566,8 +888,10 @@ where
             rand,
             executions: 0,
             stability: None,
+            user_stats: UserStatsTracker::new(),
             start_time: Duration::from_millis(0),
             metadata: SerdeAnyMap::default(),
+            named_metadata: NamedSerdeAnyMap::default(),
             corpus,
             feedback_states,
             solutions,
@@ -639,6 +963,25 @@ where
     }
 }
 
+impl<C, FT, I, R, SC> HasUserStats for StdState<C, FT, I, R, SC>
+where
+    C: Corpus<I>,
+    I: Input,
+    R: Rand,
+    FT: FeedbackStatesTuple,
+    SC: Corpus<I>,
+{
+    #[inline]
+    fn user_stats(&self) -> Option<&UserStatsTracker> {
+        Some(&self.user_stats)
+    }
+
+    #[inline]
+    fn user_stats_mut(&mut self) -> Option<&mut UserStatsTracker> {
+        Some(&mut self.user_stats)
+    }
+}
+
 #[cfg(feature = "python")]
 /// `State` Python bindings
 pub mod pybind {
@@ -823,3 +1166,267 @@ pub mod pybind {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use core::{any::TypeId, time::Duration};
+    use serde::{Deserialize, Serialize};
+    use std::{env, fs, path::PathBuf, process};
+
+    #[cfg(feature = "introspection")]
+    use crate::monitors::ClientPerfMonitor;
+    use crate::{
+        bolts::{
+            anymap::unpack_type_id,
+            rands::StdRand,
+            serdeany::{registered_id, registered_name, RegistryBuilder, SerdeAnyMap},
+        },
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        state::{
+            state_file_checksum, HasCorpus, HasExecutions, HasMaxSize, HasMetadata,
+            HasNamedMetadata, HasUserStats, StateFile, StateFileV1, StdState, StdStateV1,
+            DEFAULT_MAX_SIZE, STATE_FILE_MAGIC,
+        },
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestStateMetadata {
+        val: u32,
+    }
+
+    crate::impl_serdeany!(TestStateMetadata);
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct OtherStateMetadata {}
+
+    crate::impl_serdeany!(OtherStateMetadata);
+
+    /// A fresh directory for the files of a test, unique to the test and this process
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("libafl_test_{}_{}", name, process::id()));
+        drop(fs::remove_dir_all(&dir));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The bytes of a state file of the given version and payload
+    fn state_file(version: u32, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = STATE_FILE_MAGIC.to_vec();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        bytes.extend_from_slice(&state_file_checksum(payload).to_le_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn test_state_save_load() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        state.add_metadata(TestStateMetadata { val: 1337 });
+        *state.executions_mut() = 42;
+
+        let dir = test_dir("state_save_load");
+        let path = dir.join("state");
+        state.save_to(&path).unwrap();
+        let loaded = TestState::load_from(&path).unwrap();
+        assert_eq!(*loaded.executions(), 42);
+        assert_eq!(
+            loaded.metadata().get::<TestStateMetadata>().unwrap().val,
+            1337
+        );
+
+        // A corrupted save does not load
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+        assert!(TestState::load_from(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_state_load_fields() {
+        let state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        // The file of a newer build, with a field unknown here, and without most known ones
+        let payload = postcard::to_allocvec(&StateFile {
+            fields: vec![
+                ("rand".into(), postcard::to_allocvec(&state.rand).unwrap()),
+                (
+                    "corpus".into(),
+                    postcard::to_allocvec(&state.corpus).unwrap(),
+                ),
+                (
+                    "feedback_states".into(),
+                    postcard::to_allocvec(&()).unwrap(),
+                ),
+                (
+                    "solutions".into(),
+                    postcard::to_allocvec(&state.solutions).unwrap(),
+                ),
+                (
+                    "executions".into(),
+                    postcard::to_allocvec(&9_usize).unwrap(),
+                ),
+                ("from_the_future".into(), vec![1, 2, 3]),
+            ],
+            metadata: vec![("UnknownMetadata".into(), vec![0])],
+        })
+        .unwrap();
+
+        let dir = test_dir("state_load_fields");
+        let path = dir.join("state");
+        fs::write(&path, state_file(4, &payload)).unwrap();
+        let loaded = TestState::load_from(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(*loaded.executions(), 9);
+        assert_eq!(loaded.max_size(), DEFAULT_MAX_SIZE);
+        assert!(loaded.metadata().is_empty());
+    }
+
+    #[test]
+    fn test_registered_name() {
+        let mut metadata = SerdeAnyMap::new();
+        metadata.insert(TestStateMetadata { val: 7 });
+        let entries = metadata.to_named_entries().unwrap();
+        assert_eq!(entries[0].0, "libafl::state::tests::TestStateMetadata");
+        assert_eq!(
+            registered_name(unpack_type_id(TypeId::of::<TestStateMetadata>())),
+            Some("libafl::state::tests::TestStateMetadata")
+        );
+
+        // Some older files name the types without their module path
+        let old_entries = vec![("TestStateMetadata".to_string(), entries[0].1.clone())];
+        let (loaded, skipped) = SerdeAnyMap::from_named_entries(old_entries).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(loaded.get::<TestStateMetadata>().unwrap().val, 7);
+    }
+
+    #[test]
+    fn test_registered_name_collision() {
+        // The name stays with the type registered first
+        assert!(RegistryBuilder::register_named::<OtherStateMetadata>(
+            "libafl::state::tests::TestStateMetadata"
+        )
+        .is_err());
+        assert_eq!(
+            registered_id("libafl::state::tests::TestStateMetadata"),
+            Some(unpack_type_id(TypeId::of::<TestStateMetadata>()))
+        );
+        assert_eq!(
+            registered_name(unpack_type_id(TypeId::of::<OtherStateMetadata>())),
+            Some("libafl::state::tests::OtherStateMetadata")
+        );
+    }
+
+    #[test]
+    fn test_state_load_v1() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus
+            .add(Testcase::new(BytesInput::new(b"old".to_vec())))
+            .unwrap();
+        let old = StdStateV1 {
+            rand: StdRand::with_seed(0),
+            executions: 7,
+            start_time: Duration::from_millis(0),
+            corpus,
+            feedback_states: (),
+            solutions: InMemoryCorpus::<BytesInput>::new(),
+            metadata: SerdeAnyMap::default(),
+            max_size: 16,
+            stability: None,
+            #[cfg(feature = "introspection")]
+            introspection_monitor: ClientPerfMonitor::new(),
+        };
+        let payload = postcard::to_allocvec(&StateFileV1 {
+            metadata: vec![],
+            state: postcard::to_allocvec(&old).unwrap(),
+        })
+        .unwrap();
+
+        let dir = test_dir("state_load_v1");
+        let path = dir.join("state");
+        fs::write(&path, state_file(1, &payload)).unwrap();
+        let loaded = TestState::load_from(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(*loaded.executions(), 7);
+        assert_eq!(loaded.max_size(), 16);
+        assert!(loaded.user_stats().is_some());
+        assert!(loaded.named_metadata().is_empty());
+        assert_eq!(loaded.corpus().count(), 1);
+    }
+
+    #[test]
+    fn test_named_metadata() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        state.add_named_metadata("phase1", TestStateMetadata { val: 1 });
+        state.add_named_metadata("phase2", TestStateMetadata { val: 2 });
+
+        assert!(state.has_named_metadata::<TestStateMetadata>("phase1"));
+        assert!(!state.has_metadata::<TestStateMetadata>());
+        let named = state.named_metadata();
+        assert_eq!(named.get::<TestStateMetadata>("phase2").unwrap().val, 2);
+
+        let removed = state
+            .named_metadata_mut()
+            .remove::<TestStateMetadata>("phase1")
+            .unwrap();
+        assert_eq!(removed.val, 1);
+        assert!(!state.has_named_metadata::<TestStateMetadata>("phase1"));
+    }
+
+    #[test]
+    fn test_named_metadata_save_load() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        state.add_named_metadata("phase1", TestStateMetadata { val: 1 });
+        let mut testcase = Testcase::new(BytesInput::new(b"new".to_vec()));
+        testcase.add_named_metadata("phase2", TestStateMetadata { val: 2 });
+        let idx = state.corpus_mut().add(testcase).unwrap();
+
+        let dir = test_dir("named_metadata_save_load");
+        let path = dir.join("state");
+        state.save_to(&path).unwrap();
+        let loaded = TestState::load_from(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            loaded
+                .named_metadata()
+                .get::<TestStateMetadata>("phase1")
+                .unwrap()
+                .val,
+            1
+        );
+        let testcase = loaded.corpus().get(idx).unwrap().borrow();
+        assert!(testcase.metadata().is_empty());
+        assert_eq!(
+            testcase
+                .named_metadata()
+                .get::<TestStateMetadata>("phase2")
+                .unwrap()
+                .val,
+            2
+        );
+    }
+}
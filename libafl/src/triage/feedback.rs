@@ -0,0 +1,163 @@
+//! The [`CrashBucketFeedback`] sorts the solutions into buckets and counts the crashes per bucket.
+
+use alloc::string::{String, ToString};
+use core::{fmt::Debug, marker::PhantomData};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    corpus::Testcase,
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    monitors::UserStats,
+    observers::{ObserverWithHashField, ObserversTuple},
+    state::{HasClientPerfMonitor, HasMetadata},
+    triage::{take_last_crash, BucketKey},
+    Error,
+};
+
+/// The bucket of a solution
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct CrashBucketMetadata {
+    /// The id of the bucket, see [`BucketKey::bucket_id`]
+    pub bucket: u64,
+    /// The properties of the crash the bucket is made of
+    pub key: BucketKey,
+}
+
+crate::impl_serdeany!(CrashBucketMetadata);
+
+/// The number of crashes seen per bucket, in the state
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CrashBucketsMetadata {
+    /// The number of crashes per bucket id
+    pub counts: HashMap<u64, usize>,
+}
+
+crate::impl_serdeany!(
+    CrashBucketsMetadata,
+    "libafl::triage::feedback::CrashBucketsMetadata"
+);
+
+/// A feedback putting each crash into a bucket, by signal, faulting address class and stack hash.
+///
+/// It is meant to run last in the objective, once the crash is known to be a solution, e.g.
+/// `feedback_and_fast!(CrashFeedback::new(), CrashBucketFeedback::new(&backtrace_observer))`.
+/// Every crash is counted in [`CrashBucketsMetadata`] and reported to the monitor, by default
+/// it keeps them all; with [`CrashBucketFeedback::only_new_buckets`] it keeps the first of each bucket.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CrashBucketFeedback<O> {
+    name: String,
+    observer_name: String,
+    only_new_buckets: bool,
+    last_bucket: Option<CrashBucketMetadata>,
+    phantom: PhantomData<O>,
+}
+
+impl<I, S, O> Feedback<I, S> for CrashBucketFeedback<O>
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasMetadata,
+    O: ObserverWithHashField + Named + Debug,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let stack_hash = observers
+            .match_name::<O>(&self.observer_name)
+            .and_then(|observer| *observer.hash());
+        let key = BucketKey::new(&take_last_crash().unwrap_or_default(), stack_hash);
+        let bucket = key.bucket_id();
+        self.last_bucket = Some(CrashBucketMetadata { bucket, key });
+
+        if !state.has_metadata::<CrashBucketsMetadata>() {
+            state.add_metadata(CrashBucketsMetadata::default());
+        }
+        let buckets = state
+            .metadata_mut()
+            .get_mut::<CrashBucketsMetadata>()
+            .unwrap();
+        let count = buckets.counts.entry(bucket).or_insert(0);
+        *count += 1;
+        let count = *count;
+        let bucket_count = buckets.counts.len();
+
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: format!("bucket {:016x}", bucket),
+                value: UserStats::Number(count as u64),
+                phantom: PhantomData,
+            },
+        )?;
+        if count == 1 {
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name: "crash buckets".to_string(),
+                    value: UserStats::Number(bucket_count as u64),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+
+        Ok(!self.only_new_buckets || count == 1)
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(bucket) = self.last_bucket.take() {
+            testcase.add_metadata(bucket);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_bucket = None;
+        Ok(())
+    }
+}
+
+impl<O> Named for CrashBucketFeedback<O> {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<O> CrashBucketFeedback<O>
+where
+    O: ObserverWithHashField + Named + Debug,
+{
+    /// Creates a new [`CrashBucketFeedback`], taking the stack hash from the given observer,
+    /// e.g. a [`crate::observers::BacktraceObserver`]
+    #[must_use]
+    pub fn new(observer: &O) -> Self {
+        Self {
+            name: "CrashBucketFeedback".to_string(),
+            observer_name: observer.name().to_string(),
+            only_new_buckets: false,
+            last_bucket: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Only consider the first crash of each bucket a solution
+    #[must_use]
+    pub fn only_new_buckets(mut self) -> Self {
+        self.only_new_buckets = true;
+        self
+    }
+}
@@ -0,0 +1,153 @@
+//! Crash triage: classify the crashes found by the objective into buckets.
+//!
+//! A crash is bucketed by the signal it raised, the class of the faulting address, and the hash
+//! of its stack, see [`BucketKey`]. Executors record the signal and the faulting address of the
+//! last crash with [`record_crash`], the [`CrashBucketFeedback`] then stores the bucket in the
+//! metadata of each solution and reports the number of crashes per bucket to the monitor.
+
+pub mod feedback;
+pub use feedback::*;
+
+use core::hash::Hasher;
+
+use ahash::AHasher;
+use serde::{Deserialize, Serialize};
+
+/// Faulting addresses below this bound are considered near-null, e.g. a field of a null struct
+pub const NEAR_NULL_BOUND: u64 = 0x10000;
+
+/// Faulting addresses this close to the stack pointer are considered on the stack
+pub const STACK_WINDOW: u64 = 8 * 1024 * 1024;
+
+/// What the executor knows about the last crash
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CrashInfo {
+    /// The signal that terminated the target, if any
+    pub signal: Option<i32>,
+    /// The address that caused the fault, if known
+    pub fault_address: Option<u64>,
+    /// The stack pointer at the time of the crash, if known
+    pub stack_pointer: Option<u64>,
+}
+
+static mut LAST_CRASH: Option<CrashInfo> = None;
+
+/// Record the last crash, called by the executors before the objective runs
+pub fn record_crash(info: CrashInfo) {
+    unsafe {
+        LAST_CRASH = Some(info);
+    }
+}
+
+/// Take the last recorded crash, leaving nothing behind for the next run
+#[must_use]
+pub fn take_last_crash() -> Option<CrashInfo> {
+    unsafe { LAST_CRASH.take() }
+}
+
+/// The class of a faulting address
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressClass {
+    /// The null pointer itself
+    Null,
+    /// A small offset from the null pointer
+    NearNull,
+    /// An address close to the stack pointer
+    Stack,
+    /// Any other address
+    Wild,
+    /// The faulting address is not known
+    Unknown,
+}
+
+impl AddressClass {
+    /// Classify `address`, using the `stack_pointer` at the time of the crash if known
+    #[must_use]
+    pub fn classify(address: Option<u64>, stack_pointer: Option<u64>) -> Self {
+        match address {
+            None => Self::Unknown,
+            Some(0) => Self::Null,
+            Some(address) if address < NEAR_NULL_BOUND => Self::NearNull,
+            Some(address) => match stack_pointer {
+                Some(sp) if address.max(sp) - address.min(sp) < STACK_WINDOW => Self::Stack,
+                _ => Self::Wild,
+            },
+        }
+    }
+}
+
+/// The properties of a crash that decide its bucket
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BucketKey {
+    /// The signal that terminated the target, if any
+    pub signal: Option<i32>,
+    /// The class of the faulting address
+    pub address_class: AddressClass,
+    /// The hash of the stack of the crash, if known
+    pub stack_hash: Option<u64>,
+}
+
+impl BucketKey {
+    /// Creates the key of a crash
+    #[must_use]
+    pub fn new(info: &CrashInfo, stack_hash: Option<u64>) -> Self {
+        Self {
+            signal: info.signal,
+            address_class: AddressClass::classify(info.fault_address, info.stack_pointer),
+            stack_hash,
+        }
+    }
+
+    /// The id of the bucket, stable across runs and clients
+    #[must_use]
+    pub fn bucket_id(&self) -> u64 {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write_i64(self.signal.map_or(-1, i64::from));
+        hasher.write_u8(self.address_class as u8);
+        hasher.write_u64(self.stack_hash.unwrap_or_default());
+        hasher.write_u8(u8::from(self.stack_hash.is_some()));
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::triage::{AddressClass, BucketKey, CrashInfo};
+
+    #[test]
+    fn test_address_class() {
+        assert_eq!(AddressClass::classify(None, None), AddressClass::Unknown);
+        assert_eq!(AddressClass::classify(Some(0), None), AddressClass::Null);
+        assert_eq!(
+            AddressClass::classify(Some(0x18), None),
+            AddressClass::NearNull
+        );
+        assert_eq!(
+            AddressClass::classify(Some(0x7ffd_0000_0100), Some(0x7ffd_0000_1000)),
+            AddressClass::Stack
+        );
+        assert_eq!(
+            AddressClass::classify(Some(0x4141_4141), Some(0x7ffd_0000_1000)),
+            AddressClass::Wild
+        );
+    }
+
+    #[test]
+    fn test_bucket_id() {
+        let info = CrashInfo {
+            signal: Some(11),
+            fault_address: Some(0),
+            stack_pointer: None,
+        };
+        let key = BucketKey::new(&info, Some(1234));
+        assert_eq!(
+            key.bucket_id(),
+            BucketKey::new(&info, Some(1234)).bucket_id()
+        );
+        assert_ne!(
+            key.bucket_id(),
+            BucketKey::new(&info, Some(1235)).bucket_id()
+        );
+        assert_ne!(key.bucket_id(), BucketKey::new(&info, None).bucket_id());
+    }
+}
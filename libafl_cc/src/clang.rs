@@ -8,7 +8,7 @@ use std::{
     vec::Vec,
 };
 
-use crate::{CompilerWrapper, Error, LIB_EXT, LIB_PREFIX};
+use crate::{CompilerWrapper, EnvConfig, Error, LIB_EXT, LIB_PREFIX};
 
 fn dll_extension<'a>() -> &'a str {
     if cfg!(target_os = "windows") {
@@ -78,6 +78,7 @@ pub struct ClangWrapper {
     link_args: Vec<String>,
     passes: Vec<LLVMPasses>,
     passes_args: Vec<String>,
+    env_args: Vec<String>,
 }
 
 #[allow(clippy::match_same_arms)] // for the linking = false wip for "shared"
@@ -175,6 +176,8 @@ impl CompilerWrapper for ClangWrapper {
         // Fuzzing define common among tools
         new_args.push("-DFUZZING_BUILD_MODE_UNSAFE_FOR_PRODUCTION=1".into());
 
+        new_args.extend_from_slice(&self.env_args);
+
         // Libraries needed by libafl on Windows
         #[cfg(windows)]
         if linking {
@@ -226,17 +229,21 @@ impl CompilerWrapper for ClangWrapper {
     where
         S: AsRef<str>,
     {
+        let lib = dir
+            .join(format!("{}{}.{}", LIB_PREFIX, name.as_ref(), LIB_EXT))
+            .into_os_string()
+            .into_string()
+            .unwrap();
+        if cfg!(target_env = "msvc") {
+            // clang on windows-msvc links with `link.exe` or `lld-link`
+            return self.add_link_arg(format!("-Wl,/WHOLEARCHIVE:{}", lib));
+        }
         if cfg!(target_vendor = "apple") {
             //self.add_link_arg("-force_load".into())?;
         } else {
             self.add_link_arg("-Wl,--whole-archive");
         }
-        self.add_link_arg(
-            dir.join(format!("{}{}.{}", LIB_PREFIX, name.as_ref(), LIB_EXT))
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        );
+        self.add_link_arg(lib);
         if cfg!(target_vendor = "apple") {
             self
         } else {
@@ -331,6 +338,7 @@ impl ClangWrapper {
             link_args: vec![],
             passes: vec![],
             passes_args: vec![],
+            env_args: vec![],
             is_silent: false,
         }
     }
@@ -385,6 +393,43 @@ impl ClangWrapper {
         self.need_libafl_arg = value;
         self
     }
+
+    /// Configure the instrumentation, the passes, the sanitizers, and the runtime.
+    /// Has to be called before [`CompilerWrapper::parse_args`].
+    pub fn configure(&mut self, config: &EnvConfig) -> &'_ mut Self {
+        if config.dont_optimize {
+            self.optimize = false;
+        }
+        if config.quiet {
+            self.is_silent = true;
+        }
+        for pass in config.passes() {
+            if !self.passes.contains(&pass) {
+                self.passes.push(pass);
+            }
+        }
+        self.passes_args.extend(config.passes_args());
+        self.env_args.extend(config.args());
+        if let Some(runtime) = &config.runtime {
+            if cfg!(target_env = "msvc") {
+                self.add_link_arg(format!("-Wl,/WHOLEARCHIVE:{}", runtime.display()));
+            } else if cfg!(target_vendor = "apple") {
+                self.add_link_arg(format!("-Wl,-force_load,{}", runtime.display()));
+            } else {
+                self.add_link_arg("-Wl,--whole-archive");
+                self.add_link_arg(runtime.display().to_string());
+                self.add_link_arg("-Wl,-no-whole-archive");
+            }
+        }
+        self
+    }
+
+    /// Configure the wrapper with the `AFL_LLVM_*` and `AFL_USE_*` environment variables, see [`EnvConfig`].
+    /// Has to be called before [`CompilerWrapper::parse_args`].
+    pub fn configure_from_env(&mut self) -> Result<&'_ mut Self, Error> {
+        let config = EnvConfig::from_env()?;
+        Ok(self.configure(&config))
+    }
 }
 
 #[cfg(test)]
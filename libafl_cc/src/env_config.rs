@@ -0,0 +1,353 @@
+//! Configures the [`crate::ClangWrapper`] with the environment variables known from `AFL++`,
+//! such as `AFL_LLVM_INSTRUMENT`, `AFL_LLVM_CMPLOG`, or `AFL_USE_ASAN`.
+
+use std::{env, path::PathBuf, string::String, vec::Vec};
+
+use crate::{Error, LLVMPasses};
+
+/// The max size of the ngrams, as supported by the AFL pass and the runtime of `libafl_targets`
+pub const NGRAM_SIZE_MAX: u32 = 16;
+
+/// The max size of the context of K-context sensitivity, as supported by the AFL pass and the runtime
+pub const CTX_MAX_K: u32 = 32;
+
+/// Parses the numerical variable `name`, if set
+fn parse_var<F>(var: &F, name: &str) -> Result<Option<u32>, Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    var(name)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                Error::InvalidArguments(format!("Invalid value for {}: {}", name, value))
+            })
+        })
+        .transpose()
+}
+
+/// The edge coverage instrumentation, from `AFL_LLVM_INSTRUMENT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instrumentation {
+    /// `SanitizerCoverage` `trace-pc-guard`, for the `sancov_pcguard` runtime of `libafl_targets` (default)
+    PcGuard,
+    /// The AFL coverage pass (`AFL`, `CLASSIC`)
+    Afl,
+    /// No edge coverage (`NONE`), e.g. for cmplog-only builds
+    None,
+}
+
+/// The configuration of the wrapper, from the environment
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvConfig {
+    /// The edge coverage instrumentation, from `AFL_LLVM_INSTRUMENT`
+    pub instrumentation: Instrumentation,
+    /// Full context sensitive edges, hashing the calling context into each edge: `CTX` or `AFL_LLVM_CTX`
+    pub ctx: bool,
+    /// K-context sensitive edges, hashing the last `k` callers into each edge (0 to disable):
+    /// `CTX-K`, `CALLER` (`k = 1`), or `AFL_LLVM_CTX_K`
+    pub ctx_k: u32,
+    /// N-gram edges, hashing the last `n - 1` locations into each edge (0 to disable): `NGRAM-N` or `AFL_LLVM_NGRAM_SIZE`
+    pub ngram: u32,
+    /// Trace the comparisons and the comparing routines, from `AFL_LLVM_CMPLOG`
+    pub cmplog: bool,
+    /// Extract the tokens of the target into a dictionary, from `AFL_LLVM_AUTODICT` or `AFL_LLVM_DICT2FILE`
+    pub autodict: bool,
+    /// The instrumented share of the edges of the AFL pass in percent, from `AFL_INST_RATIO`
+    pub inst_ratio: Option<u32>,
+    /// The sanitizers to build with, from `AFL_USE_ASAN`, `AFL_USE_UBSAN`, `AFL_USE_MSAN`, and `AFL_USE_CFISAN`
+    pub sanitizers: Vec<&'static str>,
+    /// Only instrument the functions and files in this list, from `AFL_LLVM_ALLOWLIST`
+    pub allowlist: Option<PathBuf>,
+    /// Do not instrument the functions and files in this list, from `AFL_LLVM_DENYLIST`
+    pub denylist: Option<PathBuf>,
+    /// Do not add optimization flags, from `AFL_DONT_OPTIMIZE`
+    pub dont_optimize: bool,
+    /// Silence the wrapper, from `AFL_QUIET`
+    pub quiet: bool,
+    /// A static runtime to link, e.g. a fuzzer built with `libafl_targets`, from `LIBAFL_CC_RUNTIME`
+    pub runtime: Option<PathBuf>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            instrumentation: Instrumentation::PcGuard,
+            ctx: false,
+            ctx_k: 0,
+            ngram: 0,
+            cmplog: false,
+            autodict: false,
+            inst_ratio: None,
+            sanitizers: vec![],
+            allowlist: None,
+            denylist: None,
+            dont_optimize: false,
+            quiet: false,
+            runtime: None,
+        }
+    }
+}
+
+impl EnvConfig {
+    /// Reads the configuration from the environment variables
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Reads the configuration from the variables `var` returns, for the given name
+    pub fn from_vars<F>(var: F) -> Result<Self, Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        // Like AFL++, any value but `0` enables a flag
+        let flag = |name: &str| var(name).map_or(false, |value| value != "0");
+
+        // e.g. `CLASSIC,CTX` or `NGRAM-4`, options imply the AFL pass
+        let mut instrumentation = None;
+        let mut ctx = flag("AFL_LLVM_CTX");
+        let mut ctx_k = parse_var(&var, "AFL_LLVM_CTX_K")?.unwrap_or(0);
+        let mut ngram = parse_var(&var, "AFL_LLVM_NGRAM_SIZE")?.unwrap_or(0);
+        if flag("AFL_LLVM_CALLER") {
+            ctx_k = 1;
+        }
+        let modes = var("AFL_LLVM_INSTRUMENT")
+            .unwrap_or_default()
+            .to_uppercase();
+        for mode in modes
+            .split(|c| c == ',' || c == ':')
+            .filter(|m| !m.is_empty())
+        {
+            let (mode, size) = match mode.split_once('-') {
+                Some((mode, size)) if size.chars().all(|c| c.is_ascii_digit()) => {
+                    (mode, size.parse().ok())
+                }
+                _ => (mode, None),
+            };
+            match (mode, size) {
+                ("PCGUARD" | "PC-GUARD" | "SANCOV", None) => {
+                    instrumentation = Some(Instrumentation::PcGuard);
+                }
+                ("AFL" | "CLASSIC", None) => instrumentation = Some(Instrumentation::Afl),
+                ("NONE", None) => instrumentation = Some(Instrumentation::None),
+                ("CTX", None) => ctx = true,
+                ("CTX", Some(k)) => ctx_k = k,
+                ("CALLER", None) => ctx_k = 1,
+                ("NGRAM", Some(n)) => ngram = n,
+                _ => {
+                    return Err(Error::InvalidArguments(format!(
+                        "Unknown AFL_LLVM_INSTRUMENT: {}",
+                        modes
+                    )))
+                }
+            }
+        }
+
+        if ngram != 0 && !(2..=NGRAM_SIZE_MAX).contains(&ngram) {
+            return Err(Error::InvalidArguments(format!(
+                "The ngram size must be between 2 and {}, not {}",
+                NGRAM_SIZE_MAX, ngram
+            )));
+        }
+        if ctx_k > CTX_MAX_K {
+            return Err(Error::InvalidArguments(format!(
+                "The context size must be at most {}, not {}",
+                CTX_MAX_K, ctx_k
+            )));
+        }
+        if ctx && ctx_k > 0 {
+            return Err(Error::InvalidArguments(
+                "Full context sensitivity (CTX) and K-context sensitivity (CTX-K) are exclusive"
+                    .into(),
+            ));
+        }
+        let sensitive = ctx || ctx_k > 0 || ngram > 0;
+        let instrumentation = match instrumentation {
+            Some(Instrumentation::Afl) | None if sensitive => Instrumentation::Afl,
+            Some(_) if sensitive => {
+                return Err(Error::InvalidArguments(
+                    "Context sensitive and ngram coverage need AFL_LLVM_INSTRUMENT=CLASSIC".into(),
+                ))
+            }
+            Some(instrumentation) => instrumentation,
+            None => Instrumentation::PcGuard,
+        };
+
+        let inst_ratio = match var("AFL_INST_RATIO") {
+            Some(ratio) => match ratio.parse() {
+                Ok(ratio) if (1..=100).contains(&ratio) => Some(ratio),
+                _ => {
+                    return Err(Error::InvalidArguments(format!(
+                        "AFL_INST_RATIO must be between 1 and 100, not {}",
+                        ratio
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        let sanitizers = [
+            ("AFL_USE_ASAN", "address"),
+            ("AFL_USE_UBSAN", "undefined"),
+            ("AFL_USE_MSAN", "memory"),
+            ("AFL_USE_CFISAN", "cfi"),
+        ]
+        .iter()
+        .filter(|(name, _)| flag(name))
+        .map(|(_, sanitizer)| *sanitizer)
+        .collect();
+
+        Ok(Self {
+            instrumentation,
+            ctx,
+            ctx_k,
+            ngram,
+            cmplog: flag("AFL_LLVM_CMPLOG"),
+            autodict: flag("AFL_LLVM_AUTODICT") || var("AFL_LLVM_DICT2FILE").is_some(),
+            inst_ratio,
+            sanitizers,
+            allowlist: var("AFL_LLVM_ALLOWLIST").map(PathBuf::from),
+            denylist: var("AFL_LLVM_DENYLIST").map(PathBuf::from),
+            dont_optimize: flag("AFL_DONT_OPTIMIZE"),
+            quiet: flag("AFL_QUIET"),
+            runtime: var("LIBAFL_CC_RUNTIME").map(PathBuf::from),
+        })
+    }
+
+    /// The LLVM passes to load
+    #[must_use]
+    pub fn passes(&self) -> Vec<LLVMPasses> {
+        let mut passes = vec![];
+        if self.instrumentation == Instrumentation::Afl {
+            passes.push(LLVMPasses::AFLCoverage);
+        }
+        if self.cmplog {
+            passes.push(LLVMPasses::CmpLogRtn);
+        }
+        if self.autodict {
+            passes.push(LLVMPasses::AutoTokens);
+        }
+        passes
+    }
+
+    /// The arguments of the LLVM passes
+    #[must_use]
+    pub fn passes_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if self.instrumentation != Instrumentation::Afl {
+            return args;
+        }
+        if let Some(ratio) = self.inst_ratio {
+            args.push(format!("-inst_ratio={}", ratio));
+        }
+        if self.ctx {
+            args.push("-ctx".into());
+        }
+        if self.ctx_k > 0 {
+            args.push(format!("-ctx_k={}", self.ctx_k));
+        }
+        if self.ngram > 0 {
+            args.push(format!("-ngram={}", self.ngram));
+        }
+        args
+    }
+
+    /// The arguments for the compiler, when compiling and when linking
+    #[must_use]
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![];
+
+        let mut sancov = vec![];
+        if self.instrumentation == Instrumentation::PcGuard {
+            sancov.push("trace-pc-guard");
+        }
+        if self.cmplog {
+            sancov.push("trace-cmp");
+        }
+        if !sancov.is_empty() {
+            args.push(format!("-fsanitize-coverage={}", sancov.join(",")));
+            if let Some(allowlist) = &self.allowlist {
+                args.push(format!(
+                    "-fsanitize-coverage-allowlist={}",
+                    allowlist.display()
+                ));
+            }
+            if let Some(denylist) = &self.denylist {
+                args.push(format!(
+                    "-fsanitize-coverage-ignorelist={}",
+                    denylist.display()
+                ));
+            }
+        }
+
+        if !self.sanitizers.is_empty() {
+            args.push(format!("-fsanitize={}", self.sanitizers.join(",")));
+        }
+        if self.sanitizers.contains(&"cfi") {
+            // CFI needs LTO and hidden visibility
+            args.push("-flto".into());
+            args.push("-fvisibility=hidden".into());
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{EnvConfig, Instrumentation, LLVMPasses};
+
+    fn config(vars: &[(&str, &str)]) -> Result<EnvConfig, crate::Error> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect();
+        EnvConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_env_config() {
+        let default = config(&[]).unwrap();
+        assert_eq!(default, EnvConfig::default());
+        assert_eq!(default.args(), vec!["-fsanitize-coverage=trace-pc-guard"]);
+        assert!(default.passes().is_empty());
+
+        let afl = config(&[
+            ("AFL_LLVM_INSTRUMENT", "classic"),
+            ("AFL_LLVM_CMPLOG", "1"),
+            ("AFL_LLVM_DICT2FILE", "/tmp/dict"),
+            ("AFL_INST_RATIO", "50"),
+            ("AFL_USE_ASAN", "1"),
+            ("AFL_USE_MSAN", "0"),
+        ])
+        .unwrap();
+        assert_eq!(afl.instrumentation, Instrumentation::Afl);
+        assert_eq!(
+            afl.passes(),
+            vec![
+                LLVMPasses::AFLCoverage,
+                LLVMPasses::CmpLogRtn,
+                LLVMPasses::AutoTokens
+            ]
+        );
+        assert_eq!(afl.passes_args(), vec!["-inst_ratio=50"]);
+        assert_eq!(
+            afl.args(),
+            vec!["-fsanitize-coverage=trace-cmp", "-fsanitize=address"]
+        );
+
+        let ngram = config(&[("AFL_LLVM_INSTRUMENT", "NGRAM-4")]).unwrap();
+        assert_eq!(ngram.instrumentation, Instrumentation::Afl);
+        assert_eq!(ngram.passes_args(), vec!["-ngram=4"]);
+        let ctx = config(&[("AFL_LLVM_INSTRUMENT", "classic,ctx-2")]).unwrap();
+        assert_eq!(ctx.passes_args(), vec!["-ctx_k=2"]);
+        assert_eq!(ctx.args(), Vec::<String>::new());
+        assert!(config(&[("AFL_LLVM_INSTRUMENT", "CTX,CALLER")]).is_err());
+        assert!(config(&[("AFL_LLVM_INSTRUMENT", "PCGUARD"), ("AFL_LLVM_CTX", "1")]).is_err());
+        assert!(config(&[("AFL_LLVM_NGRAM_SIZE", "17")]).is_err());
+
+        assert!(config(&[("AFL_LLVM_INSTRUMENT", "magic")]).is_err());
+        assert!(config(&[("AFL_INST_RATIO", "0")]).is_err());
+    }
+}
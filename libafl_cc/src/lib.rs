@@ -63,6 +63,10 @@ pub mod cfg;
 pub use cfg::{CfgEdge, ControlFlowGraph, EntryBasicBlockInfo, HasWeight};
 pub mod clang;
 pub use clang::{ClangWrapper, LLVMPasses};
+pub mod env_config;
+pub use env_config::{EnvConfig, Instrumentation};
+pub mod msvc;
+pub use msvc::MsvcWrapper;
 
 /// `LibAFL` CC Error Type
 #[derive(Debug)]
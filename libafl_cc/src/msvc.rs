@@ -0,0 +1,329 @@
+//! MSVC compiler Wrapper from `LibAFL`, for Windows-native targets built with `cl.exe`
+
+use std::{convert::Into, path::Path, string::String, vec::Vec};
+
+use crate::{CompilerWrapper, Error, LIB_EXT, LIB_PREFIX};
+
+/// The libraries `LibAFL` needs on Windows, as printed by `rustc --print native-static-libs`
+const WINDOWS_LIBS: [&str; 6] = [
+    "ws2_32.lib",
+    "bcrypt.lib",
+    "advapi32.lib",
+    "kernel32.lib",
+    "userenv.lib",
+    "ntdll.lib",
+];
+
+/// Wrap `cl.exe`.
+///
+/// `cl.exe` knows no `trace-pc-guard`: instrument with [`MsvcWrapper::sancov_8bit_counters`]
+/// and observe the counters with the `sancov_8bit` feature of `libafl_targets`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug)]
+pub struct MsvcWrapper {
+    is_silent: bool,
+    optimize: bool,
+    wrapped_cl: String,
+
+    linking: bool,
+    need_libafl_arg: bool,
+    has_libafl_arg: bool,
+
+    parse_args_called: bool,
+    base_args: Vec<String>,
+    cc_args: Vec<String>,
+    link_args: Vec<String>,
+    /// The args after `/link`, for the linker
+    user_link_args: Vec<String>,
+}
+
+impl CompilerWrapper for MsvcWrapper {
+    fn parse_args<S>(&mut self, args: &[S]) -> Result<&'_ mut Self, Error>
+    where
+        S: AsRef<str>,
+    {
+        let mut new_args: Vec<String> = vec![];
+        if args.is_empty() {
+            return Err(Error::InvalidArguments(
+                "The number of arguments cannot be 0".to_string(),
+            ));
+        }
+
+        if self.parse_args_called {
+            return Err(Error::Unknown(
+                "CompilerWrapper::parse_args cannot be called twice on the same instance"
+                    .to_string(),
+            ));
+        }
+        self.parse_args_called = true;
+
+        if args.len() == 1 {
+            return Err(Error::InvalidArguments(
+                "LibAFL Compiler wrapper - no commands specified. Use me as compiler.".to_string(),
+            ));
+        }
+
+        let mut linking = true;
+        // `/LD` still links, but a DLL
+        let mut dll = false;
+        let mut suppress_linking = 0;
+        let mut user_link_args = vec![];
+        let mut args = args[1..].iter().map(AsRef::as_ref);
+        while let Some(arg) = args.next() {
+            // `cl.exe` takes its options with a `/` or a `-`
+            let option = arg
+                .strip_prefix('/')
+                .or_else(|| arg.strip_prefix('-'))
+                .unwrap_or_default();
+            match option {
+                "-libafl-no-link" | "fsanitize=fuzzer-no-link" => {
+                    suppress_linking += 1;
+                    self.has_libafl_arg = true;
+                    continue;
+                }
+                "-libafl" | "fsanitize=fuzzer" => {
+                    suppress_linking += 1337;
+                    self.has_libafl_arg = true;
+                    continue;
+                }
+                "c" | "E" | "EP" | "P" | "Zs" => linking = false,
+                "LD" | "LDd" => dll = true,
+                // Everything after `/link` goes to the linker
+                option if option.eq_ignore_ascii_case("link") => {
+                    user_link_args.extend(args.by_ref().map(str::to_string));
+                    break;
+                }
+                _ => (),
+            };
+            new_args.push(arg.to_string());
+        }
+        // The runtime has the fuzzer `main`, it goes into the executable loading the DLL
+        if linking
+            && dll
+            && (suppress_linking >= 1337 || (suppress_linking == 0 && !self.need_libafl_arg))
+        {
+            return Err(Error::InvalidArguments(
+                "Cannot link the LibAFL runtime into a DLL (/LD), build it with --libafl-no-link"
+                    .to_string(),
+            ));
+        }
+        if linking && suppress_linking > 0 && suppress_linking < 1337 {
+            linking = false;
+            new_args.push(
+                Path::new(env!("OUT_DIR"))
+                    .join(format!("{}no-link-rt.{}", LIB_PREFIX, LIB_EXT))
+                    .into_os_string()
+                    .into_string()
+                    .unwrap(),
+            );
+        }
+
+        self.linking = linking;
+
+        if self.optimize {
+            new_args.push("/Zi".into());
+            new_args.push("/O2".into());
+        }
+
+        // Fuzzing define common among tools
+        new_args.push("/DFUZZING_BUILD_MODE_UNSAFE_FOR_PRODUCTION=1".into());
+
+        self.base_args = new_args;
+        self.user_link_args = user_link_args;
+        Ok(self)
+    }
+
+    fn add_arg<S>(&mut self, arg: S) -> &'_ mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.base_args.push(arg.as_ref().to_string());
+        self
+    }
+
+    fn add_cc_arg<S>(&mut self, arg: S) -> &'_ mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.cc_args.push(arg.as_ref().to_string());
+        self
+    }
+
+    /// Add an argument for `link.exe`, passed after `/link`
+    fn add_link_arg<S>(&mut self, arg: S) -> &'_ mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.link_args.push(arg.as_ref().to_string());
+        self
+    }
+
+    fn link_staticlib<S>(&mut self, dir: &Path, name: S) -> &'_ mut Self
+    where
+        S: AsRef<str>,
+    {
+        let lib = dir.join(format!("{}{}.{}", LIB_PREFIX, name.as_ref(), LIB_EXT));
+        self.add_link_arg(format!("/WHOLEARCHIVE:{}", lib.display()))
+    }
+
+    fn command(&mut self) -> Result<Vec<String>, Error> {
+        let mut args = vec![self.wrapped_cl.clone()];
+        args.extend_from_slice(self.base_args.as_slice());
+        if self.need_libafl_arg && !self.has_libafl_arg {
+            if !self.user_link_args.is_empty() {
+                args.push("/link".into());
+                args.extend_from_slice(self.user_link_args.as_slice());
+            }
+            return Ok(args);
+        }
+
+        if self.linking {
+            args.push("/link".into());
+            args.extend_from_slice(self.user_link_args.as_slice());
+            args.extend_from_slice(self.link_args.as_slice());
+            args.extend(WINDOWS_LIBS.iter().map(|lib| (*lib).to_string()));
+        } else {
+            args.extend_from_slice(self.cc_args.as_slice());
+            if !self.user_link_args.is_empty() {
+                args.push("/link".into());
+                args.extend_from_slice(self.user_link_args.as_slice());
+            }
+        }
+
+        Ok(args)
+    }
+
+    fn is_linking(&self) -> bool {
+        self.linking
+    }
+
+    fn silence(&mut self, value: bool) -> &'_ mut Self {
+        self.is_silent = value;
+        self
+    }
+
+    fn is_silent(&self) -> bool {
+        self.is_silent
+    }
+}
+
+impl Default for MsvcWrapper {
+    /// Create a new MSVC Wrapper
+    #[must_use]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MsvcWrapper {
+    /// Create a new MSVC Wrapper
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            is_silent: false,
+            optimize: true,
+            wrapped_cl: "cl.exe".into(),
+            linking: false,
+            need_libafl_arg: false,
+            has_libafl_arg: false,
+            parse_args_called: false,
+            base_args: vec![],
+            cc_args: vec![],
+            link_args: vec![],
+            user_link_args: vec![],
+        }
+    }
+
+    /// Sets the wrapped `cl.exe`, if it is not in the `PATH`
+    pub fn wrapped_cl(&mut self, cl: String) -> &'_ mut Self {
+        self.wrapped_cl = cl;
+        self
+    }
+
+    /// Disable optimizations
+    pub fn dont_optimize(&mut self) -> &'_ mut Self {
+        self.optimize = false;
+        self
+    }
+
+    /// Instrument the edges with 8-bit counters (MSVC 16.9 or newer)
+    pub fn sancov_8bit_counters(&mut self) -> &'_ mut Self {
+        self.add_cc_arg("/fsanitize-coverage=inline-8bit-counters")
+            .add_cc_arg("/fsanitize-coverage=edge")
+    }
+
+    /// Instrument the comparisons and divisions, for `CmpLog` and value profile
+    pub fn sancov_trace_cmp(&mut self) -> &'_ mut Self {
+        self.add_cc_arg("/fsanitize-coverage=trace-cmp")
+            .add_cc_arg("/fsanitize-coverage=trace-div")
+    }
+
+    /// Build with `AddressSanitizer`
+    pub fn asan(&mut self) -> &'_ mut Self {
+        self.add_arg("/fsanitize=address")
+    }
+
+    /// Set if linking
+    pub fn linking(&mut self, value: bool) -> &'_ mut Self {
+        self.linking = value;
+        self
+    }
+
+    /// Set if it needs the --libafl arg to add the custom arguments to `cl.exe`
+    pub fn need_libafl_arg(&mut self, value: bool) -> &'_ mut Self {
+        self.need_libafl_arg = value;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CompilerWrapper, MsvcWrapper};
+
+    #[test]
+    fn test_msvc_link_args() {
+        let args = MsvcWrapper::new()
+            .parse_args(&["libafl_cl", "/Fefuzzer.exe", "harness.c", "/link", "/DEBUG"])
+            .unwrap()
+            .add_link_arg("/WHOLEARCHIVE:libfuzzer.lib")
+            .command()
+            .unwrap();
+        let link = args.iter().position(|arg| arg == "/link").unwrap();
+        assert_eq!(&args[..3], ["cl.exe", "/Fefuzzer.exe", "harness.c"]);
+        assert_eq!(args[link + 1], "/DEBUG");
+        assert_eq!(args[link + 2], "/WHOLEARCHIVE:libfuzzer.lib");
+        assert!(args.contains(&"ws2_32.lib".to_string()));
+
+        let args = MsvcWrapper::new()
+            .parse_args(&["libafl_cl", "-c", "harness.c"])
+            .unwrap()
+            .sancov_8bit_counters()
+            .command()
+            .unwrap();
+        assert!(!args.contains(&"/link".to_string()));
+        assert!(args.contains(&"/fsanitize-coverage=inline-8bit-counters".to_string()));
+    }
+
+    #[test]
+    fn test_msvc_dll() {
+        assert!(MsvcWrapper::new()
+            .parse_args(&["libafl_cl", "/LD", "harness.c"])
+            .is_err());
+        assert!(MsvcWrapper::new()
+            .parse_args(&["libafl_cl", "/LD", "--libafl", "harness.c"])
+            .is_err());
+
+        let mut cl = MsvcWrapper::new();
+        cl.parse_args(&["libafl_cl", "/LD", "--libafl-no-link", "target.c"])
+            .unwrap();
+        assert!(!cl.is_linking());
+        let args = cl.command().unwrap();
+        assert!(args.contains(&"/LD".to_string()));
+        assert!(args.iter().any(|arg| arg.contains("no-link-rt")));
+
+        // Compiling only, nothing is linked
+        assert!(MsvcWrapper::new()
+            .parse_args(&["libafl_cl", "/LD", "/c", "harness.c"])
+            .is_ok());
+    }
+}
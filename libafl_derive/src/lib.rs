@@ -59,13 +59,58 @@
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, DeriveInput, Lit, Meta, NestedMeta};
 
-/// Derive macro to implement `SerdeAny`, to use a type in a `SerdeAnyMap`
-#[proc_macro_derive(SerdeAny)]
+/// Derive macro to implement `SerdeAny`, to use a type in a `SerdeAnyMap`.
+///
+/// The type is registered as its path. Types saved in state files should be given a name that
+/// stays the same when they move, with `#[serdeany(name = "...")]`.
+#[proc_macro_derive(SerdeAny, attributes(serdeany))]
 pub fn libafl_serdeany_derive(input: TokenStream) -> TokenStream {
-    let name = parse_macro_input!(input as DeriveInput).ident;
-    TokenStream::from(quote! {
-        libafl::impl_serdeany!(#name);
-    })
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = input.ident;
+    match registered_name(&input.attrs) {
+        Ok(Some(registered_name)) => TokenStream::from(quote! {
+            libafl::impl_serdeany!(#name, #registered_name);
+        }),
+        Ok(None) => TokenStream::from(quote! {
+            libafl::impl_serdeany!(#name);
+        }),
+        Err(err) => TokenStream::from(err.to_compile_error()),
+    }
+}
+
+/// The name given with `#[serdeany(name = "...")]`, if any
+fn registered_name(attrs: &[syn::Attribute]) -> syn::Result<Option<syn::LitStr>> {
+    let mut registered_name = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serdeany")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected serdeany(name = \"...\")",
+                ))
+            }
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(name_value))
+                    if name_value.path.is_ident("name") =>
+                {
+                    match name_value.lit {
+                        Lit::Str(lit) => registered_name = Some(lit),
+                        lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                    }
+                }
+                nested => {
+                    return Err(syn::Error::new_spanned(
+                        nested,
+                        "unknown serdeany attribute",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(registered_name)
 }
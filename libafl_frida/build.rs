@@ -1,9 +1,16 @@
 // build.rs
 
+use std::env;
+
 fn main() {
-    cc::Build::new().file("src/gettls.c").compile("libgettls.a");
+    // The build script runs on the host, look at the target
+    let target_family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+
+    // Only the ASan runtime, unix-only, needs the tls pointer
+    if target_family == "unix" {
+        cc::Build::new().file("src/gettls.c").compile("libgettls.a");
 
-    // Force linking against libc++
-    #[cfg(unix)]
-    println!("cargo:rustc-link-lib=dylib=c++");
+        // Force linking against libc++
+        println!("cargo:rustc-link-lib=dylib=c++");
+    }
 }
@@ -222,6 +222,13 @@ impl Allocator {
         self.shadow_bit as u32
     }
 
+    /// The page size of the system
+    #[inline]
+    #[must_use]
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
     #[inline]
     #[must_use]
     fn round_up_to_page(&self, size: usize) -> usize {
@@ -567,6 +567,31 @@ impl AsanRuntime {
         );
         #[cfg(not(target_vendor = "apple"))]
         hook_func!(None, malloc_usable_size, (ptr: *mut c_void), usize);
+        // Not every libc version has these
+        if Module::find_export_by_name(None, "aligned_alloc").is_some() {
+            hook_func!(
+                None,
+                aligned_alloc,
+                (alignment: usize, size: usize),
+                *mut c_void
+            );
+        }
+        if Module::find_export_by_name(None, "reallocarray").is_some() {
+            hook_func!(
+                None,
+                reallocarray,
+                (ptr: *mut c_void, nmemb: usize, size: usize),
+                *mut c_void
+            );
+        }
+        #[cfg(not(target_os = "android"))]
+        if Module::find_export_by_name(None, "valloc").is_some() {
+            hook_func!(None, valloc, (size: usize), *mut c_void);
+        }
+        #[cfg(target_os = "linux")]
+        if Module::find_export_by_name(None, "pvalloc").is_some() {
+            hook_func!(None, pvalloc, (size: usize), *mut c_void);
+        }
 
         for libname in ["libc++.so", "libc++.so.1", "libc++_shared.so"] {
             for export in Module::enumerate_exports(libname) {
@@ -847,6 +872,7 @@ impl AsanRuntime {
             *mut c_char
         );
         hook_func!(None, strdup, (s: *const c_char), *mut c_char);
+        hook_func!(None, strndup, (s: *const c_char, n: usize), *mut c_char);
         hook_func!(None, strlen, (s: *const c_char), usize);
         hook_func!(None, strnlen, (s: *const c_char, n: usize), usize);
         hook_func!(
@@ -108,9 +108,13 @@ impl AsanRuntime {
 
     #[inline]
     pub fn hook_calloc(&mut self, nmemb: usize, size: usize) -> *mut c_void {
-        let ret = unsafe { self.allocator_mut().alloc(size * nmemb, 8) };
+        let size = match nmemb.checked_mul(size) {
+            Some(size) => size,
+            None => return std::ptr::null_mut(),
+        };
+        let ret = unsafe { self.allocator_mut().alloc(size, 8) };
         unsafe {
-            memset(ret, 0, size * nmemb);
+            memset(ret, 0, size);
         }
         ret
     }
@@ -130,6 +134,19 @@ impl AsanRuntime {
         }
     }
 
+    #[inline]
+    pub fn hook_reallocarray(
+        &mut self,
+        ptr: *mut c_void,
+        nmemb: usize,
+        size: usize,
+    ) -> *mut c_void {
+        match nmemb.checked_mul(size) {
+            Some(size) => self.hook_realloc(ptr, size),
+            None => std::ptr::null_mut(),
+        }
+    }
+
     #[inline]
     pub fn hook_check_free(&mut self, ptr: *mut c_void) -> bool {
         self.allocator_mut().is_managed(ptr)
@@ -162,6 +179,32 @@ impl AsanRuntime {
         0
     }
 
+    #[inline]
+    pub fn hook_aligned_alloc(&mut self, alignment: usize, size: usize) -> *mut c_void {
+        unsafe { self.allocator_mut().alloc(size, alignment) }
+    }
+
+    /// Our allocations start on a page boundary anyway
+    #[cfg(not(target_os = "android"))]
+    #[inline]
+    pub fn hook_valloc(&mut self, size: usize) -> *mut c_void {
+        unsafe { self.allocator_mut().alloc(size, 8) }
+    }
+
+    /// Like `valloc`, with the size rounded up to whole pages, so `pvalloc(0)` gets one page
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn hook_pvalloc(&mut self, size: usize) -> *mut c_void {
+        let page_size = self.allocator().page_size();
+        match size.max(1).checked_add(page_size - 1) {
+            Some(size) => unsafe {
+                self.allocator_mut()
+                    .alloc(size / page_size * page_size, page_size)
+            },
+            None => std::ptr::null_mut(),
+        }
+    }
+
     #[inline]
     #[cfg(all(not(target_vendor = "apple")))]
     pub fn hook_malloc_usable_size(&mut self, ptr: *mut c_void) -> usize {
@@ -912,12 +955,36 @@ impl AsanRuntime {
         }
 
         unsafe {
-            let ret = self.allocator_mut().alloc(size, 8) as *mut c_char;
+            let ret = self.allocator_mut().alloc(size + 1, 8) as *mut c_char;
             strcpy(ret, s);
             ret
         }
     }
 
+    #[inline]
+    pub fn hook_strndup(&mut self, s: *const c_char, n: usize) -> *mut c_char {
+        extern "C" {
+            fn strnlen(s: *const c_char, n: usize) -> usize;
+        }
+        let size = unsafe { strnlen(s, n) };
+        if !(self.shadow_check_func().unwrap())(s as *const c_void, size) {
+            AsanErrors::get_mut().report_error(AsanError::BadFuncArgRead((
+                "strndup".to_string(),
+                self.real_address_for_stalked(AsanRuntime::pc()),
+                s as usize,
+                size,
+                Backtrace::new(),
+            )));
+        }
+
+        unsafe {
+            let ret = self.allocator_mut().alloc(size + 1, 8) as *mut c_char;
+            (s as *const u8).copy_to(ret as *mut u8, size);
+            *ret.add(size) = 0;
+            ret
+        }
+    }
+
     #[inline]
     pub fn hook_strlen(&mut self, s: *const c_char) -> usize {
         extern "C" {
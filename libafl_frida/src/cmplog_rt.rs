@@ -4,12 +4,14 @@
 //! related to the input.
 //! Read the [`RedQueen`](https://www.ndss-symposium.org/ndss-paper/redqueen-fuzzing-with-input-to-state-correspondence/) paper for the general concepts.
 use dynasmrt::{dynasm, DynasmApi, DynasmLabelApi};
+use frida_gum::{interceptor::Interceptor, Gum, Module, NativePointer};
 use libafl::{
     inputs::{HasTargetBytes, Input},
     Error,
 };
 use libafl_targets;
 use libafl_targets::CMPLOG_MAP_W;
+use libc::c_char;
 use rangemap::RangeMap;
 use std::ffi::c_void;
 
@@ -17,6 +19,9 @@ use crate::helper::FridaRuntime;
 extern "C" {
     /// Tracks cmplog instructions
     pub fn __libafl_targets_cmplog_instructions(k: u64, shape: u8, arg1: u64, arg2: u64);
+
+    /// Tracks cmplog routines
+    pub fn __libafl_targets_cmplog_routines(k: usize, ptr1: *const u8, ptr2: *const u8);
 }
 
 #[cfg(target_arch = "aarch64")]
@@ -72,11 +77,12 @@ impl FridaRuntime for CmpLogRuntime {
     /// This will generate the instrumentation blobs for the current arch.
     fn init(
         &mut self,
-        _gum: &frida_gum::Gum,
+        gum: &Gum,
         _ranges: &RangeMap<usize, (u16, String)>,
         _modules_to_instrument: &[&str],
     ) {
         self.generate_instrumentation_blobs();
+        self.hook_routines(gum);
     }
 
     fn pre_exec<I: Input + HasTargetBytes>(&mut self, _input: &I) -> Result<(), Error> {
@@ -115,6 +121,52 @@ impl CmpLogRuntime {
         }
     }
 
+    /// Log the operands of a call to a comparison routine, such as `memcmp`, at `retaddr`
+    fn log_routine(retaddr: usize, ptr1: *const c_void, ptr2: *const c_void) {
+        let mut k = (retaddr >> 4) ^ (retaddr << 8);
+
+        k &= CMPLOG_MAP_W - 1;
+
+        unsafe {
+            __libafl_targets_cmplog_routines(k, ptr1 as *const u8, ptr2 as *const u8);
+        }
+    }
+
+    /// Replace the `memcmp`-family functions, to log their arguments.
+    /// Functions another runtime, such as `ASan`, replaced already are not logged.
+    fn hook_routines(&mut self, gum: &Gum) {
+        let mut interceptor = Interceptor::obtain(gum);
+
+        macro_rules! hook_cmp_func {
+            ($name:ident, ($s1:ident : $s1_type:ty, $s2:ident : $s2_type:ty $(, $param:ident : $param_type:ty)*)) => {
+                paste::paste! {
+                    extern "C" {
+                        fn $name($s1: $s1_type, $s2: $s2_type $(, $param: $param_type)*) -> i32;
+                    }
+                    unsafe extern "C" fn [<replacement_ $name>]($s1: $s1_type, $s2: $s2_type $(, $param: $param_type)*) -> i32 {
+                        let invocation = Interceptor::current_invocation();
+                        CmpLogRuntime::log_routine(invocation.return_addr(), $s1 as *const c_void, $s2 as *const c_void);
+                        $name($s1, $s2 $(, $param)*)
+                    }
+                    if let Some(address) = Module::find_export_by_name(None, stringify!($name)) {
+                        interceptor.replace(
+                            address,
+                            NativePointer([<replacement_ $name>] as *mut c_void),
+                            NativePointer(self as *mut _ as *mut c_void)
+                        ).ok();
+                    }
+                }
+            }
+        }
+
+        hook_cmp_func!(memcmp, (s1: *const c_void, s2: *const c_void, n: usize));
+        hook_cmp_func!(bcmp, (s1: *const c_void, s2: *const c_void, n: usize));
+        hook_cmp_func!(strcmp, (s1: *const c_char, s2: *const c_char));
+        hook_cmp_func!(strncmp, (s1: *const c_char, s2: *const c_char, n: usize));
+        hook_cmp_func!(strcasecmp, (s1: *const c_char, s2: *const c_char));
+        hook_cmp_func!(strncasecmp, (s1: *const c_char, s2: *const c_char, n: usize));
+    }
+
     /// Generate the instrumentation blobs for the current arch.
     #[allow(clippy::similar_names)]
     fn generate_instrumentation_blobs(&mut self) {
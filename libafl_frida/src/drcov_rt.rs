@@ -10,6 +10,7 @@ use libafl_targets::drcov::{DrCovBasicBlock, DrCovWriter};
 use rangemap::RangeMap;
 use std::collections::HashMap;
 use std::hash::Hasher;
+use std::path::PathBuf;
 
 /// Generates `DrCov` traces
 #[derive(Debug, Clone)]
@@ -19,6 +20,7 @@ pub struct DrCovRuntime {
     /// The memory ragnes of this target
     ranges: RangeMap<usize, (u16, String)>,
     stalked_addresses: HashMap<usize, usize>,
+    coverage_directory: PathBuf,
 }
 
 impl FridaRuntime for DrCovRuntime {
@@ -30,7 +32,7 @@ impl FridaRuntime for DrCovRuntime {
         _modules_to_instrument: &[&str],
     ) {
         self.ranges = ranges.clone();
-        std::fs::create_dir_all("./coverage")
+        std::fs::create_dir_all(&self.coverage_directory)
             .expect("failed to create directory for coverage files");
     }
 
@@ -40,12 +42,14 @@ impl FridaRuntime for DrCovRuntime {
     }
 
     /// Called after execution, writes the trace to a unique `DrCov` file for this trace
-    /// into `./coverage/<trace_hash>.drcov`
+    /// into `<coverage_directory>/<trace_hash>.drcov`, by default `./coverage`
     fn post_exec<I: Input + HasTargetBytes>(&mut self, input: &I) -> Result<(), Error> {
         let mut hasher = AHasher::new_with_keys(0, 0);
         hasher.write(input.target_bytes().as_slice());
 
-        let filename = format!("./coverage/{:016x}.drcov", hasher.finish(),);
+        let filename = self
+            .coverage_directory
+            .join(format!("{:016x}.drcov", hasher.finish()));
         DrCovWriter::new(&self.ranges).write(&filename, &self.drcov_basic_blocks)?;
         self.drcov_basic_blocks.clear();
 
@@ -57,10 +61,17 @@ impl DrCovRuntime {
     /// Creates a new [`DrCovRuntime`]
     #[must_use]
     pub fn new() -> Self {
+        Self::with_path(PathBuf::from("./coverage"))
+    }
+
+    /// Creates a new [`DrCovRuntime`] that writes its traces to `coverage_directory`
+    #[must_use]
+    pub fn with_path(coverage_directory: PathBuf) -> Self {
         Self {
             drcov_basic_blocks: vec![],
             ranges: RangeMap::new(),
             stalked_addresses: HashMap::new(),
+            coverage_directory,
         }
     }
 
@@ -1,6 +1,9 @@
 use crate::helper::{FridaInstrumentationHelper, FridaRuntimeTuple};
 
-use core::fmt::{self, Debug, Formatter};
+use core::{
+    fmt::{self, Debug, Formatter},
+    ops::Range,
+};
 use frida_gum::{
     stalker::{NoneEventSink, Stalker},
     Gum, MemoryRange, NativePointer,
@@ -28,8 +31,13 @@ where
     OT: ObserversTuple<I, S>,
 {
     base: InProcessExecutor<'a, H, I, OT, S>,
+    gum: &'a Gum,
     /// Frida's dynamic rewriting engine
     stalker: Stalker<'a>,
+    /// The ranges stalker does not instrument
+    excluded: Vec<Range<usize>>,
+    /// The trust threshold set with [`FridaInProcessExecutor::with_trust_threshold`]
+    trust_threshold: Option<i32>,
     /// User provided callback for instrumentation
     helper: &'c mut FridaInstrumentationHelper<'b, RT>,
     followed: bool,
@@ -125,7 +133,6 @@ where
         base: InProcessExecutor<'a, H, I, OT, S>,
         helper: &'c mut FridaInstrumentationHelper<'b, RT>,
     ) -> Self {
-        let mut stalker = Stalker::new(gum);
         // Include the current module (the fuzzer) in stalked ranges. We clone the ranges so that
         // we don't add it to the INSTRUMENTED ranges.
         let mut ranges = helper.ranges().clone();
@@ -140,22 +147,70 @@ where
                 break;
             }
         }
-        for range in ranges.gaps(&(0..usize::MAX)) {
+        let excluded: Vec<Range<usize>> = ranges.gaps(&(0..usize::MAX)).collect();
+        for range in &excluded {
             println!("excluding range: {:x}-{:x}", range.start, range.end);
-            stalker.exclude(&MemoryRange::new(
-                NativePointer(range.start as *mut c_void),
-                range.end - range.start,
-            ));
         }
 
         Self {
             base,
-            stalker,
+            gum,
+            stalker: Self::new_stalker(gum, &excluded, None),
+            excluded,
+            trust_threshold: None,
             helper,
             followed: false,
             _phantom: PhantomData,
         }
     }
+
+    /// A [`Stalker`] leaving out the `excluded` ranges
+    fn new_stalker(
+        gum: &'a Gum,
+        excluded: &[Range<usize>],
+        trust_threshold: Option<i32>,
+    ) -> Stalker<'a> {
+        let mut stalker = Stalker::new(gum);
+        for range in excluded {
+            stalker.exclude(&MemoryRange::new(
+                NativePointer(range.start as *mut c_void),
+                range.end - range.start,
+            ));
+        }
+        if let Some(threshold) = trust_threshold {
+            stalker.set_trust_threshold(threshold);
+        }
+        stalker
+    }
+
+    /// Sets how many times a block has to run unmodified before its translation is reused as-is.
+    /// Frida's default is `1`. `0` trusts the code from the start, and saves re-checking the blocks of large binaries,
+    /// `-1` never does, for self-modifying or JITted targets.
+    #[must_use]
+    pub fn with_trust_threshold(mut self, threshold: i32) -> Self {
+        self.stalker.set_trust_threshold(threshold);
+        self.trust_threshold = Some(threshold);
+        self
+    }
+
+    /// Drops the translations of the blocks in `range`, so they are instrumented again on their next execution.
+    /// Call it after the module at `range` was unloaded, or its code changed.
+    /// `frida_gum` cannot invalidate single blocks, so this drops all translations if any block is in `range`.
+    pub fn invalidate_range(&mut self, range: Range<usize>) {
+        if !self.helper.take_translated_blocks(range).is_empty() {
+            self.flush_translations();
+        }
+    }
+
+    /// Drops the translations of all blocks, so they are instrumented again on their next execution
+    pub fn flush_translations(&mut self) {
+        if self.followed {
+            self.stalker.unfollow_me();
+            self.followed = false;
+        }
+        self.helper.take_translated_blocks(0..usize::MAX);
+        self.stalker = Self::new_stalker(self.gum, &self.excluded, self.trust_threshold);
+    }
 }
 
 #[cfg(windows)]
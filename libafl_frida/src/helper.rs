@@ -4,14 +4,13 @@ use libafl::{
     Error,
 };
 
-#[cfg(unix)]
 use libafl_targets::drcov::DrCovBasicBlock;
 
+#[cfg(unix)]
+use crate::asan::asan_rt::AsanRuntime;
 #[cfg(all(feature = "cmplog", target_arch = "aarch64"))]
 use crate::cmplog_rt::CmpLogRuntime;
-use crate::coverage_rt::CoverageRuntime;
-#[cfg(unix)]
-use crate::{asan::asan_rt::AsanRuntime, drcov_rt::DrCovRuntime};
+use crate::{coverage_rt::CoverageRuntime, drcov_rt::DrCovRuntime};
 #[cfg(target_arch = "aarch64")]
 use capstone::{
     arch::{self, BuildsCapstone},
@@ -22,9 +21,12 @@ use capstone::{
     arch::{self, BuildsCapstone},
     Capstone,
 };
-use core::fmt::{self, Debug, Formatter};
-#[cfg(unix)]
+use core::{
+    fmt::{self, Debug, Formatter},
+    ops::Range,
+};
 use frida_gum::CpuContext;
+use std::{collections::BTreeSet, path::Path};
 
 #[cfg(unix)]
 use frida_gum::instruction_writer::InstructionWriter;
@@ -124,6 +126,8 @@ pub struct FridaInstrumentationHelper<'a, RT> {
     module_map: ModuleMap,
     options: &'a FuzzerOptions,
     runtimes: RT,
+    /// The start addresses of the blocks Stalker translated
+    translated_blocks: BTreeSet<usize>,
 }
 
 impl<RT> Debug for FridaInstrumentationHelper<'_, RT> {
@@ -155,7 +159,7 @@ fn pc(context: &CpuContext) -> usize {
     context.pc() as usize
 }
 
-#[cfg(all(target_arch = "x86_64", unix))]
+#[cfg(target_arch = "x86_64")]
 fn pc(context: &CpuContext) -> usize {
     context.rip() as usize
 }
@@ -201,6 +205,15 @@ where
             .to_string_lossy()
             .to_string()];
         modules_to_instrument.append(&mut options.libs_to_instrument.clone());
+        modules_to_instrument.retain(|module| {
+            let name = Path::new(module)
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+            !options
+                .dont_instrument_libs
+                .iter()
+                .any(|lib| *lib == name || lib == module)
+        });
         let modules_to_instrument: Vec<&str> =
             modules_to_instrument.iter().map(AsRef::as_ref).collect();
 
@@ -224,6 +237,7 @@ where
             module_map: ModuleMap::new_from_names(&modules_to_instrument),
             options,
             runtimes,
+            translated_blocks: BTreeSet::new(),
         };
 
         if options.cmplog || options.asan || !options.disable_coverage {
@@ -248,11 +262,15 @@ where
 
             let transformer = Transformer::from_callback(gum, |basic_block, output| {
                 let mut first = true;
+                let mut block_start = true;
                 for instruction in basic_block {
                     let instr = instruction.instr();
-                    #[cfg(unix)]
                     let instr_size = instr.bytes().len();
                     let address = instr.address();
+                    if block_start {
+                        block_start = false;
+                        helper.translated_blocks.insert(address as usize);
+                    }
                     //println!("block @ {:x} transformed to {:x}", address, output.writer().pc());
 
                     //println!(
@@ -270,7 +288,6 @@ where
                                 rt.emit_coverage_mapping(address, &output);
                             }
 
-                            #[cfg(unix)]
                             if let Some(rt) = helper.runtime_mut::<DrCovRuntime>() {
                                 instruction.put_callout(|context| {
                                     let real_address = rt.real_address_for_stalked(pc(&context));
@@ -342,7 +359,6 @@ where
                             );
                         }
 
-                        #[cfg(unix)]
                         if let Some(rt) = helper.runtime_mut::<DrCovRuntime>() {
                             rt.add_stalked_address(
                                 output.writer().pc() as usize - instr_size,
@@ -402,6 +418,21 @@ where
         self.runtimes.post_exec_all(input)
     }
 
+    /// The start addresses of the blocks Stalker translated so far
+    #[must_use]
+    pub fn translated_blocks(&self) -> &BTreeSet<usize> {
+        &self.translated_blocks
+    }
+
+    /// Forgets the translated blocks in `range`, and returns them
+    pub(crate) fn take_translated_blocks(&mut self, range: Range<usize>) -> Vec<usize> {
+        let blocks: Vec<usize> = self.translated_blocks.range(range).copied().collect();
+        for block in &blocks {
+            self.translated_blocks.remove(block);
+        }
+        blocks
+    }
+
     /// If stalker is enabled
     pub fn stalker_enabled(&self) -> bool {
         self.options.cmplog || self.options.asan || !self.options.disable_coverage
@@ -0,0 +1,190 @@
+//! Replace or wrap functions of the target with Rust closures, e.g. to stub out network calls or time.
+//!
+//! Replacements see the first [`HOOK_ARGS`] integer or pointer arguments of a call,
+//! and return an integer or a pointer, which covers most of libc and the usual C APIs.
+use core::fmt::{self, Debug, Formatter};
+use std::ffi::c_void;
+
+use frida_gum::{interceptor::Interceptor, Gum, Module, NativePointer};
+use libafl::{
+    inputs::{HasTargetBytes, Input},
+    Error,
+};
+use rangemap::RangeMap;
+
+use crate::helper::FridaRuntime;
+
+/// The number of arguments a replacement gets
+pub const HOOK_ARGS: usize = 6;
+
+/// The arguments of a hooked call
+pub type HookArgs = [usize; HOOK_ARGS];
+
+/// The function to hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookTarget {
+    /// An exported symbol, of the given module or of any module
+    Symbol {
+        /// The module exporting the symbol, `None` looks in all modules
+        module: Option<String>,
+        /// The name of the symbol
+        name: String,
+    },
+    /// An address, e.g. of a function that is not exported
+    Address(usize),
+}
+
+impl HookTarget {
+    /// The exported symbol `name`, of any module
+    #[must_use]
+    pub fn symbol(name: &str) -> Self {
+        Self::Symbol {
+            module: None,
+            name: name.to_string(),
+        }
+    }
+
+    /// The address of this function, if it exists
+    fn resolve(&self) -> Option<NativePointer> {
+        match self {
+            Self::Symbol { module, name } => Module::find_export_by_name(module.as_deref(), name),
+            Self::Address(address) => Some(NativePointer(*address as *mut c_void)),
+        }
+    }
+}
+
+/// The original implementation of a hooked function, for replacements that wrap it
+#[derive(Debug, Clone, Copy)]
+pub struct OriginalFn(NativePointer);
+
+impl OriginalFn {
+    /// Calls the original implementation with `args`
+    ///
+    /// # Safety
+    /// The args have to be valid for the original function.
+    #[must_use]
+    pub unsafe fn call(&self, args: &HookArgs) -> usize {
+        let original: extern "C" fn(usize, usize, usize, usize, usize, usize) -> usize =
+            core::mem::transmute(self.0 .0);
+        original(args[0], args[1], args[2], args[3], args[4], args[5])
+    }
+}
+
+/// A replacement, called with the args of the call and the original implementation
+type Replacement = Box<dyn FnMut(&HookArgs, OriginalFn) -> usize>;
+
+/// An installed hook, the `replacement_data` of its [`Interceptor`] replacement
+struct Hook {
+    replacement: Replacement,
+    original: OriginalFn,
+}
+
+/// Every replaced function jumps here, and on to its [`Hook`].
+/// Reading more args than the function has is harmless: they are registers or caller stack.
+unsafe extern "C" fn replacement_trampoline(
+    arg0: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+    arg5: usize,
+) -> usize {
+    let mut invocation = Interceptor::current_invocation();
+    let hook = &mut *(invocation.replacement_data().unwrap().0 as *mut Hook);
+    (hook.replacement)(&[arg0, arg1, arg2, arg3, arg4, arg5], hook.original)
+}
+
+/// Replaces the functions of the target with Rust closures, when the [`crate::helper::FridaInstrumentationHelper`] is created
+pub struct HookRuntime {
+    /// The hooks to install on init
+    pending: Vec<(HookTarget, Replacement)>,
+    /// The installed hooks, boxed as the [`Interceptor`] points to them
+    hooks: Vec<Box<Hook>>,
+}
+
+impl Debug for HookRuntime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookRuntime")
+            .field(
+                "pending",
+                &self
+                    .pending
+                    .iter()
+                    .map(|(target, _)| target)
+                    .collect::<Vec<_>>(),
+            )
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+impl FridaRuntime for HookRuntime {
+    /// Installs the hooks
+    fn init(
+        &mut self,
+        gum: &Gum,
+        _ranges: &RangeMap<usize, (u16, String)>,
+        _modules_to_instrument: &[&str],
+    ) {
+        let mut interceptor = Interceptor::obtain(gum);
+        for (target, replacement) in self.pending.drain(..) {
+            let function = target
+                .resolve()
+                .unwrap_or_else(|| panic!("Failed to find function {:?}", target));
+            let mut hook = Box::new(Hook {
+                replacement,
+                original: OriginalFn(function),
+            });
+            let original = interceptor
+                .replace(
+                    function,
+                    NativePointer(replacement_trampoline as *mut c_void),
+                    NativePointer(hook.as_mut() as *mut Hook as *mut c_void),
+                )
+                .unwrap_or_else(|_| panic!("Failed to replace function {:?}", target));
+            hook.original = OriginalFn(original);
+            self.hooks.push(hook);
+        }
+    }
+
+    fn pre_exec<I: Input + HasTargetBytes>(&mut self, _input: &I) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_exec<I: Input + HasTargetBytes>(&mut self, _input: &I) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl HookRuntime {
+    /// Creates a new [`HookRuntime`], without hooks
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pending: vec![],
+            hooks: vec![],
+        }
+    }
+
+    /// Replaces `target` with `replacement`, which may call the original to wrap it
+    #[must_use]
+    pub fn with_replacement<F>(mut self, target: HookTarget, replacement: F) -> Self
+    where
+        F: FnMut(&HookArgs, OriginalFn) -> usize + 'static,
+    {
+        self.pending.push((target, Box::new(replacement)));
+        self
+    }
+
+    /// Replaces `target` with a stub returning `value`, e.g. `0` for `sleep`
+    #[must_use]
+    pub fn with_stub(self, target: HookTarget, value: usize) -> Self {
+        self.with_replacement(target, move |_, _| value)
+    }
+}
+
+impl Default for HookRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
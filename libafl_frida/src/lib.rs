@@ -78,6 +78,8 @@ pub mod helper;
 
 pub mod drcov_rt;
 
+pub mod hook_rt;
+
 /// The frida executor
 pub mod executor;
 
@@ -0,0 +1,194 @@
+//! The feedbacks giving `libFuzzer` semantics to a `LibAFL` run: harnesses rejecting inputs, and artifact names.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::Hasher,
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    bolts::{tuples::Named, AsSlice},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    state::HasClientPerfMonitor,
+    Error,
+};
+
+/// Set if the harness rejected the last input by returning `-1`
+static REJECTED: AtomicBool = AtomicBool::new(false);
+
+/// Records the return value of the harness for the [`RejectFeedback`].
+/// `-1` rejects the input, the other values are reserved by `libFuzzer` and treated like `0`.
+pub fn record_return_value(ret: i32) {
+    REJECTED.store(ret == -1, Ordering::Relaxed);
+}
+
+/// Not interesting if the harness rejected the input, returning `-1`.
+/// Put it first in a `feedback_and_fast`, so rejected inputs do not reach the coverage feedbacks.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RejectFeedback {}
+
+impl<I, S> Feedback<I, S> for RejectFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        Ok(!REJECTED.load(Ordering::Relaxed))
+    }
+}
+
+impl Named for RejectFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "RejectFeedback"
+    }
+}
+
+impl RejectFeedback {
+    /// Creates a new [`RejectFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// The path of an artifact, `<prefix><kind>-<hash>` like `libFuzzer`, e.g. `out/crash-0123456789abcdef`
+#[must_use]
+pub fn artifact_path(prefix: &str, kind: &str, bytes: &[u8]) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(bytes);
+    PathBuf::from(format!("{}{}-{:016x}", prefix, kind, hasher.finish()))
+}
+
+/// The directory the artifacts with the given prefix are written to
+#[must_use]
+pub fn artifact_dir(prefix: &str) -> PathBuf {
+    // `crashes/` has no file part, `out/crash-` is in the `out` dir
+    if prefix.ends_with('/') {
+        return PathBuf::from(prefix);
+    }
+    match Path::new(prefix).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// The objective of a `libFuzzer` run: crashes, timeouts and out of memory errors,
+/// named `<prefix><kind>-<hash>` like the artifacts of `libFuzzer`.
+#[derive(Debug, Clone)]
+pub struct ArtifactFeedback {
+    prefix: String,
+    /// The kind of the last objective, `crash`, `timeout` or `oom`
+    kind: Option<&'static str>,
+}
+
+impl<I, S> Feedback<I, S> for ArtifactFeedback
+where
+    I: Input + HasTargetBytes,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.kind = match exit_kind {
+            ExitKind::Crash => Some("crash"),
+            ExitKind::Timeout => Some("timeout"),
+            ExitKind::Oom => Some("oom"),
+            _ => None,
+        };
+        Ok(self.kind.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        let kind = match self.kind.take() {
+            Some(kind) => kind,
+            None => return Ok(()),
+        };
+        let path = match testcase.input() {
+            Some(input) => artifact_path(&self.prefix, kind, input.target_bytes().as_slice()),
+            None => return Ok(()),
+        };
+        testcase.set_filename(path.to_string_lossy().into_owned());
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.kind = None;
+        Ok(())
+    }
+}
+
+impl Named for ArtifactFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "ArtifactFeedback"
+    }
+}
+
+impl ArtifactFeedback {
+    /// Creates a new [`ArtifactFeedback`], naming the artifacts with the given `-artifact_prefix`
+    #[must_use]
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            kind: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::feedbacks::{artifact_dir, artifact_path};
+
+    #[test]
+    fn test_artifact_path() {
+        let crash = artifact_path("out/crash-dir/", "crash", b"AAAA");
+        let name = crash.to_string_lossy();
+        assert!(name.starts_with("out/crash-dir/crash-"));
+        assert_eq!(name.len(), "out/crash-dir/crash-".len() + 16);
+        assert_eq!(crash, artifact_path("out/crash-dir/", "crash", b"AAAA"));
+        assert_ne!(crash, artifact_path("out/crash-dir/", "crash", b"AAAB"));
+
+        // A prefix is not necessarily a directory
+        let timeout = artifact_path("out/run1-", "timeout", b"");
+        assert!(timeout.to_string_lossy().starts_with("out/run1-timeout-"));
+
+        assert_eq!(
+            artifact_dir("out/crash-dir/"),
+            PathBuf::from("out/crash-dir/")
+        );
+        assert_eq!(artifact_dir("out/run1-"), PathBuf::from("out"));
+        assert_eq!(artifact_dir("run1-"), PathBuf::from("."));
+        assert_eq!(artifact_dir(""), PathBuf::from("."));
+    }
+}
@@ -0,0 +1,383 @@
+//! Runs `libFuzzer` harnesses: either fuzzes them, or runs single inputs like `libFuzzer` does for file arguments.
+
+use core::time::Duration;
+use std::{env, fs, num::NonZeroUsize, process, time::Instant};
+
+use libafl::{
+    bolts::{
+        current_nanos, current_time,
+        launcher::Launcher,
+        os::Cores,
+        rands::StdRand,
+        shmem::{ShMemProvider, StdShMemProvider},
+        tuples::{tuple_list, Merge},
+        AsSlice,
+    },
+    corpus::{CachedOnDiskCorpus, Corpus, OnDiskCorpus},
+    events::{EventConfig, EventRestarter, LlmpRestartingEventManager, ProgressReporter},
+    executors::{inprocess::InProcessExecutor, ExitKind, TimeoutExecutor},
+    feedback_and_fast, feedback_or,
+    feedbacks::{MapFeedbackState, MaxMapFeedback, TimeFeedback},
+    fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandBytesGenerator,
+    inputs::{BytesInput, HasTargetBytes, Input},
+    monitors::MultiMonitor,
+    mutators::scheduled::{havoc_mutations, tokens_mutations, StdScheduledMutator},
+    mutators::token_mutations::Tokens,
+    observers::{HitcountsMapObserver, StdMapObserver, TimeObserver},
+    schedulers::{IndexesLenTimeMinimizerScheduler, QueueScheduler},
+    stages::StdMutationalStage,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMaxSize, HasMetadata, StdState},
+    Error,
+};
+use libafl_targets::{EDGES_MAP, MAX_EDGES_NUM};
+
+use crate::{
+    feedbacks::{artifact_dir, record_return_value, ArtifactFeedback, RejectFeedback},
+    options::LibfuzzerOptions,
+};
+
+/// Testcases kept in memory, the others are read from the corpus directory
+const CORPUS_CACHE_SIZE: usize = 4096;
+
+/// The size of the generated inputs, if the corpus is empty
+const GENERATED_INPUT_SIZE: usize = 32;
+
+/// The time between two progress reports while fuzzing for a number of runs
+const STATS_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Runs each of the `inputs` once, like `libFuzzer` does if it gets files instead of directories
+pub fn run_inputs<H>(options: &LibfuzzerOptions, harness: &mut H) -> Result<(), Error>
+where
+    H: FnMut(&[u8]) -> i32,
+{
+    println!("Running {} inputs 1 time(s) each.", options.inputs.len());
+    for path in &options.inputs {
+        println!("Running: {}", path.display());
+        let mut buf = fs::read(path)?;
+        if let Some(max_len) = options.max_len {
+            buf.truncate(max_len);
+        }
+        let start = Instant::now();
+        if harness(&buf) == -1 {
+            println!("The harness rejected {}", path.display());
+        }
+        println!(
+            "Executed {} in {} ms",
+            path.display(),
+            start.elapsed().as_millis()
+        );
+    }
+    Ok(())
+}
+
+/// Fuzzes until the client executed the target `runs` times, counting the executions loading the
+/// corpus, like `-runs` of `libFuzzer`. The last fuzzing round may go over `runs`.
+pub fn fuzz_runs<E, EM, I, S, ST, Z>(
+    fuzzer: &mut Z,
+    stages: &mut ST,
+    executor: &mut E,
+    state: &mut S,
+    mgr: &mut EM,
+    runs: u64,
+) -> Result<(), Error>
+where
+    Z: Fuzzer<E, EM, I, S, ST>,
+    I: Input,
+    EM: ProgressReporter<I>,
+    S: HasExecutions + HasClientPerfMonitor,
+{
+    let mut last = current_time();
+    while (*state.executions() as u64) < runs {
+        fuzzer.fuzz_one(stages, executor, state, mgr)?;
+        last = mgr.maybe_report_progress(state, last, STATS_TIMEOUT)?;
+    }
+    Ok(())
+}
+
+/// Fuzzes the `harness` with the given options, on `options.clients()` cores.
+/// The testcases are written to the first corpus directory, as `libFuzzer` does.
+#[allow(clippy::too_many_lines, clippy::similar_names)]
+pub fn fuzz<H>(options: &LibfuzzerOptions, mut harness: H) -> Result<(), Error>
+where
+    H: FnMut(&[u8]) -> i32,
+{
+    // Without corpus directory, `libFuzzer` keeps its corpus in memory only
+    let corpus_dir = options.dirs.first().cloned().unwrap_or_else(|| {
+        env::temp_dir().join(format!("libafl_libfuzzer_corpus_{}", process::id()))
+    });
+    fs::create_dir_all(&corpus_dir)?;
+
+    let cores = Cores::from((0..options.clients()).collect::<Vec<_>>());
+    let shmem_provider = StdShMemProvider::new()?;
+    let monitor = MultiMonitor::new(|s| println!("{}", s));
+
+    let mut run_client = |state: Option<StdState<_, _, _, _, _>>,
+                          mut mgr: LlmpRestartingEventManager<_, _, _, _>,
+                          _core_id| {
+        // Create an observation channel using the coverage map
+        let edges = unsafe { &mut EDGES_MAP[0..MAX_EDGES_NUM] };
+        let edges_observer = HitcountsMapObserver::new(StdMapObserver::new("edges", edges));
+
+        // Create an observation channel to keep track of the execution time
+        let time_observer = TimeObserver::new("time");
+
+        // The state of the edges feedback.
+        let feedback_state = MapFeedbackState::with_observer(&edges_observer);
+
+        // Feedback to rate the interestingness of an input, unless the harness rejected it
+        let feedback = feedback_and_fast!(
+            RejectFeedback::new(),
+            feedback_or!(
+                MaxMapFeedback::new_tracking(&feedback_state, &edges_observer, true, false),
+                TimeFeedback::new_with_observer(&time_observer)
+            )
+        );
+
+        // A feedback to choose if an input is a solution or not, naming it like libFuzzer
+        let objective = ArtifactFeedback::new(&options.artifact_prefix);
+
+        // If not restarting, create a State from scratch
+        let mut state = state.unwrap_or_else(|| {
+            StdState::new(
+                StdRand::with_seed(options.seed.unwrap_or_else(current_nanos)),
+                CachedOnDiskCorpus::new(corpus_dir.clone(), CORPUS_CACHE_SIZE).unwrap(),
+                OnDiskCorpus::new(artifact_dir(&options.artifact_prefix)).unwrap(),
+                tuple_list!(feedback_state),
+            )
+        });
+
+        if let Some(max_len) = options.max_len {
+            state.set_max_size(max_len);
+        }
+
+        // Create a dictionary if not existing
+        if let Some(dict) = &options.dict {
+            if state.metadata().get::<Tokens>().is_none() {
+                state.add_metadata(Tokens::from_file(dict)?);
+            }
+        }
+
+        // A minimization+queue policy to get testcasess from the corpus
+        let scheduler = IndexesLenTimeMinimizerScheduler::new(QueueScheduler::new());
+
+        // A fuzzer with feedbacks and a corpus scheduler
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+        // The wrapped harness function, calling out to the LLVM-style harness
+        let mut libfuzzer_harness = |input: &BytesInput| {
+            let target = input.target_bytes();
+            record_return_value(harness(target.as_slice()));
+            ExitKind::Ok
+        };
+
+        let mut executor = TimeoutExecutor::new(
+            InProcessExecutor::new(
+                &mut libfuzzer_harness,
+                tuple_list!(edges_observer, time_observer),
+                &mut fuzzer,
+                &mut state,
+                &mut mgr,
+            )?,
+            options.timeout,
+        );
+
+        // In case the corpus is empty (on first run), reset
+        if state.corpus().count() < 1 {
+            state
+                .load_initial_inputs(&mut fuzzer, &mut executor, &mut mgr, &options.dirs)
+                .unwrap_or_else(|_| panic!("Failed to load initial corpus at {:?}", &options.dirs));
+            println!("We imported {} inputs from disk.", state.corpus().count());
+        }
+        if options.merge {
+            // Only the interesting inputs made it into the first corpus directory
+            println!("Merged {} inputs.", state.corpus().count());
+            mgr.send_exiting()?;
+            process::exit(0);
+        }
+        // With `-runs=0`, only the corpus runs
+        if state.corpus().count() < 1 && options.runs != Some(0) {
+            let size = options.max_len.map_or(GENERATED_INPUT_SIZE, |max_len| {
+                max_len.clamp(1, GENERATED_INPUT_SIZE)
+            });
+            let mut generator = RandBytesGenerator::new(size);
+            state
+                .generate_initial_inputs_forced(
+                    &mut fuzzer,
+                    &mut executor,
+                    &mut generator,
+                    &mut mgr,
+                    8,
+                )
+                .expect("Failed to generate the initial corpus");
+        }
+
+        let mutator = StdScheduledMutator::new(havoc_mutations().merge(tokens_mutations()));
+        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+        if let Some(runs) = options.runs {
+            fuzz_runs(
+                &mut fuzzer,
+                &mut stages,
+                &mut executor,
+                &mut state,
+                &mut mgr,
+                runs,
+            )?;
+            println!("Done {} runs.", state.executions());
+            mgr.send_exiting()?;
+            process::exit(0);
+        } else {
+            fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)?;
+        }
+        Ok(())
+    };
+
+    let launcher = Launcher::builder()
+        .shmem_provider(shmem_provider)
+        .configuration(EventConfig::from_name("libfuzzer"))
+        .monitor(monitor)
+        .run_client(&mut run_client)
+        .cores(&cores)
+        .exit_cleanly_after(NonZeroUsize::new(options.clients()))
+        .build();
+    match launcher.launch() {
+        // The clients are done, or the user stopped them
+        Err(Error::ShuttingDown) => {
+            println!("\nFuzzing stopped. Good Bye.");
+            Ok(())
+        }
+        res => res,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{Executor, ExitKind, HasObservers},
+        fuzzer::StdFuzzer,
+        inputs::BytesInput,
+        mutators::scheduled::{havoc_mutations, StdScheduledMutator},
+        schedulers::QueueScheduler,
+        stages::StdMutationalStage,
+        state::{HasCorpus, HasExecutions, StdState},
+        Error,
+    };
+
+    use crate::{
+        fuzz::{fuzz_runs, run_inputs},
+        options::LibfuzzerOptions,
+    };
+
+    /// Counts the runs of the target
+    #[derive(Debug, Default)]
+    struct CountingExecutor {
+        runs: usize,
+        observers: (),
+    }
+
+    impl<EM, S, Z> Executor<EM, BytesInput, S, Z> for CountingExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            _input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            self.runs += 1;
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    impl<S> HasObservers<BytesInput, (), S> for CountingExecutor {
+        fn observers(&self) -> &() {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut () {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_fuzz_runs() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"seed".to_vec())))
+            .unwrap();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), (), ());
+        let mut executor = CountingExecutor::default();
+        let mut mgr = NopEventManager {};
+        let mut stages = tuple_list!(StdMutationalStage::new(StdScheduledMutator::new(
+            havoc_mutations()
+        )));
+
+        // The loaded corpus counts, 0 runs nothing more
+        *state.executions_mut() = 1;
+        fuzz_runs(
+            &mut fuzzer,
+            &mut stages,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            0,
+        )
+        .unwrap();
+        assert_eq!(executor.runs, 0);
+
+        // The runs are executions, not fuzzing rounds
+        fuzz_runs(
+            &mut fuzzer,
+            &mut stages,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            1000,
+        )
+        .unwrap();
+        assert!(*state.executions() >= 1000);
+        assert_eq!(executor.runs, *state.executions() - 1);
+    }
+
+    #[test]
+    fn test_run_inputs() {
+        let dir = env::temp_dir().join(format!("libafl_libfuzzer_run_inputs_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let short = dir.join("short");
+        let long = dir.join("long");
+        fs::write(&short, b"AB").unwrap();
+        fs::write(&long, b"ABCDEFGH").unwrap();
+
+        let options = LibfuzzerOptions {
+            max_len: Some(4),
+            inputs: vec![short, long],
+            ..LibfuzzerOptions::default()
+        };
+        let mut runs = vec![];
+        run_inputs(&options, &mut |buf: &[u8]| {
+            runs.push(buf.to_vec());
+            -1
+        })
+        .unwrap();
+        assert_eq!(runs, vec![b"AB".to_vec(), b"ABCD".to_vec()]);
+
+        let missing = LibfuzzerOptions {
+            inputs: vec![dir.join("missing")],
+            ..LibfuzzerOptions::default()
+        };
+        assert!(run_inputs(&missing, &mut |_: &[u8]| 0).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
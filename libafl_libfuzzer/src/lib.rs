@@ -0,0 +1,145 @@
+//! A drop-in replacement for the `libFuzzer` runtime, fuzzing `libFuzzer` harnesses with `LibAFL`.
+//!
+//! Link a harness defining `LLVMFuzzerTestOneInput` against this library instead of `-fsanitize=fuzzer`,
+//! and run it with the usual `libFuzzer` flags: `-fork`/`-jobs`, `-dict`, `-max_len`, `-runs`, `-timeout`,
+//! `-seed`, `-artifact_prefix`, `-merge`, and corpus directories. Files instead of directories are run once, to reproduce a crash.
+//! Programs with a `main` of their own can call [`LLVMFuzzerRunDriver`], like with `libFuzzer`.
+
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![allow(
+    clippy::unreadable_literal,
+    clippy::type_repetition_in_bounds,
+    clippy::missing_errors_doc,
+    clippy::cast_possible_truncation,
+    clippy::used_underscore_binding,
+    clippy::ptr_as_ptr,
+    clippy::missing_panics_doc,
+    clippy::missing_docs_in_private_items,
+    clippy::module_name_repetitions,
+    clippy::unreadable_literal
+)]
+#![cfg_attr(debug_assertions, warn(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(not(debug_assertions), deny(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(
+    not(debug_assertions),
+    deny(
+        bad_style,
+        const_err,
+        dead_code,
+        improper_ctypes,
+        non_shorthand_field_patterns,
+        no_mangle_generic_items,
+        overflowing_literals,
+        path_statements,
+        patterns_in_fns_without_body,
+        private_in_public,
+        unconditional_recursion,
+        unused,
+        unused_allocation,
+        unused_comparisons,
+        unused_parens,
+        while_true
+    )
+)]
+
+use std::{
+    env,
+    ffi::CStr,
+    os::raw::{c_char, c_int},
+    process,
+};
+
+use libafl_targets::{libfuzzer_initialize, libfuzzer_test_one_input};
+
+pub mod options;
+pub use options::LibfuzzerOptions;
+
+pub mod feedbacks;
+pub use feedbacks::{ArtifactFeedback, RejectFeedback};
+
+pub mod fuzz;
+pub use fuzz::{fuzz, run_inputs};
+
+/// Runs `harness` like `libFuzzer` would, with the given `libFuzzer`-style `args`.
+/// Returns the exit code of the run.
+pub fn run_driver<H>(args: &[String], mut harness: H) -> i32
+where
+    H: FnMut(&[u8]) -> i32,
+{
+    let options = match LibfuzzerOptions::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            println!("Invalid arguments: {:?}", err);
+            return 1;
+        }
+    };
+
+    // Call LLVMFuzzerInitialize() if present.
+    if libfuzzer_initialize(args) == -1 {
+        println!("Warning: LLVMFuzzerInitialize failed with -1");
+    }
+
+    let res = if options.inputs.is_empty() {
+        fuzz(&options, harness)
+    } else {
+        run_inputs(&options, &mut harness)
+    };
+    match res {
+        Ok(()) => 0,
+        Err(err) => {
+            println!("Fuzzing failed: {:?}", err);
+            1
+        }
+    }
+}
+
+/// The `libFuzzer` entrypoint for programs with a `main` of their own:
+/// `int LLVMFuzzerRunDriver(int *argc, char ***argv, int (*UserCb)(const uint8_t *Data, size_t Size))`
+///
+/// # Safety
+/// `argc` and `argv` have to be valid, as passed to `main`.
+#[no_mangle]
+#[allow(clippy::similar_names)]
+pub unsafe extern "C" fn LLVMFuzzerRunDriver(
+    argc: *mut c_int,
+    argv: *mut *mut *mut c_char,
+    harness: Option<extern "C" fn(*const u8, usize) -> c_int>,
+) -> c_int {
+    let harness = harness.expect("LLVMFuzzerRunDriver called without a harness");
+    #[allow(clippy::cast_sign_loss)]
+    let args: Vec<String> = (0..*argc as usize)
+        .map(|i| {
+            CStr::from_ptr(*(*argv).add(i))
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    run_driver(&args, |buf| harness(buf.as_ptr(), buf.len()))
+}
+
+/// The main fn, called by the `main` of `libafl_targets` for harnesses without a `main` of their own.
+#[no_mangle]
+pub extern "C" fn libafl_main() {
+    let args: Vec<String> = env::args().collect();
+    process::exit(run_driver(&args, libfuzzer_test_one_input));
+}
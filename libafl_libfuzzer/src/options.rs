@@ -0,0 +1,189 @@
+//! The `libFuzzer` commandline flags understood by this runtime.
+//!
+//! Flags look like `-name=value`, everything else is a corpus directory or, for files, an input to run once.
+//! Like `libFuzzer`, unknown flags are reported and ignored.
+
+use core::time::Duration;
+use std::path::PathBuf;
+
+use libafl::Error;
+
+/// The default timeout of `libFuzzer`, in seconds
+const DEFAULT_TIMEOUT_SECS: u64 = 1200;
+
+/// The options of a run, parsed from `libFuzzer`-style args
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibfuzzerOptions {
+    /// The number of fuzzing processes, from `-fork` or `-jobs`; 0 runs a single client
+    pub fork: usize,
+    /// The dictionary, from `-dict`
+    pub dict: Option<PathBuf>,
+    /// The maximum length of an input, from `-max_len`
+    pub max_len: Option<usize>,
+    /// The number of executions of each client, from `-runs`, including the ones running the corpus.
+    /// 0 only runs the corpus, unset fuzzes indefinitely
+    pub runs: Option<u64>,
+    /// The timeout of an execution, from `-timeout` (in seconds)
+    pub timeout: Duration,
+    /// The seed of the rng, from `-seed`; 0 or unset picks a random one
+    pub seed: Option<u64>,
+    /// The prefix of the crash and timeout files, from `-artifact_prefix`, e.g. `out/` or `out/run1-`
+    pub artifact_prefix: String,
+    /// Only merge the interesting inputs of the other corpus directories into the first one, from `-merge`
+    pub merge: bool,
+    /// The corpus directories. New testcases are written to the first one
+    pub dirs: Vec<PathBuf>,
+    /// The inputs to run once, instead of fuzzing
+    pub inputs: Vec<PathBuf>,
+}
+
+impl Default for LibfuzzerOptions {
+    fn default() -> Self {
+        Self {
+            fork: 0,
+            dict: None,
+            max_len: None,
+            runs: None,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            seed: None,
+            artifact_prefix: String::new(),
+            merge: false,
+            dirs: vec![],
+            inputs: vec![],
+        }
+    }
+}
+
+/// Parses the value of a numeric flag
+fn parse_number<T>(name: &str, value: &str) -> Result<T, Error>
+where
+    T: core::str::FromStr,
+{
+    value
+        .parse()
+        .map_err(|_| Error::IllegalArgument(format!("Invalid value for -{}: {}", name, value)))
+}
+
+impl LibfuzzerOptions {
+    /// Parses the `args`, as passed to `main`, the first one being the program name
+    pub fn parse(args: &[String]) -> Result<Self, Error> {
+        let mut options = Self::default();
+        for arg in args.iter().skip(1) {
+            let flag = match arg.strip_prefix('-') {
+                // `--` separates the args of the harness, which `libFuzzer` does not look at
+                Some("-") => break,
+                Some(flag) => flag,
+                None => {
+                    let path = PathBuf::from(arg);
+                    if path.is_file() {
+                        options.inputs.push(path);
+                    } else {
+                        options.dirs.push(path);
+                    }
+                    continue;
+                }
+            };
+            let (name, value) = flag.split_once('=').unwrap_or((flag, "1"));
+            match name {
+                "fork" | "jobs" => options.fork = parse_number(name, value)?,
+                "dict" => options.dict = Some(PathBuf::from(value)),
+                "max_len" => options.max_len = Some(parse_number(name, value)?),
+                "runs" => {
+                    // Negative means indefinitely
+                    let runs: i64 = parse_number(name, value)?;
+                    options.runs = u64::try_from(runs).ok();
+                }
+                "timeout" => options.timeout = Duration::from_secs(parse_number(name, value)?),
+                "seed" => {
+                    let seed: u64 = parse_number(name, value)?;
+                    options.seed = (seed != 0).then(|| seed);
+                }
+                "artifact_prefix" => options.artifact_prefix = value.to_string(),
+                "merge" => options.merge = value != "0",
+                // Flags that do not change how we fuzz
+                "workers" | "close_fd_mask" | "print_final_stats" | "rss_limit_mb"
+                | "malloc_limit_mb" | "use_value_profile" | "reload" => (),
+                _ => println!("WARNING: unknown flag -{}, ignored", name),
+            }
+        }
+        Ok(options)
+    }
+
+    /// The number of clients to spawn. Merging always runs in a single client
+    #[must_use]
+    pub fn clients(&self) -> usize {
+        if self.merge {
+            1
+        } else {
+            self.fork.max(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::path::PathBuf;
+
+    use crate::options::LibfuzzerOptions;
+
+    #[test]
+    fn test_parse_options() {
+        let args = [
+            "./fuzzer",
+            "-fork=4",
+            "-dict=png.dict",
+            "-max_len=4096",
+            "-runs=-1",
+            "-timeout=5",
+            "-seed=0",
+            "-artifact_prefix=out/",
+            "-some_new_flag=1",
+            "corpus",
+            "seeds",
+            "--",
+            "-ignored",
+        ]
+        .iter()
+        .map(|arg| (*arg).to_string())
+        .collect::<Vec<_>>();
+
+        let options = LibfuzzerOptions::parse(&args).unwrap();
+        assert_eq!(options.clients(), 4);
+        assert_eq!(options.dict, Some(PathBuf::from("png.dict")));
+        assert_eq!(options.max_len, Some(4096));
+        assert_eq!(options.runs, None);
+        assert_eq!(options.timeout, Duration::from_secs(5));
+        assert_eq!(options.seed, None);
+        assert_eq!(options.artifact_prefix, "out/");
+        assert_eq!(
+            options.dirs,
+            vec![PathBuf::from("corpus"), PathBuf::from("seeds")]
+        );
+        assert!(options.inputs.is_empty());
+        assert!(!options.merge);
+
+        let args = ["./fuzzer", "-fork=4", "-merge=1", "new", "old"]
+            .iter()
+            .map(|arg| (*arg).to_string())
+            .collect::<Vec<_>>();
+        let options = LibfuzzerOptions::parse(&args).unwrap();
+        assert!(options.merge);
+        assert_eq!(options.clients(), 1);
+
+        let args = ["./fuzzer", "-runs=0"]
+            .iter()
+            .map(|arg| (*arg).to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(LibfuzzerOptions::parse(&args).unwrap().runs, Some(0));
+
+        let args = ["./fuzzer", "-runs=100000"]
+            .iter()
+            .map(|arg| (*arg).to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(LibfuzzerOptions::parse(&args).unwrap().runs, Some(100_000));
+
+        let args = ["./fuzzer".to_string(), "-max_len=big".to_string()];
+        assert!(LibfuzzerOptions::parse(&args).is_err());
+    }
+}
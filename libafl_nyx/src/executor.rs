@@ -0,0 +1,104 @@
+//! The [`NyxExecutor`] runs the inputs in the `QEMU-Nyx` VM of a [`NyxHelper`].
+
+use core::marker::PhantomData;
+
+use libafl::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    Error,
+};
+use libnyx::NyxReturnValue;
+
+use crate::helper::{NyxHelper, MAX_FILE};
+
+/// An executor passing each input to the agent in a Nyx VM, and restoring the VM snapshot after the run.
+/// The coverage comes from the bitmap of the VM, see [`NyxHelper::bitmap_ptr`].
+#[derive(Debug)]
+pub struct NyxExecutor<'a, I, OT, S> {
+    helper: &'a mut NyxHelper,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<'a, I, OT, S> NyxExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`NyxExecutor`], running the inputs in the VM of `helper`
+    pub fn new(helper: &'a mut NyxHelper, observers: OT) -> Self {
+        Self {
+            helper,
+            observers,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The helper of the VM
+    #[must_use]
+    pub fn helper(&self) -> &NyxHelper {
+        self.helper
+    }
+
+    /// The helper of the VM (mutable)
+    pub fn helper_mut(&mut self) -> &mut NyxHelper {
+        self.helper
+    }
+}
+
+impl<'a, EM, I, OT, S, Z> Executor<EM, I, S, Z> for NyxExecutor<'a, I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let target_bytes = input.target_bytes();
+        // Longer inputs do not fit in the payload buffer
+        let buffer = &target_bytes.as_slice()[..target_bytes.as_slice().len().min(MAX_FILE)];
+        let nyx_process = self.helper.nyx_process_mut();
+        nyx_process.set_input(buffer, buffer.len() as u32);
+
+        match nyx_process.exec() {
+            NyxReturnValue::Normal => Ok(ExitKind::Ok),
+            NyxReturnValue::Crash | NyxReturnValue::Asan => Ok(ExitKind::Crash),
+            NyxReturnValue::Timeout => Ok(ExitKind::Timeout),
+            NyxReturnValue::InvalidWriteToPayload => Err(Error::IllegalState(
+                "The target wrote to the Nyx payload buffer".into(),
+            )),
+            NyxReturnValue::Error => Err(Error::IllegalState(
+                "The Nyx agent reported an error".into(),
+            )),
+            NyxReturnValue::IoError => Err(Error::Unknown("The QEMU-Nyx process died".into())),
+            NyxReturnValue::Abort => {
+                self.helper.shutdown();
+                Err(Error::IllegalState(
+                    "The Nyx agent aborted the fuzzing, see the hprintf output".into(),
+                ))
+            }
+        }
+    }
+}
+
+impl<'a, I, OT, S> HasObservers<I, OT, S> for NyxExecutor<'a, I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
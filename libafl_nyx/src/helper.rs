@@ -0,0 +1,180 @@
+//! The [`NyxHelper`] boots the `QEMU-Nyx` VM of a target and keeps the connection to its agent.
+
+use core::{
+    fmt::{self, Debug, Formatter},
+    time::Duration,
+};
+use std::{fs, path::Path};
+
+use libafl::Error;
+use libnyx::{NyxProcess, NyxReturnValue};
+
+/// The size of the payload buffer, the largest input the agent passes to the target
+pub const MAX_FILE: usize = 1024 * 1024;
+/// The default timeout of a run
+pub const DEFAULT_NYX_TIMEOUT: Duration = Duration::from_secs(2);
+/// The timeout of the first run, while the agent starts the target and takes the snapshot
+const INIT_TIMEOUT: Duration = Duration::from_secs(10);
+/// The longest timeout Nyx takes, it counts the seconds in a `u8`
+pub const MAX_NYX_TIMEOUT: Duration = Duration::from_secs(255);
+
+/// A `QEMU-Nyx` VM running a target packed with the `nyx_packer`
+pub struct NyxHelper {
+    nyx_process: NyxProcess,
+    bitmap_size: usize,
+    trace_bits: *mut u8,
+    timeout: Duration,
+}
+
+impl Debug for NyxHelper {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NyxHelper")
+            .field("bitmap_size", &self.bitmap_size)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl NyxHelper {
+    /// Boots the VM of the target in `share_dir` on the core `cpu_id`, and runs it once, for the
+    /// agent to take the snapshot. The VM keeps its files in `work_dir`.
+    /// With `snap_mode`, the VM gets restored to the snapshot after each run.
+    /// In `parallel_mode`, the instance without a `parent_cpu_id` creates the snapshot, the others
+    /// reuse the snapshot of the instance on `parent_cpu_id`, all of them in the same `work_dir`.
+    pub fn new(
+        share_dir: &Path,
+        work_dir: &Path,
+        cpu_id: u32,
+        snap_mode: bool,
+        parallel_mode: bool,
+        parent_cpu_id: Option<u32>,
+    ) -> Result<Self, Error> {
+        let share_dir = share_dir.to_str().ok_or_else(|| {
+            Error::IllegalArgument(format!("Invalid Nyx share dir {:?}", share_dir))
+        })?;
+        fs::create_dir_all(work_dir)?;
+        let work_dir = work_dir.to_str().ok_or_else(|| {
+            Error::IllegalArgument(format!("Invalid Nyx work dir {:?}", work_dir))
+        })?;
+        let cpu_id = cpu_id as usize;
+
+        let nyx_process = match (parallel_mode, parent_cpu_id) {
+            (false, _) => NyxProcess::new(share_dir, work_dir, cpu_id, MAX_FILE, true),
+            (true, None) => NyxProcess::new_parent(share_dir, work_dir, cpu_id, MAX_FILE, true),
+            (true, Some(parent_cpu_id)) => {
+                NyxProcess::new_child(share_dir, work_dir, cpu_id, parent_cpu_id as usize)
+            }
+        };
+        let mut nyx_process = nyx_process
+            .map_err(|err| Error::IllegalState(format!("Could not start Nyx: {}", err)))?;
+
+        nyx_process.option_set_reload_mode(snap_mode);
+        set_timeout(&mut nyx_process, INIT_TIMEOUT)?;
+
+        // The first run boots the target up to the snapshot
+        nyx_process.set_input(b"INIT", 4);
+        let failure = match nyx_process.exec() {
+            NyxReturnValue::Normal => None,
+            NyxReturnValue::Crash | NyxReturnValue::Asan => {
+                Some("The target crashed before the Nyx snapshot")
+            }
+            NyxReturnValue::Timeout => Some("The target timed out before the Nyx snapshot"),
+            _ => Some("The Nyx VM did not come up, check the share dir and the agent"),
+        };
+        if let Some(failure) = failure {
+            nyx_process.shutdown();
+            return Err(Error::IllegalState(failure.into()));
+        }
+
+        set_timeout(&mut nyx_process, DEFAULT_NYX_TIMEOUT)?;
+        let bitmap_size = nyx_process.bitmap_buffer_size();
+        let trace_bits = nyx_process.bitmap_buffer_mut().as_mut_ptr();
+        Ok(Self {
+            nyx_process,
+            bitmap_size,
+            trace_bits,
+            timeout: DEFAULT_NYX_TIMEOUT,
+        })
+    }
+
+    /// Sets the timeout of a run, at most [`MAX_NYX_TIMEOUT`]
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+        set_timeout(&mut self.nyx_process, timeout)?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    /// The timeout of a run
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// The coverage map the VM writes to, to wrap in a map observer
+    #[must_use]
+    pub fn bitmap_ptr(&self) -> *mut u8 {
+        self.trace_bits
+    }
+
+    /// The size of the coverage map
+    #[must_use]
+    pub fn bitmap_size(&self) -> usize {
+        self.bitmap_size
+    }
+
+    /// The process running the VM
+    pub fn nyx_process_mut(&mut self) -> &mut NyxProcess {
+        &mut self.nyx_process
+    }
+
+    /// The message the agent left for the last crash, e.g. the panic of a kernel
+    #[must_use]
+    pub fn crash_message(&self) -> String {
+        String::from_utf8_lossy(&self.nyx_process.aux_misc()).into_owned()
+    }
+
+    /// Shuts the VM down
+    pub fn shutdown(&mut self) {
+        self.nyx_process.shutdown();
+    }
+}
+
+/// Nyx takes the timeout in seconds, at most 255, and microseconds
+fn nyx_timeout(timeout: Duration) -> Result<(u8, u32), Error> {
+    let secs = u8::try_from(timeout.as_secs()).map_err(|_| {
+        Error::IllegalArgument(format!(
+            "Nyx timeouts are at most {:?}, got {:?}",
+            MAX_NYX_TIMEOUT, timeout
+        ))
+    })?;
+    Ok((secs, timeout.subsec_micros()))
+}
+
+fn set_timeout(nyx_process: &mut NyxProcess, timeout: Duration) -> Result<(), Error> {
+    let (secs, micros) = nyx_timeout(timeout)?;
+    nyx_process.option_set_timeout(secs, micros);
+    nyx_process.option_apply();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::helper::{nyx_timeout, MAX_NYX_TIMEOUT};
+
+    #[test]
+    fn test_nyx_timeout() {
+        assert_eq!(
+            nyx_timeout(Duration::from_millis(2500)).unwrap(),
+            (2, 500_000)
+        );
+        assert_eq!(nyx_timeout(MAX_NYX_TIMEOUT).unwrap(), (255, 0));
+        assert_eq!(
+            nyx_timeout(MAX_NYX_TIMEOUT + Duration::from_micros(999_999)).unwrap(),
+            (255, 999_999)
+        );
+        // Longer timeouts are not silently cut
+        assert!(nyx_timeout(MAX_NYX_TIMEOUT + Duration::from_secs(1)).is_err());
+    }
+}
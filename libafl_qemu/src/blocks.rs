@@ -0,0 +1,134 @@
+//! Hit counts of the basic blocks, an alternative to the edges coverage for the targets
+//! that need to tell how many times a loop ran to make progress.
+
+use core::{cmp::max, pin::Pin};
+
+use hashbrown::{hash_map::Entry, HashMap};
+use libafl::{inputs::Input, state::HasMetadata};
+use libafl_targets::{EDGES_MAP, EDGES_MAP_SIZE, MAX_EDGES_NUM};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    emu::Emulator,
+    helper::{QemuHelper, QemuHelperTuple, QemuInstrumentationFilter},
+    hooks::QemuHooks,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QemuBlocksMapMetadata {
+    pub map: HashMap<u64, u64>,
+    pub current_id: u64,
+}
+
+impl QemuBlocksMapMetadata {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+            current_id: 0,
+        }
+    }
+}
+
+libafl::impl_serdeany!(
+    QemuBlocksMapMetadata,
+    "libafl_qemu::blocks::QemuBlocksMapMetadata"
+);
+
+/// Counts the executions of each basic block in the [`EDGES_MAP`], one entry per block.
+/// The counts saturate at 255; observe the map with a `HitcountsMapObserver` to bucket them like AFL.
+#[derive(Debug)]
+pub struct QemuBlockCoverageHelper {
+    filter: QemuInstrumentationFilter,
+}
+
+impl QemuBlockCoverageHelper {
+    #[must_use]
+    pub fn new(filter: QemuInstrumentationFilter) -> Self {
+        Self { filter }
+    }
+
+    #[must_use]
+    pub fn must_instrument(&self, addr: u64) -> bool {
+        self.filter.allowed(addr)
+    }
+
+    /// The filter of the instrumented blocks, to change before the snapshot is taken.
+    /// Flush the JIT after changing it, so that the translated blocks follow it.
+    pub fn filter_mut(&mut self) -> &mut QemuInstrumentationFilter {
+        &mut self.filter
+    }
+}
+
+impl Default for QemuBlockCoverageHelper {
+    fn default() -> Self {
+        Self::new(QemuInstrumentationFilter::None)
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuBlockCoverageHelper
+where
+    I: Input,
+    S: HasMetadata,
+{
+    fn init_hooks<'a, QT>(&self, hooks: Pin<&QemuHooks<'a, I, QT, S>>)
+    where
+        QT: QemuHelperTuple<I, S>,
+    {
+        hooks.block_generation(gen_unique_block_ids::<I, QT, S>);
+        hooks.emulator().set_exec_block_hook(trace_block_hitcount);
+    }
+}
+
+pub fn gen_unique_block_ids<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    state: Option<&mut S>,
+    pc: u64,
+) -> Option<u64>
+where
+    S: HasMetadata,
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type::<QemuBlockCoverageHelper>() {
+        if !h.must_instrument(pc) {
+            return None;
+        }
+    }
+    let state = state.expect("The gen_unique_block_ids hook works only for in-process fuzzing");
+    if state.metadata().get::<QemuBlocksMapMetadata>().is_none() {
+        state.add_metadata(QemuBlocksMapMetadata::new());
+    }
+    let meta = state
+        .metadata_mut()
+        .get_mut::<QemuBlocksMapMetadata>()
+        .unwrap();
+
+    match meta.map.entry(pc) {
+        Entry::Occupied(e) => {
+            let id = *e.get();
+            let nxt = (id as usize + 1) & (EDGES_MAP_SIZE - 1);
+            unsafe {
+                MAX_EDGES_NUM = max(MAX_EDGES_NUM, nxt);
+            }
+            Some(id)
+        }
+        Entry::Vacant(e) => {
+            let id = meta.current_id;
+            e.insert(id);
+            meta.current_id = (id + 1) & (EDGES_MAP_SIZE as u64 - 1);
+            unsafe {
+                MAX_EDGES_NUM = meta.current_id as usize;
+            }
+            Some(id)
+        }
+    }
+}
+
+/// Counts an execution of a block, saturating so that hot loops do not wrap back to a low count
+pub extern "C" fn trace_block_hitcount(id: u64) {
+    unsafe {
+        EDGES_MAP[id as usize] = EDGES_MAP[id as usize].saturating_add(1);
+    }
+}
@@ -1,5 +1,9 @@
 use hashbrown::{hash_map::Entry, HashMap};
-use libafl::{inputs::Input, state::HasMetadata};
+use libafl::{
+    bolts::coverage_report::{CoverageReport, CoverageSite},
+    inputs::Input,
+    state::HasMetadata,
+};
 pub use libafl_targets::{
     edges_max_num, EDGES_MAP, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE, EDGES_MAP_SIZE, MAX_EDGES_NUM,
 };
@@ -7,7 +11,8 @@ use serde::{Deserialize, Serialize};
 use std::{cell::UnsafeCell, cmp::max, pin::Pin};
 
 use crate::{
-    emu::Emulator,
+    elf::EasyElf,
+    emu::{Emulator, GuestAddr},
     helper::{hash_me, QemuHelper, QemuHelperTuple, QemuInstrumentationFilter},
     hooks::QemuHooks,
 };
@@ -28,7 +33,64 @@ impl QemuEdgesMapMetadata {
     }
 }
 
-libafl::impl_serdeany!(QemuEdgesMapMetadata);
+libafl::impl_serdeany!(
+    QemuEdgesMapMetadata,
+    "libafl_qemu::edges::QemuEdgesMapMetadata"
+);
+
+/// A [`CoverageReport`] over the edges found so far, attributing each edge to the module and
+/// function of its source block. The functions come from the ELF symbols of the mapped modules.
+#[must_use]
+pub fn edges_coverage_report(emulator: &Emulator, meta: &QemuEdgesMapMetadata) -> CoverageReport {
+    // The mapped ranges of each module, and its load address
+    let mut ranges: Vec<(GuestAddr, GuestAddr, String)> = vec![];
+    let mut load_addrs: HashMap<String, GuestAddr> = HashMap::new();
+    for map in emulator.mappings() {
+        if let Some(path) = map.path() {
+            if path.is_empty() || path.starts_with('[') {
+                continue;
+            }
+            ranges.push((map.start(), map.end(), path.to_string()));
+            let load_addr = map.start().saturating_sub(map.offset());
+            let entry = load_addrs.entry(path.to_string()).or_insert(load_addr);
+            *entry = (*entry).min(load_addr);
+        }
+    }
+
+    let mut edges: HashMap<String, Vec<(GuestAddr, usize)>> = HashMap::new();
+    for ((src, _dest), id) in &meta.map {
+        let src = *src as GuestAddr;
+        let module = ranges
+            .iter()
+            .find(|(start, end, _)| *start <= src && src < *end)
+            .map_or("unknown", |(_, _, path)| path);
+        edges
+            .entry(module.to_string())
+            .or_default()
+            .push((src, *id as usize));
+    }
+
+    let mut sites = vec![];
+    for (module, module_edges) in edges {
+        let mut buffer = vec![];
+        let elf = EasyElf::from_file(&module, &mut buffer).ok();
+        let load_addr = load_addrs.get(&module).copied().unwrap_or(0);
+        for (src, index) in module_edges {
+            let function = elf
+                .as_ref()
+                .and_then(|elf| elf.function_at(src, load_addr))
+                .map_or_else(|| format!("{:#x}", src), ToString::to_string);
+            sites.push(CoverageSite {
+                index,
+                module: module.clone(),
+                function,
+                file: None,
+                line: 0,
+            });
+        }
+    }
+    CoverageReport::new(sites)
+}
 
 #[derive(Debug)]
 pub struct QemuEdgeCoverageHelper {
@@ -57,6 +119,12 @@ impl QemuEdgeCoverageHelper {
     pub fn must_instrument(&self, addr: u64) -> bool {
         self.filter.allowed(addr)
     }
+
+    /// The filter of the instrumented edges, to change before the snapshot is taken.
+    /// Flush the JIT after changing it, so that the translated blocks follow it.
+    pub fn filter_mut(&mut self) -> &mut QemuInstrumentationFilter {
+        &mut self.filter
+    }
 }
 
 impl Default for QemuEdgeCoverageHelper {
@@ -112,6 +180,12 @@ impl QemuEdgeCoverageChildHelper {
     pub fn must_instrument(&self, addr: u64) -> bool {
         self.filter.allowed(addr)
     }
+
+    /// The filter of the instrumented edges, to change before the snapshot is taken.
+    /// Flush the JIT after changing it, so that the translated blocks follow it.
+    pub fn filter_mut(&mut self) -> &mut QemuInstrumentationFilter {
+        &mut self.filter
+    }
 }
 
 impl Default for QemuEdgeCoverageChildHelper {
@@ -57,6 +57,25 @@ impl<'a> EasyElf<'a> {
         None
     }
 
+    /// The name of the function containing `addr`, from the symbols of the ELF
+    #[must_use]
+    pub fn function_at(&self, addr: GuestAddr, load_addr: GuestAddr) -> Option<&str> {
+        let addr = if self.is_pic() {
+            addr.checked_sub(load_addr)?
+        } else {
+            addr
+        } as u64;
+        self.elf
+            .syms
+            .iter()
+            .find(|sym| {
+                sym.is_function()
+                    && sym.st_value <= addr
+                    && addr < sym.st_value + sym.st_size.max(1)
+            })
+            .and_then(|sym| self.elf.strtab.get_at(sym.st_name))
+    }
+
     fn is_pic(&self) -> bool {
         self.elf.header.e_type == ET_DYN
     }
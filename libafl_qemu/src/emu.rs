@@ -6,12 +6,15 @@ use core::{
     mem::{transmute, MaybeUninit},
     ptr::{addr_of, addr_of_mut, copy_nonoverlapping, null},
 };
+use libafl::executors::ExitKind;
 use libc::c_int;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use num_traits::Num;
 use std::{slice::from_raw_parts, str::from_utf8_unchecked};
 use strum_macros::EnumIter;
 
+use crate::Regs;
+
 #[cfg(not(any(cpu_target = "x86_64", cpu_target = "aarch64")))]
 /// `GuestAddr` is u32 for 32-bit targets
 pub type GuestAddr = u32;
@@ -309,6 +312,33 @@ impl Drop for GuestMaps {
     }
 }
 
+/// What to do after the callback of a breakpoint, see [`Emulator::set_breakpoint_with_callback`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointAction {
+    /// Go on with the execution
+    Continue,
+    /// Stop the execution, reporting this [`ExitKind`]
+    Stop(ExitKind),
+}
+
+/// The callback of a breakpoint, called with the address of the breakpoint
+pub type BreakpointCallback = Box<dyn FnMut(&Emulator, GuestAddr) -> BreakpointAction>;
+
+static mut BREAKPOINT_CALLBACKS: Vec<(GuestAddr, BreakpointCallback)> = vec![];
+/// The breakpoints stepped over by a callback continuing without moving the pc
+static mut DISARMED_BREAKPOINTS: Vec<GuestAddr> = vec![];
+
+/// Hooked on the address of each breakpoint with a callback, arms it again once stepped over
+extern "C" fn rearm_breakpoint(addr: u64) {
+    unsafe {
+        let len = DISARMED_BREAKPOINTS.len();
+        DISARMED_BREAKPOINTS.retain(|a| u64::from(*a) != addr);
+        if DISARMED_BREAKPOINTS.len() != len {
+            libafl_qemu_set_breakpoint(addr);
+        }
+    }
+}
+
 static mut EMULATOR_IS_INITIALIZED: bool = false;
 
 #[derive(Debug)]
@@ -440,6 +470,81 @@ impl Emulator {
         }
     }
 
+    /// Sets a breakpoint calling `callback` when hit in [`Emulator::run_with_callbacks`].
+    /// The callback can read and write the registers, then goes on or stops the execution with an [`ExitKind`],
+    /// e.g. to report reaching `abort` or an error handler as a crash.
+    /// It must not set or remove breakpoints with callbacks itself.
+    ///
+    /// This also sets a hook on `addr`, do not set another one there with [`Emulator::set_hook`].
+    pub fn set_breakpoint_with_callback<F>(&self, addr: GuestAddr, callback: F)
+    where
+        F: FnMut(&Emulator, GuestAddr) -> BreakpointAction + 'static,
+    {
+        let known = unsafe {
+            let known = BREAKPOINT_CALLBACKS.iter().any(|(a, _)| *a == addr);
+            BREAKPOINT_CALLBACKS.retain(|(a, _)| *a != addr);
+            BREAKPOINT_CALLBACKS.push((addr, Box::new(callback)));
+            known
+        };
+        if !known {
+            self.set_hook(addr, rearm_breakpoint, addr.into());
+            self.set_breakpoint(addr);
+        }
+    }
+
+    /// Removes a breakpoint set with [`Emulator::set_breakpoint_with_callback`], and its callback
+    pub fn remove_breakpoint_with_callback(&self, addr: GuestAddr) {
+        let disarmed = unsafe {
+            BREAKPOINT_CALLBACKS.retain(|(a, _)| *a != addr);
+            let len = DISARMED_BREAKPOINTS.len();
+            DISARMED_BREAKPOINTS.retain(|a| *a != addr);
+            DISARMED_BREAKPOINTS.len() != len
+        };
+        self.remove_hook(addr);
+        if !disarmed {
+            self.remove_breakpoint(addr);
+        }
+    }
+
+    /// Runs the emulator like [`Emulator::run`], calling the callbacks of the breakpoints
+    /// set with [`Emulator::set_breakpoint_with_callback`] until one of them stops the execution.
+    /// Returns the [`ExitKind`] of that callback, or `None` if stopped by a breakpoint without callback.
+    ///
+    /// A callback going on without moving the pc steps over its breakpoint,
+    /// which is armed again as soon as the instruction at its address ran.
+    /// # Safety
+    ///
+    /// Same as [`Emulator::run`].
+    pub unsafe fn run_with_callbacks(&self) -> Option<ExitKind> {
+        loop {
+            // The pc may have been moved away from a breakpoint stepped over in a previous run
+            let pc: GuestAddr = self.read_reg(Regs::Pc).ok()?;
+            DISARMED_BREAKPOINTS.retain(|&addr| {
+                if addr == pc {
+                    true
+                } else {
+                    self.set_breakpoint(addr);
+                    false
+                }
+            });
+            self.run();
+
+            let pc: GuestAddr = self.read_reg(Regs::Pc).ok()?;
+            let (_, callback) = BREAKPOINT_CALLBACKS
+                .iter_mut()
+                .find(|(addr, _)| *addr == pc)?;
+            match callback(self, pc) {
+                BreakpointAction::Stop(exit_kind) => return Some(exit_kind),
+                BreakpointAction::Continue => {
+                    if self.read_reg::<_, GuestAddr>(Regs::Pc) == Ok(pc) {
+                        self.remove_breakpoint(pc);
+                        DISARMED_BREAKPOINTS.push(pc);
+                    }
+                }
+            }
+        }
+    }
+
     /// This function will run the emulator until the next breakpoint, or until finish.
     /// # Safety
     ///
@@ -529,6 +634,17 @@ impl Emulator {
         .map(|addr| addr as GuestAddr)
     }
 
+    /// Maps a read-write buffer of `size` bytes in the guest, and returns its guest address and its host pointer.
+    /// The host reads and writes it in place, e.g. to deliver the inputs, without `read_mem` and `write_mem` copies.
+    ///
+    /// Map it before the snapshot is taken, else the [`crate::QemuSnapshotHelper`] unmaps it on reset.
+    pub fn map_shared_buffer(&self, size: usize) -> Result<(GuestAddr, *mut u8), String> {
+        let guest_addr = self
+            .map_private(0, size, MmapPerms::ReadWrite)
+            .map_err(|_| format!("Failed to map a shared buffer of {} bytes", size))?;
+        Ok((guest_addr, self.g2h(guest_addr)))
+    }
+
     pub fn mprotect(&self, addr: GuestAddr, size: usize, perms: MmapPerms) -> Result<(), String> {
         let res = unsafe { target_mprotect(addr.into(), size as u64, perms.into()) };
         if res == 0 {
@@ -855,6 +971,13 @@ pub mod pybind {
             }
         }
 
+        fn map_shared_buffer(&self, size: usize) -> PyResult<(GuestAddr, u64)> {
+            self.emu
+                .map_shared_buffer(size)
+                .map(|(guest_addr, host_ptr)| (guest_addr, host_ptr as u64))
+                .map_err(PyValueError::new_err)
+        }
+
         fn mprotect(&self, addr: GuestAddr, size: usize, perms: i32) -> PyResult<()> {
             if let Ok(p) = MmapPerms::try_from(perms) {
                 self.emu
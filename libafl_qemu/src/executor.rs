@@ -7,12 +7,14 @@ use core::{
 use libafl::{
     bolts::shmem::ShMemProvider,
     events::{EventFirer, EventRestarter},
-    executors::{Executor, ExitKind, HasObservers, InProcessExecutor, InProcessForkExecutor},
+    executors::{
+        BatchExecutor, Executor, ExitKind, HasObservers, InProcessExecutor, InProcessForkExecutor,
+    },
     feedbacks::Feedback,
     fuzzer::HasObjective,
     inputs::Input,
     observers::ObserversTuple,
-    state::{HasClientPerfMonitor, HasSolutions},
+    state::{HasClientPerfMonitor, HasExecutions, HasSolutions},
     Error,
 };
 
@@ -145,6 +147,76 @@ where
     }
 }
 
+impl<'a, EM, H, I, OT, QT, S, Z> BatchExecutor<EM, I, OT, S, Z>
+    for QemuExecutor<'a, H, I, OT, QT, S>
+where
+    H: FnMut(&I) -> ExitKind,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+    S: HasExecutions,
+{
+    /// Runs the batch with a single handle on the emulator and the helpers
+    fn run_batch<F>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        inputs: &[I],
+        on_exec: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&mut Z, &mut S, &mut EM, &OT, &I, ExitKind) -> Result<(), Error>,
+    {
+        let helpers = unsafe { self.hooks.as_mut().get_unchecked_mut().helpers_mut() };
+        run_batch_with_helpers(
+            &mut self.inner,
+            helpers,
+            fuzzer,
+            state,
+            mgr,
+            inputs,
+            on_exec,
+        )
+    }
+}
+
+/// Runs the `inputs` on `inner`, one after the other.
+/// The helpers still reset the target, e.g. restore the snapshot, before each input: only the
+/// emulator handle and the lookup of the helpers are shared by the batch.
+fn run_batch_with_helpers<E, EM, F, I, OT, QT, S, Z>(
+    inner: &mut E,
+    helpers: &mut QT,
+    fuzzer: &mut Z,
+    state: &mut S,
+    mgr: &mut EM,
+    inputs: &[I],
+    mut on_exec: F,
+) -> Result<(), Error>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    F: FnMut(&mut Z, &mut S, &mut EM, &OT, &I, ExitKind) -> Result<(), Error>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+    S: HasExecutions,
+{
+    let emu = Emulator::new_empty();
+    for input in inputs {
+        inner.observers_mut().pre_exec_all(state, input)?;
+        helpers.pre_exec_all(&emu, input);
+        let r = inner.run_target(fuzzer, state, mgr, input);
+        helpers.post_exec_all(&emu, input);
+        let exit_kind = r?;
+        *state.executions_mut() += 1;
+        inner
+            .observers_mut()
+            .post_exec_all(state, input, &exit_kind)?;
+        on_exec(fuzzer, state, mgr, inner.observers(), input, exit_kind)?;
+    }
+    Ok(())
+}
+
 pub struct QemuForkExecutor<'a, H, I, OT, QT, S, SP>
 where
     H: FnMut(&I) -> ExitKind,
@@ -286,3 +358,147 @@ where
         self.inner.observers_mut()
     }
 }
+
+impl<'a, EM, H, I, OT, QT, S, Z, SP> BatchExecutor<EM, I, OT, S, Z>
+    for QemuForkExecutor<'a, H, I, OT, QT, S, SP>
+where
+    H: FnMut(&I) -> ExitKind,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+    S: HasExecutions,
+    SP: ShMemProvider,
+{
+    /// Runs the batch with a single handle on the emulator and the helpers
+    fn run_batch<F>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        inputs: &[I],
+        on_exec: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&mut Z, &mut S, &mut EM, &OT, &I, ExitKind) -> Result<(), Error>,
+    {
+        let helpers = unsafe { self.hooks.as_mut().get_unchecked_mut().helpers_mut() };
+        run_batch_with_helpers(
+            &mut self.inner,
+            helpers,
+            fuzzer,
+            state,
+            mgr,
+            inputs,
+            on_exec,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use std::rc::Rc;
+
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsSlice},
+        corpus::InMemoryCorpus,
+        executors::{Executor, ExitKind, HasObservers},
+        inputs::{BytesInput, HasTargetBytes},
+        state::StdState,
+        Error,
+    };
+
+    use crate::{emu::Emulator, executor::run_batch_with_helpers, helper::QemuHelper};
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Restores the guest memory before each run, like the snapshot helper
+    #[derive(Debug)]
+    struct RestoringHelper {
+        memory: Rc<RefCell<Vec<u8>>>,
+        snapshot: Vec<u8>,
+    }
+
+    impl QemuHelper<BytesInput, TestState> for RestoringHelper {
+        fn pre_exec(&mut self, _emulator: &Emulator, _input: &BytesInput) {
+            self.memory.borrow_mut().clone_from(&self.snapshot);
+        }
+    }
+
+    /// Records the guest memory it starts from, then writes its input to it
+    #[derive(Debug)]
+    struct CorruptingExecutor {
+        memory: Rc<RefCell<Vec<u8>>>,
+        seen: Vec<Vec<u8>>,
+        observers: (),
+    }
+
+    impl<EM, Z> Executor<EM, BytesInput, TestState, Z> for CorruptingExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut TestState,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            let mut memory = self.memory.borrow_mut();
+            self.seen.push(memory.clone());
+            memory.copy_from_slice(input.target_bytes().as_slice());
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    impl HasObservers<BytesInput, (), TestState> for CorruptingExecutor {
+        fn observers(&self) -> &() {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut () {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_batch_restores_each_input() {
+        let memory = Rc::new(RefCell::new(vec![0; 4]));
+        let mut helpers = tuple_list!(RestoringHelper {
+            memory: memory.clone(),
+            snapshot: vec![0; 4],
+        });
+        let mut executor = CorruptingExecutor {
+            memory,
+            seen: vec![],
+            observers: (),
+        };
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let inputs = [
+            BytesInput::new(vec![0xff; 4]),
+            BytesInput::new(vec![1, 2, 3, 4]),
+        ];
+
+        let mut runs = 0;
+        run_batch_with_helpers(
+            &mut executor,
+            &mut helpers,
+            &mut (),
+            &mut state,
+            &mut (),
+            &inputs,
+            |_, _, _, _, _, _| {
+                runs += 1;
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        // The second input runs on the snapshot, not on the memory the first one corrupted
+        assert_eq!(runs, 2);
+        assert_eq!(executor.seen, vec![vec![0; 4], vec![0; 4]]);
+    }
+}
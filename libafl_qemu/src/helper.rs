@@ -111,6 +111,67 @@ impl QemuInstrumentationFilter {
             QemuInstrumentationFilter::None => true,
         }
     }
+
+    /// Include `range`: add it to an allow list, or remove it from a deny list
+    pub fn allow(&mut self, range: Range<u64>) {
+        match self {
+            QemuInstrumentationFilter::AllowList(l) => l.push(range),
+            QemuInstrumentationFilter::DenyList(l) => remove_range(l, &range),
+            QemuInstrumentationFilter::None => (),
+        }
+    }
+
+    /// Exclude `range`: remove it from an allow list, or add it to a deny list
+    pub fn deny(&mut self, range: Range<u64>) {
+        match self {
+            QemuInstrumentationFilter::AllowList(l) => remove_range(l, &range),
+            QemuInstrumentationFilter::DenyList(l) => l.push(range),
+            QemuInstrumentationFilter::None => {
+                *self = QemuInstrumentationFilter::DenyList(vec![range]);
+            }
+        }
+    }
+
+    /// Include the mappings of the files whose path contains `name`, e.g. the target binary
+    pub fn allow_module(&mut self, emulator: &Emulator, name: &str) {
+        for range in module_ranges(emulator, name) {
+            self.allow(range);
+        }
+    }
+
+    /// Exclude the mappings of the files whose path contains `name`, e.g. `libc` or `ld-linux`
+    pub fn deny_module(&mut self, emulator: &Emulator, name: &str) {
+        for range in module_ranges(emulator, name) {
+            self.deny(range);
+        }
+    }
+}
+
+/// The ranges of the mappings of the files whose path contains `name`
+fn module_ranges(emulator: &Emulator, name: &str) -> Vec<Range<u64>> {
+    emulator
+        .mappings()
+        .filter(|map| map.path().map_or(false, |path| path.contains(name)))
+        .map(|map| u64::from(map.start())..u64::from(map.end()))
+        .collect()
+}
+
+/// Remove `range` from the ranges of `list`, splitting the ones it overlaps
+fn remove_range(list: &mut Vec<Range<u64>>, range: &Range<u64>) {
+    let mut remaining = vec![];
+    for rng in list.drain(..) {
+        if rng.end <= range.start || range.end <= rng.start {
+            remaining.push(rng);
+            continue;
+        }
+        if rng.start < range.start {
+            remaining.push(rng.start..range.start);
+        }
+        if range.end < rng.end {
+            remaining.push(range.end..rng.end);
+        }
+    }
+    *list = remaining;
 }
 
 #[must_use]
@@ -0,0 +1,184 @@
+//! Follow the executable mappings created after the snapshot, e.g. by `dlopen` of a plugin,
+//! so that their code is instrumented and the library survives the snapshot resets.
+//!
+//! Restoring the snapshot would unmap the library and make the loader forget it, so the next run
+//! loads it again at another address. Instead, the snapshot is taken again after an execution
+//! creating executable mappings, with the library, its data, and the state of the loader.
+
+use core::{ops::Range, pin::Pin};
+
+use libafl::inputs::Input;
+
+use crate::{
+    edges::{QemuEdgeCoverageChildHelper, QemuEdgeCoverageHelper},
+    emu::Emulator,
+    helper::{QemuHelper, QemuHelperTuple},
+    hooks::QemuHooks,
+    snapshot::{QemuSnapshotHelper, SNAPSHOT_PAGE_MASK, SNAPSHOT_PAGE_SIZE},
+    GuestAddr, SYS_mmap, SYS_mprotect,
+};
+
+#[derive(Debug)]
+pub struct QemuLateMapsHelper {
+    /// Only follow the mappings whose path contains one of these, all of them if empty
+    modules: Vec<String>,
+    /// The executable mappings created after the snapshot
+    maps: Vec<Range<GuestAddr>>,
+    started: bool,
+}
+
+impl QemuLateMapsHelper {
+    /// Follow all the executable mappings created after the snapshot
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_modules(vec![])
+    }
+
+    /// Follow the executable mappings of the files whose path contains one of `modules`
+    #[must_use]
+    pub fn with_modules(modules: Vec<String>) -> Self {
+        Self {
+            modules,
+            maps: vec![],
+            started: false,
+        }
+    }
+
+    /// The executable mappings created after the snapshot so far
+    #[must_use]
+    pub fn maps(&self) -> &[Range<GuestAddr>] {
+        &self.maps
+    }
+
+    /// If the mapping of the file at `path` has to be followed
+    #[must_use]
+    pub fn must_follow(&self, path: Option<&str>) -> bool {
+        self.modules.is_empty()
+            || path.map_or(false, |path| {
+                self.modules
+                    .iter()
+                    .any(|module| path.contains(module.as_str()))
+            })
+    }
+}
+
+impl Default for QemuLateMapsHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The pages spanned by `size` bytes at `start`
+fn page_range(start: GuestAddr, size: GuestAddr) -> Range<GuestAddr> {
+    let end = (start + size + SNAPSHOT_PAGE_SIZE as GuestAddr - 1) & SNAPSHOT_PAGE_MASK;
+    (start & SNAPSHOT_PAGE_MASK)..end
+}
+
+impl<I, S> QemuHelper<I, S> for QemuLateMapsHelper
+where
+    I: Input,
+{
+    fn init_hooks<'a, QT>(&self, hooks: Pin<&QemuHooks<'a, I, QT, S>>)
+    where
+        QT: QemuHelperTuple<I, S>,
+    {
+        hooks.after_syscalls(trace_late_maps::<I, QT, S>);
+    }
+
+    fn pre_exec(&mut self, _emulator: &Emulator, _input: &I) {
+        // The snapshot is taken before the first execution, with the mappings existing so far
+        self.started = true;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[allow(non_upper_case_globals)]
+pub fn trace_late_maps<I, QT, S>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    result: u64,
+    sys_num: i32,
+    a0: u64,
+    a1: u64,
+    a2: u64,
+    _a3: u64,
+    _a4: u64,
+    _a5: u64,
+    _a6: u64,
+    _a7: u64,
+) -> u64
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    let (start, size, prot) = match i64::from(sys_num) {
+        SYS_mmap if result as GuestAddr != GuestAddr::MAX => (result, a1, a2),
+        SYS_mprotect if result == 0 => (a0, a1, a2),
+        _ => return result,
+    };
+    if prot as i32 & libc::PROT_EXEC == 0 {
+        return result;
+    }
+
+    let h = helpers
+        .match_first_type_mut::<QemuLateMapsHelper>()
+        .unwrap();
+    if !h.started {
+        return result;
+    }
+    let pages = page_range(start as GuestAddr, size as GuestAddr);
+    let path = emulator
+        .mappings()
+        .find(|map| map.start() <= pages.start && pages.start < map.end())
+        .and_then(|map| map.path().map(ToString::to_string));
+    if !h.must_follow(path.as_deref()) {
+        return result;
+    }
+    h.maps.push(pages.clone());
+
+    // The code of the mapping is not translated yet, no need to flush the JIT
+    let range = u64::from(pages.start)..u64::from(pages.end);
+    if let Some(edges) = helpers.match_first_type_mut::<QemuEdgeCoverageHelper>() {
+        edges.filter_mut().allow(range.clone());
+    }
+    if let Some(edges) = helpers.match_first_type_mut::<QemuEdgeCoverageChildHelper>() {
+        edges.filter_mut().allow(range);
+    }
+    // The whole library, with its data, and the loader knowing it, go into the snapshot
+    if let Some(snapshot) = helpers.match_first_type_mut::<QemuSnapshotHelper>() {
+        snapshot.request_resnapshot();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        late_maps::{page_range, QemuLateMapsHelper},
+        snapshot::SNAPSHOT_PAGE_SIZE,
+        GuestAddr,
+    };
+
+    #[test]
+    fn test_late_maps_filter() {
+        let all = QemuLateMapsHelper::new();
+        assert!(all.must_follow(Some("/usr/lib/libplugin.so")));
+        assert!(all.must_follow(None));
+
+        let plugins = QemuLateMapsHelper::with_modules(vec!["libplugin".to_string()]);
+        assert!(plugins.must_follow(Some("/usr/lib/libplugin.so")));
+        assert!(!plugins.must_follow(Some("/usr/lib/libc.so.6")));
+        // Anonymous executable mappings are JITted code, not a module
+        assert!(!plugins.must_follow(None));
+    }
+
+    #[test]
+    fn test_page_range() {
+        let page = SNAPSHOT_PAGE_SIZE as GuestAddr;
+        assert_eq!(page_range(page, page), page..2 * page);
+        assert_eq!(page_range(page + 1, 1), page..2 * page);
+        assert_eq!(page_range(page - 1, 2), 0..2 * page);
+        assert_eq!(page_range(3 * page, 0), 3 * page..3 * page);
+    }
+}
@@ -41,12 +41,20 @@ pub use hooks::*;
 
 pub mod edges;
 pub use edges::QemuEdgeCoverageHelper;
+pub mod blocks;
+pub use blocks::QemuBlockCoverageHelper;
 pub mod cmplog;
 pub use cmplog::QemuCmpLogHelper;
 pub mod snapshot;
 pub use snapshot::QemuSnapshotHelper;
+pub mod late_maps;
+pub use late_maps::QemuLateMapsHelper;
 pub mod asan;
 pub use asan::{init_with_asan, QemuAsanHelper};
+pub mod plugin;
+pub use plugin::{QemuPlugin, QemuPluginHelper, QemuPluginHooks};
+pub mod taint;
+pub use taint::{QemuTaintFocusMutator, QemuTaintHelper, QemuTaintMetadata, QemuTaintStage};
 
 pub mod executor;
 pub use executor::{QemuExecutor, QemuForkExecutor};
@@ -0,0 +1,469 @@
+//! A narrow, stable interface to write [`QemuHelper`]s out of tree.
+//!
+//! Implementing [`QemuHelper`] directly requires to follow the internal generics of the hooks,
+//! that change between releases. A [`QemuPlugin`] instead only sees the [`Emulator`], the target
+//! bytes of the input, and the hooks explicitly requested with the [`QemuPluginHooks`] handle.
+//! Wrap it in a [`QemuPluginHelper`] to use it as any other helper.
+
+use core::{fmt::Debug, pin::Pin};
+
+use libafl::{
+    bolts::AsSlice,
+    inputs::{HasTargetBytes, Input},
+};
+
+use crate::{
+    emu::{Emulator, SyscallHookResult},
+    helper::{QemuHelper, QemuHelperTuple},
+    hooks::QemuHooks,
+    GuestAddr,
+};
+
+/// The version of the [`QemuPlugin`] interface.
+/// It is only bumped on breaking changes to [`QemuPlugin`] and [`QemuPluginHooks`],
+/// independently of the version of `libafl_qemu`.
+pub const QEMU_PLUGIN_API_VERSION: u32 = 1;
+
+/// The opaque handle used by a [`QemuPlugin`] to request the hooks it needs.
+/// Only the requested hooks are installed, the others cost nothing at runtime.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QemuPluginHooks {
+    blocks: bool,
+    reads: bool,
+    writes: bool,
+    syscalls: bool,
+    after_syscalls: bool,
+}
+
+impl QemuPluginHooks {
+    /// Call [`QemuPlugin::on_block`] for each executed basic block.
+    /// This installs a block generation hook, do not mix it with other helpers generating block ids.
+    pub fn blocks(&mut self) -> &mut Self {
+        self.blocks = true;
+        self
+    }
+
+    /// Call [`QemuPlugin::on_read`] for each memory read
+    pub fn reads(&mut self) -> &mut Self {
+        self.reads = true;
+        self
+    }
+
+    /// Call [`QemuPlugin::on_write`] for each memory write
+    pub fn writes(&mut self) -> &mut Self {
+        self.writes = true;
+        self
+    }
+
+    /// Call [`QemuPlugin::on_syscall`] before each syscall
+    pub fn syscalls(&mut self) -> &mut Self {
+        self.syscalls = true;
+        self
+    }
+
+    /// Call [`QemuPlugin::on_after_syscall`] after each syscall
+    pub fn after_syscalls(&mut self) -> &mut Self {
+        self.after_syscalls = true;
+        self
+    }
+}
+
+/// A QEMU helper with an interface independent of the internals of `libafl_qemu`.
+/// All methods have a default no-op implementation.
+pub trait QemuPlugin: 'static + Debug {
+    /// Request the hooks this plugin needs
+    fn register_hooks(&self, _hooks: &mut QemuPluginHooks) {}
+
+    /// Called once, before the first execution
+    fn init(&mut self, _emulator: &Emulator) {}
+
+    /// Called before each execution with the bytes of the input
+    fn pre_exec(&mut self, _emulator: &Emulator, _input: &[u8]) {}
+
+    /// Called after each execution with the bytes of the input
+    fn post_exec(&mut self, _emulator: &Emulator, _input: &[u8]) {}
+
+    /// Called for each executed basic block, if requested
+    fn on_block(&mut self, _emulator: &Emulator, _pc: GuestAddr) {}
+
+    /// Called for each memory read of `size` bytes at `addr`, if requested
+    fn on_read(&mut self, _emulator: &Emulator, _id: u64, _addr: GuestAddr, _size: usize) {}
+
+    /// Called for each memory write of `size` bytes at `addr`, if requested
+    fn on_write(&mut self, _emulator: &Emulator, _id: u64, _addr: GuestAddr, _size: usize) {}
+
+    /// Called before each syscall, if requested.
+    /// Return a value to skip the syscall, making it return this value instead.
+    fn on_syscall(&mut self, _emulator: &Emulator, _sys_num: i32, _args: &[u64; 8]) -> Option<u64> {
+        None
+    }
+
+    /// Called after each syscall, if requested. Returns the (possibly changed) result of the syscall.
+    fn on_after_syscall(
+        &mut self,
+        _emulator: &Emulator,
+        result: u64,
+        _sys_num: i32,
+        _args: &[u64; 8],
+    ) -> u64 {
+        result
+    }
+}
+
+/// The [`QemuHelper`] running a [`QemuPlugin`]
+#[derive(Debug)]
+pub struct QemuPluginHelper<P>
+where
+    P: QemuPlugin,
+{
+    plugin: P,
+    initialized: bool,
+}
+
+impl<P> QemuPluginHelper<P>
+where
+    P: QemuPlugin,
+{
+    /// Creates a new [`QemuPluginHelper`] running `plugin`
+    #[must_use]
+    pub fn new(plugin: P) -> Self {
+        Self {
+            plugin,
+            initialized: false,
+        }
+    }
+
+    /// The plugin
+    #[must_use]
+    pub fn plugin(&self) -> &P {
+        &self.plugin
+    }
+
+    /// The plugin (mutable)
+    pub fn plugin_mut(&mut self) -> &mut P {
+        &mut self.plugin
+    }
+}
+
+impl<I, S, P> QemuHelper<I, S> for QemuPluginHelper<P>
+where
+    I: Input + HasTargetBytes,
+    P: QemuPlugin,
+{
+    fn init_hooks<'a, QT>(&self, hooks: Pin<&QemuHooks<'a, I, QT, S>>)
+    where
+        QT: QemuHelperTuple<I, S>,
+    {
+        let mut requested = QemuPluginHooks::default();
+        self.plugin.register_hooks(&mut requested);
+
+        if requested.blocks {
+            hooks.block_generation(gen_plugin_block::<I, QT, S>);
+            hooks.block_execution(trace_plugin_block::<I, QT, S, P>);
+        }
+        if requested.reads {
+            hooks.read1_execution(trace_plugin_read::<I, QT, S, P, 1>);
+            hooks.read2_execution(trace_plugin_read::<I, QT, S, P, 2>);
+            hooks.read4_execution(trace_plugin_read::<I, QT, S, P, 4>);
+            hooks.read8_execution(trace_plugin_read::<I, QT, S, P, 8>);
+            hooks.read_n_execution(trace_plugin_read_n::<I, QT, S, P>);
+        }
+        if requested.writes {
+            hooks.write1_execution(trace_plugin_write::<I, QT, S, P, 1>);
+            hooks.write2_execution(trace_plugin_write::<I, QT, S, P, 2>);
+            hooks.write4_execution(trace_plugin_write::<I, QT, S, P, 4>);
+            hooks.write8_execution(trace_plugin_write::<I, QT, S, P, 8>);
+            hooks.write_n_execution(trace_plugin_write_n::<I, QT, S, P>);
+        }
+        if requested.syscalls {
+            hooks.syscalls(plugin_syscall::<I, QT, S, P>);
+        }
+        if requested.after_syscalls {
+            hooks.after_syscalls(plugin_after_syscall::<I, QT, S, P>);
+        }
+    }
+
+    fn pre_exec(&mut self, emulator: &Emulator, input: &I) {
+        if !self.initialized {
+            self.plugin.init(emulator);
+            self.initialized = true;
+        }
+        self.plugin
+            .pre_exec(emulator, input.target_bytes().as_slice());
+    }
+
+    fn post_exec(&mut self, emulator: &Emulator, input: &I) {
+        self.plugin
+            .post_exec(emulator, input.target_bytes().as_slice());
+    }
+}
+
+fn plugin_of<I, QT, S, P>(helpers: &mut QT) -> &mut P
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    &mut helpers
+        .match_first_type_mut::<QemuPluginHelper<P>>()
+        .unwrap()
+        .plugin
+}
+
+fn gen_plugin_block<I, QT, S>(
+    _emulator: &Emulator,
+    _helpers: &mut QT,
+    _state: Option<&mut S>,
+    pc: u64,
+) -> Option<u64>
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    Some(pc)
+}
+
+fn trace_plugin_block<I, QT, S, P>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    pc: u64,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    plugin_of::<I, QT, S, P>(helpers).on_block(emulator, pc as GuestAddr);
+}
+
+fn trace_plugin_read<I, QT, S, P, const N: usize>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    id: u64,
+    addr: GuestAddr,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    plugin_of::<I, QT, S, P>(helpers).on_read(emulator, id, addr, N);
+}
+
+fn trace_plugin_read_n<I, QT, S, P>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    id: u64,
+    addr: GuestAddr,
+    size: usize,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    plugin_of::<I, QT, S, P>(helpers).on_read(emulator, id, addr, size);
+}
+
+fn trace_plugin_write<I, QT, S, P, const N: usize>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    id: u64,
+    addr: GuestAddr,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    plugin_of::<I, QT, S, P>(helpers).on_write(emulator, id, addr, N);
+}
+
+fn trace_plugin_write_n<I, QT, S, P>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    id: u64,
+    addr: GuestAddr,
+    size: usize,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    plugin_of::<I, QT, S, P>(helpers).on_write(emulator, id, addr, size);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plugin_syscall<I, QT, S, P>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    sys_num: i32,
+    a0: u64,
+    a1: u64,
+    a2: u64,
+    a3: u64,
+    a4: u64,
+    a5: u64,
+    a6: u64,
+    a7: u64,
+) -> SyscallHookResult
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    SyscallHookResult::new(plugin_of::<I, QT, S, P>(helpers).on_syscall(
+        emulator,
+        sys_num,
+        &[a0, a1, a2, a3, a4, a5, a6, a7],
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plugin_after_syscall<I, QT, S, P>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    result: u64,
+    sys_num: i32,
+    a0: u64,
+    a1: u64,
+    a2: u64,
+    a3: u64,
+    a4: u64,
+    a5: u64,
+    a6: u64,
+    a7: u64,
+) -> u64
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+    P: QemuPlugin,
+{
+    plugin_of::<I, QT, S, P>(helpers).on_after_syscall(
+        emulator,
+        result,
+        sys_num,
+        &[a0, a1, a2, a3, a4, a5, a6, a7],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{bolts::tuples::tuple_list, inputs::BytesInput};
+
+    use crate::{
+        emu::Emulator,
+        helper::QemuHelper,
+        plugin::{
+            plugin_after_syscall, plugin_syscall, QemuPlugin, QemuPluginHelper, QemuPluginHooks,
+        },
+    };
+
+    /// Skips the syscall `0`, and counts the inits and executions
+    #[derive(Debug, Default)]
+    struct TestPlugin {
+        inits: usize,
+        execs: usize,
+    }
+
+    impl QemuPlugin for TestPlugin {
+        fn register_hooks(&self, hooks: &mut QemuPluginHooks) {
+            hooks.syscalls();
+        }
+
+        fn init(&mut self, _emulator: &Emulator) {
+            self.inits += 1;
+        }
+
+        fn pre_exec(&mut self, _emulator: &Emulator, input: &[u8]) {
+            assert_eq!(input, b"input");
+            self.execs += 1;
+        }
+
+        fn on_syscall(
+            &mut self,
+            _emulator: &Emulator,
+            sys_num: i32,
+            _args: &[u64; 8],
+        ) -> Option<u64> {
+            if sys_num == 0 {
+                Some(1337)
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_plugin_helper() {
+        let emu = Emulator::new_empty();
+        let input = BytesInput::new(b"input".to_vec());
+
+        let mut requested = QemuPluginHooks::default();
+        TestPlugin::default().register_hooks(&mut requested);
+        assert!(requested.syscalls);
+        assert!(!requested.blocks && !requested.reads && !requested.writes);
+
+        let mut helpers = tuple_list!(QemuPluginHelper::new(TestPlugin::default()));
+        for _ in 0..3 {
+            QemuHelper::<BytesInput, ()>::pre_exec(&mut helpers.0, &emu, &input);
+        }
+        assert_eq!(helpers.0.plugin().inits, 1);
+        assert_eq!(helpers.0.plugin().execs, 3);
+
+        let skipped = plugin_syscall::<BytesInput, _, (), TestPlugin>(
+            &emu,
+            &mut helpers,
+            None,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        );
+        assert!(skipped.skip_syscall);
+        assert_eq!(skipped.retval, 1337);
+        let run = plugin_syscall::<BytesInput, _, (), TestPlugin>(
+            &emu,
+            &mut helpers,
+            None,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        );
+        assert!(!run.skip_syscall);
+
+        // The result is left as is by default
+        assert_eq!(
+            plugin_after_syscall::<BytesInput, _, (), TestPlugin>(
+                &emu,
+                &mut helpers,
+                None,
+                42,
+                1,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ),
+            42
+        );
+    }
+}
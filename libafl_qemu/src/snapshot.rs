@@ -19,6 +19,17 @@ use crate::{
 pub const SNAPSHOT_PAGE_SIZE: usize = 4096;
 pub const SNAPSHOT_PAGE_MASK: GuestAddr = !(SNAPSHOT_PAGE_SIZE as GuestAddr - 1);
 
+/// How the [`QemuSnapshotHelper`] handles a shared (`MAP_SHARED`) guest mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedMappingPolicy {
+    /// Leave the mapping alone, writes done by the target persist across runs
+    Skip,
+    /// Snapshot and restore it like a private mapping, writing back to the underlying file or shm
+    SnapshotRestore,
+    /// Replace the mapping with a private copy of its content, so the backing object is never modified
+    RedirectToCopy,
+}
+
 #[derive(Debug)]
 pub struct SnapshotPageInfo {
     pub addr: GuestAddr,
@@ -50,6 +61,10 @@ pub struct QemuSnapshotHelper {
     pub brk: GuestAddr,
     pub mmap_start: GuestAddr,
     pub empty: bool,
+    pub shared_policies: Vec<(String, SharedMappingPolicy)>,
+    pub default_shared_policy: SharedMappingPolicy,
+    /// Take the snapshot again at the next reset, instead of restoring it
+    pub resnapshot: bool,
 }
 
 impl QemuSnapshotHelper {
@@ -62,7 +77,42 @@ impl QemuSnapshotHelper {
             brk: 0,
             mmap_start: 0,
             empty: true,
+            shared_policies: vec![],
+            default_shared_policy: SharedMappingPolicy::Skip,
+            resnapshot: false,
+        }
+    }
+
+    /// Create a snapshot helper with a policy for shared mappings whose path contains the given patterns.
+    /// Shared mappings not matching any pattern get the `default` policy.
+    #[must_use]
+    pub fn with_shared_policies(
+        policies: Vec<(String, SharedMappingPolicy)>,
+        default: SharedMappingPolicy,
+    ) -> Self {
+        Self {
+            shared_policies: policies,
+            default_shared_policy: default,
+            ..Self::new()
+        }
+    }
+
+    /// Add a policy for the shared mappings whose path contains `pattern`
+    pub fn add_shared_policy(&mut self, pattern: &str, policy: SharedMappingPolicy) {
+        self.shared_policies.push((pattern.to_string(), policy));
+    }
+
+    /// The policy for a shared mapping of the given path, the first matching pattern wins
+    #[must_use]
+    pub fn shared_policy(&self, path: Option<&str>) -> SharedMappingPolicy {
+        if let Some(path) = path {
+            for (pattern, policy) in &self.shared_policies {
+                if path.contains(pattern.as_str()) {
+                    return *policy;
+                }
+            }
         }
+        self.default_shared_policy
     }
 
     #[allow(clippy::uninit_assumed_init)]
@@ -70,16 +120,26 @@ impl QemuSnapshotHelper {
         self.brk = emulator.get_brk();
         self.mmap_start = emulator.get_mmap_start();
         self.pages.clear();
+        let mut redirects = vec![];
         for map in emulator.mappings() {
+            let policy = if map.is_priv() {
+                SharedMappingPolicy::SnapshotRestore
+            } else {
+                self.shared_policy(map.path())
+            };
+            if policy == SharedMappingPolicy::RedirectToCopy {
+                redirects.push((map.start(), (map.end() - map.start()) as usize, map.flags()));
+            }
             let mut addr = map.start();
             while addr < map.end() {
                 let mut info = SnapshotPageInfo {
                     addr,
                     perms: map.flags(),
-                    private: map.is_priv(),
+                    private: map.is_priv() || policy == SharedMappingPolicy::RedirectToCopy,
                     data: None,
                 };
-                if map.flags().is_w() {
+                // Skipped pages are still tracked, so they are not treated as new maps on reset
+                if map.flags().is_w() && policy != SharedMappingPolicy::Skip {
                     unsafe {
                         info.data = Some(Box::new(core::mem::MaybeUninit::uninit().assume_init()));
                         emulator.read_mem(addr, &mut info.data.as_mut().unwrap()[..]);
@@ -89,9 +149,31 @@ impl QemuSnapshotHelper {
                 addr += SNAPSHOT_PAGE_SIZE as GuestAddr;
             }
         }
+        for (start, size, perms) in redirects {
+            Self::redirect_to_copy(emulator, start, size, perms);
+        }
         self.empty = false;
     }
 
+    /// Overlay a shared mapping with a private anonymous mapping holding a copy of its content
+    fn redirect_to_copy(emulator: &Emulator, start: GuestAddr, size: usize, perms: MmapPerms) {
+        let mut content = vec![0; size];
+        unsafe { emulator.read_mem(start, &mut content) };
+        emulator
+            .map_fixed(start, size, MmapPerms::ReadWrite)
+            .expect("Failed to overlay a shared mapping with a private copy");
+        unsafe { emulator.write_mem(start, &content) };
+        if perms != MmapPerms::ReadWrite {
+            drop(emulator.mprotect(start, size, perms));
+        }
+    }
+
+    /// Take the snapshot again at the next reset, keeping the current state of the target,
+    /// e.g. the libraries loaded in the last execution
+    pub fn request_resnapshot(&mut self) {
+        self.resnapshot = true;
+    }
+
     pub fn page_access(&mut self, page: GuestAddr) {
         unsafe {
             let acc = self.accesses.get_or_default().get();
@@ -209,6 +291,14 @@ where
     fn pre_exec(&mut self, emulator: &Emulator, _input: &I) {
         if self.empty {
             self.snapshot(emulator);
+        } else if self.resnapshot {
+            // The current state becomes the snapshot, new maps and dirty pages included
+            *self.new_maps.get_mut().unwrap() = IntervalTree::new();
+            for acc in self.accesses.iter_mut() {
+                unsafe { (*acc.get()).clear() };
+            }
+            self.resnapshot = false;
+            self.snapshot(emulator);
         } else {
             self.reset(emulator);
         }
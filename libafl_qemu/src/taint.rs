@@ -0,0 +1,668 @@
+//! Lightweight byte-level taint tracking, to find the input offsets reaching comparisons.
+//!
+//! The bytes of the input are tainted with their offset where the harness writes the input in
+//! guest memory. Taint follows memory: a store right after a tainted load of the same size copies
+//! its taint, as in `memcpy`-like loops, any other store clears it. A comparison of a value equal
+//! to a recently loaded tainted value marks the offsets of that value as hot.
+//!
+//! Registers are not tracked, so this is an approximation: values computed from the input, such as
+//! parsed integers or checksums, do not reach the comparisons, and an unrelated operand equal to a
+//! recently loaded tainted value marks its offsets as hot anyway.
+//! The [`QemuTaintStage`] stores the hot offsets of each testcase in a [`QemuTaintMetadata`],
+//! once, and the [`QemuTaintFocusMutator`] concentrates on them.
+
+use core::{marker::PhantomData, pin::Pin};
+use std::collections::{BTreeSet, VecDeque};
+
+use hashbrown::HashMap;
+use libafl::{
+    bolts::{
+        rands::Rand,
+        tuples::{MatchFirstType, Named},
+        AsSlice,
+    },
+    corpus::Corpus,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasBytesVec, HasTargetBytes, Input},
+    mutators::{MutationResult, Mutator},
+    observers::ObserversTuple,
+    stages::Stage,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    emu::Emulator,
+    executor::QemuExecutor,
+    helper::{QemuHelper, QemuHelperTuple, QemuInstrumentationFilter},
+    hooks::QemuHooks,
+    GuestAddr,
+};
+
+/// How many tainted loads are kept to be matched against the operands of comparisons
+pub const TAINT_LOADS_WINDOW: usize = 16;
+
+/// The size of the pages of the shadow memory
+const SHADOW_PAGE_SIZE: usize = 4096;
+
+/// A byte of the shadow memory not tainted by the input
+const UNTAINTED: u32 = u32::MAX;
+
+/// The input offsets of a testcase reaching comparisons
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct QemuTaintMetadata {
+    /// The offsets, sorted
+    pub offsets: Vec<usize>,
+}
+
+libafl::impl_serdeany!(QemuTaintMetadata);
+
+#[derive(Debug, Clone, Copy)]
+struct TaintedLoad {
+    value: u64,
+    size: usize,
+    offsets: [Option<usize>; 8],
+}
+
+/// The input offset of each tainted guest byte, by pages.
+/// The accesses out of the bounds of the tainted memory are rejected without a lookup.
+#[derive(Debug, Default)]
+struct ShadowMemory {
+    pages: HashMap<GuestAddr, Box<[u32; SHADOW_PAGE_SIZE]>>,
+    /// The tainted memory is within `start..end`
+    start: GuestAddr,
+    end: GuestAddr,
+}
+
+impl ShadowMemory {
+    fn page_of(addr: GuestAddr) -> (GuestAddr, usize) {
+        let page_size = SHADOW_PAGE_SIZE as GuestAddr;
+        (addr - addr % page_size, (addr % page_size) as usize)
+    }
+
+    fn clear(&mut self) {
+        self.pages.clear();
+        self.start = 0;
+        self.end = 0;
+    }
+
+    fn may_be_tainted(&self, addr: GuestAddr, size: usize) -> bool {
+        addr < self.end && addr.saturating_add(size as GuestAddr) > self.start
+    }
+
+    fn extend_bounds(&mut self, start: GuestAddr, end: GuestAddr) {
+        if self.start == self.end {
+            self.start = start;
+            self.end = end;
+        } else {
+            self.start = self.start.min(start);
+            self.end = self.end.max(end);
+        }
+    }
+
+    /// Taints the `len` bytes at `addr` with the offsets `0..len`
+    #[allow(clippy::cast_possible_truncation)]
+    fn taint_range(&mut self, addr: GuestAddr, len: usize) {
+        let mut off = 0;
+        while off < len {
+            let (base, first) = Self::page_of(addr + off as GuestAddr);
+            let count = (SHADOW_PAGE_SIZE - first).min(len - off);
+            let page = self
+                .pages
+                .entry(base)
+                .or_insert_with(|| Box::new([UNTAINTED; SHADOW_PAGE_SIZE]));
+            for (i, byte) in page[first..first + count].iter_mut().enumerate() {
+                *byte = (off + i) as u32;
+            }
+            off += count;
+        }
+        if len > 0 {
+            self.extend_bounds(addr, addr + len as GuestAddr);
+        }
+    }
+
+    /// The input offsets of the `size` bytes at `addr`, up to 8
+    fn get(&self, addr: GuestAddr, size: usize) -> [Option<usize>; 8] {
+        let mut offsets = [None; 8];
+        if !self.may_be_tainted(addr, size) {
+            return offsets;
+        }
+        let mut page = None;
+        for (i, off) in offsets.iter_mut().enumerate().take(size) {
+            let (base, idx) = Self::page_of(addr + i as GuestAddr);
+            if idx == 0 || page.is_none() {
+                page = Some(self.pages.get(&base));
+            }
+            if let Some(Some(page)) = page {
+                if page[idx] != UNTAINTED {
+                    *off = Some(page[idx] as usize);
+                }
+            }
+        }
+        offsets
+    }
+
+    /// Taints the byte at `addr` with the input offset `offset`, or clears it
+    #[allow(clippy::cast_possible_truncation)]
+    fn set(&mut self, addr: GuestAddr, offset: Option<usize>) {
+        let (base, idx) = Self::page_of(addr);
+        match offset {
+            Some(offset) => {
+                self.pages
+                    .entry(base)
+                    .or_insert_with(|| Box::new([UNTAINTED; SHADOW_PAGE_SIZE]))[idx] =
+                    offset as u32;
+                self.extend_bounds(addr, addr + 1);
+            }
+            None => {
+                if self.may_be_tainted(addr, 1) {
+                    if let Some(page) = self.pages.get_mut(&base) {
+                        page[idx] = UNTAINTED;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A helper tracking the input offsets reaching comparisons, see the [module docs](self)
+#[derive(Debug)]
+pub struct QemuTaintHelper {
+    input_addr: GuestAddr,
+    max_len: usize,
+    filter: QemuInstrumentationFilter,
+    shadow: ShadowMemory,
+    loads: VecDeque<TaintedLoad>,
+    /// The last load, if it was tainted and no store followed it yet
+    last_load: Option<TaintedLoad>,
+    hot: BTreeSet<usize>,
+}
+
+impl QemuTaintHelper {
+    /// The harness writes the input, at most `max_len` bytes, at `input_addr` in guest memory
+    #[must_use]
+    pub fn new(input_addr: GuestAddr, max_len: usize, filter: QemuInstrumentationFilter) -> Self {
+        Self {
+            input_addr,
+            max_len: max_len.min(UNTAINTED as usize),
+            filter,
+            shadow: ShadowMemory::default(),
+            loads: VecDeque::with_capacity(TAINT_LOADS_WINDOW),
+            last_load: None,
+            hot: BTreeSet::new(),
+        }
+    }
+
+    /// If the comparisons at `addr` are traced
+    #[must_use]
+    pub fn must_instrument(&self, addr: u64) -> bool {
+        self.filter.allowed(addr)
+    }
+
+    /// The offsets found reaching comparisons in the last execution, sorted
+    #[must_use]
+    pub fn hot_offsets(&self) -> Vec<usize> {
+        self.hot.iter().copied().collect()
+    }
+
+    fn on_read(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
+        let size = size.min(8);
+        let offsets = self.shadow.get(addr, size);
+        let mut buf = [0; 8];
+        if offsets.iter().any(Option::is_some) {
+            unsafe { emulator.read_mem(addr, &mut buf[..size]) };
+        }
+        self.record_load(u64::from_le_bytes(buf), size, offsets);
+    }
+
+    fn record_load(&mut self, value: u64, size: usize, offsets: [Option<usize>; 8]) {
+        // A store after an untainted load copies nothing tainted
+        if offsets.iter().all(Option::is_none) {
+            self.last_load = None;
+            return;
+        }
+        let load = TaintedLoad {
+            value,
+            size,
+            offsets,
+        };
+        if self.loads.len() == TAINT_LOADS_WINDOW {
+            self.loads.pop_front();
+        }
+        self.loads.push_back(load);
+        self.last_load = Some(load);
+    }
+
+    fn on_write(&mut self, addr: GuestAddr, size: usize) {
+        // A store copies the load right before it, at most once
+        let copied = self.last_load.take().filter(|load| load.size == size);
+        if copied.is_none() && !self.shadow.may_be_tainted(addr, size) {
+            return;
+        }
+        for i in 0..size {
+            let offset = copied.and_then(|load| load.offsets.get(i).copied().flatten());
+            self.shadow.set(addr + i as GuestAddr, offset);
+        }
+    }
+
+    fn on_cmp(&mut self, size: usize, v0: u64, v1: u64) {
+        for load in &self.loads {
+            // Narrower loads may have been extended before the comparison
+            if load.size <= size && (load.value == v0 || load.value == v1) {
+                self.hot.extend(load.offsets.iter().flatten());
+            }
+        }
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuTaintHelper
+where
+    I: Input + HasTargetBytes,
+{
+    fn init_hooks<'a, QT>(&self, hooks: Pin<&QemuHooks<'a, I, QT, S>>)
+    where
+        QT: QemuHelperTuple<I, S>,
+    {
+        hooks.read1_execution(trace_taint_read::<I, QT, S, 1>);
+        hooks.read2_execution(trace_taint_read::<I, QT, S, 2>);
+        hooks.read4_execution(trace_taint_read::<I, QT, S, 4>);
+        hooks.read8_execution(trace_taint_read::<I, QT, S, 8>);
+        hooks.read_n_execution(trace_taint_read_n::<I, QT, S>);
+        hooks.write1_execution(trace_taint_write::<I, QT, S, 1>);
+        hooks.write2_execution(trace_taint_write::<I, QT, S, 2>);
+        hooks.write4_execution(trace_taint_write::<I, QT, S, 4>);
+        hooks.write8_execution(trace_taint_write::<I, QT, S, 8>);
+        hooks.write_n_execution(trace_taint_write_n::<I, QT, S>);
+        // This replaces the cmp generation hook of QemuCmpLogHelper, use it in another executor
+        hooks.cmp_generation(gen_taint_cmp_ids::<I, QT, S>);
+        hooks.cmp1_execution(trace_taint_cmp1::<I, QT, S>);
+        hooks.cmp2_execution(trace_taint_cmp2::<I, QT, S>);
+        hooks.cmp4_execution(trace_taint_cmp4::<I, QT, S>);
+        hooks.cmp8_execution(trace_taint_cmp8::<I, QT, S>);
+    }
+
+    fn pre_exec(&mut self, _emulator: &Emulator, input: &I) {
+        self.shadow.clear();
+        self.loads.clear();
+        self.last_load = None;
+        self.hot.clear();
+        let len = input.target_bytes().as_slice().len().min(self.max_len);
+        self.shadow.taint_range(self.input_addr, len);
+    }
+}
+
+pub fn trace_taint_read<I, QT, S, const N: usize>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    addr: GuestAddr,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_read(emulator, addr, N);
+    }
+}
+
+pub fn trace_taint_read_n<I, QT, S>(
+    emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    addr: GuestAddr,
+    size: usize,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_read(emulator, addr, size);
+    }
+}
+
+pub fn trace_taint_write<I, QT, S, const N: usize>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    addr: GuestAddr,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_write(addr, N);
+    }
+}
+
+pub fn trace_taint_write_n<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    addr: GuestAddr,
+    size: usize,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_write(addr, size);
+    }
+}
+
+pub fn gen_taint_cmp_ids<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    pc: u64,
+    _size: usize,
+) -> Option<u64>
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type::<QemuTaintHelper>() {
+        if !h.must_instrument(pc) {
+            return None;
+        }
+    }
+    Some(pc)
+}
+
+pub fn trace_taint_cmp1<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    v0: u8,
+    v1: u8,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_cmp(1, u64::from(v0), u64::from(v1));
+    }
+}
+
+pub fn trace_taint_cmp2<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    v0: u16,
+    v1: u16,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_cmp(2, u64::from(v0), u64::from(v1));
+    }
+}
+
+pub fn trace_taint_cmp4<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    v0: u32,
+    v1: u32,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_cmp(4, u64::from(v0), u64::from(v1));
+    }
+}
+
+pub fn trace_taint_cmp8<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: Option<&mut S>,
+    _id: u64,
+    v0: u64,
+    v1: u64,
+) where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type_mut::<QemuTaintHelper>() {
+        h.on_cmp(8, v0, v1);
+    }
+}
+
+/// A stage running the testcases without a [`QemuTaintMetadata`] in a [`QemuExecutor`] with a
+/// [`QemuTaintHelper`], and storing the hot offsets found
+pub struct QemuTaintStage<'a, EM, H, I, OT, QT, S, Z>
+where
+    H: FnMut(&I) -> ExitKind,
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+{
+    tracer_executor: QemuExecutor<'a, H, I, OT, QT, S>,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(EM, I, OT, S, Z)>,
+}
+
+impl<'a, EM, H, I, OT, QT, S, Z> core::fmt::Debug for QemuTaintStage<'a, EM, H, I, OT, QT, S, Z>
+where
+    H: FnMut(&I) -> ExitKind,
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("QemuTaintStage")
+            .field("tracer_executor", &self.tracer_executor)
+            .finish()
+    }
+}
+
+impl<'a, EM, H, I, OT, QT, S, Z> QemuTaintStage<'a, EM, H, I, OT, QT, S, Z>
+where
+    H: FnMut(&I) -> ExitKind,
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+{
+    /// The helpers of `tracer_executor` must include a [`QemuTaintHelper`]
+    pub fn new(tracer_executor: QemuExecutor<'a, H, I, OT, QT, S>) -> Self {
+        Self {
+            tracer_executor,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The executor tracing the taint
+    pub fn executor(&self) -> &QemuExecutor<'a, H, I, OT, QT, S> {
+        &self.tracer_executor
+    }
+}
+
+impl<'a, E, EM, H, I, OT, QT, S, Z> Stage<E, EM, S, Z>
+    for QemuTaintStage<'a, EM, H, I, OT, QT, S, Z>
+where
+    H: FnMut(&I) -> ExitKind,
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+    QT: QemuHelperTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let input = {
+            let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+            if testcase.has_metadata::<QemuTaintMetadata>() {
+                return Ok(());
+            }
+            testcase.load_input()?.clone()
+        };
+
+        self.tracer_executor
+            .observers_mut()
+            .pre_exec_all(state, &input)?;
+        let exit_kind = self
+            .tracer_executor
+            .run_target(fuzzer, state, manager, &input)?;
+        *state.executions_mut() += 1;
+        self.tracer_executor
+            .observers_mut()
+            .post_exec_all(state, &input, &exit_kind)?;
+
+        let offsets = self
+            .tracer_executor
+            .hooks()
+            .helpers()
+            .match_first_type::<QemuTaintHelper>()
+            .ok_or_else(|| Error::IllegalArgument("QemuTaintStage needs a QemuTaintHelper".into()))?
+            .hot_offsets();
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(QemuTaintMetadata { offsets });
+        Ok(())
+    }
+}
+
+/// Mutates one of the hot offsets, in the [`QemuTaintMetadata`] of the current testcase
+#[derive(Default, Debug)]
+pub struct QemuTaintFocusMutator;
+
+impl<I, S> Mutator<I, S> for QemuTaintFocusMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasCorpus<I>,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let idx = match *state.corpus().current() {
+            Some(idx) => idx,
+            None => return Ok(MutationResult::Skipped),
+        };
+        let len = input.bytes().len();
+        let offsets: Vec<usize> = match state
+            .corpus()
+            .get(idx)?
+            .borrow()
+            .metadata()
+            .get::<QemuTaintMetadata>()
+        {
+            Some(meta) => meta.offsets.iter().copied().filter(|o| *o < len).collect(),
+            None => return Ok(MutationResult::Skipped),
+        };
+        if offsets.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let off = *state.rand_mut().choose(&offsets);
+        let byte = &mut input.bytes_mut()[off];
+        *byte = match state.rand_mut().below(3) {
+            0 => state.rand_mut().below(256) as u8,
+            1 => byte.wrapping_add(1 + state.rand_mut().below(35) as u8),
+            _ => byte.wrapping_sub(1 + state.rand_mut().below(35) as u8),
+        };
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for QemuTaintFocusMutator {
+    fn name(&self) -> &str {
+        "QemuTaintFocusMutator"
+    }
+}
+
+impl QemuTaintFocusMutator {
+    /// Creates a new [`QemuTaintFocusMutator`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        helper::QemuInstrumentationFilter,
+        taint::{QemuTaintHelper, ShadowMemory, SHADOW_PAGE_SIZE},
+        GuestAddr,
+    };
+
+    #[test]
+    fn test_shadow_memory() {
+        let page = SHADOW_PAGE_SIZE as GuestAddr;
+        let mut shadow = ShadowMemory::default();
+        assert!(!shadow.may_be_tainted(0, 8));
+
+        // Across a page boundary
+        shadow.taint_range(2 * page - 2, 4);
+        assert_eq!(
+            shadow.get(2 * page - 3, 6),
+            [None, Some(0), Some(1), Some(2), Some(3), None, None, None]
+        );
+        assert!(!shadow.may_be_tainted(0, 8));
+        assert!(!shadow.may_be_tainted(2 * page + 2, 8));
+
+        shadow.set(2 * page, None);
+        shadow.set(4 * page, Some(7));
+        assert_eq!(shadow.get(2 * page, 1)[0], None);
+        assert_eq!(shadow.get(4 * page, 1)[0], Some(7));
+        assert!(shadow.may_be_tainted(3 * page, 1));
+
+        shadow.clear();
+        assert_eq!(shadow.get(4 * page, 1)[0], None);
+    }
+
+    #[test]
+    fn test_taint_propagation() {
+        let page = SHADOW_PAGE_SIZE as GuestAddr;
+        let mut helper = QemuTaintHelper::new(page, 16, QemuInstrumentationFilter::None);
+        helper.shadow.taint_range(page, 16);
+
+        // A 2 bytes load of the offsets 4 and 5, stored elsewhere, then compared
+        let offsets = helper.shadow.get(page + 4, 2);
+        helper.record_load(0x4241, 2, offsets);
+        helper.on_write(3 * page, 2);
+        assert_eq!(helper.shadow.get(3 * page, 2)[..2], [Some(4), Some(5)]);
+        helper.on_cmp(4, 0x4241, 0x1337);
+        assert_eq!(helper.hot_offsets(), vec![4, 5]);
+
+        // A store of another size clears the taint
+        helper.record_load(0x4241, 2, offsets);
+        helper.on_write(3 * page, 1);
+        assert_eq!(helper.shadow.get(3 * page, 2)[..2], [None, Some(5)]);
+
+        // A second store does not copy the load again
+        helper.on_write(5 * page, 2);
+        assert_eq!(helper.shadow.get(5 * page, 2)[..2], [None, None]);
+
+        // A store after an untainted load copies nothing tainted
+        helper.record_load(0x4241, 2, offsets);
+        helper.record_load(0x1337, 2, [None; 8]);
+        helper.on_write(3 * page, 2);
+        assert_eq!(helper.shadow.get(3 * page, 2)[..2], [None, None]);
+
+        // Comparisons of other values, or narrower than the load, mark nothing
+        helper.hot.clear();
+        helper.on_cmp(8, 1, 2);
+        helper.on_cmp(1, 0x4241, 0);
+        assert!(helper.hot_offsets().is_empty());
+    }
+}
@@ -30,7 +30,7 @@ use libafl::{
     Error,
 };
 
-use crate::{CORPUS_CACHE_SIZE, DEFAULT_TIMEOUT_SECS};
+use crate::{objective_command_hook, SugarMonitor, CORPUS_CACHE_SIZE, DEFAULT_TIMEOUT_SECS};
 
 /// The default coverage map size to use for forkserver targets
 pub const DEFAULT_MAP_SIZE: usize = 65536;
@@ -75,6 +75,18 @@ pub struct ForkserverBytesCoverageSugar<'a, const MAP_SIZE: usize> {
     /// Fuzz `iterations` number of times, instead of indefinitely; implies use of `fuzz_loop_for`
     #[builder(default = None)]
     iterations: Option<u64>,
+    /// Keep the output of each client in `<output_dir>/logs`, instead of discarding it
+    #[builder(default = false)]
+    client_logs: bool,
+    /// Respawn the clients that died, instead of letting their cores idle
+    #[builder(default = false)]
+    respawn_clients: bool,
+    /// A command to run on each new objective, with the path of the testcase as last argument
+    #[builder(default = None)]
+    objective_command: Option<&'a [String]>,
+    /// Write AFL-compatible plot data to `<output_dir>/stats`, for `afl-plot` and `afl-whatsup`
+    #[builder(default = false)]
+    plot_data: bool,
 }
 
 #[allow(clippy::similar_names)]
@@ -109,7 +121,11 @@ impl<'a, const MAP_SIZE: usize> ForkserverBytesCoverageSugar<'a, MAP_SIZE> {
         let shmem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
         let mut shmem_provider_client = shmem_provider.clone();
 
-        let monitor = MultiMonitor::new(|s| println!("{}", s));
+        let monitor = SugarMonitor::new(
+            MultiMonitor::new(|s| println!("{}", s)),
+            self.plot_data.then(|| self.output_dir.join("stats")),
+        )
+        .expect("Failed to create the stats directory");
 
         let mut run_client = |state: Option<StdState<_, _, _, _, _>>,
                               mut mgr: LlmpRestartingEventManager<_, _, _, _>,
@@ -173,6 +189,9 @@ impl<'a, const MAP_SIZE: usize> ForkserverBytesCoverageSugar<'a, MAP_SIZE> {
 
             // A fuzzer with feedbacks and a corpus scheduler
             let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+            if let Some(hook) = self.objective_command.and_then(objective_command_hook) {
+                fuzzer = fuzzer.with_objective_hook(hook);
+            }
 
             let forkserver = if self.shmem_testcase {
                 ForkserverExecutorBuilder::new()
@@ -275,6 +294,11 @@ impl<'a, const MAP_SIZE: usize> ForkserverBytesCoverageSugar<'a, MAP_SIZE> {
             Ok(())
         };
 
+        let logs_dir = self.output_dir.join("logs");
+        if self.client_logs {
+            fs::create_dir_all(&logs_dir).expect("Failed to create the logs directory");
+        }
+
         let launcher = Launcher::builder()
             .shmem_provider(shmem_provider)
             .configuration(conf)
@@ -282,9 +306,12 @@ impl<'a, const MAP_SIZE: usize> ForkserverBytesCoverageSugar<'a, MAP_SIZE> {
             .run_client(&mut run_client)
             .cores(self.cores)
             .broker_port(self.broker_port)
-            .remote_broker_addr(self.remote_broker_addr);
+            .remote_broker_addr(self.remote_broker_addr)
+            .stdout_dir(self.client_logs.then(|| logs_dir.as_path()))
+            .respawn_clients(self.respawn_clients);
         #[cfg(unix)]
         let launcher = launcher.stdout_file(Some("/dev/null"));
+
         match launcher.build().launch() {
             Ok(()) => (),
             Err(Error::ShuttingDown) => println!("\nFuzzing stopped by user. Good Bye."),
@@ -57,6 +57,15 @@
     )
 )]
 
+use core::time::Duration;
+use std::path::PathBuf;
+
+use libafl::{
+    fuzzer::CommandObjectiveHook,
+    monitors::{AflPlotMonitor, ClientStats, Monitor},
+    Error,
+};
+
 pub mod inmemory;
 pub use inmemory::InMemoryBytesCoverageSugar;
 
@@ -76,6 +85,69 @@ pub const DEFAULT_TIMEOUT_SECS: u64 = 1200;
 /// Anything else will be on disk.
 pub const CORPUS_CACHE_SIZE: usize = 4096;
 
+/// The hook running the `objective_command` of a sugar, the first element being the program
+fn objective_command_hook(command: &[String]) -> Option<CommandObjectiveHook> {
+    let (program, args) = command.split_first()?;
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    Some(CommandObjectiveHook::new(program, &args))
+}
+
+/// The monitor of a sugar: the `base` monitor, writing AFL-compatible plot data if asked to
+#[derive(Debug, Clone)]
+enum SugarMonitor<M>
+where
+    M: Monitor,
+{
+    Plain(M),
+    Plot(AflPlotMonitor<M>),
+}
+
+impl<M> SugarMonitor<M>
+where
+    M: Monitor,
+{
+    /// Wraps `base`, writing the plot data to `plot_dir`, if any
+    fn new(base: M, plot_dir: Option<PathBuf>) -> Result<Self, Error> {
+        Ok(match plot_dir {
+            Some(dir) => Self::Plot(AflPlotMonitor::new(base, dir)?),
+            None => Self::Plain(base),
+        })
+    }
+}
+
+impl<M> Monitor for SugarMonitor<M>
+where
+    M: Monitor,
+{
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        match self {
+            Self::Plain(monitor) => monitor.client_stats_mut(),
+            Self::Plot(monitor) => monitor.client_stats_mut(),
+        }
+    }
+
+    fn client_stats(&self) -> &[ClientStats] {
+        match self {
+            Self::Plain(monitor) => monitor.client_stats(),
+            Self::Plot(monitor) => monitor.client_stats(),
+        }
+    }
+
+    fn start_time(&mut self) -> Duration {
+        match self {
+            Self::Plain(monitor) => monitor.start_time(),
+            Self::Plot(monitor) => monitor.start_time(),
+        }
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        match self {
+            Self::Plain(monitor) => monitor.display(event_msg, sender_id),
+            Self::Plot(monitor) => monitor.display(event_msg, sender_id),
+        }
+    }
+}
+
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 
@@ -37,7 +37,7 @@ use libafl_qemu::{
 };
 use libafl_targets::CmpLogObserver;
 
-use crate::{CORPUS_CACHE_SIZE, DEFAULT_TIMEOUT_SECS};
+use crate::{objective_command_hook, SugarMonitor, CORPUS_CACHE_SIZE, DEFAULT_TIMEOUT_SECS};
 
 /// Sugar to create a `libfuzzer`-style fuzzer that uses
 /// `QEMU`-based binary-only instrumentation
@@ -78,6 +78,19 @@ where
     /// Fuzz `iterations` number of times, instead of indefinitely; implies use of `fuzz_loop_for`
     #[builder(default = None)]
     iterations: Option<u64>,
+    /// Keep the output of each client in `<output_dir>/logs`, instead of discarding it
+    #[builder(default = false)]
+    client_logs: bool,
+    /// Respawn the clients that died, instead of letting their cores idle
+    #[builder(default = false)]
+    respawn_clients: bool,
+    /// A command to run on each new objective, with the path of the testcase as last argument.
+    /// It also runs for the crashes and timeouts caught by the in-process executors.
+    #[builder(default = None)]
+    objective_command: Option<&'a [String]>,
+    /// Write AFL-compatible plot data to `<output_dir>/stats`, for `afl-plot` and `afl-whatsup`
+    #[builder(default = false)]
+    plot_data: bool,
 }
 
 impl<'a, H> Debug for QemuBytesCoverageSugar<'a, H>
@@ -95,6 +108,10 @@ where
             .field("broker_port", &self.broker_port)
             .field("cores", &self.cores)
             .field("remote_broker_addr", &self.remote_broker_addr)
+            .field("client_logs", &self.client_logs)
+            .field("respawn_clients", &self.respawn_clients)
+            .field("objective_command", &self.objective_command)
+            .field("plot_data", &self.plot_data)
             .field(
                 "harness",
                 if self.harness.is_some() {
@@ -139,7 +156,11 @@ where
 
         let shmem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
 
-        let monitor = MultiMonitor::new(|s| println!("{}", s));
+        let monitor = SugarMonitor::new(
+            MultiMonitor::new(|s| println!("{}", s)),
+            self.plot_data.then(|| self.output_dir.join("stats")),
+        )
+        .expect("Failed to create the stats directory");
 
         let mut run_client = |state: Option<StdState<_, _, _, _, _>>,
                               mut mgr: LlmpRestartingEventManager<_, _, _, _>,
@@ -200,6 +221,9 @@ where
 
             // A fuzzer with feedbacks and a corpus scheduler
             let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+            if let Some(hook) = self.objective_command.and_then(objective_command_hook) {
+                fuzzer = fuzzer.with_objective_hook(hook);
+            }
 
             // The wrapped harness function, calling out to the LLVM-style harness
             let mut harness = |input: &BytesInput| {
@@ -417,6 +441,11 @@ where
             Ok(())
         };
 
+        let logs_dir = self.output_dir.join("logs");
+        if self.client_logs {
+            fs::create_dir_all(&logs_dir).expect("Failed to create the logs directory");
+        }
+
         let launcher = Launcher::builder()
             .shmem_provider(shmem_provider)
             .configuration(conf)
@@ -424,9 +453,12 @@ where
             .run_client(&mut run_client)
             .cores(self.cores)
             .broker_port(self.broker_port)
-            .remote_broker_addr(self.remote_broker_addr);
+            .remote_broker_addr(self.remote_broker_addr)
+            .stdout_dir(self.client_logs.then(|| logs_dir.as_path()))
+            .respawn_clients(self.respawn_clients);
         #[cfg(unix)]
         let launcher = launcher.stdout_file(Some("/dev/null"));
+
         launcher.build().launch().expect("Launcher failed");
     }
 }
@@ -109,6 +109,16 @@ fn main() {
         .define("ACCOUNTING_MAP_SIZE", Some(&*format!("{}", acc_map_size)))
         .compile("coverage");
 
+    #[cfg(feature = "sancov_stack_depth")]
+    {
+        println!("cargo:rerun-if-changed=src/resources.c");
+
+        cc::Build::new()
+            .define("SANCOV_STACK_DEPTH", "1")
+            .file(src_dir.join("resources.c"))
+            .compile("resources");
+    }
+
     println!("cargo:rerun-if-changed=src/cmplog.h");
     println!("cargo:rerun-if-changed=src/cmplog.c");
 
@@ -118,6 +128,15 @@ fn main() {
         .file(src_dir.join("cmplog.c"))
         .compile("cmplog");
 
+    // COFF has no `__start_`/`__stop_` symbols, the sancov sections get explicit delimiters
+    if env::var("CARGO_CFG_TARGET_OS").map_or(false, |os| os == "windows") {
+        println!("cargo:rerun-if-changed=src/sancov_win_sections.c");
+
+        cc::Build::new()
+            .file(src_dir.join("sancov_win_sections.c"))
+            .compile("sancov_win_sections");
+    }
+
     println!("cargo:rustc-link-search=native={}", &out_dir);
 
     println!("cargo:rerun-if-changed=build.rs");
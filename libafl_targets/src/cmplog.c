@@ -8,6 +8,10 @@
 
 #include <windows.h>
 
+#if defined(_MSC_VER)
+
+void *__asan_region_is_poisoned(void *beg, size_t size);
+
 void *__libafl_asan_region_is_poisoned(void *beg, size_t size) {
 
   (void)beg;
@@ -16,7 +20,20 @@ void *__libafl_asan_region_is_poisoned(void *beg, size_t size) {
 
 }
 
-#pragma comment(linker, "/alternatename:__asan_region_is_poisoned=__libafl_asan_region_is_poisoned")
+#pragma comment(linker, "/alternatename:" WIN_SYM_PREFIX "__asan_region_is_poisoned=" WIN_SYM_PREFIX "__libafl_asan_region_is_poisoned")
+
+#else
+
+// The windows-gnu linkers know no /alternatename, but weak symbols
+__attribute__((weak)) void *__asan_region_is_poisoned(void *beg, size_t size) {
+
+  (void)beg;
+  (void)size;
+  return NULL;
+
+}
+
+#endif
 
 #elif defined(__unix__) || (defined(__APPLE__) && defined(__MACH__))
 
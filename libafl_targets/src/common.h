@@ -39,11 +39,18 @@
   #define MAYBE_THREAD_LOCAL
 #endif
 
-#ifdef _WIN32
+#ifdef _MSC_VER
+  #include <intrin.h>
   #define RETADDR (uintptr_t)_ReturnAddress()
-  #define EXPORT_FN __declspec(dllexport)
+  #define FRAMEADDR (uintptr_t)_AddressOfReturnAddress()
 #else
   #define RETADDR (uintptr_t)__builtin_return_address(0)
+  #define FRAMEADDR (uintptr_t)__builtin_frame_address(0)
+#endif
+
+#ifdef _WIN32
+  #define EXPORT_FN __declspec(dllexport)
+#else
   #define EXPORT_FN
 #endif
 
@@ -1,5 +1,7 @@
 #include "common.h"
 
+#include <string.h>
+
 typedef uint32_t prev_loc_t;
 
 /* Maximum ngram size */
@@ -29,3 +31,11 @@ MAYBE_THREAD_LOCAL prev_loc_t __afl_prev_loc[NGRAM_SIZE_MAX];
 MAYBE_THREAD_LOCAL prev_loc_t __afl_prev_caller[CTX_MAX_K];
 MAYBE_THREAD_LOCAL uint32_t   __afl_prev_ctx;
 MAYBE_THREAD_LOCAL prev_loc_t __afl_acc_prev_loc;
+
+// Resets the path state of the context sensitive and ngram coverage of the AFL pass,
+// so each execution starts from the same context
+EXPORT_FN void libafl_targets_reset_prev_loc(void) {
+  memset(__afl_prev_loc, 0, sizeof(__afl_prev_loc));
+  memset(__afl_prev_caller, 0, sizeof(__afl_prev_caller));
+  __afl_prev_ctx = 0;
+}
@@ -31,6 +31,8 @@ extern "C" {
     /// End of libafl token section
     #[cfg(target_os = "linux")]
     pub static __token_stop: *const u8;
+
+    fn libafl_targets_reset_prev_loc();
 }
 pub use __afl_acc_memop_ptr as ACCOUNTING_MEMOP_MAP_PTR;
 pub use __afl_area_ptr as EDGES_MAP_PTR;
@@ -57,6 +59,19 @@ pub fn autotokens() -> Result<Tokens, Error> {
     }
 }
 
+/// The max size of the ngrams of the AFL pass (`-ngram`)
+pub const NGRAM_SIZE_MAX: usize = 16;
+
+/// The max size of the context of the AFL pass (`-ctx_k`)
+pub const CTX_MAX_K: usize = 32;
+
+/// Resets the previous locations and the calling context tracked by the context sensitive (`-ctx`, `-ctx_k`)
+/// and ngram (`-ngram`) coverage of the AFL pass.
+/// Call it before each execution, else the first edges of a run depend on where the previous run ended.
+pub fn reset_prev_loc() {
+    unsafe { libafl_targets_reset_prev_loc() }
+}
+
 /// The size of the map for edges.
 #[no_mangle]
 pub static mut __afl_map_size: usize = EDGES_MAP_SIZE;
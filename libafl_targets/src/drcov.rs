@@ -6,6 +6,7 @@ use core::ptr::addr_of;
 use libafl::Error;
 use rangemap::RangeMap;
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{BufWriter, Write},
     path::Path,
@@ -66,39 +67,53 @@ impl<'a> DrCovWriter<'a> {
             .write_all(b"DRCOV VERSION: 2\nDRCOV FLAVOR: libafl\n")
             .unwrap();
 
-        let modules: Vec<(&std::ops::Range<usize>, &(u16, String))> =
-            self.module_mapping.iter().collect();
+        // Excluded locations split a module into several ranges, merge them back by module id
+        let mut modules: BTreeMap<u16, (usize, usize, &str)> = BTreeMap::new();
+        for (range, (id, path)) in self.module_mapping.iter() {
+            let module = modules
+                .entry(*id)
+                .or_insert((range.start, range.end, path.as_str()));
+            module.0 = module.0.min(range.start);
+            module.1 = module.1.max(range.end);
+        }
         writer
             .write_all(format!("Module Table: version 2, count {}\n", modules.len()).as_bytes())
             .unwrap();
         writer
             .write_all(b"Columns: id, base, end, entry, checksum, timestamp, path\n")
             .unwrap();
-        for module in modules {
-            let (range, (id, path)) = module;
+        for (id, (base, end, path)) in &modules {
             writer
                 .write_all(
                     format!(
                         "{:03}, 0x{:x}, 0x{:x}, 0x00000000, 0x00000000, 0x00000000, {}\n",
-                        id, range.start, range.end, path
+                        id, base, end, path
                     )
                     .as_bytes(),
                 )
                 .unwrap();
         }
+
+        // Blocks outside of the modules, or in an excluded location, are not in the trace
+        let basic_blocks: Vec<DrCovBasicBlockEntry> = basic_blocks
+            .iter()
+            .filter_map(|block| {
+                let (_, (id, _)) = self.module_mapping.get_key_value(&block.start)?;
+                let (base, _, _) = modules[id];
+                Some(DrCovBasicBlockEntry {
+                    start: (block.start - base) as u32,
+                    size: (block.end - block.start) as u16,
+                    mod_id: *id,
+                })
+            })
+            .collect();
         writer
             .write_all(format!("BB Table: {} bbs\n", basic_blocks.len()).as_bytes())
             .unwrap();
-        for block in basic_blocks {
-            let (range, (id, _)) = self.module_mapping.get_key_value(&block.start).unwrap();
-            let basic_block = DrCovBasicBlockEntry {
-                start: (block.start - range.start) as u32,
-                size: (block.end - block.start) as u16,
-                mod_id: *id,
-            };
+        for basic_block in &basic_blocks {
             writer
                 .write_all(unsafe {
-                    std::slice::from_raw_parts(addr_of!(basic_block) as *const u8, 8)
+                    std::slice::from_raw_parts(addr_of!(*basic_block) as *const u8, 8)
                 })
                 .unwrap();
         }
@@ -0,0 +1,303 @@
+//! A forkserver runtime for targets linked with `libafl_targets`, speaking the `AFL++` protocol,
+//! so that they can be fuzzed by `LibAFL`'s `ForkserverExecutor` or by `afl-fuzz`.
+//!
+//! The [`crate::libafl_main!`] macro builds the `main` of such a target around a harness. Under a
+//! fuzzer, the harness runs on the testcases from shared memory, or from the input file, possibly
+//! in a persistent loop. Without a fuzzer, it runs once, on the file given as argument or on stdin.
+
+use alloc::vec::Vec;
+use core::ptr;
+use std::{env, fs, io::Read};
+
+use libafl::{
+    bolts::{
+        shmem::{ShMem, ShMemId, ShMemProvider, StdShMemProvider},
+        AsMutSlice, AsSlice,
+    },
+    Error,
+};
+
+use crate::{
+    coverage::{edges_max_num, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE},
+    EDGES_MAP_SIZE,
+};
+
+/// The fuzzer writes its commands to this file descriptor, and reads the status from the next one
+pub const FORKSRV_FD: i32 = 198;
+/// The environment variable telling the target to run in persistent mode, as in `AFL++`
+pub const PERSISTENT_ENV_VAR: &str = "__AFL_PERSISTENT";
+
+const FS_OPT_ENABLED: u32 = 0x80000001;
+const FS_OPT_SHDMEM_FUZZ: u32 = 0x01000000;
+const FS_OPT_MAPSIZE: u32 = 0x40000000;
+const SHMEM_FUZZ_HDR_SIZE: usize = 4;
+const MAX_FILE: usize = 1024 * 1024;
+
+static mut SHMEM_PROVIDER: Option<StdShMemProvider> = None;
+static mut COVERAGE_MAP: Option<<StdShMemProvider as ShMemProvider>::ShMem> = None;
+static mut INPUT_MAP: Option<<StdShMemProvider as ShMemProvider>::ShMem> = None;
+static mut IS_PERSISTENT: bool = false;
+static mut FIRST_PASS: bool = true;
+static mut CYCLE_CNT: u32 = 0;
+
+/// The coverage map size announced with [`FS_OPT_MAPSIZE`], as `FS_OPT_SET_MAPSIZE` in `AFL++`
+fn fs_opt_set_mapsize(size: usize) -> u32 {
+    ((size.saturating_sub(1) as u32) << 1) & 0x00ff_fffe
+}
+
+/// Fails if a coverage map of `map_len` bytes cannot hold the `edges` of the target
+fn check_map_len(map_len: usize, edges: usize) -> Result<(), Error> {
+    if map_len < edges {
+        return Err(Error::IllegalArgument(format!(
+            "The coverage map of the fuzzer ({} bytes) is smaller than the {} edges of the target, give it an AFL_MAP_SIZE of at least {}",
+            map_len, edges, edges
+        )));
+    }
+    Ok(())
+}
+
+/// Maps the shared map with the id in `env_name`, of the size in `{env_name}_SIZE` if given
+unsafe fn existing_map(
+    env_name: &str,
+    default_size: usize,
+) -> Result<<StdShMemProvider as ShMemProvider>::ShMem, Error> {
+    if SHMEM_PROVIDER.is_none() {
+        SHMEM_PROVIDER = Some(StdShMemProvider::new()?);
+    }
+    let id = env::var(env_name)?;
+    let size = match env::var(format!("{}_SIZE", env_name)) {
+        Ok(size) => size.parse()?,
+        Err(_) => default_size,
+    };
+    SHMEM_PROVIDER
+        .as_mut()
+        .unwrap()
+        .shmem_from_id_and_size(ShMemId::from_string(&id), size)
+}
+
+/// Maps the coverage map the fuzzer passes in `__AFL_SHM_ID` as [`EDGES_MAP_PTR`].
+/// Returns `false` if there is none, i.e. if the target does not run under a fuzzer.
+/// Fails if the map cannot hold all the edges the instrumentation assigned.
+pub fn map_shared_memory() -> Result<bool, Error> {
+    if env::var_os("__AFL_SHM_ID").is_none() {
+        return Ok(false);
+    }
+    unsafe {
+        let mut map = existing_map("__AFL_SHM_ID", EDGES_MAP_SIZE)?;
+        // The guards already hold their index into the map
+        check_map_len(map.len(), edges_max_num())?;
+        EDGES_MAP_PTR = map.as_mut_slice().as_mut_ptr();
+        EDGES_MAP_PTR_SIZE = map.len();
+        COVERAGE_MAP = Some(map);
+    }
+    Ok(true)
+}
+
+/// Reads a `u32` from the fuzzer
+unsafe fn read_u32(fd: i32) -> Result<u32, Error> {
+    let mut val = 0_u32;
+    if libc::read(fd, ptr::addr_of_mut!(val) as *mut libc::c_void, 4) == 4 {
+        Ok(val)
+    } else {
+        Err(Error::Unknown("Could not read from the fuzzer".into()))
+    }
+}
+
+/// Writes a `u32` to the fuzzer
+unsafe fn write_u32(fd: i32, val: u32) -> Result<(), Error> {
+    if libc::write(fd, ptr::addr_of!(val) as *const libc::c_void, 4) == 4 {
+        Ok(())
+    } else {
+        Err(Error::Unknown("Could not write to the fuzzer".into()))
+    }
+}
+
+/// Starts the forkserver, if the target runs under a fuzzer.
+/// The forkserver shakes hands with the fuzzer, then forks a child for each run or, in persistent
+/// mode, resumes the child stopped in [`persistent_loop`].
+/// Only returns in the children, with `true`, or with `false` if there is no fuzzer.
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+pub fn start_forkserver() -> Result<bool, Error> {
+    unsafe {
+        // Is there a fuzzer at the other end?
+        if libc::fcntl(FORKSRV_FD + 1, libc::F_GETFD) < 0 {
+            return Ok(false);
+        }
+
+        // The fuzzer only needs a map for the edges of the target
+        let mut status = FS_OPT_ENABLED | FS_OPT_MAPSIZE | fs_opt_set_mapsize(edges_max_num());
+        if env::var_os("__AFL_SHM_FUZZ_ID").is_some() {
+            INPUT_MAP = Some(existing_map(
+                "__AFL_SHM_FUZZ_ID",
+                MAX_FILE + SHMEM_FUZZ_HDR_SIZE,
+            )?);
+            status |= FS_OPT_SHDMEM_FUZZ;
+        }
+        write_u32(FORKSRV_FD + 1, status)?;
+        if INPUT_MAP.is_some() && read_u32(FORKSRV_FD)? & FS_OPT_SHDMEM_FUZZ == 0 {
+            // The fuzzer writes the testcases to the file after all
+            INPUT_MAP = None;
+        }
+        IS_PERSISTENT = env::var_os(PERSISTENT_ENV_VAR).is_some();
+
+        let mut child_pid = 0;
+        let mut child_stopped = false;
+        loop {
+            let was_killed = read_u32(FORKSRV_FD)?;
+            // The stopped child timed out before and got killed, reap it
+            if child_stopped && was_killed != 0 {
+                child_stopped = false;
+                if libc::waitpid(child_pid, ptr::null_mut(), 0) < 0 {
+                    return Err(Error::Unknown("Could not reap the killed child".into()));
+                }
+            }
+
+            if child_stopped {
+                libc::kill(child_pid, libc::SIGCONT);
+                child_stopped = false;
+            } else {
+                child_pid = libc::fork();
+                if child_pid < 0 {
+                    return Err(Error::Unknown("Fork failed".into()));
+                }
+                if child_pid == 0 {
+                    libc::close(FORKSRV_FD);
+                    libc::close(FORKSRV_FD + 1);
+                    return Ok(true);
+                }
+            }
+
+            write_u32(FORKSRV_FD + 1, child_pid as u32)?;
+            let mut status = 0;
+            let options = if IS_PERSISTENT { libc::WUNTRACED } else { 0 };
+            if libc::waitpid(child_pid, &mut status, options) < 0 {
+                return Err(Error::Unknown("Could not wait for the child".into()));
+            }
+            child_stopped = libc::WIFSTOPPED(status);
+            write_u32(FORKSRV_FD + 1, status as u32)?;
+        }
+    }
+}
+
+/// The persistent loop, as `__AFL_LOOP` in `AFL++`: returns `true` as long as the harness should
+/// run on the next input in this process, at most `max_cnt` times in a row.
+/// Without a persistent fuzzer, this returns `true` only once.
+#[must_use]
+pub fn persistent_loop(max_cnt: u32) -> bool {
+    unsafe {
+        if FIRST_PASS {
+            FIRST_PASS = false;
+            CYCLE_CNT = max_cnt;
+            if IS_PERSISTENT && !EDGES_MAP_PTR.is_null() {
+                // Forget the coverage of the initialization
+                ptr::write_bytes(EDGES_MAP_PTR, 0, EDGES_MAP_PTR_SIZE);
+            }
+            return true;
+        }
+        if IS_PERSISTENT && CYCLE_CNT > 1 {
+            CYCLE_CNT -= 1;
+            // The forkserver resumes us for the next run
+            libc::raise(libc::SIGSTOP);
+            return true;
+        }
+        false
+    }
+}
+
+/// The current input: from the shared map, if the fuzzer passes the testcases in shared memory,
+/// else from the file given as first argument, else from stdin.
+pub fn read_input() -> Result<Vec<u8>, Error> {
+    unsafe {
+        if let Some(map) = &INPUT_MAP {
+            let slice = map.as_slice();
+            let mut len_bytes = [0_u8; SHMEM_FUZZ_HDR_SIZE];
+            len_bytes.copy_from_slice(&slice[..SHMEM_FUZZ_HDR_SIZE]);
+            let len =
+                (u32::from_ne_bytes(len_bytes) as usize).min(slice.len() - SHMEM_FUZZ_HDR_SIZE);
+            return Ok(slice[SHMEM_FUZZ_HDR_SIZE..SHMEM_FUZZ_HDR_SIZE + len].to_vec());
+        }
+    }
+    if let Some(path) = env::args_os().nth(1) {
+        return Ok(fs::read(path)?);
+    }
+    // The fuzzer rewrites the file behind stdin for each run, read it from the start
+    unsafe {
+        libc::lseek(libc::STDIN_FILENO, 0, libc::SEEK_SET);
+    }
+    let mut input = vec![];
+    std::io::stdin().read_to_end(&mut input)?;
+    Ok(input)
+}
+
+/// Runs `harness` on the inputs of the fuzzer, up to `max_cnt` runs per process in persistent mode.
+/// Without a fuzzer, it runs once on the file given as first argument, or on stdin.
+pub fn run_harness<F>(mut harness: F, max_cnt: u32) -> Result<(), Error>
+where
+    F: FnMut(&[u8]),
+{
+    map_shared_memory()?;
+    if !start_forkserver()? {
+        harness(&read_input()?);
+        return Ok(());
+    }
+    while persistent_loop(max_cnt) {
+        harness(&read_input()?);
+    }
+    Ok(())
+}
+
+/// Defines the `main` of a target around a harness taking the input bytes, see
+/// [`forkserver::run_harness`](crate::forkserver::run_harness).
+/// With a second argument, the harness runs in persistent mode, up to that many times per process.
+///
+/// ```rust,ignore
+/// libafl_targets::libafl_main!(|data: &[u8]| {
+///     my_target::parse(data);
+/// }, 1000);
+/// ```
+#[macro_export]
+macro_rules! libafl_main {
+    ($harness:expr) => {
+        $crate::libafl_main!($harness, 1);
+    };
+    ($harness:expr, $max_cnt:expr) => {
+        fn main() {
+            $crate::forkserver::run_harness($harness, $max_cnt).expect("Could not run the harness");
+        }
+    };
+}
+
+/// The persistent loop for a custom `main`, as `__AFL_LOOP` in `AFL++`:
+/// `while libafl_loop!(1000) { ... }`, after [`map_shared_memory`] and [`start_forkserver`].
+#[macro_export]
+macro_rules! libafl_loop {
+    ($max_cnt:expr) => {
+        $crate::forkserver::persistent_loop($max_cnt)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::forkserver::{check_map_len, fs_opt_set_mapsize, FS_OPT_MAPSIZE};
+
+    /// `FS_OPT_GET_MAPSIZE` of `AFL++`, as the `ForkserverExecutor` decodes it
+    fn fs_opt_get_mapsize(status: u32) -> usize {
+        (((status & 0x00ff_fffe) >> 1) + 1) as usize
+    }
+
+    #[test]
+    fn test_announced_map_size() {
+        for edges in [1, 2, 1337, 65536, 1 << 23] {
+            let status = FS_OPT_MAPSIZE | fs_opt_set_mapsize(edges);
+            assert_eq!(fs_opt_get_mapsize(status), edges);
+        }
+    }
+
+    #[test]
+    fn test_check_map_len() {
+        assert!(check_map_len(65536, 1337).is_ok());
+        assert!(check_map_len(1337, 1337).is_ok());
+        // The guards would index past the map of the fuzzer
+        assert!(check_map_len(1024, 1337).is_err());
+    }
+}
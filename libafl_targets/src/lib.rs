@@ -69,6 +69,11 @@ pub mod sancov_pcguard;
 #[cfg(any(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts",))]
 pub use sancov_pcguard::*;
 
+#[cfg(feature = "sancov_pc_table")]
+pub mod sancov_pcs;
+#[cfg(feature = "sancov_pc_table")]
+pub use sancov_pcs::*;
+
 #[cfg(any(feature = "sancov_cmplog", feature = "sancov_value_profile"))]
 pub mod sancov_cmp;
 #[cfg(any(feature = "sancov_cmplog", feature = "sancov_value_profile"))]
@@ -93,5 +98,14 @@ pub use value_profile::*;
 pub mod cmplog;
 pub use cmplog::*;
 
+pub mod resources;
+pub use resources::*;
+
+pub mod log_ring;
+pub use log_ring::*;
+
 #[cfg(feature = "std")]
 pub mod drcov;
+
+#[cfg(all(unix, feature = "forkserver"))]
+pub mod forkserver;
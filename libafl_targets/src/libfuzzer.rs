@@ -33,5 +33,7 @@ pub fn libfuzzer_initialize(args: &[String]) -> i32 {
 /// Calls the libfuzzer harness. We actually think the target is unsafe and crashes eventually, that's why we do all this fuzzing.
 #[allow(clippy::must_use_candidate)]
 pub fn libfuzzer_test_one_input(buf: &[u8]) -> i32 {
+    #[cfg(feature = "sancov_stack_depth")]
+    crate::resources::record_stack_depth_baseline();
     unsafe { LLVMFuzzerTestOneInput(buf.as_ptr(), buf.len()) }
 }
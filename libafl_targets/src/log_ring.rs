@@ -0,0 +1,78 @@
+//! The target side of the [`libafl::observers::LogRingObserver`]: the harness writes its debug
+//! output to the log ring, and the fuzzer attaches it to the crashes.
+
+use core::{ptr, slice};
+
+use libafl::observers::log_ring_write;
+#[cfg(feature = "std")]
+use libafl::{
+    bolts::{
+        shmem::{ShMem, ShMemProvider, StdShMemProvider},
+        AsMutSlice,
+    },
+    observers::LOG_RING_ENV,
+    Error,
+};
+
+#[doc(hidden)]
+pub use alloc::format as __log_ring_format;
+
+static mut LOG_RING_PTR: *mut u8 = ptr::null_mut();
+static mut LOG_RING_LEN: usize = 0;
+
+/// Use the memory at `ring_ptr` as log ring, e.g. the ring of the observer of an in-process fuzzer
+///
+/// # Safety
+/// The memory must stay valid for `len` bytes as long as the target logs.
+pub unsafe fn log_ring_set(ring_ptr: *mut u8, len: usize) {
+    LOG_RING_PTR = ring_ptr;
+    LOG_RING_LEN = len;
+}
+
+/// Map the log ring from the shared memory described in the [`LOG_RING_ENV`] env variable,
+/// set by the fuzzer with `ShMem::write_to_env`
+#[cfg(feature = "std")]
+pub fn log_ring_init_from_env() -> Result<(), Error> {
+    let mut provider = StdShMemProvider::new()?;
+    let mut shmem = provider.existing_from_env(LOG_RING_ENV)?;
+    unsafe {
+        log_ring_set(shmem.as_mut_slice().as_mut_ptr(), shmem.len());
+    }
+    // The mapping lives as long as the target
+    core::mem::forget(shmem);
+    core::mem::forget(provider);
+    Ok(())
+}
+
+/// Append `data` to the log ring, if there is one
+pub fn target_log(data: &[u8]) {
+    unsafe {
+        if !LOG_RING_PTR.is_null() {
+            log_ring_write(slice::from_raw_parts_mut(LOG_RING_PTR, LOG_RING_LEN), data);
+        }
+    }
+}
+
+/// Append `len` bytes at `data` to the log ring, for C harnesses
+///
+/// # Safety
+/// `data` must be valid for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn libafl_target_log(data: *const u8, len: usize) {
+    if !data.is_null() {
+        target_log(slice::from_raw_parts(data, len));
+    }
+}
+
+/// Format a line to the log ring, like `println!`
+#[macro_export]
+macro_rules! target_println {
+    () => {
+        $crate::log_ring::target_log(b"\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::log_ring::target_log(
+            $crate::log_ring::__log_ring_format!("{}\n", format_args!($($arg)*)).as_bytes(),
+        )
+    };
+}
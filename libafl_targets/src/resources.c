@@ -0,0 +1,28 @@
+#include "common.h"
+
+#ifdef SANCOV_STACK_DEPTH
+
+// Updated by the code instrumented with -fsanitize-coverage=stack-depth
+MAYBE_THREAD_LOCAL uintptr_t __sancov_lowest_stack;
+
+// The frame of the harness, 0 until it is recorded for the current run
+static MAYBE_THREAD_LOCAL uintptr_t libafl_initial_stack;
+
+void libafl_stack_depth_reset(void) {
+  libafl_initial_stack = 0;
+  __sancov_lowest_stack = UINTPTR_MAX;
+}
+
+// Called right before the harness, so this frame is at the depth of the harness frame
+void libafl_stack_depth_record_initial(void) {
+  libafl_initial_stack = FRAMEADDR;
+}
+
+uintptr_t libafl_stack_depth_max(void) {
+  if (!libafl_initial_stack || libafl_initial_stack <= __sancov_lowest_stack) {
+    return 0;
+  }
+  return libafl_initial_stack - __sancov_lowest_stack;
+}
+
+#endif  // SANCOV_STACK_DEPTH
@@ -0,0 +1,244 @@
+//! Observers for the resources used by a run: the maximum stack depth and the largest allocation.
+//!
+//! Each observer is a map with a single entry, holding a logarithmic step of the maximum, so that
+//! a [`libafl::feedbacks::MaxMapFeedback`] saves the inputs reaching a new order of magnitude.
+
+use alloc::string::{String, ToString};
+use core::{fmt::Debug, marker::PhantomData, slice};
+
+#[cfg(any(feature = "sancov_stack_depth", feature = "sanitizer_malloc_hook"))]
+use libafl::feedbacks::MaxMapFeedback;
+use libafl::{
+    bolts::{tuples::Named, AsMutIterator, AsRefIterator, HasLen},
+    executors::ExitKind,
+    observers::{MapObserver, Observer},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// The step function of `libFuzzer` for the stack depth: exact up to 8, then 8 steps per power of two
+#[must_use]
+pub fn resource_step(value: usize) -> usize {
+    if value == 0 {
+        return 0;
+    }
+    let log2 = (usize::BITS - 1 - value.leading_zeros()) as usize;
+    if log2 < 3 {
+        return value;
+    }
+    let log2 = log2 - 3;
+    (log2 + 1) * 8 + ((value >> log2) & 7)
+}
+
+/// The instrumentation measuring a resource
+pub trait ResourceProbe: 'static + Debug {
+    /// Reset the measure before a run
+    fn reset();
+    /// The maximum measured since the last reset
+    fn max() -> usize;
+}
+
+/// A map observer with a single entry, the step of the maximum measured by `P` during the run
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    name: String,
+    value: usize,
+    initial: usize,
+    phantom: PhantomData<P>,
+}
+
+impl<P> ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    /// Creates a new [`ResourceObserver`] with the given name
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: 0,
+            initial: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The maximum measured in the last run, not stepped
+    #[must_use]
+    pub fn last_max(&self) -> usize {
+        P::max()
+    }
+}
+
+impl<I, S, P> Observer<I, S> for ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.value = self.initial;
+        P::reset();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.value = resource_step(P::max());
+        Ok(())
+    }
+}
+
+impl<P> Named for ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<P> HasLen for ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+impl<P> MapObserver for ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    type Entry = usize;
+
+    fn get(&self, _idx: usize) -> &usize {
+        &self.value
+    }
+
+    fn get_mut(&mut self, _idx: usize) -> &mut usize {
+        &mut self.value
+    }
+
+    fn usable_count(&self) -> usize {
+        1
+    }
+
+    fn hash(&self) -> u64 {
+        self.value as u64
+    }
+
+    fn initial(&self) -> usize {
+        self.initial
+    }
+
+    fn initial_mut(&mut self) -> &mut usize {
+        &mut self.initial
+    }
+}
+
+impl<'it, P> AsRefIterator<'it> for ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    type Item = usize;
+    type IntoIter = slice::Iter<'it, usize>;
+
+    fn as_ref_iter(&'it self) -> Self::IntoIter {
+        slice::from_ref(&self.value).iter()
+    }
+}
+
+impl<'it, P> AsMutIterator<'it> for ResourceObserver<P>
+where
+    P: ResourceProbe,
+{
+    type Item = usize;
+    type IntoIter = slice::IterMut<'it, usize>;
+
+    fn as_mut_iter(&'it mut self) -> Self::IntoIter {
+        slice::from_mut(&mut self.value).iter_mut()
+    }
+}
+
+#[cfg(feature = "sancov_stack_depth")]
+extern "C" {
+    fn libafl_stack_depth_reset();
+    fn libafl_stack_depth_record_initial();
+    fn libafl_stack_depth_max() -> usize;
+}
+
+/// Records the frame the stack depth is measured from, call it right before calling the target.
+/// `libfuzzer_test_one_input` does it already.
+#[cfg(feature = "sancov_stack_depth")]
+#[allow(clippy::inline_always)]
+#[inline(always)]
+pub fn record_stack_depth_baseline() {
+    unsafe { libafl_stack_depth_record_initial() }
+}
+
+/// The stack depth below the harness, measured by the `-fsanitize-coverage=stack-depth` instrumentation.
+/// The depth is 0 for the runs without [`record_stack_depth_baseline`].
+#[cfg(feature = "sancov_stack_depth")]
+#[derive(Debug)]
+pub struct StackDepthProbe;
+
+#[cfg(feature = "sancov_stack_depth")]
+impl ResourceProbe for StackDepthProbe {
+    fn reset() {
+        unsafe { libafl_stack_depth_reset() }
+    }
+
+    fn max() -> usize {
+        unsafe { libafl_stack_depth_max() }
+    }
+}
+
+/// Observes the maximum stack depth of a run, in bytes, on the thread running the target
+#[cfg(feature = "sancov_stack_depth")]
+pub type StackDepthObserver = ResourceObserver<StackDepthProbe>;
+
+/// Saves the inputs reaching a new maximum stack depth step, use a `MapFeedbackState` of size 1
+#[cfg(feature = "sancov_stack_depth")]
+pub type StackDepthFeedback<I, S> = MaxMapFeedback<I, StackDepthObserver, S, usize>;
+
+#[cfg(feature = "sanitizer_malloc_hook")]
+static MAX_ALLOCATION: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+/// Called by the allocator of the sanitizers for each allocation
+#[cfg(feature = "sanitizer_malloc_hook")]
+#[no_mangle]
+pub extern "C" fn __sanitizer_malloc_hook(_ptr: *const core::ffi::c_void, size: usize) {
+    MAX_ALLOCATION.fetch_max(size, core::sync::atomic::Ordering::Relaxed);
+}
+
+/// The largest single allocation, reported by the malloc hook of the sanitizers.
+/// The target must be built with a sanitizer, e.g. `ASan`.
+#[cfg(feature = "sanitizer_malloc_hook")]
+#[derive(Debug)]
+pub struct MaxAllocationProbe;
+
+#[cfg(feature = "sanitizer_malloc_hook")]
+impl ResourceProbe for MaxAllocationProbe {
+    fn reset() {
+        MAX_ALLOCATION.store(0, core::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn max() -> usize {
+        MAX_ALLOCATION.load(core::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Observes the largest single allocation of a run, in bytes
+#[cfg(feature = "sanitizer_malloc_hook")]
+pub type MaxAllocationObserver = ResourceObserver<MaxAllocationProbe>;
+
+/// Saves the inputs reaching a new largest allocation step, use a `MapFeedbackState` of size 1
+#[cfg(feature = "sanitizer_malloc_hook")]
+pub type MaxAllocationFeedback<I, S> = MaxMapFeedback<I, MaxAllocationObserver, S, usize>;
@@ -1,7 +1,10 @@
 //! [`LLVM` `8-bi-counters`](https://clang.llvm.org/docs/SanitizerCoverage.html#tracing-pcs-with-guards) runtime for `LibAFL`.
+//! Harnesses built with the stock `-fsanitize-coverage=inline-8bit-counters` of `clang` work without any custom pass.
 use alloc::vec::Vec;
 use core::slice::from_raw_parts_mut;
 
+use libafl::observers::MultiMapObserver;
+
 /// A [`Vec`] of `8-bit-counters` maps for multiple modules.
 /// They are initialized by calling [`__sanitizer_cov_8bit_counters_init`](
 pub static mut COUNTERS_MAPS: Vec<&'static mut [u8]> = Vec::new();
@@ -13,6 +16,22 @@ pub static mut COUNTERS_MAPS: Vec<&'static mut [u8]> = Vec::new();
 #[no_mangle]
 #[allow(clippy::cast_sign_loss)]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
-pub fn __sanitizer_cov_8bit_counters_init(start: *mut u8, stop: *mut u8) {
+pub extern "C" fn __sanitizer_cov_8bit_counters_init(start: *mut u8, stop: *mut u8) {
     unsafe { COUNTERS_MAPS.push(from_raw_parts_mut(start, stop.offset_from(start) as usize)) }
 }
+
+/// The number of counters of all modules
+#[must_use]
+pub fn counters_maps_len() -> usize {
+    unsafe { COUNTERS_MAPS.iter().map(|map| map.len()).sum() }
+}
+
+/// A [`MultiMapObserver`] over the counters of all modules, e.g. to wrap in a `HitcountsMapObserver`.
+/// The index of a counter in this observer is its index in the pc-table of `sancov_pc_table`.
+///
+/// # Safety
+/// The observer aliases [`COUNTERS_MAPS`]: create only one, after all modules were loaded.
+#[must_use]
+pub unsafe fn counters_maps_observer(name: &'static str) -> MultiMapObserver<'static, u8> {
+    MultiMapObserver::new(name, &mut COUNTERS_MAPS)
+}
@@ -0,0 +1,123 @@
+//! [`LLVM` `PC-Table`](https://clang.llvm.org/docs/SanitizerCoverage.html#pc-table) runtime for `LibAFL`,
+//! mapping the edges of the `pc_guard` or `8-bit-counters` instrumentation back to the functions and modules of the target,
+//! for [`libafl::bolts::coverage_report`]. The target must be built with `-fsanitize-coverage=trace-pc-guard,pc-table`,
+//! or, with the `sancov_8bit` feature, with `-fsanitize-coverage=inline-8bit-counters,pc-table`.
+
+use alloc::{string::String, vec::Vec};
+use core::{ffi::c_void, slice};
+use std::ffi::CStr;
+
+use libafl::bolts::coverage_report::{CoverageReport, CoverageSite};
+
+#[cfg(not(feature = "sancov_8bit"))]
+use crate::coverage::MAX_EDGES_NUM;
+#[cfg(feature = "sancov_8bit")]
+use crate::sancov_8bit::counters_maps_len;
+
+/// An entry of the pc-table, as emitted by `LLVM`
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct PcTableEntry {
+    /// The address of the instrumented block
+    pub pc: usize,
+    /// The flags of the block, see [`PcTableEntry::is_function_entry`]
+    pub flags: usize,
+}
+
+/// The entry is the first block of a function
+const PC_FLAG_FUNC_ENTRY: usize = 1;
+
+impl PcTableEntry {
+    /// If the block is the entry of a function
+    #[must_use]
+    pub fn is_function_entry(&self) -> bool {
+        self.flags & PC_FLAG_FUNC_ENTRY != 0
+    }
+}
+
+/// The pc-tables of all modules, with the index of the edge of their first entry
+static mut PC_TABLES: Vec<(usize, &'static [PcTableEntry])> = Vec::new();
+
+/// Callback for the sancov `pc-table`, called by `llvm` for each module, right after
+/// the guards or the counters of the module were initialized.
+///
+/// # Safety
+/// Keeps a reference to the table from `pcs_beg` to `pcs_end`, which must be valid forever.
+#[no_mangle]
+pub unsafe extern "C" fn __sanitizer_cov_pcs_init(pcs_beg: *const usize, pcs_end: *const usize) {
+    let len = (pcs_end as usize - pcs_beg as usize) / core::mem::size_of::<PcTableEntry>();
+    let table = slice::from_raw_parts(pcs_beg as *const PcTableEntry, len);
+    // The guards or counters of this module are the last ones, in the same order as the table
+    #[cfg(not(feature = "sancov_8bit"))]
+    let total = MAX_EDGES_NUM;
+    #[cfg(feature = "sancov_8bit")]
+    let total = counters_maps_len();
+    PC_TABLES.push((total.saturating_sub(len), table));
+}
+
+/// The pc-tables of all modules, with the index in the coverage map of their first entry
+pub fn pc_tables() -> impl Iterator<Item = (usize, &'static [PcTableEntry])> {
+    unsafe { PC_TABLES.iter().copied() }
+}
+
+/// The module containing `pc`, and the name of the exported symbol right before it
+#[cfg(unix)]
+fn dladdr(pc: usize) -> (String, Option<String>) {
+    unsafe {
+        let mut info: libc::Dl_info = core::mem::zeroed();
+        if libc::dladdr(pc as *const c_void, &mut info) == 0 || info.dli_fname.is_null() {
+            return ("unknown".into(), None);
+        }
+        let module = CStr::from_ptr(info.dli_fname).to_string_lossy().into();
+        let symbol = (!info.dli_sname.is_null())
+            .then(|| CStr::from_ptr(info.dli_sname).to_string_lossy().into());
+        (module, symbol)
+    }
+}
+
+#[cfg(not(unix))]
+fn dladdr(_pc: usize) -> (String, Option<String>) {
+    ("unknown".into(), None)
+}
+
+/// The sites of all edges of the target, symbolized with its debug info, where available.
+/// This is slow, only call it once, e.g. to write a report at the end of the campaign.
+#[must_use]
+pub fn pc_table_sites() -> Vec<CoverageSite> {
+    let mut sites = vec![];
+    for (first_edge, table) in pc_tables() {
+        let mut function = String::from("unknown");
+        for (i, entry) in table.iter().enumerate() {
+            let mut file = None;
+            let mut line = 0;
+            let mut symbol = None;
+            backtrace::resolve(entry.pc as *mut c_void, |sym| {
+                if symbol.is_none() {
+                    symbol = sym.name().map(|name| name.to_string());
+                    file = sym.filename().map(|f| f.to_string_lossy().into_owned());
+                    line = sym.lineno().unwrap_or(0);
+                }
+            });
+            let (module, exported) = dladdr(entry.pc);
+            if entry.is_function_entry() {
+                function = symbol
+                    .or(exported)
+                    .unwrap_or_else(|| format!("{:#x}", entry.pc));
+            }
+            sites.push(CoverageSite {
+                index: first_edge + i,
+                module,
+                function: function.clone(),
+                file,
+                line,
+            });
+        }
+    }
+    sites
+}
+
+/// A [`CoverageReport`] over all edges of the target, see [`pc_table_sites`]
+#[must_use]
+pub fn pc_table_coverage_report() -> CoverageReport {
+    CoverageReport::new(pc_table_sites())
+}
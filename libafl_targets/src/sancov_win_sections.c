@@ -0,0 +1,38 @@
+// From compiler-rt's sanitizer_coverage_win_sections.cpp
+
+// COFF has no __start_/__stop_ symbols for the sancov sections.
+// The linker sorts the sections with the same name before the `$` by their suffix,
+// so the variables in `$A` and `$Z` enclose what the instrumentation puts in `$M`.
+// The instrumentation skips the uint64_t at the start.
+
+#ifdef _WIN32
+
+#include <stdint.h>
+
+#ifdef _MSC_VER
+  #define SECTION_RW(NAME) __pragma(section(NAME, read, write)) __declspec(allocate(NAME))
+  #define SECTION_R(NAME) __pragma(section(NAME, read)) __declspec(allocate(NAME))
+  #define ALIGN_1 __declspec(align(1))
+
+  // Keep the sections with the data, as the instrumentation would on ELF
+  #pragma comment(linker, "/MERGE:.SCOV=.data")
+  #pragma comment(linker, "/MERGE:.SCOVP=.rdata")
+#else
+  #define SECTION_RW(NAME) __attribute__((section(NAME)))
+  #define SECTION_R(NAME) __attribute__((section(NAME)))
+  #define ALIGN_1 __attribute__((aligned(1)))
+#endif
+
+// -fsanitize-coverage=inline-8bit-counters
+SECTION_RW(".SCOV$CA") uint64_t __start___sancov_cntrs = 0;
+SECTION_RW(".SCOV$CZ") ALIGN_1 uint8_t __stop___sancov_cntrs = 0;
+
+// -fsanitize-coverage=trace-pc-guard (clang-cl only)
+SECTION_RW(".SCOV$GA") uint64_t __start___sancov_guards = 0;
+SECTION_RW(".SCOV$GZ") ALIGN_1 uint8_t __stop___sancov_guards = 0;
+
+// -fsanitize-coverage=pc-table
+SECTION_R(".SCOVP$A") const uint64_t __start___sancov_pcs = 0;
+SECTION_R(".SCOVP$Z") const ALIGN_1 uint8_t __stop___sancov_pcs = 0;
+
+#endif  // _WIN32
@@ -0,0 +1,190 @@
+//! The [`TinyInstExecutor`] runs the target under `TinyInst`, and writes the covered basic blocks into [`TINYINST_MAP`].
+
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    bolts::AsSlice,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::ObserversTuple,
+    Error,
+};
+use tinyinst::tinyinst::{litecov::RunResult, TinyInst};
+
+/// The size of the [`TINYINST_MAP`]
+pub const TINYINST_MAP_SIZE: usize = 65536;
+
+/// The coverage map of the [`TinyInstExecutor`], one entry per basic block offset (hashed).
+/// Observe it with a `StdMapObserver`.
+pub static mut TINYINST_MAP: [u8; TINYINST_MAP_SIZE] = [0; TINYINST_MAP_SIZE];
+
+/// In the args of the target program, `@@` is replaced with the path of the input file
+const INPUT_PLACEHOLDER: &str = "@@";
+
+/// The args of the target program, with the path of the `input_file` in place of `@@`
+fn program_args_for(program_args: &[String], input_file: &Path) -> Vec<String> {
+    let input_path = input_file.to_string_lossy();
+    program_args
+        .iter()
+        .map(|arg| arg.replace(INPUT_PLACEHOLDER, &input_path))
+        .collect()
+}
+
+/// The entry of the [`TINYINST_MAP`] for a covered basic block offset
+fn map_index(offset: u64) -> usize {
+    ((offset >> 4) ^ offset) as usize % TINYINST_MAP_SIZE
+}
+
+/// An executor running the target program under `TinyInst`.
+/// Each input is written to a file, passed to the program in place of `@@`.
+pub struct TinyInstExecutor<I, OT, S> {
+    tinyinst: TinyInst,
+    /// The basic block offsets covered in the last run
+    coverage: Vec<u64>,
+    input_file: PathBuf,
+    observers: OT,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, OT, S> Debug for TinyInstExecutor<I, OT, S>
+where
+    OT: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TinyInstExecutor")
+            .field("input_file", &self.input_file)
+            .field("observers", &self.observers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, OT, S> TinyInstExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    /// Creates a new [`TinyInstExecutor`].
+    /// `tinyinst_args` are the args of `TinyInst`, such as `-instrument_module target.dll`,
+    /// `program_args` the target program and its args, with `@@` for the `input_file`.
+    pub fn new(
+        tinyinst_args: &[String],
+        program_args: &[String],
+        input_file: PathBuf,
+        timeout: Duration,
+        observers: OT,
+    ) -> Result<Self, Error> {
+        if program_args.is_empty() {
+            return Err(Error::IllegalArgument(
+                "TinyInstExecutor needs a program to run".into(),
+            ));
+        }
+        let program_args = program_args_for(program_args, &input_file);
+        let timeout = u32::try_from(timeout.as_millis())
+            .map_err(|_| Error::IllegalArgument(format!("Timeout too long: {:?}", timeout)))?;
+
+        let tinyinst = unsafe { TinyInst::new(tinyinst_args, &program_args, timeout) };
+        Ok(Self {
+            tinyinst,
+            coverage: vec![],
+            input_file,
+            observers,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The file the inputs are written to
+    #[must_use]
+    pub fn input_file(&self) -> &PathBuf {
+        &self.input_file
+    }
+}
+
+impl<EM, I, OT, S, Z> Executor<EM, I, S, Z> for TinyInstExecutor<I, OT, S>
+where
+    I: Input + HasTargetBytes,
+    OT: ObserversTuple<I, S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut S,
+        _mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        fs::write(&self.input_file, input.target_bytes().as_slice())?;
+
+        let status = unsafe { self.tinyinst.run() };
+
+        // `TinyInst` reports each basic block once, the first time it is covered
+        self.tinyinst.vec_coverage(&mut self.coverage, true);
+        for offset in self.coverage.drain(..) {
+            let index = map_index(offset);
+            unsafe {
+                TINYINST_MAP[index] = TINYINST_MAP[index].saturating_add(1);
+            }
+        }
+
+        match status {
+            RunResult::OK => Ok(ExitKind::Ok),
+            RunResult::CRASH => Ok(ExitKind::Crash),
+            RunResult::HANG => Ok(ExitKind::Timeout),
+            RunResult::OTHER_ERROR => {
+                Err(Error::Unknown("TinyInst could not run the target".into()))
+            }
+            _ => Err(Error::Unknown("Unknown TinyInst run result".into())),
+        }
+    }
+}
+
+impl<I, OT, S> HasObservers<I, OT, S> for TinyInstExecutor<I, OT, S>
+where
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        &self.observers
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        &mut self.observers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::executor::{map_index, program_args_for, TINYINST_MAP_SIZE};
+
+    #[test]
+    fn test_program_args() {
+        let args = vec![
+            "target.exe".to_string(),
+            "-f".to_string(),
+            "@@".to_string(),
+            "--out=@@.out".to_string(),
+        ];
+        assert_eq!(
+            program_args_for(&args, Path::new("cur_input")),
+            vec!["target.exe", "-f", "cur_input", "--out=cur_input.out"]
+        );
+    }
+
+    #[test]
+    fn test_map_index() {
+        assert!(map_index(u64::MAX) < TINYINST_MAP_SIZE);
+        // Neighbouring blocks land in different entries
+        assert_ne!(map_index(0x1000), map_index(0x1010));
+        assert_eq!(map_index(0x1234), map_index(0x1234));
+    }
+}
@@ -0,0 +1,69 @@
+//! `LibAFL` executor for [`TinyInst`](https://github.com/googleprojectzero/TinyInst), lightweight dynamic
+//! instrumentation for binary-only targets on Windows and macOS.
+//!
+//! `TinyInst` runs the target program under a debugger and instruments the modules given with
+//! `-instrument_module`. Each run gets its input from a file, and reports the basic blocks it
+//! covered for the first time, which the `TinyInstExecutor` writes into the `TINYINST_MAP`.
+//!
+//! The crate is not part of the workspace, `tinyinst` builds `TinyInst` with `cmake`: build it from its dir.
+
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![allow(
+    clippy::unreadable_literal,
+    clippy::type_repetition_in_bounds,
+    clippy::missing_errors_doc,
+    clippy::cast_possible_truncation,
+    clippy::used_underscore_binding,
+    clippy::ptr_as_ptr,
+    clippy::missing_panics_doc,
+    clippy::missing_docs_in_private_items,
+    clippy::module_name_repetitions
+)]
+#![cfg_attr(debug_assertions, warn(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(not(debug_assertions), deny(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    //unused_results
+))]
+#![cfg_attr(
+    not(debug_assertions),
+    deny(
+        bad_style,
+        const_err,
+        dead_code,
+        improper_ctypes,
+        non_shorthand_field_patterns,
+        no_mangle_generic_items,
+        overflowing_literals,
+        path_statements,
+        patterns_in_fns_without_body,
+        private_in_public,
+        unconditional_recursion,
+        unused,
+        unused_allocation,
+        unused_comparisons,
+        unused_parens,
+        while_true
+    )
+)]
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub mod executor;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub use executor::{TinyInstExecutor, TINYINST_MAP, TINYINST_MAP_SIZE};
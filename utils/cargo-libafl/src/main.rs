@@ -0,0 +1,362 @@
+//! `cargo libafl`: a `cargo-fuzz` compatible subcommand, fuzzing the targets in the `fuzz/` directory
+//! of a Rust project with the `libafl_libfuzzer` runtime instead of `libFuzzer`.
+
+use clap::{self, Args, Parser, Subcommand};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+mod project;
+mod tmin;
+
+use project::{check_status, files_in, FuzzProject};
+
+/// The result of the commands of `cargo libafl`
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// `cargo` passes the name of the subcommand on, as first argument
+#[derive(Debug, Parser)]
+#[clap(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Libafl(Opt),
+}
+
+/// The commandline args of `cargo libafl`
+#[derive(Debug, Args)]
+#[clap(
+    about = "Fuzz the cargo-fuzz targets of a project with LibAFL",
+    author = "Andrea Fioraldi <andreafioraldi@gmail.com>, Dominik Maier <domenukk@gmail.com>"
+)]
+struct Opt {
+    #[clap(
+        long,
+        help = "The fuzz directory, default is ./fuzz",
+        name = "FUZZ_DIR",
+        default_value = "fuzz"
+    )]
+    fuzz_dir: PathBuf,
+
+    #[clap(subcommand)]
+    command: Cmd,
+}
+
+/// How to build the fuzz targets
+#[derive(Debug, Args)]
+pub struct BuildOpt {
+    #[clap(
+        long,
+        env = "LIBAFL_LIBFUZZER_RUNTIME",
+        help = "The liblibafl_libfuzzer.a runtime to link the fuzz targets against",
+        name = "RUNTIME"
+    )]
+    runtime: Option<PathBuf>,
+
+    #[clap(
+        short,
+        long,
+        help = "The sanitizer to build with: address, memory, thread, or none (needs nightly)",
+        name = "SANITIZER",
+        default_value = "none"
+    )]
+    sanitizer: String,
+
+    #[clap(
+        short = 'a',
+        long,
+        help = "Build with debug assertions and overflow checks"
+    )]
+    debug_assertions: bool,
+
+    #[clap(
+        long,
+        help = "The features of the fuzz crate to build",
+        name = "FEATURES"
+    )]
+    features: Option<String>,
+
+    #[clap(
+        long = "target",
+        help = "The target triple to build for, default is the host",
+        name = "TRIPLE"
+    )]
+    triple: Option<String>,
+}
+
+/// The subcommands
+#[derive(Debug, Subcommand)]
+enum Cmd {
+    /// List the fuzz targets
+    List,
+    /// Build the fuzz targets
+    Build {
+        #[clap(flatten)]
+        build: BuildOpt,
+        /// The fuzz target to build, default is all of them
+        target: Option<String>,
+    },
+    /// Fuzz a target
+    Run {
+        #[clap(flatten)]
+        build: BuildOpt,
+        #[clap(short, long, help = "The number of fuzzing processes", name = "JOBS")]
+        jobs: Option<usize>,
+        /// The fuzz target
+        target: String,
+        /// The corpus directories, default is fuzz/corpus/<target>
+        corpus: Vec<PathBuf>,
+        /// libFuzzer-style args for the runtime, e.g. `-max_len=1024`
+        #[clap(last = true)]
+        args: Vec<String>,
+    },
+    /// Minimize a corpus, keeping only the inputs with new coverage
+    Cmin {
+        #[clap(flatten)]
+        build: BuildOpt,
+        /// The fuzz target
+        target: String,
+        /// The corpus directory, default is fuzz/corpus/<target>
+        corpus: Option<PathBuf>,
+    },
+    /// Minimize a crashing input
+    Tmin {
+        #[clap(flatten)]
+        build: BuildOpt,
+        /// The fuzz target
+        target: String,
+        /// The crashing input
+        input: PathBuf,
+    },
+    /// Run a corpus through a coverage build and write a LCOV report to fuzz/coverage/<target>
+    Coverage {
+        #[clap(flatten)]
+        build: BuildOpt,
+        /// The fuzz target
+        target: String,
+        /// The corpus directories, default is fuzz/corpus/<target>
+        corpus: Vec<PathBuf>,
+    },
+}
+
+fn main() {
+    let Cargo::Libafl(opt) = Cargo::parse();
+    if let Err(err) = run(opt) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(opt: Opt) -> Result<()> {
+    let project = FuzzProject::find(&opt.fuzz_dir)?;
+    match opt.command {
+        Cmd::List => {
+            for target in project.targets()? {
+                println!("{}", target);
+            }
+            Ok(())
+        }
+        Cmd::Build { build, target } => {
+            if let Some(target) = &target {
+                project.check_target(target)?;
+            }
+            project.build(target.as_deref(), &build)
+        }
+        Cmd::Run {
+            build,
+            jobs,
+            target,
+            corpus,
+            args,
+        } => fuzz(&project, &build, &target, jobs, corpus, &args),
+        Cmd::Cmin {
+            build,
+            target,
+            corpus,
+        } => cmin(&project, &build, &target, corpus),
+        Cmd::Tmin {
+            build,
+            target,
+            input,
+        } => tmin(&project, &build, &target, &input),
+        Cmd::Coverage {
+            build,
+            target,
+            corpus,
+        } => coverage(&project, &build, &target, corpus),
+    }
+}
+
+/// Builds `target`, and returns its binary and its (created) artifacts directory
+fn prepare(project: &FuzzProject, build: &BuildOpt, target: &str) -> Result<(PathBuf, PathBuf)> {
+    project.check_target(target)?;
+    project.build(Some(target), build)?;
+    let artifacts = project.artifacts_dir(target);
+    fs::create_dir_all(&artifacts)?;
+    Ok((project.binary(target, build)?, artifacts))
+}
+
+fn fuzz(
+    project: &FuzzProject,
+    build: &BuildOpt,
+    target: &str,
+    jobs: Option<usize>,
+    mut corpus: Vec<PathBuf>,
+    args: &[String],
+) -> Result<()> {
+    let (binary, artifacts) = prepare(project, build, target)?;
+    if corpus.is_empty() {
+        corpus.push(project.corpus_dir(target));
+    }
+    fs::create_dir_all(&corpus[0])?;
+
+    let mut cmd = Command::new(binary);
+    cmd.arg(format!("-artifact_prefix={}", artifacts.display()));
+    if let Some(jobs) = jobs {
+        cmd.arg(format!("-fork={}", jobs));
+    }
+    check_status(target, cmd.args(&corpus).args(args).status()?)
+}
+
+fn cmin(
+    project: &FuzzProject,
+    build: &BuildOpt,
+    target: &str,
+    corpus: Option<PathBuf>,
+) -> Result<()> {
+    let (binary, artifacts) = prepare(project, build, target)?;
+    let corpus = corpus.unwrap_or_else(|| project.corpus_dir(target));
+    let file_name = corpus
+        .file_name()
+        .ok_or("Invalid corpus directory")?
+        .to_string_lossy();
+    let old = corpus.with_file_name(format!("{}.cmin-old", file_name));
+
+    // Merge the old corpus into an empty one
+    fs::rename(&corpus, &old)?;
+    fs::create_dir_all(&corpus)?;
+    let status = Command::new(binary)
+        .arg("-merge=1")
+        .arg(format!("-artifact_prefix={}", artifacts.display()))
+        .arg(&corpus)
+        .arg(&old)
+        .status()?;
+    if status.success() {
+        fs::remove_dir_all(&old)?;
+    } else {
+        fs::remove_dir_all(&corpus)?;
+        fs::rename(&old, &corpus)?;
+    }
+    check_status("corpus minimization", status)
+}
+
+fn tmin(project: &FuzzProject, build: &BuildOpt, target: &str, input: &Path) -> Result<()> {
+    let (binary, artifacts) = prepare(project, build, target)?;
+    let candidate = artifacts.join(".tmin_candidate");
+    let crashes = |data: &[u8]| -> Result<bool> {
+        fs::write(&candidate, data)?;
+        let status = Command::new(&binary)
+            .arg(&candidate)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(!status.success())
+    };
+
+    let data = fs::read(input)?;
+    if !crashes(&data)? {
+        return Err(format!("{} does not crash {}", input.display(), target).into());
+    }
+    let len = data.len();
+    let minimized = tmin::minimize(data, crashes)?;
+    fs::remove_file(&candidate)?;
+
+    let file_name = input
+        .file_name()
+        .ok_or("Invalid input file")?
+        .to_string_lossy();
+    let out = artifacts.join(format!("minimized-from-{}", file_name));
+    fs::write(&out, &minimized)?;
+    println!(
+        "Minimized {} from {} to {} bytes: {}",
+        input.display(),
+        len,
+        minimized.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+/// An llvm tool: the one of the `llvm-tools-preview` component if installed, else the one in the `PATH`
+fn llvm_tool(name: &str) -> PathBuf {
+    let sysroot = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if let Ok(sysroot) = sysroot {
+        let bins = Path::new(&sysroot).join("lib").join("rustlib");
+        if let Ok(entries) = fs::read_dir(&bins) {
+            for entry in entries.flatten() {
+                let tool = entry.path().join("bin").join(name);
+                if tool.is_file() {
+                    return tool;
+                }
+            }
+        }
+    }
+    PathBuf::from(name)
+}
+
+fn coverage(
+    project: &FuzzProject,
+    build: &BuildOpt,
+    target: &str,
+    mut corpus: Vec<PathBuf>,
+) -> Result<()> {
+    project.check_target(target)?;
+    project.build_coverage(target, build)?;
+    let binary = project.coverage_binary(target, build)?;
+    if corpus.is_empty() {
+        corpus.push(project.corpus_dir(target));
+    }
+
+    let out = project.coverage_dir(target);
+    let raw = out.join("raw");
+    drop(fs::remove_dir_all(&raw));
+    fs::create_dir_all(&raw)?;
+
+    // One process per input, a crashing input does not lose the coverage of the others
+    let inputs = files_in(&corpus)?;
+    for input in &inputs {
+        Command::new(&binary)
+            .arg(input)
+            .env("LLVM_PROFILE_FILE", raw.join("default-%p.profraw"))
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+    }
+
+    let profdata = out.join("coverage.profdata");
+    let status = Command::new(llvm_tool("llvm-profdata"))
+        .args(["merge", "-sparse", "-o"])
+        .arg(&profdata)
+        .args(files_in(&[raw])?)
+        .status()?;
+    check_status("llvm-profdata", status)?;
+
+    let lcov = out.join("lcov.info");
+    let status = Command::new(llvm_tool("llvm-cov"))
+        .args(["export", "-format=lcov", "-instr-profile"])
+        .arg(&profdata)
+        .arg(&binary)
+        .stdout(fs::File::create(&lcov)?)
+        .status()?;
+    check_status("llvm-cov", status)?;
+
+    println!(
+        "Coverage of {} inputs written to {}",
+        inputs.len(),
+        lcov.display()
+    );
+    Ok(())
+}
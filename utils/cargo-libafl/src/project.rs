@@ -0,0 +1,225 @@
+//! A `cargo-fuzz` project: the `fuzz/` directory, with its `Cargo.toml` and its `fuzz_targets`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+
+use crate::{BuildOpt, Result};
+
+/// The instrumentation `libafl_libfuzzer` gets its coverage from
+const SANCOV_RUSTFLAGS: &str = "-Cpasses=sancov-module \
+    -Cllvm-args=-sanitizer-coverage-level=3 \
+    -Cllvm-args=-sanitizer-coverage-trace-pc-guard \
+    -Cllvm-args=-sanitizer-coverage-trace-compares";
+
+/// A `cargo-fuzz` project
+#[derive(Debug)]
+pub struct FuzzProject {
+    fuzz_dir: PathBuf,
+}
+
+impl FuzzProject {
+    /// Finds the fuzz directory `fuzz_dir`, relative to the current directory or any of its parents
+    pub fn find(fuzz_dir: &Path) -> Result<Self> {
+        if fuzz_dir.is_absolute() {
+            return Self::open(fuzz_dir);
+        }
+        let cwd = env::current_dir()?;
+        for dir in cwd.ancestors() {
+            let candidate = dir.join(fuzz_dir);
+            if candidate.join("Cargo.toml").is_file() {
+                return Self::open(&candidate);
+            }
+        }
+        Err(format!(
+            "Could not find a fuzz project at {} in {} or its parents, see `cargo fuzz init`",
+            fuzz_dir.display(),
+            cwd.display()
+        )
+        .into())
+    }
+
+    fn open(fuzz_dir: &Path) -> Result<Self> {
+        if !fuzz_dir.join("Cargo.toml").is_file() {
+            return Err(format!("No Cargo.toml in {}", fuzz_dir.display()).into());
+        }
+        Ok(Self {
+            fuzz_dir: fuzz_dir.to_path_buf(),
+        })
+    }
+
+    /// The names of the fuzz targets, one per file in `fuzz_targets`
+    pub fn targets(&self) -> Result<Vec<String>> {
+        let mut targets = vec![];
+        for entry in fs::read_dir(self.fuzz_dir.join("fuzz_targets"))? {
+            let path = entry?.path();
+            if path.extension().map_or(false, |ext| ext == "rs") {
+                if let Some(stem) = path.file_stem() {
+                    targets.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        targets.sort();
+        Ok(targets)
+    }
+
+    /// Fails if `target` is not one of the fuzz targets
+    pub fn check_target(&self, target: &str) -> Result<()> {
+        let targets = self.targets()?;
+        if targets.iter().any(|t| t == target) {
+            Ok(())
+        } else {
+            Err(format!(
+                "No fuzz target {}, the fuzz targets are: {}",
+                target,
+                targets.join(", ")
+            )
+            .into())
+        }
+    }
+
+    /// The default corpus of `target`
+    #[must_use]
+    pub fn corpus_dir(&self, target: &str) -> PathBuf {
+        self.fuzz_dir.join("corpus").join(target)
+    }
+
+    /// The directory the crashes of `target` go to
+    #[must_use]
+    pub fn artifacts_dir(&self, target: &str) -> PathBuf {
+        self.fuzz_dir.join("artifacts").join(target)
+    }
+
+    /// The directory the coverage data of `target` goes to
+    #[must_use]
+    pub fn coverage_dir(&self, target: &str) -> PathBuf {
+        self.fuzz_dir.join("coverage").join(target)
+    }
+
+    /// The target directory of the coverage builds, kept apart from the fuzzing builds
+    fn coverage_target_dir(&self) -> PathBuf {
+        self.fuzz_dir.join("coverage").join("target")
+    }
+
+    /// Builds `target`, or all fuzz targets, instrumented for fuzzing
+    pub fn build(&self, target: Option<&str>, opt: &BuildOpt) -> Result<()> {
+        let mut rustflags = format!("{} --cfg fuzzing", SANCOV_RUSTFLAGS);
+        if opt.debug_assertions {
+            rustflags.push_str(" -Cdebug-assertions -Coverflow-checks");
+        }
+        if opt.sanitizer != "none" {
+            rustflags.push_str(&format!(" -Zsanitizer={}", opt.sanitizer));
+        }
+        self.cargo_build(target, opt, &rustflags, &self.fuzz_dir.join("target"))
+    }
+
+    /// Builds `target` instrumented for source-based coverage, see [`Self::coverage_binary`]
+    pub fn build_coverage(&self, target: &str, opt: &BuildOpt) -> Result<()> {
+        self.cargo_build(
+            Some(target),
+            opt,
+            "-Cinstrument-coverage --cfg fuzzing",
+            &self.coverage_target_dir(),
+        )
+    }
+
+    fn cargo_build(
+        &self,
+        target: Option<&str>,
+        opt: &BuildOpt,
+        rustflags: &str,
+        target_dir: &Path,
+    ) -> Result<()> {
+        let runtime = opt.runtime.as_ref().ok_or(
+            "No libafl_libfuzzer runtime given: build it with `cargo build --release -p libafl_libfuzzer` \
+             and pass the resulting liblibafl_libfuzzer.a with --runtime or LIBAFL_LIBFUZZER_RUNTIME",
+        )?;
+        let runtime = fs::canonicalize(runtime)?;
+
+        let mut cmd = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+        cmd.arg("build")
+            .arg("--release")
+            .arg("--manifest-path")
+            .arg(self.fuzz_dir.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(target_dir)
+            // An explicit target, so the build scripts are not instrumented
+            .arg("--target")
+            .arg(self.triple(opt)?);
+        match target {
+            Some(target) => cmd.arg("--bin").arg(target),
+            None => cmd.arg("--bins"),
+        };
+        if let Some(features) = &opt.features {
+            cmd.arg("--features").arg(features);
+        }
+        let mut rustflags = rustflags.to_string();
+        if let Ok(extra) = env::var("RUSTFLAGS") {
+            rustflags.push(' ');
+            rustflags.push_str(&extra);
+        }
+        // `libfuzzer-sys` links this archive instead of building `libFuzzer`
+        cmd.env("CUSTOM_LIBFUZZER_PATH", runtime)
+            .env("RUSTFLAGS", rustflags);
+
+        check_status("cargo build", cmd.status()?)
+    }
+
+    /// The target triple to build for: the given one, or the host
+    fn triple(&self, opt: &BuildOpt) -> Result<String> {
+        if let Some(triple) = &opt.triple {
+            return Ok(triple.clone());
+        }
+        let output = Command::new("rustc").arg("-vV").output()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.strip_prefix("host: "))
+            .map(str::to_string)
+            .ok_or_else(|| "Could not get the host triple from rustc".into())
+    }
+
+    /// The fuzzing binary of `target`, see [`Self::build`]
+    pub fn binary(&self, target: &str, opt: &BuildOpt) -> Result<PathBuf> {
+        Ok(self
+            .fuzz_dir
+            .join("target")
+            .join(self.triple(opt)?)
+            .join("release")
+            .join(target))
+    }
+
+    /// The coverage binary of `target`, see [`Self::build_coverage`]
+    pub fn coverage_binary(&self, target: &str, opt: &BuildOpt) -> Result<PathBuf> {
+        Ok(self
+            .coverage_target_dir()
+            .join(self.triple(opt)?)
+            .join("release")
+            .join(target))
+    }
+}
+
+/// Turns a failed `status` into an error
+pub fn check_status(what: &str, status: ExitStatus) -> Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed: {}", what, status).into())
+    }
+}
+
+/// The files of the given directories, sorted
+pub fn files_in(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for dir in dirs {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,50 @@
+//! Test case minimization: removes ever smaller blocks of a crashing input, as long as it keeps crashing.
+
+use crate::Result;
+
+/// Minimizes `input`, keeping only the bytes needed for `crashes` to return `true`
+pub fn minimize<F>(input: Vec<u8>, mut crashes: F) -> Result<Vec<u8>>
+where
+    F: FnMut(&[u8]) -> Result<bool>,
+{
+    let mut data = input;
+    let mut block = data.len().next_power_of_two() / 2;
+    while block > 0 {
+        let mut pos = 0;
+        while pos < data.len() {
+            let end = (pos + block).min(data.len());
+            let mut candidate = Vec::with_capacity(data.len() - (end - pos));
+            candidate.extend_from_slice(&data[..pos]);
+            candidate.extend_from_slice(&data[end..]);
+            if crashes(&candidate)? {
+                println!(
+                    "Removed {} bytes at {}, {} left",
+                    end - pos,
+                    pos,
+                    candidate.len()
+                );
+                data = candidate;
+            } else {
+                pos = end;
+            }
+        }
+        block /= 2;
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tmin::minimize;
+
+    #[test]
+    fn test_minimize() {
+        // Crashes as long as it contains both `A` and `B`
+        let crashes = |data: &[u8]| Ok(data.contains(&b'A') && data.contains(&b'B'));
+        assert_eq!(minimize(b"xxAxxxxBxx".to_vec(), crashes).unwrap(), b"AB");
+        assert_eq!(minimize(b"BA".to_vec(), crashes).unwrap(), b"BA");
+
+        // The errors of the target are passed on
+        assert!(minimize(b"AB".to_vec(), |_| Err("target failed".into())).is_err());
+    }
+}
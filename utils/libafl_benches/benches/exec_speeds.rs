@@ -0,0 +1,85 @@
+//! Measure the overhead of the executor, the observers, and the feedbacks against a no-op harness
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libafl::{
+    bolts::{rands::StdRand, tuples::tuple_list},
+    corpus::InMemoryCorpus,
+    events::SimpleEventManager,
+    executors::{inprocess::InProcessExecutor, ExitKind, HasObservers},
+    feedbacks::{CrashFeedback, Feedback, MapFeedbackState, MaxMapFeedback},
+    fuzzer::{ExecutesInput, HasFeedback, StdFuzzer},
+    inputs::BytesInput,
+    monitors::NopMonitor,
+    observers::{ObserversTuple, StdMapObserver},
+    schedulers::QueueScheduler,
+    state::StdState,
+};
+
+const MAP_SIZE: usize = 65536;
+
+static mut MAP: [u8; MAP_SIZE] = [0; MAP_SIZE];
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+    let observer = StdMapObserver::new("map", unsafe { &mut MAP });
+    let feedback_state = MapFeedbackState::with_observer(&observer);
+    let feedback = MaxMapFeedback::new(&feedback_state, &observer);
+    let objective = CrashFeedback::new();
+
+    let mut state = StdState::new(
+        StdRand::with_seed(0),
+        InMemoryCorpus::new(),
+        InMemoryCorpus::new(),
+        tuple_list!(feedback_state),
+    );
+    let mut mgr = SimpleEventManager::new(NopMonitor::new());
+    let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+    let mut executor = InProcessExecutor::new(
+        &mut harness,
+        tuple_list!(observer),
+        &mut fuzzer,
+        &mut state,
+        &mut mgr,
+    )
+    .expect("Failed to create the Executor");
+
+    let input = BytesInput::new(vec![b'a'; 64]);
+
+    c.bench_function("noop_exec", |b| {
+        b.iter(|| {
+            black_box(
+                fuzzer
+                    .execute_input(&mut state, &mut executor, &mut mgr, &input)
+                    .unwrap(),
+            )
+        })
+    });
+    c.bench_function("observers_reset", |b| {
+        b.iter(|| {
+            executor
+                .observers_mut()
+                .pre_exec_all(&mut state, &input)
+                .unwrap();
+        })
+    });
+    c.bench_function("map_feedback_scan", |b| {
+        b.iter(|| {
+            black_box(
+                fuzzer
+                    .feedback_mut()
+                    .is_interesting(
+                        &mut state,
+                        &mut mgr,
+                        &input,
+                        executor.observers(),
+                        &ExitKind::Ok,
+                    )
+                    .unwrap(),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);